@@ -0,0 +1,262 @@
+//! BitChat GATT server handling, independent of the bus
+//!
+//! A peripheral exposes the BitChat service with two characteristics: remote
+//! centrals write packets to TX and subscribe to RX for what we send them. BlueZ
+//! drives a registered GATT application over D-Bus with `WriteValue`,
+//! `ReadValue`, `StartNotify` and `StopNotify` calls. [`GattServer`] handles those
+//! calls so the platform advertiser only translates the bus requests, and tests
+//! can drive it the way BlueZ would without a radio.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bitchat_core::Result as BitchatResult;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::error::BleTransportError;
+use crate::link::{BleLinkId, GattWriteSender, DEFAULT_LINK_MTU};
+
+// ----------------------------------------------------------------------------
+// Notification Session
+// ----------------------------------------------------------------------------
+
+/// Notification session on the RX characteristic, opened by `StartNotify`
+///
+/// The bus multiplexes subscriptions: one session serves every subscribed
+/// central and ends once the last of them unsubscribes.
+#[async_trait]
+pub trait GattNotifier: Send + 'static {
+    /// Send a value to the subscribed centrals
+    async fn notify(&mut self, value: Vec<u8>) -> BitchatResult<()>;
+
+    /// Whether the centrals unsubscribed
+    fn is_stopped(&self) -> bool;
+}
+
+// ----------------------------------------------------------------------------
+// GATT Server
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct GattState {
+    writes: Option<GattWriteSender>,
+    notifications: Option<mpsc::UnboundedSender<Vec<u8>>>,
+}
+
+/// Handles requests to our BitChat GATT service
+///
+/// Clones share state, so each characteristic callback can hold its own.
+#[derive(Debug, Clone, Default)]
+pub struct GattServer {
+    state: Arc<Mutex<GattState>>,
+}
+
+impl GattServer {
+    /// Create a server that drops writes until a sender is attached
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deliver what remote centrals write to the TX characteristic to `sender`
+    pub fn attach_write_sender(&self, sender: GattWriteSender) {
+        self.lock().writes = Some(sender);
+    }
+
+    /// Handle a `WriteValue` call on the TX characteristic from `device`
+    pub fn handle_write(&self, device: BleLinkId, value: Vec<u8>) -> BitchatResult<()> {
+        if value.is_empty() {
+            return Ok(());
+        }
+        debug!("GATT write of {} bytes from {}", value.len(), device);
+
+        let state = self.lock();
+        let sender = state
+            .writes
+            .as_ref()
+            .ok_or_else(|| BleTransportError::WriteFailed("no write receiver".to_string()))?;
+        sender
+            .send((device, value))
+            .map_err(|_| BleTransportError::ReceiveChannelClosed.into())
+    }
+
+    /// Handle a `ReadValue` call on the RX characteristic
+    ///
+    /// Packets only flow through notifications, so reads see an empty value.
+    pub fn handle_read(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Handle `StartNotify` on the RX characteristic
+    ///
+    /// Replaces any earlier session, which the bus only keeps while someone is
+    /// subscribed.
+    pub fn start_notify<N: GattNotifier>(&self, mut notifier: N) {
+        let (sender, mut values) = mpsc::unbounded_channel::<Vec<u8>>();
+        self.lock().notifications = Some(sender);
+        debug!("Remote central subscribed to BitChat notifications");
+
+        tokio::spawn(async move {
+            while let Some(value) = values.recv().await {
+                if notifier.is_stopped() {
+                    break;
+                }
+                if let Err(e) = notifier.notify(value).await {
+                    warn!("Failed to send GATT notification: {}", e);
+                    break;
+                }
+            }
+            debug!("BitChat notification session ended");
+        });
+    }
+
+    /// Handle `StopNotify` on the RX characteristic
+    pub fn stop_notify(&self) {
+        self.lock().notifications = None;
+    }
+
+    /// Whether any remote central is subscribed to notifications
+    pub fn is_notifying(&self) -> bool {
+        self.lock()
+            .notifications
+            .as_ref()
+            .is_some_and(|sender| !sender.is_closed())
+    }
+
+    /// Send `data` to subscribed centrals, split to fit single notifications
+    ///
+    /// Returns whether anyone was subscribed.
+    pub fn notify(&self, data: &[u8]) -> bool {
+        let mut state = self.lock();
+        let Some(sender) = state.notifications.as_ref() else {
+            return false;
+        };
+        let sent = data
+            .chunks(DEFAULT_LINK_MTU)
+            .all(|chunk| sender.send(chunk.to_vec()).is_ok());
+        if !sent {
+            state.notifications = None;
+        }
+        sent
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, GattState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    /// Notification session as the mocked bus opens it for subscribers
+    struct MockNotifier {
+        values: mpsc::UnboundedSender<Vec<u8>>,
+        stopped: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl GattNotifier for MockNotifier {
+        async fn notify(&mut self, value: Vec<u8>) -> BitchatResult<()> {
+            self.values
+                .send(value)
+                .map_err(|_| BleTransportError::ReceiveChannelClosed.into())
+        }
+
+        fn is_stopped(&self) -> bool {
+            self.stopped.load(Ordering::SeqCst)
+        }
+    }
+
+    /// A mocked BlueZ bus calling into a registered GATT server
+    struct MockBus {
+        server: GattServer,
+    }
+
+    impl MockBus {
+        /// `WriteValue` on TX, as the central at `address` writes
+        fn write_value(&self, address: &str, value: &[u8]) -> BitchatResult<()> {
+            self.server
+                .handle_write(BleLinkId::new(address), value.to_vec())
+        }
+
+        /// `StartNotify` on RX, returning what subscribers receive
+        fn start_notify(&self) -> (mpsc::UnboundedReceiver<Vec<u8>>, Arc<AtomicBool>) {
+            let (values, received) = mpsc::unbounded_channel();
+            let stopped = Arc::new(AtomicBool::new(false));
+            self.server.start_notify(MockNotifier {
+                values,
+                stopped: stopped.clone(),
+            });
+            (received, stopped)
+        }
+    }
+
+    async fn recv<T>(receiver: &mut mpsc::UnboundedReceiver<T>) -> Option<T> {
+        timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .expect("timed out")
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_delivered_by_device() {
+        let bus = MockBus {
+            server: GattServer::new(),
+        };
+        assert!(
+            bus.write_value("AA:BB:CC:DD:EE:01", b"early").is_err(),
+            "writes fail until the transport attaches"
+        );
+
+        let (sender, mut writes) = mpsc::unbounded_channel();
+        bus.server.attach_write_sender(sender);
+        bus.write_value("AA:BB:CC:DD:EE:01", b"hello").unwrap();
+        bus.write_value("AA:BB:CC:DD:EE:02", b"").unwrap();
+        bus.write_value("AA:BB:CC:DD:EE:02", b"world").unwrap();
+
+        assert_eq!(
+            recv(&mut writes).await,
+            Some((BleLinkId::new("AA:BB:CC:DD:EE:01"), b"hello".to_vec()))
+        );
+        assert_eq!(
+            recv(&mut writes).await,
+            Some((BleLinkId::new("AA:BB:CC:DD:EE:02"), b"world".to_vec()))
+        );
+        assert!(bus.server.handle_read().is_empty());
+
+        drop(writes);
+        assert!(bus.write_value("AA:BB:CC:DD:EE:01", b"late").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_notifications_reach_subscribers_until_stopped() {
+        let bus = MockBus {
+            server: GattServer::new(),
+        };
+        assert!(!bus.server.notify(b"nobody listening"));
+
+        let (mut received, stopped) = bus.start_notify();
+        assert!(bus.server.is_notifying());
+        let packet = vec![7u8; DEFAULT_LINK_MTU + 10];
+        assert!(bus.server.notify(&packet));
+        assert_eq!(recv(&mut received).await.unwrap().len(), DEFAULT_LINK_MTU);
+        assert_eq!(recv(&mut received).await.unwrap().len(), 10);
+
+        // The last central unsubscribes without the bus calling StopNotify
+        stopped.store(true, Ordering::SeqCst);
+        assert!(bus.server.notify(b"ignored"));
+        assert_eq!(recv(&mut received).await, None);
+        assert!(!bus.server.notify(b"session gone"));
+        assert!(!bus.server.is_notifying());
+
+        let (mut received, _stopped) = bus.start_notify();
+        bus.server.stop_notify();
+        assert!(!bus.server.notify(b"after stop"));
+        assert_eq!(recv(&mut received).await, None);
+    }
+}
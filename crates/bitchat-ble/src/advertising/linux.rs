@@ -1,15 +1,26 @@
-//! Linux BLE advertising implementation using bluer (BlueZ)
+//! Linux BLE advertising and GATT server using bluer (BlueZ)
+//!
+//! Besides advertising, registers the BitChat GATT application so other
+//! centrals, including other Linux nodes, can connect to us as a peripheral.
 
 use bitchat_core::internal::{IdentityKeyPair, TransportError};
 use bitchat_core::{BitchatError, PeerId, Result as BitchatResult};
+use bluer::gatt::local::{
+    Application, ApplicationHandle, Characteristic, CharacteristicNotifier, CharacteristicNotify,
+    CharacteristicNotifyMethod, CharacteristicRead, CharacteristicWrite, CharacteristicWriteMethod,
+    ReqError, Service,
+};
 use tracing::{debug, info};
 
 use crate::config::BleTransportConfig;
+use crate::error::BleTransportError;
+use crate::link::{BleLinkId, GattWriteSender};
 use crate::protocol::{
     generate_advertising_data, generate_device_name, BITCHAT_RX_CHARACTERISTIC_UUID,
     BITCHAT_SERVICE_UUID, BITCHAT_TX_CHARACTERISTIC_UUID,
 };
 
+use super::gatt::{GattNotifier, GattServer};
 use super::BleAdvertiser;
 
 // ----------------------------------------------------------------------------
@@ -19,7 +30,9 @@ use super::BleAdvertiser;
 pub struct LinuxAdvertiser {
     session: Option<bluer::Session>,
     adapter: Option<bluer::Adapter>,
-    advertisement_handle: Option<bluer::adv::Advertisement>,
+    advertisement_handle: Option<bluer::adv::AdvertisementHandle>,
+    gatt_handle: Option<ApplicationHandle>,
+    gatt_server: GattServer,
     is_advertising: bool,
 }
 
@@ -29,6 +42,8 @@ impl LinuxAdvertiser {
             session: None,
             adapter: None,
             advertisement_handle: None,
+            gatt_handle: None,
+            gatt_server: GattServer::new(),
             is_advertising: false,
         }
     }
@@ -64,6 +79,110 @@ impl LinuxAdvertiser {
         info!("Linux BLE adapter initialized for advertising");
         Ok(())
     }
+
+    /// Register the BitChat GATT application with BlueZ, once
+    async fn serve_gatt_application(&mut self) -> BitchatResult<()> {
+        if self.gatt_handle.is_some() {
+            return Ok(());
+        }
+
+        let adapter = self.adapter.as_ref().unwrap();
+        let app_handle = adapter
+            .serve_gatt_application(gatt_application(&self.gatt_server))
+            .await
+            .map_err(|e| {
+                BitchatError::Transport(TransportError::InvalidConfiguration {
+                    reason: format!("Failed to register GATT service: {}", e),
+                })
+            })?;
+
+        self.gatt_handle = Some(app_handle);
+        info!("Registered BitChat GATT service");
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// BlueZ GATT Application
+// ----------------------------------------------------------------------------
+
+/// Build the BitChat GATT application, routing BlueZ requests to `server`
+fn gatt_application(server: &GattServer) -> Application {
+    let write_server = server.clone();
+    let read_server = server.clone();
+    let notify_server = server.clone();
+
+    // TX characteristic: remote centrals write packets to us
+    let tx = Characteristic {
+        uuid: BITCHAT_TX_CHARACTERISTIC_UUID,
+        write: Some(CharacteristicWrite {
+            write: true,
+            write_without_response: true,
+            method: CharacteristicWriteMethod::Fun(Box::new(move |value, request| {
+                let server = write_server.clone();
+                Box::pin(async move {
+                    // Same address format btleplug reports, so writers we also
+                    // discovered as central resolve to their peers
+                    let device = BleLinkId::new(request.device_address.to_string());
+                    server.handle_write(device, value).map_err(|e| {
+                        debug!("Rejected GATT write: {}", e);
+                        ReqError::Failed
+                    })
+                })
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    // RX characteristic: we notify subscribed centrals
+    let rx = Characteristic {
+        uuid: BITCHAT_RX_CHARACTERISTIC_UUID,
+        read: Some(CharacteristicRead {
+            read: true,
+            fun: Box::new(move |_| {
+                let value = read_server.handle_read();
+                Box::pin(async move { Ok(value) })
+            }),
+            ..Default::default()
+        }),
+        notify: Some(CharacteristicNotify {
+            notify: true,
+            method: CharacteristicNotifyMethod::Fun(Box::new(move |notifier| {
+                notify_server.start_notify(BluezNotifier(notifier));
+                Box::pin(async {})
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    Application {
+        services: vec![Service {
+            uuid: BITCHAT_SERVICE_UUID,
+            primary: true,
+            characteristics: vec![tx, rx],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+/// BlueZ notification session for the RX characteristic
+struct BluezNotifier(CharacteristicNotifier);
+
+#[async_trait::async_trait]
+impl GattNotifier for BluezNotifier {
+    async fn notify(&mut self, value: Vec<u8>) -> BitchatResult<()> {
+        self.0
+            .notify(value)
+            .await
+            .map_err(|e| BleTransportError::WriteFailed(format!("GATT notification: {}", e)).into())
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.0.is_stopped()
+    }
 }
 
 #[async_trait::async_trait]
@@ -76,36 +195,11 @@ impl BleAdvertiser for LinuxAdvertiser {
     ) -> BitchatResult<()> {
         self.initialize().await?;
 
+        self.serve_gatt_application().await?;
+
         let adapter = self.adapter.as_ref().unwrap();
         let device_name = generate_device_name(peer_id, &config.device_name_prefix);
 
-        // Create GATT service
-        let app = bluer::gatt::local::Application::new();
-        let service_handle = app.service(BITCHAT_SERVICE_UUID).unwrap();
-
-        // Add TX characteristic (write)
-        let _tx_char = service_handle
-            .characteristic(BITCHAT_TX_CHARACTERISTIC_UUID)
-            .write(|data, _| {
-                debug!("Received data on TX characteristic: {} bytes", data.len());
-                Ok(())
-            })
-            .build();
-
-        // Add RX characteristic (read/notify)
-        let _rx_char = service_handle
-            .characteristic(BITCHAT_RX_CHARACTERISTIC_UUID)
-            .read(|_| Ok(vec![]))
-            .notify()
-            .build();
-
-        // Register GATT application
-        let app_handle = adapter.serve_gatt_application(app).await.map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to register GATT service: {}", e),
-            })
-        })?;
-
         // Generate secure advertising data
         let secure_advertising_data = generate_advertising_data(*peer_id, identity, &device_name)?;
 
@@ -138,8 +232,10 @@ impl BleAdvertiser for LinuxAdvertiser {
     }
 
     async fn stop_advertising(&mut self) -> BitchatResult<()> {
+        // Dropping the handles stops advertising and unregisters the GATT service
+        self.gatt_handle = None;
         if let Some(handle) = self.advertisement_handle.take() {
-            drop(handle); // Dropping the handle stops advertising
+            drop(handle);
             self.is_advertising = false;
             info!("Stopped BLE advertising");
         }
@@ -157,9 +253,310 @@ impl BleAdvertiser for LinuxAdvertiser {
         config: &BleTransportConfig,
     ) -> BitchatResult<()> {
         if self.is_advertising {
-            self.stop_advertising().await?;
+            // Keep the GATT service up so connected centrals stay subscribed
+            self.advertisement_handle = None;
             self.start_advertising(peer_id, identity, config).await?;
         }
         Ok(())
     }
+
+    fn attach_write_sender(&mut self, sender: GattWriteSender) {
+        self.gatt_server.attach_write_sender(sender);
+    }
+
+    fn gatt_server(&self) -> Option<GattServer> {
+        Some(self.gatt_server.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::mpsc as std_mpsc;
+    use std::time::Duration;
+
+    use dbus::arg::{prop_cast, PropMap, RefArg, Variant};
+    use dbus::blocking::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged;
+    use dbus::blocking::Connection;
+    use dbus::channel::{Channel, MatchingReceiver, Sender};
+    use dbus::message::MatchRule;
+    use dbus::{Message, Path};
+    use tokio::sync::mpsc;
+    use tokio::time::timeout;
+
+    const ADAPTER_PATH: &str = "/org/bluez/hci0";
+    const DEVICE_PATH: &str = "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_01";
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    type ManagedObjects = HashMap<Path<'static>, HashMap<String, PropMap>>;
+
+    /// Private bus daemon standing in for the system bus BlueZ lives on
+    struct TestBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl TestBus {
+        /// Start a bus daemon, or `None` where dbus-daemon is not installed
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        fn connect(&self) -> Connection {
+            connect(&self.address)
+        }
+    }
+
+    fn connect(address: &str) -> Connection {
+        let mut channel = Channel::open_private(address).unwrap();
+        channel.register().unwrap();
+        Connection::from(channel)
+    }
+
+    impl Drop for TestBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// A registration call the mocked BlueZ received: method, object path and caller
+    type Call = (String, Path<'static>, String);
+
+    fn variant<T: RefArg + 'static>(value: T) -> Variant<Box<dyn RefArg>> {
+        Variant(Box::new(value))
+    }
+
+    fn adapter_properties() -> PropMap {
+        let mut properties = PropMap::new();
+        properties.insert(
+            "Address".to_string(),
+            variant("00:11:22:33:44:55".to_string()),
+        );
+        properties.insert("AddressType".to_string(), variant("public".to_string()));
+        properties.insert("Name".to_string(), variant("mock".to_string()));
+        properties.insert("Powered".to_string(), variant(true));
+        properties
+    }
+
+    /// Answer a call to org.bluez the way BlueZ would for a powered adapter
+    fn bluez_reply(message: &Message, calls: &std_mpsc::Sender<Call>) -> Message {
+        let member = message
+            .member()
+            .map(|member| member.to_string())
+            .unwrap_or_default();
+        match member.as_str() {
+            "GetManagedObjects" => {
+                let interfaces: HashMap<String, PropMap> = [
+                    ("org.bluez.Adapter1".to_string(), adapter_properties()),
+                    ("org.bluez.GattManager1".to_string(), PropMap::new()),
+                    (
+                        "org.bluez.LEAdvertisingManager1".to_string(),
+                        PropMap::new(),
+                    ),
+                ]
+                .into_iter()
+                .collect();
+                let objects: ManagedObjects = [(Path::from(ADAPTER_PATH), interfaces)]
+                    .into_iter()
+                    .collect();
+                message.method_return().append1(objects)
+            }
+            "Get" => {
+                let (_, name): (String, String) = message.read2().unwrap();
+                match adapter_properties().remove(&name) {
+                    Some(value) => message.method_return().append1(value),
+                    None => {
+                        Message::new_error(message, "org.freedesktop.DBus.Error.InvalidArgs", &name)
+                            .unwrap()
+                    }
+                }
+            }
+            "GetAll" => message.method_return().append1(adapter_properties()),
+            "RegisterApplication"
+            | "UnregisterApplication"
+            | "RegisterAdvertisement"
+            | "UnregisterAdvertisement" => {
+                let path: Path = message.read1().unwrap();
+                let caller = message
+                    .sender()
+                    .map(|sender| sender.to_string())
+                    .unwrap_or_default();
+                let _ = calls.send((member, path.into_static(), caller));
+                message.method_return()
+            }
+            _ => Message::new_error(message, "org.bluez.Error.NotSupported", &member).unwrap(),
+        }
+    }
+
+    /// Serve a mocked org.bluez with one powered adapter, reporting registrations
+    fn mock_bluez(bus: &TestBus) -> std_mpsc::Receiver<Call> {
+        let conn = bus.connect();
+        conn.request_name("org.bluez", false, true, false).unwrap();
+        let (calls, received) = std_mpsc::channel();
+        conn.start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |message, conn| {
+                let _ = conn.send(bluez_reply(&message, &calls));
+                true
+            }),
+        );
+        std::thread::spawn(move || while conn.process(Duration::from_millis(100)).is_ok() {});
+        received
+    }
+
+    fn next_call(calls: &std_mpsc::Receiver<Call>) -> Call {
+        calls
+            .recv_timeout(TIMEOUT)
+            .expect("timed out waiting for BlueZ call")
+    }
+
+    /// Object path of the registered characteristic with `uuid`
+    fn characteristic(objects: &ManagedObjects, uuid: impl ToString) -> Path<'static> {
+        let uuid = uuid.to_string();
+        objects
+            .iter()
+            .find(|(_, interfaces)| {
+                interfaces
+                    .get("org.bluez.GattCharacteristic1")
+                    .and_then(|properties| prop_cast::<String>(properties, "UUID"))
+                    .is_some_and(|value| *value == uuid)
+            })
+            .map(|(path, _)| path.clone())
+            .expect("characteristic not registered")
+    }
+
+    /// Act as BlueZ towards the registered application: write to TX as a remote
+    /// central, subscribe to RX and return the first notification value
+    fn drive_application(
+        address: &str,
+        owner: String,
+        app: Path<'static>,
+        server: GattServer,
+    ) -> Vec<u8> {
+        let client = connect(address);
+        let (objects,): (ManagedObjects,) = client
+            .with_proxy(owner.clone(), app, TIMEOUT)
+            .method_call(
+                "org.freedesktop.DBus.ObjectManager",
+                "GetManagedObjects",
+                (),
+            )
+            .unwrap();
+        let tx = characteristic(&objects, BITCHAT_TX_CHARACTERISTIC_UUID);
+        let rx = characteristic(&objects, BITCHAT_RX_CHARACTERISTIC_UUID);
+
+        let mut options = PropMap::new();
+        options.insert("device".to_string(), variant(Path::from(DEVICE_PATH)));
+        options.insert("link".to_string(), variant("LE".to_string()));
+        options.insert("mtu".to_string(), variant(517u16));
+        options.insert("type".to_string(), variant("command".to_string()));
+        let () = client
+            .with_proxy(owner.clone(), tx, TIMEOUT)
+            .method_call(
+                "org.bluez.GattCharacteristic1",
+                "WriteValue",
+                (b"hello".to_vec(), options),
+            )
+            .unwrap();
+
+        let (values, received) = std_mpsc::channel();
+        let rule = MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged")
+            .with_path(rx.clone());
+        client
+            .add_match(rule, move |changed: PropertiesPropertiesChanged, _, _| {
+                if let Some(value) = prop_cast::<Vec<u8>>(&changed.changed_properties, "Value") {
+                    let _ = values.send(value.clone());
+                }
+                true
+            })
+            .unwrap();
+        let () = client
+            .with_proxy(owner, rx, TIMEOUT)
+            .method_call("org.bluez.GattCharacteristic1", "StartNotify", ())
+            .unwrap();
+
+        // BlueZ hands the notification session to the application asynchronously
+        let deadline = std::time::Instant::now() + TIMEOUT;
+        while !server.is_notifying() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "notifications never started"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(server.notify(b"packet"));
+        loop {
+            client.process(Duration::from_millis(100)).unwrap();
+            if let Ok(value) = received.try_recv() {
+                return value;
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "no notification signalled"
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_gatt_application_against_mocked_bluez() {
+        let Some(bus) = TestBus::start() else {
+            eprintln!("dbus-daemon not installed, skipping");
+            return;
+        };
+        // bluer talks to BlueZ on the system bus
+        std::env::set_var("DBUS_SYSTEM_BUS_ADDRESS", &bus.address);
+        let calls = mock_bluez(&bus);
+
+        let (writes_tx, mut writes) = mpsc::unbounded_channel();
+        let mut advertiser = LinuxAdvertiser::new();
+        advertiser.attach_write_sender(writes_tx);
+        let server = advertiser.gatt_server().expect("Linux serves GATT");
+        let identity = IdentityKeyPair::generate().unwrap();
+        let peer_id = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        advertiser
+            .start_advertising(&peer_id, &identity, &BleTransportConfig::default())
+            .await
+            .unwrap();
+
+        let (method, app, owner) = next_call(&calls);
+        assert_eq!(method, "RegisterApplication");
+        assert_eq!(next_call(&calls).0, "RegisterAdvertisement");
+
+        let (address, registered) = (bus.address.clone(), app.clone());
+        let notified = tokio::task::spawn_blocking(move || {
+            drive_application(&address, owner, registered, server)
+        })
+        .await
+        .unwrap();
+        assert_eq!(notified, b"packet");
+        assert_eq!(
+            timeout(TIMEOUT, writes.recv()).await.unwrap(),
+            Some((BleLinkId::new("AA:BB:CC:DD:EE:01"), b"hello".to_vec()))
+        );
+
+        // Stopping drops the handles, which unregister both from BlueZ
+        advertiser.stop_advertising().await.unwrap();
+        let mut unregistered = [next_call(&calls), next_call(&calls)];
+        unregistered.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(unregistered[0].0, "UnregisterAdvertisement");
+        assert_eq!(unregistered[1].0, "UnregisterApplication");
+        assert_eq!(unregistered[1].1, app);
+    }
 }
@@ -2,13 +2,16 @@
 
 use std::time::Duration;
 
+use tokio::time::Instant;
+
 use bitchat_core::internal::IdentityKeyPair;
 use bitchat_core::{PeerId, Result as BitchatResult};
 use tracing::{debug, info};
 
 use crate::config::BleTransportConfig;
+use crate::link::GattWriteSender;
 
-use super::{BleAdvertiser, PlatformAdvertiser};
+use super::{BleAdvertiser, GattServer, PlatformAdvertiser};
 
 // ----------------------------------------------------------------------------
 // Advertising Manager
@@ -18,17 +21,23 @@ use super::{BleAdvertiser, PlatformAdvertiser};
 pub struct AdvertisingManager {
     advertiser: PlatformAdvertiser,
     current_peer_id: Option<PeerId>,
-    #[allow(dead_code)]
     rotation_interval: Option<Duration>,
+    last_rotation: Option<Instant>,
 }
 
 impl AdvertisingManager {
     /// Create a new advertising manager
     pub fn new() -> Self {
+        Self::with_advertiser(PlatformAdvertiser::new())
+    }
+
+    /// Create an advertising manager driving a specific advertiser
+    pub fn with_advertiser(advertiser: PlatformAdvertiser) -> Self {
         Self {
-            advertiser: PlatformAdvertiser::new(),
+            advertiser,
             current_peer_id: None,
             rotation_interval: None,
+            last_rotation: None,
         }
     }
 
@@ -43,6 +52,7 @@ impl AdvertisingManager {
             .start_advertising(&peer_id, identity, config)
             .await?;
         self.current_peer_id = Some(peer_id);
+        self.last_rotation = Some(Instant::now());
         info!("BLE advertising started for peer {}", peer_id);
         Ok(())
     }
@@ -51,6 +61,7 @@ impl AdvertisingManager {
     pub async fn stop(&mut self) -> BitchatResult<()> {
         self.advertiser.stop_advertising().await?;
         self.current_peer_id = None;
+        self.last_rotation = None;
         info!("BLE advertising stopped");
         Ok(())
     }
@@ -60,23 +71,43 @@ impl AdvertisingManager {
         self.advertiser.is_advertising()
     }
 
+    /// Deliver what remote centrals write to our GATT server to `sender`
+    pub fn attach_write_sender(&mut self, sender: GattWriteSender) {
+        self.advertiser.attach_write_sender(sender);
+    }
+
+    /// GATT server notifying centrals subscribed to us, if the platform has one
+    pub fn gatt_server(&self) -> Option<GattServer> {
+        self.advertiser.gatt_server()
+    }
+
     /// Enable periodic rotation of advertising data (for privacy)
-    #[allow(dead_code)]
     pub fn enable_rotation(&mut self, interval: Duration) {
         self.rotation_interval = Some(interval);
     }
 
-    /// Manually rotate advertising data
+    /// When the advertised peer ID is next due to rotate, while advertising
+    pub fn next_rotation(&self) -> Option<Instant> {
+        Some(self.last_rotation? + self.rotation_interval?)
+    }
+
+    /// Rotate advertising data to a new peer ID signed by `identity`
+    ///
+    /// Only restarts the advertiser if it is running; the new ID is used the next
+    /// time advertising starts otherwise.
     pub async fn rotate(
         &mut self,
+        peer_id: PeerId,
         identity: &IdentityKeyPair,
         config: &BleTransportConfig,
     ) -> BitchatResult<()> {
-        if let Some(peer_id) = self.current_peer_id {
+        if self.current_peer_id.is_some() {
             self.advertiser
                 .update_advertising_data(&peer_id, identity, config)
                 .await?;
-            debug!("Rotated BLE advertising data for peer {}", peer_id);
+            self.current_peer_id = Some(peer_id);
+            self.last_rotation = Some(Instant::now());
+            debug!("Rotated BLE advertising data to peer {}", peer_id);
         }
         Ok(())
     }
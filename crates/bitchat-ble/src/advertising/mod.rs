@@ -1,19 +1,24 @@
 //! Cross-platform advertising trait and platform detection
 
 pub mod fallback;
+pub mod gatt;
 #[cfg(target_os = "linux")]
 pub mod linux;
 #[cfg(target_os = "macos")]
 pub mod macos;
 pub mod manager;
+pub mod simulated;
 
 // Re-export manager types
+pub use gatt::{GattNotifier, GattServer};
 pub use manager::AdvertisingManager;
+pub use simulated::SimulatedAdvertiser;
 
 use bitchat_core::internal::IdentityKeyPair;
 use bitchat_core::{PeerId, Result as BitchatResult};
 
 use crate::config::BleTransportConfig;
+use crate::link::GattWriteSender;
 
 // ----------------------------------------------------------------------------
 // Cross-platform Advertising Trait
@@ -43,6 +48,18 @@ pub trait BleAdvertiser: Send + Sync {
         identity: &IdentityKeyPair,
         config: &BleTransportConfig,
     ) -> BitchatResult<()>;
+
+    /// Deliver what remote centrals write to our TX characteristic to `sender`
+    ///
+    /// Advertisers without a GATT server that accepts writes drop the sender.
+    fn attach_write_sender(&mut self, _sender: GattWriteSender) {}
+
+    /// GATT server behind our BitChat service, to notify subscribed centrals
+    ///
+    /// Advertisers without a GATT server have no one to notify.
+    fn gatt_server(&self) -> Option<GattServer> {
+        None
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -57,6 +74,7 @@ pub enum PlatformAdvertiser {
     MacOS(macos::MacOSAdvertiser),
     #[allow(dead_code)]
     Fallback(fallback::FallbackAdvertiser),
+    Simulated(simulated::SimulatedAdvertiser),
 }
 
 impl PlatformAdvertiser {
@@ -106,6 +124,11 @@ impl BleAdvertiser for PlatformAdvertiser {
                     .start_advertising(peer_id, identity, config)
                     .await
             }
+            Self::Simulated(ref mut advertiser) => {
+                advertiser
+                    .start_advertising(peer_id, identity, config)
+                    .await
+            }
         }
     }
 
@@ -116,6 +139,7 @@ impl BleAdvertiser for PlatformAdvertiser {
             #[cfg(target_os = "macos")]
             Self::MacOS(ref mut advertiser) => advertiser.stop_advertising().await,
             Self::Fallback(ref mut advertiser) => advertiser.stop_advertising().await,
+            Self::Simulated(ref mut advertiser) => advertiser.stop_advertising().await,
         }
     }
 
@@ -126,6 +150,7 @@ impl BleAdvertiser for PlatformAdvertiser {
             #[cfg(target_os = "macos")]
             Self::MacOS(ref advertiser) => advertiser.is_advertising(),
             Self::Fallback(ref advertiser) => advertiser.is_advertising(),
+            Self::Simulated(ref advertiser) => advertiser.is_advertising(),
         }
     }
 
@@ -153,6 +178,33 @@ impl BleAdvertiser for PlatformAdvertiser {
                     .update_advertising_data(peer_id, identity, config)
                     .await
             }
+            Self::Simulated(ref mut advertiser) => {
+                advertiser
+                    .update_advertising_data(peer_id, identity, config)
+                    .await
+            }
+        }
+    }
+
+    fn attach_write_sender(&mut self, sender: GattWriteSender) {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::Linux(ref mut advertiser) => advertiser.attach_write_sender(sender),
+            #[cfg(target_os = "macos")]
+            Self::MacOS(ref mut advertiser) => advertiser.attach_write_sender(sender),
+            Self::Fallback(ref mut advertiser) => advertiser.attach_write_sender(sender),
+            Self::Simulated(ref mut advertiser) => advertiser.attach_write_sender(sender),
+        }
+    }
+
+    fn gatt_server(&self) -> Option<GattServer> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::Linux(ref advertiser) => advertiser.gatt_server(),
+            #[cfg(target_os = "macos")]
+            Self::MacOS(ref advertiser) => advertiser.gatt_server(),
+            Self::Fallback(ref advertiser) => advertiser.gatt_server(),
+            Self::Simulated(ref advertiser) => advertiser.gatt_server(),
         }
     }
 }
@@ -0,0 +1,93 @@
+//! Advertising on a simulated radio
+
+use std::collections::HashMap;
+
+use bitchat_core::internal::IdentityKeyPair;
+use bitchat_core::{PeerId, Result as BitchatResult};
+use tracing::debug;
+
+use crate::config::BleTransportConfig;
+use crate::link::simulated::SimulatedNode;
+use crate::link::{BleAdvertisement, GattWriteSender};
+use crate::protocol::{generate_advertising_data, generate_device_name};
+
+use super::{BleAdvertiser, GattServer};
+
+// ----------------------------------------------------------------------------
+// Simulated Implementation
+// ----------------------------------------------------------------------------
+
+/// Advertiser and GATT server of a simulated node
+pub struct SimulatedAdvertiser {
+    node: SimulatedNode,
+    is_advertising: bool,
+}
+
+impl SimulatedAdvertiser {
+    pub fn new(node: SimulatedNode) -> Self {
+        Self {
+            node,
+            is_advertising: false,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BleAdvertiser for SimulatedAdvertiser {
+    async fn start_advertising(
+        &mut self,
+        peer_id: &PeerId,
+        identity: &IdentityKeyPair,
+        config: &BleTransportConfig,
+    ) -> BitchatResult<()> {
+        let device_name = generate_device_name(peer_id, &config.device_name_prefix);
+        let secure_advertising_data = generate_advertising_data(*peer_id, identity, &device_name)?;
+
+        let mut manufacturer_data = HashMap::new();
+        manufacturer_data.insert(0xFFFF, secure_advertising_data); // Same company ID as Linux
+
+        self.node.set_advertisement(Some(BleAdvertisement {
+            local_name: Some(device_name.clone()),
+            manufacturer_data,
+            rssi: None,
+        }));
+        self.is_advertising = true;
+
+        debug!(
+            "Simulated node {} advertising as '{}'",
+            self.node.address(),
+            device_name
+        );
+        Ok(())
+    }
+
+    async fn stop_advertising(&mut self) -> BitchatResult<()> {
+        self.node.set_advertisement(None);
+        self.is_advertising = false;
+        Ok(())
+    }
+
+    fn is_advertising(&self) -> bool {
+        self.is_advertising
+    }
+
+    async fn update_advertising_data(
+        &mut self,
+        peer_id: &PeerId,
+        identity: &IdentityKeyPair,
+        config: &BleTransportConfig,
+    ) -> BitchatResult<()> {
+        if self.is_advertising {
+            self.start_advertising(peer_id, identity, config).await?;
+        }
+        Ok(())
+    }
+
+    fn attach_write_sender(&mut self, sender: GattWriteSender) {
+        self.node.set_gatt_writes(sender);
+    }
+
+    fn gatt_server(&self) -> Option<GattServer> {
+        Some(self.node.gatt_server())
+    }
+}
@@ -2,12 +2,21 @@
 
 use std::time::Duration;
 
+use bitchat_core::config::BleTransportConfig as CanonicalBleConfig;
+
 // ----------------------------------------------------------------------------
 // Configuration
 // ----------------------------------------------------------------------------
 
+/// How often the advertised peer ID changes unless configured otherwise
+pub const DEFAULT_PEER_ID_ROTATION_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
 /// Configuration for BLE transport
+///
+/// Scan duty cycle and connection budget defaults come from the canonical
+/// [`bitchat_core::config::BleTransportConfig`].
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct BleTransportConfig {
     /// Maximum time to wait for scanning
     pub scan_timeout: Duration,
@@ -19,6 +28,23 @@ pub struct BleTransportConfig {
     pub device_name_prefix: String,
     /// Whether to automatically reconnect on disconnection
     pub auto_reconnect: bool,
+    /// How long each scan window lasts
+    pub duty_on_duration: Duration,
+    /// Pause between scan windows
+    pub duty_off_duration: Duration,
+    /// Maximum links we hold as central, counting ones still connecting
+    pub max_central_links: usize,
+    /// Weakest signal in dBm we still connect to
+    pub dynamic_rssi_threshold: i32,
+    /// Maximum discovered peers kept waiting for a connection slot
+    pub connection_candidates_max: usize,
+    /// Minimum time between connection attempts
+    pub connect_rate_limit_interval: Duration,
+    /// How often to advertise under a fresh ephemeral peer ID
+    ///
+    /// `None` advertises the identity's own fingerprint, which lets passive
+    /// observers follow the device.
+    pub peer_id_rotation_interval: Option<Duration>,
 }
 
 impl Default for BleTransportConfig {
@@ -29,6 +55,7 @@ impl Default for BleTransportConfig {
             max_packet_size: 500, // Conservative limit for BLE
             device_name_prefix: "BitChat".to_string(),
             auto_reconnect: true,
+            ..Self::from_canonical(&CanonicalBleConfig::canonical())
         }
     }
 }
@@ -39,6 +66,24 @@ impl BleTransportConfig {
         Self::default()
     }
 
+    /// Create a configuration from the canonical core BLE parameters
+    pub fn from_canonical(canonical: &CanonicalBleConfig) -> Self {
+        Self {
+            scan_timeout: canonical.scan_timeout,
+            connection_timeout: canonical.connection_timeout,
+            max_packet_size: canonical.max_packet_size,
+            device_name_prefix: canonical.device_name_prefix.clone(),
+            auto_reconnect: true,
+            duty_on_duration: canonical.duty_on_duration,
+            duty_off_duration: canonical.duty_off_duration,
+            max_central_links: canonical.max_central_links,
+            dynamic_rssi_threshold: canonical.dynamic_rssi_threshold,
+            connection_candidates_max: canonical.connection_candidates_max,
+            connect_rate_limit_interval: canonical.connect_rate_limit_interval,
+            peer_id_rotation_interval: Some(DEFAULT_PEER_ID_ROTATION_INTERVAL),
+        }
+    }
+
     /// Set scan timeout
     pub fn with_scan_timeout(mut self, timeout: Duration) -> Self {
         self.scan_timeout = timeout;
@@ -68,4 +113,35 @@ impl BleTransportConfig {
         self.auto_reconnect = enabled;
         self
     }
+
+    /// Set scan window and pause durations
+    pub fn with_duty_cycle(mut self, on: Duration, off: Duration) -> Self {
+        self.duty_on_duration = on;
+        self.duty_off_duration = off;
+        self
+    }
+
+    /// Set maximum central links
+    pub fn with_max_central_links(mut self, max: usize) -> Self {
+        self.max_central_links = max;
+        self
+    }
+
+    /// Set weakest signal to connect to
+    pub fn with_rssi_threshold(mut self, threshold: i32) -> Self {
+        self.dynamic_rssi_threshold = threshold;
+        self
+    }
+
+    /// Set minimum time between connection attempts
+    pub fn with_connect_rate_limit(mut self, interval: Duration) -> Self {
+        self.connect_rate_limit_interval = interval;
+        self
+    }
+
+    /// Set how often the advertised peer ID rotates, or disable rotation
+    pub fn with_peer_id_rotation(mut self, interval: Option<Duration>) -> Self {
+        self.peer_id_rotation_interval = interval;
+        self
+    }
 }
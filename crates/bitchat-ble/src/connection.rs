@@ -1,20 +1,15 @@
 //! BLE connection management and data transmission
 
-use std::collections::HashMap;
-use std::sync::Arc;
-
 use bitchat_core::internal::TransportError;
 use bitchat_core::{BitchatError, PeerId, Result as BitchatResult};
-use btleplug::api::{Peripheral as _, WriteType};
-use btleplug::platform::Peripheral;
 use futures::stream::StreamExt;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::mpsc;
 use tokio::time::timeout;
 use tracing::{debug, error, info};
 
 use crate::config::BleTransportConfig;
-use crate::peer::BlePeer;
-use crate::protocol::{BITCHAT_RX_CHARACTERISTIC_UUID, BITCHAT_TX_CHARACTERISTIC_UUID};
+use crate::link::BleLink;
+use crate::peer::SharedPeers;
 
 // ----------------------------------------------------------------------------
 // Connection Management
@@ -39,10 +34,10 @@ impl BleConnection {
 
     /// Connect to a specific peer with proper state management
     #[allow(dead_code)]
-    pub async fn connect_to_peer(
+    pub async fn connect_to_peer<L: BleLink>(
         &self,
         peer_id: &PeerId,
-        peers: &Arc<RwLock<HashMap<PeerId, BlePeer>>>,
+        peers: &SharedPeers<L>,
     ) -> BitchatResult<()> {
         let mut peers_lock = peers.write().await;
         let peer = peers_lock.get_mut(peer_id).ok_or_else(|| {
@@ -71,37 +66,22 @@ impl BleConnection {
 
         peer.start_connection_attempt();
 
-        let connect_result =
-            timeout(self.config.connection_timeout, peer.peripheral.connect()).await;
+        let connect_result = timeout(self.config.connection_timeout, peer.link.connect()).await;
 
         match connect_result {
             Ok(Ok(_)) => {
                 peer.mark_connected();
                 info!("Connected to peer: {}", peer_id);
 
-                // Discover services and characteristics
-                if let Err(e) = peer.peripheral.discover_services().await {
-                    error!("Failed to discover services for peer {}: {}", peer_id, e);
-                    peer.mark_failed();
-                    return Err(BitchatError::Transport(TransportError::ConnectionFailed {
-                        peer_id: peer_id.to_string(),
-                        reason: format!("Failed to discover services: {}", e),
-                    }));
-                }
-
                 // Start receiving data from this peer
-                self.start_receiving_from_peer(peer_id, &peer.peripheral)
-                    .await?;
+                self.start_receiving_from_peer(peer_id, &peer.link).await?;
 
                 Ok(())
             }
             Ok(Err(e)) => {
                 peer.mark_failed();
                 error!("Failed to connect to peer {}: {}", peer_id, e);
-                Err(BitchatError::Transport(TransportError::ConnectionFailed {
-                    peer_id: peer_id.to_string(),
-                    reason: format!("Connection failed: {}", e),
-                }))
+                Err(e)
             }
             Err(_) => {
                 peer.mark_failed();
@@ -115,47 +95,25 @@ impl BleConnection {
 
     /// Start receiving data from a connected peer
     #[allow(dead_code)]
-    pub async fn start_receiving_from_peer(
+    pub async fn start_receiving_from_peer<L: BleLink>(
         &self,
         peer_id: &PeerId,
-        peripheral: &Peripheral,
+        link: &L,
     ) -> BitchatResult<()> {
-        // Find the RX characteristic
-        let characteristics = peripheral.characteristics();
-        let rx_char = characteristics
-            .iter()
-            .find(|c| c.uuid == BITCHAT_RX_CHARACTERISTIC_UUID)
-            .ok_or_else(|| {
-                BitchatError::Transport(TransportError::InvalidConfiguration {
-                    reason: "RX characteristic not found".to_string(),
-                })
-            })?;
-
         // Subscribe to notifications
-        peripheral.subscribe(rx_char).await.map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to subscribe to notifications: {}", e),
-            })
-        })?;
+        let mut notifications = link.notifications().await?;
 
         // Start notification handler
         let packet_tx = self.packet_tx.clone();
         let peer_id_copy = *peer_id;
-        let mut notifications = peripheral.notifications().await.map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to get notifications stream: {}", e),
-            })
-        })?;
 
         tokio::spawn(async move {
             while let Some(data) = notifications.next().await {
-                if data.uuid == BITCHAT_RX_CHARACTERISTIC_UUID {
-                    // Send raw data without deserializing
-                    if !data.value.is_empty() {
-                        if let Err(e) = packet_tx.send((peer_id_copy, data.value)) {
-                            error!("Failed to send received packet to channel: {}", e);
-                            break;
-                        }
+                // Send raw data without deserializing
+                if !data.is_empty() {
+                    if let Err(e) = packet_tx.send((peer_id_copy, data)) {
+                        error!("Failed to send received packet to channel: {}", e);
+                        break;
                     }
                 }
             }
@@ -166,11 +124,11 @@ impl BleConnection {
     }
 
     /// Send data to a connected peer
-    pub async fn send_to_peer(
+    pub async fn send_to_peer<L: BleLink>(
         &self,
         peer_id: &PeerId,
         data: &[u8],
-        peers: &Arc<RwLock<HashMap<PeerId, BlePeer>>>,
+        peers: &SharedPeers<L>,
     ) -> BitchatResult<()> {
         let peers_lock = peers.read().await;
         let peer = peers_lock.get(peer_id).ok_or_else(|| {
@@ -186,30 +144,9 @@ impl BleConnection {
             }));
         }
 
-        // Find the TX characteristic
-        let characteristics = peer.peripheral.characteristics();
-        let tx_char = characteristics
-            .iter()
-            .find(|c| c.uuid == BITCHAT_TX_CHARACTERISTIC_UUID)
-            .ok_or_else(|| {
-                BitchatError::Transport(TransportError::InvalidConfiguration {
-                    reason: "TX characteristic not found".to_string(),
-                })
-            })?;
-
         // Split data into chunks if necessary (BLE MTU limitations)
-        const BLE_MTU: usize = 244; // Conservative MTU size
-        let chunks: Vec<&[u8]> = data.chunks(BLE_MTU).collect();
-
-        for chunk in chunks {
-            peer.peripheral
-                .write(tx_char, chunk, WriteType::WithoutResponse)
-                .await
-                .map_err(|e| {
-                    BitchatError::Transport(TransportError::InvalidConfiguration {
-                        reason: format!("Failed to write to characteristic: {}", e),
-                    })
-                })?;
+        for chunk in data.chunks(peer.link.mtu()) {
+            peer.link.write(chunk).await?;
         }
 
         debug!("Sent {} bytes to peer {}", data.len(), peer_id);
@@ -218,15 +155,15 @@ impl BleConnection {
 
     /// Disconnect from a peer
     #[allow(dead_code)]
-    pub async fn disconnect_peer(
+    pub async fn disconnect_peer<L: BleLink>(
         &self,
         peer_id: &PeerId,
-        peers: &Arc<RwLock<HashMap<PeerId, BlePeer>>>,
+        peers: &SharedPeers<L>,
     ) -> BitchatResult<()> {
         let mut peers_lock = peers.write().await;
         if let Some(peer) = peers_lock.get_mut(peer_id) {
             if peer.is_connected() {
-                if let Err(e) = peer.peripheral.disconnect().await {
+                if let Err(e) = peer.link.disconnect().await {
                     error!("Failed to disconnect from peer {}: {}", peer_id, e);
                 }
                 peer.mark_disconnected();
@@ -238,14 +175,14 @@ impl BleConnection {
 
     /// Disconnect from all peers
     #[allow(dead_code)]
-    pub async fn disconnect_all_peers(
+    pub async fn disconnect_all_peers<L: BleLink>(
         &self,
-        peers: &Arc<RwLock<HashMap<PeerId, BlePeer>>>,
+        peers: &SharedPeers<L>,
     ) -> BitchatResult<()> {
         let mut peers_lock = peers.write().await;
         for (peer_id, peer) in peers_lock.iter_mut() {
             if peer.is_connected() {
-                if let Err(e) = peer.peripheral.disconnect().await {
+                if let Err(e) = peer.link.disconnect().await {
                     error!("Failed to disconnect from peer {}: {}", peer_id, e);
                 }
                 peer.mark_disconnected();
@@ -257,10 +194,7 @@ impl BleConnection {
 
     /// Get list of connected peer IDs
     #[allow(dead_code)]
-    pub async fn get_connected_peers(
-        &self,
-        peers: &Arc<RwLock<HashMap<PeerId, BlePeer>>>,
-    ) -> Vec<PeerId> {
+    pub async fn get_connected_peers<L: BleLink>(&self, peers: &SharedPeers<L>) -> Vec<PeerId> {
         let peers_lock = peers.read().await;
         peers_lock
             .iter()
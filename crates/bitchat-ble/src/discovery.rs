@@ -1,202 +1,202 @@
 //! BLE device discovery, scanning, and advertising
 //!
 //! This module provides comprehensive BLE functionality including:
-//! - Device scanning and peer discovery using btleplug
+//! - Device scanning and peer discovery through a [`BleCentral`]
 //! - Cross-platform BLE advertising with platform-specific implementations
 //! - Production-ready peripheral mode support on supported platforms
 
-use std::collections::HashMap;
 use std::sync::Arc;
 
-use bitchat_core::internal::{IdentityKeyPair, TransportError};
-use bitchat_core::{BitchatError, PeerId, Result as BitchatResult};
-use btleplug::api::{Central, CentralEvent, Manager as _, Peripheral, ScanFilter};
-use btleplug::platform::{Adapter, Manager};
+use bitchat_core::internal::IdentityKeyPair;
+use bitchat_core::{PeerId, Result as BitchatResult};
+use futures::stream::BoxStream;
 use tokio::sync::RwLock;
+use tokio::time::Instant;
 use tracing::{debug, info};
 
-use crate::advertising::AdvertisingManager;
+use crate::advertising::{AdvertisingManager, GattServer};
 use crate::config::BleTransportConfig;
-use crate::peer::BlePeer;
-use crate::protocol::{extract_and_verify_peer_id, BITCHAT_SERVICE_UUID};
+use crate::link::platform::PlatformCentral;
+use crate::link::{BleAdvertisement, BleCentral, BleCentralEvent, BleLinkId, GattWriteSender};
+use crate::peer::{BlePeer, ConnectionState, SharedPeers};
+use crate::protocol::extract_and_verify_peer_id;
 
 // ----------------------------------------------------------------------------
 // Discovery Implementation
 // ----------------------------------------------------------------------------
 
 /// Handles BLE device discovery and scanning
-pub struct BleDiscovery {
+pub struct BleDiscovery<C: BleCentral = PlatformCentral> {
     config: BleTransportConfig,
-    adapter: Option<Adapter>,
+    central: C,
     advertising_manager: AdvertisingManager,
 }
 
-impl BleDiscovery {
+impl<C: BleCentral> BleDiscovery<C> {
     /// Create a new discovery manager
-    pub fn new(config: BleTransportConfig) -> Self {
+    pub fn new(
+        config: BleTransportConfig,
+        central: C,
+        mut advertising_manager: AdvertisingManager,
+    ) -> Self {
+        if let Some(interval) = config.peer_id_rotation_interval {
+            advertising_manager.enable_rotation(interval);
+        }
         Self {
             config,
-            adapter: None,
-            advertising_manager: AdvertisingManager::new(),
+            central,
+            advertising_manager,
         }
     }
 
-    /// Initialize BLE adapter
-    #[allow(dead_code)]
-    pub async fn initialize_adapter(&mut self) -> BitchatResult<()> {
-        let manager = Manager::new().await.map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to create BLE manager: {}", e),
-            })
-        })?;
-
-        let adapters = manager.adapters().await.map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to get BLE adapters: {}", e),
-            })
-        })?;
-
-        if adapters.is_empty() {
-            return Err(BitchatError::Transport(
-                TransportError::TransportUnavailable {
-                    transport_type: "BLE".to_string(),
-                },
-            ));
-        }
+    /// Subscribe to discoveries and dropped links from the central
+    pub async fn events(&self) -> BitchatResult<BoxStream<'static, BleCentralEvent>> {
+        self.central.events().await
+    }
 
-        self.adapter = Some(adapters[0].clone());
-        info!("BLE adapter initialized");
-        Ok(())
+    /// Deliver what remote centrals write to our GATT server to `sender`
+    pub fn attach_write_sender(&mut self, sender: GattWriteSender) {
+        self.advertising_manager.attach_write_sender(sender);
     }
 
-    /// Get adapter reference
-    #[allow(dead_code)]
-    pub fn adapter(&self) -> Option<&Adapter> {
-        self.adapter.as_ref()
+    /// GATT server notifying centrals subscribed to us, if the platform has one
+    pub fn gatt_server(&self) -> Option<GattServer> {
+        self.advertising_manager.gatt_server()
     }
 
     /// Start scanning for BitChat peers
     pub async fn start_scanning(&self) -> BitchatResult<()> {
-        let adapter = self.adapter.as_ref().ok_or_else(|| {
-            BitchatError::Transport(TransportError::TransportUnavailable {
-                transport_type: "BLE adapter not initialized".to_string(),
-            })
-        })?;
-
-        let scan_filter = ScanFilter {
-            services: vec![BITCHAT_SERVICE_UUID],
-        };
-
-        adapter.start_scan(scan_filter).await.map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to start BLE scan: {}", e),
-            })
-        })?;
-
+        self.central.start_scan().await?;
         info!("Started BLE scanning for BitChat peers");
         Ok(())
     }
 
     /// Stop scanning for peers
     pub async fn stop_scanning(&self) -> BitchatResult<()> {
-        if let Some(adapter) = &self.adapter {
-            adapter.stop_scan().await.map_err(|e| {
-                BitchatError::Transport(TransportError::InvalidConfiguration {
-                    reason: format!("Failed to stop BLE scan: {}", e),
-                })
-            })?;
-        }
-        Ok(())
+        self.central.stop_scan().await
     }
 
-    /// Process discovery events from BLE adapter
+    /// Process discovery events from the central
     ///
-    /// Uses cryptographic verification to prevent peer impersonation attacks.
-    /// Only devices with valid signed PeerAnnouncements will be accepted.
-    #[allow(dead_code)]
+    /// Returns the peer the event was about: a newly discovered peer, or one whose
+    /// link dropped. Uses cryptographic verification to prevent peer impersonation
+    /// attacks. Only devices with valid signed PeerAnnouncements will be accepted.
     pub async fn process_discovery_event(
         &self,
-        event: CentralEvent,
-        peers: &Arc<RwLock<HashMap<PeerId, BlePeer>>>,
+        event: BleCentralEvent,
+        peers: &SharedPeers<C::Link>,
         cached_peers: &Arc<RwLock<Vec<PeerId>>>,
-    ) -> BitchatResult<()> {
+    ) -> BitchatResult<Option<PeerId>> {
         match event {
-            CentralEvent::DeviceDiscovered(id) => {
-                if let Some(adapter) = &self.adapter {
-                    if let Ok(peripheral) = adapter.peripheral(&id).await {
-                        if let Ok(Some(properties)) = peripheral.properties().await {
-                            if let Some(name) = &properties.local_name {
-                                if name.starts_with(&self.config.device_name_prefix) {
-                                    // Get manufacturer-specific data for secure verification
-                                    let manufacturer_data = properties
-                                        .manufacturer_data
-                                        .get(&0xFFFF) // Use company ID 0xFFFF for test/development
-                                        .cloned()
-                                        .unwrap_or_default();
-
-                                    if manufacturer_data.is_empty() {
-                                        debug!("Rejecting device '{}' - missing secure advertising data", name);
-                                        return Ok(());
-                                    }
-
-                                    match extract_and_verify_peer_id(
-                                        name,
-                                        &manufacturer_data,
-                                        &self.config.device_name_prefix,
-                                        60, // 60 second max age
-                                    ) {
-                                        Ok(Some(peer_id)) => {
-                                            let ble_peer =
-                                                BlePeer::new(peer_id, peripheral, name.clone());
-
-                                            let mut peers_lock = peers.write().await;
-                                            if let std::collections::hash_map::Entry::Vacant(e) =
-                                                peers_lock.entry(peer_id)
-                                            {
-                                                debug!(
-                                                    "Discovered secure BitChat peer: {} ({})",
-                                                    peer_id, name
-                                                );
-                                                e.insert(ble_peer);
-
-                                                // Update cached peers list
-                                                let mut cached = cached_peers.write().await;
-                                                cached.push(peer_id);
-                                            }
-                                        }
-                                        Ok(None) => {
-                                            debug!(
-                                                "Cryptographic verification failed for device: {}",
-                                                name
-                                            );
-                                        }
-                                        Err(e) => {
-                                            debug!(
-                                                "Invalid advertising data from device '{}': {}",
-                                                name, e
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            BleCentralEvent::Discovered { id, advertisement } => {
+                self.process_advertisement(id, advertisement, peers, cached_peers)
+                    .await
             }
-            CentralEvent::DeviceDisconnected(id) => {
-                // Find peer by peripheral ID and mark as disconnected
+            BleCentralEvent::Disconnected { id } => {
+                // Find peer by link ID and mark as disconnected
                 let mut peers_lock = peers.write().await;
                 for peer in peers_lock.values_mut() {
-                    if peer.peripheral_id() == id {
+                    if peer.link_id() == id {
+                        let was_connected = peer.is_connected();
                         peer.mark_disconnected();
                         debug!("Peer {} disconnected", peer.peer_id);
-                        break;
+                        return Ok(was_connected.then_some(peer.peer_id));
                     }
                 }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Verify an advertisement and record the peer behind it
+    async fn process_advertisement(
+        &self,
+        id: BleLinkId,
+        advertisement: BleAdvertisement,
+        peers: &SharedPeers<C::Link>,
+        cached_peers: &Arc<RwLock<Vec<PeerId>>>,
+    ) -> BitchatResult<Option<PeerId>> {
+        let Some(name) = advertisement
+            .local_name
+            .filter(|name| name.starts_with(&self.config.device_name_prefix))
+        else {
+            return Ok(None);
+        };
+
+        // Get manufacturer-specific data for secure verification
+        let manufacturer_data = advertisement
+            .manufacturer_data
+            .get(&0xFFFF) // Use company ID 0xFFFF for test/development
+            .cloned()
+            .unwrap_or_default();
+
+        if manufacturer_data.is_empty() {
+            debug!(
+                "Rejecting device '{}' - missing secure advertising data",
+                name
+            );
+            return Ok(None);
+        }
+
+        let peer_id = match extract_and_verify_peer_id(
+            &name,
+            &manufacturer_data,
+            &self.config.device_name_prefix,
+            60, // 60 second max age
+        ) {
+            Ok(Some(peer_id)) => peer_id,
+            Ok(None) => {
+                debug!("Cryptographic verification failed for device: {}", name);
+                return Ok(None);
+            }
+            Err(e) => {
+                debug!("Invalid advertising data from device '{}': {}", name, e);
+                return Ok(None);
+            }
+        };
+
+        let mut peers_lock = peers.write().await;
+
+        // A known device advertising a rotated ID keeps its link and identity
+        let rotated_from = peers_lock
+            .values()
+            .find(|peer| peer.link_id() == id && peer.peer_id != peer_id)
+            .map(|peer| peer.peer_id);
+        if let Some(old_peer_id) = rotated_from {
+            if let Some(mut peer) = peers_lock.remove(&old_peer_id) {
+                debug!("BLE peer {} rotated to {}", old_peer_id, peer_id);
+                peer.peer_id = peer_id;
+                peer.device_name = name;
+                peer.rssi = advertisement.rssi;
+                peers_lock.insert(peer_id, peer);
+
+                let mut cached = cached_peers.write().await;
+                cached.retain(|cached_id| *cached_id != old_peer_id);
+                cached.push(peer_id);
+            }
+            return Ok(None);
+        }
+
+        match peers_lock.entry(peer_id) {
+            std::collections::hash_map::Entry::Occupied(mut e) => {
+                // A peer whose link dropped is reported again once it is back in range
+                let peer = e.get_mut();
+                peer.rssi = advertisement.rssi;
+                Ok((peer.connection_state == ConnectionState::Disconnected).then_some(peer_id))
+            }
+            std::collections::hash_map::Entry::Vacant(e) => {
+                let link = self.central.link(&id).await?;
+                let mut ble_peer = BlePeer::new(peer_id, link, name.clone());
+                ble_peer.rssi = advertisement.rssi;
+                debug!("Discovered secure BitChat peer: {} ({})", peer_id, name);
+                e.insert(ble_peer);
+
+                // Update cached peers list
+                let mut cached = cached_peers.write().await;
+                cached.push(peer_id);
+                Ok(Some(peer_id))
             }
-            _ => {}
         }
-        Ok(())
     }
 
     /// Start advertising as a BitChat peer
@@ -230,11 +230,19 @@ impl BleDiscovery {
         self.advertising_manager.is_advertising()
     }
 
-    /// Rotate advertising data for privacy
-    #[allow(dead_code)]
-    pub async fn rotate_advertising(&mut self, identity: &IdentityKeyPair) -> BitchatResult<()> {
+    /// When the advertised peer ID is next due to rotate
+    pub fn next_rotation(&self) -> Option<Instant> {
+        self.advertising_manager.next_rotation()
+    }
+
+    /// Rotate advertising data to a new peer ID for privacy
+    pub async fn rotate_advertising(
+        &mut self,
+        peer_id: PeerId,
+        identity: &IdentityKeyPair,
+    ) -> BitchatResult<()> {
         self.advertising_manager
-            .rotate(identity, &self.config)
+            .rotate(peer_id, identity, &self.config)
             .await?;
         debug!("Rotated BLE advertising data");
         Ok(())
@@ -10,8 +10,11 @@
 //! - [`config`] - Transport configuration and settings
 //! - [`error`] - Error types specific to BLE transport
 //! - [`protocol`] - BLE protocol constants and utilities
+//! - [`link`] - Hardware-independent central and link traits, with btleplug and
+//!   simulated radio backends
 //! - [`peer`] - Peer state management and connection tracking
 //! - [`discovery`] - Device scanning and peer discovery
+//! - [`scheduling`] - Scan duty cycling and the central connection budget
 //! - [`connection`] - Connection management and data transmission
 //! - [`transport`] - Main transport implementation
 //!
@@ -50,20 +53,39 @@
 //!
 //! ### Discovery Support
 //! Linux and macOS support peer discovery via btleplug's central mode scanning.
+//!
+//! ### Simulation
+//! [`SimulatedRadio`] stands in for the radio on any platform. Each
+//! [`SimulatedNode`] gets a central and advertiser for
+//! [`BleTransportTask::with_central`], and the radio controls which nodes are in
+//! range, at what signal strength, and when links drop.
 
 mod advertising;
 mod config;
 mod connection;
 mod discovery;
 mod error;
+pub mod link;
 mod peer;
 mod protocol;
+mod scheduling;
 mod transport;
 
 // Public API exports
-pub use advertising::{AdvertisingManager, BleAdvertiser};
-pub use config::BleTransportConfig;
+pub use advertising::{
+    AdvertisingManager, BleAdvertiser, GattNotifier, GattServer, PlatformAdvertiser,
+    SimulatedAdvertiser,
+};
+pub use config::{BleTransportConfig, DEFAULT_PEER_ID_ROTATION_INTERVAL};
 pub use error::BleTransportError;
+pub use link::platform::{PlatformCentral, PlatformLink};
+pub use link::simulated::{
+    SimulatedCentral, SimulatedLink, SimulatedNode, SimulatedNodeConfig, SimulatedRadio,
+};
+pub use link::{
+    BleAdvertisement, BleCentral, BleCentralEvent, BleLink, BleLinkId, GattWriteSender,
+    DEFAULT_LINK_MTU,
+};
 pub use peer::{BlePeer, ConnectionState};
 pub use protocol::{
     generate_device_name, BITCHAT_RX_CHARACTERISTIC_UUID, BITCHAT_SERVICE_UUID,
@@ -0,0 +1,118 @@
+//! Hardware-independent BLE link layer
+//!
+//! The transport reaches the radio only through [`BleCentral`], which scans for
+//! BitChat peripherals and opens links to them, and [`BleLink`], one link to a
+//! remote peripheral's GATT service. [`platform`] implements both over btleplug
+//! and [`simulated`] over an in-memory radio, so the scan, connect and forwarding
+//! logic runs the same against either.
+
+pub mod platform;
+pub mod simulated;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use async_trait::async_trait;
+use bitchat_core::Result as BitchatResult;
+use futures::stream::BoxStream;
+use tokio::sync::mpsc;
+
+// ----------------------------------------------------------------------------
+// Link Types
+// ----------------------------------------------------------------------------
+
+/// Conservative ATT payload size for a single write when the stack hides the MTU
+pub const DEFAULT_LINK_MTU: usize = 244;
+
+/// Radio address of a remote BLE device
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BleLinkId(String);
+
+impl BleLinkId {
+    /// Create a link ID from a platform address
+    pub fn new(address: impl Into<String>) -> Self {
+        Self(address.into())
+    }
+
+    /// Get the address as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for BleLinkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Advertising data seen while scanning
+#[derive(Debug, Clone, Default)]
+pub struct BleAdvertisement {
+    /// Advertised local name
+    pub local_name: Option<String>,
+    /// Manufacturer-specific data by company ID
+    pub manufacturer_data: HashMap<u16, Vec<u8>>,
+    /// Received signal strength in dBm
+    pub rssi: Option<i16>,
+}
+
+/// Radio events reported by a [`BleCentral`]
+#[derive(Debug, Clone)]
+pub enum BleCentralEvent {
+    /// A device advertised while we were scanning
+    Discovered {
+        id: BleLinkId,
+        advertisement: BleAdvertisement,
+    },
+    /// The link to a device dropped
+    Disconnected { id: BleLinkId },
+}
+
+/// Data remote centrals wrote to our TX characteristic, by the writer's address
+pub type GattWriteSender = mpsc::UnboundedSender<(BleLinkId, Vec<u8>)>;
+
+// ----------------------------------------------------------------------------
+// Link Traits
+// ----------------------------------------------------------------------------
+
+/// One link from our central to a remote BitChat peripheral
+#[async_trait]
+pub trait BleLink: Clone + fmt::Debug + Send + Sync + 'static {
+    /// Radio address of the remote device
+    fn id(&self) -> BleLinkId;
+
+    /// Largest payload a single write can carry
+    fn mtu(&self) -> usize;
+
+    /// Connect and discover the BitChat service
+    async fn connect(&self) -> BitchatResult<()>;
+
+    /// Close the link
+    async fn disconnect(&self) -> BitchatResult<()>;
+
+    /// Write to the remote TX characteristic without response
+    async fn write(&self, data: &[u8]) -> BitchatResult<()>;
+
+    /// Subscribe to the remote RX characteristic
+    async fn notifications(&self) -> BitchatResult<BoxStream<'static, Vec<u8>>>;
+}
+
+/// The central role of a BLE radio: scanning and opening links
+#[async_trait]
+pub trait BleCentral: Send + Sync + 'static {
+    /// Link type this central opens
+    type Link: BleLink;
+
+    /// Stream of discoveries and dropped links
+    async fn events(&self) -> BitchatResult<BoxStream<'static, BleCentralEvent>>;
+
+    /// Start scanning for BitChat peripherals
+    async fn start_scan(&self) -> BitchatResult<()>;
+
+    /// Stop scanning
+    async fn stop_scan(&self) -> BitchatResult<()>;
+
+    /// Get a link to a discovered device, without connecting
+    async fn link(&self, id: &BleLinkId) -> BitchatResult<Self::Link>;
+}
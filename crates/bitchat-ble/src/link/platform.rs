@@ -0,0 +1,249 @@
+//! BLE links over the platform Bluetooth stack via btleplug
+
+use bitchat_core::internal::TransportError;
+use bitchat_core::{BitchatError, Result as BitchatResult};
+use btleplug::api::{
+    Central, CentralEvent, Characteristic, Manager as _, Peripheral as _, ScanFilter, WriteType,
+};
+use btleplug::platform::{Adapter, Manager, Peripheral, PeripheralId};
+use futures::stream::{BoxStream, StreamExt};
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::protocol::{
+    BITCHAT_RX_CHARACTERISTIC_UUID, BITCHAT_SERVICE_UUID, BITCHAT_TX_CHARACTERISTIC_UUID,
+};
+
+use super::{BleAdvertisement, BleCentral, BleCentralEvent, BleLink, BleLinkId, DEFAULT_LINK_MTU};
+
+// ----------------------------------------------------------------------------
+// Platform Central
+// ----------------------------------------------------------------------------
+
+/// Central backed by the first Bluetooth adapter btleplug finds
+///
+/// The adapter is opened on first use, so creating a transport does not need a radio.
+#[derive(Default)]
+pub struct PlatformCentral {
+    adapter: OnceCell<Adapter>,
+}
+
+impl PlatformCentral {
+    /// Create a central that opens the adapter lazily
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the adapter, opening it if needed
+    async fn adapter(&self) -> BitchatResult<&Adapter> {
+        self.adapter
+            .get_or_try_init(|| async {
+                let manager = Manager::new().await.map_err(|e| {
+                    BitchatError::Transport(TransportError::InvalidConfiguration {
+                        reason: format!("Failed to create BLE manager: {}", e),
+                    })
+                })?;
+
+                let adapters = manager.adapters().await.map_err(|e| {
+                    BitchatError::Transport(TransportError::InvalidConfiguration {
+                        reason: format!("Failed to get BLE adapters: {}", e),
+                    })
+                })?;
+
+                let adapter = adapters.into_iter().next().ok_or_else(|| {
+                    BitchatError::Transport(TransportError::TransportUnavailable {
+                        transport_type: "BLE".to_string(),
+                    })
+                })?;
+                info!("BLE adapter initialized");
+                Ok(adapter)
+            })
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl BleCentral for PlatformCentral {
+    type Link = PlatformLink;
+
+    async fn events(&self) -> BitchatResult<BoxStream<'static, BleCentralEvent>> {
+        let adapter = self.adapter().await?.clone();
+        let events = adapter.events().await.map_err(|e| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: format!("Failed to get BLE events: {}", e),
+            })
+        })?;
+
+        Ok(events
+            .filter_map(move |event| {
+                let adapter = adapter.clone();
+                async move {
+                    match event {
+                        CentralEvent::DeviceDiscovered(id) | CentralEvent::DeviceUpdated(id) => {
+                            let peripheral = adapter.peripheral(&id).await.ok()?;
+                            let properties = peripheral.properties().await.ok()??;
+                            Some(BleCentralEvent::Discovered {
+                                id: link_id(&id),
+                                advertisement: BleAdvertisement {
+                                    local_name: properties.local_name,
+                                    manufacturer_data: properties.manufacturer_data,
+                                    rssi: properties.rssi,
+                                },
+                            })
+                        }
+                        CentralEvent::DeviceDisconnected(id) => {
+                            Some(BleCentralEvent::Disconnected { id: link_id(&id) })
+                        }
+                        _ => None,
+                    }
+                }
+            })
+            .boxed())
+    }
+
+    async fn start_scan(&self) -> BitchatResult<()> {
+        let scan_filter = ScanFilter {
+            services: vec![BITCHAT_SERVICE_UUID],
+        };
+
+        self.adapter()
+            .await?
+            .start_scan(scan_filter)
+            .await
+            .map_err(|e| {
+                BitchatError::Transport(TransportError::InvalidConfiguration {
+                    reason: format!("Failed to start BLE scan: {}", e),
+                })
+            })
+    }
+
+    async fn stop_scan(&self) -> BitchatResult<()> {
+        // Nothing to stop if the adapter was never opened
+        if let Some(adapter) = self.adapter.get() {
+            adapter.stop_scan().await.map_err(|e| {
+                BitchatError::Transport(TransportError::InvalidConfiguration {
+                    reason: format!("Failed to stop BLE scan: {}", e),
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn link(&self, id: &BleLinkId) -> BitchatResult<PlatformLink> {
+        let peripherals = self.adapter().await?.peripherals().await.map_err(|e| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: format!("Failed to list BLE peripherals: {}", e),
+            })
+        })?;
+
+        peripherals
+            .into_iter()
+            .find(|peripheral| link_id(&peripheral.id()) == *id)
+            .map(|peripheral| PlatformLink { peripheral })
+            .ok_or_else(|| {
+                BitchatError::Transport(TransportError::PeerNotFound {
+                    peer_id: id.to_string(),
+                })
+            })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Platform Link
+// ----------------------------------------------------------------------------
+
+/// Link to a btleplug peripheral
+#[derive(Debug, Clone)]
+pub struct PlatformLink {
+    peripheral: Peripheral,
+}
+
+impl PlatformLink {
+    /// Find one of the BitChat characteristics discovered on connect
+    fn characteristic(&self, uuid: uuid::Uuid, name: &str) -> BitchatResult<Characteristic> {
+        self.peripheral
+            .characteristics()
+            .into_iter()
+            .find(|c| c.uuid == uuid)
+            .ok_or_else(|| {
+                BitchatError::Transport(TransportError::InvalidConfiguration {
+                    reason: format!("{} characteristic not found", name),
+                })
+            })
+    }
+}
+
+#[async_trait::async_trait]
+impl BleLink for PlatformLink {
+    fn id(&self) -> BleLinkId {
+        link_id(&self.peripheral.id())
+    }
+
+    fn mtu(&self) -> usize {
+        DEFAULT_LINK_MTU
+    }
+
+    async fn connect(&self) -> BitchatResult<()> {
+        self.peripheral.connect().await.map_err(|e| {
+            BitchatError::Transport(TransportError::ConnectionFailed {
+                peer_id: self.id().to_string(),
+                reason: format!("Connection failed: {}", e),
+            })
+        })?;
+
+        self.peripheral.discover_services().await.map_err(|e| {
+            BitchatError::Transport(TransportError::ConnectionFailed {
+                peer_id: self.id().to_string(),
+                reason: format!("Failed to discover services: {}", e),
+            })
+        })
+    }
+
+    async fn disconnect(&self) -> BitchatResult<()> {
+        self.peripheral.disconnect().await.map_err(|e| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: format!("Failed to disconnect: {}", e),
+            })
+        })
+    }
+
+    async fn write(&self, data: &[u8]) -> BitchatResult<()> {
+        let tx_char = self.characteristic(BITCHAT_TX_CHARACTERISTIC_UUID, "TX")?;
+        self.peripheral
+            .write(&tx_char, data, WriteType::WithoutResponse)
+            .await
+            .map_err(|e| {
+                BitchatError::Transport(TransportError::InvalidConfiguration {
+                    reason: format!("Failed to write to characteristic: {}", e),
+                })
+            })
+    }
+
+    async fn notifications(&self) -> BitchatResult<BoxStream<'static, Vec<u8>>> {
+        let rx_char = self.characteristic(BITCHAT_RX_CHARACTERISTIC_UUID, "RX")?;
+        self.peripheral.subscribe(&rx_char).await.map_err(|e| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: format!("Failed to subscribe to notifications: {}", e),
+            })
+        })?;
+
+        let notifications = self.peripheral.notifications().await.map_err(|e| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: format!("Failed to get notifications stream: {}", e),
+            })
+        })?;
+
+        Ok(notifications
+            .filter_map(|data| async move {
+                (data.uuid == BITCHAT_RX_CHARACTERISTIC_UUID).then_some(data.value)
+            })
+            .boxed())
+    }
+}
+
+/// Link ID for a btleplug peripheral
+///
+/// `PeripheralId` differs per platform, so its debug form serves as the opaque address.
+fn link_id(id: &PeripheralId) -> BleLinkId {
+    BleLinkId::new(format!("{:?}", id))
+}
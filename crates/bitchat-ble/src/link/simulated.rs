@@ -0,0 +1,576 @@
+//! Simulated in-memory BLE radio
+//!
+//! A [`SimulatedRadio`] connects any number of [`SimulatedNode`]s. Each node
+//! has a central for scanning and connecting, and an advertiser whose GATT
+//! server receives what remote centrals write and notifies the centrals
+//! subscribed to it. Tests place nodes in range of
+//! each other with a signal strength and drop links by moving them apart, so the
+//! BLE transport runs without a Bluetooth radio.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use bitchat_core::Result as BitchatResult;
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::sync::mpsc;
+
+use crate::advertising::{GattNotifier, GattServer, SimulatedAdvertiser};
+use crate::error::BleTransportError;
+
+use super::{
+    BleAdvertisement, BleCentral, BleCentralEvent, BleLink, BleLinkId, GattWriteSender,
+    DEFAULT_LINK_MTU,
+};
+
+// ----------------------------------------------------------------------------
+// Radio
+// ----------------------------------------------------------------------------
+
+/// Per-node radio limits
+#[derive(Debug, Clone)]
+pub struct SimulatedNodeConfig {
+    /// Largest write the node accepts
+    pub mtu: usize,
+    /// Links the node can hold at once, as central or peripheral
+    pub max_connections: usize,
+}
+
+impl Default for SimulatedNodeConfig {
+    fn default() -> Self {
+        Self {
+            mtu: DEFAULT_LINK_MTU,
+            max_connections: 7,
+        }
+    }
+}
+
+/// In-memory radio shared by simulated nodes
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedRadio {
+    state: Arc<Mutex<RadioState>>,
+}
+
+#[derive(Debug, Default)]
+struct RadioState {
+    nodes: HashMap<BleLinkId, NodeState>,
+    /// Signal strength between nodes in range, keyed by ordered address pair
+    rssi: HashMap<(BleLinkId, BleLinkId), i16>,
+    /// Open links by (central, peripheral), with the central's notification channel
+    links: HashMap<(BleLinkId, BleLinkId), Option<mpsc::UnboundedSender<Vec<u8>>>>,
+}
+
+#[derive(Debug)]
+struct NodeState {
+    config: SimulatedNodeConfig,
+    advertisement: Option<BleAdvertisement>,
+    scanning: bool,
+    central_events: Vec<mpsc::UnboundedSender<BleCentralEvent>>,
+    gatt_server: GattServer,
+}
+
+impl SimulatedRadio {
+    /// Create an empty radio
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node, out of range of every other node
+    pub fn add_node(&self, config: SimulatedNodeConfig) -> SimulatedNode {
+        let mut state = self.lock();
+        let address = BleLinkId::new(format!("sim-{}", state.nodes.len() + 1));
+        state.nodes.insert(
+            address.clone(),
+            NodeState {
+                config,
+                advertisement: None,
+                scanning: false,
+                central_events: Vec::new(),
+                gatt_server: GattServer::new(),
+            },
+        );
+        SimulatedNode {
+            radio: self.clone(),
+            address,
+        }
+    }
+
+    /// Put two nodes in range at a signal strength, or out of range with `None`
+    ///
+    /// Moving nodes out of range drops the links between them. Nodes coming
+    /// into range discover each other if one is scanning and the other advertising.
+    pub fn set_rssi(&self, a: &SimulatedNode, b: &SimulatedNode, rssi: Option<i16>) {
+        let mut state = self.lock();
+        let key = pair(&a.address, &b.address);
+        match rssi {
+            Some(rssi) => {
+                state.rssi.insert(key, rssi);
+                state.advertise_to(&a.address, &b.address);
+                state.advertise_to(&b.address, &a.address);
+            }
+            None => {
+                state.rssi.remove(&key);
+                state.drop_links(&a.address, &b.address);
+            }
+        }
+    }
+
+    /// Drop any links between two nodes, as if the connection was lost
+    pub fn disconnect(&self, a: &SimulatedNode, b: &SimulatedNode) {
+        self.lock().drop_links(&a.address, &b.address);
+    }
+
+    /// What a node currently advertises, as a passive observer would see it
+    pub fn advertisement(&self, node: &SimulatedNode) -> Option<BleAdvertisement> {
+        self.lock()
+            .nodes
+            .get(&node.address)
+            .and_then(|node| node.advertisement.clone())
+    }
+
+    /// Number of links a node holds, as central or peripheral
+    pub fn connection_count(&self, node: &SimulatedNode) -> usize {
+        self.lock().connection_count(&node.address)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RadioState> {
+        // A panicking test thread must not wedge the other nodes
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl RadioState {
+    fn node(&self, address: &BleLinkId) -> Result<&NodeState, BleTransportError> {
+        self.nodes
+            .get(address)
+            .ok_or_else(|| BleTransportError::PeerNotFound {
+                peer_id: address.to_string(),
+            })
+    }
+
+    fn rssi(&self, a: &BleLinkId, b: &BleLinkId) -> Option<i16> {
+        self.rssi.get(&pair(a, b)).copied()
+    }
+
+    fn connection_count(&self, address: &BleLinkId) -> usize {
+        self.links
+            .keys()
+            .filter(|(central, peripheral)| central == address || peripheral == address)
+            .count()
+    }
+
+    /// Report `advertiser` to `scanner` if it can currently see it
+    fn advertise_to(&mut self, advertiser: &BleLinkId, scanner: &BleLinkId) {
+        let Some(rssi) = self.rssi(advertiser, scanner) else {
+            return;
+        };
+        let Some(mut advertisement) = self
+            .nodes
+            .get(advertiser)
+            .and_then(|node| node.advertisement.clone())
+        else {
+            return;
+        };
+        advertisement.rssi = Some(rssi);
+
+        if let Some(node) = self.nodes.get_mut(scanner).filter(|node| node.scanning) {
+            node.emit(BleCentralEvent::Discovered {
+                id: advertiser.clone(),
+                advertisement,
+            });
+        }
+    }
+
+    /// Close every link between two nodes and tell the centrals
+    fn drop_links(&mut self, a: &BleLinkId, b: &BleLinkId) {
+        for (central, peripheral) in [(a, b), (b, a)] {
+            if self
+                .links
+                .remove(&(central.clone(), peripheral.clone()))
+                .is_some()
+            {
+                if let Some(node) = self.nodes.get_mut(central) {
+                    node.emit(BleCentralEvent::Disconnected {
+                        id: peripheral.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl NodeState {
+    fn emit(&mut self, event: BleCentralEvent) {
+        self.central_events
+            .retain(|sender| sender.send(event.clone()).is_ok());
+    }
+}
+
+fn pair(a: &BleLinkId, b: &BleLinkId) -> (BleLinkId, BleLinkId) {
+    if a.as_str() <= b.as_str() {
+        (a.clone(), b.clone())
+    } else {
+        (b.clone(), a.clone())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Nodes
+// ----------------------------------------------------------------------------
+
+/// One device on a simulated radio
+#[derive(Debug, Clone)]
+pub struct SimulatedNode {
+    radio: SimulatedRadio,
+    address: BleLinkId,
+}
+
+impl SimulatedNode {
+    /// Radio address other nodes see this node at
+    pub fn address(&self) -> &BleLinkId {
+        &self.address
+    }
+
+    /// Central role of this node
+    pub fn central(&self) -> SimulatedCentral {
+        SimulatedCentral { node: self.clone() }
+    }
+
+    /// Advertiser and GATT server of this node
+    pub fn advertiser(&self) -> SimulatedAdvertiser {
+        SimulatedAdvertiser::new(self.clone())
+    }
+
+    /// Start or stop advertising, reporting a new advertisement to scanners in range
+    pub(crate) fn set_advertisement(&self, advertisement: Option<BleAdvertisement>) {
+        let mut state = self.radio.lock();
+        let advertising = advertisement.is_some();
+        if let Some(node) = state.nodes.get_mut(&self.address) {
+            node.advertisement = advertisement;
+        }
+        if advertising {
+            let scanners: Vec<BleLinkId> = state.nodes.keys().cloned().collect();
+            for scanner in scanners.iter().filter(|scanner| **scanner != self.address) {
+                state.advertise_to(&self.address, scanner);
+            }
+        }
+    }
+
+    /// Deliver writes from remote centrals to `sender`
+    pub(crate) fn set_gatt_writes(&self, sender: GattWriteSender) {
+        self.gatt_server().attach_write_sender(sender);
+    }
+
+    /// GATT server remote centrals write to and subscribe to
+    pub(crate) fn gatt_server(&self) -> GattServer {
+        self.radio
+            .lock()
+            .node(&self.address)
+            .map(|node| node.gatt_server.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Central role of a simulated node
+pub struct SimulatedCentral {
+    node: SimulatedNode,
+}
+
+#[async_trait::async_trait]
+impl BleCentral for SimulatedCentral {
+    type Link = SimulatedLink;
+
+    async fn events(&self) -> BitchatResult<BoxStream<'static, BleCentralEvent>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut state = self.node.radio.lock();
+        state
+            .nodes
+            .get_mut(&self.node.address)
+            .ok_or(BleTransportError::AdapterNotAvailable)?
+            .central_events
+            .push(sender);
+        Ok(receiver_stream(receiver))
+    }
+
+    async fn start_scan(&self) -> BitchatResult<()> {
+        let mut state = self.node.radio.lock();
+        let address = &self.node.address;
+        state
+            .nodes
+            .get_mut(address)
+            .ok_or(BleTransportError::AdapterNotAvailable)?
+            .scanning = true;
+
+        let advertisers: Vec<BleLinkId> = state.nodes.keys().cloned().collect();
+        for advertiser in advertisers
+            .iter()
+            .filter(|advertiser| *advertiser != address)
+        {
+            state.advertise_to(advertiser, address);
+        }
+        Ok(())
+    }
+
+    async fn stop_scan(&self) -> BitchatResult<()> {
+        if let Some(node) = self.node.radio.lock().nodes.get_mut(&self.node.address) {
+            node.scanning = false;
+        }
+        Ok(())
+    }
+
+    async fn link(&self, id: &BleLinkId) -> BitchatResult<SimulatedLink> {
+        self.node.radio.lock().node(id)?;
+        Ok(SimulatedLink {
+            radio: self.node.radio.clone(),
+            central: self.node.address.clone(),
+            peripheral: id.clone(),
+        })
+    }
+}
+
+/// Link from a simulated central to a simulated peripheral
+#[derive(Debug, Clone)]
+pub struct SimulatedLink {
+    radio: SimulatedRadio,
+    central: BleLinkId,
+    peripheral: BleLinkId,
+}
+
+impl SimulatedLink {
+    fn key(&self) -> (BleLinkId, BleLinkId) {
+        (self.central.clone(), self.peripheral.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl BleLink for SimulatedLink {
+    fn id(&self) -> BleLinkId {
+        self.peripheral.clone()
+    }
+
+    fn mtu(&self) -> usize {
+        let state = self.radio.lock();
+        [&self.central, &self.peripheral]
+            .into_iter()
+            .filter_map(|address| state.node(address).ok())
+            .map(|node| node.config.mtu)
+            .min()
+            .unwrap_or(DEFAULT_LINK_MTU)
+    }
+
+    async fn connect(&self) -> BitchatResult<()> {
+        let mut state = self.radio.lock();
+        if state.links.contains_key(&self.key()) {
+            return Ok(());
+        }
+
+        let failed = |reason: &str| {
+            BleTransportError::ConnectionFailed(format!("{}: {}", self.peripheral, reason))
+        };
+        if state.rssi(&self.central, &self.peripheral).is_none() {
+            return Err(failed("out of range").into());
+        }
+        if state.node(&self.peripheral)?.advertisement.is_none() {
+            return Err(failed("not connectable").into());
+        }
+        for address in [&self.central, &self.peripheral] {
+            if state.connection_count(address) >= state.node(address)?.config.max_connections {
+                return Err(failed("connection limit reached").into());
+            }
+        }
+
+        state.links.insert(self.key(), None);
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> BitchatResult<()> {
+        self.radio.lock().links.remove(&self.key());
+        Ok(())
+    }
+
+    async fn write(&self, data: &[u8]) -> BitchatResult<()> {
+        let mtu = self.mtu();
+        let state = self.radio.lock();
+        if !state.links.contains_key(&self.key()) {
+            return Err(BleTransportError::PeerNotConnected.into());
+        }
+        if data.len() > mtu {
+            return Err(BleTransportError::PacketTooLarge {
+                size: data.len(),
+                max_size: mtu,
+            }
+            .into());
+        }
+
+        let server = state.node(&self.peripheral)?.gatt_server.clone();
+        drop(state);
+        server.handle_write(self.central.clone(), data.to_vec())
+    }
+
+    async fn notifications(&self) -> BitchatResult<BoxStream<'static, Vec<u8>>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut state = self.radio.lock();
+        let notifications = state
+            .links
+            .get_mut(&self.key())
+            .ok_or(BleTransportError::PeerNotConnected)?;
+        *notifications = Some(sender);
+
+        // Open the peripheral's notification session for its first subscriber
+        let server = state.node(&self.peripheral)?.gatt_server.clone();
+        if !server.is_notifying() {
+            server.start_notify(SimulatedNotifier {
+                radio: self.radio.clone(),
+                peripheral: self.peripheral.clone(),
+            });
+        }
+        Ok(receiver_stream(receiver))
+    }
+}
+
+/// Notification session of a simulated peripheral, shared by its subscribers
+struct SimulatedNotifier {
+    radio: SimulatedRadio,
+    peripheral: BleLinkId,
+}
+
+impl SimulatedNotifier {
+    fn subscribers(
+        state: &RadioState,
+        peripheral: &BleLinkId,
+    ) -> Vec<mpsc::UnboundedSender<Vec<u8>>> {
+        state
+            .links
+            .iter()
+            .filter(|((_, linked), _)| linked == peripheral)
+            .filter_map(|(_, notifications)| notifications.clone())
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl GattNotifier for SimulatedNotifier {
+    async fn notify(&mut self, value: Vec<u8>) -> BitchatResult<()> {
+        let state = self.radio.lock();
+        for subscriber in Self::subscribers(&state, &self.peripheral) {
+            let _ = subscriber.send(value.clone());
+        }
+        Ok(())
+    }
+
+    fn is_stopped(&self) -> bool {
+        Self::subscribers(&self.radio.lock(), &self.peripheral).is_empty()
+    }
+}
+
+/// Turn a channel into a stream that ends when every sender is dropped
+fn receiver_stream<T: Send + 'static>(
+    receiver: mpsc::UnboundedReceiver<T>,
+) -> BoxStream<'static, T> {
+    stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advertise(node: &SimulatedNode, name: &str) {
+        node.set_advertisement(Some(BleAdvertisement {
+            local_name: Some(name.to_string()),
+            ..Default::default()
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_scanning_reports_advertisers_in_range() {
+        let radio = SimulatedRadio::new();
+        let (a, b, c) = (
+            radio.add_node(SimulatedNodeConfig::default()),
+            radio.add_node(SimulatedNodeConfig::default()),
+            radio.add_node(SimulatedNodeConfig::default()),
+        );
+        radio.set_rssi(&a, &b, Some(-40));
+        advertise(&b, "b");
+        advertise(&c, "c");
+
+        let central = a.central();
+        let mut events = central.events().await.unwrap();
+        central.start_scan().await.unwrap();
+        match events.next().await {
+            Some(BleCentralEvent::Discovered { id, advertisement }) => {
+                assert_eq!(&id, b.address());
+                assert_eq!(advertisement.rssi, Some(-40));
+            }
+            other => panic!("Expected b to be discovered, got {:?}", other),
+        }
+
+        // C comes into range later
+        radio.set_rssi(&a, &c, Some(-80));
+        assert!(matches!(
+            events.next().await,
+            Some(BleCentralEvent::Discovered { id, .. }) if &id == c.address()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_links_carry_writes_within_mtu() {
+        let radio = SimulatedRadio::new();
+        let a = radio.add_node(SimulatedNodeConfig::default());
+        let b = radio.add_node(SimulatedNodeConfig {
+            mtu: 20,
+            ..Default::default()
+        });
+        let (sender, mut writes) = mpsc::unbounded_channel();
+        b.set_gatt_writes(sender);
+        advertise(&b, "b");
+
+        let link = a.central().link(b.address()).await.unwrap();
+        assert!(link.connect().await.is_err(), "b is out of range");
+        radio.set_rssi(&a, &b, Some(-50));
+        link.connect().await.unwrap();
+        assert_eq!(link.mtu(), 20);
+
+        link.write(b"hello").await.unwrap();
+        assert_eq!(
+            writes.recv().await,
+            Some((a.address().clone(), b"hello".to_vec()))
+        );
+        assert!(link.write(&[0u8; 21]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connection_limit_and_drops() {
+        let radio = SimulatedRadio::new();
+        let a = radio.add_node(SimulatedNodeConfig {
+            max_connections: 1,
+            ..Default::default()
+        });
+        let (b, c) = (
+            radio.add_node(SimulatedNodeConfig::default()),
+            radio.add_node(SimulatedNodeConfig::default()),
+        );
+        for node in [&b, &c] {
+            radio.set_rssi(&a, node, Some(-60));
+            advertise(node, "peer");
+        }
+
+        let central = a.central();
+        let mut events = central.events().await.unwrap();
+        let to_b = central.link(b.address()).await.unwrap();
+        let to_c = central.link(c.address()).await.unwrap();
+        to_b.connect().await.unwrap();
+        assert!(to_c.connect().await.is_err());
+        assert_eq!(radio.connection_count(&a), 1);
+
+        // Moving b away drops the link and frees the slot
+        radio.set_rssi(&a, &b, None);
+        assert!(matches!(
+            events.next().await,
+            Some(BleCentralEvent::Disconnected { id }) if &id == b.address()
+        ));
+        assert!(to_b.write(b"gone").await.is_err());
+        to_c.connect().await.unwrap();
+    }
+}
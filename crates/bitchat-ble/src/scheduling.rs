@@ -0,0 +1,242 @@
+//! Scan duty cycling and the central connection budget
+//!
+//! Scanning continuously drains the battery, so scans run in windows of
+//! `duty_on_duration` separated by `duty_off_duration`. Connections are bounded
+//! the same way: at most `max_central_links` links as central, filled from the
+//! strongest discovered peers above the RSSI threshold, no faster than one
+//! attempt per `connect_rate_limit_interval`.
+
+use std::collections::HashMap;
+
+use bitchat_core::PeerId;
+use tokio::time::Instant;
+
+use crate::config::BleTransportConfig;
+
+// ----------------------------------------------------------------------------
+// Scan Duty Cycle
+// ----------------------------------------------------------------------------
+
+/// Alternates scan windows and pauses while discovery is enabled
+#[derive(Debug)]
+pub struct ScanDutyCycle {
+    config: BleTransportConfig,
+    /// Whether the scan window is open, while discovery is enabled
+    phase: Option<bool>,
+    /// When the current window or pause ends
+    next_toggle: Instant,
+}
+
+impl ScanDutyCycle {
+    /// Create a duty cycle with discovery disabled
+    pub fn new(config: BleTransportConfig) -> Self {
+        Self {
+            config,
+            phase: None,
+            next_toggle: Instant::now(),
+        }
+    }
+
+    /// Enable discovery, opening a scan window now
+    pub fn start(&mut self, now: Instant) {
+        self.phase = Some(true);
+        self.next_toggle = now + self.config.duty_on_duration;
+    }
+
+    /// Disable discovery
+    pub fn stop(&mut self) {
+        self.phase = None;
+    }
+
+    /// When the scan state next changes, if discovery is enabled
+    pub fn next_toggle(&self) -> Option<Instant> {
+        self.phase.map(|_| self.next_toggle)
+    }
+
+    /// Move to the next window or pause, returning whether to scan
+    pub fn toggle(&mut self, now: Instant) -> bool {
+        let scanning = !self.phase.unwrap_or(false);
+        self.phase = Some(scanning);
+        self.next_toggle = now
+            + if scanning {
+                self.config.duty_on_duration
+            } else {
+                self.config.duty_off_duration
+            };
+        scanning
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Connection Budget
+// ----------------------------------------------------------------------------
+
+/// Discovered peers waiting for a central link, ranked by signal strength
+#[derive(Debug)]
+pub struct ConnectionBudget {
+    config: BleTransportConfig,
+    /// Latest RSSI of each candidate
+    candidates: HashMap<PeerId, Option<i16>>,
+    /// When we last attempted a connection
+    last_attempt: Option<Instant>,
+}
+
+impl ConnectionBudget {
+    /// Create an empty budget
+    pub fn new(config: BleTransportConfig) -> Self {
+        Self {
+            config,
+            candidates: HashMap::new(),
+            last_attempt: None,
+        }
+    }
+
+    /// Offer a discovered peer for connection
+    ///
+    /// Peers below the RSSI threshold are turned away. Peers whose signal is
+    /// unknown are accepted but ranked below every measured one. When the list
+    /// is full, the weakest candidate makes room for a stronger one.
+    pub fn offer(&mut self, peer_id: PeerId, rssi: Option<i16>) -> bool {
+        if rssi.is_some_and(|rssi| i32::from(rssi) < self.config.dynamic_rssi_threshold) {
+            self.candidates.remove(&peer_id);
+            return false;
+        }
+
+        if !self.candidates.contains_key(&peer_id)
+            && self.candidates.len() >= self.config.connection_candidates_max
+        {
+            match self.weakest() {
+                Some((weakest, weakest_rssi)) if weakest_rssi < rssi => {
+                    self.candidates.remove(&weakest);
+                }
+                _ => return false,
+            }
+        }
+        self.candidates.insert(peer_id, rssi);
+        true
+    }
+
+    /// Forget a candidate, e.g. because it connected another way
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        self.candidates.remove(peer_id);
+    }
+
+    /// Number of peers waiting for a slot
+    pub fn candidate_count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// When the next candidate may be connected to, given the links in use
+    ///
+    /// Returns `None` when there is nobody to connect to or no free slot.
+    pub fn next_attempt(&self, links_in_use: usize) -> Option<Instant> {
+        self.has_slot(links_in_use)
+            .then(|| self.rate_limited_until().unwrap_or_else(Instant::now))
+    }
+
+    /// Take the strongest candidate if a slot is free and the rate limit allows
+    pub fn next_candidate(&mut self, links_in_use: usize, now: Instant) -> Option<PeerId> {
+        if !self.has_slot(links_in_use) || self.rate_limited_until().is_some_and(|t| t > now) {
+            return None;
+        }
+        let peer_id = self
+            .candidates
+            .iter()
+            .max_by_key(|(_, rssi)| **rssi)
+            .map(|(peer_id, _)| *peer_id)?;
+        self.candidates.remove(&peer_id);
+        self.last_attempt = Some(now);
+        Some(peer_id)
+    }
+
+    fn has_slot(&self, links_in_use: usize) -> bool {
+        !self.candidates.is_empty() && links_in_use < self.config.max_central_links
+    }
+
+    fn rate_limited_until(&self) -> Option<Instant> {
+        self.last_attempt
+            .map(|last| last + self.config.connect_rate_limit_interval)
+    }
+
+    fn weakest(&self) -> Option<(PeerId, Option<i16>)> {
+        self.candidates
+            .iter()
+            .min_by_key(|(_, rssi)| **rssi)
+            .map(|(peer_id, rssi)| (*peer_id, *rssi))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn peer(id: u8) -> PeerId {
+        PeerId::new([id, 0, 0, 0, 0, 0, 0, 0])
+    }
+
+    fn config() -> BleTransportConfig {
+        BleTransportConfig::default()
+            .with_duty_cycle(Duration::from_secs(5), Duration::from_secs(10))
+            .with_max_central_links(2)
+            .with_rssi_threshold(-90)
+            .with_connect_rate_limit(Duration::from_millis(500))
+    }
+
+    #[test]
+    fn test_duty_cycle_alternates_windows() {
+        let mut duty = ScanDutyCycle::new(config());
+        assert_eq!(duty.next_toggle(), None);
+
+        let now = Instant::now();
+        duty.start(now);
+        assert_eq!(duty.next_toggle(), Some(now + Duration::from_secs(5)));
+
+        let later = now + Duration::from_secs(5);
+        assert!(!duty.toggle(later));
+        assert_eq!(duty.next_toggle(), Some(later + Duration::from_secs(10)));
+        assert!(duty.toggle(later + Duration::from_secs(10)));
+
+        duty.stop();
+        assert_eq!(duty.next_toggle(), None);
+    }
+
+    #[test]
+    fn test_budget_prefers_strong_peers_within_limits() {
+        let mut budget = ConnectionBudget::new(config());
+        assert!(!budget.offer(peer(1), Some(-95)));
+        assert!(budget.offer(peer(2), Some(-70)));
+        assert!(budget.offer(peer(3), Some(-40)));
+        assert!(budget.offer(peer(4), None));
+
+        let now = Instant::now();
+        assert_eq!(budget.next_candidate(0, now), Some(peer(3)));
+        // Rate limited until the interval passes
+        assert_eq!(budget.next_candidate(1, now), None);
+        let later = now + Duration::from_millis(500);
+        assert_eq!(budget.next_candidate(1, later), Some(peer(2)));
+
+        // No free slot, however long we wait
+        assert_eq!(budget.next_attempt(2), None);
+        assert_eq!(
+            budget.next_candidate(2, later + Duration::from_secs(5)),
+            None
+        );
+        assert_eq!(budget.candidate_count(), 1);
+    }
+
+    #[test]
+    fn test_full_candidate_list_keeps_strongest() {
+        let mut config = config();
+        config.connection_candidates_max = 2;
+        let mut budget = ConnectionBudget::new(config);
+        assert!(budget.offer(peer(1), Some(-80)));
+        assert!(budget.offer(peer(2), Some(-60)));
+
+        assert!(!budget.offer(peer(3), Some(-85)));
+        assert!(budget.offer(peer(4), Some(-50)));
+        assert_eq!(budget.candidate_count(), 2);
+        assert_eq!(budget.next_candidate(0, Instant::now()), Some(peer(4)));
+    }
+}
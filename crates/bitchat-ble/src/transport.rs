@@ -5,10 +5,16 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
 use smallvec::SmallVec;
 
-use bitchat_core::internal::{IdentityKeyPair, TransportError};
-use bitchat_core::protocol::{BitchatPacket, DiscoveredPeer, MessageType, WireFormat};
+use bitchat_core::internal::{generate_fingerprint, IdentityKeyPair, TransportError};
+use bitchat_core::protocol::packet::PROTOCOL_VERSION_2;
+use bitchat_core::protocol::{
+    BitchatPacket, DeduplicationManager, DiscoveredPeer, Fragment, MessageFragmenter,
+    MessageReassembler, MessageType, PacketFlags, PacketId, WireFormat,
+};
+use bitchat_core::types::Ttl;
 use bitchat_core::{BitchatError, BitchatResult, PeerId, Timestamp, TransportTask};
 use bitchat_core::{EffectReceiver, EventSender};
 use bitchat_harness::{
@@ -17,18 +23,26 @@ use bitchat_harness::{
 };
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
 
+use crate::advertising::{AdvertisingManager, GattServer, PlatformAdvertiser};
 use crate::config::BleTransportConfig;
 use crate::connection::BleConnection;
 use crate::discovery::BleDiscovery;
-use crate::peer::BlePeer;
+use crate::link::platform::PlatformCentral;
+use crate::link::{BleCentral, BleCentralEvent, BleLinkId, DEFAULT_LINK_MTU};
+use crate::peer::SharedPeers;
+use crate::scheduling::{ConnectionBudget, ScanDutyCycle};
 
 // ----------------------------------------------------------------------------
 // BLE Transport Task Implementation
 // ----------------------------------------------------------------------------
 
 /// BLE transport task that implements the new transport architecture
-pub struct BleTransportTask {
+///
+/// Generic over the [`BleCentral`] it reaches the radio through, so the same task
+/// runs over btleplug or over a [`SimulatedRadio`](crate::SimulatedRadio).
+pub struct BleTransportTask<C: BleCentral = PlatformCentral> {
     /// Transport type
     transport_type: ChannelTransportType,
     /// Channels provided by the runtime harness
@@ -38,15 +52,19 @@ pub struct BleTransportTask {
     /// Transport configuration
     config: BleTransportConfig,
     /// Discovery manager
-    discovery: BleDiscovery,
+    discovery: BleDiscovery<C>,
     /// Connection manager
     connection: BleConnection,
     /// Discovered peers
-    peers: Arc<RwLock<HashMap<PeerId, BlePeer>>>,
-    /// Our own peer ID for identification
+    peers: SharedPeers<C::Link>,
+    /// Peer ID we currently advertise
     local_peer_id: PeerId,
-    /// Identity keypair for advertising
+    /// Identity keypair for announces, and for advertising without rotation
     identity: IdentityKeyPair,
+    /// Throwaway keypair signing the current ephemeral advertisement
+    ephemeral_identity: Option<IdentityKeyPair>,
+    /// Our stable peer ID, learned from the announces Core Logic sends
+    local_stable_id: Option<PeerId>,
     /// Background task handles
     #[allow(dead_code)]
     task_handles: Vec<JoinHandle<()>>,
@@ -54,6 +72,20 @@ pub struct BleTransportTask {
     cached_peers: Arc<RwLock<Vec<PeerId>>>,
     /// Packet receiver for incoming data from BLE connections
     packet_rx: Option<mpsc::UnboundedReceiver<(PeerId, Vec<u8>)>>,
+    /// Writes remote centrals made to our GATT server, by their link ID
+    gatt_rx: Option<mpsc::UnboundedReceiver<(BleLinkId, Vec<u8>)>>,
+    /// GATT server notifying the centrals subscribed to us
+    gatt_server: Option<GattServer>,
+    /// Peers linked to us only as centrals, by when they last wrote to us
+    gatt_peers: HashMap<PeerId, Instant>,
+    /// Scan windows while discovery is enabled
+    duty_cycle: ScanDutyCycle,
+    /// Discovered peers waiting for a central link
+    budget: ConnectionBudget,
+    /// Packets already seen, so flooded packets are relayed only once
+    deduplication: DeduplicationManager,
+    /// Fragments of packets larger than `max_packet_size`, by sender
+    reassembler: MessageReassembler,
 }
 
 impl Default for BleTransportTask {
@@ -63,32 +95,128 @@ impl Default for BleTransportTask {
 }
 
 impl BleTransportTask {
-    /// Create a new BLE transport task
+    /// Create a new BLE transport task on the platform Bluetooth stack
     pub fn new() -> Self {
-        let local_peer_id = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]); // Default for now
-        let config = BleTransportConfig::default();
+        Self::with_central(
+            PlatformCentral::new(),
+            PlatformAdvertiser::new(),
+            BleTransportConfig::default(),
+        )
+    }
+}
+
+impl<C: BleCentral> BleTransportTask<C> {
+    /// Create a BLE transport task over the given central and advertiser
+    pub fn with_central(
+        central: C,
+        advertiser: PlatformAdvertiser,
+        config: BleTransportConfig,
+    ) -> Self {
         let (packet_tx, packet_rx) = mpsc::unbounded_channel();
+        let (gatt_tx, gatt_rx) = mpsc::unbounded_channel();
 
-        let discovery = BleDiscovery::new(config.clone());
+        let mut discovery = BleDiscovery::new(
+            config.clone(),
+            central,
+            AdvertisingManager::with_advertiser(advertiser),
+        );
+        discovery.attach_write_sender(gatt_tx);
+        let gatt_server = discovery.gatt_server();
         let connection = BleConnection::new(config.clone(), packet_tx);
         let identity = IdentityKeyPair::generate().unwrap_or_else(|_| {
             // Fallback to a dummy identity if generation fails
             IdentityKeyPair::generate().unwrap() // This would normally not fail twice
         });
+        // Advertising the identity's own fingerprint would defeat rotation, so there
+        // is no falling back to it
+        let ephemeral_identity = config.peer_id_rotation_interval.map(|_| {
+            IdentityKeyPair::generate().expect("Failed to generate an ephemeral BLE identity")
+        });
+        let advertising_identity = ephemeral_identity.as_ref().unwrap_or(&identity);
+        let local_peer_id =
+            generate_fingerprint(advertising_identity.public_key_bytes()).to_peer_id();
 
         Self {
             transport_type: ChannelTransportType::Ble,
             transport_channels: None,
             running: false,
-            config,
             discovery,
             connection,
             peers: Arc::new(RwLock::new(HashMap::new())),
             local_peer_id,
             identity,
+            ephemeral_identity,
+            local_stable_id: None,
             task_handles: Vec::new(),
             cached_peers: Arc::new(RwLock::new(Vec::new())),
             packet_rx: Some(packet_rx),
+            gatt_rx: Some(gatt_rx),
+            gatt_server,
+            gatt_peers: HashMap::new(),
+            duty_cycle: ScanDutyCycle::new(config.clone()),
+            budget: ConnectionBudget::new(config.clone()),
+            deduplication: DeduplicationManager::for_ble_mesh(),
+            reassembler: MessageReassembler::new(),
+            config,
+        }
+    }
+
+    /// Sign announces with this identity
+    ///
+    /// Without peer ID rotation the identity also signs the advertisement, and the
+    /// local peer ID is its fingerprint, which is what remote scanners verify.
+    pub fn with_identity(mut self, identity: IdentityKeyPair) -> Self {
+        self.identity = identity;
+        self.update_advertised_peer_id();
+        self
+    }
+
+    /// The peer ID we currently advertise
+    pub fn local_peer_id(&self) -> PeerId {
+        self.local_peer_id
+    }
+
+    /// Advertise under the fingerprint of the keypair signing the advertisement
+    fn update_advertised_peer_id(&mut self) {
+        self.local_peer_id =
+            generate_fingerprint(self.advertising_identity().public_key_bytes()).to_peer_id();
+    }
+
+    /// Move to a fresh throwaway keypair and advertise under its fingerprint
+    ///
+    /// Consecutive IDs cannot be linked to each other or to the identity. Peers
+    /// learn who is behind the ID from the Noise handshakes we complete with them.
+    fn new_ephemeral_identity(&mut self) -> BitchatResult<()> {
+        self.ephemeral_identity = Some(IdentityKeyPair::generate()?);
+        self.update_advertised_peer_id();
+        Ok(())
+    }
+
+    /// Keypair signing the advertisement
+    fn advertising_identity(&self) -> &IdentityKeyPair {
+        self.ephemeral_identity.as_ref().unwrap_or(&self.identity)
+    }
+
+    /// Advertise under a fresh ephemeral peer ID
+    ///
+    /// Links stay up, and peers that completed a handshake with us keep the
+    /// binding to our stable identity, so sessions carry on under the new ID.
+    ///
+    /// If no new keypair can be generated the current ephemeral ID is advertised
+    /// for another interval, never the identity's own fingerprint.
+    async fn rotate_peer_id(&mut self) {
+        let previous = self.local_peer_id;
+        if let Err(e) = self.new_ephemeral_identity() {
+            tracing::warn!("Keeping BLE peer ID {}: {}", previous, e);
+        }
+        let peer_id = self.local_peer_id;
+        let identity = self.advertising_identity().clone();
+        match self.discovery.rotate_advertising(peer_id, &identity).await {
+            Ok(()) if peer_id != previous => {
+                tracing::info!("Rotated BLE peer ID {} to {}", previous, peer_id)
+            }
+            Ok(()) => {}
+            Err(e) => tracing::warn!("Failed to rotate BLE peer ID: {}", e),
         }
     }
 
@@ -112,10 +240,26 @@ impl BleTransportTask {
                 reason: "BLE packet receiver already taken".to_string(),
             })
         })?;
+        let mut gatt_writes = self.gatt_rx.take().ok_or_else(|| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: "BLE GATT write receiver already taken".to_string(),
+            })
+        })?;
+        let mut central_events = match self.discovery.events().await {
+            Ok(events) => events,
+            Err(e) => {
+                tracing::warn!("BLE central events unavailable: {}", e);
+                stream::pending().boxed()
+            }
+        };
 
         self.running = true;
 
         while self.running {
+            let next_scan_toggle = self.duty_cycle.next_toggle();
+            let next_connect = self.budget.next_attempt(self.central_links_in_use().await);
+            let next_rotation = self.discovery.next_rotation();
+
             tokio::select! {
                 // Process effects from Core Logic
                 effect = effect_receiver.recv() => {
@@ -146,9 +290,32 @@ impl BleTransportTask {
                     }
                 }
 
-                // Periodic discovery scanning
-                _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {
-                    self.perform_discovery_scan().await;
+                // Discoveries and dropped links from the central
+                Some(event) = central_events.next() => {
+                    self.handle_central_event(event).await;
+                }
+
+                // Writes remote centrals made to our GATT server
+                Some((link_id, data)) = gatt_writes.recv() => {
+                    let peer_id = self.peer_for_link(&link_id).await;
+                    if let Err(e) = self.handle_incoming_write(peer_id, data).await {
+                        tracing::error!("Failed to handle GATT write from {}: {}", link_id, e);
+                    }
+                }
+
+                // Open or close the scan window
+                _ = sleep_until(next_scan_toggle) => {
+                    self.toggle_scan_window().await;
+                }
+
+                // Advertise under a fresh peer ID
+                _ = sleep_until(next_rotation) => {
+                    self.rotate_peer_id().await;
+                }
+
+                // Fill a free central link slot
+                _ = sleep_until(next_connect) => {
+                    self.connect_next_candidate().await;
                 }
 
                 // Periodic maintenance
@@ -178,11 +345,13 @@ impl BleTransportTask {
                 packet,
                 transport,
             } if transport == self.transport_type => {
+                self.note_outgoing_packet(&packet);
                 self.send_bitchat_packet_to_peer(peer_id, packet).await?;
             }
             Effect::BroadcastBitchatPacket { packet, transport }
                 if transport == self.transport_type =>
             {
+                self.note_outgoing_packet(&packet);
                 self.broadcast_bitchat_packet(packet).await?;
             }
             Effect::InitiateConnection { peer_id, transport }
@@ -202,6 +371,13 @@ impl BleTransportTask {
             Effect::StopTransportDiscovery { transport } if transport == self.transport_type => {
                 self.stop_discovery().await?;
             }
+            Effect::BindLinkIdentity {
+                peer_id,
+                handshake,
+                transport,
+            } if transport == self.transport_type => {
+                self.bind_link_identity(peer_id, handshake).await?;
+            }
             _ => {
                 // Effect not for this transport - ignore
             }
@@ -209,9 +385,59 @@ impl BleTransportTask {
         Ok(())
     }
 
+    /// Learn our stable peer ID from the announces Core Logic sends, and remember
+    /// our packets so copies relayed back to us are not flooded again
+    fn note_outgoing_packet(&mut self, packet: &BitchatPacket) {
+        if packet.message_type() == MessageType::Announce {
+            self.local_stable_id = Some(packet.sender_id);
+        }
+        self.is_duplicate(packet);
+    }
+
+    /// Whether the packet was seen before, recording it if not
+    fn is_duplicate(&mut self, packet: &BitchatPacket) -> bool {
+        self.deduplication.check_and_add(packet_id(packet))
+    }
+
+    /// Find the peer holding the link to `peer_id`
+    ///
+    /// Accepts the advertised ID of a discovered peer, or the stable ID a Noise
+    /// handshake proved over a peer's link, preferring a connected link.
+    async fn link_peer_id(&self, peer_id: PeerId) -> Option<PeerId> {
+        let peers = self.peers.read().await;
+        if peers.contains_key(&peer_id) {
+            return Some(peer_id);
+        }
+        peers
+            .values()
+            .filter(|peer| peer.stable_id == Some(peer_id))
+            .max_by_key(|peer| peer.is_connected())
+            .map(|peer| peer.peer_id)
+    }
+
+    /// Find the peer holding a link `peer_id` sent us a handshake over
+    ///
+    /// Handshake replies go back that way, since completing the handshake is what
+    /// binds the link to the peer.
+    async fn handshake_link_peer_id(&self, peer_id: PeerId) -> Option<PeerId> {
+        self.peers
+            .read()
+            .await
+            .values()
+            .filter(|peer| matches!(peer.handshake, Some((sender, _)) if sender == peer_id))
+            .max_by_key(|peer| peer.is_connected())
+            .map(|peer| peer.peer_id)
+    }
+
     /// Send packet to specific peer via BLE
     async fn send_packet_to_peer(&mut self, peer_id: PeerId, data: Vec<u8>) -> BitchatResult<()> {
-        // Check if peer exists and is connected
+        let peer_id = self.link_peer_id(peer_id).await.ok_or_else(|| {
+            BitchatError::Transport(TransportError::PeerNotFound {
+                peer_id: peer_id.to_string(),
+            })
+        })?;
+
+        // Check if peer is connected
         {
             let peers = self.peers.read().await;
             let peer = peers.get(&peer_id).ok_or_else(|| {
@@ -247,35 +473,37 @@ impl BleTransportTask {
         peer_id: PeerId,
         packet: BitchatPacket,
     ) -> BitchatResult<()> {
-        // Serialize the packet to binary wire format
-        let data = WireFormat::encode(&packet).map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to encode BitChat packet: {}", e),
-            })
-        })?;
+        // Peers that only connected to us as centrals are reached through notifications
+        if self.gatt_peers.contains_key(&peer_id) && !self.has_central_link(peer_id).await {
+            let frames = self.encode_frames(&packet, self.notification_frame_size())?;
+            if !self.notify_frames(&frames) {
+                return Err(BitchatError::Transport(TransportError::ConnectionFailed {
+                    peer_id: peer_id.to_string(),
+                    reason: "Peer not subscribed to notifications".to_string(),
+                }));
+            }
+            return Ok(());
+        }
 
-        // Use existing packet sending logic
-        self.send_packet_to_peer(peer_id, data).await
+        let link_peer = match self.link_peer_id(peer_id).await {
+            Some(link_peer) => Some(link_peer),
+            None if packet.message_type() == MessageType::NoiseHandshake => {
+                self.handshake_link_peer_id(peer_id).await
+            }
+            None => None,
+        }
+        .unwrap_or(peer_id);
+        let frame_size = self.frame_size(&[link_peer]).await;
+
+        // Use existing packet sending logic, one frame at a time
+        for frame in self.encode_frames(&packet, frame_size)? {
+            self.send_packet_to_peer(link_peer, frame).await?;
+        }
+        Ok(())
     }
 
     /// Broadcast BitChat packet to all connected peers
     async fn broadcast_bitchat_packet(&mut self, packet: BitchatPacket) -> BitchatResult<()> {
-        // Serialize the packet to binary wire format
-        let data = WireFormat::encode(&packet).map_err(|e| {
-            BitchatError::Transport(TransportError::InvalidConfiguration {
-                reason: format!("Failed to encode BitChat packet: {}", e),
-            })
-        })?;
-
-        // Check packet size
-        if data.len() > self.config.max_packet_size {
-            return Err(BitchatError::Transport(
-                TransportError::InvalidConfiguration {
-                    reason: "Packet too large for BLE".to_string(),
-                },
-            ));
-        }
-
         // Broadcast to all connected peers
         let peers = self.peers.read().await;
         let connected_peers: Vec<PeerId> = peers
@@ -285,48 +513,276 @@ impl BleTransportTask {
             .collect();
         drop(peers);
 
+        // One set of frames every link can carry, so relays see the same fragments
+        let mut frame_size = self.frame_size(&connected_peers).await;
+        if !self.gatt_peers.is_empty() {
+            frame_size = frame_size.min(self.notification_frame_size());
+        }
+        let frames = self.encode_frames(&packet, frame_size)?;
+
         for peer_id in connected_peers {
-            if let Err(e) = self
-                .connection
-                .send_to_peer(&peer_id, &data, &self.peers)
-                .await
-            {
-                tracing::warn!("Failed to broadcast to peer {}: {}", peer_id, e);
-                // Continue broadcasting to other peers instead of failing completely
+            for frame in &frames {
+                if let Err(e) = self
+                    .connection
+                    .send_to_peer(&peer_id, frame, &self.peers)
+                    .await
+                {
+                    tracing::warn!("Failed to broadcast to peer {}: {}", peer_id, e);
+                    // Continue broadcasting to other peers instead of failing completely
+                    break;
+                }
             }
         }
 
+        // One notification reaches every peer linked to us only as a central
+        if !self.gatt_peers.is_empty() && !self.notify_frames(&frames) {
+            tracing::debug!("No centrals subscribed to broadcast notifications");
+        }
+
         Ok(())
     }
 
+    /// Whether we hold a connected central link to `peer_id`
+    async fn has_central_link(&self, peer_id: PeerId) -> bool {
+        let Some(link_peer) = self.link_peer_id(peer_id).await else {
+            return false;
+        };
+        self.peers
+            .read()
+            .await
+            .get(&link_peer)
+            .is_some_and(|peer| peer.is_connected())
+    }
+
+    /// Largest frame that fits in a single GATT notification
+    fn notification_frame_size(&self) -> usize {
+        self.config.max_packet_size.min(DEFAULT_LINK_MTU)
+    }
+
+    /// Notify frames to the centrals subscribed to our GATT server
+    ///
+    /// Returns whether anyone was subscribed.
+    fn notify_frames(&self, frames: &[Vec<u8>]) -> bool {
+        let Some(server) = &self.gatt_server else {
+            return false;
+        };
+        frames.iter().all(|frame| server.notify(frame))
+    }
+
+    /// Largest frame that fits in a single write on each of the peers' links
+    ///
+    /// Receivers decode every write on its own, so a frame must not be split
+    /// across writes.
+    async fn frame_size(&self, peer_ids: &[PeerId]) -> usize {
+        let peers = self.peers.read().await;
+        peer_ids
+            .iter()
+            .filter_map(|peer_id| peers.get(peer_id))
+            .map(|peer| peer.link.mtu())
+            .fold(self.config.max_packet_size, usize::min)
+    }
+
+    /// Encode a packet into frames of at most `frame_size` bytes
+    ///
+    /// Larger packets, such as file chunks, are split into canonical fragment
+    /// packets carrying the encoded original, which the receiving transport
+    /// reassembles. Fragments keep the original's routing and TTL, so relays
+    /// forward them like any other packet.
+    fn encode_frames(
+        &self,
+        packet: &BitchatPacket,
+        frame_size: usize,
+    ) -> BitchatResult<Vec<Vec<u8>>> {
+        let data = encode_packet(packet)?;
+        if data.len() <= frame_size {
+            return Ok(vec![data]);
+        }
+
+        let mut template = BitchatPacket::new(
+            MessageType::Fragment,
+            packet.sender_id,
+            packet.recipient_id,
+            packet.header.timestamp,
+            Vec::new(),
+            PacketFlags::NONE,
+        )?
+        .with_version(PROTOCOL_VERSION_2);
+        template.header.ttl = packet.header.ttl;
+        let overhead = encode_packet(&template)?.len();
+        let fragment_size = frame_size.saturating_sub(overhead);
+        let fragments = MessageFragmenter::new(fragment_size)
+            .fragment_message(&data, packet.header.message_type.as_u8())?;
+
+        fragments
+            .into_iter()
+            .map(|fragment| {
+                let mut frame = template.clone();
+                frame.payload = fragment.to_wire_format();
+                frame.header.payload_length = frame.payload.len() as u32;
+                encode_packet(&frame)
+            })
+            .collect()
+    }
+
+    /// Add a fragment addressed to us, returning the packet it completes
+    fn reassemble(&mut self, fragment: &BitchatPacket) -> BitchatResult<Option<BitchatPacket>> {
+        let piece = Fragment::from_wire_format(&fragment.payload)?;
+        match self.reassembler.add_fragment(piece, fragment.sender_id)? {
+            Some((data, _)) => Ok(Some(WireFormat::decode(&data)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Initiate BLE connection to peer
     async fn initiate_connection(&mut self, peer_id: PeerId) -> BitchatResult<()> {
-        let peers = self.peers.read().await;
-        if peers.contains_key(&peer_id) {
-            drop(peers);
+        if !self.peers.read().await.contains_key(&peer_id) {
+            return Err(BitchatError::Transport(TransportError::PeerNotFound {
+                peer_id: peer_id.to_string(),
+            }));
+        }
+
+        self.budget.remove(&peer_id);
+        self.connection
+            .connect_to_peer(&peer_id, &self.peers)
+            .await?;
+        tracing::info!("Established BLE connection to peer {}", peer_id);
+
+        // Send connection established event to Core Logic
+        let event = Event::ConnectionEstablished {
+            peer_id,
+            transport: self.transport_type,
+        };
+        self.send_event(event).await
+    }
 
-            // Simulate BLE connection establishment
-            tracing::info!("Established BLE connection to peer {}", peer_id);
+    /// Handle a discovery or dropped link reported by the central
+    ///
+    /// Newly discovered peers are reported to Core Logic and offered to the
+    /// connection budget, which links to the strongest of them as slots free up.
+    async fn handle_central_event(&mut self, event: BleCentralEvent) {
+        let dropped = matches!(event, BleCentralEvent::Disconnected { .. });
+        let peer_id = match self
+            .discovery
+            .process_discovery_event(event, &self.peers, &self.cached_peers)
+            .await
+        {
+            Ok(Some(peer_id)) => peer_id,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Failed to process BLE discovery event: {}", e);
+                return;
+            }
+        };
 
-            // Send connection established event to Core Logic
-            let event = Event::ConnectionEstablished {
+        let event = if dropped {
+            Event::ConnectionLost {
                 peer_id,
                 transport: self.transport_type,
-            };
-            self.send_event(event).await?;
+                reason: "BLE link dropped".to_string(),
+            }
+        } else {
+            let rssi = self.peers.read().await.get(&peer_id).and_then(|p| p.rssi);
+            Event::PeerDiscovered {
+                peer_id,
+                transport: self.transport_type,
+                signal_strength: signal_strength(rssi),
+            }
+        };
+        if let Err(e) = self.send_event(event).await {
+            tracing::warn!("Failed to report BLE peer {}: {}", peer_id, e);
+        }
+
+        if !dropped {
+            let rssi = self.peers.read().await.get(&peer_id).and_then(|p| p.rssi);
+            if !self.budget.offer(peer_id, rssi) {
+                tracing::debug!("Not connecting to weak BLE peer {}", peer_id);
+            }
+        }
+    }
+
+    /// Number of links we hold or are opening as central
+    async fn central_links_in_use(&self) -> usize {
+        self.peers
+            .read()
+            .await
+            .values()
+            .filter(|peer| peer.is_connected() || peer.is_connecting())
+            .count()
+    }
+
+    /// Connect to the strongest waiting peer, if the budget allows it now
+    async fn connect_next_candidate(&mut self) {
+        let links_in_use = self.central_links_in_use().await;
+        let Some(peer_id) = self.budget.next_candidate(links_in_use, Instant::now()) else {
+            return;
+        };
+        tracing::debug!(
+            "Connecting to BLE peer {} ({} candidates waiting)",
+            peer_id,
+            self.budget.candidate_count()
+        );
+        if let Err(e) = self.initiate_connection(peer_id).await {
+            tracing::warn!("Failed to connect to discovered peer {}: {}", peer_id, e);
+        }
+    }
 
-            Ok(())
+    /// Open or close the scan window as the duty cycle says
+    async fn toggle_scan_window(&mut self) {
+        let result = if self.duty_cycle.toggle(Instant::now()) {
+            self.discovery.start_scanning().await
         } else {
-            Err(BitchatError::Transport(TransportError::PeerNotFound {
-                peer_id: peer_id.to_string(),
-            }))
+            self.discovery.stop_scanning().await
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to toggle BLE scan window: {}", e);
         }
     }
 
+    /// Find the peer behind a radio address, if we discovered it
+    async fn peer_for_link(&self, link_id: &BleLinkId) -> Option<PeerId> {
+        self.peers
+            .read()
+            .await
+            .values()
+            .find(|peer| peer.link_id() == *link_id)
+            .map(|peer| peer.peer_id)
+    }
+
+    /// Handle data a remote central wrote to our GATT server
+    ///
+    /// Writers we have not discovered ourselves are identified by the sender of
+    /// the packet they wrote. Writers we hold no central link to are remembered,
+    /// so packets for them go out as notifications.
+    async fn handle_incoming_write(
+        &mut self,
+        from_peer: Option<PeerId>,
+        data: Vec<u8>,
+    ) -> BitchatResult<()> {
+        let from_peer = match from_peer {
+            Some(peer_id) => peer_id,
+            None => match WireFormat::decode(&data) {
+                Ok(packet) => packet.sender_id,
+                Err(e) => {
+                    tracing::debug!("Failed to decode GATT write: {}", e);
+                    return Ok(());
+                }
+            },
+        };
+        if self.has_central_link(from_peer).await {
+            self.gatt_peers.remove(&from_peer);
+        } else {
+            self.gatt_peers.insert(from_peer, Instant::now());
+        }
+        self.handle_incoming_packet(from_peer, data).await
+    }
+
     /// Start BLE advertising
     async fn start_advertising(&mut self) -> BitchatResult<()> {
         self.discovery
-            .start_advertising(self.local_peer_id, &self.identity)
+            .start_advertising(
+                self.local_peer_id,
+                self.ephemeral_identity.as_ref().unwrap_or(&self.identity),
+            )
             .await?;
         tracing::info!("Started BLE advertising");
         Ok(())
@@ -342,27 +798,19 @@ impl BleTransportTask {
     /// Start BLE discovery
     async fn start_discovery(&mut self) -> BitchatResult<()> {
         self.discovery.start_scanning().await?;
+        self.duty_cycle.start(Instant::now());
         tracing::info!("Started BLE discovery");
         Ok(())
     }
 
     /// Stop BLE discovery
     async fn stop_discovery(&mut self) -> BitchatResult<()> {
+        self.duty_cycle.stop();
         self.discovery.stop_scanning().await?;
         tracing::info!("Stopped BLE discovery");
         Ok(())
     }
 
-    /// Perform discovery scan for BLE peers
-    async fn perform_discovery_scan(&mut self) {
-        // Mock discovery of a peer for demonstration
-        if self.peers.read().await.is_empty() {
-            let _mock_peer = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
-            // We would need a real peripheral for a proper implementation
-            // For now, this is just demonstrating the structure
-        }
-    }
-
     /// Send event to Core Logic
     async fn send_event(&self, event: Event) -> BitchatResult<()> {
         let sender = self
@@ -412,20 +860,47 @@ impl BleTransportTask {
             // Don't return here - still forward announce packets to core logic
         }
 
-        // Check if packet is for us
-        let is_for_us = packet.is_broadcast() || packet.recipient_id == Some(self.local_peer_id);
+        // Flooded packets reach us over every path, including our own packets
+        if self.is_duplicate(&packet) {
+            return Ok(());
+        }
 
-        if is_for_us {
-            // Send packet to Core Logic
-            let event = Event::BitchatPacketReceived {
-                from: packet.sender_id,
-                packet: packet.clone(),
-                transport: self.transport_type,
+        // Check if packet is for us
+        let is_for_us = packet.recipient_id == Some(self.local_peer_id)
+            || packet.recipient_id.is_some() && packet.recipient_id == self.local_stable_id;
+
+        if is_for_us || packet.is_broadcast() {
+            // Fragments are relayed as they are, but only whole packets reach Core Logic
+            let delivered = if packet.header.message_type == MessageType::Fragment {
+                match self.reassemble(&packet) {
+                    Ok(Some(original)) if !self.is_duplicate(&original) => Some(original),
+                    Ok(_) => None,
+                    Err(e) => {
+                        tracing::debug!("Dropping fragment from {}: {}", from_peer, e);
+                        None
+                    }
+                }
+            } else {
+                Some(packet.clone())
             };
-            self.send_event(event).await?;
+            if let Some(delivered) = delivered {
+                // Core Logic names this packet if it completes a handshake
+                if is_for_us
+                    && delivered.message_type() == MessageType::NoiseHandshake
+                    && delivered.header.ttl == Ttl::MAX
+                {
+                    self.note_incoming_handshake(from_peer, &delivered).await;
+                }
+                let event = Event::BitchatPacketReceived {
+                    from: delivered.sender_id,
+                    packet: delivered,
+                    transport: self.transport_type,
+                };
+                self.send_event(event).await?;
+            }
         }
 
-        // If not for us and TTL > 0, forward the packet (mesh routing)
+        // If not only for us and TTL > 0, forward the packet (mesh routing)
         if !is_for_us && packet.header.ttl.value() > 0 {
             self.forward_packet(packet, from_peer).await?;
         }
@@ -464,6 +939,14 @@ impl BleTransportTask {
             .collect();
         drop(peers);
 
+        // Peers linked to us only as centrals share one notification
+        if self.gatt_peers.keys().any(|peer_id| *peer_id != from_peer) {
+            let frames = self.encode_frames(&packet, self.notification_frame_size())?;
+            if !self.notify_frames(&frames) {
+                tracing::debug!("No centrals subscribed to forwarded notifications");
+            }
+        }
+
         if connected_peers.is_empty() {
             tracing::debug!("No peers to forward packet to");
             return Ok(());
@@ -493,6 +976,8 @@ impl BleTransportTask {
 
     /// Perform periodic maintenance
     async fn perform_maintenance(&mut self) {
+        self.reassembler.cleanup_expired();
+
         let _current_time = std::time::SystemTime::now();
         let timeout_threshold = Duration::from_secs(300); // 5 minutes
 
@@ -502,7 +987,7 @@ impl BleTransportTask {
             let peers = self.peers.read().await;
             for (peer_id, peer) in peers.iter() {
                 if let Some(last_attempt) = peer.last_connection_attempt {
-                    if last_attempt.elapsed() > timeout_threshold {
+                    if !peer.is_connected() && last_attempt.elapsed() > timeout_threshold {
                         stale_peers.push(*peer_id);
                     }
                 }
@@ -520,6 +1005,10 @@ impl BleTransportTask {
                 tracing::debug!("Removed stale BLE peer {}", peer_id);
             }
         }
+
+        // Forget centrals that stopped writing to us
+        self.gatt_peers
+            .retain(|_, last_write| last_write.elapsed() <= timeout_threshold);
     }
 
     /// Get discovered peers (non-blocking)
@@ -535,44 +1024,12 @@ impl BleTransportTask {
         self.running
     }
 
-    /// Send an announce packet to a specific peer
-    pub async fn send_announce_to_peer(
-        &mut self,
-        peer_id: PeerId,
-        nickname: String,
-    ) -> BitchatResult<()> {
-        let noise_public_key = [0u8; 32]; // TODO: Get from noise session when available
-
-        let announce_packet = BitchatPacket::create_announce(
-            self.local_peer_id,
-            nickname,
-            noise_public_key,
-            &self.identity,
-            None, // No direct neighbors for now
-            Timestamp::now(),
-        )?;
-
-        self.send_bitchat_packet_to_peer(peer_id, announce_packet)
-            .await
-    }
-
-    /// Broadcast an announce packet to all connected peers
-    pub async fn broadcast_announce(&mut self, nickname: String) -> BitchatResult<()> {
-        let noise_public_key = [0u8; 32]; // TODO: Get from noise session when available
-
-        let announce_packet = BitchatPacket::create_announce(
-            self.local_peer_id,
-            nickname,
-            noise_public_key,
-            &self.identity,
-            None, // No direct neighbors for now
-            Timestamp::now(),
-        )?;
-
-        self.broadcast_bitchat_packet(announce_packet).await
-    }
-
     /// Handle an incoming announce packet from a peer
+    ///
+    /// Announces don't bind links to identities: the TTL is outside the signature,
+    /// so any neighbour can replay another peer's announce as if it were its own.
+    /// A neighbour's own announce naming another identity than the one a handshake
+    /// proved over its link is refused.
     pub async fn handle_announce_packet(
         &mut self,
         peer_id: PeerId,
@@ -585,32 +1042,62 @@ impl BleTransportTask {
         // Parse and verify the announce packet
         let discovered_peer = DiscoveredPeer::from_announce_packet(&packet, Timestamp::now())?;
 
-        // Verify the sender ID matches the packet sender
-        if discovered_peer.peer_id != peer_id {
-            return Err(BitchatError::invalid_packet(
-                "Announce packet sender ID mismatch",
-            ));
+        // Only the neighbour's own announce arrives with its TTL unspent; relayed
+        // announces describe peers further away
+        if packet.header.ttl != Ttl::MAX {
+            return Ok(());
         }
 
-        // Update or add the peer to our discovered peers list
-        {
-            let mut peers = self.peers.write().await;
-            if let Some(ble_peer) = peers.get_mut(&peer_id) {
-                // Update existing peer with announce information
-                ble_peer.update_from_announce(&discovered_peer)?;
-            } else {
-                // Add new peer discovered via announce
-                let ble_peer = BlePeer::from_discovered_peer(discovered_peer)?;
-                peers.insert(peer_id, ble_peer);
+        let peers = self.peers.read().await;
+        match peers.get(&peer_id).and_then(|peer| peer.stable_id) {
+            Some(stable_id) if stable_id != discovered_peer.peer_id => {
+                Err(BitchatError::invalid_packet(
+                    "Announce does not match the identity bound to the link",
+                ))
             }
+            _ => Ok(()),
+        }
+    }
+
+    /// Remember a handshake packet a neighbour sent us directly over its link
+    async fn note_incoming_handshake(&mut self, from_peer: PeerId, packet: &BitchatPacket) {
+        if let Some(ble_peer) = self.peers.write().await.get_mut(&from_peer) {
+            ble_peer.handshake = Some((packet.sender_id, packet_id(packet)));
         }
+    }
+
+    /// Bind the link a completed Noise handshake arrived over to the peer it proved
+    ///
+    /// `handshake` is the packet that completed it. The link keeps the first
+    /// identity proven over it, and Core Logic learns of the neighbour only now.
+    async fn bind_link_identity(
+        &mut self,
+        stable_id: PeerId,
+        handshake: PacketId,
+    ) -> BitchatResult<()> {
+        let (link_peer, rssi) = {
+            let mut peers = self.peers.write().await;
+            // Handshakes over links we don't hold, like GATT-only ones, bind nothing
+            let Some(ble_peer) = peers
+                .values_mut()
+                .find(|peer| matches!(&peer.handshake, Some((_, id)) if *id == handshake))
+            else {
+                return Ok(());
+            };
+            ble_peer.handshake = None;
+            if !ble_peer.bind_identity(stable_id)? {
+                return Ok(());
+            }
+            (ble_peer.peer_id, ble_peer.rssi)
+        };
+        tracing::debug!("BLE peer {} proved stable ID {}", link_peer, stable_id);
 
         // Notify the runtime about the peer discovery
         if let Some(ref channels) = self.transport_channels {
             let event = Event::PeerDiscovered {
-                peer_id,
+                peer_id: stable_id,
                 transport: self.transport_type,
-                signal_strength: None, // BLE signal strength not available here
+                signal_strength: signal_strength(rssi),
             };
 
             if let Err(e) = channels.event_sender().send(event).await {
@@ -622,8 +1109,39 @@ impl BleTransportTask {
     }
 }
 
+/// Identify a packet for deduplication and handshake binding
+fn packet_id(packet: &BitchatPacket) -> PacketId {
+    PacketId::from_packet_data(
+        packet.sender_id,
+        packet.header.timestamp.as_millis(),
+        &packet.payload,
+    )
+}
+
+/// Serialize a packet to binary wire format
+fn encode_packet(packet: &BitchatPacket) -> BitchatResult<Vec<u8>> {
+    WireFormat::encode(packet).map_err(|e| {
+        BitchatError::Transport(TransportError::InvalidConfiguration {
+            reason: format!("Failed to encode BitChat packet: {}", e),
+        })
+    })
+}
+
+/// Signal strength as reported to Core Logic
+fn signal_strength(rssi: Option<i16>) -> Option<i8> {
+    rssi.map(|rssi| rssi.clamp(i8::MIN as i16, i8::MAX as i16) as i8)
+}
+
+/// Sleep until `deadline`, or forever if there is none
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
 #[async_trait]
-impl TransportTask for BleTransportTask {
+impl<C: BleCentral> TransportTask for BleTransportTask<C> {
     fn attach_channels(
         &mut self,
         event_sender: EventSender,
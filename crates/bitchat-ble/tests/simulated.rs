@@ -0,0 +1,543 @@
+//! BLE transport tests over the simulated radio
+//!
+//! Runs full transport tasks for three nodes on one [`SimulatedRadio`], with A and
+//! C out of each other's range, to check discovery, connection, delivery, mesh
+//! relaying and flooding through B, fragmentation of packets larger than a BLE
+//! frame, notifications to peers linked only as centrals, binding links to the
+//! identities handshakes prove over them and dropped links without any Bluetooth
+//! hardware.
+
+use std::time::Duration;
+
+use bitchat_ble::{
+    BleTransportConfig, BleTransportTask, PlatformAdvertiser, SimulatedNode, SimulatedNodeConfig,
+    SimulatedRadio,
+};
+use bitchat_core::internal::IdentityKeyPair;
+use bitchat_core::protocol::file_transfer::MAX_CHUNK_SIZE;
+use bitchat_core::protocol::packet::PROTOCOL_VERSION_2;
+use bitchat_core::protocol::{BitchatPacket, MessageType, PacketId};
+use bitchat_core::types::Ttl;
+use bitchat_core::{ChannelTransportType, Effect, Event, PeerId, Timestamp, TransportTask};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::timeout;
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+const TRANSPORT: ChannelTransportType = ChannelTransportType::Ble;
+
+/// A transport task running on one simulated node
+struct TestNode {
+    peer_id: PeerId,
+    events: mpsc::Receiver<Event>,
+    effects: broadcast::Sender<Effect>,
+}
+
+impl TestNode {
+    fn spawn(node: &SimulatedNode) -> Self {
+        Self::spawn_with_config(node, BleTransportConfig::default())
+    }
+
+    fn spawn_with_config(node: &SimulatedNode, config: BleTransportConfig) -> Self {
+        let (event_tx, events) = mpsc::channel(100);
+        let (effects, effect_rx) = broadcast::channel(100);
+
+        let mut transport = BleTransportTask::with_central(
+            node.central(),
+            PlatformAdvertiser::Simulated(node.advertiser()),
+            config,
+        )
+        .with_identity(IdentityKeyPair::generate().unwrap());
+        transport.attach_channels(event_tx, effect_rx).unwrap();
+        let peer_id = transport.local_peer_id();
+        tokio::spawn(async move { transport.run().await });
+
+        Self {
+            peer_id,
+            events,
+            effects,
+        }
+    }
+
+    fn send(&self, effect: Effect) {
+        self.effects.send(effect).unwrap();
+    }
+
+    /// Wait for the first event matching `predicate`, skipping the rest
+    async fn expect<F>(&mut self, what: &str, predicate: F) -> Event
+    where
+        F: Fn(&Event) -> bool,
+    {
+        timeout(Duration::from_secs(5), async {
+            loop {
+                let event = self.events.recv().await.expect("transport stopped");
+                if predicate(&event) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("Timed out waiting for {}", what))
+    }
+
+    /// Wait until the transport has connected to all of `peers`, in any order
+    async fn expect_connected(&mut self, peers: &[PeerId]) {
+        let mut pending = peers.to_vec();
+        while !pending.is_empty() {
+            if let Event::ConnectionEstablished { peer_id, .. } = self
+                .expect("connection", |event| {
+                    matches!(event, Event::ConnectionEstablished { .. })
+                })
+                .await
+            {
+                pending.retain(|peer| *peer != peer_id);
+            }
+        }
+    }
+
+    async fn expect_packet(&mut self, content: &[u8]) -> PeerId {
+        match self
+            .expect("packet", |event| {
+                matches!(event, Event::BitchatPacketReceived { packet, .. } if packet.payload == content)
+            })
+            .await
+        {
+            Event::BitchatPacketReceived { from, .. } => from,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// A line of nodes A - B - C where A and C cannot hear each other
+fn line() -> (SimulatedRadio, Vec<SimulatedNode>) {
+    let radio = SimulatedRadio::new();
+    let nodes: Vec<_> = (0..3)
+        .map(|_| radio.add_node(SimulatedNodeConfig::default()))
+        .collect();
+    radio.set_rssi(&nodes[0], &nodes[1], Some(-50));
+    radio.set_rssi(&nodes[1], &nodes[2], Some(-60));
+    (radio, nodes)
+}
+
+/// Spawn transports on all nodes and start them advertising and scanning
+fn start(nodes: &[SimulatedNode]) -> Vec<TestNode> {
+    let test_nodes: Vec<_> = nodes.iter().map(TestNode::spawn).collect();
+    start_all(&test_nodes);
+    test_nodes
+}
+
+/// Have node `from` prove `stable_id` to node `to` over the link between them
+///
+/// Stands in for both Core Logics: `from` sends a handshake packet directly and
+/// `to` reports that it completed the handshake.
+async fn prove_identity(test_nodes: &mut [TestNode], from: usize, to: usize, stable_id: PeerId) {
+    let recipient = test_nodes[to].peer_id;
+    let handshake = BitchatPacket::new_simple(
+        MessageType::NoiseHandshake,
+        stable_id,
+        stable_id.as_bytes().to_vec(),
+    )
+    .with_recipient(recipient);
+    test_nodes[from].send(Effect::SendBitchatPacket {
+        peer_id: recipient,
+        packet: handshake.clone(),
+        transport: TRANSPORT,
+    });
+    test_nodes[to].expect_packet(stable_id.as_bytes()).await;
+    test_nodes[to].send(Effect::BindLinkIdentity {
+        peer_id: stable_id,
+        handshake: PacketId::from_packet_data(
+            stable_id,
+            handshake.header.timestamp.as_millis(),
+            &handshake.payload,
+        ),
+        transport: TRANSPORT,
+    });
+}
+
+fn start_all(test_nodes: &[TestNode]) {
+    for node in test_nodes {
+        node.send(Effect::StartListening {
+            transport: TRANSPORT,
+        });
+        node.send(Effect::StartTransportDiscovery {
+            transport: TRANSPORT,
+        });
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_discovery_reports_signal_strength_and_connects() {
+    let (radio, nodes) = line();
+    let mut test_nodes = start(&nodes);
+    let (a, b, c) = (
+        test_nodes[0].peer_id,
+        test_nodes[1].peer_id,
+        test_nodes[2].peer_id,
+    );
+
+    let event = test_nodes[0]
+        .expect("discovery", |event| {
+            matches!(event, Event::PeerDiscovered { .. })
+        })
+        .await;
+    assert!(matches!(
+        event,
+        Event::PeerDiscovered { peer_id, signal_strength: Some(-50), .. } if peer_id == b
+    ));
+    test_nodes[0].expect_connected(&[b]).await;
+
+    // B hears both neighbours and holds a link to each
+    test_nodes[1].expect_connected(&[a, c]).await;
+    test_nodes[2].expect_connected(&[b]).await;
+    assert_eq!(radio.connection_count(&nodes[1]), 4);
+}
+
+#[tokio::test]
+async fn test_packets_are_delivered_and_relayed() {
+    let (_radio, nodes) = line();
+    let mut test_nodes = start(&nodes);
+    let (a, b, c) = (
+        test_nodes[0].peer_id,
+        test_nodes[1].peer_id,
+        test_nodes[2].peer_id,
+    );
+    test_nodes[0].expect_connected(&[b]).await;
+    test_nodes[1].expect_connected(&[a, c]).await;
+
+    test_nodes[0].send(Effect::SendBitchatPacket {
+        peer_id: b,
+        packet: BitchatPacket::new_simple(MessageType::Message, a, b"hello B".to_vec())
+            .with_recipient(b),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[1].expect_packet(b"hello B").await, a);
+
+    // C is out of A's range, so B forwards the packet on
+    test_nodes[0].send(Effect::SendBitchatPacket {
+        peer_id: b,
+        packet: BitchatPacket::new_simple(MessageType::Message, a, b"hello C".to_vec())
+            .with_recipient(c),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[2].expect_packet(b"hello C").await, a);
+}
+
+#[tokio::test]
+async fn test_broadcasts_are_flooded_once() {
+    let (_radio, nodes) = line();
+    let mut test_nodes = start(&nodes);
+    let (a, b, c) = (
+        test_nodes[0].peer_id,
+        test_nodes[1].peer_id,
+        test_nodes[2].peer_id,
+    );
+    test_nodes[0].expect_connected(&[b]).await;
+    test_nodes[1].expect_connected(&[a, c]).await;
+    test_nodes[2].expect_connected(&[b]).await;
+
+    test_nodes[0].send(Effect::BroadcastBitchatPacket {
+        packet: BitchatPacket::new_simple(MessageType::Message, a, b"hello all".to_vec()),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[1].expect_packet(b"hello all").await, a);
+    assert_eq!(test_nodes[2].expect_packet(b"hello all").await, a);
+
+    // Copies relayed back over the other links are dropped
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for node in &mut test_nodes {
+        while let Ok(event) = node.events.try_recv() {
+            assert!(
+                !matches!(&event, Event::BitchatPacketReceived { packet, .. } if packet.payload == b"hello all"),
+                "broadcast delivered twice to {}",
+                node.peer_id
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_file_chunks_are_fragmented_and_reassembled() {
+    let (_radio, nodes) = line();
+    let mut test_nodes = start(&nodes);
+    let (a, b, c) = (
+        test_nodes[0].peer_id,
+        test_nodes[1].peer_id,
+        test_nodes[2].peer_id,
+    );
+    test_nodes[0].expect_connected(&[b]).await;
+    test_nodes[1].expect_connected(&[a, c]).await;
+    test_nodes[2].expect_connected(&[b]).await;
+
+    // A full file chunk is far larger than one BLE frame
+    let chunk = |seed: u8| -> Vec<u8> {
+        (0..MAX_CHUNK_SIZE)
+            .map(|i| (i as u8).wrapping_mul(31) ^ seed)
+            .collect()
+    };
+    let packet = |payload: Vec<u8>| {
+        BitchatPacket::new_simple(MessageType::NoiseEncrypted, a, payload)
+            .with_version(PROTOCOL_VERSION_2)
+    };
+    assert!(MAX_CHUNK_SIZE > BleTransportConfig::default().max_packet_size);
+
+    // Directed to C, so B relays each fragment on
+    test_nodes[0].send(Effect::SendBitchatPacket {
+        peer_id: b,
+        packet: packet(chunk(1)).with_recipient(c),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[2].expect_packet(&chunk(1)).await, a);
+
+    // Broadcast fragments are reassembled by every node they are flooded to
+    test_nodes[0].send(Effect::BroadcastBitchatPacket {
+        packet: packet(chunk(2)),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[1].expect_packet(&chunk(2)).await, a);
+    assert_eq!(test_nodes[2].expect_packet(&chunk(2)).await, a);
+}
+
+#[tokio::test]
+async fn test_central_only_peers_are_reached_through_notifications() {
+    let radio = SimulatedRadio::new();
+    let nodes = [
+        radio.add_node(SimulatedNodeConfig::default()),
+        radio.add_node(SimulatedNodeConfig::default()),
+    ];
+    radio.set_rssi(&nodes[0], &nodes[1], Some(-50));
+    let mut test_nodes: Vec<_> = nodes.iter().map(TestNode::spawn).collect();
+    let (a, b) = (test_nodes[0].peer_id, test_nodes[1].peer_id);
+
+    // A only scans, so B never discovers it and holds no central link to it
+    test_nodes[0].send(Effect::StartTransportDiscovery {
+        transport: TRANSPORT,
+    });
+    start_all(&test_nodes[1..]);
+    test_nodes[0].expect_connected(&[b]).await;
+
+    test_nodes[0].send(Effect::SendBitchatPacket {
+        peer_id: b,
+        packet: BitchatPacket::new_simple(MessageType::Message, a, b"hello B".to_vec())
+            .with_recipient(b),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[1].expect_packet(b"hello B").await, a);
+
+    // B answers over the GATT server A subscribed to
+    test_nodes[1].send(Effect::SendBitchatPacket {
+        peer_id: a,
+        packet: BitchatPacket::new_simple(MessageType::Message, b, b"hello A".to_vec())
+            .with_recipient(a),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[0].expect_packet(b"hello A").await, b);
+
+    test_nodes[1].send(Effect::BroadcastBitchatPacket {
+        packet: BitchatPacket::new_simple(MessageType::Message, b, b"hello all".to_vec()),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[0].expect_packet(b"hello all").await, b);
+    assert_eq!(radio.connection_count(&nodes[1]), 1);
+}
+
+#[tokio::test]
+async fn test_dropped_link_is_reported() {
+    let (radio, nodes) = line();
+    let mut test_nodes = start(&nodes);
+    let (a, b) = (test_nodes[0].peer_id, test_nodes[1].peer_id);
+    test_nodes[0].expect_connected(&[b]).await;
+    test_nodes[1].expect_connected(&[a]).await;
+
+    radio.set_rssi(&nodes[0], &nodes[1], None);
+    let event = test_nodes[0]
+        .expect("lost connection", |event| {
+            matches!(event, Event::ConnectionLost { .. })
+        })
+        .await;
+    assert!(matches!(event, Event::ConnectionLost { peer_id, .. } if peer_id == b));
+    assert_eq!(radio.connection_count(&nodes[0]), 0);
+}
+
+#[tokio::test]
+async fn test_weak_peers_are_discovered_but_not_connected() {
+    let (radio, nodes) = line();
+    // B only links to neighbours stronger than C's -60 dBm
+    let config = BleTransportConfig::default()
+        .with_rssi_threshold(-55)
+        .with_connect_rate_limit(Duration::from_millis(10));
+    let mut test_nodes = vec![
+        TestNode::spawn(&nodes[0]),
+        TestNode::spawn_with_config(&nodes[1], config),
+        TestNode::spawn(&nodes[2]),
+    ];
+    start_all(&test_nodes);
+    let (a, b, c) = (
+        test_nodes[0].peer_id,
+        test_nodes[1].peer_id,
+        test_nodes[2].peer_id,
+    );
+
+    test_nodes[1]
+        .expect(
+            "discovery of C",
+            |event| matches!(event, Event::PeerDiscovered { peer_id, .. } if *peer_id == c),
+        )
+        .await;
+    test_nodes[1].expect_connected(&[a]).await;
+    test_nodes[0].expect_connected(&[b]).await;
+    test_nodes[2].expect_connected(&[b]).await;
+
+    // Links A -> B, B -> A and C -> B, but none from B to C
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(radio.connection_count(&nodes[1]), 3);
+}
+
+#[tokio::test]
+async fn test_replayed_announce_does_not_bind_a_link() {
+    let (_radio, nodes) = line();
+    let mut test_nodes = start(&nodes);
+    let (a, b, c) = (
+        test_nodes[0].peer_id,
+        test_nodes[1].peer_id,
+        test_nodes[2].peer_id,
+    );
+    test_nodes[0].expect_connected(&[b]).await;
+    test_nodes[1].expect_connected(&[a, c]).await;
+    test_nodes[2].expect_connected(&[b]).await;
+
+    // A announces its stable identity, and C captures the copy B relays
+    let noise_key = [7u8; 32];
+    let stable_a = PeerId::from_noise_key(&noise_key);
+    let announce = BitchatPacket::create_announce(
+        stable_a,
+        "alice".to_string(),
+        noise_key,
+        &IdentityKeyPair::generate().unwrap(),
+        None,
+        Timestamp::now(),
+    )
+    .unwrap();
+    test_nodes[0].send(Effect::BroadcastBitchatPacket {
+        packet: announce.clone(),
+        transport: TRANSPORT,
+    });
+    test_nodes[2].expect_packet(&announce.payload).await;
+
+    // C replays it over its own link with the TTL reset, as if it were A
+    let mut replayed = announce;
+    replayed.header.ttl = Ttl::MAX;
+    test_nodes[2].send(Effect::BroadcastBitchatPacket {
+        packet: replayed,
+        transport: TRANSPORT,
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Neither announce proved anything, so B has no link for A's stable ID
+    let message = |content: &[u8]| {
+        BitchatPacket::new_simple(MessageType::Message, b, content.to_vec())
+            .with_recipient(stable_a)
+    };
+    test_nodes[1].send(Effect::SendBitchatPacket {
+        peer_id: stable_a,
+        packet: message(b"for alice"),
+        transport: TRANSPORT,
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    while let Ok(event) = test_nodes[1].events.try_recv() {
+        assert!(
+            !matches!(event, Event::PeerDiscovered { peer_id, .. } if peer_id == stable_a),
+            "B took a replayed announce for A"
+        );
+    }
+    while let Ok(event) = test_nodes[2].events.try_recv() {
+        assert!(
+            !matches!(&event, Event::BitchatPacketReceived { packet, .. } if packet.payload == b"for alice"),
+            "B sent A's traffic to C"
+        );
+    }
+
+    // A handshake over A's own link proves the identity
+    prove_identity(&mut test_nodes, 0, 1, stable_a).await;
+    test_nodes[1]
+        .expect(
+            "proven identity",
+            |event| matches!(event, Event::PeerDiscovered { peer_id, .. } if *peer_id == stable_a),
+        )
+        .await;
+    test_nodes[1].send(Effect::SendBitchatPacket {
+        peer_id: stable_a,
+        packet: message(b"for alice, again"),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[0].expect_packet(b"for alice, again").await, b);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    while let Ok(event) = test_nodes[2].events.try_recv() {
+        assert!(
+            !matches!(&event, Event::BitchatPacketReceived { packet, .. } if packet.payload == b"for alice, again"),
+            "B sent A's traffic to C"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_rotated_peer_id_keeps_proven_identity() {
+    let (radio, nodes) = line();
+    let config =
+        BleTransportConfig::default().with_peer_id_rotation(Some(Duration::from_millis(300)));
+    let mut test_nodes = vec![
+        TestNode::spawn_with_config(&nodes[0], config),
+        TestNode::spawn(&nodes[1]),
+    ];
+    start_all(&test_nodes);
+    let (a, b) = (test_nodes[0].peer_id, test_nodes[1].peer_id);
+    test_nodes[0].expect_connected(&[b]).await;
+    test_nodes[1].expect_connected(&[a]).await;
+
+    // A's Core Logic completes a handshake with B under its stable identity
+    let stable_a = PeerId::new([0xA; 8]);
+    prove_identity(&mut test_nodes, 0, 1, stable_a).await;
+    test_nodes[1]
+        .expect(
+            "proven identity",
+            |event| matches!(event, Event::PeerDiscovered { peer_id, .. } if *peer_id == stable_a),
+        )
+        .await;
+
+    // Observers see A under a new name once the interval passes
+    let advertised_name = || radio.advertisement(&nodes[0]).unwrap().local_name.unwrap();
+    let first_name = advertised_name();
+    timeout(Duration::from_secs(5), async {
+        while advertised_name() == first_name {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("A never rotated its peer ID");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // B still reaches A under its stable ID, over the same link
+    test_nodes[1].send(Effect::SendBitchatPacket {
+        peer_id: stable_a,
+        packet: BitchatPacket::new_simple(MessageType::Message, b, b"still there?".to_vec())
+            .with_recipient(stable_a),
+        transport: TRANSPORT,
+    });
+    assert_eq!(test_nodes[0].expect_packet(b"still there?").await, b);
+    while let Ok(event) = test_nodes[1].events.try_recv() {
+        assert!(
+            !matches!(
+                event,
+                Event::PeerDiscovered { .. } | Event::ConnectionLost { .. }
+            ),
+            "rotation looked like a new peer to B: {:?}",
+            event
+        );
+    }
+}
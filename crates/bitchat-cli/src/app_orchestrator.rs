@@ -9,12 +9,13 @@ use bitchat_core::{
     internal::{
         create_app_event_channel, create_command_channel, create_effect_channel,
         create_effect_receiver, create_event_channel, ChannelConfig, ConsoleLogger, DeliveryConfig,
-        EffectSender, LogLevel, RateLimitConfig, SessionConfig, TransportError,
+        EffectSender, FileStorage, LogLevel, RateLimitConfig, SessionConfig, TransportError,
     },
     BitchatError, BitchatResult, ChannelTransportType, EventSender, PeerId, TransportTask,
 };
 use bitchat_runtime::logic::{CoreLogicTask, LoggerWrapper};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::task::JoinHandle;
 
 // ----------------------------------------------------------------------------
@@ -89,6 +90,11 @@ pub struct CliAppOrchestrator {
     paused_transports: HashMap<ChannelTransportType, bool>,
     /// Terminal interface for external interaction
     terminal_interface: Option<TerminalInterfaceTask>,
+    /// Directory keeping our identity key and known peer identities between runs
+    identity_dir: Option<PathBuf>,
+    /// Download and part file directories for file transfers, if not the defaults
+    #[cfg(feature = "experimental")]
+    file_transfer_dirs: Option<(PathBuf, Option<PathBuf>)>,
     /// Running state
     running: bool,
 }
@@ -120,6 +126,9 @@ impl CliAppOrchestrator {
             transport_handles: HashMap::new(),
             paused_transports: HashMap::new(),
             terminal_interface: None,
+            identity_dir: None,
+            #[cfg(feature = "experimental")]
+            file_transfer_dirs: None,
             running: false,
         }
     }
@@ -144,10 +153,29 @@ impl CliAppOrchestrator {
             transport_handles: HashMap::new(),
             paused_transports: HashMap::new(),
             terminal_interface: None,
+            identity_dir: None,
+            #[cfg(feature = "experimental")]
+            file_transfer_dirs: None,
             running: false,
         }
     }
 
+    /// Keep our identity key and known peer identities in `dir` between runs
+    ///
+    /// Without one, a new identity is generated on every start. Must be called
+    /// before `start()`.
+    pub fn set_identity_dir(&mut self, dir: PathBuf) {
+        self.identity_dir = Some(dir);
+    }
+
+    /// Save accepted files to `download_dir` and keep part files in `partial_dir`
+    ///
+    /// Must be called before `start()`.
+    #[cfg(feature = "experimental")]
+    pub fn set_file_transfer_dirs(&mut self, download_dir: PathBuf, partial_dir: Option<PathBuf>) {
+        self.file_transfer_dirs = Some((download_dir, partial_dir));
+    }
+
     /// Start the CLI application
     pub async fn start(&mut self) -> BitchatResult<()> {
         if self.running {
@@ -193,6 +221,13 @@ impl CliAppOrchestrator {
             DeliveryConfig::default(),
             RateLimitConfig::default(),
         )?;
+        if let Some(dir) = &self.identity_dir {
+            core_logic = core_logic.with_identity_storage(Box::new(FileStorage::open(dir)?))?;
+        }
+        #[cfg(feature = "experimental")]
+        if let Some((download_dir, partial_dir)) = self.file_transfer_dirs.clone() {
+            core_logic = core_logic.with_file_transfer_dirs(download_dir, partial_dir);
+        }
 
         let core_handle = tokio::spawn(async move { core_logic.run().await });
         self.core_logic_handle = Some(core_handle);
@@ -50,6 +50,9 @@ pub struct CliAppConfig {
 
     /// Runtime behavior configuration
     pub runtime: RuntimeConfig,
+
+    /// File transfer configuration
+    pub files: FilesConfig,
 }
 
 /// CLI-specific configuration options
@@ -89,12 +92,22 @@ pub struct IdentityConfig {
     pub name: Option<String>,
 
     /// Whether to save generated identity to file for reuse
+    ///
+    /// Also keeps our signing key and known peer identities in a directory
+    /// beside the identity file.
     pub persist_identity: bool,
 
     /// Path to identity file (defaults to ~/.bitchat/identity.toml)
     pub identity_file: Option<PathBuf>,
 }
 
+/// Identity details saved to the identity file between runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredIdentity {
+    /// Nickname last set with the `nick` command
+    pub nickname: Option<String>,
+}
+
 /// Runtime behavior configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuntimeConfig {
@@ -114,6 +127,17 @@ pub struct RuntimeConfig {
     pub heartbeat_interval_secs: u64,
 }
 
+/// Where received files and partially received files are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilesConfig {
+    /// Directory accepted files are saved to
+    pub download_dir: PathBuf,
+
+    /// Directory for partially received files (defaults to a `.bitchat-partial`
+    /// directory under `download_dir`, so finished files are renamed into place)
+    pub partial_dir: Option<PathBuf>,
+}
+
 // ----------------------------------------------------------------------------
 // Default Implementations
 // ----------------------------------------------------------------------------
@@ -143,6 +167,15 @@ impl Default for IdentityConfig {
     }
 }
 
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            download_dir: PathBuf::from("."),
+            partial_dir: None,
+        }
+    }
+}
+
 impl Default for RuntimeConfig {
     fn default() -> Self {
         Self {
@@ -410,6 +443,54 @@ impl CliAppConfig {
             .collect()
     }
 
+    /// Path of the identity file, defaulting to ~/.bitchat/identity.toml
+    pub fn identity_file_path(&self) -> Result<PathBuf, ConfigError> {
+        match &self.identity.identity_file {
+            Some(path) => Ok(path.clone()),
+            None => Ok(Self::default_config_path()?.with_file_name("identity.toml")),
+        }
+    }
+
+    /// Directory beside the identity file that keeps our identity key and known peers
+    pub fn identity_storage_dir(&self) -> Result<PathBuf, ConfigError> {
+        Ok(self.identity_file_path()?.with_extension("keys"))
+    }
+
+    /// Load the saved identity, or an empty one if nothing was saved
+    pub fn load_stored_identity(&self) -> Result<StoredIdentity, ConfigError> {
+        if !self.identity.persist_identity {
+            return Ok(StoredIdentity::default());
+        }
+        let path = self.identity_file_path()?;
+        if !path.exists() {
+            return Ok(StoredIdentity::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| ConfigError::FileSystem(format!("Failed to read identity file: {}", e)))?;
+        toml::from_str(&contents)
+            .map_err(|e| ConfigError::Loading(format!("Failed to parse identity file: {}", e)))
+    }
+
+    /// Save identity details for the next run, unless persistence is disabled
+    pub fn save_stored_identity(&self, identity: &StoredIdentity) -> Result<(), ConfigError> {
+        if !self.identity.persist_identity {
+            return Ok(());
+        }
+        let path = self.identity_file_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                ConfigError::FileSystem(format!("Failed to create identity directory: {}", e))
+            })?;
+        }
+
+        let toml_string = toml::to_string_pretty(identity).map_err(|e| {
+            ConfigError::Serialization(format!("Failed to serialize identity: {}", e))
+        })?;
+        std::fs::write(&path, toml_string)
+            .map_err(|e| ConfigError::FileSystem(format!("Failed to write identity file: {}", e)))
+    }
+
     /// Create example configuration file content
     pub fn example_config() -> String {
         let example_config = CliAppConfig {
@@ -538,6 +619,36 @@ mod tests {
         assert!(transports.contains(&ChannelTransportType::Nostr));
     }
 
+    #[test]
+    fn test_stored_identity_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("bitchat-identity-{}.toml", std::process::id()));
+        let mut config = CliAppConfig::default();
+        config.identity.identity_file = Some(path.clone());
+        assert_eq!(
+            config.load_stored_identity().unwrap(),
+            StoredIdentity::default()
+        );
+
+        let identity = StoredIdentity {
+            nickname: Some("alice".to_string()),
+        };
+        config.save_stored_identity(&identity).unwrap();
+        assert_eq!(config.load_stored_identity().unwrap(), identity);
+        assert_eq!(
+            config.identity_storage_dir().unwrap(),
+            path.with_extension("keys")
+        );
+
+        // Nothing is read or written once persistence is turned off
+        config.identity.persist_identity = false;
+        assert_eq!(
+            config.load_stored_identity().unwrap(),
+            StoredIdentity::default()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
     #[test]
     fn test_example_config_generation() {
         let example = CliAppConfig::example_config();
@@ -545,5 +656,6 @@ mod tests {
         assert!(example.contains("[cli]"));
         assert!(example.contains("[identity]"));
         assert!(example.contains("[runtime]"));
+        assert!(example.contains("[files]"));
     }
 }
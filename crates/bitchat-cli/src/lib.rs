@@ -10,7 +10,11 @@ pub use app_orchestrator::{
     start_cli_application, start_cli_application_with_config,
     start_cli_application_with_transports, CliAppOrchestrator, TransportConfig,
 };
-pub use config::{CliAppConfig, CliConfig, ConfigError, IdentityConfig, RuntimeConfig};
+pub use config::{
+    CliAppConfig, CliConfig, ConfigError, FilesConfig, IdentityConfig, RuntimeConfig,
+    StoredIdentity,
+};
 pub use terminal_interface::{
-    MessageDirection, PeerUIState, SystemStatus, TerminalInterfaceTask, UIMessage, UIState,
+    GroupUIState, MessageDirection, PeerUIState, SystemStatus, TerminalInterfaceTask,
+    TransferUIState, UIMessage, UIState,
 };
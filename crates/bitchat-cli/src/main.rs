@@ -2,11 +2,16 @@
 //!
 //! Command-line client with robust configuration management using figment
 
-use bitchat_cli::{CliAppConfig, CliAppOrchestrator, ConfigError, TransportConfig};
+use bitchat_cli::{
+    CliAppConfig, CliAppOrchestrator, ConfigError, MessageDirection, StoredIdentity,
+    TerminalInterfaceTask, TransportConfig,
+};
+use bitchat_core::channel::communication::{FileTransferState, TransferDirection};
 use bitchat_core::{
-    internal::TransportError, BitchatError, BitchatResult, ChannelTransportType, ConnectionStatus,
-    PeerId,
+    internal::{MessageId, TransportError},
+    AppEvent, BitchatError, BitchatResult, ChannelTransportType, ConnectionStatus, PeerId,
 };
+use bitchat_nostr::{RelayServer, RelayServerConfig};
 use clap::{Arg, Command};
 use std::io::{self, Write};
 
@@ -19,7 +24,7 @@ pub struct BitchatCliApp {
     /// Application orchestrator
     orchestrator: CliAppOrchestrator,
     /// Configuration
-    _config: CliAppConfig,
+    config: CliAppConfig,
     /// Running state
     running: bool,
 }
@@ -57,15 +62,39 @@ impl BitchatCliApp {
             config.cli.verbose,
             transport_config,
         );
+        if config.identity.persist_identity {
+            let identity_dir = config
+                .identity_storage_dir()
+                .map_err(ApplicationError::Configuration)?;
+            orchestrator.set_identity_dir(identity_dir);
+        }
+        #[cfg(feature = "experimental")]
+        orchestrator.set_file_transfer_dirs(
+            config.files.download_dir.clone(),
+            config.files.partial_dir.clone(),
+        );
 
         orchestrator
             .start()
             .await
             .map_err(ApplicationError::Runtime)?;
 
+        // Announce under the nickname chosen in an earlier run
+        let stored = config
+            .load_stored_identity()
+            .map_err(ApplicationError::Configuration)?;
+        if let (Some(nickname), Some(terminal)) =
+            (stored.nickname, orchestrator.terminal_interface())
+        {
+            terminal
+                .handle_set_nickname(nickname)
+                .await
+                .map_err(ApplicationError::Runtime)?;
+        }
+
         Ok(Self {
             orchestrator,
-            _config: config,
+            config,
             running: false,
         })
     }
@@ -101,6 +130,21 @@ impl BitchatCliApp {
 
         println!("\nShutting down BitChat CLI...");
 
+        // Keep the nickname for the next run, including one adopted from a linked device
+        let mut nickname = None;
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.apply_pending_events().await?;
+            nickname = terminal
+                .get_state_snapshot()
+                .and_then(|state| state.nickname);
+        }
+        if nickname.is_some() {
+            let stored = StoredIdentity { nickname };
+            if let Err(e) = self.config.save_stored_identity(&stored) {
+                println!("Warning: nickname will not be kept after exit: {}", e);
+            }
+        }
+
         self.orchestrator.stop().await?;
         self.running = false;
 
@@ -113,9 +157,14 @@ impl BitchatCliApp {
         let stdin = io::stdin();
 
         loop {
-            // Print status and prompt
+            // Catch up on what Core Logic reported, then print status and prompt
+            if let Some(terminal) = self.orchestrator.terminal_interface() {
+                terminal.apply_pending_events().await?;
+            }
             self.print_status().await?;
             self.print_recent_messages().await?;
+            self.print_file_offers().await?;
+            self.print_group_invitations().await?;
             print!("bitchat> ");
             self.flush_stdout()?;
 
@@ -174,6 +223,14 @@ impl BitchatCliApp {
                 let message = parts[1..].join(" ");
                 self.send_broadcast_message(message).await?;
             }
+            "reply" => {
+                if parts.len() < 3 {
+                    println!("Usage: reply <message_id> <message>");
+                    return Ok(());
+                }
+                self.reply_to_message(parts[1], parts[2..].join(" "))
+                    .await?;
+            }
             "private" => {
                 if parts.len() < 3 {
                     println!("Usage: private <peer_id> <message>");
@@ -196,9 +253,62 @@ impl BitchatCliApp {
             "discover" => {
                 self.start_discovery().await?;
             }
+            "fav" => match parts.get(1).copied().unwrap_or("list") {
+                "list" => self.print_favorites().await?,
+                "add" | "remove" if parts.len() == 3 => {
+                    self.set_favorite(parts[2], parts[1] == "add").await?;
+                }
+                _ => {
+                    println!("Usage: fav [list] | fav add <peer_id> | fav remove <peer_id>");
+                }
+            },
+            "send-file" => {
+                if parts.len() < 3 {
+                    println!("Usage: send-file <peer_id> <path>");
+                    println!("   Example: send-file 0102030405060708 ./photo.jpg");
+                    return Ok(());
+                }
+                self.send_file(parts[1], parts[2..].join(" ")).await?;
+            }
+            "accept" => {
+                if parts.len() < 2 {
+                    println!("Usage: accept <transfer_id> [directory]");
+                    return Ok(());
+                }
+                let directory = (parts.len() > 2).then(|| parts[2..].join(" "));
+                self.accept_file(parts[1], directory).await?;
+            }
+            "reject" => {
+                if parts.len() != 2 {
+                    println!("Usage: reject <transfer_id>");
+                    return Ok(());
+                }
+                self.reject_file(parts[1]).await?;
+            }
+            "transfers" => {
+                self.print_transfers().await?;
+            }
+            "group" => {
+                self.handle_group_command(&parts[1..]).await?;
+            }
+            "link-device" => match parts.get(1) {
+                None => self.link_device().await?,
+                Some(uri) if parts.len() == 2 => self.accept_device_link(uri).await?,
+                Some(_) => println!("Usage: link-device [uri]"),
+            },
+            "devices" => {
+                self.print_devices().await?;
+            }
             "stop-discovery" => {
                 self.stop_discovery().await?;
             }
+            "nick" | "/nick" => {
+                if parts.len() < 2 {
+                    println!("Usage: nick <nickname>");
+                    return Ok(());
+                }
+                self.set_nickname(parts[1..].join(" ")).await?;
+            }
             "clear" => {
                 self.clear_screen()?;
             }
@@ -215,11 +325,24 @@ impl BitchatCliApp {
 
     /// Send broadcast message
     async fn send_broadcast_message(&self, message: String) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_send_broadcast(message).await?;
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reply to a message in the thread it was sent in
+    async fn reply_to_message(&self, message_id: &str, message: String) -> BitchatResult<()> {
         if let Some(terminal) = self.orchestrator.terminal_interface() {
             terminal
-                .handle_send_message(self.orchestrator.peer_id(), message)
+                .handle_reply_to_message(message_id.to_string(), message)
                 .await?;
-            println!("Broadcast message sent");
         } else {
             return Err(BitchatError::Transport(
                 TransportError::InvalidConfiguration {
@@ -296,6 +419,554 @@ impl BitchatCliApp {
         Ok(())
     }
 
+    /// Change our nickname, saving it for the next run once the runtime accepts it
+    async fn set_nickname(&self, nickname: String) -> BitchatResult<()> {
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        };
+        terminal.handle_set_nickname(nickname).await?;
+        let reply = wait_for_reply(terminal, |app_event| {
+            matches!(app_event, AppEvent::NicknameChanged { .. })
+        })
+        .await?;
+        let Some(AppEvent::NicknameChanged { nickname }) = reply else {
+            return Ok(());
+        };
+        println!("Nickname set to '{}'", nickname);
+
+        let stored = StoredIdentity {
+            nickname: Some(nickname),
+        };
+        if let Err(e) = self.config.save_stored_identity(&stored) {
+            println!("Warning: nickname will not be kept after exit: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Favorite or unfavorite a peer
+    async fn set_favorite(&self, peer_id_str: &str, favorite: bool) -> BitchatResult<()> {
+        let peer_id = self.parse_peer_id(peer_id_str)?;
+
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_set_favorite(peer_id, favorite).await?;
+            if favorite {
+                println!("Favorited {}", peer_id);
+            } else {
+                println!("Unfavorited {}", peer_id);
+            }
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Print favorites, marking mutual favorites reachable over Nostr
+    async fn print_favorites(&self) -> BitchatResult<()> {
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Ok(());
+        };
+        terminal.handle_list_favorites().await?;
+        let reply = wait_for_reply(terminal, |app_event| {
+            matches!(app_event, AppEvent::FavoritesReport { .. })
+        })
+        .await?;
+        if reply.is_none() {
+            return Ok(());
+        }
+
+        let Some(state) = terminal.get_state_snapshot() else {
+            return Ok(());
+        };
+        if state.favorites.is_empty() {
+            println!("No favorites yet. Use 'fav add <peer_id>' to add one.");
+            return Ok(());
+        }
+
+        println!("Favorites ({}):", state.favorites.len());
+        for favorite in &state.favorites {
+            let relation = if favorite.is_mutual() {
+                "[MUTUAL]"
+            } else if favorite.favorite {
+                "[FAV]"
+            } else {
+                "[FAN]"
+            };
+            let nostr = favorite
+                .nostr_public_key
+                .map(|key| format!("nostr {}", hex::encode(key)))
+                .unwrap_or_else(|| "no nostr key".to_string());
+            println!("  {} {} - {}", relation, favorite.peer_id, nostr);
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Offer a file to a peer
+    async fn send_file(&self, peer_id_str: &str, path: String) -> BitchatResult<()> {
+        let recipient = self.parse_peer_id(peer_id_str)?;
+
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_send_file(recipient, path.clone()).await?;
+            println!("Offering {} to {}", path, recipient);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Accept a file offer, optionally into a specific directory
+    async fn accept_file(&self, transfer_id: &str, directory: Option<String>) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal
+                .handle_accept_file(transfer_id.to_string(), directory)
+                .await?;
+            println!("Accepted transfer {}", transfer_id);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject a file offer
+    async fn reject_file(&self, transfer_id: &str) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_reject_file(transfer_id.to_string()).await?;
+            println!("Rejected transfer {}", transfer_id);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Print file transfers in either direction with their progress
+    async fn print_transfers(&self) -> BitchatResult<()> {
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Ok(());
+        };
+        terminal.handle_list_transfers().await?;
+        let reply = wait_for_reply(terminal, |app_event| {
+            matches!(app_event, AppEvent::TransfersReport { .. })
+        })
+        .await?;
+        if reply.is_none() {
+            return Ok(());
+        }
+
+        let Some(state) = terminal.get_state_snapshot() else {
+            return Ok(());
+        };
+        if state.transfers.is_empty() {
+            println!("No file transfers. Use 'send-file <peer_id> <path>' to send one.");
+            return Ok(());
+        }
+
+        println!("File Transfers ({}):", state.transfers.len());
+        for transfer in &state.transfers {
+            let info = &transfer.info;
+            let direction = match info.direction {
+                TransferDirection::Incoming => "<-",
+                TransferDirection::Outgoing => "->",
+            };
+            let detail = transfer
+                .saved_to
+                .as_ref()
+                .map(|path| format!(" saved to {}", path))
+                .or_else(|| transfer.error.as_ref().map(|error| format!(" ({})", error)))
+                .unwrap_or_default();
+            println!(
+                "  {} {} {} {} - {} bytes, {:.0}% {}{}",
+                short_transfer_id(&info.transfer_id),
+                direction,
+                info.peer_id,
+                info.filename,
+                info.size,
+                info.progress() * 100.0,
+                info.state,
+                detail
+            );
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Print incoming file offers still waiting for an answer
+    async fn print_file_offers(&self) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            if let Some(state) = terminal.get_state_snapshot() {
+                let offers: Vec<_> = state
+                    .transfers
+                    .iter()
+                    .map(|transfer| &transfer.info)
+                    .filter(|info| {
+                        info.direction == TransferDirection::Incoming
+                            && info.state == FileTransferState::Offered
+                    })
+                    .collect();
+                for info in &offers {
+                    let id = short_transfer_id(&info.transfer_id);
+                    println!(
+                        "[FILE] {} offers {} ({} bytes) - 'accept {}' or 'reject {}'",
+                        info.peer_id, info.filename, info.size, id, id
+                    );
+                }
+
+                if !offers.is_empty() {
+                    println!();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Dispatch a `group` subcommand
+    async fn handle_group_command(&self, args: &[&str]) -> BitchatResult<()> {
+        match args {
+            [] | ["list"] => self.print_groups().await?,
+            ["create", name @ ..] if !name.is_empty() => self.create_group(name.join(" ")).await?,
+            ["invite", group_id, peer_id] => self.invite_to_group(group_id, peer_id).await?,
+            ["join", group_id] => self.join_group(group_id).await?,
+            ["leave", group_id] => self.leave_group(group_id).await?,
+            ["kick", group_id, peer_id] => self.kick_from_group(group_id, peer_id).await?,
+            ["say", group_id, message @ ..] if !message.is_empty() => {
+                self.send_group_message(group_id, message.join(" ")).await?
+            }
+            ["view", group_id] => self.print_group(group_id).await?,
+            _ => {
+                println!("Usage: group [list] | group create <name> | group view <group_id>");
+                println!("       group invite|kick <group_id> <peer_id>");
+                println!("       group join|leave <group_id> | group say <group_id> <message>");
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a group with us as its owner
+    async fn create_group(&self, name: String) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_create_group(name.clone(), None).await?;
+            println!("Creating group {}", name);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Invite a peer to a group
+    async fn invite_to_group(&self, group_id: &str, peer_id_str: &str) -> BitchatResult<()> {
+        let peer_id = self.parse_peer_id(peer_id_str)?;
+
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal
+                .handle_invite_to_group(group_id.to_string(), peer_id)
+                .await?;
+            println!("Inviting {} to group {}", peer_id, group_id);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Join a group we were invited to
+    async fn join_group(&self, group_id: &str) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_join_group(group_id.to_string()).await?;
+            println!("Joining group {}", group_id);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Leave a group
+    async fn leave_group(&self, group_id: &str) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_leave_group(group_id.to_string()).await?;
+            println!("Leaving group {}", group_id);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Remove a member from a group
+    async fn kick_from_group(&self, group_id: &str, peer_id_str: &str) -> BitchatResult<()> {
+        let peer_id = self.parse_peer_id(peer_id_str)?;
+
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal
+                .handle_kick_from_group(group_id.to_string(), peer_id)
+                .await?;
+            println!("Removing {} from group {}", peer_id, group_id);
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Send a message to every member of a group
+    async fn send_group_message(&self, group_id: &str, message: String) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal
+                .handle_send_group_message(group_id.to_string(), message)
+                .await?;
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Print our groups and the groups we were invited to
+    async fn print_groups(&self) -> BitchatResult<()> {
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Ok(());
+        };
+        terminal.handle_list_groups().await?;
+        let reply = wait_for_reply(terminal, |app_event| {
+            matches!(app_event, AppEvent::GroupsReport { .. })
+        })
+        .await?;
+        if reply.is_none() {
+            return Ok(());
+        }
+
+        let Some(state) = terminal.get_state_snapshot() else {
+            return Ok(());
+        };
+        if state.groups.is_empty() {
+            println!("No groups yet. Use 'group create <name>' to start one.");
+            return Ok(());
+        }
+
+        println!("Groups ({}):", state.groups.len());
+        for group in &state.groups {
+            let status = if group.joined { "" } else { " [INVITED]" };
+            println!(
+                "  {} {} - {} members, {} messages{}",
+                short_group_id(&group.info.group_id),
+                group.info.name,
+                group.info.members.len(),
+                group.messages.len(),
+                status
+            );
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Print a group's members and recent messages
+    async fn print_group(&self, group_id: &str) -> BitchatResult<()> {
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Ok(());
+        };
+        let Some(state) = terminal.get_state_snapshot() else {
+            return Ok(());
+        };
+        let Some(group) = state
+            .groups
+            .iter()
+            .find(|group| group.info.group_id.starts_with(group_id))
+        else {
+            println!(
+                "No group {}. Use 'group list' to see your groups.",
+                group_id
+            );
+            return Ok(());
+        };
+
+        println!("{} ({})", group.info.name, group.info.group_id);
+        if let Some(description) = &group.info.description {
+            println!("{}", description);
+        }
+        let members: Vec<String> = group
+            .info
+            .members
+            .iter()
+            .map(|member| {
+                let admin = if member.admin { " [ADMIN]" } else { "" };
+                format!("{} ({}){}", member.nickname, member.peer_id, admin)
+            })
+            .collect();
+        println!("Members: {}", members.join(", "));
+        println!();
+
+        if group.messages.is_empty() {
+            println!(
+                "No messages yet. Use 'group say {} <message>' to write one.",
+                group_id
+            );
+        }
+        for message in &group.messages {
+            let sender = if message.direction == MessageDirection::Outgoing {
+                "you".to_string()
+            } else {
+                state.display_name(&message.from)
+            };
+            println!("  <{}> {}", sender, message.content);
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Print group invitations we have not answered
+    async fn print_group_invitations(&self) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            if let Some(state) = terminal.get_state_snapshot() {
+                let invitations: Vec<_> =
+                    state.groups.iter().filter(|group| !group.joined).collect();
+                for group in &invitations {
+                    let inviter = group
+                        .invited_by
+                        .map(|peer_id| peer_id.to_string())
+                        .unwrap_or_else(|| "A member".to_string());
+                    println!(
+                        "[GROUP] {} invited you to {} - 'group join {}'",
+                        inviter,
+                        group.info.name,
+                        short_group_id(&group.info.group_id)
+                    );
+                }
+
+                if !invitations.is_empty() {
+                    println!();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Show a one-time URI for linking another device to our identity
+    async fn link_device(&self) -> BitchatResult<()> {
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        };
+        terminal.handle_link_device().await?;
+        let reply = wait_for_reply(terminal, |app_event| {
+            matches!(app_event, AppEvent::DeviceLinkOffer { .. })
+        })
+        .await?;
+        let Some(AppEvent::DeviceLinkOffer { uri, expires_at }) = reply else {
+            return Ok(());
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        println!("On the new device, run:");
+        println!("  link-device {}", uri);
+        println!(
+            "The offer works once and expires in {} minutes.",
+            expires_at.saturating_sub(now).div_ceil(60_000)
+        );
+        println!();
+        Ok(())
+    }
+
+    /// Link this device to the identity that created a link offer
+    async fn accept_device_link(&self, uri: &str) -> BitchatResult<()> {
+        if let Some(terminal) = self.orchestrator.terminal_interface() {
+            terminal.handle_accept_device_link(uri.to_string()).await?;
+            println!("Linking to the device that created the offer");
+        } else {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "Terminal interface not available".to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Print the devices linked to our identity
+    async fn print_devices(&self) -> BitchatResult<()> {
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Ok(());
+        };
+        terminal.handle_list_devices().await?;
+        let reply = wait_for_reply(terminal, |app_event| {
+            matches!(app_event, AppEvent::DevicesReport { .. })
+        })
+        .await?;
+        if reply.is_none() {
+            return Ok(());
+        }
+
+        let Some(state) = terminal.get_state_snapshot() else {
+            return Ok(());
+        };
+        if state.devices.len() <= 1 {
+            println!("No linked devices. Use 'link-device' to link one.");
+            return Ok(());
+        }
+
+        println!("Devices ({}):", state.devices.len());
+        for device in &state.devices {
+            let status = if device.this_device {
+                "this device"
+            } else if device.online {
+                "online"
+            } else {
+                "offline"
+            };
+            let peer_id = device.peer_id.to_string();
+            if device.name == peer_id {
+                println!("  {} ({})", peer_id, status);
+            } else {
+                println!("  {} - {} ({})", device.name, peer_id, status);
+            }
+        }
+        println!();
+        Ok(())
+    }
+
     /// Parse peer ID from hex string with better error messages
     fn parse_peer_id(&self, peer_id_str: &str) -> BitchatResult<PeerId> {
         let peer_bytes = hex::decode(peer_id_str).map_err(|_| {
@@ -342,10 +1013,28 @@ impl BitchatCliApp {
         println!("  help                           Show this help message");
         println!("  status                         Show detailed application status");
         println!("  peers                          List discovered peers");
-        println!("  send <message>                 Send broadcast message");
+        println!("  send <message>                 Send a public message to the mesh");
         println!("  private <peer_id> <message>    Send private message to specific peer");
+        println!("  reply <message_id> <message>   Reply to a message shown with its [id]");
         println!("  connect <peer_id>              Connect to specific peer");
         println!("  discover                       Start peer discovery");
+        println!("  fav [list]                     List favorites and mutual favorites");
+        println!("  fav add|remove <peer_id>       Favorite or unfavorite a peer");
+        println!("  send-file <peer_id> <path>     Offer a file to a peer");
+        println!("  accept <transfer_id> [dir]     Accept a file offer, saving it to dir");
+        println!("  reject <transfer_id>           Reject a file offer");
+        println!("  transfers                      List file transfers and their progress");
+        println!("  group [list]                   List groups and invitations");
+        println!("  group create <name>            Create a group");
+        println!("  group invite <group_id> <peer> Invite a peer to a group");
+        println!("  group join|leave <group_id>    Join a group you were invited to, or leave one");
+        println!("  group kick <group_id> <peer>   Remove a member from a group");
+        println!("  group say <group_id> <message> Send a message to a group");
+        println!("  group view <group_id>          Show a group's members and messages");
+        println!("  link-device                    Show a one-time URI for linking another device");
+        println!("  link-device <uri>              Link this device using another device's URI");
+        println!("  devices                        List devices linked to your identity");
+        println!("  nick <nickname>                Change your nickname and announce it");
         println!("  stop-discovery                 Stop peer discovery");
         println!("  clear                          Clear screen");
         println!("  quit | exit                    Exit application");
@@ -390,6 +1079,31 @@ impl BitchatCliApp {
                 println!("  Connected Peers: {}", state.peers.len());
                 println!("  Recent Messages: {}", state.recent_messages.len());
                 println!("  Active Operations: {:?}", state.busy_operations);
+
+                if !state.relays.is_empty() {
+                    println!("  Nostr Relays:");
+                    for relay in &state.relays {
+                        let latency = relay
+                            .average_latency_ms
+                            .map(|ms| format!("{}ms", ms))
+                            .unwrap_or_else(|| "-".to_string());
+                        let backoff = relay
+                            .backoff_remaining_ms
+                            .map(|ms| format!(" (backoff {}s)", ms.div_ceil(1000)))
+                            .unwrap_or_default();
+
+                        println!(
+                            "    {} - {} score {:.2} | ok {} / failed {} | avg {}{}",
+                            relay.url,
+                            relay.health,
+                            relay.score,
+                            relay.events_accepted,
+                            relay.events_failed,
+                            latency,
+                            backoff
+                        );
+                    }
+                }
                 println!();
             } else {
                 println!("Unable to retrieve system status");
@@ -398,36 +1112,84 @@ impl BitchatCliApp {
         Ok(())
     }
 
-    /// Print discovered peers with better formatting
+    /// Print announced peers by nickname, then peers that have not announced
     async fn print_peers(&self) -> BitchatResult<()> {
-        if let Some(terminal) = self.orchestrator.terminal_interface() {
-            if let Some(state) = terminal.get_state_snapshot() {
-                if state.peers.is_empty() {
-                    println!("No peers discovered yet. Try running 'discover' to find peers.");
-                } else {
-                    println!("Discovered Peers ({}):", state.peers.len());
-                    for (peer_id, peer_state) in &state.peers {
-                        let status_icon = match peer_state.status {
-                            ConnectionStatus::Connected => "[CONN]",
-                            ConnectionStatus::Connecting => "[PING]",
-                            ConnectionStatus::Discovering => "[DISC]",
-                            ConnectionStatus::Disconnected => "[DISC]",
-                            ConnectionStatus::Error => "[ERR]",
-                        };
+        let Some(terminal) = self.orchestrator.terminal_interface() else {
+            return Ok(());
+        };
+        terminal.handle_list_peers().await?;
+        let reply = wait_for_reply(terminal, |app_event| {
+            matches!(app_event, AppEvent::PeerListReport { .. })
+        })
+        .await?;
+        if reply.is_none() {
+            return Ok(());
+        }
 
-                        println!(
-                            "  {} {} - {:?} via {:?} ({} msgs)",
-                            status_icon,
-                            peer_id,
-                            peer_state.status,
-                            peer_state.transport.unwrap_or(ChannelTransportType::Ble),
-                            peer_state.message_count
-                        );
-                    }
-                }
-                println!();
+        let Some(state) = terminal.get_state_snapshot() else {
+            return Ok(());
+        };
+        if state.announced_peers.is_empty() && state.peers.is_empty() {
+            println!("No peers discovered yet. Try running 'discover' to find peers.");
+            return Ok(());
+        }
+
+        let status_icon = |status: Option<ConnectionStatus>| match status {
+            Some(ConnectionStatus::Connected) => "[CONN]",
+            Some(ConnectionStatus::Connecting) => "[PING]",
+            Some(ConnectionStatus::Error) => "[ERR]",
+            Some(ConnectionStatus::Discovering | ConnectionStatus::Disconnected) | None => "[DISC]",
+        };
+
+        if !state.announced_peers.is_empty() {
+            println!("Peers ({}):", state.announced_peers.len());
+            for peer in &state.announced_peers {
+                let status = state.peers.get(&peer.peer_id).map(|p| p.status);
+                let transports: Vec<String> = peer
+                    .transports
+                    .iter()
+                    .map(|transport| format!("{:?}", transport))
+                    .collect();
+                let rssi = peer
+                    .rssi
+                    .map(|rssi| format!(", {} dBm", rssi))
+                    .unwrap_or_default();
+                println!(
+                    "  {} @{} ({}) via {}{} - {}",
+                    status_icon(status),
+                    state.display_name(&peer.peer_id),
+                    peer.peer_id,
+                    transports.join(", "),
+                    rssi,
+                    &peer.fingerprint[..peer.fingerprint.len().min(16)]
+                );
             }
         }
+
+        let unannounced: Vec<_> = state
+            .peers
+            .iter()
+            .filter(|(peer_id, _)| {
+                !state
+                    .announced_peers
+                    .iter()
+                    .any(|peer| peer.peer_id == **peer_id)
+            })
+            .collect();
+        if !unannounced.is_empty() {
+            println!("Not yet announced ({}):", unannounced.len());
+            for (peer_id, peer_state) in unannounced {
+                println!(
+                    "  {} {} - {:?} via {:?} ({} msgs)",
+                    status_icon(Some(peer_state.status)),
+                    peer_id,
+                    peer_state.status,
+                    peer_state.transport.unwrap_or(ChannelTransportType::Ble),
+                    peer_state.message_count
+                );
+            }
+        }
+        println!();
         Ok(())
     }
 
@@ -438,12 +1200,28 @@ impl BitchatCliApp {
                 // Show the last 3 messages to keep the interface clean
                 let recent: Vec<_> = state.recent_messages.iter().rev().take(3).collect();
                 for message in recent.iter().rev() {
-                    let direction_icon = match message.direction {
-                        bitchat_cli::MessageDirection::Incoming => "<-",
-                        bitchat_cli::MessageDirection::Outgoing => "->",
+                    // Public messages have no recipient
+                    let direction_icon = match (message.to, message.direction) {
+                        (None, _) => "*",
+                        (Some(_), bitchat_cli::MessageDirection::Incoming) => "<-",
+                        (Some(_), bitchat_cli::MessageDirection::Outgoing) => "->",
                     };
 
-                    println!("{} {}: {}", direction_icon, message.from, message.content);
+                    // Messages that mention us or reply to us stand out
+                    let highlight = if message.highlighted { "! " } else { "" };
+                    let id = message
+                        .message_id
+                        .map(|id| format!(" [{}]", short_message_id(&id)))
+                        .unwrap_or_default();
+
+                    println!(
+                        "{}{} {}{}: {}",
+                        highlight,
+                        direction_icon,
+                        state.display_name(&message.from),
+                        id,
+                        message.content
+                    );
                 }
 
                 if !state.recent_messages.is_empty() {
@@ -464,6 +1242,53 @@ impl BitchatCliApp {
 // Helper Functions and Error Types
 // ----------------------------------------------------------------------------
 
+/// How long a command waits for Core Logic to answer
+const REPLY_TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_secs(2);
+
+/// Wait for the app event answering a command just sent
+///
+/// Core Logic refuses a command with `SystemError`. A refusal or no answer at
+/// all is printed and returns `None`.
+async fn wait_for_reply<F>(
+    terminal: &TerminalInterfaceTask,
+    is_reply: F,
+) -> BitchatResult<Option<AppEvent>>
+where
+    F: Fn(&AppEvent) -> bool,
+{
+    let reply = terminal
+        .wait_for_reply(REPLY_TIMEOUT, |app_event| {
+            is_reply(app_event) || matches!(app_event, AppEvent::SystemError { .. })
+        })
+        .await?;
+    match reply {
+        Some(AppEvent::SystemError { error }) => {
+            println!("Error: {}", error);
+            Ok(None)
+        }
+        None => {
+            println!("Core Logic did not answer in time");
+            Ok(None)
+        }
+        reply => Ok(reply),
+    }
+}
+
+/// Abbreviate a transfer ID to a prefix the runtime accepts in commands
+fn short_transfer_id(transfer_id: &str) -> &str {
+    transfer_id.get(..8).unwrap_or(transfer_id)
+}
+
+/// Abbreviate a group ID to a prefix the runtime accepts in commands
+fn short_group_id(group_id: &str) -> &str {
+    group_id.get(..8).unwrap_or(group_id)
+}
+
+/// Abbreviate a message ID to a prefix the runtime accepts in commands
+fn short_message_id(message_id: &MessageId) -> String {
+    message_id.to_hex()[..8].to_string()
+}
+
 /// Application-level errors
 #[derive(Debug, thiserror::Error)]
 pub enum ApplicationError {
@@ -489,8 +1314,11 @@ async fn run_interactive_mode(
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Create a simplified config for interactive mode
     let mut config = if automation_mode {
-        // For automation mode, use default config to avoid file parsing issues
-        CliAppConfig::default()
+        // For automation mode, use default config to avoid file parsing issues, and
+        // keep runs independent of any saved identity
+        let mut config = CliAppConfig::default();
+        config.identity.persist_identity = false;
+        config
     } else {
         CliAppConfig::load()?
     };
@@ -1099,6 +1927,36 @@ async fn handle_automation_command(
     Ok(())
 }
 
+// ----------------------------------------------------------------------------
+// Relay Mode Implementation
+// ----------------------------------------------------------------------------
+
+/// Run the embedded Nostr relay until interrupted
+async fn run_relay_mode(
+    listen: &str,
+    max_events: Option<usize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listen_addr: std::net::SocketAddr = listen
+        .parse()
+        .map_err(|e| format!("Invalid listen address {}: {}", listen, e))?;
+
+    let mut relay_config = RelayServerConfig::with_listen_addr(listen_addr);
+    if let Some(max_events) = max_events {
+        relay_config.max_stored_events = max_events;
+    }
+
+    let relay = RelayServer::bind(relay_config).await?;
+    println!("BitChat relay listening on {}", relay.url());
+    println!("Press Ctrl+C to stop");
+
+    tokio::select! {
+        result = relay.run() => result?,
+        _ = tokio::signal::ctrl_c() => println!("\nBitChat relay stopped"),
+    }
+
+    Ok(())
+}
+
 /// Run normal interactive mode
 async fn run_normal_mode(config: CliAppConfig) -> Result<(), Box<dyn std::error::Error>> {
     let mut app = BitchatCliApp::new(config).await?;
@@ -1137,6 +1995,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .value_name("RELAY_URL"),
                 ),
         )
+        .subcommand(
+            Command::new("relay")
+                .about("Run an embedded Nostr relay for local testing and private deployments")
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .short('l')
+                        .help("Address to listen on")
+                        .value_name("ADDR")
+                        .default_value("127.0.0.1:7777"),
+                )
+                .arg(
+                    Arg::new("max-events")
+                        .long("max-events")
+                        .help("Maximum number of stored events")
+                        .value_name("COUNT")
+                        .value_parser(clap::value_parser!(usize)),
+                ),
+        )
         .arg(
             Arg::new("config")
                 .long("config")
@@ -1216,6 +2093,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return run_interactive_mode(automation_mode, name, relay).await;
     }
 
+    // Handle relay subcommand
+    if let Some(relay_matches) = matches.subcommand_matches("relay") {
+        let listen = relay_matches
+            .get_one::<String>("listen")
+            .expect("listen has a default value");
+        let max_events = relay_matches.get_one::<usize>("max-events").copied();
+
+        return run_relay_mode(listen, max_events).await;
+    }
+
     // Load configuration with CLI overrides
     let config = if let Some(config_file) = matches.get_one::<String>("config") {
         // Load from specific file
@@ -3,14 +3,25 @@
 //! Implements terminal UI task with traditional concurrent patterns and cross-platform support
 //! Moved from bitchat-core to bitchat-cli crate for better architectural separation.
 
+use bitchat_core::channel::communication::{
+    FavoriteStatus, FileTransferInfo, GroupInfo, LinkedDeviceInfo, PeerInfo, RelayStatus,
+};
 use bitchat_core::{
-    internal::{AppEventReceiver, CommandSender, LogLevel, TaskId, TransportError},
+    internal::{AppEventReceiver, CommandSender, LogLevel, MessageId, TaskId, TransportError},
     AppEvent, BitchatError, BitchatResult, ChannelTransportType, Command, ConnectionStatus, PeerId,
 };
 use bitchat_runtime::logic::LoggerWrapper;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Fingerprint hex digits shown after a nickname shared by several peers
+///
+/// Four digits collide after a few hundred peers; eight make a collision
+/// between two peers on the same mesh implausible.
+const FINGERPRINT_SUFFIX_LEN: usize = 8;
 
 // ----------------------------------------------------------------------------
 // UI State Management
@@ -29,6 +40,22 @@ pub struct UIState {
     pub system_status: SystemStatus,
     /// UI busy indicator
     pub busy_operations: Vec<String>,
+    /// Latest Nostr relay statistics
+    pub relays: Vec<RelayStatus>,
+    /// Peers we favorite or that favorite us
+    pub favorites: Vec<FavoriteStatus>,
+    /// Our nickname, once one has been set
+    pub nickname: Option<String>,
+    /// Peers known from their announces, ordered by nickname
+    pub announced_peers: Vec<PeerInfo>,
+    /// File transfers in either direction, oldest first
+    pub transfers: Vec<TransferUIState>,
+    /// Groups we are in or were invited to
+    pub groups: Vec<GroupUIState>,
+    /// Devices linked to our identity, including this one
+    pub devices: Vec<LinkedDeviceInfo>,
+    /// Our latest link offer URI and when it expires
+    pub device_link_offer: Option<(String, u64)>,
 }
 
 /// Per-peer UI state
@@ -49,6 +76,121 @@ pub struct UIMessage {
     pub content: String,
     pub timestamp: u64,
     pub direction: MessageDirection,
+    /// Content-addressed ID, when known, for replying to the message
+    pub message_id: Option<MessageId>,
+    /// Whether the message mentions us or replies to one of our messages
+    pub highlighted: bool,
+}
+
+/// File transfer as shown in the UI
+#[derive(Debug, Clone)]
+pub struct TransferUIState {
+    pub info: FileTransferInfo,
+    /// Where a received file was saved
+    pub saved_to: Option<String>,
+    /// Why the transfer failed
+    pub error: Option<String>,
+}
+
+/// Group as shown in the UI
+#[derive(Debug, Clone)]
+pub struct GroupUIState {
+    pub info: GroupInfo,
+    /// Whether we are a member, rather than only invited
+    pub joined: bool,
+    /// Who invited us, if we know
+    pub invited_by: Option<PeerId>,
+    /// Recent group messages, oldest first
+    pub messages: Vec<UIMessage>,
+}
+
+impl UIState {
+    /// Name to show for a peer: its nickname, with a short fingerprint suffix
+    /// when another peer or we go by the same nickname
+    pub fn display_name(&self, peer_id: &PeerId) -> String {
+        let Some(peer) = self
+            .announced_peers
+            .iter()
+            .find(|peer| peer.peer_id == *peer_id)
+        else {
+            return peer_id.to_string();
+        };
+
+        let collides = self.nickname.as_deref() == Some(peer.nickname.as_str())
+            || self
+                .announced_peers
+                .iter()
+                .any(|other| other.peer_id != peer.peer_id && other.nickname == peer.nickname);
+        if collides {
+            let suffix = peer
+                .fingerprint
+                .get(..FINGERPRINT_SUFFIX_LEN)
+                .unwrap_or(&peer.fingerprint);
+            format!("{}#{}", peer.nickname, suffix)
+        } else {
+            peer.nickname.clone()
+        }
+    }
+
+    /// Record the latest snapshot of a group, keeping its messages
+    fn update_group(&mut self, info: GroupInfo, joined: bool, invited_by: Option<PeerId>) {
+        match self
+            .groups
+            .iter_mut()
+            .find(|group| group.info.group_id == info.group_id)
+        {
+            Some(group) => {
+                group.info = info;
+                group.joined = joined;
+                group.invited_by = invited_by.or(group.invited_by.take());
+            }
+            None => self.groups.push(GroupUIState {
+                info,
+                joined,
+                invited_by,
+                messages: Vec::new(),
+            }),
+        }
+    }
+
+    /// Add a message to a group's view, keeping the last 100
+    fn push_group_message(&mut self, group_id: &str, message: UIMessage) {
+        if let Some(group) = self
+            .groups
+            .iter_mut()
+            .find(|group| group.info.group_id == group_id)
+        {
+            group.messages.push(message);
+            if group.messages.len() > 100 {
+                group.messages.remove(0);
+            }
+        }
+    }
+
+    /// Record the latest snapshot of a transfer, keeping what earlier events told us
+    fn update_transfer(
+        &mut self,
+        info: FileTransferInfo,
+        saved_to: Option<String>,
+        error: Option<String>,
+    ) {
+        match self
+            .transfers
+            .iter_mut()
+            .find(|transfer| transfer.info.transfer_id == info.transfer_id)
+        {
+            Some(transfer) => {
+                transfer.info = info;
+                transfer.saved_to = saved_to.or(transfer.saved_to.take());
+                transfer.error = error.or(transfer.error.take());
+            }
+            None => self.transfers.push(TransferUIState {
+                info,
+                saved_to,
+                error,
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -75,6 +217,14 @@ impl Default for UIState {
             recent_messages: Vec::new(),
             system_status: SystemStatus::Starting,
             busy_operations: Vec::new(),
+            relays: Vec::new(),
+            favorites: Vec::new(),
+            nickname: None,
+            announced_peers: Vec::new(),
+            transfers: Vec::new(),
+            groups: Vec::new(),
+            devices: Vec::new(),
+            device_link_offer: None,
         }
     }
 }
@@ -89,8 +239,9 @@ pub struct TerminalInterfaceTask {
     state: Arc<Mutex<UIState>>,
     /// Channel for sending commands to Core Logic
     command_sender: CommandSender,
-    /// Channel for receiving app events from Core Logic
-    app_event_receiver: AppEventReceiver,
+    /// Channel for receiving app events from Core Logic, shared with callers
+    /// waiting for the reply to a command
+    app_event_receiver: tokio::sync::Mutex<AppEventReceiver>,
     /// Logger for task communication
     logger: LoggerWrapper,
     /// Task running state
@@ -115,7 +266,7 @@ impl TerminalInterfaceTask {
         Self {
             state: Arc::new(Mutex::new(initial_state)),
             command_sender,
-            app_event_receiver,
+            app_event_receiver: tokio::sync::Mutex::new(app_event_receiver),
             logger,
             running: false,
             our_peer_id,
@@ -147,7 +298,7 @@ impl TerminalInterfaceTask {
         while self.running {
             tokio::select! {
                 // Process app events from Core Logic
-                app_event = self.app_event_receiver.recv() => {
+                app_event = self.app_event_receiver.get_mut().recv() => {
                     match app_event {
                         Some(event) => {
                             if let Err(e) = self.process_app_event(event).await {
@@ -197,7 +348,7 @@ impl TerminalInterfaceTask {
     }
 
     /// Process app event from Core Logic with graceful degradation
-    async fn process_app_event(&mut self, app_event: AppEvent) -> BitchatResult<()> {
+    async fn process_app_event(&self, app_event: AppEvent) -> BitchatResult<()> {
         let mut state = self.state.lock().map_err(|_| {
             BitchatError::Transport(TransportError::InvalidConfiguration {
                 reason: "UI state lock poisoned".to_string(),
@@ -217,6 +368,8 @@ impl TerminalInterfaceTask {
                     content,
                     timestamp,
                     direction: MessageDirection::Incoming,
+                    message_id: None,
+                    highlighted: false,
                 };
                 state.recent_messages.push(ui_message);
 
@@ -244,6 +397,8 @@ impl TerminalInterfaceTask {
                     content,
                     timestamp,
                     direction: MessageDirection::Outgoing,
+                    message_id: None,
+                    highlighted: false,
                 };
                 state.recent_messages.push(ui_message);
 
@@ -258,6 +413,73 @@ impl TerminalInterfaceTask {
                 }
             }
 
+            AppEvent::BroadcastReceived {
+                message_id,
+                from,
+                content,
+                timestamp,
+                ..
+            } => {
+                let ui_message = UIMessage {
+                    from,
+                    to: None,
+                    content,
+                    timestamp,
+                    direction: MessageDirection::Incoming,
+                    message_id: Some(message_id),
+                    highlighted: false,
+                };
+                state.recent_messages.push(ui_message);
+
+                if let Some(peer_state) = state.peers.get_mut(&from) {
+                    peer_state.last_seen = Some(timestamp);
+                }
+
+                // Trim to last 100 messages
+                if state.recent_messages.len() > 100 {
+                    state.recent_messages.remove(0);
+                }
+            }
+
+            AppEvent::BroadcastSent {
+                message_id,
+                content,
+                timestamp,
+            } => {
+                let ui_message = UIMessage {
+                    from: self.our_peer_id,
+                    to: None,
+                    content,
+                    timestamp,
+                    direction: MessageDirection::Outgoing,
+                    message_id: Some(message_id),
+                    highlighted: false,
+                };
+                state.recent_messages.push(ui_message);
+
+                // Trim to last 100 messages
+                if state.recent_messages.len() > 100 {
+                    state.recent_messages.remove(0);
+                }
+            }
+
+            AppEvent::MessageHighlighted {
+                message_id,
+                from,
+                content,
+                ..
+            } => {
+                // Highlights follow the received event for the same message
+                if let Some(ui_message) = state.recent_messages.iter_mut().rev().find(|known| {
+                    known.from == from
+                        && known.content == content
+                        && known.message_id.is_none_or(|id| id == message_id)
+                }) {
+                    ui_message.message_id = Some(message_id);
+                    ui_message.highlighted = true;
+                }
+            }
+
             AppEvent::PeerStatusChanged {
                 peer_id,
                 status,
@@ -384,6 +606,139 @@ impl TerminalInterfaceTask {
                 tracing::info!("Internal state: {} sessions, {} stored messages, {} pending deliveries, uptime: {}ms", 
                     active_sessions, message_store_size, pending_deliveries, uptime_ms);
             }
+            AppEvent::RelayStatusReport { relays } => {
+                state.relays = relays;
+            }
+            AppEvent::FavoriteStatusChanged { status } => {
+                state
+                    .favorites
+                    .retain(|favorite| favorite.peer_id != status.peer_id);
+                if status.favorite || status.favorited_us {
+                    state.favorites.push(status);
+                }
+            }
+            AppEvent::FavoritesReport { favorites } => {
+                state.favorites = favorites;
+            }
+            AppEvent::PeerListReport { peers } => {
+                state.announced_peers = peers;
+            }
+            AppEvent::NicknameChanged { nickname } => {
+                state.nickname = Some(nickname);
+            }
+            AppEvent::PeerNicknameChanged {
+                peer_id, nickname, ..
+            } => {
+                if let Some(peer) = state
+                    .announced_peers
+                    .iter_mut()
+                    .find(|peer| peer.peer_id == peer_id)
+                {
+                    peer.nickname = nickname;
+                }
+            }
+            AppEvent::FileOffered { transfer, .. }
+            | AppEvent::FileTransferProgress { transfer } => {
+                state.update_transfer(transfer, None, None);
+            }
+            AppEvent::FileTransferCompleted { transfer, saved_to } => {
+                state.update_transfer(transfer, saved_to, None);
+            }
+            AppEvent::FileTransferFailed { transfer, reason } => {
+                state.update_transfer(transfer, None, Some(reason));
+            }
+            AppEvent::TransfersReport { transfers } => {
+                let known = std::mem::take(&mut state.transfers);
+                for info in transfers {
+                    let previous = known
+                        .iter()
+                        .find(|transfer| transfer.info.transfer_id == info.transfer_id);
+                    state.update_transfer(
+                        info,
+                        previous.and_then(|transfer| transfer.saved_to.clone()),
+                        previous.and_then(|transfer| transfer.error.clone()),
+                    );
+                }
+            }
+            AppEvent::GroupInvitation { group, from } => {
+                state.update_group(group, false, Some(from));
+            }
+            AppEvent::GroupMembershipChanged { group, left, .. } => {
+                if left.contains(&self.our_peer_id) {
+                    state
+                        .groups
+                        .retain(|known| known.info.group_id != group.group_id);
+                } else {
+                    state.update_group(group, true, None);
+                }
+            }
+            AppEvent::GroupMessageReceived {
+                group_id,
+                from,
+                content,
+                timestamp,
+            } => {
+                let message = UIMessage {
+                    from,
+                    to: None,
+                    content,
+                    timestamp,
+                    direction: MessageDirection::Incoming,
+                    message_id: None,
+                    highlighted: false,
+                };
+                state.push_group_message(&group_id, message);
+            }
+            AppEvent::GroupMessageSent {
+                group_id,
+                content,
+                timestamp,
+            } => {
+                let message = UIMessage {
+                    from: self.our_peer_id,
+                    to: None,
+                    content,
+                    timestamp,
+                    direction: MessageDirection::Outgoing,
+                    message_id: None,
+                    highlighted: false,
+                };
+                state.push_group_message(&group_id, message);
+            }
+            AppEvent::GroupsReport {
+                groups,
+                invitations,
+            } => {
+                let current: Vec<&str> = groups
+                    .iter()
+                    .chain(&invitations)
+                    .map(|group| group.group_id.as_str())
+                    .collect();
+                let known = std::mem::take(&mut state.groups);
+                state.groups = known
+                    .into_iter()
+                    .filter(|group| current.contains(&group.info.group_id.as_str()))
+                    .collect();
+                for group in groups {
+                    state.update_group(group, true, None);
+                }
+                for group in invitations {
+                    state.update_group(group, false, None);
+                }
+            }
+            AppEvent::DeviceLinkOffer { uri, expires_at } => {
+                state.device_link_offer = Some((uri, expires_at));
+            }
+            AppEvent::DeviceLinked { device } => {
+                state.device_link_offer = None;
+                state
+                    .devices
+                    .retain(|known| known.device_id != device.device_id);
+                state.devices.push(device);
+            }
+            AppEvent::DevicesReport { devices } => {
+                state.devices = devices;
+            }
         }
 
         Ok(())
@@ -479,6 +834,49 @@ impl TerminalInterfaceTask {
         self.state.try_lock().ok().map(|state| state.clone())
     }
 
+    /// Apply the app events Core Logic has sent so far
+    ///
+    /// For callers that drive the UI themselves instead of running the task.
+    pub async fn apply_pending_events(&self) -> BitchatResult<()> {
+        let mut receiver = self.app_event_receiver.lock().await;
+        while let Ok(app_event) = receiver.try_recv() {
+            self.process_app_event(app_event).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply app events as they arrive until the one answering a command
+    ///
+    /// `is_reply` picks that event out; it is applied like any other and
+    /// returned. Returns `None` if it doesn't arrive within `timeout`.
+    pub async fn wait_for_reply<F>(
+        &self,
+        timeout: Duration,
+        is_reply: F,
+    ) -> BitchatResult<Option<AppEvent>>
+    where
+        F: Fn(&AppEvent) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        let mut receiver = self.app_event_receiver.lock().await;
+        loop {
+            let app_event = match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(app_event)) => app_event,
+                Ok(None) => {
+                    return Err(BitchatError::Transport(TransportError::Shutdown {
+                        reason: "App event channel closed".to_string(),
+                    }))
+                }
+                Err(_) => return Ok(None),
+            };
+            let reply = is_reply(&app_event).then(|| app_event.clone());
+            self.process_app_event(app_event).await?;
+            if reply.is_some() {
+                return Ok(reply);
+            }
+        }
+    }
+
     /// Check if terminal interface task is running
     pub fn is_running(&self) -> bool {
         self.running
@@ -500,6 +898,25 @@ impl TerminalInterfaceTask {
         self.send_command(command).await
     }
 
+    /// Handle user action to send a public message to the mesh
+    pub async fn handle_send_broadcast(&self, content: String) -> BitchatResult<()> {
+        let command = Command::SendBroadcast { content };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to reply to a message
+    pub async fn handle_reply_to_message(
+        &self,
+        message_id: String,
+        content: String,
+    ) -> BitchatResult<()> {
+        let command = Command::ReplyToMessage {
+            message_id,
+            content,
+        };
+        self.send_command(command).await
+    }
+
     /// Handle user action to connect to peer
     pub async fn handle_connect_to_peer(&self, peer_id: PeerId) -> BitchatResult<()> {
         let command = Command::ConnectToPeer { peer_id };
@@ -524,6 +941,130 @@ impl TerminalInterfaceTask {
         self.send_command(command).await
     }
 
+    /// Handle user action to favorite or unfavorite a peer
+    pub async fn handle_set_favorite(&self, peer_id: PeerId, favorite: bool) -> BitchatResult<()> {
+        let command = Command::SetFavorite { peer_id, favorite };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to refresh the favorite list
+    pub async fn handle_list_favorites(&self) -> BitchatResult<()> {
+        self.send_command(Command::ListFavorites).await
+    }
+
+    /// Handle user action to refresh the announced peer list
+    pub async fn handle_list_peers(&self) -> BitchatResult<()> {
+        self.send_command(Command::ListPeers).await
+    }
+
+    /// Handle user action to change our nickname
+    pub async fn handle_set_nickname(&self, nickname: String) -> BitchatResult<()> {
+        self.send_command(Command::SetNickname { nickname }).await
+    }
+
+    /// Handle user action to offer a file to a peer
+    pub async fn handle_send_file(&self, recipient: PeerId, path: String) -> BitchatResult<()> {
+        let command = Command::SendFile { recipient, path };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to accept a file offer
+    pub async fn handle_accept_file(
+        &self,
+        transfer_id: String,
+        directory: Option<String>,
+    ) -> BitchatResult<()> {
+        let command = Command::AcceptFile {
+            transfer_id,
+            directory,
+        };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to reject a file offer
+    pub async fn handle_reject_file(&self, transfer_id: String) -> BitchatResult<()> {
+        let command = Command::RejectFile { transfer_id };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to refresh the transfer list
+    pub async fn handle_list_transfers(&self) -> BitchatResult<()> {
+        self.send_command(Command::ListTransfers).await
+    }
+
+    /// Handle user action to create a group
+    pub async fn handle_create_group(
+        &self,
+        name: String,
+        description: Option<String>,
+    ) -> BitchatResult<()> {
+        let command = Command::CreateGroup { name, description };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to invite a peer to a group
+    pub async fn handle_invite_to_group(
+        &self,
+        group_id: String,
+        peer_id: PeerId,
+    ) -> BitchatResult<()> {
+        let command = Command::InviteToGroup { group_id, peer_id };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to join a group we were invited to
+    pub async fn handle_join_group(&self, group_id: String) -> BitchatResult<()> {
+        let command = Command::JoinGroup { group_id };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to leave a group
+    pub async fn handle_leave_group(&self, group_id: String) -> BitchatResult<()> {
+        let command = Command::LeaveGroup { group_id };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to remove a member from a group
+    pub async fn handle_kick_from_group(
+        &self,
+        group_id: String,
+        peer_id: PeerId,
+    ) -> BitchatResult<()> {
+        let command = Command::KickFromGroup { group_id, peer_id };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to send a message to a group
+    pub async fn handle_send_group_message(
+        &self,
+        group_id: String,
+        content: String,
+    ) -> BitchatResult<()> {
+        let command = Command::SendGroupMessage { group_id, content };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to refresh the group list
+    pub async fn handle_list_groups(&self) -> BitchatResult<()> {
+        self.send_command(Command::ListGroups).await
+    }
+
+    /// Handle user action to offer linking another device to our identity
+    pub async fn handle_link_device(&self) -> BitchatResult<()> {
+        self.send_command(Command::LinkDevice).await
+    }
+
+    /// Handle user action to link this device using another device's offer
+    pub async fn handle_accept_device_link(&self, uri: String) -> BitchatResult<()> {
+        let command = Command::AcceptDeviceLink { uri };
+        self.send_command(command).await
+    }
+
+    /// Handle user action to refresh the device list
+    pub async fn handle_list_devices(&self) -> BitchatResult<()> {
+        self.send_command(Command::ListDevices).await
+    }
+
     /// Handle user action to shutdown
     pub async fn handle_shutdown(&self) -> BitchatResult<()> {
         let command = Command::Shutdown;
@@ -556,7 +1097,7 @@ mod tests {
         let logger = LoggerWrapper::Console(ConsoleLogger::new(LogLevel::Debug));
 
         let our_peer_id = create_test_peer_id(1);
-        let mut terminal_interface =
+        let terminal_interface =
             TerminalInterfaceTask::new(our_peer_id, command_sender, app_event_receiver, logger);
 
         // Test message received processing
@@ -581,6 +1122,153 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_highlighted_messages_are_marked() {
+        let config = ChannelConfig {
+            command_buffer_size: 10,
+            event_buffer_size: 10,
+            effect_buffer_size: 10,
+            app_event_buffer_size: 10,
+        };
+
+        let (command_sender, _command_receiver) = create_command_channel(&config);
+        let (_app_event_sender, app_event_receiver) = create_app_event_channel(&config);
+        let logger = LoggerWrapper::Console(ConsoleLogger::new(LogLevel::Debug));
+        let terminal_interface = TerminalInterfaceTask::new(
+            create_test_peer_id(1),
+            command_sender,
+            app_event_receiver,
+            logger,
+        );
+
+        let from = create_test_peer_id(2);
+        let message_id = MessageId::from_bytes([7; 32]);
+        for app_event in [
+            AppEvent::MessageReceived {
+                from,
+                content: "@me hello".to_string(),
+                timestamp: 12345,
+            },
+            AppEvent::MessageHighlighted {
+                message_id,
+                from,
+                content: "@me hello".to_string(),
+                reply_to: None,
+                mentioned: true,
+                timestamp: 12345,
+            },
+        ] {
+            terminal_interface
+                .process_app_event(app_event)
+                .await
+                .unwrap();
+        }
+
+        let state = terminal_interface.get_state_snapshot().unwrap();
+        assert_eq!(state.recent_messages.len(), 1);
+        assert!(state.recent_messages[0].highlighted);
+        assert_eq!(state.recent_messages[0].message_id, Some(message_id));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_reply_applies_events_up_to_the_reply() {
+        let config = ChannelConfig {
+            command_buffer_size: 10,
+            event_buffer_size: 10,
+            effect_buffer_size: 10,
+            app_event_buffer_size: 10,
+        };
+
+        let (command_sender, _command_receiver) = create_command_channel(&config);
+        let (app_event_sender, app_event_receiver) = create_app_event_channel(&config);
+        let logger = LoggerWrapper::Console(ConsoleLogger::new(LogLevel::Debug));
+        let terminal_interface = TerminalInterfaceTask::new(
+            create_test_peer_id(1),
+            command_sender,
+            app_event_receiver,
+            logger,
+        );
+
+        app_event_sender
+            .send(AppEvent::MessageReceived {
+                from: create_test_peer_id(2),
+                content: "Hello".to_string(),
+                timestamp: 12345,
+            })
+            .await
+            .unwrap();
+        app_event_sender
+            .send(AppEvent::NicknameChanged {
+                nickname: "alice".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let reply = terminal_interface
+            .wait_for_reply(Duration::from_secs(1), |app_event| {
+                matches!(app_event, AppEvent::NicknameChanged { .. })
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            reply,
+            Some(AppEvent::NicknameChanged { nickname }) if nickname == "alice"
+        ));
+
+        // Events before the reply are applied too
+        let state = terminal_interface.get_state_snapshot().unwrap();
+        assert_eq!(state.recent_messages.len(), 1);
+        assert_eq!(state.nickname.as_deref(), Some("alice"));
+
+        // A reply that never comes times out
+        let reply = terminal_interface
+            .wait_for_reply(Duration::from_millis(10), |_| true)
+            .await
+            .unwrap();
+        assert!(reply.is_none());
+    }
+
+    #[test]
+    fn test_display_names_disambiguate_shared_nicknames() {
+        let announced = |id: u8, nickname: &str, fingerprint: &str| PeerInfo {
+            peer_id: create_test_peer_id(id),
+            nickname: nickname.to_string(),
+            fingerprint: fingerprint.to_string(),
+            noise_public_key: [id; 32],
+            signing_public_key: [id; 32],
+            nostr_public_key: None,
+            transports: Vec::new(),
+            rssi: None,
+            last_seen: 0,
+        };
+        let mut state = UIState {
+            announced_peers: vec![
+                announced(2, "alice", "a1b2c3d4e5f6a7b8"),
+                announced(3, "alice", "a1b2f0e1d2c3b4a5"),
+                announced(4, "bob", "c9d0e1f2a3b4c5d6"),
+            ],
+            ..UIState::default()
+        };
+
+        assert_eq!(
+            state.display_name(&create_test_peer_id(2)),
+            "alice#a1b2c3d4"
+        );
+        assert_eq!(
+            state.display_name(&create_test_peer_id(3)),
+            "alice#a1b2f0e1"
+        );
+        assert_eq!(state.display_name(&create_test_peer_id(4)), "bob");
+        assert_eq!(
+            state.display_name(&create_test_peer_id(5)),
+            create_test_peer_id(5).to_string()
+        );
+
+        // A peer sharing our own nickname is disambiguated too
+        state.nickname = Some("bob".to_string());
+        assert_eq!(state.display_name(&create_test_peer_id(4)), "bob#c9d0e1f2");
+    }
+
     #[test]
     fn test_ui_state_default() {
         let state = UIState::default();
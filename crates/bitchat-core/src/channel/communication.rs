@@ -4,7 +4,7 @@
 //! All inter-task communication flows through these channel message types.
 
 use crate::protocol::message_store::MessageId;
-use crate::protocol::BitchatPacket;
+use crate::protocol::{BitchatPacket, PacketId};
 use crate::PeerId;
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +29,12 @@ cfg_if::cfg_if! {
 pub enum Command {
     /// Send a message to a specific peer
     SendMessage { recipient: PeerId, content: String },
+    /// Send a signed public message to everyone in the mesh
+    SendBroadcast { content: String },
+    /// Reply to a stored message, publicly or privately as the message was sent
+    ///
+    /// Message IDs may be abbreviated to any unique prefix.
+    ReplyToMessage { message_id: String, content: String },
     /// Initiate connection to a peer
     ConnectToPeer { peer_id: PeerId },
     /// Start peer discovery across all transports
@@ -53,6 +59,52 @@ pub enum Command {
     QueryDeliveryStatus { peer_id: PeerId },
     /// Query the complete internal state for debugging
     QueryInternalState,
+    /// Favorite or unfavorite a peer, notifying it over the Noise session
+    SetFavorite { peer_id: PeerId, favorite: bool },
+    /// List peers we favorite or that favorite us
+    ListFavorites,
+    /// List peers known from their announces on any transport
+    ListPeers,
+    /// Change our nickname and announce it on every transport
+    SetNickname { nickname: String },
+    /// Offer a file on disk to a peer over its Noise session
+    SendFile { recipient: PeerId, path: String },
+    /// Accept a file offered to us, saving it to `directory` or the download directory
+    ///
+    /// Transfer IDs may be abbreviated to any unique prefix.
+    AcceptFile {
+        transfer_id: String,
+        directory: Option<String>,
+    },
+    /// Reject a file offered to us
+    RejectFile { transfer_id: String },
+    /// List file transfers in either direction
+    ListTransfers,
+    /// Create a group with ourselves as its owner
+    CreateGroup {
+        name: String,
+        description: Option<String>,
+    },
+    /// Invite a peer to one of our groups
+    ///
+    /// Group IDs may be abbreviated to any unique prefix.
+    InviteToGroup { group_id: String, peer_id: PeerId },
+    /// Join a group we were invited to
+    JoinGroup { group_id: String },
+    /// Leave one of our groups
+    LeaveGroup { group_id: String },
+    /// Remove a member from a group we administer
+    KickFromGroup { group_id: String, peer_id: PeerId },
+    /// Send a message to every member of a group
+    SendGroupMessage { group_id: String, content: String },
+    /// List our groups and the groups we were invited to
+    ListGroups,
+    /// Create a one-time offer for linking another device to our identity
+    LinkDevice,
+    /// Link this device to the identity that created a link offer
+    AcceptDeviceLink { uri: String },
+    /// List the devices linked to our identity
+    ListDevices,
 }
 
 // ----------------------------------------------------------------------------
@@ -121,6 +173,13 @@ pub enum Event {
         reason: String,
         timestamp: u64,
     },
+    /// Per-relay health and publish statistics from the Nostr transport
+    RelayStatusUpdated {
+        relays: Vec<RelayStatus>,
+        timestamp: u64,
+    },
+    /// The Nostr transport's public key (32-byte x-only), to be advertised to peers
+    LocalNostrIdentity { public_key: [u8; 32] },
 }
 
 // ----------------------------------------------------------------------------
@@ -186,6 +245,20 @@ pub enum Effect {
         to_transport: TransportType,
         reason: String,
     },
+    /// A peer's Nostr public key was learned over an authenticated session
+    BindNostrIdentity {
+        peer_id: PeerId,
+        public_key: [u8; 32],
+    },
+    /// A Noise handshake with a peer completed and became its session
+    ///
+    /// `handshake` is the handshake packet that completed it, so a mesh transport
+    /// can bind the link that packet arrived over to the peer.
+    BindLinkIdentity {
+        peer_id: PeerId,
+        handshake: PacketId,
+        transport: TransportType,
+    },
 }
 
 // ----------------------------------------------------------------------------
@@ -208,6 +281,34 @@ pub enum AppEvent {
         content: String,
         timestamp: u64,
     },
+    /// A public message was received from the mesh
+    BroadcastReceived {
+        message_id: MessageId,
+        from: PeerId,
+        /// Nickname the sender signed the message under
+        nickname: String,
+        content: String,
+        timestamp: u64,
+    },
+    /// A public message was flooded to the mesh
+    BroadcastSent {
+        message_id: MessageId,
+        content: String,
+        timestamp: u64,
+    },
+    /// A received message mentions our nickname or replies to one of our messages
+    ///
+    /// Sent alongside the regular received event so the UI can highlight it.
+    MessageHighlighted {
+        message_id: MessageId,
+        from: PeerId,
+        content: String,
+        /// Message this one replies to
+        reply_to: Option<MessageId>,
+        /// Whether our nickname is mentioned
+        mentioned: bool,
+        timestamp: u64,
+    },
     /// Peer connection status changed
     PeerStatusChanged {
         peer_id: PeerId,
@@ -275,6 +376,74 @@ pub enum AppEvent {
         memory_usage_estimate: Option<usize>,
         uptime_ms: u64,
     },
+    /// Nostr relay statistics changed
+    RelayStatusReport { relays: Vec<RelayStatus> },
+    /// Our favorite status with a peer changed, locally or by its notification
+    FavoriteStatusChanged { status: FavoriteStatus },
+    /// Favorite list in response to ListFavorites command
+    FavoritesReport { favorites: Vec<FavoriteStatus> },
+    /// Announced peers in response to ListPeers command
+    PeerListReport { peers: Vec<PeerInfo> },
+    /// Our nickname changed and was announced to the mesh
+    NicknameChanged { nickname: String },
+    /// A known peer announced a different nickname
+    PeerNicknameChanged {
+        peer_id: PeerId,
+        previous: String,
+        nickname: String,
+    },
+    /// A peer offered us a file
+    FileOffered {
+        transfer: FileTransferInfo,
+        description: Option<String>,
+    },
+    /// More of a file has been sent or received
+    FileTransferProgress { transfer: FileTransferInfo },
+    /// A file transfer finished and the file hash was verified
+    FileTransferCompleted {
+        transfer: FileTransferInfo,
+        /// Where a received file was saved
+        saved_to: Option<String>,
+    },
+    /// A file transfer was rejected, cancelled or failed
+    FileTransferFailed {
+        transfer: FileTransferInfo,
+        reason: String,
+    },
+    /// File transfers in response to ListTransfers command
+    TransfersReport { transfers: Vec<FileTransferInfo> },
+    /// A member invited us to a group
+    GroupInvitation { group: GroupInfo, from: PeerId },
+    /// Members joined or left one of our groups, or we did
+    GroupMembershipChanged {
+        group: GroupInfo,
+        joined: Vec<PeerId>,
+        left: Vec<PeerId>,
+    },
+    /// A group message was received
+    GroupMessageReceived {
+        group_id: String,
+        from: PeerId,
+        content: String,
+        timestamp: u64,
+    },
+    /// A group message was sent to the other members
+    GroupMessageSent {
+        group_id: String,
+        content: String,
+        timestamp: u64,
+    },
+    /// Groups in response to ListGroups command
+    GroupsReport {
+        groups: Vec<GroupInfo>,
+        invitations: Vec<GroupInfo>,
+    },
+    /// A device link offer is ready to be shown, e.g. as a QR code
+    DeviceLinkOffer { uri: String, expires_at: u64 },
+    /// A device was linked to our identity, or we were linked to another device's
+    DeviceLinked { device: LinkedDeviceInfo },
+    /// Linked devices in response to ListDevices command
+    DevicesReport { devices: Vec<LinkedDeviceInfo> },
 }
 
 // ----------------------------------------------------------------------------
@@ -425,6 +594,206 @@ impl fmt::Display for EncryptionStatus {
     }
 }
 
+/// Relay health as reported by the Nostr transport
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RelayHealthStatus {
+    /// Relay is accepting events promptly
+    Healthy,
+    /// Relay accepts events but is slow
+    Degraded,
+    /// Relay is failing and is being backed off
+    Unhealthy,
+    /// Relay has not been used yet
+    Unknown,
+}
+
+impl fmt::Display for RelayHealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayHealthStatus::Healthy => write!(f, "Healthy"),
+            RelayHealthStatus::Degraded => write!(f, "Degraded"),
+            RelayHealthStatus::Unhealthy => write!(f, "Unhealthy"),
+            RelayHealthStatus::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Snapshot of a single Nostr relay's health and publish statistics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayStatus {
+    /// Relay URL
+    pub url: String,
+    /// Current health classification
+    pub health: RelayHealthStatus,
+    /// Selection score (0.0 = worst, 1.0 = best)
+    pub score: f64,
+    /// Events the relay acknowledged with `OK true`
+    pub events_accepted: u32,
+    /// Events rejected or timed out
+    pub events_failed: u32,
+    /// Average publish latency in milliseconds
+    pub average_latency_ms: Option<u64>,
+    /// Consecutive publish failures
+    pub consecutive_failures: u32,
+    /// Remaining backoff before the relay is selected again
+    pub backoff_remaining_ms: Option<u64>,
+}
+
+/// Favorite relationship with a single peer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FavoriteStatus {
+    /// Peer ID
+    pub peer_id: PeerId,
+    /// We favorite the peer
+    pub favorite: bool,
+    /// The peer told us it favorites us
+    pub favorited_us: bool,
+    /// Nostr public key the peer can be reached at when out of mesh range
+    pub nostr_public_key: Option<[u8; 32]>,
+}
+
+impl FavoriteStatus {
+    /// Whether both sides favorite each other
+    pub fn is_mutual(&self) -> bool {
+        self.favorite && self.favorited_us
+    }
+}
+
+/// Peer known from its signed announces
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerInfo {
+    /// Peer ID
+    pub peer_id: PeerId,
+    /// Nickname from the peer's latest announce
+    pub nickname: String,
+    /// Fingerprint of the peer's Noise static key
+    pub fingerprint: String,
+    /// Noise static public key
+    pub noise_public_key: [u8; 32],
+    /// Ed25519 key the peer signs announces with
+    pub signing_public_key: [u8; 32],
+    /// Nostr public key the peer advertised, if any
+    pub nostr_public_key: Option<[u8; 32]>,
+    /// Transports the peer has been heard on
+    pub transports: Vec<TransportType>,
+    /// Latest signal strength reported by a transport, in dBm
+    pub rssi: Option<i8>,
+    /// When the peer was last heard from, in milliseconds since the epoch
+    pub last_seen: u64,
+}
+
+/// Direction of a file transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferDirection {
+    Incoming,
+    Outgoing,
+}
+
+impl fmt::Display for TransferDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferDirection::Incoming => write!(f, "incoming"),
+            TransferDirection::Outgoing => write!(f, "outgoing"),
+        }
+    }
+}
+
+/// Lifecycle state of a file transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileTransferState {
+    Offered,
+    InProgress,
+    Completed,
+    Failed,
+    Cancelled,
+    Expired,
+}
+
+impl fmt::Display for FileTransferState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileTransferState::Offered => write!(f, "Offered"),
+            FileTransferState::InProgress => write!(f, "In progress"),
+            FileTransferState::Completed => write!(f, "Completed"),
+            FileTransferState::Failed => write!(f, "Failed"),
+            FileTransferState::Cancelled => write!(f, "Cancelled"),
+            FileTransferState::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+/// Snapshot of a single file transfer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTransferInfo {
+    /// Transfer ID
+    pub transfer_id: String,
+    /// The other side of the transfer
+    pub peer_id: PeerId,
+    /// Whether we are sending or receiving
+    pub direction: TransferDirection,
+    /// Offered file name
+    pub filename: String,
+    /// MIME type, if the sender gave one
+    pub mime_type: Option<String>,
+    /// File size in bytes
+    pub size: u64,
+    /// Bytes sent or received so far
+    pub bytes_transferred: u64,
+    /// Current state
+    pub state: FileTransferState,
+}
+
+impl FileTransferInfo {
+    /// Fraction of the file transferred (0.0 to 1.0)
+    pub fn progress(&self) -> f64 {
+        if self.size == 0 {
+            return 1.0;
+        }
+        self.bytes_transferred as f64 / self.size as f64
+    }
+}
+
+/// Member of a group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMemberInfo {
+    /// Peer ID
+    pub peer_id: PeerId,
+    /// Nickname the member joined with
+    pub nickname: String,
+    /// Whether the member may invite and remove others
+    pub admin: bool,
+}
+
+/// Snapshot of a group's metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupInfo {
+    /// Group ID
+    pub group_id: String,
+    /// Group name
+    pub name: String,
+    /// Group description
+    pub description: Option<String>,
+    /// Current members
+    pub members: Vec<GroupMemberInfo>,
+}
+
+/// Device linked to our identity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkedDeviceInfo {
+    /// Device ID
+    pub device_id: String,
+    /// Device display name
+    pub name: String,
+    /// Peer ID the device is reached at
+    pub peer_id: PeerId,
+    /// Whether this is the device we are running on
+    pub this_device: bool,
+    /// Whether the device has been heard from in the last few minutes
+    pub online: bool,
+    /// When the device was last heard from, in milliseconds since the epoch
+    pub last_seen: u64,
+}
+
 // ----------------------------------------------------------------------------
 // Tests
 // ----------------------------------------------------------------------------
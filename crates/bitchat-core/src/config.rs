@@ -913,6 +913,7 @@ pub struct TimingConfig {
     pub channel_inactivity_threshold_secs: u64,   // uiChannelInactivityThresholdSeconds: 540s
     pub late_insert_threshold_secs: f64,          // uiLateInsertThreshold: 15.0s
     pub late_insert_threshold_geo_secs: f64,      // uiLateInsertThresholdGeo: 0.0s
+    pub peer_stale_timeout_secs: u64,             // announced peers unheard for this long are evicted
 }
 
 impl Default for TimingConfig {
@@ -930,6 +931,7 @@ impl TimingConfig {
             channel_inactivity_threshold_secs: 540, // 9 minutes
             late_insert_threshold_secs: 15.0,
             late_insert_threshold_geo_secs: 0.0,
+            peer_stale_timeout_secs: 180, // 3 minutes
         }
     }
     
@@ -941,6 +943,7 @@ impl TimingConfig {
             channel_inactivity_threshold_secs: 30,
             late_insert_threshold_secs: 1.0,
             late_insert_threshold_geo_secs: 0.0,
+            peer_stale_timeout_secs: 10,
         }
     }
 }
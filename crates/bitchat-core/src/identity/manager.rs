@@ -26,6 +26,9 @@ const CACHE_SAVE_INTERVAL_MS: u64 = 2000; // 2 seconds
 /// Maximum age for ephemeral sessions before cleanup
 const MAX_EPHEMERAL_AGE_MS: u64 = 3_600_000; // 1 hour
 
+/// Storage key of our own identity signing key
+const LOCAL_IDENTITY_KEY_NAME: &str = "local_identity_key";
+
 /// Secure identity state manager implementing the three-layer identity model
 pub struct SecureIdentityStateManager {
     /// In-memory ephemeral identities (not persisted)
@@ -286,6 +289,50 @@ impl SecureIdentityStateManager {
         })
     }
 
+    /// Record whether a peer has told us it favorites us
+    ///
+    /// `notified_at` is the creation time of a signed notification, kept so that
+    /// older ones can be recognised as replays.
+    pub fn set_favorited_us(
+        &mut self,
+        fingerprint: &Fingerprint,
+        favorited_us: bool,
+        notified_at: Option<Timestamp>,
+    ) -> Result<()> {
+        self.update_social_identity(fingerprint, |social| {
+            social.set_favorited_us(favorited_us, notified_at);
+        })
+    }
+
+    /// Creation time of the latest signed favorite notification from a peer
+    pub fn favorite_notified_at(&self, fingerprint: &Fingerprint) -> Option<Timestamp> {
+        self.get_social_identity(fingerprint)
+            .and_then(|social| social.favorite_notified_at)
+    }
+
+    /// Whether we and the peer have favorited each other
+    pub fn is_mutual_favorite(&self, fingerprint: &Fingerprint) -> bool {
+        self.get_social_identity(fingerprint)
+            .is_some_and(|social| social.is_mutual_favorite())
+    }
+
+    /// Bind a Nostr public key to a peer's fingerprint
+    pub fn set_nostr_public_key(
+        &mut self,
+        fingerprint: &Fingerprint,
+        nostr_public_key: Option<[u8; 32]>,
+    ) -> Result<()> {
+        self.update_social_identity(fingerprint, |social| {
+            social.set_nostr_public_key(nostr_public_key);
+        })
+    }
+
+    /// Nostr public key bound to a fingerprint
+    pub fn nostr_public_key(&self, fingerprint: &Fingerprint) -> Option<[u8; 32]> {
+        self.get_social_identity(fingerprint)
+            .and_then(|social| social.nostr_public_key)
+    }
+
     /// Set blocked status
     pub fn set_blocked(&mut self, fingerprint: &Fingerprint, blocked: bool) -> Result<()> {
         self.update_social_identity(fingerprint, |social| {
@@ -321,6 +368,37 @@ impl SecureIdentityStateManager {
             .collect()
     }
 
+    // ----------------------------------------------------------------------------
+    // Local Identity
+    // ----------------------------------------------------------------------------
+
+    /// Our own identity signing key, if one has been stored
+    pub fn local_identity_key(&self) -> Result<Option<[u8; 32]>> {
+        let Some(encrypted) = self.storage.retrieve(LOCAL_IDENTITY_KEY_NAME)? else {
+            return Ok(None);
+        };
+        let key = self
+            .cache_encryption_key
+            .as_ref()
+            .ok_or_else(|| BitchatError::storage_error("Storage encryption key missing"))?;
+        let decrypted = self.decrypt_data(&encrypted, key)?;
+        let bytes: [u8; 32] = decrypted
+            .as_slice()
+            .try_into()
+            .map_err(|_| BitchatError::storage_error("Stored identity key has wrong length"))?;
+        Ok(Some(bytes))
+    }
+
+    /// Store our own identity signing key, replacing any stored one
+    pub fn store_local_identity_key(&mut self, identity_key: &[u8; 32]) -> Result<()> {
+        let key = self
+            .cache_encryption_key
+            .as_ref()
+            .ok_or_else(|| BitchatError::storage_error("Storage encryption key missing"))?;
+        let encrypted = self.encrypt_data(identity_key, key)?;
+        self.storage.store(LOCAL_IDENTITY_KEY_NAME, encrypted)
+    }
+
     // ----------------------------------------------------------------------------
     // Cleanup and Maintenance
     // ----------------------------------------------------------------------------
@@ -340,6 +418,14 @@ impl SecureIdentityStateManager {
         self.save_cache_if_needed()
     }
 
+    /// Write any unsaved identity changes to storage now
+    pub fn flush(&mut self) -> Result<()> {
+        if self.cache_dirty {
+            self.save_cache_now()?;
+        }
+        Ok(())
+    }
+
     /// Panic mode: clear all identity data
     pub fn panic_clear_all_data(&mut self) -> Result<()> {
         // Clear in-memory data
@@ -588,6 +674,44 @@ mod tests {
         assert_eq!(ephemeral.get_fingerprint(), Some(&fingerprint));
     }
 
+    #[test]
+    fn test_nostr_public_key_binding() {
+        let mut manager = SecureIdentityStateManager::new_for_testing();
+        let fingerprint = manager
+            .create_cryptographic_identity([5u8; 32], None)
+            .unwrap();
+        assert_eq!(manager.nostr_public_key(&fingerprint), None);
+
+        manager
+            .set_nostr_public_key(&fingerprint, Some([6u8; 32]))
+            .unwrap();
+        assert_eq!(manager.nostr_public_key(&fingerprint), Some([6u8; 32]));
+        assert_eq!(
+            manager
+                .get_social_identity(&fingerprint)
+                .unwrap()
+                .nostr_public_key,
+            Some([6u8; 32])
+        );
+    }
+
+    #[test]
+    fn test_mutual_favorite() {
+        let mut manager = SecureIdentityStateManager::new_for_testing();
+        let fingerprint = manager
+            .create_cryptographic_identity([7u8; 32], None)
+            .unwrap();
+
+        manager.set_favorite(&fingerprint, true).unwrap();
+        assert!(!manager.is_mutual_favorite(&fingerprint));
+
+        manager.set_favorited_us(&fingerprint, true, None).unwrap();
+        assert!(manager.is_mutual_favorite(&fingerprint));
+
+        manager.set_favorite(&fingerprint, false).unwrap();
+        assert!(!manager.is_mutual_favorite(&fingerprint));
+    }
+
     #[test]
     fn test_cleanup() {
         let mut manager = SecureIdentityStateManager::new_for_testing();
@@ -605,4 +729,39 @@ mod tests {
         // Note: cleanup only removes unverified identities
         assert!(stats.total_cryptographic_identities <= 1);
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_identities_survive_restart() {
+        use crate::identity::FileStorage;
+
+        let dir = std::env::temp_dir().join(alloc::format!(
+            "bitchat-identities-{}",
+            uuid::Uuid::new_v4()
+        ));
+        let open = || {
+            let storage = Box::new(FileStorage::open(&dir).unwrap());
+            SecureIdentityStateManager::with_storage(storage, StorageConfig::default()).unwrap()
+        };
+
+        let fingerprint = Fingerprint::new([7u8; 32]);
+        let mut manager = open();
+        assert!(manager.local_identity_key().unwrap().is_none());
+        manager.store_local_identity_key(&[9u8; 32]).unwrap();
+        manager
+            .set_nickname(&fingerprint, Some("alice".to_string()))
+            .unwrap();
+        manager.set_favorite(&fingerprint, true).unwrap();
+        manager.flush().unwrap();
+        drop(manager);
+
+        let manager = open();
+        assert_eq!(manager.local_identity_key().unwrap(), Some([9u8; 32]));
+        let social = manager.get_social_identity(&fingerprint).unwrap();
+        assert_eq!(social.claimed_nickname.as_deref(), Some("alice"));
+        assert!(social.is_favorite);
+        drop(manager);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -20,5 +20,7 @@ pub use crypto::CryptographicIdentity;
 pub use ephemeral::EphemeralIdentity;
 pub use manager::SecureIdentityStateManager;
 pub use social::SocialIdentity;
+#[cfg(feature = "std")]
+pub use storage::FileStorage;
 pub use storage::{create_default_storage, create_test_storage, SecureStorage, StorageConfig};
 pub use types::{HandshakeState, TrustLevel};
@@ -19,12 +19,21 @@ pub struct SocialIdentity {
     pub trust_level: TrustLevel,
     /// Is this peer a favorite?
     pub is_favorite: bool,
+    /// Has this peer told us it favorites us?
+    #[serde(default)]
+    pub favorited_us: bool,
+    /// When the latest signed favorite notification from this peer was created
+    #[serde(default)]
+    pub favorite_notified_at: Option<Timestamp>,
     /// Is this peer blocked?
     pub is_blocked: bool,
     /// Last interaction timestamp
     pub last_interaction: Timestamp,
     /// Notes about this peer
     pub notes: Option<String>,
+    /// Nostr public key (32-byte x-only) the peer advertised over an authenticated session
+    #[serde(default)]
+    pub nostr_public_key: Option<[u8; 32]>,
 }
 
 impl SocialIdentity {
@@ -36,9 +45,12 @@ impl SocialIdentity {
             local_petname: None,
             trust_level: TrustLevel::Unknown,
             is_favorite: false,
+            favorited_us: false,
+            favorite_notified_at: None,
             is_blocked: false,
             last_interaction: Timestamp::now(),
             notes: None,
+            nostr_public_key: None,
         }
     }
 
@@ -73,6 +85,26 @@ impl SocialIdentity {
         self.last_interaction = Timestamp::now();
     }
 
+    /// Record whether the peer favorites us, and when a signed notification said so
+    pub fn set_favorited_us(&mut self, favorited_us: bool, notified_at: Option<Timestamp>) {
+        self.favorited_us = favorited_us;
+        if notified_at.is_some() {
+            self.favorite_notified_at = notified_at;
+        }
+        self.last_interaction = Timestamp::now();
+    }
+
+    /// Whether we and the peer have favorited each other
+    pub fn is_mutual_favorite(&self) -> bool {
+        self.is_favorite && self.favorited_us
+    }
+
+    /// Set the peer's Nostr public key
+    pub fn set_nostr_public_key(&mut self, nostr_public_key: Option<[u8; 32]>) {
+        self.nostr_public_key = nostr_public_key;
+        self.last_interaction = Timestamp::now();
+    }
+
     /// Set blocked status
     pub fn set_blocked(&mut self, blocked: bool) {
         self.is_blocked = blocked;
@@ -168,6 +168,136 @@ impl SecureStorage for MemoryStorage {
     }
 }
 
+// ----------------------------------------------------------------------------
+// File Storage Implementation
+// ----------------------------------------------------------------------------
+
+/// Directory-backed storage for native platforms
+///
+/// Each key is kept in its own file inside the storage directory. Writes go to a
+/// temporary file that is renamed into place, so a crash never leaves a half
+/// written entry behind.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileStorage {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileStorage {
+    /// Open (creating if needed) a storage directory
+    pub fn open<P: Into<std::path::PathBuf>>(dir: P) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            BitchatError::storage_error(alloc::format!(
+                "Failed to create storage directory {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700));
+        }
+        Ok(Self { dir })
+    }
+
+    fn path_for(&self, key: &str) -> Result<std::path::PathBuf> {
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(BitchatError::storage_error(alloc::format!(
+                "Invalid storage key: {}",
+                key
+            )));
+        }
+        Ok(self.dir.join(key))
+    }
+}
+
+#[cfg(feature = "std")]
+impl SecureStorage for FileStorage {
+    fn store(&mut self, key: &str, data: Vec<u8>) -> Result<()> {
+        use std::io::Write;
+
+        let path = self.path_for(key)?;
+        let tmp = self.dir.join(alloc::format!(".{}.tmp", key));
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let write = || -> std::io::Result<()> {
+            let mut file = options.open(&tmp)?;
+            file.write_all(&data)?;
+            file.sync_all()?;
+            std::fs::rename(&tmp, &path)
+        };
+        write().map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            BitchatError::storage_error(alloc::format!("Failed to store {}: {}", key, e))
+        })
+    }
+
+    fn retrieve(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path_for(key)?;
+        match std::fs::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(BitchatError::storage_error(alloc::format!(
+                "Failed to read {}: {}",
+                key,
+                e
+            ))),
+        }
+    }
+
+    fn delete(&mut self, key: &str) -> Result<()> {
+        let path = self.path_for(key)?;
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(BitchatError::storage_error(alloc::format!(
+                "Failed to delete {}: {}",
+                key,
+                e
+            ))),
+        }
+    }
+
+    fn list_keys(&self) -> Result<Vec<String>> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| {
+            BitchatError::storage_error(alloc::format!("Failed to list storage: {}", e))
+        })?;
+        let mut keys: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn clear_all(&mut self) -> Result<()> {
+        for key in self.list_keys()? {
+            self.delete(&key)?;
+        }
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        self.dir.is_dir()
+    }
+}
+
 // ----------------------------------------------------------------------------
 // Factory Functions
 // ----------------------------------------------------------------------------
@@ -266,4 +396,35 @@ mod tests {
         storage.clear_all().unwrap();
         assert!(storage.list_keys().unwrap().is_empty());
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_file_storage_persists_across_instances() {
+        let dir =
+            std::env::temp_dir().join(alloc::format!("bitchat-storage-{}", uuid::Uuid::new_v4()));
+
+        let mut storage = FileStorage::open(&dir).unwrap();
+        assert!(storage.is_available());
+        storage.store("identity_cache", vec![1, 2, 3]).unwrap();
+        storage.store("other_key", vec![4]).unwrap();
+        assert!(storage.store("../escape", vec![5]).is_err());
+        drop(storage);
+
+        let mut reopened = FileStorage::open(&dir).unwrap();
+        assert_eq!(
+            reopened.retrieve("identity_cache").unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            reopened.list_keys().unwrap(),
+            vec!["identity_cache".to_string(), "other_key".to_string()]
+        );
+
+        reopened.delete("other_key").unwrap();
+        assert!(reopened.retrieve("other_key").unwrap().is_none());
+        reopened.clear_all().unwrap();
+        assert!(reopened.list_keys().unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
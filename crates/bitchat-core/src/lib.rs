@@ -279,15 +279,24 @@ pub use protocol::{
     DeviceHeartbeat,
     DeviceId,
     DeviceInfo,
+    DeviceLinkGrant,
+    DeviceLinkMessage,
+    DeviceLinkOffer,
+    DeviceLinkRequest,
+    DeviceLinker,
     DeviceStatus,
     DeviceType,
+    // Group messaging sender keys
+    EncryptedGroupMessage,
     // File transfer
     FileAccept,
     FileChunk,
     FileComplete,
     FileHash,
+    FileHasher,
     FileMetadata,
     FileOffer,
+    FileResume,
     FileTransferId,
     FileTransferManager,
     FileTransferMessage,
@@ -299,20 +308,29 @@ pub use protocol::{
     GroupJoin,
     GroupKick,
     GroupLeave,
+    GroupLog,
+    GroupLogSync,
     GroupManager,
     GroupMember,
     GroupMessage,
     GroupMessagingMessage,
     GroupMetadata,
+    GroupOperation,
+    GroupOperationId,
     GroupRole,
     GroupSettings,
     GroupUpdate,
     ImplementationInfo,
+    LinkedDevice,
+    LinkedIdentity,
     MessageRef,
     MultiDeviceSessionManager,
     NegotiationStatus,
     ProtocolVersion,
     RejectionReason,
+    SenderKeyDistribution,
+    SenderKeyStore,
+    SignedGroupOperation,
     SessionStatus,
     SessionSyncMessage,
     SessionSyncRequest,
@@ -351,6 +369,8 @@ pub mod internal {
     pub use crate::errors::{
         CryptographicError, FragmentationError, PacketError, SessionError, TransportError,
     };
+    #[cfg(feature = "std")]
+    pub use crate::identity::FileStorage;
     pub use crate::identity::{
         storage::{create_default_storage, create_test_storage, SecureStorage, StorageConfig},
         CryptographicIdentity, EphemeralIdentity, HandshakeState, IdentityCache,
@@ -22,6 +22,8 @@ pub struct AnnouncePayload {
     pub signing_public_key: [u8; 32],
     /// Optional list of directly connected neighbors (full 32-byte keys for mesh routing)
     pub direct_neighbors: Option<Vec<[u8; 32]>>,
+    /// Optional Nostr public key the peer can be reached at when out of mesh range
+    pub nostr_public_key: Option<[u8; 32]>,
 }
 
 impl AnnouncePayload {
@@ -42,9 +44,16 @@ impl AnnouncePayload {
             noise_public_key,
             signing_public_key,
             direct_neighbors,
+            nostr_public_key: None,
         })
     }
 
+    /// Advertise the Nostr public key (32-byte x-only) this peer receives messages at
+    pub fn with_nostr_public_key(mut self, nostr_public_key: [u8; 32]) -> Self {
+        self.nostr_public_key = Some(nostr_public_key);
+        self
+    }
+
     /// Encode the announce payload using TLV format
     pub fn encode(&self) -> Result<Vec<u8>, BitchatError> {
         let mut codec = TlvCodec::new();
@@ -60,6 +69,10 @@ impl AnnouncePayload {
             codec.add_entry(TlvEntry::direct_neighbors(&neighbor_refs));
         }
 
+        if let Some(ref nostr_public_key) = self.nostr_public_key {
+            codec.add_entry(TlvEntry::nostr_public_key(nostr_public_key));
+        }
+
         Ok(codec.encode())
     }
 
@@ -103,11 +116,17 @@ impl AnnouncePayload {
             .map(|entry| entry.as_neighbors())
             .transpose()?;
 
+        let nostr_public_key = codec
+            .find_entry(TlvType::NostrPublicKey)
+            .map(|entry| entry.as_key())
+            .transpose()?;
+
         Ok(AnnouncePayload {
             nickname,
             noise_public_key,
             signing_public_key,
             direct_neighbors,
+            nostr_public_key,
         })
     }
 }
@@ -131,6 +150,24 @@ impl BitchatPacket {
             direct_neighbors,
         )?;
 
+        Self::create_announce_from_payload(sender_id, &payload, identity_keypair, timestamp)
+    }
+
+    /// Create a signed announce packet from a prepared payload
+    ///
+    /// The payload's signing key must belong to `identity_keypair`.
+    pub fn create_announce_from_payload(
+        sender_id: PeerId,
+        payload: &AnnouncePayload,
+        identity_keypair: &IdentityKeyPair,
+        timestamp: Timestamp,
+    ) -> Result<Self, BitchatError> {
+        if payload.signing_public_key != identity_keypair.public_key_bytes() {
+            return Err(BitchatError::invalid_packet(
+                "Announce signing key does not match identity key",
+            ));
+        }
+
         // Encode the payload
         let encoded_payload = payload.encode()?;
 
@@ -174,6 +211,40 @@ impl BitchatPacket {
 
         Ok(payload)
     }
+
+    /// Create a signed leave packet telling peers we are going away
+    ///
+    /// A leave carries no payload; peers check it against the signing key from
+    /// our last announce.
+    pub fn create_leave(
+        sender_id: PeerId,
+        identity_keypair: &IdentityKeyPair,
+        timestamp: Timestamp,
+    ) -> Result<Self, BitchatError> {
+        let mut packet = BitchatPacket::new(
+            MessageType::Leave,
+            sender_id,
+            None, // Broadcast message - no specific recipient
+            timestamp,
+            Vec::new(),
+            PacketFlags::NONE,
+        )?;
+        packet.sign(identity_keypair)?;
+
+        Ok(packet)
+    }
+
+    /// Verify a leave packet against the sender's announced signing key
+    pub fn verify_leave(&self, signing_public_key: &[u8; 32]) -> Result<(), BitchatError> {
+        if self.message_type() != MessageType::Leave {
+            return Err(BitchatError::invalid_packet(format!(
+                "Expected leave packet, got {:?}",
+                self.message_type()
+            )));
+        }
+
+        self.verify_signature(signing_public_key)
+    }
 }
 
 /// Peer information extracted from an announce packet
@@ -189,6 +260,8 @@ pub struct DiscoveredPeer {
     pub signing_public_key: [u8; 32],
     /// Directly connected neighbors (if provided)
     pub direct_neighbors: Option<Vec<[u8; 32]>>,
+    /// Nostr public key the peer advertised (if any)
+    pub nostr_public_key: Option<[u8; 32]>,
     /// When this peer was last seen
     pub last_seen: Timestamp,
 }
@@ -211,14 +284,23 @@ impl DiscoveredPeer {
             ));
         }
 
-        Ok(DiscoveredPeer {
+        Ok(Self::from_payload(peer_id, payload, timestamp))
+    }
+
+    /// Create a discovered peer from an already verified announce payload
+    ///
+    /// The caller vouches that `peer_id` belongs to the announced Noise key, e.g.
+    /// because a Noise session authenticated that key for the peer.
+    pub fn from_payload(peer_id: PeerId, payload: AnnouncePayload, timestamp: Timestamp) -> Self {
+        DiscoveredPeer {
             peer_id,
             nickname: payload.nickname,
             noise_public_key: payload.noise_public_key,
             signing_public_key: payload.signing_public_key,
             direct_neighbors: payload.direct_neighbors,
+            nostr_public_key: payload.nostr_public_key,
             last_seen: timestamp,
-        })
+        }
     }
 
     /// Get the cryptographic fingerprint of this peer
@@ -343,6 +425,46 @@ mod tests {
         assert_eq!(discovered.last_seen, timestamp);
     }
 
+    #[test]
+    fn test_announce_carries_nostr_public_key() {
+        let (noise_keypair, identity_keypair) = create_test_keypairs();
+        let peer_id = PeerId::from_noise_key(&noise_keypair.public_key_bytes());
+        let timestamp = Timestamp::now();
+
+        let payload = AnnouncePayload::new(
+            "nostr_peer".to_string(),
+            noise_keypair.public_key_bytes(),
+            identity_keypair.public_key_bytes(),
+            None,
+        )
+        .unwrap()
+        .with_nostr_public_key([0x77; 32]);
+        assert_eq!(
+            AnnouncePayload::decode(&payload.encode().unwrap()).unwrap(),
+            payload
+        );
+
+        let packet = BitchatPacket::create_announce_from_payload(
+            peer_id,
+            &payload,
+            &identity_keypair,
+            timestamp,
+        )
+        .unwrap();
+        let discovered = DiscoveredPeer::from_announce_packet(&packet, timestamp).unwrap();
+        assert_eq!(discovered.nostr_public_key, Some([0x77; 32]));
+
+        // Announces signed with a key other than the advertised one are refused
+        let (_, other_identity) = create_test_keypairs();
+        assert!(BitchatPacket::create_announce_from_payload(
+            peer_id,
+            &payload,
+            &other_identity,
+            timestamp
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_nickname_too_long() {
         let (noise_keypair, identity_keypair) = create_test_keypairs();
@@ -381,4 +503,37 @@ mod tests {
         assert_eq!(fingerprint.len(), 64); // 32 bytes * 2 hex chars
         assert!(fingerprint.chars().all(|c| c.is_ascii_hexdigit()));
     }
+
+    #[test]
+    fn test_leave_is_verified_against_announced_key() {
+        let (noise_keypair, identity_keypair) = create_test_keypairs();
+        let peer_id = PeerId::from_noise_key(&noise_keypair.public_key_bytes());
+        let signing_key = identity_keypair.public_key_bytes();
+
+        let leave =
+            BitchatPacket::create_leave(peer_id, &identity_keypair, Timestamp::now()).unwrap();
+        assert_eq!(leave.message_type(), MessageType::Leave);
+        assert!(leave.payload.is_empty());
+        assert!(leave.verify_leave(&signing_key).is_ok());
+
+        // Anyone else signing a leave for the peer is rejected
+        let forged = BitchatPacket::create_leave(
+            peer_id,
+            &IdentityKeyPair::generate().unwrap(),
+            Timestamp::now(),
+        )
+        .unwrap();
+        assert!(forged.verify_leave(&signing_key).is_err());
+
+        let announce = BitchatPacket::create_announce(
+            peer_id,
+            "not_leaving".to_string(),
+            noise_keypair.public_key_bytes(),
+            &identity_keypair,
+            None,
+            Timestamp::now(),
+        )
+        .unwrap();
+        assert!(announce.verify_leave(&signing_key).is_err());
+    }
 }
@@ -0,0 +1,184 @@
+//! Scheduling of our own announce broadcasts
+//!
+//! Peers learn our nickname and keys from announces, and forget us once they
+//! stop hearing them. The schedule follows the canonical BLE parameters: an
+//! isolated node announces every `announce_interval_secs`, a connected node
+//! every `connected_announce_base_secs_*` with random jitter so neighbours do
+//! not announce in lockstep, and a change to our nickname or neighbour set
+//! announces right away. No two announces go out within
+//! `announce_min_interval` of each other.
+
+use rand_core::RngCore;
+
+use crate::config::BleTransportConfig;
+use crate::types::Timestamp;
+
+/// Base interval and maximum jitter, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cadence {
+    base_ms: u64,
+    jitter_ms: u64,
+}
+
+impl Cadence {
+    fn from_secs(base_secs: f64, jitter_secs: f64) -> Self {
+        Self {
+            base_ms: secs_to_ms(base_secs),
+            jitter_ms: secs_to_ms(jitter_secs),
+        }
+    }
+
+    /// Pick an interval within `base ± jitter`
+    fn interval_ms<R: RngCore>(&self, rng: &mut R) -> u64 {
+        if self.jitter_ms == 0 {
+            return self.base_ms;
+        }
+        let offset = rng.next_u64() % (2 * self.jitter_ms + 1);
+        (self.base_ms + offset).saturating_sub(self.jitter_ms)
+    }
+}
+
+/// When to broadcast our next announce
+#[derive(Debug, Clone)]
+pub struct AnnounceSchedule {
+    min_interval_ms: u64,
+    isolated: Cadence,
+    sparse: Cadence,
+    dense: Cadence,
+    high_degree_threshold: usize,
+    last_sent: Option<Timestamp>,
+    /// `None` while an announce is due as soon as the minimum interval allows
+    next_due: Option<Timestamp>,
+}
+
+impl Default for AnnounceSchedule {
+    fn default() -> Self {
+        Self::new(&BleTransportConfig::default())
+    }
+}
+
+impl AnnounceSchedule {
+    /// Create a schedule per `config`, with the first announce due immediately
+    pub fn new(config: &BleTransportConfig) -> Self {
+        Self {
+            min_interval_ms: config.announce_min_interval.as_millis() as u64,
+            isolated: Cadence::from_secs(
+                config.announce_interval_secs,
+                config.connected_announce_jitter_sparse,
+            ),
+            sparse: Cadence::from_secs(
+                config.connected_announce_base_secs_sparse,
+                config.connected_announce_jitter_sparse,
+            ),
+            dense: Cadence::from_secs(
+                config.connected_announce_base_secs_dense,
+                config.connected_announce_jitter_dense,
+            ),
+            high_degree_threshold: config.high_degree_threshold,
+            last_sent: None,
+            next_due: None,
+        }
+    }
+
+    /// Announce again as soon as the minimum interval allows
+    ///
+    /// Called when our nickname or our set of neighbours changes.
+    pub fn announce_soon(&mut self) {
+        self.next_due = None;
+    }
+
+    /// Whether an announce should go out at `now`, given how many neighbours we have
+    ///
+    /// Returning `true` records the announce as sent and schedules the next one.
+    pub fn poll<R: RngCore>(&mut self, now: Timestamp, neighbours: usize, rng: &mut R) -> bool {
+        if self.next_due.is_some_and(|due| now < due) {
+            return false;
+        }
+        if self.last_sent.is_some_and(|sent| {
+            now.as_millis().saturating_sub(sent.as_millis()) < self.min_interval_ms
+        }) {
+            return false;
+        }
+
+        let cadence = match neighbours {
+            0 => self.isolated,
+            n if n >= self.high_degree_threshold => self.dense,
+            _ => self.sparse,
+        };
+        let interval_ms = cadence.interval_ms(rng).max(self.min_interval_ms);
+        self.last_sent = Some(now);
+        self.next_due = Some(now + interval_ms);
+        true
+    }
+
+    /// When the next announce is due, if one is scheduled
+    pub fn next_due(&self) -> Option<Timestamp> {
+        self.next_due
+    }
+}
+
+fn secs_to_ms(secs: f64) -> u64 {
+    (secs.max(0.0) * 1000.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use rand_chacha::ChaCha8Rng;
+    use rand_core::SeedableRng;
+
+    fn schedule() -> AnnounceSchedule {
+        AnnounceSchedule::new(&BleTransportConfig::canonical())
+    }
+
+    #[test]
+    fn test_intervals_are_jittered_around_the_neighbourhood_cadence() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut schedule = schedule();
+        let mut now = Timestamp::new(1_000);
+        assert!(
+            schedule.poll(now, 0, &mut rng),
+            "first announce is immediate"
+        );
+
+        let mut intervals = Vec::new();
+        for _ in 0..20 {
+            let due = schedule.next_due().unwrap();
+            assert!(!schedule.poll(Timestamp::new(due.as_millis() - 1), 2, &mut rng));
+            now = due;
+            assert!(schedule.poll(now, 2, &mut rng));
+            intervals.push(schedule.next_due().unwrap().as_millis() - now.as_millis());
+        }
+
+        // Sparse neighbourhoods announce every 15s ± 4s
+        assert!(intervals.iter().all(|ms| (11_000..=19_000).contains(ms)));
+        assert!(
+            intervals.iter().any(|ms| *ms != intervals[0]),
+            "intervals are jittered"
+        );
+
+        // Isolated nodes announce every 4s ± 4s, but never faster than the minimum
+        assert!(schedule.poll(schedule.next_due().unwrap(), 0, &mut rng));
+        let next = schedule.next_due().unwrap();
+        assert!(next.as_millis() - schedule.last_sent.unwrap().as_millis() >= 1_000);
+    }
+
+    #[test]
+    fn test_changes_announce_soon_but_respect_the_minimum_interval() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut config = BleTransportConfig::canonical();
+        config.announce_min_interval = Duration::from_secs(2);
+        let mut schedule = AnnounceSchedule::new(&config);
+
+        assert!(schedule.poll(Timestamp::new(10_000), 3, &mut rng));
+        assert!(!schedule.poll(Timestamp::new(10_500), 3, &mut rng));
+
+        schedule.announce_soon();
+        assert!(!schedule.poll(Timestamp::new(11_000), 4, &mut rng));
+        assert!(schedule.poll(Timestamp::new(12_000), 4, &mut rng));
+        assert!(schedule.next_due().unwrap() > Timestamp::new(20_000));
+    }
+}
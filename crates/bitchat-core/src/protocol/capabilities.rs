@@ -13,7 +13,7 @@ use alloc::{
 use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
-use crate::protocol::message::NoisePayloadType;
+use crate::protocol::message::{NoisePayload, NoisePayloadType};
 use crate::types::{PeerId, Timestamp};
 use crate::{BitchatError, Result};
 
@@ -153,6 +153,54 @@ impl CapabilityId {
     pub fn nostr_transport() -> Self {
         Self("transport.nostr.v1".to_string())
     }
+
+    /// Signed favorite notification capability
+    pub fn favorite_notifications() -> Self {
+        Self("favorite_notifications.v1".to_string())
+    }
+
+    /// Reply references in private messages, carried under the `HAS_REPLY_TO` flag
+    pub fn message_replies() -> Self {
+        Self("message_replies.v1".to_string())
+    }
+
+    /// Capability a peer must have negotiated before it is sent a Noise payload type
+    ///
+    /// Returns `None` for payloads every implementation understands, including the
+    /// negotiation messages themselves.
+    pub fn required_for(payload_type: NoisePayloadType) -> Option<Self> {
+        match payload_type {
+            NoisePayloadType::PrivateMessage
+            | NoisePayloadType::ReadReceipt
+            | NoisePayloadType::Delivered
+            | NoisePayloadType::VerifyChallenge
+            | NoisePayloadType::VerifyResponse
+            | NoisePayloadType::VersionHello
+            | NoisePayloadType::VersionAck
+            | NoisePayloadType::CapabilityRejection => None,
+            NoisePayloadType::FavoriteNotification => Some(Self::favorite_notifications()),
+            NoisePayloadType::FileOffer
+            | NoisePayloadType::FileAccept
+            | NoisePayloadType::FileChunk
+            | NoisePayloadType::FileComplete
+            | NoisePayloadType::FileResume => Some(Self::file_transfer()),
+            NoisePayloadType::GroupCreate
+            | NoisePayloadType::GroupInvite
+            | NoisePayloadType::GroupJoin
+            | NoisePayloadType::GroupLeave
+            | NoisePayloadType::GroupMessage
+            | NoisePayloadType::GroupUpdate
+            | NoisePayloadType::GroupKick
+            | NoisePayloadType::GroupSenderKey
+            | NoisePayloadType::GroupSync => Some(Self::group_messaging()),
+            NoisePayloadType::DeviceAnnouncement
+            | NoisePayloadType::SessionSyncRequest
+            | NoisePayloadType::SessionSyncResponse
+            | NoisePayloadType::DeviceHeartbeat
+            | NoisePayloadType::DeviceLinkRequest
+            | NoisePayloadType::DeviceLinkGrant => Some(Self::multi_device_sync()),
+        }
+    }
 }
 
 impl core::fmt::Display for CapabilityId {
@@ -298,6 +346,8 @@ impl VersionHello {
             Capability::new(CapabilityId::multi_device_sync(), "1.0".to_string()),
             Capability::new(CapabilityId::ble_transport(), "1.0".to_string()),
             Capability::new(CapabilityId::nostr_transport(), "1.0".to_string()),
+            Capability::new(CapabilityId::favorite_notifications(), "1.0".to_string()),
+            Capability::new(CapabilityId::message_replies(), "1.0".to_string()),
         ];
 
         Self::new(
@@ -474,6 +524,7 @@ impl CapabilityManager {
             .insert(hello.peer_id, mutual_capabilities.clone());
         self.peer_versions
             .insert(hello.peer_id, negotiated_version.clone());
+        self.mark_as_negotiated(&hello.peer_id);
 
         Ok(VersionAck::new(
             self.local_peer_id,
@@ -490,6 +541,7 @@ impl CapabilityManager {
             .insert(ack.peer_id, ack.mutual_capabilities.clone());
         self.peer_versions
             .insert(ack.peer_id, ack.negotiated_version.clone());
+        self.mark_as_negotiated(&ack.peer_id);
         Ok(())
     }
 
@@ -593,6 +645,12 @@ impl CapabilityManager {
 
     // Private helper methods
 
+    /// A peer that answered is neither pending nor legacy, even if it answered late
+    fn mark_as_negotiated(&mut self, peer_id: &PeerId) {
+        self.hello_timeouts.remove(peer_id);
+        self.legacy_peers.remove(peer_id);
+    }
+
     fn negotiate_version(&self, peer_versions: &[ProtocolVersion]) -> Result<ProtocolVersion> {
         let our_version = ProtocolVersion::current();
 
@@ -668,6 +726,48 @@ impl CapabilityMessage {
             CapabilityMessage::Rejection(_) => NoisePayloadType::CapabilityRejection,
         }
     }
+
+    /// Whether a Noise payload type carries a capability negotiation message
+    pub fn is_negotiation_payload(payload_type: NoisePayloadType) -> bool {
+        matches!(
+            payload_type,
+            NoisePayloadType::VersionHello
+                | NoisePayloadType::VersionAck
+                | NoisePayloadType::CapabilityRejection
+        )
+    }
+
+    /// Encode as a Noise payload for transmission inside an encrypted session
+    pub fn to_noise_payload(&self) -> Result<NoisePayload> {
+        let data = match self {
+            CapabilityMessage::Hello(hello) => bincode::serialize(hello),
+            CapabilityMessage::Ack(ack) => bincode::serialize(ack),
+            CapabilityMessage::Rejection(rejection) => bincode::serialize(rejection),
+        }
+        .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        Ok(NoisePayload::new(self.payload_type(), data))
+    }
+
+    /// Decode from a Noise payload
+    pub fn from_noise_payload(payload: &NoisePayload) -> Result<Self> {
+        let message = match payload.payload_type {
+            NoisePayloadType::VersionHello => {
+                bincode::deserialize(&payload.data).map(CapabilityMessage::Hello)
+            }
+            NoisePayloadType::VersionAck => {
+                bincode::deserialize(&payload.data).map(CapabilityMessage::Ack)
+            }
+            NoisePayloadType::CapabilityRejection => {
+                bincode::deserialize(&payload.data).map(CapabilityMessage::Rejection)
+            }
+            _ => {
+                return Err(BitchatError::invalid_packet(
+                    "Not a capability negotiation payload",
+                ))
+            }
+        };
+        message.map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -838,6 +938,101 @@ mod tests {
         assert!(!manager.peer_supports_capability(&timeout_peer, &CapabilityId::file_transfer()));
     }
 
+    #[test]
+    fn test_answered_hello_does_not_time_out() {
+        let peer_id = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let remote_peer = PeerId::new([9, 10, 11, 12, 13, 14, 15, 16]);
+        let mut manager = CapabilityManager::new(peer_id).unwrap();
+        let mut remote = CapabilityManager::new(remote_peer).unwrap();
+
+        // Both hellos are overdue by the time the answers are processed
+        manager.track_hello_sent(remote_peer);
+        manager
+            .hello_timeouts
+            .insert(remote_peer, Timestamp::new(0));
+        remote.track_hello_sent(peer_id);
+        remote.hello_timeouts.insert(peer_id, Timestamp::new(0));
+
+        let ack = manager
+            .process_hello(&remote.create_hello().unwrap())
+            .unwrap();
+        remote.process_ack(&ack).unwrap();
+
+        assert!(manager.check_hello_timeouts().is_empty());
+        assert!(remote.check_hello_timeouts().is_empty());
+        assert_eq!(
+            manager.get_negotiation_status(&remote_peer),
+            NegotiationStatus::Negotiated
+        );
+        assert_eq!(
+            remote.get_negotiation_status(&peer_id),
+            NegotiationStatus::Negotiated
+        );
+        assert!(manager.should_use_feature(&remote_peer, &CapabilityId::file_transfer()));
+
+        // A late hello from a peer already treated as legacy supersedes the fallback
+        manager.mark_as_legacy_peer(remote_peer);
+        manager
+            .process_hello(&remote.create_hello().unwrap())
+            .unwrap();
+        assert_eq!(
+            manager.get_negotiation_status(&remote_peer),
+            NegotiationStatus::Negotiated
+        );
+    }
+
+    #[test]
+    fn test_capability_message_noise_payload_round_trip() {
+        let peer_id = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let hello = CapabilityMessage::Hello(VersionHello::standard(peer_id).unwrap());
+        let rejection = CapabilityMessage::Rejection(CapabilityRejection::new(
+            peer_id,
+            RejectionReason::IncompatibleVersion,
+            None,
+        ));
+
+        for message in [hello, rejection] {
+            let payload = message.to_noise_payload().unwrap();
+            assert_eq!(payload.payload_type, message.payload_type());
+            let decoded = NoisePayload::from_binary(&payload.to_binary()).unwrap();
+            assert_eq!(
+                CapabilityMessage::from_noise_payload(&decoded).unwrap(),
+                message
+            );
+        }
+
+        let other = NoisePayload::new(NoisePayloadType::PrivateMessage, Vec::new());
+        assert!(CapabilityMessage::from_noise_payload(&other).is_err());
+    }
+
+    #[test]
+    fn test_required_capability_for_payload_types() {
+        assert_eq!(
+            CapabilityId::required_for(NoisePayloadType::PrivateMessage),
+            None
+        );
+        assert_eq!(
+            CapabilityId::required_for(NoisePayloadType::VersionHello),
+            None
+        );
+        assert_eq!(
+            CapabilityId::required_for(NoisePayloadType::FileChunk),
+            Some(CapabilityId::file_transfer())
+        );
+        assert_eq!(
+            CapabilityId::required_for(NoisePayloadType::GroupMessage),
+            Some(CapabilityId::group_messaging())
+        );
+        assert_eq!(
+            CapabilityId::required_for(NoisePayloadType::SessionSyncRequest),
+            Some(CapabilityId::multi_device_sync())
+        );
+        assert_eq!(
+            CapabilityId::required_for(NoisePayloadType::FavoriteNotification),
+            Some(CapabilityId::favorite_notifications())
+        );
+    }
+
     #[test]
     fn test_graceful_degradation_with_canonical_implementation() {
         let peer_id = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
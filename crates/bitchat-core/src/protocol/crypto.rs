@@ -5,6 +5,7 @@
 //! and fingerprint generation.
 
 use alloc::{vec, vec::Vec};
+use curve25519_dalek::montgomery::MontgomeryPoint;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand_core::{CryptoRng, RngCore};
 use sha2::{Digest, Sha256};
@@ -116,29 +117,18 @@ impl NoiseKeyPair {
     pub fn generate_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
         let mut private_key = [0u8; 32];
         rng.fill_bytes(&mut private_key);
-
-        // Use curve25519-dalek for key derivation
-        use curve25519_dalek::constants::X25519_BASEPOINT;
-        use curve25519_dalek::scalar::Scalar;
-
-        let scalar = Scalar::from_bytes_mod_order(private_key);
-        let point = scalar * X25519_BASEPOINT;
-        let public_key = point.to_bytes();
-
-        Self {
-            private_key,
-            public_key,
-        }
+        Self::from_bytes(&private_key)
     }
 
     /// Create from raw private key bytes
+    ///
+    /// The public key is the X25519 function of the private key (RFC 7748), which
+    /// clamps the scalar before multiplying. Snow derives the static key it sends
+    /// in the handshake the same way, so fingerprints computed from this key
+    /// match the ones peers compute from our handshake. Reducing the unclamped
+    /// bytes mod the group order instead gives a different point for most keys.
     pub fn from_bytes(private_key: &[u8; 32]) -> Self {
-        use curve25519_dalek::constants::X25519_BASEPOINT;
-        use curve25519_dalek::scalar::Scalar;
-
-        let scalar = Scalar::from_bytes_mod_order(*private_key);
-        let point = scalar * X25519_BASEPOINT;
-        let public_key = point.to_bytes();
+        let public_key = MontgomeryPoint::mul_base_clamped(*private_key).to_bytes();
 
         Self {
             private_key: *private_key,
@@ -369,4 +359,43 @@ mod tests {
 
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
+
+    #[test]
+    fn test_noise_public_key_matches_handshake_static() {
+        let alice_key = NoiseKeyPair::generate();
+        let bob_key = NoiseKeyPair::generate();
+
+        let mut alice = NoiseHandshake::initiator(&alice_key).unwrap();
+        let mut bob = NoiseHandshake::responder(&bob_key).unwrap();
+        bob.read_message(&alice.write_message(b"").unwrap())
+            .unwrap();
+        alice
+            .read_message(&bob.write_message(b"").unwrap())
+            .unwrap();
+        bob.read_message(&alice.write_message(b"").unwrap())
+            .unwrap();
+
+        assert_eq!(alice.get_remote_static(), Some(bob_key.public_key_bytes()));
+        assert_eq!(bob.get_remote_static(), Some(alice_key.public_key_bytes()));
+    }
+    #[test]
+    fn test_noise_public_key_rfc7748_vectors() {
+        // RFC 7748 section 6.1
+        let vectors = [
+            (
+                "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+                "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a",
+            ),
+            (
+                "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+                "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f",
+            ),
+        ];
+
+        for (private_hex, public_hex) in vectors {
+            let private_key: [u8; 32] = hex::decode(private_hex).unwrap().try_into().unwrap();
+            let key_pair = NoiseKeyPair::from_bytes(&private_key);
+            assert_eq!(hex::encode(key_pair.public_key_bytes()), public_hex);
+        }
+    }
 }
@@ -0,0 +1,549 @@
+//! Linking additional devices to a BitChat identity
+//!
+//! A device that already holds an identity links a new one by showing a one-time
+//! [`DeviceLinkOffer`], usually as a QR code of its `bitchat://link` URI. The offer
+//! names the existing device's peer ID and carries a random secret. The new device
+//! proves it scanned the offer with a request keyed by that secret, sent over an
+//! ordinary Noise session, and is answered with the identity key and the list of
+//! linked devices, sealed under a key derived from the same secret. An offer is
+//! used up by the first valid request, so a leaked URI is worthless once the
+//! intended device has been linked.
+//!
+//! Linked devices then find each other with signed
+//! [`DeviceAnnouncement`](crate::protocol::session_sync::DeviceAnnouncement)s and
+//! keep their histories in step through the
+//! [`MultiDeviceSessionManager`](crate::protocol::session_sync::MultiDeviceSessionManager).
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305,
+};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::protocol::message::{NoisePayload, NoisePayloadType};
+use crate::protocol::session_sync::{DeviceId, DeviceInfo};
+use crate::types::{PeerId, Timestamp};
+use crate::{BitchatError, Result};
+
+// ----------------------------------------------------------------------------
+// Constants
+// ----------------------------------------------------------------------------
+
+/// How long a link offer can be used, in milliseconds (10 minutes)
+pub const LINK_OFFER_TTL: u64 = 10 * 60 * 1000;
+
+/// Maximum number of link offers outstanding at once
+pub const MAX_LINK_OFFERS: usize = 4;
+
+/// Version of the link offer format
+const LINK_OFFER_VERSION: u8 = 1;
+
+/// URI scheme and path link offers are shared under
+const LINK_URI_PREFIX: &str = "bitchat://link?data=";
+
+/// Domain separation for the request proof
+const PROOF_LABEL: &[u8] = b"bitchat-device-link-proof";
+
+/// Domain separation for the key the identity is sealed under
+const SEAL_LABEL: &[u8] = b"bitchat-device-link-seal";
+
+// ----------------------------------------------------------------------------
+// Link Offers
+// ----------------------------------------------------------------------------
+
+/// One-time offer to link a new device to this identity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceLinkOffer {
+    /// Format version for future compatibility
+    pub version: u8,
+    /// Device the new device sends its link request to
+    pub peer_id: PeerId,
+    /// Identifies the offer in the link request
+    pub link_id: String,
+    /// Secret the link request and grant are keyed with
+    pub secret: [u8; 32],
+    /// When the offer stops being accepted
+    pub expires_at: Timestamp,
+}
+
+impl DeviceLinkOffer {
+    /// Create an offer with a fresh secret
+    pub fn generate(peer_id: PeerId, now: Timestamp) -> Self {
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        Self {
+            version: LINK_OFFER_VERSION,
+            peer_id,
+            link_id: uuid::Uuid::new_v4().to_string(),
+            secret,
+            expires_at: now + LINK_OFFER_TTL,
+        }
+    }
+
+    /// Encode as a URI for sharing, e.g. in a QR code
+    pub fn to_uri(&self) -> Result<String> {
+        let serialized = bincode::serialize(self)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+
+        use base64::{engine::general_purpose, Engine as _};
+        Ok(format!(
+            "{}{}",
+            LINK_URI_PREFIX,
+            general_purpose::URL_SAFE_NO_PAD.encode(serialized)
+        ))
+    }
+
+    /// Parse an offer from its URI
+    pub fn from_uri(uri: &str) -> Result<Self> {
+        let encoded = uri
+            .trim()
+            .strip_prefix(LINK_URI_PREFIX)
+            .ok_or_else(|| BitchatError::invalid_packet("Invalid device link URI format"))?;
+
+        use base64::{engine::general_purpose, Engine as _};
+        let serialized = general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| BitchatError::invalid_packet(format!("Invalid base64: {}", e)))?;
+        let offer: Self = bincode::deserialize(&serialized)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+
+        if offer.version != LINK_OFFER_VERSION {
+            return Err(BitchatError::invalid_packet(
+                "Unsupported device link offer version",
+            ));
+        }
+        Ok(offer)
+    }
+
+    /// Whether the offer can no longer be used
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Proof that a device requesting the link scanned this offer
+    fn proof(&self, device_id: &DeviceId) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(PROOF_LABEL);
+        hasher.update(self.secret);
+        hasher.update(self.link_id.as_bytes());
+        hasher.update(device_id.as_str().as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Key the granted identity is sealed under
+    fn seal_key(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(SEAL_LABEL);
+        hasher.update(self.secret);
+        hasher.finalize().into()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Link Messages
+// ----------------------------------------------------------------------------
+
+/// A device already linked to the identity, and the peer ID it is reached at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkedDevice {
+    /// Peer ID of the device's own Noise key
+    pub peer_id: PeerId,
+    /// Device details
+    pub device: DeviceInfo,
+}
+
+/// Identity material handed to a newly linked device
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkedIdentity {
+    /// Ed25519 identity key the linked devices share
+    pub identity_key: [u8; 32],
+    /// Nickname the identity announces itself with
+    pub nickname: String,
+    /// Devices already linked, including the one granting the link
+    pub devices: Vec<LinkedDevice>,
+}
+
+impl core::fmt::Debug for LinkedIdentity {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LinkedIdentity")
+            .field("identity_key", &"<redacted>")
+            .field("nickname", &self.nickname)
+            .field("devices", &self.devices)
+            .finish()
+    }
+}
+
+/// Request from a new device to be linked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceLinkRequest {
+    /// Offer being answered
+    pub link_id: String,
+    /// The device asking to be linked
+    pub device: DeviceInfo,
+    /// Proof the device knows the offer's secret
+    pub proof: [u8; 32],
+}
+
+/// Answer to a link request, carrying the identity sealed under the offer's secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceLinkGrant {
+    /// Offer the request answered
+    pub link_id: String,
+    /// Serialized [`LinkedIdentity`], encrypted with ChaCha20-Poly1305
+    pub sealed_identity: Vec<u8>,
+}
+
+/// All device linking message types
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceLinkMessage {
+    /// Link request from the new device
+    Request(DeviceLinkRequest),
+    /// Link grant from the existing device
+    Grant(DeviceLinkGrant),
+}
+
+impl DeviceLinkMessage {
+    /// Get the corresponding NoisePayloadType for this message
+    pub fn payload_type(&self) -> NoisePayloadType {
+        match self {
+            DeviceLinkMessage::Request(_) => NoisePayloadType::DeviceLinkRequest,
+            DeviceLinkMessage::Grant(_) => NoisePayloadType::DeviceLinkGrant,
+        }
+    }
+
+    /// Encode as a Noise payload
+    pub fn to_noise_payload(&self) -> Result<NoisePayload> {
+        let data = match self {
+            DeviceLinkMessage::Request(request) => bincode::serialize(request),
+            DeviceLinkMessage::Grant(grant) => bincode::serialize(grant),
+        }
+        .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        Ok(NoisePayload::new(self.payload_type(), data))
+    }
+
+    /// Decode from a Noise payload
+    pub fn from_noise_payload(payload: &NoisePayload) -> Result<Self> {
+        let message = match payload.payload_type {
+            NoisePayloadType::DeviceLinkRequest => {
+                bincode::deserialize(&payload.data).map(DeviceLinkMessage::Request)
+            }
+            NoisePayloadType::DeviceLinkGrant => {
+                bincode::deserialize(&payload.data).map(DeviceLinkMessage::Grant)
+            }
+            _ => return Err(BitchatError::invalid_packet("Not a device link payload")),
+        };
+        message.map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Device Linker
+// ----------------------------------------------------------------------------
+
+/// Tracks the link offers this device has shown and the ones it has scanned
+#[derive(Debug, Clone, Default)]
+pub struct DeviceLinker {
+    /// Offers shown by this device that no request has used yet
+    offers: BTreeMap<String, DeviceLinkOffer>,
+    /// Offers scanned by this device, by the peer that showed them
+    scanned: BTreeMap<PeerId, DeviceLinkOffer>,
+}
+
+impl DeviceLinker {
+    /// Create a linker with no outstanding offers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an offer for linking a new device to this one
+    ///
+    /// Expired offers are dropped, and the oldest is replaced once
+    /// [`MAX_LINK_OFFERS`] are outstanding.
+    pub fn create_offer(&mut self, peer_id: PeerId, now: Timestamp) -> DeviceLinkOffer {
+        self.offers.retain(|_, offer| !offer.is_expired(now));
+        if self.offers.len() >= MAX_LINK_OFFERS {
+            if let Some(oldest) = self
+                .offers
+                .values()
+                .min_by_key(|offer| offer.expires_at)
+                .map(|offer| offer.link_id.clone())
+            {
+                self.offers.remove(&oldest);
+            }
+        }
+
+        let offer = DeviceLinkOffer::generate(peer_id, now);
+        self.offers.insert(offer.link_id.clone(), offer.clone());
+        offer
+    }
+
+    /// Answer an offer this device scanned
+    ///
+    /// The grant for it is only accepted from the peer that showed the offer.
+    pub fn request(
+        &mut self,
+        offer: DeviceLinkOffer,
+        device: DeviceInfo,
+        now: Timestamp,
+    ) -> Result<DeviceLinkRequest> {
+        if offer.is_expired(now) {
+            return Err(BitchatError::invalid_packet(
+                "Device link offer has expired",
+            ));
+        }
+        let request = DeviceLinkRequest {
+            link_id: offer.link_id.clone(),
+            proof: offer.proof(&device.device_id),
+            device,
+        };
+        self.scanned.insert(offer.peer_id, offer);
+        Ok(request)
+    }
+
+    /// Check a link request against our offers and seal our identity for it
+    ///
+    /// The offer is used up by the first request that proves knowledge of its
+    /// secret; requests with a wrong proof leave it in place.
+    pub fn grant(
+        &mut self,
+        request: &DeviceLinkRequest,
+        identity: &LinkedIdentity,
+        now: Timestamp,
+    ) -> Result<DeviceLinkGrant> {
+        let offer = self
+            .offers
+            .get(&request.link_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Unknown device link offer"))?;
+        if offer.is_expired(now) {
+            self.offers.remove(&request.link_id);
+            return Err(BitchatError::invalid_packet(
+                "Device link offer has expired",
+            ));
+        }
+        if !constant_time_eq(&offer.proof(&request.device.device_id), &request.proof) {
+            return Err(BitchatError::invalid_packet("Invalid device link proof"));
+        }
+
+        let plaintext = bincode::serialize(identity)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        let sealed_identity = seal(&offer.seal_key(), request.link_id.as_bytes(), &plaintext)?;
+        self.offers.remove(&request.link_id);
+
+        Ok(DeviceLinkGrant {
+            link_id: request.link_id.clone(),
+            sealed_identity,
+        })
+    }
+
+    /// Open the grant answering the offer we scanned from `from`
+    pub fn open_grant(&mut self, from: PeerId, grant: &DeviceLinkGrant) -> Result<LinkedIdentity> {
+        let offer = self
+            .scanned
+            .get(&from)
+            .filter(|offer| offer.link_id == grant.link_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Unrequested device link grant"))?;
+
+        let plaintext = open(
+            &offer.seal_key(),
+            grant.link_id.as_bytes(),
+            &grant.sealed_identity,
+        )?;
+        let identity = bincode::deserialize(&plaintext)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        self.scanned.remove(&from);
+        Ok(identity)
+    }
+
+    /// Whether we are waiting for a grant from a peer
+    pub fn is_awaiting_grant(&self, peer_id: &PeerId) -> bool {
+        self.scanned.contains_key(peer_id)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Helpers
+// ----------------------------------------------------------------------------
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// Each offer's secret seals at most one grant, so a fixed nonce is safe
+const NONCE: [u8; 12] = [0u8; 12];
+
+fn seal(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    ChaCha20Poly1305::new(key.into())
+        .encrypt(
+            (&NONCE).into(),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| BitchatError::encryption_error("Device link encryption failed"))
+}
+
+fn open(key: &[u8; 32], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(
+            (&NONCE).into(),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| BitchatError::decryption_error("Device link decryption failed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::session_sync::DeviceType;
+    use crate::types::Fingerprint;
+
+    fn primary() -> PeerId {
+        PeerId::new([1; 8])
+    }
+
+    fn new_device(id: u8) -> DeviceInfo {
+        DeviceInfo::new(
+            DeviceId::from_string(format!("device-{}", id)),
+            format!("Device {}", id),
+            DeviceType::Desktop,
+            Fingerprint::new([id; 32]),
+        )
+    }
+
+    fn identity() -> LinkedIdentity {
+        LinkedIdentity {
+            identity_key: [7; 32],
+            nickname: "alice".to_string(),
+            devices: vec![LinkedDevice {
+                peer_id: primary(),
+                device: new_device(1),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_offer_uri_roundtrip() {
+        let offer = DeviceLinkOffer::generate(primary(), Timestamp::new(1_000));
+        let uri = offer.to_uri().unwrap();
+        assert!(uri.starts_with("bitchat://link?data="));
+        assert_eq!(DeviceLinkOffer::from_uri(&uri).unwrap(), offer);
+
+        assert!(DeviceLinkOffer::from_uri("bitchat://verify?data=AAAA").is_err());
+        assert!(DeviceLinkOffer::from_uri("bitchat://link?data=not base64").is_err());
+    }
+
+    #[test]
+    fn test_link_grants_identity_once() {
+        let now = Timestamp::new(1_000);
+        let mut existing = DeviceLinker::new();
+        let mut new = DeviceLinker::new();
+
+        let offer = existing.create_offer(primary(), now);
+        let scanned = DeviceLinkOffer::from_uri(&offer.to_uri().unwrap()).unwrap();
+        let request = new.request(scanned, new_device(2), now).unwrap();
+        assert!(new.is_awaiting_grant(&primary()));
+
+        let grant = existing.grant(&request, &identity(), now).unwrap();
+        assert_ne!(
+            grant.sealed_identity,
+            bincode::serialize(&identity()).unwrap()
+        );
+        assert_eq!(new.open_grant(primary(), &grant).unwrap(), identity());
+        assert!(!new.is_awaiting_grant(&primary()));
+
+        // The offer is used up, and the grant cannot be opened twice
+        assert!(existing.grant(&request, &identity(), now).is_err());
+        assert!(new.open_grant(primary(), &grant).is_err());
+    }
+
+    #[test]
+    fn test_link_rejects_bad_proofs_and_expired_offers() {
+        let now = Timestamp::new(1_000);
+        let mut existing = DeviceLinker::new();
+        let offer = existing.create_offer(primary(), now);
+
+        // A device that only knows the link ID cannot forge the proof
+        let forged = DeviceLinkRequest {
+            link_id: offer.link_id.clone(),
+            device: new_device(3),
+            proof: [0; 32],
+        };
+        assert!(existing.grant(&forged, &identity(), now).is_err());
+
+        // Nor can a proof be moved to another device ID
+        let mut request = DeviceLinker::new()
+            .request(offer.clone(), new_device(2), now)
+            .unwrap();
+        request.device = new_device(3);
+        assert!(existing.grant(&request, &identity(), now).is_err());
+
+        let later = now + LINK_OFFER_TTL;
+        assert!(DeviceLinker::new()
+            .request(offer.clone(), new_device(2), later)
+            .is_err());
+        let request = DeviceLinker::new()
+            .request(offer, new_device(2), now)
+            .unwrap();
+        assert!(existing.grant(&request, &identity(), later).is_err());
+    }
+
+    #[test]
+    fn test_grant_only_opens_from_offering_peer() {
+        let now = Timestamp::new(1_000);
+        let mut existing = DeviceLinker::new();
+        let mut new = DeviceLinker::new();
+
+        let offer = existing.create_offer(primary(), now);
+        let request = new.request(offer, new_device(2), now).unwrap();
+        let grant = existing.grant(&request, &identity(), now).unwrap();
+
+        assert!(new.open_grant(PeerId::new([9; 8]), &grant).is_err());
+        let mut tampered = grant.clone();
+        tampered.sealed_identity[0] ^= 1;
+        assert!(new.open_grant(primary(), &tampered).is_err());
+        assert!(new.open_grant(primary(), &grant).is_ok());
+    }
+
+    #[test]
+    fn test_offers_are_bounded() {
+        let now = Timestamp::new(1_000);
+        let mut linker = DeviceLinker::new();
+        let first = linker.create_offer(primary(), now);
+        for _ in 0..MAX_LINK_OFFERS {
+            linker.create_offer(primary(), now + 1);
+        }
+        assert_eq!(linker.offers.len(), MAX_LINK_OFFERS);
+        assert!(!linker.offers.contains_key(&first.link_id));
+    }
+
+    #[test]
+    fn test_link_message_payload_roundtrip() {
+        let offer = DeviceLinkOffer::generate(primary(), Timestamp::new(1_000));
+        let request = DeviceLinker::new()
+            .request(offer, new_device(2), Timestamp::new(1_000))
+            .unwrap();
+        let message = DeviceLinkMessage::Request(request);
+
+        let payload = message.to_noise_payload().unwrap();
+        assert_eq!(payload.payload_type, NoisePayloadType::DeviceLinkRequest);
+        assert_eq!(
+            DeviceLinkMessage::from_noise_payload(&payload).unwrap(),
+            message
+        );
+
+        let wrong = NoisePayload::new(NoisePayloadType::PrivateMessage, payload.data);
+        assert!(DeviceLinkMessage::from_noise_payload(&wrong).is_err());
+    }
+}
@@ -0,0 +1,396 @@
+//! Favorite notifications
+//!
+//! Favoriting a peer sends it a notification over the Noise session carrying our
+//! Nostr public key. Once both sides have favorited each other they are mutual
+//! favorites and can keep messaging over Nostr when they leave mesh range, matching
+//! the behaviour of the Swift/iOS reference implementation.
+//!
+//! The canonical apps send the notification as a private message whose content is
+//! `[FAVORITED]:<npub>` or `[UNFAVORITED]:<npub>` ([`CanonicalFavorite`]); the Noise
+//! session it arrives over is what authenticates it. Peers that negotiated the
+//! `favorite_notifications` capability exchange a [`FavoriteNotification`] instead,
+//! signed with the sender's Ed25519 identity key. It names the recipient and carries
+//! a timestamp, so it cannot be replayed to a different peer or later on.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::crypto::IdentityKeyPair;
+use crate::protocol::message::NoisePayloadType;
+use crate::types::{PeerId, Timestamp};
+use crate::{BitchatError, Result};
+
+/// Oldest signed notification accepted, relative to when it is received
+pub const MAX_NOTIFICATION_AGE_MS: u64 = 5 * 60 * 1000;
+
+/// How far ahead of our clock a signed notification may be dated
+pub const MAX_CLOCK_SKEW_MS: u64 = 60 * 1000;
+
+/// Content prefix of a canonical favorite message
+pub const FAVORITED_PREFIX: &str = "[FAVORITED]:";
+
+/// Content prefix of a canonical unfavorite message
+pub const UNFAVORITED_PREFIX: &str = "[UNFAVORITED]:";
+
+/// Domain separator for favorite notification signatures
+const SIGNATURE_CONTEXT: &[u8] = b"bitchat-favorite-v1";
+
+/// Signed notice that the sender favorited or unfavorited the recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FavoriteNotification {
+    /// Whether the sender now favorites the recipient
+    pub favorite: bool,
+    /// Peer the notification is addressed to
+    pub to_peer_id: PeerId,
+    /// Nostr public key (32-byte x-only) the sender can be reached at
+    pub nostr_public_key: Option<[u8; 32]>,
+    /// Sender's Ed25519 identity key
+    pub signing_public_key: [u8; 32],
+    /// When the notification was created
+    pub timestamp: Timestamp,
+    /// Ed25519 signature over the fields above
+    pub signature: Vec<u8>,
+}
+
+impl FavoriteNotification {
+    /// Create a notification signed with the sender's identity key
+    pub fn new_signed(
+        favorite: bool,
+        to_peer_id: PeerId,
+        nostr_public_key: Option<[u8; 32]>,
+        identity_key: &IdentityKeyPair,
+    ) -> Self {
+        let mut notification = Self {
+            favorite,
+            to_peer_id,
+            nostr_public_key,
+            signing_public_key: identity_key.public_key_bytes(),
+            timestamp: Timestamp::now(),
+            signature: Vec::new(),
+        };
+        notification.signature = identity_key.sign(notification.signed_bytes()).to_vec();
+        notification
+    }
+
+    /// Get the corresponding NoisePayloadType for this notification
+    pub fn payload_type(&self) -> NoisePayloadType {
+        NoisePayloadType::FavoriteNotification
+    }
+
+    /// Bytes covered by the signature
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SIGNATURE_CONTEXT.len() + 82);
+        bytes.extend_from_slice(SIGNATURE_CONTEXT);
+        bytes.push(self.favorite as u8);
+        bytes.extend_from_slice(self.to_peer_id.as_bytes());
+        match self.nostr_public_key {
+            Some(key) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&key);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&self.signing_public_key);
+        bytes.extend_from_slice(&self.timestamp.as_millis().to_be_bytes());
+        bytes
+    }
+
+    /// Check the signature, that the notification is addressed to `recipient` and
+    /// that it is fresh
+    ///
+    /// `last_seen` is the timestamp of the latest notification accepted from the
+    /// same sender; anything not newer than it is a replay.
+    pub fn verify(&self, recipient: PeerId, last_seen: Option<Timestamp>) -> Result<()> {
+        if self.to_peer_id != recipient {
+            return Err(BitchatError::invalid_packet(
+                "Favorite notification addressed to another peer",
+            ));
+        }
+        let now = Timestamp::now().as_millis();
+        let created = self.timestamp.as_millis();
+        if now.saturating_sub(created) > MAX_NOTIFICATION_AGE_MS
+            || created.saturating_sub(now) > MAX_CLOCK_SKEW_MS
+        {
+            return Err(BitchatError::invalid_packet(
+                "Favorite notification is not fresh",
+            ));
+        }
+        if last_seen.is_some_and(|seen| self.timestamp <= seen) {
+            return Err(BitchatError::invalid_packet(
+                "Favorite notification older than the last one seen",
+            ));
+        }
+        let signature: [u8; 64] = self
+            .signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| BitchatError::signature_error())?;
+        IdentityKeyPair::verify(&self.signing_public_key, self.signed_bytes(), &signature)
+    }
+
+    /// Serialize to binary format for transmission
+    pub fn to_binary(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
+
+    /// Deserialize from binary format
+    pub fn from_binary(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Canonical Format
+// ----------------------------------------------------------------------------
+
+/// Favorite notice in the canonical apps' private message format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanonicalFavorite {
+    /// Whether the sender now favorites the recipient
+    pub favorite: bool,
+    /// Nostr public key (32-byte x-only) the sender can be reached at
+    pub nostr_public_key: Option<[u8; 32]>,
+}
+
+impl CanonicalFavorite {
+    /// Private message content carrying this notice
+    pub fn to_content(&self) -> String {
+        let prefix = if self.favorite {
+            FAVORITED_PREFIX
+        } else {
+            UNFAVORITED_PREFIX
+        };
+        let npub = self.nostr_public_key.map(npub::encode).unwrap_or_default();
+        alloc::format!("{}{}", prefix, npub)
+    }
+
+    /// Parse private message content, if it is a favorite notice
+    ///
+    /// A notice whose npub is missing or malformed still counts, without a key.
+    pub fn from_content(content: &str) -> Option<Self> {
+        let (favorite, npub) = match content.strip_prefix(FAVORITED_PREFIX) {
+            Some(npub) => (true, npub),
+            None => (false, content.strip_prefix(UNFAVORITED_PREFIX)?),
+        };
+        Some(Self {
+            favorite,
+            nostr_public_key: npub::decode(npub.trim()),
+        })
+    }
+}
+
+/// Bech32 (NIP-19) encoding of Nostr public keys
+mod npub {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    const HRP: &str = "npub";
+    const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+
+    fn polymod(values: &[u8]) -> u32 {
+        let mut checksum = 1u32;
+        for &value in values {
+            let top = checksum >> 25;
+            checksum = ((checksum & 0x01ff_ffff) << 5) ^ u32::from(value);
+            for (i, generator) in GENERATOR.iter().enumerate() {
+                if (top >> i) & 1 == 1 {
+                    checksum ^= generator;
+                }
+            }
+        }
+        checksum
+    }
+
+    fn expanded_hrp() -> Vec<u8> {
+        let mut values: Vec<u8> = HRP.bytes().map(|b| b >> 5).collect();
+        values.push(0);
+        values.extend(HRP.bytes().map(|b| b & 0x1f));
+        values
+    }
+
+    fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+        let mut acc = 0u32;
+        let mut bits = 0u32;
+        let max = (1u32 << to) - 1;
+        let mut out = Vec::new();
+        for &value in data {
+            acc = (acc << from) | u32::from(value);
+            bits += from;
+            while bits >= to {
+                bits -= to;
+                out.push(((acc >> bits) & max) as u8);
+            }
+        }
+        if pad {
+            if bits > 0 {
+                out.push(((acc << (to - bits)) & max) as u8);
+            }
+        } else if bits >= from || (acc << (to - bits)) & max != 0 {
+            return None;
+        }
+        Some(out)
+    }
+
+    /// Encode a public key as an `npub1...` string
+    pub fn encode(public_key: [u8; 32]) -> String {
+        let data = convert_bits(&public_key, 8, 5, true).unwrap_or_default();
+        let mut values = expanded_hrp();
+        values.extend_from_slice(&data);
+        values.extend_from_slice(&[0; 6]);
+        let checksum = polymod(&values) ^ 1;
+
+        let mut npub = String::with_capacity(HRP.len() + 1 + data.len() + 6);
+        npub.push_str(HRP);
+        npub.push('1');
+        for &value in &data {
+            npub.push(CHARSET[value as usize] as char);
+        }
+        for i in 0..6 {
+            npub.push(CHARSET[((checksum >> (5 * (5 - i))) & 0x1f) as usize] as char);
+        }
+        npub
+    }
+
+    /// Decode an `npub1...` string, if it is a well-formed public key
+    pub fn decode(npub: &str) -> Option<[u8; 32]> {
+        let npub = npub.to_ascii_lowercase();
+        let data = npub.strip_prefix(HRP)?.strip_prefix('1')?;
+        let values: Vec<u8> = data
+            .bytes()
+            .map(|c| CHARSET.iter().position(|&d| d == c).map(|i| i as u8))
+            .collect::<Option<_>>()?;
+        if values.len() < 6 {
+            return None;
+        }
+
+        let mut checked = expanded_hrp();
+        checked.extend_from_slice(&values);
+        if polymod(&checked) != 1 {
+            return None;
+        }
+        convert_bits(&values[..values.len() - 6], 5, 8, false)?
+            .try_into()
+            .ok()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(byte: u8) -> PeerId {
+        PeerId::new([byte; 8])
+    }
+
+    #[test]
+    fn test_signed_notification_round_trip() {
+        let identity = IdentityKeyPair::generate().unwrap();
+        let notification =
+            FavoriteNotification::new_signed(true, peer(2), Some([7; 32]), &identity);
+
+        let decoded =
+            FavoriteNotification::from_binary(&notification.to_binary().unwrap()).unwrap();
+        assert_eq!(decoded, notification);
+        assert!(decoded.verify(peer(2), None).is_ok());
+        assert_eq!(
+            decoded.payload_type(),
+            NoisePayloadType::FavoriteNotification
+        );
+    }
+
+    #[test]
+    fn test_notification_rejects_other_recipient_and_tampering() {
+        let identity = IdentityKeyPair::generate().unwrap();
+        let notification =
+            FavoriteNotification::new_signed(true, peer(2), Some([7; 32]), &identity);
+        assert!(notification.verify(peer(3), None).is_err());
+
+        let mut tampered = notification.clone();
+        tampered.nostr_public_key = Some([8; 32]);
+        assert!(tampered.verify(peer(2), None).is_err());
+
+        let mut flipped = notification;
+        flipped.favorite = false;
+        assert!(flipped.verify(peer(2), None).is_err());
+    }
+
+    #[test]
+    fn test_notification_rejects_stale_and_replayed() {
+        let identity = IdentityKeyPair::generate().unwrap();
+        let notification = FavoriteNotification::new_signed(true, peer(2), None, &identity);
+        assert!(notification
+            .verify(
+                peer(2),
+                Some(Timestamp::new(notification.timestamp.as_millis() - 1))
+            )
+            .is_ok());
+        assert!(notification
+            .verify(peer(2), Some(notification.timestamp))
+            .is_err());
+
+        let resigned = |timestamp: Timestamp| {
+            let mut old = notification.clone();
+            old.timestamp = timestamp;
+            old.signature = identity.sign(old.signed_bytes()).to_vec();
+            old
+        };
+        let now = Timestamp::now().as_millis();
+        let stale = resigned(Timestamp::new(now - MAX_NOTIFICATION_AGE_MS - 1000));
+        assert!(stale.verify(peer(2), None).is_err());
+        let future = resigned(Timestamp::new(now + MAX_CLOCK_SKEW_MS + 1000));
+        assert!(future.verify(peer(2), None).is_err());
+    }
+
+    #[test]
+    fn test_canonical_favorite_content() {
+        // NIP-19 example key
+        let key: [u8; 32] =
+            hex::decode("3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let npub = "npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6";
+
+        let favorite = CanonicalFavorite {
+            favorite: true,
+            nostr_public_key: Some(key),
+        };
+        assert_eq!(
+            favorite.to_content(),
+            alloc::format!("[FAVORITED]:{}", npub)
+        );
+        assert_eq!(
+            CanonicalFavorite::from_content(&favorite.to_content()),
+            Some(favorite)
+        );
+
+        let unfavorite =
+            CanonicalFavorite::from_content(&alloc::format!("[UNFAVORITED]:{}", npub)).unwrap();
+        assert!(!unfavorite.favorite);
+        assert_eq!(unfavorite.nostr_public_key, Some(key));
+
+        // A corrupted checksum drops the key, not the notice
+        let corrupted = CanonicalFavorite::from_content(&alloc::format!(
+            "[FAVORITED]:{}q",
+            &npub[..npub.len() - 1]
+        ))
+        .unwrap();
+        assert!(corrupted.favorite && corrupted.nostr_public_key.is_none());
+
+        assert!(CanonicalFavorite::from_content("hello").is_none());
+    }
+}
@@ -2,16 +2,21 @@
 //!
 //! This module implements the file transfer protocol allowing users to send files
 //! securely through the BitChat network using chunked, encrypted transfer.
+//!
+//! Nothing here needs the whole file in memory: [`FileHasher`] hashes a file as it
+//! is read, and sessions only track which chunks have arrived, so the runtime can
+//! stream chunks from and to disk.
 
 use alloc::{
     string::{String, ToString},
     vec,
     vec::Vec,
 };
+use core::ops::Range;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::protocol::message::NoisePayloadType;
+use crate::protocol::message::{NoisePayload, NoisePayloadType};
 use crate::types::{PeerId, Timestamp};
 use crate::{BitchatError, Result};
 
@@ -22,12 +27,15 @@ use crate::{BitchatError, Result};
 /// Maximum size of a single file chunk (16KB)
 pub const MAX_CHUNK_SIZE: usize = 16 * 1024;
 
-/// Maximum file size supported (100MB)
-pub const MAX_FILE_SIZE: usize = 100 * 1024 * 1024;
+/// Maximum file size supported (1GB)
+pub const MAX_FILE_SIZE: usize = 1024 * 1024 * 1024;
 
 /// File transfer timeout in seconds (30 minutes)
 pub const TRANSFER_TIMEOUT_SECONDS: u64 = 30 * 60;
 
+/// Maximum number of missing-chunk ranges carried by a single resume request
+pub const MAX_RESUME_RANGES: usize = 1024;
+
 // ----------------------------------------------------------------------------
 // Core Types
 // ----------------------------------------------------------------------------
@@ -77,7 +85,16 @@ pub struct FileMetadata {
 impl FileMetadata {
     /// Create new file metadata
     pub fn new(filename: String, size: u64, mime_type: Option<String>, data: &[u8]) -> Self {
-        let hash = FileHash::from_data(data);
+        Self::with_hash(filename, size, mime_type, FileHash::from_data(data))
+    }
+
+    /// Create file metadata for a file hashed while streaming it
+    pub fn with_hash(
+        filename: String,
+        size: u64,
+        mime_type: Option<String>,
+        hash: FileHash,
+    ) -> Self {
         Self {
             filename,
             size,
@@ -87,6 +104,27 @@ impl FileMetadata {
         }
     }
 
+    /// Number of chunks the file is split into
+    pub fn total_chunks(&self) -> u32 {
+        self.size.div_ceil(MAX_CHUNK_SIZE as u64) as u32
+    }
+
+    /// Byte offset of a chunk within the file
+    pub fn chunk_offset(&self, chunk_index: u32) -> u64 {
+        chunk_index as u64 * MAX_CHUNK_SIZE as u64
+    }
+
+    /// Length of a chunk, or `None` if the index is out of range
+    ///
+    /// Every chunk is `MAX_CHUNK_SIZE` bytes except possibly the last one.
+    pub fn chunk_len(&self, chunk_index: u32) -> Option<usize> {
+        if chunk_index >= self.total_chunks() {
+            return None;
+        }
+        let remaining = self.size - self.chunk_offset(chunk_index);
+        Some(remaining.min(MAX_CHUNK_SIZE as u64) as usize)
+    }
+
     /// Validate file metadata
     pub fn validate(&self) -> Result<()> {
         if self.filename.is_empty() {
@@ -139,6 +177,38 @@ impl FileHash {
     }
 }
 
+/// Incremental SHA-256 hasher for files read piece by piece
+#[derive(Debug, Clone, Default)]
+pub struct FileHasher {
+    hasher: Sha256,
+    bytes_hashed: u64,
+}
+
+impl FileHasher {
+    /// Create a new hasher
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next piece of the file
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.bytes_hashed += data.len() as u64;
+    }
+
+    /// Number of bytes hashed so far
+    pub fn bytes_hashed(&self) -> u64 {
+        self.bytes_hashed
+    }
+
+    /// Finish hashing and return the file hash
+    pub fn finalize(self) -> FileHash {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&self.hasher.finalize());
+        FileHash(hash)
+    }
+}
+
 impl core::fmt::Display for FileHash {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", hex::encode(self.0))
@@ -184,7 +254,7 @@ impl FileOffer {
 
     /// Get total number of chunks needed for this file
     pub fn total_chunks(&self) -> u32 {
-        self.metadata.size.div_ceil(MAX_CHUNK_SIZE as u64) as u32
+        self.metadata.total_chunks()
     }
 }
 
@@ -311,6 +381,39 @@ impl FileComplete {
     }
 }
 
+/// Request from the receiver to resend the chunks it is still missing
+///
+/// Sent when the sender reconnects or the transfer stalls, so only the gaps are
+/// retransmitted. Badly fragmented transfers are requested in several rounds of at
+/// most [`MAX_RESUME_RANGES`] ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileResume {
+    /// Transfer ID being resumed
+    pub transfer_id: FileTransferId,
+    /// Missing chunk indices as half-open ranges
+    pub missing: Vec<Range<u32>>,
+}
+
+impl FileResume {
+    /// Create a resume request for the given missing ranges
+    pub fn new(transfer_id: FileTransferId, missing: Vec<Range<u32>>) -> Self {
+        Self {
+            transfer_id,
+            missing,
+        }
+    }
+
+    /// Iterate over the requested chunk indices
+    pub fn chunks(&self) -> impl Iterator<Item = u32> + '_ {
+        self.missing.iter().flat_map(|range| range.clone())
+    }
+
+    /// Number of chunks requested
+    pub fn chunk_count(&self) -> u32 {
+        self.missing.iter().map(|range| range.len() as u32).sum()
+    }
+}
+
 // ----------------------------------------------------------------------------
 // File Transfer Protocol Messages
 // ----------------------------------------------------------------------------
@@ -326,6 +429,8 @@ pub enum FileTransferMessage {
     Chunk(FileChunk),
     /// Transfer completion notification
     Complete(FileComplete),
+    /// Request to resend missing chunks
+    Resume(FileResume),
 }
 
 impl FileTransferMessage {
@@ -336,6 +441,7 @@ impl FileTransferMessage {
             FileTransferMessage::Accept(accept) => &accept.transfer_id,
             FileTransferMessage::Chunk(chunk) => &chunk.transfer_id,
             FileTransferMessage::Complete(complete) => &complete.transfer_id,
+            FileTransferMessage::Resume(resume) => &resume.transfer_id,
         }
     }
 
@@ -346,8 +452,45 @@ impl FileTransferMessage {
             FileTransferMessage::Accept(_) => NoisePayloadType::FileAccept,
             FileTransferMessage::Chunk(_) => NoisePayloadType::FileChunk,
             FileTransferMessage::Complete(_) => NoisePayloadType::FileComplete,
+            FileTransferMessage::Resume(_) => NoisePayloadType::FileResume,
         }
     }
+
+    /// Encode as a Noise payload
+    pub fn to_noise_payload(&self) -> Result<NoisePayload> {
+        let data = match self {
+            FileTransferMessage::Offer(offer) => bincode::serialize(offer),
+            FileTransferMessage::Accept(accept) => bincode::serialize(accept),
+            FileTransferMessage::Chunk(chunk) => bincode::serialize(chunk),
+            FileTransferMessage::Complete(complete) => bincode::serialize(complete),
+            FileTransferMessage::Resume(resume) => bincode::serialize(resume),
+        }
+        .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        Ok(NoisePayload::new(self.payload_type(), data))
+    }
+
+    /// Decode from a Noise payload
+    pub fn from_noise_payload(payload: &NoisePayload) -> Result<Self> {
+        let message = match payload.payload_type {
+            NoisePayloadType::FileOffer => {
+                bincode::deserialize(&payload.data).map(FileTransferMessage::Offer)
+            }
+            NoisePayloadType::FileAccept => {
+                bincode::deserialize(&payload.data).map(FileTransferMessage::Accept)
+            }
+            NoisePayloadType::FileChunk => {
+                bincode::deserialize(&payload.data).map(FileTransferMessage::Chunk)
+            }
+            NoisePayloadType::FileComplete => {
+                bincode::deserialize(&payload.data).map(FileTransferMessage::Complete)
+            }
+            NoisePayloadType::FileResume => {
+                bincode::deserialize(&payload.data).map(FileTransferMessage::Resume)
+            }
+            _ => return Err(BitchatError::invalid_packet("Not a file transfer payload")),
+        };
+        message.map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -436,26 +579,30 @@ impl FileTransferSession {
             return Err(BitchatError::invalid_packet("Transfer ID mismatch"));
         }
 
-        if chunk.chunk_index >= self.total_chunks {
+        if chunk.chunk_index >= self.total_chunks || chunk.total_chunks != self.total_chunks {
             return Err(BitchatError::invalid_packet("Chunk index out of range"));
         }
 
+        if self.metadata.chunk_len(chunk.chunk_index) != Some(chunk.data.len()) {
+            return Err(BitchatError::invalid_packet("Chunk length mismatch"));
+        }
+
         if !chunk.verify() {
             return Err(BitchatError::invalid_packet("Chunk verification failed"));
         }
 
-        // Mark chunk as received
+        // Mark chunk as received; the transfer completes once the file hash is checked
         self.chunks_received[chunk.chunk_index as usize] = true;
         self.last_activity = Timestamp::now();
 
-        // Check if transfer is complete
-        if self.chunks_received.iter().all(|&received| received) {
-            self.status = TransferStatus::Completed;
-        }
-
         Ok(())
     }
 
+    /// Whether every chunk has been received
+    pub fn is_fully_received(&self) -> bool {
+        self.chunks_received.iter().all(|&received| received)
+    }
+
     /// Get transfer progress as percentage (0.0 to 1.0)
     pub fn progress(&self) -> f64 {
         if self.total_chunks == 0 {
@@ -470,6 +617,17 @@ impl FileTransferSession {
         received_count as f64 / self.total_chunks as f64
     }
 
+    /// Bytes covered by the chunks received (or, for the sender, sent) so far
+    pub fn bytes_transferred(&self) -> u64 {
+        self.chunks_received
+            .iter()
+            .enumerate()
+            .filter(|(_, &received)| received)
+            .filter_map(|(index, _)| self.metadata.chunk_len(index as u32))
+            .map(|len| len as u64)
+            .sum()
+    }
+
     /// Check if transfer has expired
     pub fn is_expired(&self) -> bool {
         let now = Timestamp::now();
@@ -487,18 +645,76 @@ impl FileTransferSession {
             .collect()
     }
 
+    /// Get missing chunks as half-open ranges, at most `limit` of them
+    pub fn missing_ranges(&self, limit: usize) -> Vec<Range<u32>> {
+        let mut ranges: Vec<Range<u32>> = Vec::new();
+        for index in self.missing_chunks() {
+            if let Some(range) = ranges.last_mut().filter(|range| range.end == index) {
+                range.end += 1;
+            } else if ranges.len() == limit {
+                break;
+            } else {
+                ranges.push(index..index + 1);
+            }
+        }
+        ranges
+    }
+
+    /// Build a request for the chunks still missing (receiver side)
+    pub fn resume_request(&self) -> FileResume {
+        FileResume::new(
+            self.transfer_id.clone(),
+            self.missing_ranges(MAX_RESUME_RANGES),
+        )
+    }
+
+    /// Take the recipient's word on which chunks it still needs (sender side)
+    ///
+    /// The listed chunks are marked unsent and every other chunk delivered, so a
+    /// sender restored from an old record agrees with the recipient again.
+    pub fn apply_resume(&mut self, resume: &FileResume) -> Result<()> {
+        if resume.transfer_id != self.transfer_id {
+            return Err(BitchatError::invalid_packet("Transfer ID mismatch"));
+        }
+
+        if self.status != TransferStatus::InProgress {
+            return Err(BitchatError::invalid_packet("Transfer not in progress"));
+        }
+
+        if resume.missing.len() > MAX_RESUME_RANGES
+            || resume
+                .missing
+                .iter()
+                .any(|range| range.start >= range.end || range.end > self.total_chunks)
+        {
+            return Err(BitchatError::invalid_packet("Invalid resume range"));
+        }
+
+        self.chunks_received = vec![true; self.total_chunks as usize];
+        for index in resume.chunks() {
+            self.chunks_received[index as usize] = false;
+        }
+        self.last_activity = Timestamp::now();
+
+        Ok(())
+    }
+
     /// Mark transfer as completed
-    pub fn complete(&mut self, _final_hash: &FileHash) -> Result<()> {
+    ///
+    /// `final_hash` is the hash of the assembled file, which must match the offer.
+    pub fn complete(&mut self, final_hash: &FileHash) -> Result<()> {
         if self.status != TransferStatus::InProgress {
             return Err(BitchatError::invalid_packet("Transfer not in progress"));
         }
 
-        if !self.chunks_received.iter().all(|&received| received) {
+        if !self.is_fully_received() {
             return Err(BitchatError::invalid_packet("Not all chunks received"));
         }
 
-        // Note: In a real implementation, you would verify the final hash here
-        // against the assembled file data
+        if *final_hash != self.metadata.hash {
+            self.fail("File hash mismatch".to_string());
+            return Err(BitchatError::invalid_packet("File hash mismatch"));
+        }
 
         self.status = TransferStatus::Completed;
         self.last_activity = Timestamp::now();
@@ -619,6 +835,26 @@ impl FileTransferManager {
         Ok(())
     }
 
+    /// Process a resume request for an outgoing transfer
+    pub fn process_resume(&mut self, resume: &FileResume) -> Result<()> {
+        let session = self
+            .sessions
+            .get_mut(&resume.transfer_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not found"))?;
+
+        session.apply_resume(resume)
+    }
+
+    /// Re-insert a session restored from persistent storage
+    pub fn restore_session(&mut self, session: FileTransferSession) -> Result<()> {
+        if self.sessions.contains_key(&session.transfer_id) {
+            return Err(BitchatError::invalid_packet("Transfer ID already exists"));
+        }
+
+        self.sessions.insert(session.transfer_id.clone(), session);
+        Ok(())
+    }
+
     /// Get a transfer session by ID
     pub fn get_session(&self, transfer_id: &FileTransferId) -> Option<&FileTransferSession> {
         self.sessions.get(transfer_id)
@@ -743,6 +979,60 @@ mod tests {
         assert_eq!(session.status, TransferStatus::InProgress);
     }
 
+    #[test]
+    fn test_file_hasher_matches_whole_file_hash() {
+        let data: Vec<u8> = (0..40_000u32).map(|i| i as u8).collect();
+        let mut hasher = FileHasher::new();
+        for piece in data.chunks(1000) {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.bytes_hashed(), data.len() as u64);
+        assert_eq!(hasher.finalize(), FileHash::from_data(&data));
+    }
+
+    #[test]
+    fn test_chunk_layout() {
+        let size = 2 * MAX_CHUNK_SIZE as u64 + 10;
+        let metadata =
+            FileMetadata::with_hash("layout.bin".to_string(), size, None, FileHash::new([0; 32]));
+
+        assert_eq!(metadata.total_chunks(), 3);
+        assert_eq!(metadata.chunk_offset(2), 2 * MAX_CHUNK_SIZE as u64);
+        assert_eq!(metadata.chunk_len(0), Some(MAX_CHUNK_SIZE));
+        assert_eq!(metadata.chunk_len(2), Some(10));
+        assert_eq!(metadata.chunk_len(3), None);
+    }
+
+    #[test]
+    fn test_session_completes_only_with_matching_hash() {
+        let data = vec![9u8; MAX_CHUNK_SIZE + 5];
+        let metadata = FileMetadata::new("two.bin".to_string(), data.len() as u64, None, &data);
+        let offer = FileOffer::new(metadata, None).unwrap();
+        let mut session =
+            FileTransferSession::from_offer(&offer, PeerId::new([1; 8]), PeerId::new([2; 8]));
+        session.accept();
+
+        // A short first chunk would leave a hole in the file
+        let short = FileChunk::new(offer.transfer_id.clone(), 0, 2, vec![9; 5]).unwrap();
+        assert!(session.receive_chunk(&short).is_err());
+
+        for (index, piece) in data.chunks(MAX_CHUNK_SIZE).enumerate() {
+            let chunk =
+                FileChunk::new(offer.transfer_id.clone(), index as u32, 2, piece.to_vec()).unwrap();
+            session.receive_chunk(&chunk).unwrap();
+        }
+        assert!(session.is_fully_received());
+        assert!(session.missing_chunks().is_empty());
+        assert_eq!(session.status, TransferStatus::InProgress);
+
+        let mut tampered = session.clone();
+        assert!(tampered.complete(&FileHash::new([0; 32])).is_err());
+        assert_eq!(tampered.status, TransferStatus::Failed);
+
+        session.complete(&FileHash::from_data(&data)).unwrap();
+        assert_eq!(session.status, TransferStatus::Completed);
+    }
+
     #[test]
     fn test_transfer_manager() {
         let mut manager = FileTransferManager::new();
@@ -768,4 +1058,114 @@ mod tests {
         let session = manager.get_session(&offer.transfer_id).unwrap();
         assert_eq!(session.status, TransferStatus::InProgress);
     }
+
+    #[test]
+    fn test_file_transfer_message_noise_payload_round_trip() {
+        let data = vec![7u8; MAX_CHUNK_SIZE + 10];
+        let metadata = FileMetadata::new("payload.bin".to_string(), data.len() as u64, None, &data);
+        let offer = FileOffer::new(metadata, Some("test".to_string())).unwrap();
+        let transfer_id = offer.transfer_id.clone();
+
+        let messages = [
+            FileTransferMessage::Offer(offer),
+            FileTransferMessage::Accept(FileAccept::accept(transfer_id.clone())),
+            FileTransferMessage::Chunk(
+                FileChunk::new(transfer_id.clone(), 1, 2, data[MAX_CHUNK_SIZE..].to_vec()).unwrap(),
+            ),
+            FileTransferMessage::Complete(FileComplete::failure(
+                transfer_id.clone(),
+                "cancelled".to_string(),
+            )),
+            FileTransferMessage::Resume(FileResume::new(transfer_id, vec![0..1, 3..7])),
+        ];
+        for message in messages {
+            let payload = message.to_noise_payload().unwrap();
+            assert_eq!(payload.payload_type, message.payload_type());
+            let decoded = NoisePayload::from_binary(&payload.to_binary()).unwrap();
+            assert_eq!(
+                FileTransferMessage::from_noise_payload(&decoded).unwrap(),
+                message
+            );
+        }
+
+        let other = NoisePayload::new(NoisePayloadType::VersionHello, Vec::new());
+        assert!(FileTransferMessage::from_noise_payload(&other).is_err());
+    }
+
+    #[test]
+    fn test_bytes_transferred_counts_short_last_chunk() {
+        let data = vec![1u8; MAX_CHUNK_SIZE + 10];
+        let metadata = FileMetadata::new("bytes.bin".to_string(), data.len() as u64, None, &data);
+        let offer = FileOffer::new(metadata, None).unwrap();
+        let mut session =
+            FileTransferSession::from_offer(&offer, PeerId::new([1; 8]), PeerId::new([2; 8]));
+        session.accept();
+        assert_eq!(session.bytes_transferred(), 0);
+
+        let last = FileChunk::new(
+            offer.transfer_id.clone(),
+            1,
+            2,
+            data[MAX_CHUNK_SIZE..].to_vec(),
+        )
+        .unwrap();
+        session.receive_chunk(&last).unwrap();
+        assert_eq!(session.bytes_transferred(), 10);
+
+        let first = FileChunk::new(
+            offer.transfer_id.clone(),
+            0,
+            2,
+            data[..MAX_CHUNK_SIZE].to_vec(),
+        )
+        .unwrap();
+        session.receive_chunk(&first).unwrap();
+        assert_eq!(session.bytes_transferred(), data.len() as u64);
+    }
+
+    #[test]
+    fn test_resume_requests_only_missing_ranges() {
+        let data = vec![3u8; 6 * MAX_CHUNK_SIZE];
+        let metadata = FileMetadata::new("gaps.bin".to_string(), data.len() as u64, None, &data);
+        let offer = FileOffer::new(metadata, None).unwrap();
+        let mut receiver =
+            FileTransferSession::from_offer(&offer, PeerId::new([1; 8]), PeerId::new([2; 8]));
+        receiver.accept();
+
+        for index in [0u32, 3, 5] {
+            let start = index as usize * MAX_CHUNK_SIZE;
+            let chunk = FileChunk::new(
+                offer.transfer_id.clone(),
+                index,
+                6,
+                data[start..start + MAX_CHUNK_SIZE].to_vec(),
+            )
+            .unwrap();
+            receiver.receive_chunk(&chunk).unwrap();
+        }
+
+        assert_eq!(receiver.missing_ranges(usize::MAX), vec![1..3, 4..5]);
+        assert_eq!(receiver.missing_ranges(1), vec![1..3]);
+        let resume = receiver.resume_request();
+        assert_eq!(resume.chunks().collect::<Vec<_>>(), vec![1, 2, 4]);
+        assert_eq!(resume.chunk_count(), 3);
+
+        // A sender restored from an old record takes the recipient's word for it
+        let mut manager = FileTransferManager::new();
+        let mut sender = receiver.clone();
+        sender.chunks_received = vec![false; 6];
+        manager.restore_session(sender.clone()).unwrap();
+        assert!(manager.restore_session(sender).is_err());
+
+        manager.process_resume(&resume).unwrap();
+        let sender = manager.get_session(&offer.transfer_id).unwrap();
+        assert_eq!(sender.missing_chunks(), vec![1, 2, 4]);
+
+        let out_of_range =
+            FileResume::new(offer.transfer_id.clone(), core::iter::once(5..7).collect());
+        assert!(manager.process_resume(&out_of_range).is_err());
+        let empty_range =
+            FileResume::new(offer.transfer_id.clone(), core::iter::once(2..2).collect());
+        assert!(manager.process_resume(&empty_range).is_err());
+    }
 }
@@ -237,6 +237,11 @@ impl MessageFragmenter {
 
         // Calculate fragments needed
         let fragment_data_size = self.max_fragment_size.saturating_sub(13); // Account for header
+        if fragment_data_size == 0 {
+            return Err(BitchatError::invalid_packet(
+                "Fragment size leaves no room for data",
+            ));
+        }
         let total_fragments = data.len().div_ceil(fragment_data_size);
 
         if total_fragments > MAX_FRAGMENTS_PER_MESSAGE as usize {
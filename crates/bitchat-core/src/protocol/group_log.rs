@@ -0,0 +1,887 @@
+//! Signed, conflict-free group membership log
+//!
+//! Every change to a group's membership or metadata is an operation signed with
+//! its author's Ed25519 identity key. Each operation names the log heads its
+//! author had seen as parents, so the log forms a causal graph that members can
+//! exchange piecemeal until they all hold the same operations.
+//!
+//! Group state is never updated in arrival order. It is rebuilt by replaying the
+//! log in a deterministic order (Lamport clock, then operation ID) that respects
+//! causality, checking each operation against the membership and roles in force at
+//! that point. Operations that fail the check stay in the log without effect, so
+//! members holding the same operations agree on the group once a partition heals,
+//! whatever order the operations reached them in.
+//!
+//! Only the creator and peers invited somewhere in the log may add operations, so
+//! a stranger can't grow the log with operations that would never take effect.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::ToString,
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::protocol::crypto::IdentityKeyPair;
+use crate::protocol::group_messaging::{
+    GroupCreate, GroupId, GroupInvite, GroupJoin, GroupKick, GroupLeave, GroupMetadata, GroupRole,
+    GroupUpdate,
+};
+use crate::protocol::message::NoisePayloadType;
+use crate::types::PeerId;
+use crate::{BitchatError, Result};
+
+// ----------------------------------------------------------------------------
+// Constants
+// ----------------------------------------------------------------------------
+
+/// Maximum number of parents a single operation may name
+pub const MAX_OPERATION_PARENTS: usize = 64;
+
+/// Maximum number of operations held back while their parents are missing
+pub const MAX_PENDING_OPERATIONS: usize = 1024;
+
+/// Domain separator for group operation signatures
+const SIGNATURE_CONTEXT: &[u8] = b"bitchat-group-op-v1";
+
+/// Identifier of an operation: SHA-256 of its signed bytes
+pub type GroupOperationId = [u8; 32];
+
+// ----------------------------------------------------------------------------
+// Operations
+// ----------------------------------------------------------------------------
+
+/// A change to a group's membership or metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GroupOperation {
+    /// Group creation, the root of every log
+    Create(GroupCreate),
+    /// Invitation naming the invitee's identity key
+    Invite(GroupInvite),
+    /// Invitee joining the group
+    Join(GroupJoin),
+    /// Member leaving the group
+    Leave(GroupLeave),
+    /// Group metadata update
+    Update(GroupUpdate),
+    /// Member kick
+    Kick(GroupKick),
+}
+
+impl GroupOperation {
+    /// Get the group ID for this operation
+    pub fn group_id(&self) -> &GroupId {
+        match self {
+            GroupOperation::Create(create) => create.group_id(),
+            GroupOperation::Invite(invite) => &invite.group_id,
+            GroupOperation::Join(join) => &join.group_id,
+            GroupOperation::Leave(leave) => &leave.group_id,
+            GroupOperation::Update(update) => &update.group_id,
+            GroupOperation::Kick(kick) => &kick.group_id,
+        }
+    }
+
+    /// Get the corresponding NoisePayloadType for this operation
+    pub fn payload_type(&self) -> NoisePayloadType {
+        match self {
+            GroupOperation::Create(_) => NoisePayloadType::GroupCreate,
+            GroupOperation::Invite(_) => NoisePayloadType::GroupInvite,
+            GroupOperation::Join(_) => NoisePayloadType::GroupJoin,
+            GroupOperation::Leave(_) => NoisePayloadType::GroupLeave,
+            GroupOperation::Update(_) => NoisePayloadType::GroupUpdate,
+            GroupOperation::Kick(_) => NoisePayloadType::GroupKick,
+        }
+    }
+}
+
+/// Group operation signed by its author
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedGroupOperation {
+    /// Peer that performed the operation
+    pub author: PeerId,
+    /// Author's Ed25519 identity key
+    pub signing_public_key: [u8; 32],
+    /// Lamport clock: one more than the highest parent
+    pub lamport: u64,
+    /// Log heads the author had seen
+    pub parents: Vec<GroupOperationId>,
+    /// The change itself
+    pub operation: GroupOperation,
+    /// Ed25519 signature over the fields above
+    pub signature: Vec<u8>,
+}
+
+impl SignedGroupOperation {
+    /// Create an operation signed with the author's identity key
+    pub fn new_signed(
+        author: PeerId,
+        lamport: u64,
+        parents: Vec<GroupOperationId>,
+        operation: GroupOperation,
+        identity_key: &IdentityKeyPair,
+    ) -> Result<Self> {
+        let mut signed = Self {
+            author,
+            signing_public_key: identity_key.public_key_bytes(),
+            lamport,
+            parents,
+            operation,
+            signature: Vec::new(),
+        };
+        signed.signature = identity_key.sign(signed.signed_bytes()?).to_vec();
+        Ok(signed)
+    }
+
+    /// Get the group ID for this operation
+    pub fn group_id(&self) -> &GroupId {
+        self.operation.group_id()
+    }
+
+    /// Get the corresponding NoisePayloadType for this operation
+    pub fn payload_type(&self) -> NoisePayloadType {
+        self.operation.payload_type()
+    }
+
+    /// Identifier of this operation
+    pub fn id(&self) -> Result<GroupOperationId> {
+        Ok(Sha256::digest(self.signed_bytes()?).into())
+    }
+
+    /// Check the signature against the key the operation names
+    ///
+    /// Whether that key belongs to the author is decided when the log is replayed.
+    pub fn verify(&self) -> Result<()> {
+        let signature: [u8; 64] = self
+            .signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| BitchatError::signature_error())?;
+        IdentityKeyPair::verify(&self.signing_public_key, self.signed_bytes()?, &signature)
+    }
+
+    /// Bytes covered by the signature
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let fields = (
+            &self.author,
+            &self.signing_public_key,
+            self.lamport,
+            &self.parents,
+            &self.operation,
+        );
+        let encoded = bincode::serialize(&fields)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+
+        let mut bytes = Vec::with_capacity(SIGNATURE_CONTEXT.len() + encoded.len());
+        bytes.extend_from_slice(SIGNATURE_CONTEXT);
+        bytes.extend_from_slice(&encoded);
+        Ok(bytes)
+    }
+}
+
+/// Anti-entropy message exchanged between members of a group
+///
+/// Carries the sender's log heads and any operations the recipient is known to
+/// lack. A recipient that holds operations the sender lacks answers with its own
+/// sync, so two members converge after at most two round trips.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupLogSync {
+    /// Group being synchronized
+    pub group_id: GroupId,
+    /// Sender's log heads
+    pub heads: Vec<GroupOperationId>,
+    /// Operations the recipient lacks, in log order
+    pub operations: Vec<SignedGroupOperation>,
+}
+
+impl GroupLogSync {
+    /// Get the corresponding NoisePayloadType for this message
+    pub fn payload_type(&self) -> NoisePayloadType {
+        NoisePayloadType::GroupSync
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Replayed State
+// ----------------------------------------------------------------------------
+
+/// Group state reached by replaying the log up to some point
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GroupState {
+    metadata: GroupMetadata,
+    /// Identity keys of current members
+    signing_keys: BTreeMap<PeerId, [u8; 32]>,
+    /// Invitees that have not joined yet, with the key they must join with
+    invited: BTreeMap<PeerId, [u8; 32]>,
+}
+
+impl GroupState {
+    fn genesis(op: &SignedGroupOperation) -> Result<Self> {
+        let GroupOperation::Create(create) = &op.operation else {
+            return Err(BitchatError::invalid_packet(
+                "Group log must start with its creation",
+            ));
+        };
+        let metadata = &create.metadata;
+        let creator_is_sole_owner = metadata.members.len() == 1
+            && metadata
+                .get_member(&op.author)
+                .is_some_and(|member| member.role == GroupRole::Owner);
+        if metadata.creator != op.author || !creator_is_sole_owner {
+            return Err(BitchatError::invalid_packet(
+                "Group must be created with its creator as sole owner",
+            ));
+        }
+        if op.lamport != 0 || !op.parents.is_empty() {
+            return Err(BitchatError::invalid_packet(
+                "Group creation cannot have parents",
+            ));
+        }
+
+        let mut signing_keys = BTreeMap::new();
+        signing_keys.insert(op.author, op.signing_public_key);
+        Ok(Self {
+            metadata: metadata.clone(),
+            signing_keys,
+            invited: BTreeMap::new(),
+        })
+    }
+
+    /// Role of the author, if it is a member signing with its own key
+    fn author_role(&self, op: &SignedGroupOperation) -> Result<GroupRole> {
+        if self.signing_keys.get(&op.author) != Some(&op.signing_public_key) {
+            return Err(BitchatError::invalid_packet(
+                "Operation not signed by a group member",
+            ));
+        }
+        self.metadata
+            .get_member(&op.author)
+            .map(|member| member.role)
+            .ok_or_else(|| BitchatError::invalid_packet("Operation not signed by a group member"))
+    }
+
+    /// Apply an operation if the state allows it
+    ///
+    /// May leave the state partly updated on error; callers apply to a copy.
+    fn apply(&mut self, op: &SignedGroupOperation) -> Result<()> {
+        match &op.operation {
+            GroupOperation::Create(_) => {
+                return Err(BitchatError::invalid_packet("Group already created"));
+            }
+
+            GroupOperation::Join(join) => {
+                if join.member.peer_id != op.author || join.member.role != GroupRole::Member {
+                    return Err(BitchatError::invalid_packet(
+                        "Join must add its author as a member",
+                    ));
+                }
+                if self.invited.get(&op.author) != Some(&op.signing_public_key) {
+                    return Err(BitchatError::invalid_packet(
+                        "Join without a matching invitation",
+                    ));
+                }
+                self.metadata.add_member(join.member.clone())?;
+                self.invited.remove(&op.author);
+                self.signing_keys.insert(op.author, op.signing_public_key);
+            }
+
+            GroupOperation::Invite(invite) => {
+                let role = self.author_role(op)?;
+                if invite.inviter != op.author {
+                    return Err(BitchatError::invalid_packet(
+                        "Invite signed by another peer",
+                    ));
+                }
+                if !role.can_invite() && !self.metadata.settings.members_can_invite {
+                    return Err(BitchatError::invalid_packet("Member may not invite"));
+                }
+                if self.metadata.is_member(&invite.invitee) {
+                    return Err(BitchatError::invalid_packet("Invitee is already a member"));
+                }
+                self.invited
+                    .insert(invite.invitee, invite.invitee_signing_key);
+            }
+
+            GroupOperation::Leave(leave) => {
+                self.author_role(op)?;
+                if leave.peer_id != op.author {
+                    return Err(BitchatError::invalid_packet("Leave signed by another peer"));
+                }
+                self.metadata.remove_member(&op.author)?;
+                self.signing_keys.remove(&op.author);
+            }
+
+            GroupOperation::Update(update) => {
+                let role = self.author_role(op)?;
+                if update.updater != op.author {
+                    return Err(BitchatError::invalid_packet(
+                        "Update signed by another peer",
+                    ));
+                }
+                if !role.can_modify_group() {
+                    return Err(BitchatError::invalid_packet(
+                        "Member may not modify the group",
+                    ));
+                }
+                self.metadata
+                    .update(update.name.clone(), update.description.clone())?;
+                if let Some(ref settings) = update.settings {
+                    self.metadata.settings = settings.clone();
+                }
+            }
+
+            GroupOperation::Kick(kick) => {
+                let role = self.author_role(op)?;
+                if kick.kicker != op.author {
+                    return Err(BitchatError::invalid_packet("Kick signed by another peer"));
+                }
+                let target = self
+                    .metadata
+                    .get_member(&kick.kicked_member)
+                    .ok_or_else(|| BitchatError::invalid_packet("Member not found in group"))?
+                    .role;
+                if !role.can_kick() || rank(target) >= rank(role) {
+                    return Err(BitchatError::invalid_packet(
+                        "Member may not kick this member",
+                    ));
+                }
+                self.metadata.remove_member(&kick.kicked_member)?;
+                self.signing_keys.remove(&kick.kicked_member);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Kicks only go down the hierarchy: owners kick admins, admins kick members
+fn rank(role: GroupRole) -> u8 {
+    match role {
+        GroupRole::Owner => 2,
+        GroupRole::Admin => 1,
+        GroupRole::Member => 0,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Group Log
+// ----------------------------------------------------------------------------
+
+/// Causally ordered log of a group's signed operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupLog {
+    /// Creation operation every other operation descends from
+    genesis: GroupOperationId,
+    /// All operations whose parents are known
+    operations: BTreeMap<GroupOperationId, SignedGroupOperation>,
+    /// Operations no other operation names as a parent
+    heads: BTreeSet<GroupOperationId>,
+    /// Operations waiting for missing parents
+    pending: BTreeMap<GroupOperationId, SignedGroupOperation>,
+    /// Peers and identity keys allowed to author operations: the creator and
+    /// every invitee named in the log
+    authors: BTreeSet<(PeerId, [u8; 32])>,
+    /// State after replaying every operation
+    state: GroupState,
+}
+
+impl GroupLog {
+    /// Start a log from a group's creation operation
+    pub fn new(genesis: SignedGroupOperation) -> Result<Self> {
+        genesis.verify()?;
+        let state = GroupState::genesis(&genesis)?;
+        let id = genesis.id()?;
+
+        let authors = [(genesis.author, genesis.signing_public_key)]
+            .into_iter()
+            .collect();
+        let mut operations = BTreeMap::new();
+        operations.insert(id, genesis);
+        Ok(Self {
+            genesis: id,
+            operations,
+            heads: [id].into_iter().collect(),
+            pending: BTreeMap::new(),
+            authors,
+            state,
+        })
+    }
+
+    /// Rebuild a log from operations received in any order
+    pub fn from_operations(operations: Vec<SignedGroupOperation>) -> Result<Self> {
+        let (genesis, rest): (Vec<_>, Vec<_>) = operations
+            .into_iter()
+            .partition(|op| matches!(op.operation, GroupOperation::Create(_)));
+        let mut genesis = genesis.into_iter();
+        let (Some(root), None) = (genesis.next(), genesis.next()) else {
+            return Err(BitchatError::invalid_packet(
+                "Group log must have exactly one creation",
+            ));
+        };
+
+        let mut log = Self::new(root)?;
+        for op in rest {
+            log.insert(op)?;
+        }
+        Ok(log)
+    }
+
+    /// Get the group ID
+    pub fn group_id(&self) -> &GroupId {
+        &self.state.metadata.group_id
+    }
+
+    /// Group metadata after replaying the log
+    pub fn metadata(&self) -> &GroupMetadata {
+        &self.state.metadata
+    }
+
+    /// Identity key a current member signs with
+    pub fn signing_key(&self, peer_id: &PeerId) -> Option<&[u8; 32]> {
+        self.state.signing_keys.get(peer_id)
+    }
+
+    /// Identity key an invited peer must join with, if it has an open invitation
+    pub fn invitation_key(&self, peer_id: &PeerId) -> Option<&[u8; 32]> {
+        self.state.invited.get(peer_id)
+    }
+
+    /// Current log heads
+    pub fn heads(&self) -> Vec<GroupOperationId> {
+        self.heads.iter().copied().collect()
+    }
+
+    /// Whether an operation is in the log
+    pub fn contains(&self, id: &GroupOperationId) -> bool {
+        self.operations.contains_key(id)
+    }
+
+    /// Number of operations in the log
+    pub fn operation_count(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether operations are held back waiting for parents we have not seen
+    pub fn has_missing_parents(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Add a received operation
+    ///
+    /// Returns whether the log changed. Operations whose parents are unknown are
+    /// held back until the parents arrive. Operations by anyone but the creator
+    /// or a peer invited in the log are rejected. Invitees stay allowed after
+    /// leaving or being kicked, since members that had not seen that yet may
+    /// still hold operations of theirs that replay decides on.
+    pub fn insert(&mut self, op: SignedGroupOperation) -> Result<bool> {
+        if op.group_id() != self.group_id() {
+            return Err(BitchatError::invalid_packet(
+                "Operation belongs to another group",
+            ));
+        }
+        if matches!(op.operation, GroupOperation::Create(_)) {
+            let id = op.id()?;
+            return if id == self.genesis {
+                Ok(false)
+            } else {
+                Err(BitchatError::invalid_packet("Group already created"))
+            };
+        }
+        let parents: BTreeSet<_> = op.parents.iter().collect();
+        if parents.is_empty()
+            || parents.len() != op.parents.len()
+            || parents.len() > MAX_OPERATION_PARENTS
+        {
+            return Err(BitchatError::invalid_packet("Invalid operation parents"));
+        }
+        op.verify()?;
+
+        let id = op.id()?;
+        if self.operations.contains_key(&id) || self.pending.contains_key(&id) {
+            return Ok(false);
+        }
+        if !op.parents.iter().all(|parent| self.contains(parent)) {
+            if self.pending.len() >= MAX_PENDING_OPERATIONS {
+                return Err(BitchatError::invalid_packet(
+                    "Too many operations waiting for their parents",
+                ));
+            }
+            self.pending.insert(id, op);
+            return Ok(false);
+        }
+
+        self.add(id, op)?;
+        self.add_ready_pending();
+        self.replay();
+        Ok(true)
+    }
+
+    /// Sign and append a local operation on top of the current heads
+    ///
+    /// Fails without changing the log if the operation is not allowed.
+    pub fn append(
+        &mut self,
+        author: PeerId,
+        operation: GroupOperation,
+        identity_key: &IdentityKeyPair,
+    ) -> Result<SignedGroupOperation> {
+        let lamport = self
+            .heads
+            .iter()
+            .filter_map(|head| self.operations.get(head))
+            .map(|head| head.lamport)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let op = SignedGroupOperation::new_signed(
+            author,
+            lamport,
+            self.heads(),
+            operation,
+            identity_key,
+        )?;
+
+        // Ordered after everything we know, so it applies to the current state
+        self.state.clone().apply(&op)?;
+        self.insert(op.clone())?;
+        Ok(op)
+    }
+
+    /// Operations that a log with the given heads lacks, in log order
+    ///
+    /// Heads we do not know are ignored; see [`Self::knows_all`].
+    pub fn operations_since(&self, heads: &[GroupOperationId]) -> Vec<SignedGroupOperation> {
+        let mut known = BTreeSet::new();
+        let mut stack: Vec<_> = heads.iter().filter(|id| self.contains(id)).collect();
+        while let Some(id) = stack.pop() {
+            if known.insert(*id) {
+                stack.extend(&self.operations[id].parents);
+            }
+        }
+
+        self.ordered()
+            .into_iter()
+            .filter(|(_, id)| !known.contains(id))
+            .map(|(_, id)| self.operations[&id].clone())
+            .collect()
+    }
+
+    /// Whether we hold every operation named in the given heads
+    pub fn knows_all(&self, heads: &[GroupOperationId]) -> bool {
+        heads.iter().all(|id| self.contains(id))
+    }
+
+    fn add(&mut self, id: GroupOperationId, op: SignedGroupOperation) -> Result<()> {
+        // An invitee's operations descend from its invitation, which is added first
+        if !self.authors.contains(&(op.author, op.signing_public_key)) {
+            return Err(BitchatError::invalid_packet(
+                "Operation not signed by a member or invitee",
+            ));
+        }
+        let expected = op
+            .parents
+            .iter()
+            .map(|parent| self.operations[parent].lamport)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        if op.lamport != expected {
+            return Err(BitchatError::invalid_packet(
+                "Operation clock does not follow its parents",
+            ));
+        }
+
+        for parent in &op.parents {
+            self.heads.remove(parent);
+        }
+        if let GroupOperation::Invite(invite) = &op.operation {
+            self.authors
+                .insert((invite.invitee, invite.invitee_signing_key));
+        }
+        self.heads.insert(id);
+        self.operations.insert(id, op);
+        Ok(())
+    }
+
+    /// Move held-back operations into the log once their parents are present
+    fn add_ready_pending(&mut self) {
+        while let Some(id) = self
+            .pending
+            .iter()
+            .find(|(_, op)| op.parents.iter().all(|parent| self.contains(parent)))
+            .map(|(id, _)| *id)
+        {
+            if let Some(op) = self.pending.remove(&id) {
+                // An operation with a bad clock or author is dropped, as on direct insert
+                let _ = self.add(id, op);
+            }
+        }
+    }
+
+    /// Operations sorted by (Lamport clock, ID), a causal order every member shares
+    fn ordered(&self) -> Vec<(u64, GroupOperationId)> {
+        let mut order: Vec<_> = self
+            .operations
+            .iter()
+            .map(|(id, op)| (op.lamport, *id))
+            .collect();
+        order.sort_unstable();
+        order
+    }
+
+    /// Rebuild the state from the creation, skipping operations not allowed at
+    /// their point in the log
+    fn replay(&mut self) {
+        let Ok(mut state) = GroupState::genesis(&self.operations[&self.genesis]) else {
+            return;
+        };
+        for (_, id) in self.ordered() {
+            if id == self.genesis {
+                continue;
+            }
+            let mut next = state.clone();
+            if next.apply(&self.operations[&id]).is_ok() {
+                state = next;
+            }
+        }
+        self.state = state;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::group_messaging::GroupMember;
+    use crate::types::Fingerprint;
+    use alloc::string::String;
+
+    struct Member {
+        peer_id: PeerId,
+        identity: IdentityKeyPair,
+    }
+
+    impl Member {
+        fn new(byte: u8) -> Self {
+            Self {
+                peer_id: PeerId::new([byte; 8]),
+                identity: IdentityKeyPair::generate().unwrap(),
+            }
+        }
+
+        fn join(&self, group_id: &GroupId) -> GroupOperation {
+            let member = GroupMember::new(
+                self.peer_id,
+                String::from("member"),
+                Fingerprint::new([0; 32]),
+                GroupRole::Member,
+            )
+            .unwrap();
+            GroupOperation::Join(GroupJoin::new(group_id.clone(), member, None))
+        }
+    }
+
+    /// Owner log with `members` invited and joined
+    fn group_with(owner: &Member, members: &[&Member]) -> GroupLog {
+        let metadata = GroupMetadata::new(
+            String::from("Group"),
+            None,
+            owner.peer_id,
+            String::from("owner"),
+            Fingerprint::new([0; 32]),
+        )
+        .unwrap();
+        let create = SignedGroupOperation::new_signed(
+            owner.peer_id,
+            0,
+            Vec::new(),
+            GroupOperation::Create(GroupCreate::new(metadata, None)),
+            &owner.identity,
+        )
+        .unwrap();
+        let mut log = GroupLog::new(create).unwrap();
+        for member in members {
+            invite_and_join(&mut log, owner, member);
+        }
+        log
+    }
+
+    /// Invite and join `member`, returning the new operations
+    fn invite_and_join(
+        log: &mut GroupLog,
+        owner: &Member,
+        member: &Member,
+    ) -> Vec<SignedGroupOperation> {
+        let group_id = log.group_id().clone();
+        let before = log.heads();
+        let invite = GroupInvite::new(
+            group_id.clone(),
+            owner.peer_id,
+            member.peer_id,
+            member.identity.public_key_bytes(),
+            None,
+        );
+        log.append(
+            owner.peer_id,
+            GroupOperation::Invite(invite),
+            &owner.identity,
+        )
+        .unwrap();
+        log.append(member.peer_id, member.join(&group_id), &member.identity)
+            .unwrap();
+        log.operations_since(&before)
+    }
+
+    /// Exchange syncs both ways
+    fn sync(a: &mut GroupLog, b: &mut GroupLog) {
+        for op in b.operations_since(&a.heads()) {
+            a.insert(op).unwrap();
+        }
+        for op in a.operations_since(&b.heads()) {
+            b.insert(op).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_forged_operations_have_no_effect() {
+        let owner = Member::new(1);
+        let alice = Member::new(2);
+        let bob = Member::new(3);
+        let mut log = group_with(&owner, &[&alice, &bob]);
+        let group_id = log.group_id().clone();
+        let heads = log.heads();
+        let lamport = log.operation_count() as u64;
+
+        // A plain member cannot kick, and cannot sign as the owner
+        let kick = GroupOperation::Kick(GroupKick::new(
+            group_id.clone(),
+            alice.peer_id,
+            bob.peer_id,
+            None,
+        ));
+        assert!(log.append(alice.peer_id, kick, &alice.identity).is_err());
+
+        let forged = SignedGroupOperation::new_signed(
+            owner.peer_id,
+            lamport,
+            heads.clone(),
+            GroupOperation::Kick(GroupKick::new(
+                group_id.clone(),
+                owner.peer_id,
+                bob.peer_id,
+                None,
+            )),
+            &alice.identity,
+        )
+        .unwrap();
+        assert!(log.insert(forged).is_err());
+        assert!(log.metadata().is_member(&bob.peer_id));
+
+        // Joining takes an invitation for the joiner's own key
+        let mallory = Member::new(4);
+        let join = SignedGroupOperation::new_signed(
+            mallory.peer_id,
+            lamport,
+            heads.clone(),
+            mallory.join(&group_id),
+            &mallory.identity,
+        )
+        .unwrap();
+        assert!(log.insert(join).is_err());
+        assert!(!log.metadata().is_member(&mallory.peer_id));
+
+        let mut tampered = SignedGroupOperation::new_signed(
+            owner.peer_id,
+            lamport,
+            heads,
+            GroupOperation::Kick(GroupKick::new(
+                group_id.clone(),
+                owner.peer_id,
+                alice.peer_id,
+                None,
+            )),
+            &owner.identity,
+        )
+        .unwrap();
+        tampered.operation =
+            GroupOperation::Kick(GroupKick::new(group_id, owner.peer_id, bob.peer_id, None));
+        assert!(log.insert(tampered).is_err());
+        assert_eq!(log.metadata().member_count(), 3);
+
+        // None of it entered the log
+        assert_eq!(log.operation_count() as u64, lamport);
+    }
+
+    #[test]
+    fn test_partitioned_logs_converge() {
+        let owner = Member::new(1);
+        let alice = Member::new(2);
+        let bob = Member::new(3);
+        let mut owner_log = group_with(&owner, &[&alice, &bob]);
+        let group_id = owner_log.group_id().clone();
+        let mut alice_log = owner_log.clone();
+        let mut bob_log = owner_log.clone();
+
+        // While partitioned, the owner kicks Alice as she invites Carol, and Bob,
+        // who may not rename the group, leaves it instead
+        let kick = GroupKick::new(group_id.clone(), owner.peer_id, alice.peer_id, None);
+        owner_log
+            .append(owner.peer_id, GroupOperation::Kick(kick), &owner.identity)
+            .unwrap();
+        let carol = Member::new(4);
+        let invite = GroupInvite::new(
+            group_id.clone(),
+            alice.peer_id,
+            carol.peer_id,
+            carol.identity.public_key_bytes(),
+            None,
+        );
+        alice_log
+            .append(
+                alice.peer_id,
+                GroupOperation::Invite(invite),
+                &alice.identity,
+            )
+            .unwrap();
+        let rename =
+            GroupUpdate::new(group_id.clone(), bob.peer_id).with_name(String::from("Bob's"));
+        assert!(bob_log
+            .append(bob.peer_id, GroupOperation::Update(rename), &bob.identity)
+            .is_err());
+        let leave = GroupLeave::new(group_id, bob.peer_id, None);
+        bob_log
+            .append(bob.peer_id, GroupOperation::Leave(leave), &bob.identity)
+            .unwrap();
+
+        // Heal the partition pairwise, in an order no two replicas share
+        sync(&mut bob_log, &mut alice_log);
+        sync(&mut owner_log, &mut bob_log);
+        sync(&mut alice_log, &mut owner_log);
+
+        for log in [&alice_log, &bob_log] {
+            assert_eq!(log.heads(), owner_log.heads());
+            assert_eq!(log.metadata(), owner_log.metadata());
+        }
+        assert_eq!(owner_log.metadata().member_ids(), vec![owner.peer_id]);
+    }
+
+    #[test]
+    fn test_operations_wait_for_missing_parents() {
+        let owner = Member::new(1);
+        let alice = Member::new(2);
+        let mut owner_log = group_with(&owner, &[]);
+        let mut alice_log = GroupLog::from_operations(owner_log.operations_since(&[])).unwrap();
+
+        // Deliver the join before the invitation it depends on
+        let mut ops = invite_and_join(&mut owner_log, &owner, &alice);
+        let invite = ops.remove(0);
+        assert!(!alice_log.insert(ops.remove(0)).unwrap());
+        assert!(alice_log.has_missing_parents());
+        assert!(alice_log.insert(invite).unwrap());
+        assert!(!alice_log.has_missing_parents());
+        assert_eq!(alice_log.heads(), owner_log.heads());
+        assert!(alice_log.metadata().is_member(&alice.peer_id));
+    }
+}
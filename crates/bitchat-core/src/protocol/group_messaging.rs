@@ -2,15 +2,24 @@
 //!
 //! This module implements group messaging functionality, allowing users to create
 //! and participate in group conversations with multiple participants.
+//!
+//! Membership and metadata are kept in a signed operation log per group (see
+//! [`crate::protocol::group_log`]), so members converge on the same state however
+//! the mesh partitions. Group messages are encrypted with per-member sender keys
+//! (see [`crate::protocol::sender_keys`]), so each message is encrypted once
+//! however large the group is.
 
 use alloc::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     string::{String, ToString},
     vec::Vec,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::protocol::message::NoisePayloadType;
+use crate::protocol::crypto::IdentityKeyPair;
+use crate::protocol::group_log::{GroupLog, GroupLogSync, GroupOperation, SignedGroupOperation};
+use crate::protocol::message::{NoisePayload, NoisePayloadType};
+use crate::protocol::sender_keys::{EncryptedGroupMessage, SenderKeyDistribution, SenderKeyStore};
 use crate::types::{Fingerprint, PeerId, Timestamp};
 use crate::{BitchatError, Result};
 
@@ -343,10 +352,12 @@ impl GroupCreate {
 pub struct GroupInvite {
     /// Group ID being invited to
     pub group_id: GroupId,
-    /// Current group metadata (for verification)
-    pub group_metadata: GroupMetadata,
     /// Inviter's peer ID
     pub inviter: PeerId,
+    /// Invitee's peer ID
+    pub invitee: PeerId,
+    /// Identity key the invitee must sign its join with
+    pub invitee_signing_key: [u8; 32],
     /// Invitation message
     pub message: Option<String>,
     /// Invitation expiration timestamp
@@ -355,13 +366,20 @@ pub struct GroupInvite {
 
 impl GroupInvite {
     /// Create a new group invitation
-    pub fn new(group_metadata: GroupMetadata, inviter: PeerId, message: Option<String>) -> Self {
+    pub fn new(
+        group_id: GroupId,
+        inviter: PeerId,
+        invitee: PeerId,
+        invitee_signing_key: [u8; 32],
+        message: Option<String>,
+    ) -> Self {
         let expires_at = Timestamp::now() + (24 * 60 * 60 * 1000); // 24 hours
 
         Self {
-            group_id: group_metadata.group_id.clone(),
-            group_metadata,
+            group_id,
             inviter,
+            invitee,
+            invitee_signing_key,
             message,
             expires_at,
         }
@@ -559,48 +577,82 @@ impl GroupKick {
 /// All group messaging message types
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GroupMessagingMessage {
-    /// Group creation
-    Create(GroupCreate),
-    /// Group invitation
-    Invite(GroupInvite),
-    /// Group join request/notification
-    Join(GroupJoin),
-    /// Group leave notification
-    Leave(GroupLeave),
-    /// Group chat message
-    Message(GroupMessage),
-    /// Group metadata update
-    Update(GroupUpdate),
-    /// Group member kick
-    Kick(GroupKick),
+    /// Signed membership or metadata change (create, invite, join, leave, update, kick)
+    Operation(SignedGroupOperation),
+    /// Membership log synchronization
+    Sync(GroupLogSync),
+    /// Group chat message, encrypted under the sender's sender key
+    Message(EncryptedGroupMessage),
+    /// Member's sender key, sent over a pairwise session
+    SenderKey(SenderKeyDistribution),
 }
 
 impl GroupMessagingMessage {
     /// Get the group ID for this message
     pub fn group_id(&self) -> &GroupId {
         match self {
-            GroupMessagingMessage::Create(create) => create.group_id(),
-            GroupMessagingMessage::Invite(invite) => &invite.group_id,
-            GroupMessagingMessage::Join(join) => &join.group_id,
-            GroupMessagingMessage::Leave(leave) => &leave.group_id,
+            GroupMessagingMessage::Operation(operation) => operation.group_id(),
+            GroupMessagingMessage::Sync(sync) => &sync.group_id,
             GroupMessagingMessage::Message(message) => &message.group_id,
-            GroupMessagingMessage::Update(update) => &update.group_id,
-            GroupMessagingMessage::Kick(kick) => &kick.group_id,
+            GroupMessagingMessage::SenderKey(distribution) => &distribution.group_id,
         }
     }
 
     /// Get the corresponding NoisePayloadType for this message
     pub fn payload_type(&self) -> NoisePayloadType {
         match self {
-            GroupMessagingMessage::Create(_) => NoisePayloadType::GroupCreate,
-            GroupMessagingMessage::Invite(_) => NoisePayloadType::GroupInvite,
-            GroupMessagingMessage::Join(_) => NoisePayloadType::GroupJoin,
-            GroupMessagingMessage::Leave(_) => NoisePayloadType::GroupLeave,
+            GroupMessagingMessage::Operation(operation) => operation.payload_type(),
+            GroupMessagingMessage::Sync(sync) => sync.payload_type(),
             GroupMessagingMessage::Message(_) => NoisePayloadType::GroupMessage,
-            GroupMessagingMessage::Update(_) => NoisePayloadType::GroupUpdate,
-            GroupMessagingMessage::Kick(_) => NoisePayloadType::GroupKick,
+            GroupMessagingMessage::SenderKey(_) => NoisePayloadType::GroupSenderKey,
         }
     }
+
+    /// Encode as a Noise payload
+    pub fn to_noise_payload(&self) -> Result<NoisePayload> {
+        let data = match self {
+            GroupMessagingMessage::Operation(operation) => bincode::serialize(operation),
+            GroupMessagingMessage::Sync(sync) => bincode::serialize(sync),
+            GroupMessagingMessage::Message(message) => bincode::serialize(message),
+            GroupMessagingMessage::SenderKey(distribution) => bincode::serialize(distribution),
+        }
+        .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        Ok(NoisePayload::new(self.payload_type(), data))
+    }
+
+    /// Decode from a Noise payload
+    ///
+    /// Operations must arrive under the payload type of their kind.
+    pub fn from_noise_payload(payload: &NoisePayload) -> Result<Self> {
+        let message = match payload.payload_type {
+            NoisePayloadType::GroupCreate
+            | NoisePayloadType::GroupInvite
+            | NoisePayloadType::GroupJoin
+            | NoisePayloadType::GroupLeave
+            | NoisePayloadType::GroupUpdate
+            | NoisePayloadType::GroupKick => {
+                bincode::deserialize(&payload.data).map(GroupMessagingMessage::Operation)
+            }
+            NoisePayloadType::GroupSync => {
+                bincode::deserialize(&payload.data).map(GroupMessagingMessage::Sync)
+            }
+            NoisePayloadType::GroupMessage => {
+                bincode::deserialize(&payload.data).map(GroupMessagingMessage::Message)
+            }
+            NoisePayloadType::GroupSenderKey => {
+                bincode::deserialize(&payload.data).map(GroupMessagingMessage::SenderKey)
+            }
+            _ => return Err(BitchatError::invalid_packet("Not a group messaging payload")),
+        };
+        let message =
+            message.map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        if message.payload_type() != payload.payload_type {
+            return Err(BitchatError::invalid_packet(
+                "Group operation sent under the wrong payload type",
+            ));
+        }
+        Ok(message)
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -608,23 +660,48 @@ impl GroupMessagingMessage {
 // ----------------------------------------------------------------------------
 
 /// Manages group memberships and message processing
+///
+/// Local changes are signed with our identity key and appended to the group's
+/// log; received operations are merged into it, and the group metadata is
+/// whatever replaying the log yields.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GroupManager {
-    /// Groups this peer is a member of (group_id -> metadata)
-    groups: BTreeMap<GroupId, GroupMetadata>,
+    /// Logs of groups this peer is a member of (group_id -> log)
+    groups: BTreeMap<GroupId, GroupLog>,
+    /// Logs of groups this peer was invited to but has not joined
+    invitations: BTreeMap<GroupId, GroupLog>,
     /// Local peer ID
     local_peer_id: PeerId,
+    /// Our Ed25519 identity key, which signs our group operations
+    signing_key: [u8; 32],
+    /// Our sender keys and those of the other members
+    sender_keys: SenderKeyStore,
+    /// Groups whose sender key was rotated and still has to be distributed
+    key_rotations: BTreeSet<GroupId>,
 }
 
 impl GroupManager {
     /// Create a new group manager
-    pub fn new(local_peer_id: PeerId) -> Self {
+    pub fn new(local_peer_id: PeerId, identity_key: &IdentityKeyPair) -> Self {
         Self {
             groups: BTreeMap::new(),
+            invitations: BTreeMap::new(),
             local_peer_id,
+            signing_key: identity_key.private_key_bytes(),
+            sender_keys: SenderKeyStore::new(),
+            key_rotations: BTreeSet::new(),
         }
     }
 
+    /// Sign future group operations with a different identity key
+    pub fn set_identity_key(&mut self, identity_key: &IdentityKeyPair) {
+        self.signing_key = identity_key.private_key_bytes();
+    }
+
+    fn identity_key(&self) -> Result<IdentityKeyPair> {
+        IdentityKeyPair::from_bytes(&self.signing_key)
+    }
+
     /// Create a new group
     pub fn create_group(
         &mut self,
@@ -632,37 +709,92 @@ impl GroupManager {
         description: Option<String>,
         nickname: String,
         fingerprint: Fingerprint,
-    ) -> Result<GroupCreate> {
+    ) -> Result<SignedGroupOperation> {
         let metadata =
             GroupMetadata::new(name, description, self.local_peer_id, nickname, fingerprint)?;
+        let create = SignedGroupOperation::new_signed(
+            self.local_peer_id,
+            0,
+            Vec::new(),
+            GroupOperation::Create(GroupCreate::new(metadata, None)),
+            &self.identity_key()?,
+        )?;
 
-        let group_id = metadata.group_id.clone();
-        self.groups.insert(group_id, metadata.clone());
+        let log = GroupLog::new(create.clone())?;
+        let group_id = log.group_id().clone();
+        self.sender_keys.ensure_own_key(&group_id)?;
+        self.groups.insert(group_id, log);
 
-        Ok(GroupCreate::new(metadata, None))
+        Ok(create)
+    }
+
+    /// Invite a peer to a group
+    ///
+    /// The invitee can only join by signing with `invitee_signing_key`. Besides
+    /// broadcasting the returned operation to the group, send the invitee the
+    /// whole log (see [`Self::full_log`]).
+    pub fn invite_member(
+        &mut self,
+        group_id: &GroupId,
+        invitee: PeerId,
+        invitee_signing_key: [u8; 32],
+        message: Option<String>,
+    ) -> Result<SignedGroupOperation> {
+        let invite = GroupInvite::new(
+            group_id.clone(),
+            self.local_peer_id,
+            invitee,
+            invitee_signing_key,
+            message,
+        );
+        self.append(group_id, GroupOperation::Invite(invite))
     }
 
-    /// Process a group invitation
-    pub fn process_invite(&mut self, invite: &GroupInvite) -> Result<()> {
-        if invite.is_expired() {
+    /// Store the log of a group we were invited to, received from `from`
+    fn process_invitation(&mut self, from: PeerId, sync: GroupLogSync) -> Result<()> {
+        let log = GroupLog::from_operations(sync.operations)?;
+        if *log.group_id() != sync.group_id || !log.metadata().is_member(&from) {
+            return Err(BitchatError::invalid_packet(
+                "Group invitation not sent by a member",
+            ));
+        }
+        let public_key = self.identity_key()?.public_key_bytes();
+        if log.invitation_key(&self.local_peer_id) != Some(&public_key) {
+            return Err(BitchatError::invalid_packet("Not invited to this group"));
+        }
+        let expired = log
+            .operations_since(&[])
+            .iter()
+            .rev()
+            .find_map(|op| match &op.operation {
+                GroupOperation::Invite(invite) if invite.invitee == self.local_peer_id => {
+                    Some(invite.is_expired())
+                }
+                _ => None,
+            })
+            .unwrap_or(true);
+        if expired {
             return Err(BitchatError::invalid_packet("Group invitation has expired"));
         }
 
         // Don't auto-join - this would typically trigger a user prompt
-        // For now, just store the invitation for manual processing
+        self.invitations.insert(sync.group_id, log);
         Ok(())
     }
 
-    /// Join a group
+    /// Groups we have been invited to and can join
+    pub fn get_invitations(&self) -> Vec<&GroupMetadata> {
+        self.invitations.values().map(GroupLog::metadata).collect()
+    }
+
+    /// Join a group we have been invited to
     pub fn join_group(
         &mut self,
-        group_metadata: GroupMetadata,
+        group_id: &GroupId,
         nickname: String,
         fingerprint: Fingerprint,
-    ) -> Result<GroupJoin> {
-        let group_id = group_metadata.group_id.clone();
-
-        if self.groups.contains_key(&group_id) {
+    ) -> Result<SignedGroupOperation> {
+        if self.groups.contains_key(group_id) {
             return Err(BitchatError::invalid_packet(
                 "Already a member of this group",
             ));
@@ -670,10 +802,23 @@ impl GroupManager {
 
         let member =
             GroupMember::new(self.local_peer_id, nickname, fingerprint, GroupRole::Member)?;
+        let identity_key = self.identity_key()?;
+        let log = self
+            .invitations
+            .get_mut(group_id)
+            .ok_or_else(|| BitchatError::invalid_packet("No invitation to this group"))?;
+        let join = log.append(
+            self.local_peer_id,
+            GroupOperation::Join(GroupJoin::new(group_id.clone(), member, None)),
+            &identity_key,
+        )?;
 
-        self.groups.insert(group_id.clone(), group_metadata);
+        self.sender_keys.ensure_own_key(group_id)?;
+        if let Some(log) = self.invitations.remove(group_id) {
+            self.groups.insert(group_id.clone(), log);
+        }
 
-        Ok(GroupJoin::new(group_id, member, None))
+        Ok(join)
     }
 
     /// Leave a group
@@ -681,18 +826,136 @@ impl GroupManager {
         &mut self,
         group_id: &GroupId,
         reason: Option<String>,
-    ) -> Result<GroupLeave> {
-        if !self.groups.contains_key(group_id) {
-            return Err(BitchatError::invalid_packet("Not a member of this group"));
+    ) -> Result<SignedGroupOperation> {
+        let leave = GroupLeave::new(group_id.clone(), self.local_peer_id, reason);
+        self.append(group_id, GroupOperation::Leave(leave))
+    }
+
+    /// Change a group's name, description or settings
+    pub fn update_group(&mut self, update: GroupUpdate) -> Result<SignedGroupOperation> {
+        let group_id = update.group_id.clone();
+        self.append(&group_id, GroupOperation::Update(update))
+    }
+
+    /// Remove a member from a group
+    pub fn kick_member(
+        &mut self,
+        group_id: &GroupId,
+        member: PeerId,
+        reason: Option<String>,
+    ) -> Result<SignedGroupOperation> {
+        let kick = GroupKick::new(group_id.clone(), self.local_peer_id, member, reason);
+        self.append(group_id, GroupOperation::Kick(kick))
+    }
+
+    /// Sign a local operation and add it to the group's log
+    fn append(
+        &mut self,
+        group_id: &GroupId,
+        operation: GroupOperation,
+    ) -> Result<SignedGroupOperation> {
+        let identity_key = self.identity_key()?;
+        let log = self
+            .groups
+            .get_mut(group_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Not a member of this group"))?;
+        let members = log.metadata().member_ids();
+        let op = log.append(self.local_peer_id, operation, &identity_key)?;
+        self.process_removals(group_id, &members)?;
+        Ok(op)
+    }
+
+    /// Merge an operation received from the mesh into its group's log
+    pub fn process_operation(&mut self, op: SignedGroupOperation) -> Result<()> {
+        let group_id = op.group_id().clone();
+        if let Some(log) = self.invitations.get_mut(&group_id) {
+            log.insert(op)?;
+            return Ok(());
         }
 
-        self.groups.remove(group_id);
+        let log = self
+            .groups
+            .get_mut(&group_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Not a member of this group"))?;
+        let members = log.metadata().member_ids();
+        log.insert(op)?;
+        self.process_removals(&group_id, &members)
+    }
 
-        Ok(GroupLeave::new(
-            group_id.clone(),
-            self.local_peer_id,
-            reason,
-        ))
+    /// Whether a group's log is waiting for operations we have not received
+    ///
+    /// If so, send a [`Self::sync_request`] to another member.
+    pub fn needs_sync(&self, group_id: &GroupId) -> bool {
+        self.groups
+            .get(group_id)
+            .is_some_and(GroupLog::has_missing_parents)
+    }
+
+    /// Sync announcing our log heads, asking for whatever we lack
+    pub fn sync_request(&self, group_id: &GroupId) -> Result<GroupLogSync> {
+        let log = self
+            .groups
+            .get(group_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Not a member of this group"))?;
+        Ok(GroupLogSync {
+            group_id: group_id.clone(),
+            heads: log.heads(),
+            operations: Vec::new(),
+        })
+    }
+
+    /// Sync carrying a group's whole log, e.g. for a new invitee
+    pub fn full_log(&self, group_id: &GroupId) -> Result<GroupLogSync> {
+        let log = self
+            .groups
+            .get(group_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Not a member of this group"))?;
+        Ok(GroupLogSync {
+            group_id: group_id.clone(),
+            heads: log.heads(),
+            operations: log.operations_since(&[]),
+        })
+    }
+
+    /// Process a sync received from `from` over a pairwise Noise session
+    ///
+    /// For a group we are not in, the sync is treated as an invitation. Otherwise
+    /// its operations are merged, and the reply (if any) carries what `from`
+    /// lacks and asks for what we lack. Operations are signed, so they are merged
+    /// whoever relays them, but only members and invitees are answered.
+    pub fn process_sync(
+        &mut self,
+        from: PeerId,
+        sync: GroupLogSync,
+    ) -> Result<Option<GroupLogSync>> {
+        let group_id = sync.group_id.clone();
+        let Some(log) = self.groups.get_mut(&group_id) else {
+            self.process_invitation(from, sync)?;
+            return Ok(None);
+        };
+
+        let members = log.metadata().member_ids();
+        for op in sync.operations {
+            log.insert(op)?;
+        }
+        self.process_removals(&group_id, &members)?;
+
+        let Some(log) = self
+            .groups
+            .get(&group_id)
+            .filter(|log| log.metadata().is_member(&from) || log.invitation_key(&from).is_some())
+        else {
+            return Ok(None);
+        };
+        let operations = log.operations_since(&sync.heads);
+        if operations.is_empty() && log.knows_all(&sync.heads) {
+            return Ok(None);
+        }
+        Ok(Some(GroupLogSync {
+            group_id,
+            heads: log.heads(),
+            operations,
+        }))
     }
 
     /// Send a message to a group
@@ -708,52 +971,139 @@ impl GroupManager {
         ))
     }
 
-    /// Process an incoming group message
-    pub fn process_message(&mut self, message: &GroupMessagingMessage) -> Result<()> {
-        let group_id = message.group_id();
+    /// Encrypt a message to a group under our sender key
+    ///
+    /// The result is encrypted once and can be broadcast to every member holding
+    /// our sender key.
+    pub fn encrypt_message(
+        &mut self,
+        group_id: &GroupId,
+        content: String,
+    ) -> Result<(GroupMessage, EncryptedGroupMessage)> {
+        let message = self.send_message(group_id, content)?;
+        let plaintext = bincode::serialize(&message)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        let encrypted = self
+            .sender_keys
+            .encrypt(group_id, self.local_peer_id, &plaintext)?;
+        Ok((message, encrypted))
+    }
 
-        match message {
-            GroupMessagingMessage::Create(create) => {
-                // Store the group metadata
-                self.groups
-                    .insert(group_id.clone(), create.metadata.clone());
-            }
+    /// Decrypt a message broadcast to one of our groups
+    ///
+    /// The sender must be a current member, and the message inside must name the
+    /// same group and sender as its signed header.
+    pub fn decrypt_message(&mut self, encrypted: &EncryptedGroupMessage) -> Result<GroupMessage> {
+        if !self.is_group_member(&encrypted.group_id, &encrypted.sender) {
+            return Err(BitchatError::invalid_packet("Sender is not a group member"));
+        }
 
-            GroupMessagingMessage::Join(join) => {
-                if let Some(group) = self.groups.get_mut(group_id) {
-                    group.add_member(join.member.clone())?;
-                }
-            }
+        let plaintext = self.sender_keys.decrypt(encrypted)?;
+        let message: GroupMessage = bincode::deserialize(&plaintext)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        if message.group_id != encrypted.group_id || message.sender != encrypted.sender {
+            return Err(BitchatError::invalid_packet(
+                "Group message does not match its header",
+            ));
+        }
+        Ok(message)
+    }
 
-            GroupMessagingMessage::Leave(leave) => {
-                if let Some(group) = self.groups.get_mut(group_id) {
-                    group.remove_member(&leave.peer_id)?;
-                }
+    /// Our current sender key for a group, to send to another member
+    ///
+    /// Must only be sent over a pairwise Noise session with a current member.
+    pub fn sender_key_distribution(&self, group_id: &GroupId) -> Result<SenderKeyDistribution> {
+        if !self.groups.contains_key(group_id) {
+            return Err(BitchatError::invalid_packet("Not a member of this group"));
+        }
+        self.sender_keys.distribution(group_id, self.local_peer_id)
+    }
+
+    /// Store a sender key received from `from` over a pairwise Noise session
+    ///
+    /// Only a member's own key is accepted, so one member cannot stand in for
+    /// another.
+    pub fn process_sender_key(
+        &mut self,
+        from: PeerId,
+        distribution: &SenderKeyDistribution,
+    ) -> Result<()> {
+        if distribution.sender != from {
+            return Err(BitchatError::invalid_packet(
+                "Sender key distributed on behalf of another peer",
+            ));
+        }
+        if !self.is_group_member(&distribution.group_id, &from) {
+            return Err(BitchatError::invalid_packet("Sender is not a group member"));
+        }
+        self.sender_keys.process_distribution(distribution)
+    }
+
+    /// Whether we hold a member's sender key and can read its messages
+    pub fn has_sender_key(&self, group_id: &GroupId, peer_id: &PeerId) -> bool {
+        self.sender_keys.has_peer_key(group_id, peer_id)
+    }
+
+    /// Take the new sender keys of groups that lost a member
+    ///
+    /// Each must be sent to every remaining member before they can read what we
+    /// send next.
+    pub fn take_key_rotations(&mut self) -> Vec<SenderKeyDistribution> {
+        let rotated = core::mem::take(&mut self.key_rotations);
+        rotated
+            .iter()
+            .filter_map(|group_id| self.sender_key_distribution(group_id).ok())
+            .collect()
+    }
+
+    /// Forget the members a log change removed from a group
+    fn process_removals(&mut self, group_id: &GroupId, members: &[PeerId]) -> Result<()> {
+        for peer_id in members {
+            if !self.is_group_member(group_id, peer_id) {
+                self.forget_member(group_id, peer_id)?;
             }
+        }
+        Ok(())
+    }
 
-            GroupMessagingMessage::Update(update) => {
-                if let Some(group) = self.groups.get_mut(group_id) {
-                    group.update(update.name.clone(), update.description.clone())?;
+    /// Drop a member's sender key and rotate ours so it cannot read what follows
+    fn forget_member(&mut self, group_id: &GroupId, peer_id: &PeerId) -> Result<()> {
+        if *peer_id == self.local_peer_id {
+            self.groups.remove(group_id);
+            self.sender_keys.remove_group(group_id);
+            self.key_rotations.remove(group_id);
+            return Ok(());
+        }
+        if !self.groups.contains_key(group_id) {
+            return Ok(());
+        }
 
-                    if let Some(ref settings) = update.settings {
-                        group.settings = settings.clone();
-                    }
-                }
+        self.sender_keys.remove_peer(group_id, peer_id);
+        self.sender_keys.rotate(group_id)?;
+        self.key_rotations.insert(group_id.clone());
+        Ok(())
+    }
+
+    /// Process an incoming group message
+    pub fn process_message(&mut self, message: &GroupMessagingMessage) -> Result<()> {
+        match message {
+            GroupMessagingMessage::Operation(operation) => {
+                self.process_operation(operation.clone())?;
             }
 
-            GroupMessagingMessage::Kick(kick) => {
-                if let Some(group) = self.groups.get_mut(group_id) {
-                    group.remove_member(&kick.kicked_member)?;
-                }
+            GroupMessagingMessage::Sync(_) => {
+                // Syncs are handled separately by process_sync, which may reply
+                // and checks them against the authenticated Noise peer
             }
 
-            GroupMessagingMessage::Invite(_) => {
-                // Invitations are handled separately by process_invite
+            GroupMessagingMessage::SenderKey(_) => {
+                // Sender keys are handled separately by process_sender_key, which
+                // checks them against the authenticated Noise peer
             }
 
             GroupMessagingMessage::Message(_) => {
-                // Regular messages are just stored/displayed
-                // Message storage would be handled by the message store
+                // Messages are handled separately by decrypt_message, which
+                // returns the plaintext for display
             }
         }
 
@@ -762,12 +1112,12 @@ impl GroupManager {
 
     /// Get group metadata by ID
     pub fn get_group(&self, group_id: &GroupId) -> Option<&GroupMetadata> {
-        self.groups.get(group_id)
+        self.groups.get(group_id).map(GroupLog::metadata)
     }
 
     /// Get all groups this peer is a member of
     pub fn get_all_groups(&self) -> Vec<&GroupMetadata> {
-        self.groups.values().collect()
+        self.groups.values().map(GroupLog::metadata).collect()
     }
 
     /// Check if peer is a member of a group
@@ -775,10 +1125,16 @@ impl GroupManager {
         self.groups.contains_key(group_id)
     }
 
+    /// Check if another peer is a current member of one of our groups
+    fn is_group_member(&self, group_id: &GroupId, peer_id: &PeerId) -> bool {
+        self.get_group(group_id)
+            .is_some_and(|group| group.is_member(peer_id))
+    }
+
     /// Get groups where this peer has admin privileges
     pub fn get_admin_groups(&self) -> Vec<&GroupMetadata> {
-        self.groups
-            .values()
+        self.get_all_groups()
+            .into_iter()
             .filter(|group| {
                 group
                     .get_member(&self.local_peer_id)
@@ -857,7 +1213,8 @@ mod tests {
     fn test_group_manager() {
         let local_peer = PeerId::new([1; 8]);
         let fingerprint = Fingerprint::new([1; 32]);
-        let mut manager = GroupManager::new(local_peer);
+        let identity = IdentityKeyPair::generate().unwrap();
+        let mut manager = GroupManager::new(local_peer, &identity);
 
         // Create a group
         let group_create = manager
@@ -871,6 +1228,7 @@ mod tests {
 
         assert_eq!(manager.get_all_groups().len(), 1);
         assert!(manager.is_member_of(group_create.group_id()));
+        assert_eq!(group_create.signing_public_key, identity.public_key_bytes());
 
         // Send a message
         let message = manager
@@ -881,6 +1239,92 @@ mod tests {
         assert_eq!(message.content, "Hello, group!");
     }
 
+    #[test]
+    fn test_invited_member_joins_and_is_kicked() {
+        let alice_id = PeerId::new([1; 8]);
+        let bob_id = PeerId::new([2; 8]);
+        let bob_identity = IdentityKeyPair::generate().unwrap();
+        let mut alice = GroupManager::new(alice_id, &IdentityKeyPair::generate().unwrap());
+        let mut bob = GroupManager::new(bob_id, &bob_identity);
+
+        let create = alice
+            .create_group(
+                "Test Group".to_string(),
+                None,
+                "Alice".to_string(),
+                Fingerprint::new([1; 32]),
+            )
+            .unwrap();
+        let group_id = create.group_id().clone();
+
+        // Bob can only join once invited, and learns the group from its log
+        assert!(bob
+            .join_group(&group_id, "Bob".to_string(), Fingerprint::new([2; 32]))
+            .is_err());
+        alice
+            .invite_member(&group_id, bob_id, bob_identity.public_key_bytes(), None)
+            .unwrap();
+        let invitation = alice.full_log(&group_id).unwrap();
+        assert!(bob
+            .process_sync(PeerId::new([9; 8]), invitation.clone())
+            .is_err());
+        assert!(bob.process_sync(alice_id, invitation).unwrap().is_none());
+        assert_eq!(bob.get_invitations().len(), 1);
+        let join = bob
+            .join_group(&group_id, "Bob".to_string(), Fingerprint::new([2; 32]))
+            .unwrap();
+        let payload = GroupMessagingMessage::Operation(join)
+            .to_noise_payload()
+            .unwrap();
+        assert_eq!(payload.payload_type, NoisePayloadType::GroupJoin);
+        let mislabeled = NoisePayload::new(NoisePayloadType::GroupKick, payload.data.clone());
+        assert!(GroupMessagingMessage::from_noise_payload(&mislabeled).is_err());
+        alice
+            .process_message(&GroupMessagingMessage::from_noise_payload(&payload).unwrap())
+            .unwrap();
+        assert_eq!(alice.get_group(&group_id), bob.get_group(&group_id));
+        assert_eq!(
+            alice.sync_request(&group_id).unwrap().heads,
+            bob.sync_request(&group_id).unwrap().heads
+        );
+
+        // Bob may not kick the owner
+        assert!(bob.kick_member(&group_id, alice_id, None).is_err());
+
+        // Keys are only accepted from the member they belong to
+        let alice_key = alice.sender_key_distribution(&group_id).unwrap();
+        assert!(bob.process_sender_key(bob_id, &alice_key).is_err());
+        bob.process_sender_key(alice_id, &alice_key).unwrap();
+        let bob_key = bob.sender_key_distribution(&group_id).unwrap();
+        alice.process_sender_key(bob_id, &bob_key).unwrap();
+
+        let (_, encrypted) = alice
+            .encrypt_message(&group_id, "Hello, Bob!".to_string())
+            .unwrap();
+        let message = bob.decrypt_message(&encrypted).unwrap();
+        assert_eq!(message.sender, alice_id);
+        assert_eq!(message.content, "Hello, Bob!");
+
+        // Kicking Bob rotates Alice's key, so Bob cannot read what follows
+        let kick = alice.kick_member(&group_id, bob_id, None).unwrap();
+        assert!(!alice.has_sender_key(&group_id, &bob_id));
+        let rotations = alice.take_key_rotations();
+        assert_eq!(rotations.len(), 1);
+        assert_eq!(rotations[0].generation, alice_key.generation + 1);
+        assert!(alice.take_key_rotations().is_empty());
+
+        let (_, encrypted) = alice
+            .encrypt_message(&group_id, "Bob is gone".to_string())
+            .unwrap();
+        assert!(bob.decrypt_message(&encrypted).is_err());
+
+        // Bob drops the group once he processes his own kick
+        bob.process_message(&GroupMessagingMessage::Operation(kick))
+            .unwrap();
+        assert!(!bob.is_member_of(&group_id));
+        assert!(bob.sender_key_distribution(&group_id).is_err());
+    }
+
     #[test]
     fn test_group_roles() {
         assert!(GroupRole::Owner.can_invite());
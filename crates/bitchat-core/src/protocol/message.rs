@@ -7,6 +7,8 @@ use alloc::{string::String, vec::Vec};
 use core::convert::TryInto;
 use serde::{Deserialize, Serialize};
 
+use crate::protocol::crypto::IdentityKeyPair;
+use crate::protocol::packet::{BitchatPacket, MessageType, PacketFlags};
 use crate::types::{PeerId, Timestamp};
 use crate::{BitchatError, Result};
 
@@ -24,6 +26,9 @@ pub enum NoisePayloadType {
     ReadReceipt = 0x02,
     /// Message delivery confirmation
     Delivered = 0x03,
+    /// Signed favorite/unfavorite notification carrying the sender's Nostr key
+    /// (experimental; canonical apps send favorites as private messages)
+    FavoriteNotification = 0x04,
     /// QR verification challenge
     VerifyChallenge = 0x10,
     /// QR verification response
@@ -36,6 +41,8 @@ pub enum NoisePayloadType {
     FileChunk = 0x22,
     /// File transfer completion
     FileComplete = 0x23,
+    /// Request to resend missing file chunks
+    FileResume = 0x24,
     /// Group creation
     GroupCreate = 0x30,
     /// Group member invite
@@ -50,6 +57,10 @@ pub enum NoisePayloadType {
     GroupUpdate = 0x35,
     /// Group member kick/remove
     GroupKick = 0x36,
+    /// Member's sender key for encrypting group messages
+    GroupSenderKey = 0x37,
+    /// Group membership log synchronization
+    GroupSync = 0x38,
     /// Device announcement for multi-device sync
     DeviceAnnouncement = 0x40,
     /// Session synchronization request
@@ -58,6 +69,10 @@ pub enum NoisePayloadType {
     SessionSyncResponse = 0x42,
     /// Device heartbeat
     DeviceHeartbeat = 0x43,
+    /// Request from a new device to be linked to an identity
+    DeviceLinkRequest = 0x44,
+    /// Identity handed to a newly linked device
+    DeviceLinkGrant = 0x45,
     /// Version hello with capability announcement
     VersionHello = 0x50,
     /// Version acknowledgment with negotiated capabilities
@@ -79,6 +94,8 @@ impl NoisePayloadType {
 
             // Experimental message types (conditionally available)
             #[cfg(feature = "experimental")]
+            0x04 => Ok(NoisePayloadType::FavoriteNotification),
+            #[cfg(feature = "experimental")]
             0x20 => Ok(NoisePayloadType::FileOffer),
             #[cfg(feature = "experimental")]
             0x21 => Ok(NoisePayloadType::FileAccept),
@@ -87,6 +104,8 @@ impl NoisePayloadType {
             #[cfg(feature = "experimental")]
             0x23 => Ok(NoisePayloadType::FileComplete),
             #[cfg(feature = "experimental")]
+            0x24 => Ok(NoisePayloadType::FileResume),
+            #[cfg(feature = "experimental")]
             0x30 => Ok(NoisePayloadType::GroupCreate),
             #[cfg(feature = "experimental")]
             0x31 => Ok(NoisePayloadType::GroupInvite),
@@ -101,6 +120,10 @@ impl NoisePayloadType {
             #[cfg(feature = "experimental")]
             0x36 => Ok(NoisePayloadType::GroupKick),
             #[cfg(feature = "experimental")]
+            0x37 => Ok(NoisePayloadType::GroupSenderKey),
+            #[cfg(feature = "experimental")]
+            0x38 => Ok(NoisePayloadType::GroupSync),
+            #[cfg(feature = "experimental")]
             0x40 => Ok(NoisePayloadType::DeviceAnnouncement),
             #[cfg(feature = "experimental")]
             0x41 => Ok(NoisePayloadType::SessionSyncRequest),
@@ -108,6 +131,8 @@ impl NoisePayloadType {
             0x42 => Ok(NoisePayloadType::SessionSyncResponse),
             #[cfg(feature = "experimental")]
             0x43 => Ok(NoisePayloadType::DeviceHeartbeat),
+            0x44 => Ok(NoisePayloadType::DeviceLinkRequest),
+            0x45 => Ok(NoisePayloadType::DeviceLinkGrant),
             #[cfg(feature = "experimental")]
             0x50 => Ok(NoisePayloadType::VersionHello),
             #[cfg(feature = "experimental")]
@@ -127,9 +152,9 @@ impl NoisePayloadType {
 
             // Experimental types conditionally supported
             #[cfg(feature = "experimental")]
-            0x20..=0x23 | 0x30..=0x36 | 0x40..=0x43 | 0x50..=0x52 => true,
+            0x04 | 0x20..=0x24 | 0x30..=0x38 | 0x40..=0x45 | 0x50..=0x52 => true,
             #[cfg(not(feature = "experimental"))]
-            0x20..=0x23 | 0x30..=0x36 | 0x40..=0x43 | 0x50..=0x52 => false,
+            0x04 | 0x20..=0x24 | 0x30..=0x38 | 0x40..=0x45 | 0x50..=0x52 => false,
 
             _ => false,
         }
@@ -171,6 +196,14 @@ impl MessageFlags {
     /// Mentions array is present
     pub const HAS_MENTIONS: Self = Self(0x20);
 
+    /// ID of the message being replied to is present
+    ///
+    /// Canonical apps use this bit for `hasChannel`, so it is only set on messages
+    /// to peers that negotiated [`CapabilityId::message_replies`].
+    ///
+    /// [`CapabilityId::message_replies`]: crate::protocol::CapabilityId::message_replies
+    pub const HAS_REPLY_TO: Self = Self(0x40);
+
     /// Create flags from raw byte
     pub const fn new(value: u8) -> Self {
         Self(value)
@@ -211,6 +244,11 @@ impl MessageFlags {
         (self.0 & Self::HAS_MENTIONS.0) != 0
     }
 
+    /// Check if the ID of a replied-to message is present
+    pub const fn has_reply_to(self) -> bool {
+        (self.0 & Self::HAS_REPLY_TO.0) != 0
+    }
+
     /// Set relay flag
     pub fn with_relay(mut self) -> Self {
         self.0 |= Self::IS_RELAY.0;
@@ -240,6 +278,12 @@ impl MessageFlags {
         self.0 |= Self::HAS_MENTIONS.0;
         self
     }
+
+    /// Set reply-to flag
+    pub fn with_reply_to(mut self) -> Self {
+        self.0 |= Self::HAS_REPLY_TO.0;
+        self
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -267,6 +311,8 @@ pub struct BitchatMessage {
     pub sender_peer_id: Option<PeerId>,
     /// List of mentioned users
     pub mentions: Option<Vec<String>>,
+    /// ID of the message this one replies to
+    pub reply_to: Option<String>,
 }
 
 impl BitchatMessage {
@@ -282,6 +328,7 @@ impl BitchatMessage {
             recipient_nickname: None,
             sender_peer_id: None,
             mentions: None,
+            reply_to: None,
         }
     }
 
@@ -315,6 +362,48 @@ impl BitchatMessage {
         self
     }
 
+    /// Set mentions from the `@nickname` words in the content
+    pub fn with_mentions_from_content(self) -> Self {
+        let mentions = Self::mentions_in(&self.content);
+        self.with_mentions(mentions)
+    }
+
+    /// Set the ID of the message this one replies to
+    pub fn with_reply_to(mut self, message_id: String) -> Self {
+        self.flags = self.flags.with_reply_to();
+        self.reply_to = Some(message_id);
+        self
+    }
+
+    /// Nicknames mentioned as `@nickname` in message content, without duplicates
+    ///
+    /// Trailing punctuation is not part of a mention, so `@bob,` mentions `bob`.
+    pub fn mentions_in(content: &str) -> Vec<String> {
+        let mut mentions: Vec<String> = Vec::new();
+        for word in content.split_whitespace() {
+            let Some(nickname) = word.strip_prefix('@') else {
+                continue;
+            };
+            let nickname = nickname.trim_end_matches(|c: char| c.is_ascii_punctuation());
+            if nickname.is_empty()
+                || nickname.len() > 255
+                || mentions.len() == u8::MAX as usize
+                || mentions.iter().any(|known| known == nickname)
+            {
+                continue;
+            }
+            mentions.push(String::from(nickname));
+        }
+        mentions
+    }
+
+    /// Whether the message mentions a nickname
+    pub fn mentions_nickname(&self, nickname: &str) -> bool {
+        self.mentions
+            .as_ref()
+            .is_some_and(|mentions| mentions.iter().any(|mention| mention == nickname))
+    }
+
     /// Validate message structure
     pub fn validate(&self) -> Result<()> {
         // Check flag consistency
@@ -344,6 +433,12 @@ impl BitchatMessage {
             ));
         }
 
+        if self.flags.has_reply_to() && self.reply_to.is_none() {
+            return Err(BitchatError::invalid_packet(
+                "Reply-to flag set but no replied-to message",
+            ));
+        }
+
         // Check field lengths
         if self.id.len() > 255 {
             return Err(BitchatError::invalid_packet("Message ID too long"));
@@ -371,6 +466,23 @@ impl BitchatMessage {
             }
         }
 
+        if let Some(ref mentions) = self.mentions {
+            if mentions.len() > u8::MAX as usize {
+                return Err(BitchatError::invalid_packet("Too many mentions"));
+            }
+            if mentions.iter().any(|mention| mention.len() > 255) {
+                return Err(BitchatError::invalid_packet("Mention too long"));
+            }
+        }
+
+        if let Some(ref reply_to) = self.reply_to {
+            if reply_to.len() > 255 {
+                return Err(BitchatError::invalid_packet(
+                    "Replied-to message ID too long",
+                ));
+            }
+        }
+
         Ok(())
     }
 
@@ -435,6 +547,14 @@ impl BitchatMessage {
             }
         }
 
+        if self.flags.has_reply_to() {
+            if let Some(ref reply_to) = self.reply_to {
+                let reply_to_bytes = reply_to.as_bytes();
+                bytes.push(reply_to_bytes.len() as u8);
+                bytes.extend_from_slice(reply_to_bytes);
+            }
+        }
+
         Ok(bytes)
     }
 
@@ -596,6 +716,26 @@ impl BitchatMessage {
             None
         };
 
+        let reply_to = if flags.has_reply_to() {
+            if bytes.len() < offset + 1 {
+                return Err(BitchatError::invalid_packet(
+                    "Message too short for replied-to message ID",
+                ));
+            }
+            let reply_to_length = bytes[offset] as usize;
+            offset += 1;
+            if bytes.len() < offset + reply_to_length {
+                return Err(BitchatError::invalid_packet(
+                    "Message too short for replied-to message ID",
+                ));
+            }
+            let reply_to = String::from_utf8(bytes[offset..offset + reply_to_length].to_vec())
+                .map_err(|_| BitchatError::invalid_packet("Invalid replied-to message ID"))?;
+            Some(reply_to)
+        } else {
+            None
+        };
+
         let message = Self {
             flags,
             timestamp,
@@ -606,6 +746,7 @@ impl BitchatMessage {
             recipient_nickname,
             sender_peer_id,
             mentions,
+            reply_to,
         };
 
         message.validate()?;
@@ -613,6 +754,44 @@ impl BitchatMessage {
     }
 }
 
+// ----------------------------------------------------------------------------
+// Public Message Packets
+// ----------------------------------------------------------------------------
+
+impl BitchatPacket {
+    /// Create a signed public message packet for every peer in the mesh
+    ///
+    /// The packet has no recipient, so transports relay it until its TTL runs out.
+    pub fn create_public_message(
+        sender_id: PeerId,
+        message: &BitchatMessage,
+        identity_keypair: &IdentityKeyPair,
+    ) -> Result<Self> {
+        let mut packet = BitchatPacket::new(
+            MessageType::Message,
+            sender_id,
+            None,
+            message.timestamp,
+            message.to_binary()?,
+            PacketFlags::NONE,
+        )?;
+        packet.sign(identity_keypair)?;
+
+        Ok(packet)
+    }
+
+    /// Parse the message carried by a public message packet
+    pub fn parse_public_message(&self) -> Result<BitchatMessage> {
+        if self.message_type() != MessageType::Message || !self.is_broadcast() {
+            return Err(BitchatError::invalid_packet(
+                "Expected a public message packet",
+            ));
+        }
+
+        BitchatMessage::from_binary(self.payload())
+    }
+}
+
 // ----------------------------------------------------------------------------
 // Noise Payload Wrapper
 // ----------------------------------------------------------------------------
@@ -728,6 +907,54 @@ mod tests {
         assert_eq!(message, parsed);
     }
 
+    #[test]
+    fn test_bitchat_message_reply_roundtrip() {
+        let message = BitchatMessage::new(
+            "msg456".to_string(),
+            "Bob".to_string(),
+            "@alice, @carol and @alice: agreed!".to_string(),
+        )
+        .with_mentions_from_content()
+        .with_reply_to("msg123".to_string());
+
+        assert_eq!(
+            message.mentions,
+            Some(vec!["alice".to_string(), "carol".to_string()])
+        );
+        assert!(message.mentions_nickname("carol"));
+        assert!(!message.mentions_nickname("bob"));
+        assert!(message.flags.has_reply_to());
+
+        let binary = message.to_binary().unwrap();
+        let parsed = BitchatMessage::from_binary(&binary).unwrap();
+        assert_eq!(message, parsed);
+        assert_eq!(parsed.reply_to.as_deref(), Some("msg123"));
+
+        // Messages without mentions carry no mention flag
+        let plain = BitchatMessage::new("msg789".into(), "Bob".into(), "email me @ home".into())
+            .with_mentions_from_content();
+        assert!(!plain.flags.has_mentions());
+    }
+
+    #[test]
+    fn test_public_message_packet_roundtrip() {
+        let identity = IdentityKeyPair::generate().unwrap();
+        let sender = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let message = BitchatMessage::new("msg-1".into(), "alice".into(), "hello everyone".into());
+
+        let packet = BitchatPacket::create_public_message(sender, &message, &identity).unwrap();
+        assert!(packet.is_broadcast());
+        assert_eq!(packet.header.timestamp, message.timestamp);
+        packet
+            .verify_signature(&identity.public_key_bytes())
+            .unwrap();
+        assert_eq!(packet.parse_public_message().unwrap(), message);
+
+        // Packets addressed to one peer are not public messages
+        let private = packet.with_recipient(PeerId::new([9; 8]));
+        assert!(private.parse_public_message().is_err());
+    }
+
     #[test]
     fn test_noise_payload_roundtrip() {
         let payload = NoisePayload::new(
@@ -78,6 +78,12 @@ pub struct ContentAddressedMessage {
     pub timestamp: u64,
     /// Message sequence number for ordering within conversations
     pub sequence: u64,
+    /// Message this one replies to (not part of the content address)
+    #[serde(default)]
+    pub reply_to: Option<MessageId>,
+    /// Nicknames mentioned in the message (not part of the content address)
+    #[serde(default)]
+    pub mentions: Vec<String>,
 }
 
 impl ContentAddressedMessage {
@@ -102,6 +108,8 @@ impl ContentAddressedMessage {
             content,
             timestamp,
             sequence,
+            reply_to: None,
+            mentions: Vec::new(),
         }
     }
 
@@ -142,6 +150,8 @@ impl ContentAddressedMessage {
                 content,
                 timestamp: normalized_timestamp,
                 sequence,
+                reply_to: None,
+                mentions: Vec::new(),
             })
         } else {
             Ok(Self {
@@ -151,10 +161,24 @@ impl ContentAddressedMessage {
                 content,
                 timestamp: normalized_timestamp,
                 sequence,
+                reply_to: None,
+                mentions: Vec::new(),
             })
         }
     }
 
+    /// Record the message this one replies to
+    pub fn with_reply_to(mut self, reply_to: Option<MessageId>) -> Self {
+        self.reply_to = reply_to;
+        self
+    }
+
+    /// Record the nicknames mentioned in the message
+    pub fn with_mentions(mut self, mentions: Vec<String>) -> Self {
+        self.mentions = mentions;
+        self
+    }
+
     /// Compute content-addressed ID from message components
     fn compute_id(
         sender: &PeerId,
@@ -273,6 +297,10 @@ pub struct MessageStore {
     conversations: HashMap<ConversationId, Vec<MessageId>>,
     /// Messages indexed by timestamp for time-based queries
     time_index: BTreeMap<u64, Vec<MessageId>>,
+    /// Replies indexed by the message they reply to
+    reply_index: HashMap<MessageId, Vec<MessageId>>,
+    /// Messages indexed by the nicknames they mention
+    mention_index: HashMap<String, Vec<MessageId>>,
     /// Configuration for validation and limits
     config: MessageStoreConfig,
     /// Statistics
@@ -299,6 +327,8 @@ impl MessageStore {
             messages: HashMap::default(),
             conversations: HashMap::default(),
             time_index: BTreeMap::new(),
+            reply_index: HashMap::default(),
+            mention_index: HashMap::default(),
             config,
             stats: MessageStoreStats {
                 total_messages: 0,
@@ -463,6 +493,24 @@ impl MessageStore {
                 }
             }
 
+            // Remove from reply and mention indices
+            if let Some(parent) = message.reply_to {
+                if let Some(replies) = self.reply_index.get_mut(&parent) {
+                    replies.retain(|id| id != message_id);
+                    if replies.is_empty() {
+                        self.reply_index.remove(&parent);
+                    }
+                }
+            }
+            for nickname in &message.mentions {
+                if let Some(mentioning) = self.mention_index.get_mut(nickname) {
+                    mentioning.retain(|id| id != message_id);
+                    if mentioning.is_empty() {
+                        self.mention_index.remove(nickname);
+                    }
+                }
+            }
+
             self.stats.total_messages = self.stats.total_messages.saturating_sub(1);
         }
     }
@@ -493,6 +541,17 @@ impl MessageStore {
         let message_id = message.id;
         let timestamp = message.timestamp;
 
+        // Update reply and mention indices
+        if let Some(parent) = message.reply_to {
+            self.reply_index.entry(parent).or_default().push(message_id);
+        }
+        for nickname in &message.mentions {
+            let mentioning = self.mention_index.entry(nickname.clone()).or_default();
+            if !mentioning.contains(&message_id) {
+                mentioning.push(message_id);
+            }
+        }
+
         // Store message
         self.messages.insert(message_id, message);
 
@@ -571,6 +630,84 @@ impl MessageStore {
         messages
     }
 
+    /// Get direct replies to a message, ordered by timestamp
+    pub fn get_replies(&self, message_id: &MessageId) -> Vec<&ContentAddressedMessage> {
+        let reply_ids = match self.reply_index.get(message_id) {
+            Some(ids) => ids,
+            None => return Vec::new(),
+        };
+
+        self.sorted_messages(reply_ids.iter())
+    }
+
+    /// Get the whole thread a message belongs to, ordered by timestamp
+    ///
+    /// The thread starts at the oldest stored ancestor of the message and includes
+    /// every reply below it. Returns nothing for unknown messages.
+    pub fn get_thread(&self, message_id: &MessageId) -> Vec<&ContentAddressedMessage> {
+        let Some(mut root) = self.messages.get(message_id) else {
+            return Vec::new();
+        };
+
+        // Walk up to the root, guarding against reply cycles
+        let mut ancestors = Vec::new();
+        while let Some(parent) = root
+            .reply_to
+            .and_then(|parent| self.messages.get(&parent))
+            .filter(|parent| !ancestors.contains(&parent.id) && parent.id != *message_id)
+        {
+            ancestors.push(parent.id);
+            root = parent;
+        }
+
+        let mut thread = Vec::new();
+        let mut pending = Vec::from([root.id]);
+        while let Some(id) = pending.pop() {
+            if thread.contains(&id) {
+                continue;
+            }
+            thread.push(id);
+            if let Some(replies) = self.reply_index.get(&id) {
+                pending.extend(replies.iter().copied());
+            }
+        }
+
+        self.sorted_messages(thread.iter())
+    }
+
+    /// Get messages mentioning a nickname, ordered by timestamp
+    pub fn get_messages_mentioning(&self, nickname: &str) -> Vec<&ContentAddressedMessage> {
+        let message_ids = match self.mention_index.get(nickname) {
+            Some(ids) => ids,
+            None => return Vec::new(),
+        };
+
+        self.sorted_messages(message_ids.iter())
+    }
+
+    /// Look up messages by ID, sorted by timestamp, then by sequence
+    fn sorted_messages<'a>(
+        &self,
+        message_ids: impl Iterator<Item = &'a MessageId>,
+    ) -> Vec<&ContentAddressedMessage> {
+        let mut messages: Vec<_> = message_ids.filter_map(|id| self.messages.get(id)).collect();
+        messages.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then(a.sequence.cmp(&b.sequence))
+        });
+        messages
+    }
+
+    /// Find stored messages whose hex ID starts with a prefix
+    pub fn find_by_id_prefix(&self, prefix: &str) -> Vec<&ContentAddressedMessage> {
+        let prefix = prefix.to_ascii_lowercase();
+        self.messages
+            .values()
+            .filter(|message| message.id.to_hex().starts_with(&prefix))
+            .collect()
+    }
+
     /// Get all conversations involving a peer
     pub fn get_peer_conversations(&self, peer_id: &PeerId) -> Vec<&ConversationId> {
         self.conversations
@@ -689,6 +826,54 @@ mod tests {
         assert_eq!(peer1_conversations.len(), 2);
     }
 
+    #[test]
+    fn test_thread_and_mention_queries() {
+        let mut store = MessageStore::new();
+        let peer1 = create_test_peer_id(1);
+        let peer2 = create_test_peer_id(2);
+        let peer3 = create_test_peer_id(3);
+
+        let root = ContentAddressedMessage::new(peer1, None, "Lunch?".to_string(), 1);
+        let reply = ContentAddressedMessage::new(peer2, None, "@carol sure".to_string(), 1)
+            .with_reply_to(Some(root.id))
+            .with_mentions(vec!["carol".to_string()]);
+        let nested = ContentAddressedMessage::new(peer3, None, "Me too".to_string(), 1)
+            .with_reply_to(Some(reply.id));
+        let other = ContentAddressedMessage::new(peer1, Some(peer3), "@carol hi".to_string(), 2)
+            .with_mentions(vec!["carol".to_string()]);
+
+        for message in [&root, &reply, &nested, &other] {
+            store.store_message(message.clone()).unwrap();
+        }
+
+        let replies = store.get_replies(&root.id);
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].id, reply.id);
+
+        // Any message in the thread finds the whole thread
+        let thread: Vec<MessageId> = store
+            .get_thread(&nested.id)
+            .iter()
+            .map(|message| message.id)
+            .collect();
+        assert_eq!(thread.len(), 3);
+        assert!([root.id, reply.id, nested.id]
+            .iter()
+            .all(|id| thread.contains(id)));
+        assert_eq!(store.get_thread(&other.id).len(), 1);
+
+        assert_eq!(store.get_messages_mentioning("carol").len(), 2);
+        assert!(store.get_messages_mentioning("dave").is_empty());
+
+        let prefix = &reply.id.to_hex()[..12];
+        assert_eq!(store.find_by_id_prefix(prefix)[0].id, reply.id);
+
+        // Removing a message drops it from the indices
+        store.remove_message_completely(&reply.id);
+        assert!(store.get_replies(&root.id).is_empty());
+        assert_eq!(store.get_messages_mentioning("carol").len(), 1);
+    }
+
     #[test]
     fn test_conversation_id_ordering() {
         let peer1 = create_test_peer_id(1);
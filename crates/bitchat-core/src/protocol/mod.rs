@@ -13,22 +13,31 @@
 //! - `deduplication`: Message deduplication using Bloom filters
 //! - `file_transfer`: Secure file transfer protocol with chunked delivery
 //! - `group_messaging`: Group chat functionality with member management
+//! - `group_log`: Signed, conflict-free log of group membership changes
+//! - `sender_keys`: Ratcheting sender keys that encrypt group messages once per group
 //! - `session_sync`: Multi-device session synchronization
+//! - `device_linking`: One-time offers that link a new device to an identity
 //! - `capabilities`: Capability detection and version negotiation
 //! - `announce`: Peer discovery announce packets with TLV encoding
+//! - `announce_schedule`: When to broadcast our own announces
+//! - `peer_directory`: Peers known from their announces, evicted when stale
 //! - `tlv`: Type-Length-Value encoding for structured data
 //! - `acknowledgments`: Read receipts and delivery acknowledgments
+//! - `favorites`: Favorite notifications for mutual-favorite Nostr messaging
 
 pub mod acknowledgments;
 pub mod announce;
+pub mod announce_schedule;
 pub mod connection_state;
 pub mod crypto;
 pub mod deduplication;
 pub mod delivery;
+pub mod favorites;
 pub mod fragmentation;
 pub mod message;
 pub mod message_store;
 pub mod packet;
+pub mod peer_directory;
 pub mod session;
 pub mod tlv;
 pub mod wire;
@@ -40,9 +49,18 @@ pub mod file_transfer;
 #[cfg(feature = "experimental")]
 pub mod group_messaging;
 
+#[cfg(feature = "experimental")]
+pub mod group_log;
+
+#[cfg(feature = "experimental")]
+pub mod sender_keys;
+
 #[cfg(feature = "experimental")]
 pub mod session_sync;
 
+#[cfg(feature = "experimental")]
+pub mod device_linking;
+
 #[cfg(feature = "experimental")]
 pub mod capabilities;
 
@@ -86,6 +104,12 @@ pub use tlv::{TlvCodec, TlvEntry, TlvType};
 // Re-export announce types
 pub use announce::{AnnouncePayload, DiscoveredPeer};
 
+// Re-export announce schedule types
+pub use announce_schedule::AnnounceSchedule;
+
+// Re-export peer directory types
+pub use peer_directory::{DirectoryEntry, PeerDirectory};
+
 // Re-export fragmentation types
 pub use fragmentation::{Fragment, FragmentHeader, MessageFragmenter, MessageReassembler};
 
@@ -97,11 +121,14 @@ pub use acknowledgments::{
     DeliveryAck, EnhancedDeliveryStatus, ReadReceipt, ReceiptManager, ReceiptStats, ReceiptType,
 };
 
+// Re-export favorite notification types
+pub use favorites::{CanonicalFavorite, FavoriteNotification};
+
 // Experimental re-exports (only available with experimental feature flag)
 #[cfg(feature = "experimental")]
 pub use file_transfer::{
-    FileAccept, FileChunk, FileComplete, FileHash, FileMetadata, FileOffer, FileTransferId,
-    FileTransferManager, FileTransferMessage, FileTransferSession, TransferStatus,
+    FileAccept, FileChunk, FileComplete, FileHash, FileHasher, FileMetadata, FileOffer,
+    FileResume, FileTransferId, FileTransferManager, FileTransferMessage, FileTransferSession, TransferStatus,
 };
 
 #[cfg(feature = "experimental")]
@@ -110,6 +137,14 @@ pub use group_messaging::{
     GroupMessage, GroupMessagingMessage, GroupMetadata, GroupRole, GroupSettings, GroupUpdate,
 };
 
+#[cfg(feature = "experimental")]
+pub use group_log::{
+    GroupLog, GroupLogSync, GroupOperation, GroupOperationId, SignedGroupOperation,
+};
+
+#[cfg(feature = "experimental")]
+pub use sender_keys::{EncryptedGroupMessage, SenderKeyDistribution, SenderKeyStore};
+
 #[cfg(feature = "experimental")]
 pub use session_sync::{
     DeviceAnnouncement, DeviceCapabilities, DeviceHeartbeat, DeviceId, DeviceInfo, DeviceStatus,
@@ -117,6 +152,12 @@ pub use session_sync::{
     SessionSyncRequest, SessionSyncResponse, SessionSyncState,
 };
 
+#[cfg(feature = "experimental")]
+pub use device_linking::{
+    DeviceLinkGrant, DeviceLinkMessage, DeviceLinkOffer, DeviceLinkRequest, DeviceLinker,
+    LinkedDevice, LinkedIdentity,
+};
+
 #[cfg(feature = "experimental")]
 pub use capabilities::{
     Capability, CapabilityId, CapabilityManager, CapabilityMessage, CapabilityRejection,
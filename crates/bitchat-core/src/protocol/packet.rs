@@ -18,7 +18,7 @@ use crate::{BitchatError, Result};
 /// Protocol version 1 (13-byte header)
 pub const PROTOCOL_VERSION_1: u8 = 1;
 
-/// Protocol version 2 (15-byte header)  
+/// Protocol version 2 (16-byte header)
 pub const PROTOCOL_VERSION_2: u8 = 2;
 
 /// Current protocol version
@@ -28,7 +28,7 @@ pub const CURRENT_PROTOCOL_VERSION: u8 = PROTOCOL_VERSION_1;
 pub const HEADER_SIZE_V1: usize = 13;
 
 /// Fixed header size for version 2
-pub const HEADER_SIZE_V2: usize = 15;
+pub const HEADER_SIZE_V2: usize = 16;
 
 /// Maximum payload size for version 1 (255 bytes)
 pub const MAX_PAYLOAD_SIZE_V1: usize = 255;
@@ -64,6 +64,9 @@ pub enum MessageType {
     VersionHello = 0x30,
     /// Version acknowledgment
     VersionAck = 0x31,
+    /// Group chat message under its sender's group key, flooded through the mesh
+    /// (experimental, not part of the canonical protocol)
+    GroupMessage = 0x40,
 }
 
 impl MessageType {
@@ -80,6 +83,7 @@ impl MessageType {
             0x22 => Ok(MessageType::FileTransfer),
             0x30 => Ok(MessageType::VersionHello),
             0x31 => Ok(MessageType::VersionAck),
+            0x40 => Ok(MessageType::GroupMessage),
             _ => Err(BitchatError::invalid_packet("Unknown message type")),
         }
     }
@@ -408,6 +412,12 @@ impl BitchatPacket {
         self
     }
 
+    /// Set the protocol version (version 2 carries payloads over 255 bytes)
+    pub fn with_version(mut self, version: u8) -> Self {
+        self.header.version = version;
+        self
+    }
+
     /// Check if this is a broadcast message
     pub fn is_broadcast(&self) -> bool {
         self.recipient_id.is_none() || self.recipient_id == Some(PeerId::BROADCAST)
@@ -0,0 +1,460 @@
+//! Directory of peers known from their announces
+//!
+//! Every transport delivers signed announces to the Core Logic task. The
+//! directory keeps the latest nickname and keys each peer announced, the
+//! transports it was heard on and its signal strength, and forgets peers that
+//! have not been heard from within `TimingConfig::peer_stale_timeout_secs`.
+//!
+//! Announces only prove possession of the signing key they carry, so once a
+//! peer is known its signing key and nickname only change through an announce
+//! signed with the recorded key or one that arrived over an authenticated
+//! Noise session. Leaves are only honoured for peers whose signing key was
+//! learned that way, and only when signed after the peer's latest announce.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+
+use crate::channel::communication::{PeerInfo, TransportType};
+use crate::config::TimingConfig;
+use crate::protocol::announce::DiscoveredPeer;
+use crate::types::{PeerId, Timestamp};
+use crate::{BitchatError, Result};
+
+/// Oldest leave accepted, relative to when it is received
+pub const MAX_LEAVE_AGE_MS: u64 = 60_000;
+
+/// Directory entry for one announced peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    /// Identity from the peer's latest announce
+    pub peer: DiscoveredPeer,
+    /// Transports the peer has been heard on
+    pub transports: Vec<TransportType>,
+    /// Latest signal strength reported by a transport, in dBm
+    pub rssi: Option<i8>,
+    /// When the peer signed its latest accepted announce
+    pub announced_at: Timestamp,
+    /// Whether the signing key was confirmed over an authenticated Noise session
+    pub authenticated: bool,
+}
+
+impl DirectoryEntry {
+    fn heard_on(&mut self, transport: TransportType, now: Timestamp) {
+        if !self.transports.contains(&transport) {
+            self.transports.push(transport);
+        }
+        if now > self.peer.last_seen {
+            self.peer.last_seen = now;
+        }
+    }
+
+    /// Snapshot of the entry for the UI
+    pub fn info(&self) -> PeerInfo {
+        PeerInfo {
+            peer_id: self.peer.peer_id,
+            nickname: self.peer.nickname.clone(),
+            fingerprint: self.peer.fingerprint(),
+            noise_public_key: self.peer.noise_public_key,
+            signing_public_key: self.peer.signing_public_key,
+            nostr_public_key: self.peer.nostr_public_key,
+            transports: self.transports.clone(),
+            rssi: self.rssi,
+            last_seen: self.peer.last_seen.as_millis(),
+        }
+    }
+}
+
+/// Transport-agnostic table of announced peers
+#[derive(Debug, Clone)]
+pub struct PeerDirectory {
+    entries: HashMap<PeerId, DirectoryEntry>,
+    stale_after_ms: u64,
+}
+
+impl Default for PeerDirectory {
+    fn default() -> Self {
+        Self::new(&TimingConfig::default())
+    }
+}
+
+impl PeerDirectory {
+    /// Create an empty directory evicting peers per `config`
+    pub fn new(config: &TimingConfig) -> Self {
+        Self {
+            entries: HashMap::new(),
+            stale_after_ms: config.peer_stale_timeout_secs.saturating_mul(1000),
+        }
+    }
+
+    /// Record a verified announce signed at `announced_at` and heard on `transport`
+    ///
+    /// `authenticated` tells whether the announce arrived over a Noise session
+    /// bound to the announced key. Without one, an announce for a known peer
+    /// must be signed with the signing key already recorded for it.
+    ///
+    /// Returns whether the peer was not in the directory before.
+    pub fn record_announce(
+        &mut self,
+        peer: DiscoveredPeer,
+        transport: TransportType,
+        announced_at: Timestamp,
+        authenticated: bool,
+    ) -> Result<bool> {
+        let now = peer.last_seen;
+        match self.entries.get_mut(&peer.peer_id) {
+            Some(entry) => {
+                if !authenticated && entry.peer.signing_public_key != peer.signing_public_key {
+                    return Err(BitchatError::invalid_packet(
+                        "Announce signing key does not match the known peer",
+                    ));
+                }
+                let last_seen = entry.peer.last_seen;
+                entry.peer = peer;
+                entry.peer.last_seen = last_seen;
+                entry.heard_on(transport, now);
+                entry.announced_at = entry.announced_at.max(announced_at);
+                entry.authenticated |= authenticated;
+                Ok(false)
+            }
+            None => {
+                self.entries.insert(
+                    peer.peer_id,
+                    DirectoryEntry {
+                        peer,
+                        transports: alloc::vec![transport],
+                        rssi: None,
+                        announced_at,
+                        authenticated,
+                    },
+                );
+                Ok(true)
+            }
+        }
+    }
+
+    /// Refresh a known peer that was heard from on `transport`
+    pub fn record_activity(&mut self, peer_id: &PeerId, transport: TransportType, now: Timestamp) {
+        if let Some(entry) = self.entries.get_mut(peer_id) {
+            entry.heard_on(transport, now);
+        }
+    }
+
+    /// Record the signal strength a transport measured for a known peer
+    pub fn record_signal(
+        &mut self,
+        peer_id: &PeerId,
+        transport: TransportType,
+        rssi: Option<i8>,
+        now: Timestamp,
+    ) {
+        if let Some(entry) = self.entries.get_mut(peer_id) {
+            entry.heard_on(transport, now);
+            if rssi.is_some() {
+                entry.rssi = rssi;
+            }
+        }
+    }
+
+    /// Remove peers not heard from within the stale timeout, returning them
+    pub fn evict_stale(&mut self, now: Timestamp) -> Vec<PeerId> {
+        let stale_after_ms = self.stale_after_ms;
+        let stale: Vec<PeerId> = self
+            .entries
+            .values()
+            .filter(|entry| !entry.peer.is_recent(now, stale_after_ms))
+            .map(|entry| entry.peer.peer_id)
+            .collect();
+        for peer_id in &stale {
+            self.entries.remove(peer_id);
+        }
+        stale
+    }
+
+    /// Whether a leave signed at `signed_at` may remove the peer
+    ///
+    /// The peer's signing key must have been confirmed over a Noise session, and
+    /// the leave must be recent and newer than the peer's latest announce, so an
+    /// old leave can't be replayed once the peer is back.
+    pub fn accepts_leave(&self, peer_id: &PeerId, signed_at: Timestamp, now: Timestamp) -> bool {
+        self.entries.get(peer_id).is_some_and(|entry| {
+            entry.authenticated
+                && signed_at >= entry.announced_at
+                && now.as_millis().saturating_sub(signed_at.as_millis()) <= MAX_LEAVE_AGE_MS
+        })
+    }
+
+    /// Forget a peer, such as one that announced it is leaving
+    pub fn remove(&mut self, peer_id: &PeerId) -> Option<DirectoryEntry> {
+        self.entries.remove(peer_id)
+    }
+
+    /// Get a peer's entry
+    pub fn get(&self, peer_id: &PeerId) -> Option<&DirectoryEntry> {
+        self.entries.get(peer_id)
+    }
+
+    /// Nickname a peer announced
+    pub fn nickname(&self, peer_id: &PeerId) -> Option<&str> {
+        self.entries
+            .get(peer_id)
+            .map(|entry| entry.peer.nickname.as_str())
+    }
+
+    /// Find the peer announcing `nickname`, ignoring case
+    ///
+    /// Returns `None` when no peer or more than one peer uses the nickname.
+    pub fn resolve_nickname(&self, nickname: &str) -> Option<PeerId> {
+        let nickname = nickname.strip_prefix('@').unwrap_or(nickname);
+        let mut matches = self
+            .entries
+            .values()
+            .filter(|entry| entry.peer.nickname.eq_ignore_ascii_case(nickname))
+            .map(|entry| entry.peer.peer_id);
+        let peer_id = matches.next()?;
+        matches.next().is_none().then_some(peer_id)
+    }
+
+    /// Snapshots of all peers, ordered by nickname
+    pub fn list(&self) -> Vec<PeerInfo> {
+        let mut peers: Vec<PeerInfo> = self.entries.values().map(DirectoryEntry::info).collect();
+        peers.sort_by(|a, b| {
+            sort_key(&a.nickname)
+                .cmp(&sort_key(&b.nickname))
+                .then(a.peer_id.cmp(&b.peer_id))
+        });
+        peers
+    }
+
+    /// Number of peers in the directory
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the directory is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn sort_key(nickname: &str) -> String {
+    nickname.to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    use crate::protocol::crypto::{IdentityKeyPair, NoiseKeyPair};
+    use crate::protocol::packet::BitchatPacket;
+
+    fn announce(nickname: &str, noise_key: &NoiseKeyPair, at: u64) -> DiscoveredPeer {
+        signed_announce(
+            nickname,
+            noise_key,
+            &IdentityKeyPair::generate().unwrap(),
+            at,
+        )
+    }
+
+    fn signed_announce(
+        nickname: &str,
+        noise_key: &NoiseKeyPair,
+        identity_key: &IdentityKeyPair,
+        at: u64,
+    ) -> DiscoveredPeer {
+        let noise_public_key = noise_key.public_key_bytes();
+        let packet = BitchatPacket::create_announce(
+            PeerId::from_noise_key(&noise_public_key),
+            nickname.to_string(),
+            noise_public_key,
+            identity_key,
+            None,
+            Timestamp::new(at),
+        )
+        .unwrap();
+        DiscoveredPeer::from_announce_packet(&packet, Timestamp::new(at)).unwrap()
+    }
+
+    fn directory() -> PeerDirectory {
+        PeerDirectory::new(&TimingConfig::testing())
+    }
+
+    #[test]
+    fn test_announces_update_identity_and_transports() {
+        let mut directory = directory();
+        let key = NoiseKeyPair::generate();
+        let identity = IdentityKeyPair::generate().unwrap();
+        let alice = signed_announce("alice", &key, &identity, 1_000);
+        let peer_id = alice.peer_id;
+
+        assert!(directory
+            .record_announce(alice, TransportType::Ble, Timestamp::new(1_000), false)
+            .unwrap());
+        directory.record_signal(
+            &peer_id,
+            TransportType::Ble,
+            Some(-60),
+            Timestamp::new(1_500),
+        );
+        let alicia = signed_announce("alicia", &key, &identity, 2_000);
+        assert!(!directory
+            .record_announce(alicia, TransportType::Nostr, Timestamp::new(2_000), false)
+            .unwrap());
+
+        let entry = directory.get(&peer_id).unwrap();
+        assert_eq!(entry.peer.nickname, "alicia");
+        assert_eq!(
+            entry.transports,
+            vec![TransportType::Ble, TransportType::Nostr]
+        );
+        assert_eq!(entry.rssi, Some(-60));
+        assert_eq!(entry.peer.last_seen, Timestamp::new(2_000));
+
+        // Signals and activity from unknown peers are ignored
+        let stranger = PeerId::new([9; 8]);
+        directory.record_signal(
+            &stranger,
+            TransportType::Ble,
+            Some(-40),
+            Timestamp::new(2_000),
+        );
+        directory.record_activity(&stranger, TransportType::Ble, Timestamp::new(2_000));
+        assert_eq!(directory.len(), 1);
+    }
+
+    #[test]
+    fn test_spoofed_self_certified_announce_is_rejected() {
+        let mut directory = directory();
+        let key = NoiseKeyPair::generate();
+        let alice = announce("alice", &key, 1_000);
+        let peer_id = alice.peer_id;
+        let signing_key = alice.signing_public_key;
+        directory
+            .record_announce(alice, TransportType::Ble, Timestamp::new(1_000), false)
+            .unwrap();
+
+        // Anyone can announce Alice's public Noise key under a signing key of their own
+        let spoofed = announce("mallory", &key, 2_000);
+        assert!(directory
+            .record_announce(spoofed, TransportType::Ble, Timestamp::new(2_000), false)
+            .is_err());
+        let entry = directory.get(&peer_id).unwrap();
+        assert_eq!(entry.peer.nickname, "alice");
+        assert_eq!(entry.peer.signing_public_key, signing_key);
+
+        // An announce over an authenticated session may rotate the signing key
+        let rotated = announce("alice", &key, 3_000);
+        let rotated_key = rotated.signing_public_key;
+        assert!(!directory
+            .record_announce(rotated, TransportType::Ble, Timestamp::new(3_000), true)
+            .unwrap());
+        assert_eq!(
+            directory.get(&peer_id).unwrap().peer.signing_public_key,
+            rotated_key
+        );
+    }
+
+    #[test]
+    fn test_leaves_need_authenticated_recent_signatures() {
+        let mut directory = directory();
+        let key = NoiseKeyPair::generate();
+        let identity = IdentityKeyPair::generate().unwrap();
+        let alice = signed_announce("alice", &key, &identity, 1_000);
+        let peer_id = alice.peer_id;
+        directory
+            .record_announce(alice, TransportType::Ble, Timestamp::new(1_000), false)
+            .unwrap();
+
+        // The signing key has only been self-certified so far
+        let now = Timestamp::new(2_000);
+        assert!(!directory.accepts_leave(&peer_id, Timestamp::new(1_500), now));
+
+        let alice = signed_announce("alice", &key, &identity, 3_000);
+        directory
+            .record_announce(alice, TransportType::Ble, Timestamp::new(3_000), true)
+            .unwrap();
+        let now = Timestamp::new(4_000);
+        assert!(directory.accepts_leave(&peer_id, Timestamp::new(3_500), now));
+
+        // Leaves from before the latest announce or long ago are replays
+        assert!(!directory.accepts_leave(&peer_id, Timestamp::new(2_500), now));
+        let later = Timestamp::new(3_500 + MAX_LEAVE_AGE_MS + 1);
+        assert!(!directory.accepts_leave(&peer_id, Timestamp::new(3_500), later));
+        assert!(!directory.accepts_leave(&PeerId::new([9; 8]), Timestamp::new(3_500), now));
+    }
+
+    #[test]
+    fn test_nicknames_resolve_to_unique_peers() {
+        let mut directory = directory();
+        let alice = announce("Alice", &NoiseKeyPair::generate(), 1_000);
+        let alice_id = alice.peer_id;
+        directory
+            .record_announce(alice, TransportType::Ble, Timestamp::new(1_000), false)
+            .unwrap();
+        directory
+            .record_announce(
+                announce("bob", &NoiseKeyPair::generate(), 1_000),
+                TransportType::Ble,
+                Timestamp::new(1_000),
+                false,
+            )
+            .unwrap();
+
+        assert_eq!(directory.nickname(&alice_id), Some("Alice"));
+        assert_eq!(directory.resolve_nickname("@alice"), Some(alice_id));
+        assert_eq!(directory.resolve_nickname("carol"), None);
+
+        directory
+            .record_announce(
+                announce("BOB", &NoiseKeyPair::generate(), 1_000),
+                TransportType::Ble,
+                Timestamp::new(1_000),
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            directory.resolve_nickname("bob"),
+            None,
+            "ambiguous nickname"
+        );
+
+        let nicknames: Vec<String> = directory
+            .list()
+            .into_iter()
+            .map(|peer| peer.nickname)
+            .collect();
+        assert_eq!(nicknames[0], "Alice");
+        assert_eq!(nicknames.len(), 3);
+    }
+
+    #[test]
+    fn test_stale_peers_are_evicted() {
+        let mut directory = directory();
+        let quiet = announce("quiet", &NoiseKeyPair::generate(), 1_000);
+        let chatty = announce("chatty", &NoiseKeyPair::generate(), 1_000);
+        let (quiet_id, chatty_id) = (quiet.peer_id, chatty.peer_id);
+        directory
+            .record_announce(quiet, TransportType::Ble, Timestamp::new(1_000), false)
+            .unwrap();
+        directory
+            .record_announce(chatty, TransportType::Ble, Timestamp::new(1_000), false)
+            .unwrap();
+
+        directory.record_activity(&chatty_id, TransportType::Ble, Timestamp::new(8_000));
+        assert!(directory.evict_stale(Timestamp::new(11_000)).is_empty());
+
+        assert_eq!(
+            directory.evict_stale(Timestamp::new(11_001)),
+            vec![quiet_id]
+        );
+        assert!(directory.get(&chatty_id).is_some());
+        assert_eq!(
+            directory.evict_stale(Timestamp::new(18_001)),
+            vec![chatty_id]
+        );
+        assert!(directory.is_empty());
+    }
+}
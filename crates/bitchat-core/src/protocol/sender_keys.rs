@@ -0,0 +1,614 @@
+//! Sender keys for group messaging
+//!
+//! Every group member encrypts its messages under its own sender key: a chain key
+//! that is ratcheted forward after each message, and an Ed25519 key that signs
+//! each ciphertext. Members hand their sender keys to each other over pairwise
+//! Noise sessions, after which a group message is encrypted once and can be
+//! broadcast over the mesh to the whole group.
+//!
+//! The ratchet gives forward secrecy: a chain key only derives keys for later
+//! messages, and message keys are discarded once used. When a member is removed,
+//! the others move to a new generation of their sender keys and distribute it to
+//! the remaining members only, so the removed member cannot read what follows.
+
+use alloc::{collections::BTreeMap, string::ToString, vec::Vec};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305,
+};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::protocol::crypto::IdentityKeyPair;
+use crate::protocol::group_messaging::GroupId;
+use crate::types::PeerId;
+use crate::{BitchatError, Result};
+
+// ----------------------------------------------------------------------------
+// Constants
+// ----------------------------------------------------------------------------
+
+/// Furthest a received message may be ahead of its sender's chain
+///
+/// Bounds the work an attacker can cause with a single forged iteration number.
+pub const MAX_MESSAGE_KEY_JUMP: u32 = 2000;
+
+/// Message keys kept per sender for messages that arrive out of order
+pub const MAX_SKIPPED_MESSAGE_KEYS: usize = 256;
+
+/// Domain separation for deriving a message key from a chain key
+const MESSAGE_KEY_LABEL: &[u8] = b"bitchat-sender-key-message";
+
+/// Domain separation for ratcheting a chain key
+const CHAIN_KEY_LABEL: &[u8] = b"bitchat-sender-key-chain";
+
+// ----------------------------------------------------------------------------
+// Chain Keys
+// ----------------------------------------------------------------------------
+
+/// Symmetric ratchet state of a sender key
+#[derive(Clone, PartialEq, Eq)]
+struct ChainKey {
+    key: [u8; 32],
+    iteration: u32,
+}
+
+impl core::fmt::Debug for ChainKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ChainKey")
+            .field("key", &"<redacted>")
+            .field("iteration", &self.iteration)
+            .finish()
+    }
+}
+
+impl ChainKey {
+    fn generate() -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        Self { key, iteration: 0 }
+    }
+
+    /// Key for the message at this chain's iteration
+    fn message_key(&self) -> [u8; 32] {
+        derive(MESSAGE_KEY_LABEL, &self.key)
+    }
+
+    /// Ratchet forward one message; the old key cannot be recovered from the new one
+    fn advance(&mut self) {
+        self.key = derive(CHAIN_KEY_LABEL, &self.key);
+        self.iteration += 1;
+    }
+}
+
+fn derive(label: &[u8], key: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(label);
+    hasher.update(key);
+    hasher.finalize().into()
+}
+
+// ----------------------------------------------------------------------------
+// Wire Types
+// ----------------------------------------------------------------------------
+
+/// A member's sender key, sent to each other member over a pairwise Noise session
+///
+/// Carries the chain at its current position, so the recipient can read the
+/// sender's messages from here on but nothing sent before.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SenderKeyDistribution {
+    /// Group the key is for
+    pub group_id: GroupId,
+    /// Member that encrypts with this key
+    pub sender: PeerId,
+    /// Key generation, bumped whenever the sender rotates its key
+    pub generation: u32,
+    /// Chain position the key is at
+    pub iteration: u32,
+    /// Chain key at `iteration`
+    pub chain_key: [u8; 32],
+    /// Ed25519 public key the sender signs its messages with
+    pub verifying_key: [u8; 32],
+}
+
+impl core::fmt::Debug for SenderKeyDistribution {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SenderKeyDistribution")
+            .field("group_id", &self.group_id)
+            .field("sender", &self.sender)
+            .field("generation", &self.generation)
+            .field("iteration", &self.iteration)
+            .field("chain_key", &"<redacted>")
+            .field("verifying_key", &self.verifying_key)
+            .finish()
+    }
+}
+
+/// Group message encrypted once under its sender's key, for broadcast to the group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedGroupMessage {
+    /// Group the message was sent to
+    pub group_id: GroupId,
+    /// Member that sent the message
+    pub sender: PeerId,
+    /// Generation of the sender key used
+    pub generation: u32,
+    /// Chain position of the message key used
+    pub iteration: u32,
+    /// ChaCha20-Poly1305 ciphertext of the serialized message
+    pub ciphertext: Vec<u8>,
+    /// Ed25519 signature over the header and ciphertext
+    pub signature: Vec<u8>,
+}
+
+impl EncryptedGroupMessage {
+    /// Header fields, bound into both the AEAD and the signature
+    fn header(group_id: &GroupId, sender: &PeerId, generation: u32, iteration: u32) -> Vec<u8> {
+        let group_id = group_id.as_str().as_bytes();
+        let mut header = Vec::with_capacity(4 + group_id.len() + 8 + 8);
+        header.extend_from_slice(&(group_id.len() as u32).to_be_bytes());
+        header.extend_from_slice(group_id);
+        header.extend_from_slice(sender.as_bytes());
+        header.extend_from_slice(&generation.to_be_bytes());
+        header.extend_from_slice(&iteration.to_be_bytes());
+        header
+    }
+
+    fn signed_data(&self) -> Vec<u8> {
+        let mut data = Self::header(
+            &self.group_id,
+            &self.sender,
+            self.generation,
+            self.iteration,
+        );
+        data.extend_from_slice(&self.ciphertext);
+        data
+    }
+
+    /// Serialize for broadcast
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
+
+    /// Deserialize a received broadcast
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Sender Key Store
+// ----------------------------------------------------------------------------
+
+/// Our own sender key for a group
+#[derive(Clone, PartialEq, Eq)]
+struct OwnSenderKey {
+    generation: u32,
+    chain: ChainKey,
+    signing_key: [u8; 32],
+}
+
+impl core::fmt::Debug for OwnSenderKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OwnSenderKey")
+            .field("generation", &self.generation)
+            .field("chain", &self.chain)
+            .field("signing_key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl OwnSenderKey {
+    fn generate(generation: u32) -> Result<Self> {
+        let signing_key = IdentityKeyPair::generate()?.private_key_bytes();
+        Ok(Self {
+            generation,
+            chain: ChainKey::generate(),
+            signing_key,
+        })
+    }
+}
+
+/// Another member's sender key for a group
+#[derive(Clone, PartialEq, Eq)]
+struct PeerSenderKey {
+    generation: u32,
+    chain: ChainKey,
+    verifying_key: [u8; 32],
+    /// Message keys derived past but not used yet, by iteration
+    skipped: BTreeMap<u32, [u8; 32]>,
+}
+
+impl core::fmt::Debug for PeerSenderKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PeerSenderKey")
+            .field("generation", &self.generation)
+            .field("chain", &self.chain)
+            .field("verifying_key", &self.verifying_key)
+            .field("skipped", &self.skipped.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl PeerSenderKey {
+    /// Take the message key for an iteration, ratcheting the chain up to it
+    fn message_key(&mut self, iteration: u32) -> Result<[u8; 32]> {
+        if iteration < self.chain.iteration {
+            return self
+                .skipped
+                .remove(&iteration)
+                .ok_or_else(|| BitchatError::invalid_packet("Group message key already used"));
+        }
+        if iteration - self.chain.iteration > MAX_MESSAGE_KEY_JUMP {
+            return Err(BitchatError::invalid_packet(
+                "Group message too far ahead of sender key",
+            ));
+        }
+
+        while self.chain.iteration < iteration {
+            self.skipped
+                .insert(self.chain.iteration, self.chain.message_key());
+            self.chain.advance();
+        }
+        while self.skipped.len() > MAX_SKIPPED_MESSAGE_KEYS {
+            self.skipped.pop_first();
+        }
+        let key = self.chain.message_key();
+        self.chain.advance();
+        Ok(key)
+    }
+}
+
+/// Sender keys for every group we are in: ours and the other members'
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct SenderKeyStore {
+    own: BTreeMap<GroupId, OwnSenderKey>,
+    peers: BTreeMap<GroupId, BTreeMap<PeerId, PeerSenderKey>>,
+}
+
+impl core::fmt::Debug for SenderKeyStore {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SenderKeyStore")
+            .field("own", &self.own)
+            .field("peers", &self.peers)
+            .finish()
+    }
+}
+
+impl SenderKeyStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make sure we have a sender key for a group, creating the first generation
+    pub fn ensure_own_key(&mut self, group_id: &GroupId) -> Result<()> {
+        if !self.own.contains_key(group_id) {
+            self.own
+                .insert(group_id.clone(), OwnSenderKey::generate(0)?);
+        }
+        Ok(())
+    }
+
+    /// Replace our sender key for a group with a fresh one of the next generation
+    pub fn rotate(&mut self, group_id: &GroupId) -> Result<()> {
+        let generation = self
+            .own
+            .get(group_id)
+            .map_or(0, |key| key.generation.wrapping_add(1));
+        self.own
+            .insert(group_id.clone(), OwnSenderKey::generate(generation)?);
+        Ok(())
+    }
+
+    /// Our current sender key for a group, ready to send to another member
+    pub fn distribution(
+        &self,
+        group_id: &GroupId,
+        local_peer_id: PeerId,
+    ) -> Result<SenderKeyDistribution> {
+        let own = self
+            .own
+            .get(group_id)
+            .ok_or_else(|| BitchatError::invalid_packet("No sender key for group"))?;
+        Ok(SenderKeyDistribution {
+            group_id: group_id.clone(),
+            sender: local_peer_id,
+            generation: own.generation,
+            iteration: own.chain.iteration,
+            chain_key: own.chain.key,
+            verifying_key: IdentityKeyPair::from_bytes(&own.signing_key)?.public_key_bytes(),
+        })
+    }
+
+    /// Store another member's sender key
+    ///
+    /// Older generations are refused. A repeat of the current generation only
+    /// moves the chain forward, never back to keys that were already used.
+    pub fn process_distribution(&mut self, distribution: &SenderKeyDistribution) -> Result<()> {
+        let keys = self.peers.entry(distribution.group_id.clone()).or_default();
+        if let Some(existing) = keys.get(&distribution.sender) {
+            if distribution.generation < existing.generation {
+                return Err(BitchatError::invalid_packet("Stale sender key generation"));
+            }
+            if distribution.generation == existing.generation {
+                if distribution.verifying_key != existing.verifying_key {
+                    return Err(BitchatError::invalid_packet(
+                        "Sender key changed without a new generation",
+                    ));
+                }
+                if distribution.iteration <= existing.chain.iteration {
+                    return Ok(());
+                }
+            }
+        }
+
+        keys.insert(
+            distribution.sender,
+            PeerSenderKey {
+                generation: distribution.generation,
+                chain: ChainKey {
+                    key: distribution.chain_key,
+                    iteration: distribution.iteration,
+                },
+                verifying_key: distribution.verifying_key,
+                skipped: BTreeMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Whether we can read a member's messages in a group
+    pub fn has_peer_key(&self, group_id: &GroupId, peer_id: &PeerId) -> bool {
+        self.peers
+            .get(group_id)
+            .is_some_and(|keys| keys.contains_key(peer_id))
+    }
+
+    /// Encrypt and sign a message with our sender key, then ratchet it forward
+    pub fn encrypt(
+        &mut self,
+        group_id: &GroupId,
+        local_peer_id: PeerId,
+        plaintext: &[u8],
+    ) -> Result<EncryptedGroupMessage> {
+        let own = self
+            .own
+            .get_mut(group_id)
+            .ok_or_else(|| BitchatError::invalid_packet("No sender key for group"))?;
+        let header = EncryptedGroupMessage::header(
+            group_id,
+            &local_peer_id,
+            own.generation,
+            own.chain.iteration,
+        );
+        let ciphertext = seal(&own.chain.message_key(), &header, plaintext)?;
+
+        let mut message = EncryptedGroupMessage {
+            group_id: group_id.clone(),
+            sender: local_peer_id,
+            generation: own.generation,
+            iteration: own.chain.iteration,
+            ciphertext,
+            signature: Vec::new(),
+        };
+        let signature = IdentityKeyPair::from_bytes(&own.signing_key)?.sign(message.signed_data());
+        message.signature = signature.to_vec();
+        own.chain.advance();
+        Ok(message)
+    }
+
+    /// Verify and decrypt a member's message
+    ///
+    /// The sender's chain only moves forward once the message has decrypted, so a
+    /// forged message cannot burn message keys.
+    pub fn decrypt(&mut self, message: &EncryptedGroupMessage) -> Result<Vec<u8>> {
+        let key = self
+            .peers
+            .get_mut(&message.group_id)
+            .and_then(|keys| keys.get_mut(&message.sender))
+            .ok_or_else(|| BitchatError::invalid_packet("No sender key for group member"))?;
+        if message.generation != key.generation {
+            return Err(BitchatError::invalid_packet(
+                "Group message uses another sender key generation",
+            ));
+        }
+        let signature: [u8; 64] = message
+            .signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| BitchatError::signature_error())?;
+        IdentityKeyPair::verify(&key.verifying_key, message.signed_data(), &signature)?;
+
+        let mut ratcheted = key.clone();
+        let message_key = ratcheted.message_key(message.iteration)?;
+        let header = EncryptedGroupMessage::header(
+            &message.group_id,
+            &message.sender,
+            message.generation,
+            message.iteration,
+        );
+        let plaintext = open(&message_key, &header, &message.ciphertext)?;
+        *key = ratcheted;
+        Ok(plaintext)
+    }
+
+    /// Forget a member's sender key, e.g. once it has left the group
+    pub fn remove_peer(&mut self, group_id: &GroupId, peer_id: &PeerId) {
+        if let Some(keys) = self.peers.get_mut(group_id) {
+            keys.remove(peer_id);
+        }
+    }
+
+    /// Forget every key for a group we are no longer in
+    pub fn remove_group(&mut self, group_id: &GroupId) {
+        self.own.remove(group_id);
+        self.peers.remove(group_id);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// AEAD Helpers
+// ----------------------------------------------------------------------------
+
+// Each message key encrypts exactly one message, so a fixed nonce is safe
+const NONCE: [u8; 12] = [0u8; 12];
+
+fn seal(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    ChaCha20Poly1305::new(key.into())
+        .encrypt(
+            (&NONCE).into(),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| BitchatError::encryption_error("Group message encryption failed"))
+}
+
+fn open(key: &[u8; 32], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(
+            (&NONCE).into(),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| BitchatError::decryption_error("Group message decryption failed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alice() -> PeerId {
+        PeerId::new([1; 8])
+    }
+
+    fn bob() -> PeerId {
+        PeerId::new([2; 8])
+    }
+
+    /// Alice's and Bob's stores, with Bob holding Alice's sender key
+    fn stores(group_id: &GroupId) -> (SenderKeyStore, SenderKeyStore) {
+        let mut alice_keys = SenderKeyStore::new();
+        alice_keys.ensure_own_key(group_id).unwrap();
+        let mut bob_keys = SenderKeyStore::new();
+        bob_keys
+            .process_distribution(&alice_keys.distribution(group_id, alice()).unwrap())
+            .unwrap();
+        (alice_keys, bob_keys)
+    }
+
+    #[test]
+    fn test_encrypt_once_decrypt_in_any_order() {
+        let group_id = GroupId::generate();
+        let (mut alice_keys, mut bob_keys) = stores(&group_id);
+
+        let messages: Vec<EncryptedGroupMessage> = (0..3u8)
+            .map(|i| alice_keys.encrypt(&group_id, alice(), &[i; 10]).unwrap())
+            .collect();
+        assert_ne!(messages[0].ciphertext, messages[1].ciphertext);
+
+        for index in [2usize, 0, 1] {
+            let wire = messages[index].to_bytes().unwrap();
+            let received = EncryptedGroupMessage::from_bytes(&wire).unwrap();
+            assert_eq!(bob_keys.decrypt(&received).unwrap(), vec![index as u8; 10]);
+        }
+
+        // Every message key is single use
+        assert!(bob_keys.decrypt(&messages[1]).is_err());
+    }
+
+    #[test]
+    fn test_forged_and_tampered_messages_rejected() {
+        let group_id = GroupId::generate();
+        let (mut alice_keys, mut bob_keys) = stores(&group_id);
+        let message = alice_keys.encrypt(&group_id, alice(), b"hello").unwrap();
+
+        let mut tampered = message.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert!(bob_keys.decrypt(&tampered).is_err());
+
+        let mut relabeled = message.clone();
+        relabeled.iteration += 1;
+        assert!(bob_keys.decrypt(&relabeled).is_err());
+
+        let mut too_far = message.clone();
+        too_far.iteration = MAX_MESSAGE_KEY_JUMP + 1;
+        assert!(bob_keys.decrypt(&too_far).is_err());
+
+        // None of the failures advanced the chain
+        assert_eq!(bob_keys.decrypt(&message).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_late_joiner_cannot_read_earlier_messages() {
+        let group_id = GroupId::generate();
+        let mut alice_keys = SenderKeyStore::new();
+        alice_keys.ensure_own_key(&group_id).unwrap();
+        let earlier = alice_keys.encrypt(&group_id, alice(), b"before").unwrap();
+
+        let mut bob_keys = SenderKeyStore::new();
+        bob_keys
+            .process_distribution(&alice_keys.distribution(&group_id, alice()).unwrap())
+            .unwrap();
+        assert!(bob_keys.decrypt(&earlier).is_err());
+
+        let later = alice_keys.encrypt(&group_id, alice(), b"after").unwrap();
+        assert_eq!(bob_keys.decrypt(&later).unwrap(), b"after");
+    }
+
+    #[test]
+    fn test_rotation_starts_new_generation() {
+        let group_id = GroupId::generate();
+        let (mut alice_keys, mut bob_keys) = stores(&group_id);
+        let old = alice_keys.distribution(&group_id, alice()).unwrap();
+
+        alice_keys.rotate(&group_id).unwrap();
+        let new = alice_keys.distribution(&group_id, alice()).unwrap();
+        assert_eq!(new.generation, old.generation + 1);
+        assert_ne!(new.chain_key, old.chain_key);
+
+        // Without the new key, Bob cannot read what Alice sends next
+        let message = alice_keys.encrypt(&group_id, alice(), b"secret").unwrap();
+        assert!(bob_keys.decrypt(&message).is_err());
+
+        bob_keys.process_distribution(&new).unwrap();
+        assert_eq!(bob_keys.decrypt(&message).unwrap(), b"secret");
+        assert!(bob_keys.process_distribution(&old).is_err());
+
+        // A replayed key for the current generation never rewinds the chain
+        bob_keys.process_distribution(&new).unwrap();
+        assert!(bob_keys.decrypt(&message).is_err());
+
+        bob_keys.remove_peer(&group_id, &alice());
+        assert!(!bob_keys.has_peer_key(&group_id, &alice()));
+        assert!(!bob_keys.has_peer_key(&group_id, &bob()));
+    }
+
+    #[test]
+    fn test_debug_output_redacts_key_material() {
+        let group_id = GroupId::generate();
+        let (alice_keys, bob_keys) = stores(&group_id);
+        let distribution = alice_keys.distribution(&group_id, alice()).unwrap();
+        let own = alice_keys.own.get(&group_id).unwrap();
+
+        let chain_key = alloc::format!("{:?}", distribution.chain_key);
+        let signing_key = alloc::format!("{:?}", own.signing_key);
+        for output in [
+            alloc::format!("{:?}", distribution),
+            alloc::format!("{:?}", alice_keys),
+            alloc::format!("{:?}", bob_keys),
+        ] {
+            assert!(output.contains("<redacted>"));
+            assert!(!output.contains(&chain_key));
+            assert!(!output.contains(&signing_key));
+        }
+    }
+}
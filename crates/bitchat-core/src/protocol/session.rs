@@ -50,6 +50,10 @@ pub struct NoiseSession {
     state: SessionState,
     /// Noise handshake state (during handshaking)
     handshake: Option<NoiseHandshake>,
+    /// Whether we initiated the handshake
+    initiator: bool,
+    /// Handshake messages written or read so far
+    handshake_step: u8,
     /// Noise transport state (when established)
     transport: Option<NoiseTransport>,
     /// Session creation timestamp
@@ -81,6 +85,8 @@ impl NoiseSession {
             peer_fingerprint: None,
             state: SessionState::Handshaking,
             handshake: Some(handshake),
+            initiator: true,
+            handshake_step: 0,
             transport: None,
             created_at: now,
             last_activity: now,
@@ -105,6 +111,8 @@ impl NoiseSession {
             peer_fingerprint: None,
             state: SessionState::Handshaking,
             handshake: Some(handshake),
+            initiator: false,
+            handshake_step: 0,
             transport: None,
             created_at: now,
             last_activity: now,
@@ -145,6 +153,34 @@ impl NoiseSession {
         self.created_at
     }
 
+    /// Check if we initiated the handshake
+    pub fn is_initiator(&self) -> bool {
+        self.initiator
+    }
+
+    /// Number of handshake messages written or read so far
+    pub fn handshake_step(&self) -> u8 {
+        self.handshake_step
+    }
+
+    /// Check if the handshake is waiting for the peer's next message
+    ///
+    /// In the XX pattern the initiator writes messages 1 and 3 and reads message 2,
+    /// while the responder reads messages 1 and 3 and writes message 2.
+    pub fn awaits_handshake_message(&self) -> bool {
+        self.state == SessionState::Handshaking
+            && if self.initiator {
+                self.handshake_step == 1
+            } else {
+                self.handshake_step % 2 == 0
+            }
+    }
+
+    /// Check if the handshake is waiting for the peer's first message
+    pub fn awaits_initial_message(&self) -> bool {
+        self.state == SessionState::Handshaking && !self.initiator && self.handshake_step == 0
+    }
+
     /// Process handshake message
     pub fn process_handshake_message<T: TimeSource>(
         &mut self,
@@ -166,6 +202,7 @@ impl NoiseSession {
         })?;
 
         let output = handshake.read_message(input)?;
+        self.handshake_step = self.handshake_step.saturating_add(1);
 
         // Check if handshake is complete
         let is_finished = handshake.is_handshake_finished();
@@ -224,6 +261,7 @@ impl NoiseSession {
         })?;
 
         let output = handshake.write_message(payload)?;
+        self.handshake_step = self.handshake_step.saturating_add(1);
 
         // Check if handshake is complete after writing
         let is_finished = handshake.is_handshake_finished();
@@ -356,6 +394,8 @@ impl NoiseSession {
         // Create new handshake for rekey
         let handshake = NoiseHandshake::initiator(local_key)?;
         self.handshake = Some(handshake);
+        self.initiator = true;
+        self.handshake_step = 0;
         self.transport = None; // Clear old transport
 
         // Reset counters
@@ -534,6 +574,39 @@ mod tests {
                 let decrypted = bob_session.decrypt(&ciphertext, &time_source).unwrap();
                 assert_eq!(plaintext.as_slice(), decrypted.as_slice());
             }
+
+            #[test]
+            fn test_handshake_step_tracking() {
+                let alice_key = NoiseKeyPair::generate();
+                let bob_key = NoiseKeyPair::generate();
+                let alice_id = PeerId::from_bytes(&alice_key.public_key_bytes());
+                let bob_id = PeerId::from_bytes(&bob_key.public_key_bytes());
+                let time_source = SystemTimeSource;
+
+                let mut alice = NoiseSession::new_outbound(bob_id, &alice_key, &time_source).unwrap();
+                let mut bob = NoiseSession::new_inbound(alice_id, &bob_key, &time_source).unwrap();
+                assert!(alice.is_initiator());
+                assert!(!alice.awaits_handshake_message());
+                assert!(bob.awaits_initial_message());
+
+                let msg1 = alice.create_handshake_message(b"", &time_source).unwrap();
+                assert!(alice.awaits_handshake_message());
+                bob.process_handshake_message(&msg1, &time_source).unwrap();
+                assert!(!bob.awaits_handshake_message());
+                assert!(!bob.awaits_initial_message());
+
+                let msg2 = bob.create_handshake_message(b"", &time_source).unwrap();
+                assert!(bob.awaits_handshake_message());
+                alice.process_handshake_message(&msg2, &time_source).unwrap();
+                assert!(!alice.awaits_handshake_message());
+
+                let msg3 = alice.create_handshake_message(b"", &time_source).unwrap();
+                bob.process_handshake_message(&msg3, &time_source).unwrap();
+                assert_eq!(alice.handshake_step(), 3);
+                assert_eq!(bob.handshake_step(), 3);
+                assert!(!alice.awaits_handshake_message());
+                assert!(!bob.awaits_handshake_message());
+            }
         }
     }
 }
@@ -2,6 +2,10 @@
 //!
 //! This module implements synchronization of session state and message history
 //! across multiple devices belonging to the same BitChat identity.
+//!
+//! Devices are linked to an identity with [`crate::protocol::device_linking`].
+//! Linked devices share the identity's Ed25519 key, and sign their
+//! [`DeviceAnnouncement`]s with it so the others can tell them from strangers.
 
 use alloc::{
     collections::BTreeMap,
@@ -9,8 +13,10 @@ use alloc::{
     vec::Vec,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::protocol::message::NoisePayloadType;
+use crate::protocol::crypto::{generate_fingerprint, IdentityKeyPair};
+use crate::protocol::message::{NoisePayload, NoisePayloadType};
 use crate::types::{Fingerprint, PeerId, Timestamp};
 use crate::{BitchatError, Result};
 
@@ -222,10 +228,12 @@ pub struct MessageRef {
     pub recipient: Option<PeerId>,
     /// Message timestamp
     pub timestamp: Timestamp,
-    /// Message hash for integrity verification
+    /// Hex-encoded SHA-256 of the content, for integrity verification
     pub content_hash: String,
-    /// Whether message was read on this device
+    /// Whether the message has been read on any linked device
     pub read: bool,
+    /// Message content, left out of sync requests
+    pub content: Option<String>,
 }
 
 impl MessageRef {
@@ -244,9 +252,28 @@ impl MessageRef {
             timestamp,
             content_hash,
             read: false,
+            content: None,
         }
     }
 
+    /// Attach the message content
+    pub fn with_content(mut self, content: String) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    /// Hash content the way `content_hash` expects
+    pub fn hash_content(content: &str) -> String {
+        hex::encode(Sha256::digest(content.as_bytes()))
+    }
+
+    /// Whether the ref carries content matching its hash
+    pub fn has_valid_content(&self) -> bool {
+        self.content
+            .as_deref()
+            .is_some_and(|content| Self::hash_content(content) == self.content_hash)
+    }
+
     /// Mark message as read
     pub fn mark_read(&mut self) {
         self.read = true;
@@ -285,10 +312,41 @@ impl DeviceAnnouncement {
         }
     }
 
+    /// Create an announcement signed with the identity key the device was linked to
+    pub fn signed(device_info: DeviceInfo, identity_key: &IdentityKeyPair) -> Result<Self> {
+        let identity_fingerprint = generate_fingerprint(identity_key.public_key_bytes());
+        let mut announcement = Self::new(device_info, identity_fingerprint, Vec::new());
+        let signature = identity_key.sign(announcement.signable_data()?);
+        announcement.identity_proof = signature.to_vec();
+        Ok(announcement)
+    }
+
+    /// Check the announcement is recent and signed with the given identity key
+    pub fn verify(&self, identity_public_key: &[u8; 32]) -> bool {
+        let Ok(signature) = <[u8; 64]>::try_from(self.identity_proof.as_slice()) else {
+            return false;
+        };
+        self.is_valid()
+            && self.identity_fingerprint == generate_fingerprint(identity_public_key)
+            && self.signable_data().is_ok_and(|data| {
+                IdentityKeyPair::verify(identity_public_key, data, &signature).is_ok()
+            })
+    }
+
     /// Check if announcement is still valid (not too old)
     pub fn is_valid(&self) -> bool {
         Timestamp::now() - self.timestamp < MAX_SESSION_SYNC_AGE
     }
+
+    /// Everything but the proof itself
+    fn signable_data(&self) -> Result<Vec<u8>> {
+        bincode::serialize(&(
+            &self.device_info,
+            &self.identity_fingerprint,
+            self.timestamp,
+        ))
+        .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
 }
 
 /// Session synchronization request
@@ -436,6 +494,38 @@ impl SessionSyncMessage {
             SessionSyncMessage::Heartbeat(_) => NoisePayloadType::DeviceHeartbeat,
         }
     }
+
+    /// Encode as a Noise payload
+    pub fn to_noise_payload(&self) -> Result<NoisePayload> {
+        let data = match self {
+            SessionSyncMessage::Announcement(announcement) => bincode::serialize(announcement),
+            SessionSyncMessage::SyncRequest(request) => bincode::serialize(request),
+            SessionSyncMessage::SyncResponse(response) => bincode::serialize(response),
+            SessionSyncMessage::Heartbeat(heartbeat) => bincode::serialize(heartbeat),
+        }
+        .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        Ok(NoisePayload::new(self.payload_type(), data))
+    }
+
+    /// Decode from a Noise payload
+    pub fn from_noise_payload(payload: &NoisePayload) -> Result<Self> {
+        let message = match payload.payload_type {
+            NoisePayloadType::DeviceAnnouncement => {
+                bincode::deserialize(&payload.data).map(SessionSyncMessage::Announcement)
+            }
+            NoisePayloadType::SessionSyncRequest => {
+                bincode::deserialize(&payload.data).map(SessionSyncMessage::SyncRequest)
+            }
+            NoisePayloadType::SessionSyncResponse => {
+                bincode::deserialize(&payload.data).map(SessionSyncMessage::SyncResponse)
+            }
+            NoisePayloadType::DeviceHeartbeat => {
+                bincode::deserialize(&payload.data).map(SessionSyncMessage::Heartbeat)
+            }
+            _ => return Err(BitchatError::invalid_packet("Not a session sync payload")),
+        };
+        message.map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -497,6 +587,29 @@ impl MultiDeviceSessionManager {
         self.known_devices.values().collect()
     }
 
+    /// Get a known device
+    pub fn get_device(&self, device_id: &DeviceId) -> Option<&DeviceInfo> {
+        self.known_devices.get(device_id)
+    }
+
+    /// Get this device's information
+    pub fn local_device(&self) -> &DeviceInfo {
+        &self.local_device
+    }
+
+    /// Note that a known device is still online
+    ///
+    /// Returns `false` for heartbeats from devices we do not know.
+    pub fn record_heartbeat(&mut self, heartbeat: &DeviceHeartbeat) -> bool {
+        match self.known_devices.get_mut(&heartbeat.device_id) {
+            Some(device) => {
+                device.update_last_seen();
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get online devices
     pub fn get_online_devices(&self) -> Vec<&DeviceInfo> {
         self.known_devices
@@ -517,7 +630,18 @@ impl MultiDeviceSessionManager {
     }
 
     /// Add message reference
+    ///
+    /// A message we already hold keeps its content, and stays read once either
+    /// copy has been read.
     pub fn add_message_ref(&mut self, message_ref: MessageRef) -> Result<()> {
+        if let Some(existing) = self.message_refs.get_mut(&message_ref.message_id) {
+            existing.read |= message_ref.read;
+            if existing.content.is_none() {
+                existing.content = message_ref.content;
+            }
+            return Ok(());
+        }
+
         if self.message_refs.len() >= MAX_MESSAGE_REFS {
             // Remove oldest message reference
             if let Some(oldest_id) = self
@@ -535,10 +659,38 @@ impl MultiDeviceSessionManager {
         Ok(())
     }
 
+    /// Get all message references
+    pub fn get_message_refs(&self) -> Vec<&MessageRef> {
+        self.message_refs.values().collect()
+    }
+
+    /// Mark every message received from a peer as read
+    ///
+    /// Returns how many messages were unread.
+    pub fn mark_read_from(&mut self, sender: &PeerId) -> usize {
+        let mut marked = 0;
+        for message in self.message_refs.values_mut() {
+            if message.sender == *sender && !message.read {
+                message.mark_read();
+                marked += 1;
+            }
+        }
+        marked
+    }
+
     /// Create sync request for another device
+    ///
+    /// Only message IDs and read state are sent; content travels in the response.
     pub fn create_sync_request(&mut self) -> SessionSyncRequest {
         let sessions: Vec<SessionSyncState> = self.session_states.values().cloned().collect();
-        let messages: Vec<MessageRef> = self.message_refs.values().cloned().collect();
+        let messages: Vec<MessageRef> = self
+            .message_refs
+            .values()
+            .map(|message| MessageRef {
+                content: None,
+                ..message.clone()
+            })
+            .collect();
 
         SessionSyncRequest::new(self.local_device.device_id.clone(), sessions, messages)
     }
@@ -571,10 +723,18 @@ impl MultiDeviceSessionManager {
             }
         }
 
-        // Find messages we're missing
+        // Find messages we're missing, and merge read state both ways
         for remote_message in &request.message_refs {
-            if !self.message_refs.contains_key(&remote_message.message_id) {
-                response = response.with_missing_message(remote_message.clone());
+            match self.message_refs.get_mut(&remote_message.message_id) {
+                None => response = response.with_missing_message(remote_message.clone()),
+                Some(local_message) if remote_message.read => local_message.mark_read(),
+                Some(local_message) if local_message.read => {
+                    response = response.with_new_message(MessageRef {
+                        content: None,
+                        ..local_message.clone()
+                    });
+                }
+                Some(_) => {}
             }
         }
 
@@ -586,22 +746,41 @@ impl MultiDeviceSessionManager {
     }
 
     /// Process sync response
-    pub fn process_sync_response(&mut self, response: &SessionSyncResponse) -> Result<()> {
+    ///
+    /// Returns the messages that were new to this device or have newly been read,
+    /// so the caller can bring its own history up to date. New messages whose
+    /// content is missing or does not match its hash are ignored.
+    pub fn process_sync_response(
+        &mut self,
+        response: &SessionSyncResponse,
+    ) -> Result<Vec<MessageRef>> {
         // Apply session updates
         for session in &response.session_updates {
             self.session_states.insert(session.peer_id, session.clone());
         }
 
-        // Add new messages
+        // Add new messages and read state
+        let mut changed = Vec::new();
         for message in &response.new_messages {
-            self.add_message_ref(message.clone())?;
+            match self.message_refs.get_mut(&message.message_id) {
+                Some(local_message) if message.read && !local_message.read => {
+                    local_message.mark_read();
+                    changed.push(local_message.clone());
+                }
+                Some(_) => {}
+                None if message.has_valid_content() => {
+                    self.add_message_ref(message.clone())?;
+                    changed.push(message.clone());
+                }
+                None => {}
+            }
         }
 
         // Update sync time
         self.device_sync_times
             .insert(response.device_id.clone(), Timestamp::now());
 
-        Ok(())
+        Ok(changed)
     }
 
     /// Clean up old devices and data
@@ -770,6 +949,103 @@ mod tests {
         assert_eq!(response.session_updates.len(), 1);
     }
 
+    fn create_test_message(id: &str, sender: PeerId, content: &str) -> MessageRef {
+        MessageRef::new(
+            id.to_string(),
+            sender,
+            None,
+            Timestamp::now(),
+            MessageRef::hash_content(content),
+        )
+        .with_content(content.to_string())
+    }
+
+    #[test]
+    fn test_history_and_read_state_sync() {
+        let peer_id = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut first = MultiDeviceSessionManager::new(create_test_device(1, "Device 1"));
+        let mut second = MultiDeviceSessionManager::new(create_test_device(2, "Device 2"));
+        first
+            .add_message_ref(create_test_message("msg-1", peer_id, "Hello"))
+            .unwrap();
+        first
+            .add_message_ref(create_test_message("msg-2", peer_id, "Again"))
+            .unwrap();
+
+        // The second device learns both messages with their content
+        let request = second.create_sync_request();
+        let response = first.process_sync_request(&request).unwrap();
+        let learned = second.process_sync_response(&response).unwrap();
+        assert_eq!(learned.len(), 2);
+        assert!(learned.iter().all(MessageRef::has_valid_content));
+
+        // Reading on the second device is picked up by the first from the request
+        assert_eq!(second.mark_read_from(&peer_id), 2);
+        let request = second.create_sync_request();
+        assert!(request
+            .message_refs
+            .iter()
+            .all(|message| message.content.is_none()));
+        first.process_sync_request(&request).unwrap();
+        assert!(first.message_refs.values().all(|message| message.read));
+
+        // And reading on the first device reaches a third through the response
+        let mut third = MultiDeviceSessionManager::new(create_test_device(3, "Device 3"));
+        third
+            .add_message_ref(create_test_message("msg-1", peer_id, "Hello"))
+            .unwrap();
+        let response = first
+            .process_sync_request(&third.create_sync_request())
+            .unwrap();
+        let learned = third.process_sync_response(&response).unwrap();
+        assert_eq!(learned.len(), 2);
+        assert!(third.message_refs.values().all(|message| message.read));
+    }
+
+    #[test]
+    fn test_sync_ignores_tampered_content() {
+        let peer_id = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut manager = MultiDeviceSessionManager::new(create_test_device(1, "Device 1"));
+
+        let mut tampered = create_test_message("msg-1", peer_id, "Hello");
+        tampered.content = Some("Goodbye".to_string());
+        let response = SessionSyncResponse::new(create_test_device(2, "Device 2").device_id)
+            .with_new_message(tampered)
+            .with_new_message(MessageRef::new(
+                "msg-2".to_string(),
+                peer_id,
+                None,
+                Timestamp::now(),
+                MessageRef::hash_content("Hello"),
+            ));
+
+        assert!(manager.process_sync_response(&response).unwrap().is_empty());
+        assert!(manager.message_refs.is_empty());
+    }
+
+    #[test]
+    fn test_signed_device_announcement() {
+        let identity = IdentityKeyPair::generate().unwrap();
+        let stranger = IdentityKeyPair::generate().unwrap();
+
+        let announcement =
+            DeviceAnnouncement::signed(create_test_device(2, "Device 2"), &identity).unwrap();
+        assert!(announcement.verify(&identity.public_key_bytes()));
+        assert!(!announcement.verify(&stranger.public_key_bytes()));
+
+        let mut renamed = announcement.clone();
+        renamed.device_info.name = "Impostor".to_string();
+        assert!(!renamed.verify(&identity.public_key_bytes()));
+
+        let message = SessionSyncMessage::Announcement(announcement);
+        let payload = message.to_noise_payload().unwrap();
+        assert_eq!(payload.payload_type, NoisePayloadType::DeviceAnnouncement);
+        assert_eq!(
+            SessionSyncMessage::from_noise_payload(&payload).unwrap(),
+            message
+        );
+    }
+
     #[test]
     fn test_device_status() {
         let mut device = create_test_device(1, "Test Device");
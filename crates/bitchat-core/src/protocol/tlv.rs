@@ -11,6 +11,7 @@ pub enum TlvType {
     NoisePublicKey = 0x02,
     SigningPublicKey = 0x03,
     DirectNeighbors = 0x04,
+    NostrPublicKey = 0x05,
 }
 
 impl TlvType {
@@ -20,6 +21,7 @@ impl TlvType {
             0x02 => Ok(TlvType::NoisePublicKey),
             0x03 => Ok(TlvType::SigningPublicKey),
             0x04 => Ok(TlvType::DirectNeighbors),
+            0x05 => Ok(TlvType::NostrPublicKey),
             _ => Err(BitchatError::InvalidTlvType(value)),
         }
     }
@@ -57,6 +59,11 @@ impl TlvEntry {
         Self::new(TlvType::SigningPublicKey, key.to_vec())
     }
 
+    /// Create a TLV entry for a Nostr public key (32-byte x-only secp256k1 key)
+    pub fn nostr_public_key(key: &[u8; 32]) -> Self {
+        Self::new(TlvType::NostrPublicKey, key.to_vec())
+    }
+
     /// Create a TLV entry for direct neighbors (peer IDs)
     pub fn direct_neighbors(neighbors: &[&[u8; 32]]) -> Self {
         let mut value = Vec::new();
@@ -219,6 +226,7 @@ mod tests {
         assert_eq!(TlvType::from_u8(0x02).unwrap(), TlvType::NoisePublicKey);
         assert_eq!(TlvType::from_u8(0x03).unwrap(), TlvType::SigningPublicKey);
         assert_eq!(TlvType::from_u8(0x04).unwrap(), TlvType::DirectNeighbors);
+        assert_eq!(TlvType::from_u8(0x05).unwrap(), TlvType::NostrPublicKey);
         assert!(TlvType::from_u8(0xFF).is_err());
     }
 
@@ -240,6 +248,10 @@ mod tests {
         let signing_entry = TlvEntry::signing_public_key(&key);
         assert_eq!(signing_entry.tlv_type, TlvType::SigningPublicKey);
         assert_eq!(signing_entry.as_key().unwrap(), key);
+
+        let nostr_entry = TlvEntry::nostr_public_key(&key);
+        assert_eq!(nostr_entry.tlv_type, TlvType::NostrPublicKey);
+        assert_eq!(nostr_entry.as_key().unwrap(), key);
     }
 
     #[test]
@@ -443,6 +443,21 @@ mod tests {
         assert_eq!(decoded.signature, Some(signature));
     }
 
+    #[test]
+    fn test_wire_format_v2_large_payload() {
+        let sender = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let recipient = PeerId::new([8, 7, 6, 5, 4, 3, 2, 1]);
+        let packet = BitchatPacket::new_simple(MessageType::NoiseEncrypted, sender, vec![7; 600])
+            .with_recipient(recipient)
+            .with_version(crate::protocol::packet::PROTOCOL_VERSION_2);
+
+        let encoded = WireFormat::encode(&packet).unwrap();
+        let decoded = WireFormat::decode(&encoded).unwrap();
+
+        assert_eq!(packet, decoded);
+        assert_eq!(decoded.recipient_id, Some(recipient));
+    }
+
     #[test]
     fn test_padding() {
         let data = vec![1, 2, 3, 4, 5];
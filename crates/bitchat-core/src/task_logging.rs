@@ -124,6 +124,8 @@ impl From<&Command> for MessageType {
     fn from(command: &Command) -> Self {
         let variant = match command {
             Command::SendMessage { .. } => "SendMessage",
+            Command::SendBroadcast { .. } => "SendBroadcast",
+            Command::ReplyToMessage { .. } => "ReplyToMessage",
             Command::ConnectToPeer { .. } => "ConnectToPeer",
             Command::StartDiscovery => "StartDiscovery",
             Command::StopDiscovery => "StopDiscovery",
@@ -136,6 +138,24 @@ impl From<&Command> for MessageType {
             Command::QueryPeerSession { .. } => "QueryPeerSession",
             Command::QueryDeliveryStatus { .. } => "QueryDeliveryStatus",
             Command::QueryInternalState => "QueryInternalState",
+            Command::SetFavorite { .. } => "SetFavorite",
+            Command::ListFavorites => "ListFavorites",
+            Command::ListPeers => "ListPeers",
+            Command::SetNickname { .. } => "SetNickname",
+            Command::SendFile { .. } => "SendFile",
+            Command::AcceptFile { .. } => "AcceptFile",
+            Command::RejectFile { .. } => "RejectFile",
+            Command::ListTransfers => "ListTransfers",
+            Command::CreateGroup { .. } => "CreateGroup",
+            Command::InviteToGroup { .. } => "InviteToGroup",
+            Command::JoinGroup { .. } => "JoinGroup",
+            Command::LeaveGroup { .. } => "LeaveGroup",
+            Command::KickFromGroup { .. } => "KickFromGroup",
+            Command::SendGroupMessage { .. } => "SendGroupMessage",
+            Command::ListGroups => "ListGroups",
+            Command::LinkDevice => "LinkDevice",
+            Command::AcceptDeviceLink { .. } => "AcceptDeviceLink",
+            Command::ListDevices => "ListDevices",
         };
         MessageType::Command(variant.to_string())
     }
@@ -153,6 +173,8 @@ impl From<&Event> for MessageType {
             Event::TransportHealthCheckCompleted { .. } => "TransportHealthCheckCompleted",
             Event::TransportMetricsUpdated { .. } => "TransportMetricsUpdated",
             Event::TransportFailoverOccurred { .. } => "TransportFailoverOccurred",
+            Event::RelayStatusUpdated { .. } => "RelayStatusUpdated",
+            Event::LocalNostrIdentity { .. } => "LocalNostrIdentity",
         };
         MessageType::Event(variant.to_string())
     }
@@ -176,6 +198,8 @@ impl From<&Effect> for MessageType {
             Effect::RequestTransportHealthCheck { .. } => "RequestTransportHealthCheck",
             Effect::UpdateTransportMetrics { .. } => "UpdateTransportMetrics",
             Effect::SwitchPrimaryTransport { .. } => "SwitchPrimaryTransport",
+            Effect::BindNostrIdentity { .. } => "BindNostrIdentity",
+            Effect::BindLinkIdentity { .. } => "BindLinkIdentity",
         };
         MessageType::Effect(variant.to_string())
     }
@@ -186,6 +210,9 @@ impl From<&AppEvent> for MessageType {
         let variant = match app_event {
             AppEvent::MessageReceived { .. } => "MessageReceived",
             AppEvent::MessageSent { .. } => "MessageSent",
+            AppEvent::BroadcastReceived { .. } => "BroadcastReceived",
+            AppEvent::BroadcastSent { .. } => "BroadcastSent",
+            AppEvent::MessageHighlighted { .. } => "MessageHighlighted",
             AppEvent::PeerStatusChanged { .. } => "PeerStatusChanged",
             AppEvent::DiscoveryStateChanged { .. } => "DiscoveryStateChanged",
             AppEvent::ConversationUpdated { .. } => "ConversationUpdated",
@@ -196,6 +223,25 @@ impl From<&AppEvent> for MessageType {
             AppEvent::PeerSessionReport { .. } => "PeerSessionReport",
             AppEvent::DeliveryStatusReport { .. } => "DeliveryStatusReport",
             AppEvent::InternalStateReport { .. } => "InternalStateReport",
+            AppEvent::RelayStatusReport { .. } => "RelayStatusReport",
+            AppEvent::FavoriteStatusChanged { .. } => "FavoriteStatusChanged",
+            AppEvent::FavoritesReport { .. } => "FavoritesReport",
+            AppEvent::PeerListReport { .. } => "PeerListReport",
+            AppEvent::NicknameChanged { .. } => "NicknameChanged",
+            AppEvent::PeerNicknameChanged { .. } => "PeerNicknameChanged",
+            AppEvent::FileOffered { .. } => "FileOffered",
+            AppEvent::FileTransferProgress { .. } => "FileTransferProgress",
+            AppEvent::FileTransferCompleted { .. } => "FileTransferCompleted",
+            AppEvent::FileTransferFailed { .. } => "FileTransferFailed",
+            AppEvent::TransfersReport { .. } => "TransfersReport",
+            AppEvent::GroupInvitation { .. } => "GroupInvitation",
+            AppEvent::GroupMembershipChanged { .. } => "GroupMembershipChanged",
+            AppEvent::GroupMessageReceived { .. } => "GroupMessageReceived",
+            AppEvent::GroupMessageSent { .. } => "GroupMessageSent",
+            AppEvent::GroupsReport { .. } => "GroupsReport",
+            AppEvent::DeviceLinkOffer { .. } => "DeviceLinkOffer",
+            AppEvent::DeviceLinked { .. } => "DeviceLinked",
+            AppEvent::DevicesReport { .. } => "DevicesReport",
         };
         MessageType::AppEvent(variant.to_string())
     }
@@ -240,6 +286,11 @@ impl MessageSummary for Command {
             Command::SendMessage { recipient, content } => {
                 format!("to:{} content:{:.20}...", recipient, content)
             }
+            Command::SendBroadcast { content } => format!("content:{:.20}...", content),
+            Command::ReplyToMessage {
+                message_id,
+                content,
+            } => format!("reply:{} content:{:.20}...", message_id, content),
             Command::ConnectToPeer { peer_id } => format!("peer:{}", peer_id),
             Command::StartDiscovery => "starting discovery".to_string(),
             Command::StopDiscovery => "stopping discovery".to_string(),
@@ -258,6 +309,35 @@ impl MessageSummary for Command {
                 format!("querying delivery status for peer {}", peer_id)
             }
             Command::QueryInternalState => "querying internal state".to_string(),
+            Command::SetFavorite { peer_id, favorite } => {
+                format!("peer:{} favorite:{}", peer_id, favorite)
+            }
+            Command::ListFavorites => "listing favorites".to_string(),
+            Command::ListPeers => "listing peers".to_string(),
+            Command::SetNickname { nickname } => format!("nickname:{}", nickname),
+            Command::SendFile { recipient, path } => {
+                format!("to:{} path:{}", recipient, path)
+            }
+            Command::AcceptFile { transfer_id, .. } => format!("transfer:{}", transfer_id),
+            Command::RejectFile { transfer_id } => format!("transfer:{}", transfer_id),
+            Command::ListTransfers => "listing file transfers".to_string(),
+            Command::CreateGroup { name, .. } => format!("name:{}", name),
+            Command::InviteToGroup { group_id, peer_id } => {
+                format!("group:{} peer:{}", group_id, peer_id)
+            }
+            Command::JoinGroup { group_id } => format!("group:{}", group_id),
+            Command::LeaveGroup { group_id } => format!("group:{}", group_id),
+            Command::KickFromGroup { group_id, peer_id } => {
+                format!("group:{} peer:{}", group_id, peer_id)
+            }
+            Command::SendGroupMessage { group_id, content } => {
+                format!("group:{} content:{:.20}...", group_id, content)
+            }
+            Command::ListGroups => "listing groups".to_string(),
+            Command::LinkDevice => "creating device link offer".to_string(),
+            // The URI carries the offer's secret, so it stays out of the logs
+            Command::AcceptDeviceLink { .. } => "accepting device link offer".to_string(),
+            Command::ListDevices => "listing linked devices".to_string(),
         }
     }
 }
@@ -315,6 +395,12 @@ impl MessageSummary for Event {
             Event::TransportFailoverOccurred { from_transport, to_transport, reason, timestamp } => {
                 format!("failover from:{} to:{} reason:{} at:{}", from_transport, to_transport, reason, timestamp)
             }
+            Event::RelayStatusUpdated { relays, timestamp } => {
+                format!("relays:{} at:{}", relays.len(), timestamp)
+            }
+            Event::LocalNostrIdentity { public_key } => {
+                format!("nostr_key:{}", hex::encode(public_key))
+            }
         }
     }
 }
@@ -363,6 +449,12 @@ impl MessageSummary for Effect {
             Effect::RequestTransportHealthCheck { transport_type, timeout } => format!("health check for transport:{} timeout:{:?}", transport_type, timeout),
             Effect::UpdateTransportMetrics { transport_type, latency_ms, success_rate } => format!("updating metrics for transport:{} latency:{:?}ms success_rate:{}", transport_type, latency_ms, success_rate),
             Effect::SwitchPrimaryTransport { from_transport, to_transport, reason } => format!("switching from:{} to:{} reason:{}", from_transport, to_transport, reason),
+            Effect::BindNostrIdentity { peer_id, public_key } => {
+                format!("peer:{} nostr_key:{}", peer_id, hex::encode(public_key))
+            }
+            Effect::BindLinkIdentity {
+                peer_id, transport, ..
+            } => format!("peer:{} via:{}", peer_id, transport),
         }
     }
 }
@@ -376,6 +468,23 @@ impl MessageSummary for AppEvent {
             AppEvent::MessageSent { to, content, .. } => {
                 format!("to:{} content:{:.20}...", to, content)
             }
+            AppEvent::BroadcastReceived { from, content, .. } => {
+                format!("from:{} public content:{:.20}...", from, content)
+            }
+            AppEvent::BroadcastSent { content, .. } => {
+                format!("public content:{:.20}...", content)
+            }
+            AppEvent::MessageHighlighted {
+                from,
+                mentioned,
+                content,
+                ..
+            } => {
+                format!(
+                    "from:{} mentioned:{} content:{:.20}...",
+                    from, mentioned, content
+                )
+            }
             AppEvent::PeerStatusChanged {
                 peer_id,
                 status,
@@ -457,6 +566,82 @@ impl MessageSummary for AppEvent {
                     peer_id, active_sessions, message_store_size, pending_deliveries
                 )
             }
+            AppEvent::RelayStatusReport { relays } => {
+                format!("relays:{}", relays.len())
+            }
+            AppEvent::FavoriteStatusChanged { status } => {
+                format!("peer:{} mutual:{}", status.peer_id, status.is_mutual())
+            }
+            AppEvent::FavoritesReport { favorites } => {
+                format!("favorites:{}", favorites.len())
+            }
+            AppEvent::PeerListReport { peers } => format!("peers:{}", peers.len()),
+            AppEvent::NicknameChanged { nickname } => format!("nickname:{}", nickname),
+            AppEvent::PeerNicknameChanged {
+                peer_id,
+                previous,
+                nickname,
+            } => format!("peer:{} from:{} to:{}", peer_id, previous, nickname),
+            AppEvent::FileOffered { transfer, .. } => {
+                format!(
+                    "transfer:{} from:{} size:{}",
+                    transfer.transfer_id, transfer.peer_id, transfer.size
+                )
+            }
+            AppEvent::FileTransferProgress { transfer } => {
+                format!(
+                    "transfer:{} {}/{} bytes",
+                    transfer.transfer_id, transfer.bytes_transferred, transfer.size
+                )
+            }
+            AppEvent::FileTransferCompleted { transfer, .. } => {
+                format!("transfer:{} {}", transfer.transfer_id, transfer.direction)
+            }
+            AppEvent::FileTransferFailed { transfer, reason } => {
+                format!("transfer:{} reason:{}", transfer.transfer_id, reason)
+            }
+            AppEvent::TransfersReport { transfers } => {
+                format!("transfers:{}", transfers.len())
+            }
+            AppEvent::GroupInvitation { group, from } => {
+                format!("group:{} from:{}", group.group_id, from)
+            }
+            AppEvent::GroupMembershipChanged {
+                group,
+                joined,
+                left,
+            } => {
+                format!(
+                    "group:{} joined:{} left:{}",
+                    group.group_id,
+                    joined.len(),
+                    left.len()
+                )
+            }
+            AppEvent::GroupMessageReceived {
+                group_id,
+                from,
+                content,
+                ..
+            } => {
+                format!("group:{} from:{} content:{:.20}...", group_id, from, content)
+            }
+            AppEvent::GroupMessageSent {
+                group_id, content, ..
+            } => {
+                format!("group:{} content:{:.20}...", group_id, content)
+            }
+            AppEvent::GroupsReport {
+                groups,
+                invitations,
+            } => {
+                format!("groups:{} invitations:{}", groups.len(), invitations.len())
+            }
+            AppEvent::DeviceLinkOffer { expires_at, .. } => format!("expires_at:{}", expires_at),
+            AppEvent::DeviceLinked { device } => {
+                format!("device:{} peer:{}", device.device_id, device.peer_id)
+            }
+            AppEvent::DevicesReport { devices } => format!("devices:{}", devices.len()),
         }
     }
 }
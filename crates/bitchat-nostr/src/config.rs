@@ -46,6 +46,15 @@ pub struct NostrRelayConfig {
     pub connection_timeout: Duration,
     /// Whether this relay is read-only
     pub read_only: bool,
+    /// Key used to answer this relay's NIP-42 `AUTH` challenges instead of the
+    /// transport's identity key
+    #[serde(
+        default,
+        serialize_with = "serialize_keys",
+        deserialize_with = "deserialize_keys",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub auth_key: Option<Keys>,
 }
 
 impl NostrRelayConfig {
@@ -54,8 +63,15 @@ impl NostrRelayConfig {
             url,
             connection_timeout: Duration::from_secs(10),
             read_only: false,
+            auth_key: None,
         }
     }
+
+    /// Authenticate to this relay with a dedicated key
+    pub fn with_auth_key(mut self, keys: Keys) -> Self {
+        self.auth_key = Some(keys);
+        self
+    }
 }
 
 /// Configuration for Nostr transport task
@@ -67,6 +83,9 @@ pub struct NostrConfig {
     pub connection_timeout: Duration,
     /// Maximum time to wait for message delivery
     pub message_timeout: Duration,
+    /// Number of relays each event is published to, picked by health score
+    #[serde(default = "default_max_publish_relays")]
+    pub max_publish_relays: usize,
     /// Maximum data size for transport layer
     pub max_data_size: usize,
     /// Whether to automatically reconnect to relays
@@ -82,6 +101,10 @@ pub struct NostrConfig {
     pub private_key: Option<Keys>,
 }
 
+fn default_max_publish_relays() -> usize {
+    3
+}
+
 impl Default for NostrConfig {
     fn default() -> Self {
         Self {
@@ -92,6 +115,7 @@ impl Default for NostrConfig {
             ],
             connection_timeout: Duration::from_secs(10),
             message_timeout: Duration::from_secs(30),
+            max_publish_relays: default_max_publish_relays(),
             max_data_size: 64000, // Nostr event content limit
             auto_reconnect: true,
             reconnect_interval: Duration::from_secs(5),
@@ -107,6 +131,7 @@ impl NostrConfig {
             relays: vec![NostrRelayConfig::new("ws://localhost:7777".to_string())],
             connection_timeout: Duration::from_secs(5),
             message_timeout: Duration::from_secs(10),
+            max_publish_relays: default_max_publish_relays(),
             max_data_size: 64000,
             auto_reconnect: true,
             reconnect_interval: Duration::from_secs(2),
@@ -141,6 +166,7 @@ impl NostrConfig {
             relays: vec![NostrRelayConfig::new(relay_url.to_string())],
             connection_timeout: Duration::from_secs(5),
             message_timeout: Duration::from_secs(10),
+            max_publish_relays: default_max_publish_relays(),
             max_data_size: 64000,
             auto_reconnect: true,
             reconnect_interval: Duration::from_secs(2),
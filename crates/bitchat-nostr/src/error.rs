@@ -47,11 +47,17 @@ pub enum NostrTransportError {
     #[error("Unknown peer: {peer_id}")]
     UnknownPeer { peer_id: PeerId },
 
+    #[error("No Nostr identity known for peer {peer_id}")]
+    NoNostrIdentity { peer_id: PeerId },
+
     #[error("Configuration error: {0}")]
     ConfigurationError(String),
 
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
+
+    #[error("Failed to fetch relay information for {relay}: {reason}")]
+    RelayInformationFailed { relay: String, reason: String },
 }
 
 #[cfg(not(target_arch = "wasm32"))]
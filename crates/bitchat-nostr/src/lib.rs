@@ -10,7 +10,10 @@
 //! - [`config`] - Transport configuration and settings
 //! - [`error`] - Error types specific to Nostr transport
 //! - [`message`] - BitChat message format for Nostr events
+//! - [`nip11`] - NIP-11 relay information documents
 //! - [`nip17`] - NIP-17 gift-wrapping for encrypted direct messages
+//! - [`nip42`] - NIP-42 relay authentication
+//! - `relay` - Embedded NIP-01 relay server (requires the `relay` feature)
 //! - [`transport`] - Transport task implementation using CSP channels
 //!
 //! ## Usage
@@ -60,10 +63,15 @@ pub mod config;
 pub mod embedding;
 pub mod error;
 pub mod message;
+pub mod nip11;
 pub mod nip17;
+pub mod nip42;
 pub mod relay_manager;
 pub mod transport;
 
+#[cfg(all(feature = "relay", not(target_arch = "wasm32")))]
+pub mod relay;
+
 #[cfg(test)]
 mod integration_tests;
 
@@ -72,9 +80,16 @@ pub use config::{NostrConfig, NostrRelayConfig};
 pub use embedding::{EmbeddingConfig, EmbeddingStrategy, NostrEmbeddedBitChat, BITCHAT_EMBEDDING_PREFIX};
 pub use error::NostrTransportError;
 pub use message::{BitchatNostrMessage, BITCHAT_KIND};
+pub use nip11::{RelayInformationDocument, RelayLimitation};
 pub use nip17::{Nip17Content, Nip17GiftUnwrapper, Nip17GiftWrapper, BITCHAT_NIP17_PREFIX};
-pub use relay_manager::{GeoRelayDirectory, NostrRelayManager, RelayHealth, RelayInfo, RelaySelectionStrategy};
+pub use relay_manager::{
+    GeoRelayDirectory, NostrRelayManager, PublishRequirements, RelayAuthState, RelayHealth,
+    RelayInfo, RelaySelectionStrategy,
+};
 pub use transport::NostrTransportTask;
 
+#[cfg(all(feature = "relay", not(target_arch = "wasm32")))]
+pub use relay::{RelayServer, RelayServerConfig, RelayServerHandle};
+
 // Re-export TransportTask trait for convenience
 pub use bitchat_core::transport_task::TransportTask;
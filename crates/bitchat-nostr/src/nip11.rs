@@ -0,0 +1,201 @@
+//! NIP-11 relay information documents
+//!
+//! Relays describe themselves with a JSON document served over HTTP from the relay
+//! URL when requested with `Accept: application/nostr+json`. The relay manager uses
+//! it to learn which NIPs a relay supports and what it is willing to carry before
+//! routing BitChat traffic to it.
+//!
+//! Every field is optional in practice, so parsing is deliberately lenient: missing
+//! or unknown fields never cause a document to be rejected.
+
+use serde::{Deserialize, Serialize};
+use std::string::String;
+use std::vec::Vec;
+
+use super::error::NostrTransportError;
+
+/// MIME type relays serve their information document under
+pub const NIP11_CONTENT_TYPE: &str = "application/nostr+json";
+
+// ----------------------------------------------------------------------------
+// Information Document
+// ----------------------------------------------------------------------------
+
+/// Relay information document as defined by NIP-11
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayInformationDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact: Option<String>,
+    /// NIPs the relay claims to implement
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supported_nips: Option<Vec<u16>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub software: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Server limitations imposed on clients
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limitation: Option<RelayLimitation>,
+}
+
+/// The `limitation` object of a NIP-11 document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayLimitation {
+    /// Maximum bytes of a single WebSocket message, including the `["EVENT", ...]` envelope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_message_length: Option<u64>,
+    /// Maximum open subscriptions per connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_subscriptions: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_filters: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_subid_length: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_event_tags: Option<u32>,
+    /// Maximum characters in an event's `content`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_content_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_pow_difficulty: Option<u32>,
+    /// NIP-42 authentication is required before any other action
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_required: Option<bool>,
+    /// Payment is required before the relay accepts events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_required: Option<bool>,
+    /// Writes are limited to some subset of clients
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restricted_writes: Option<bool>,
+}
+
+impl RelayInformationDocument {
+    /// Parse a document from JSON
+    pub fn from_json(json: &str) -> Result<Self, NostrTransportError> {
+        serde_json::from_str(json)
+            .map_err(|e| NostrTransportError::DeserializationFailed(e.to_string()))
+    }
+
+    /// Serialize the document to JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Whether the relay lists the given NIP; `None` when it publishes no list
+    pub fn supports_nip(&self, nip: u16) -> Option<bool> {
+        self.supported_nips.as_ref().map(|nips| nips.contains(&nip))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Fetching
+// ----------------------------------------------------------------------------
+
+/// HTTP URL of a relay's information document (`ws` → `http`, `wss` → `https`)
+pub fn information_document_url(relay_url: &str) -> Result<String, NostrTransportError> {
+    if let Some(rest) = relay_url.strip_prefix("wss://") {
+        Ok(format!("https://{}", rest))
+    } else if let Some(rest) = relay_url.strip_prefix("ws://") {
+        Ok(format!("http://{}", rest))
+    } else {
+        Err(NostrTransportError::InvalidRelayUrl {
+            url: relay_url.to_string(),
+        })
+    }
+}
+
+/// Fetch a relay's NIP-11 information document
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn fetch_information_document(
+    relay_url: &str,
+    timeout: core::time::Duration,
+) -> Result<RelayInformationDocument, NostrTransportError> {
+    let url = information_document_url(relay_url)?;
+    let failed = |reason: String| NostrTransportError::RelayInformationFailed {
+        relay: relay_url.to_string(),
+        reason,
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| failed(e.to_string()))?;
+    let body = client
+        .get(&url)
+        .header(reqwest::header::ACCEPT, NIP11_CONTENT_TYPE)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| failed(e.to_string()))?
+        .text()
+        .await
+        .map_err(|e| failed(e.to_string()))?;
+
+    RelayInformationDocument::from_json(&body).map_err(|e| failed(e.to_string()))
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_document() {
+        let json = r#"{
+            "name": "relay.example.com",
+            "supported_nips": [1, 11, 42, 59],
+            "software": "git+https://example.com/relay.git",
+            "limitation": {
+                "max_message_length": 16384,
+                "max_subscriptions": 20,
+                "max_content_length": 8196,
+                "auth_required": false,
+                "payment_required": true
+            },
+            "fees": {"admission": [{"amount": 1000000, "unit": "msats"}]}
+        }"#;
+
+        let document = RelayInformationDocument::from_json(json).unwrap();
+        assert_eq!(document.name.as_deref(), Some("relay.example.com"));
+        assert_eq!(document.supports_nip(59), Some(true));
+        assert_eq!(document.supports_nip(17), Some(false));
+
+        let limitation = document.limitation.unwrap();
+        assert_eq!(limitation.max_message_length, Some(16384));
+        assert_eq!(limitation.max_subscriptions, Some(20));
+        assert_eq!(limitation.payment_required, Some(true));
+    }
+
+    #[test]
+    fn test_parse_minimal_document() {
+        let document = RelayInformationDocument::from_json("{}").unwrap();
+        assert_eq!(document.supports_nip(1), None);
+        assert!(document.limitation.is_none());
+    }
+
+    #[test]
+    fn test_information_document_url() {
+        assert_eq!(
+            information_document_url("wss://relay.damus.io").unwrap(),
+            "https://relay.damus.io"
+        );
+        assert_eq!(
+            information_document_url("ws://localhost:7777").unwrap(),
+            "http://localhost:7777"
+        );
+        assert!(information_document_url("https://relay.damus.io").is_err());
+    }
+}
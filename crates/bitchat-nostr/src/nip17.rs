@@ -1,7 +1,8 @@
 //! NIP-17 Gift-wrapping for private direct messages
 //!
 //! This module implements the NIP-17 specification for encrypted direct messages
-//! with gift-wrapping to provide traffic analysis resistance.
+//! with gift-wrapping to provide traffic analysis resistance. Messages follow the
+//! NIP-59 rumor → seal → gift wrap layering, with both layers encrypted using NIP-44 v2.
 
 use serde::{Deserialize, Serialize};
 use std::format;
@@ -50,9 +51,13 @@ cfg_if::cfg_if! {
 /// Maximum content length for NIP-17 messages
 pub const MAX_NIP17_CONTENT_LENGTH: usize = 65535;
 
-/// Random expiration time range for gift-wrapped events (30-60 minutes)
-pub const MIN_EXPIRATION_SECONDS: i64 = 30 * 60;
-pub const MAX_EXPIRATION_SECONDS: i64 = 60 * 60;
+/// Event kind of the unsigned chat message (rumor) inside a seal
+pub const PRIVATE_DIRECT_MESSAGE_KIND: u16 = 14;
+
+/// How far back seal and gift wrap timestamps are randomized (24 hours)
+///
+/// Subscriptions for gift wraps must look back at least this far.
+pub const GIFT_WRAP_TIMESTAMP_JITTER_SECONDS: i64 = 24 * 60 * 60;
 
 /// BitChat content type prefix for embedded packets
 pub const BITCHAT_NIP17_PREFIX: &str = "bitchat1:";
@@ -170,6 +175,10 @@ impl Nip17GiftWrapper {
     }
 
     /// Create a gift-wrapped NIP-17 message
+    ///
+    /// The message travels as an unsigned kind 14 rumor, sealed (kind 13) with NIP-44
+    /// from the sender to the recipient, then gift-wrapped (kind 1059) with NIP-44
+    /// from a one-time ephemeral key to the same recipient.
     pub fn create_gift_wrapped_message(
         &mut self,
         content: &Nip17Content,
@@ -182,56 +191,44 @@ impl Nip17GiftWrapper {
                 let ephemeral_keys = self.ephemeral_keys.as_ref()
                     .ok_or_else(|| NostrTransportError::EncryptionFailed("No ephemeral keys".to_string()))?;
 
-                // Serialize content to JSON
-                let content_json = content.to_json()?;
-
-                // Step 1: Create the inner direct message event (kind 14)
-                let _inner_event = EventBuilder::new(Kind::EncryptedDirectMessage, "", vec![
-                    Tag::public_key(*recipient_pubkey),
-                ])
-                .custom_created_at(Timestamp::now())
-                .to_event(&self.sender_keys)
-                .map_err(|e| NostrTransportError::EncryptionFailed(format!("Failed to create inner event: {}", e)))?;
-
-                // Step 2: Encrypt the inner event content with recipient's pubkey
-                let secret_key = self.sender_keys.secret_key().map_err(|e| NostrTransportError::KeyOperationFailed(e.to_string()))?;
-                let encrypted_content = nip04::encrypt(
-                    secret_key,
+                // Step 1: Build the rumor, the unsigned kind 14 message itself
+                let mut rumor_tags = vec![Tag::public_key(*recipient_pubkey)];
+                if let Some(expiration) = content.expiration {
+                    rumor_tags.push(Tag::expiration(Timestamp::from(expiration as u64)));
+                }
+                let rumor = EventBuilder::new(Kind::Custom(PRIVATE_DIRECT_MESSAGE_KIND), content.content.clone(), rumor_tags)
+                    .custom_created_at(Timestamp::now())
+                    .to_unsigned_event(self.sender_keys.public_key());
+
+                // Step 2: Seal the rumor to the recipient with the sender's key
+                let sender_secret_key = self.sender_keys.secret_key().map_err(|e| NostrTransportError::KeyOperationFailed(e.to_string()))?;
+                let sealed_rumor = nip44::encrypt(
+                    sender_secret_key,
                     recipient_pubkey,
-                    &content_json,
-                ).map_err(|e| NostrTransportError::EncryptionFailed(format!("NIP-04 encryption failed: {}", e)))?;
-
-                // Step 3: Create the inner event with encrypted content
-                let inner_dm_event = EventBuilder::new(Kind::EncryptedDirectMessage, encrypted_content, vec![
-                    Tag::public_key(*recipient_pubkey),
-                ])
-                .custom_created_at(Timestamp::now())
-                .to_event(&self.sender_keys)
-                .map_err(|e| NostrTransportError::EncryptionFailed(format!("Failed to create inner DM event: {}", e)))?;
+                    rumor.as_json(),
+                    nip44::Version::V2,
+                ).map_err(|e| NostrTransportError::EncryptionFailed(format!("Seal encryption failed: {}", e)))?;
 
-                // Step 4: Serialize the inner event
-                let inner_event_json = inner_dm_event.as_json();
+                let seal = EventBuilder::new(Kind::Seal, sealed_rumor, vec![])
+                    .custom_created_at(Timestamp::from(self.generate_random_past_timestamp() as u64))
+                    .to_event(&self.sender_keys)
+                    .map_err(|e| NostrTransportError::EncryptionFailed(format!("Failed to create seal: {}", e)))?;
 
-                // Step 5: Generate a random recipient pubkey for gift-wrapping
-                let random_recipient = Keys::generate().public_key();
-
-                // Step 6: Encrypt the serialized inner event with the random recipient
+                // Step 3: Wrap the seal to the recipient with a one-time key
                 let ephemeral_secret_key = ephemeral_keys.secret_key().map_err(|e| NostrTransportError::KeyOperationFailed(e.to_string()))?;
-                let gift_wrapped_content = nip04::encrypt(
+                let gift_wrapped_content = nip44::encrypt(
                     ephemeral_secret_key,
-                    &random_recipient,
-                    &inner_event_json,
+                    recipient_pubkey,
+                    seal.as_json(),
+                    nip44::Version::V2,
                 ).map_err(|e| NostrTransportError::EncryptionFailed(format!("Gift-wrap encryption failed: {}", e)))?;
 
-                // Step 7: Create the outer gift-wrapped event (kind 1059)
-                let expiration_time = self.generate_random_expiration();
+                // No expiration tag: relays would drop the wrap before an offline
+                // recipient comes back for it
                 let outer_event = EventBuilder::new(
                     Kind::GiftWrap,
                     gift_wrapped_content,
-                    vec![
-                        Tag::public_key(random_recipient),
-                        Tag::expiration(Timestamp::from(expiration_time as u64)),
-                    ]
+                    vec![Tag::public_key(*recipient_pubkey)],
                 )
                 .custom_created_at(Timestamp::from(self.generate_random_past_timestamp() as u64))
                 .to_event(ephemeral_keys)
@@ -245,26 +242,6 @@ impl Nip17GiftWrapper {
         }
     }
 
-    /// Generate a random expiration time (30-60 minutes from now)
-    fn generate_random_expiration(&self) -> i64 {
-        cfg_if::cfg_if! {
-            if #[cfg(not(target_arch = "wasm32"))] {
-                use rand::Rng;
-                let now = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs() as i64;
-
-                let random_offset = rand::thread_rng().gen_range(MIN_EXPIRATION_SECONDS..=MAX_EXPIRATION_SECONDS);
-                now + random_offset
-            } else {
-                // WASM stub - use fixed offset
-                let now = js_sys::Date::now() as i64 / 1000;
-                now + MAX_EXPIRATION_SECONDS
-            }
-        }
-    }
-
     /// Generate a random timestamp in the past (for traffic analysis resistance)
     fn generate_random_past_timestamp(&self) -> i64 {
         cfg_if::cfg_if! {
@@ -275,8 +252,8 @@ impl Nip17GiftWrapper {
                     .unwrap_or_default()
                     .as_secs() as i64;
 
-                // Random time in the past 24 hours
-                let random_offset = rand::thread_rng().gen_range(0..86400);
+                // Random time in the recent past
+                let random_offset = rand::thread_rng().gen_range(0..GIFT_WRAP_TIMESTAMP_JITTER_SECONDS);
                 now - random_offset
             } else {
                 // WASM stub - use current time
@@ -308,6 +285,16 @@ impl Nip17GiftUnwrapper {
         &self,
         outer_event: &NostrEvent,
     ) -> Result<Option<Nip17Content>, NostrTransportError> {
+        Ok(self
+            .unwrap_gift_wrapped_message_with_sender(outer_event)?
+            .map(|(_, content)| content))
+    }
+
+    /// Unwrap a gift-wrapped NIP-17 message, returning the sender's public key from the seal
+    pub fn unwrap_gift_wrapped_message_with_sender(
+        &self,
+        outer_event: &NostrEvent,
+    ) -> Result<Option<(PublicKey, Nip17Content)>, NostrTransportError> {
         cfg_if::cfg_if! {
             if #[cfg(not(target_arch = "wasm32"))] {
                 // Check if this is a gift-wrapped event
@@ -315,53 +302,57 @@ impl Nip17GiftUnwrapper {
                     return Ok(None);
                 }
 
-                // Extract the recipient public key from the 'p' tag
-                let _recipient_pubkey = outer_event.tags.iter()
-                    .find_map(|tag| {
-                        match tag.as_vec().first() {
-                            Some(tag_name) if tag_name == "p" => {
-                                tag.as_vec().get(1).and_then(|pubkey_str| PublicKey::from_hex(pubkey_str).ok())
-                            },
-                            _ => None,
-                        }
-                    })
-                    .ok_or_else(|| NostrTransportError::EncryptionFailed("No recipient pubkey in gift wrap".to_string()))?;
-
-                // Step 1: Try to decrypt the outer gift-wrapped content
-                // We assume the gift wrap was encrypted to our public key
                 let our_secret_key = self.receiver_keys.secret_key()
                     .map_err(|e| NostrTransportError::KeyOperationFailed(e.to_string()))?;
 
-                // Try to decrypt the outer content using NIP-04 with the ephemeral sender
-                // Since we don't know the ephemeral sender key, we'll extract it from the event signature
-                let ephemeral_sender_pubkey = outer_event.pubkey;
-
-                let decrypted_outer = nip04::decrypt(
+                // Step 1: Open the gift wrap, encrypted from its ephemeral author to us
+                let seal_json = nip44::decrypt(
                     our_secret_key,
-                    &ephemeral_sender_pubkey,
+                    &outer_event.pubkey,
                     &outer_event.content,
                 ).map_err(|e| NostrTransportError::EncryptionFailed(format!("Failed to decrypt gift wrap: {}", e)))?;
 
-                // Step 2: Parse the inner event from the decrypted JSON
-                let inner_event: NostrEvent = serde_json::from_str(&decrypted_outer)
-                    .map_err(|e| NostrTransportError::DeserializationFailed(format!("Invalid inner event JSON: {}", e)))?;
-
-                // Step 3: Verify the inner event is a direct message (kind 4 or 14)
-                if inner_event.kind != Kind::EncryptedDirectMessage {
-                    return Err(NostrTransportError::EncryptionFailed("Inner event is not a direct message".to_string()));
+                let seal = NostrEvent::from_json(&seal_json)
+                    .map_err(|e| NostrTransportError::DeserializationFailed(format!("Invalid seal JSON: {}", e)))?;
+                if seal.kind != Kind::Seal {
+                    return Err(NostrTransportError::EncryptionFailed("Gift wrap does not contain a seal".to_string()));
                 }
+                seal.verify()
+                    .map_err(|e| NostrTransportError::EncryptionFailed(format!("Invalid seal signature: {}", e)))?;
 
-                // Step 4: Decrypt the inner direct message content
-                let inner_sender_pubkey = inner_event.pubkey;
-                let decrypted_inner = nip04::decrypt(
+                // Step 2: Open the seal, encrypted from the real sender to us
+                let rumor_json = nip44::decrypt(
                     our_secret_key,
-                    &inner_sender_pubkey,
-                    &inner_event.content,
-                ).map_err(|e| NostrTransportError::EncryptionFailed(format!("Failed to decrypt inner message: {}", e)))?;
+                    &seal.pubkey,
+                    &seal.content,
+                ).map_err(|e| NostrTransportError::EncryptionFailed(format!("Failed to decrypt seal: {}", e)))?;
+
+                let rumor = UnsignedEvent::from_json(&rumor_json)
+                    .map_err(|e| NostrTransportError::DeserializationFailed(format!("Invalid rumor JSON: {}", e)))?;
+
+                // The seal signature is the only proof of authorship, so the rumor must claim the same author
+                if rumor.pubkey != seal.pubkey {
+                    return Err(NostrTransportError::EncryptionFailed("Rumor author does not match seal signer".to_string()));
+                }
+                if rumor.kind != Kind::Custom(PRIVATE_DIRECT_MESSAGE_KIND) {
+                    return Ok(None);
+                }
 
-                // Step 5: Parse the final content
-                let content = Nip17Content::from_json(&decrypted_inner)?;
-                Ok(Some(content))
+                // Step 3: Read the message and its optional expiration tag
+                let expiration = rumor.tags.iter().find_map(|tag| {
+                    let tag = tag.as_vec();
+                    match tag.first() {
+                        Some(tag_name) if tag_name == "expiration" => {
+                            tag.get(1).and_then(|value| value.parse::<i64>().ok())
+                        }
+                        _ => None,
+                    }
+                });
+
+                Ok(Some((seal.pubkey, Nip17Content {
+                    content: rumor.content,
+                    expiration,
+                })))
             } else {
                 // WASM stub implementation
                 Err(NostrTransportError::EncryptionFailed("NIP-17 not implemented for WASM".to_string()))
@@ -369,6 +360,41 @@ impl Nip17GiftUnwrapper {
         }
     }
 
+    /// Unwrap a gift-wrapped BitChat packet sealed by its sender's bound Nostr key
+    ///
+    /// Anyone can gift wrap a packet claiming any sender ID, so the seal must be
+    /// signed by the key `bound_key` returns for the packet's sender. Packets from
+    /// senders without a bound key, or sealed by another key, are rejected.
+    pub fn unwrap_bitchat_packet<F>(
+        &self,
+        outer_event: &NostrEvent,
+        bound_key: F,
+    ) -> Result<Option<BitchatPacket>, NostrTransportError>
+    where
+        F: FnOnce(&PeerId) -> Option<PublicKey>,
+    {
+        cfg_if::cfg_if! {
+            if #[cfg(not(target_arch = "wasm32"))] {
+                let Some((seal_author, content)) = self.unwrap_gift_wrapped_message_with_sender(outer_event)? else {
+                    return Ok(None);
+                };
+                let Some(packet) = content.to_bitchat_packet()? else {
+                    return Ok(None);
+                };
+                if bound_key(&packet.sender_id) != Some(seal_author) {
+                    return Err(NostrTransportError::EncryptionFailed(format!(
+                        "Seal signer {} is not the Nostr key bound to peer {}",
+                        seal_author, packet.sender_id
+                    )));
+                }
+                Ok(Some(packet))
+            } else {
+                let _ = (outer_event, bound_key);
+                Err(NostrTransportError::EncryptionFailed("NIP-17 not implemented for WASM".to_string()))
+            }
+        }
+    }
+
     /// Decrypt a standard NIP-04 encrypted direct message (fallback)
     pub fn decrypt_nip04_message(
         &self,
@@ -397,32 +423,6 @@ impl Nip17GiftUnwrapper {
 // Helper Functions
 // ----------------------------------------------------------------------------
 
-/// Convert PeerId to Nostr PublicKey (deterministic mapping)
-/// This creates a deterministic mapping from BitChat PeerIds to Nostr public keys
-/// by using the PeerId bytes as a seed for key derivation
-pub fn peer_id_to_pubkey(peer_id: &PeerId) -> Result<PublicKey, NostrTransportError> {
-    cfg_if::cfg_if! {
-        if #[cfg(not(target_arch = "wasm32"))] {
-            use sha2::{Sha256, Digest};
-
-            // Use PeerId bytes as seed for deterministic key generation
-            let peer_bytes = peer_id.as_bytes();
-
-            // Expand the 8-byte PeerId to 32 bytes using SHA-256
-            let mut hasher = Sha256::new();
-            hasher.update(b"bitchat_nostr_pubkey:");
-            hasher.update(peer_bytes);
-            let key_bytes = hasher.finalize();
-
-            // Create Nostr PublicKey from the derived bytes
-            PublicKey::from_slice(&key_bytes)
-                .map_err(|e| NostrTransportError::KeyOperationFailed(format!("Invalid public key: {}", e)))
-        } else {
-            Err(NostrTransportError::EncryptionFailed("Not implemented for WASM".to_string()))
-        }
-    }
-}
-
 /// Convert Nostr PublicKey to PeerId (deterministic mapping)
 /// This creates a deterministic mapping from Nostr public keys to BitChat PeerIds
 /// by hashing the public key bytes and taking the first 8 bytes
@@ -510,6 +510,252 @@ mod tests {
         assert!(wrapper.ephemeral_keys.is_some());
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_gift_wrap_round_trip() {
+        let sender_keys = Keys::generate();
+        let recipient_keys = Keys::generate();
+        let content = Nip17Content {
+            content: "hello over nostr".to_string(),
+            expiration: Some(1_900_000_000),
+        };
+
+        let mut wrapper = Nip17GiftWrapper::new(sender_keys.clone());
+        let gift_wrap = wrapper
+            .create_gift_wrapped_message(&content, &recipient_keys.public_key())
+            .unwrap();
+
+        // Only the recipient is visible on the outside, never the sender
+        assert_eq!(gift_wrap.kind, Kind::GiftWrap);
+        assert_ne!(gift_wrap.pubkey, sender_keys.public_key());
+        assert!(gift_wrap.tags.iter().any(|tag| {
+            tag.as_vec() == ["p".to_string(), recipient_keys.public_key().to_hex()]
+        }));
+        // Nor does it expire before an offline recipient can fetch it
+        assert!(!gift_wrap.tags.iter().any(|tag| tag
+            .as_vec()
+            .first()
+            .is_some_and(|name| name == "expiration")));
+
+        let unwrapper = Nip17GiftUnwrapper::new(recipient_keys);
+        let (sender, unwrapped) = unwrapper
+            .unwrap_gift_wrapped_message_with_sender(&gift_wrap)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sender, sender_keys.public_key());
+        assert_eq!(unwrapped.content, content.content);
+        assert_eq!(unwrapped.expiration, content.expiration);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_gift_wrap_unreadable_by_others() {
+        let recipient_keys = Keys::generate();
+        let content = Nip17Content {
+            content: "not for you".to_string(),
+            expiration: None,
+        };
+
+        let mut wrapper = Nip17GiftWrapper::new(Keys::generate());
+        let gift_wrap = wrapper
+            .create_gift_wrapped_message(&content, &recipient_keys.public_key())
+            .unwrap();
+
+        let eavesdropper = Nip17GiftUnwrapper::new(Keys::generate());
+        assert!(eavesdropper
+            .unwrap_gift_wrapped_message(&gift_wrap)
+            .is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_rumor_author_must_match_seal_signer() {
+        let impersonated = Keys::generate();
+        let forger = Keys::generate();
+        let recipient_keys = Keys::generate();
+        let recipient = recipient_keys.public_key();
+
+        // A rumor claiming to come from someone else, sealed by the forger
+        let rumor = EventBuilder::new(
+            Kind::Custom(PRIVATE_DIRECT_MESSAGE_KIND),
+            "trust me",
+            vec![Tag::public_key(recipient)],
+        )
+        .to_unsigned_event(impersonated.public_key());
+        let sealed = nip44::encrypt(
+            forger.secret_key().unwrap(),
+            &recipient,
+            rumor.as_json(),
+            nip44::Version::V2,
+        )
+        .unwrap();
+        let seal = EventBuilder::new(Kind::Seal, sealed, vec![])
+            .to_event(&forger)
+            .unwrap();
+
+        let ephemeral = Keys::generate();
+        let wrapped = nip44::encrypt(
+            ephemeral.secret_key().unwrap(),
+            &recipient,
+            seal.as_json(),
+            nip44::Version::V2,
+        )
+        .unwrap();
+        let gift_wrap =
+            EventBuilder::new(Kind::GiftWrap, wrapped, vec![Tag::public_key(recipient)])
+                .to_event(&ephemeral)
+                .unwrap();
+
+        let unwrapper = Nip17GiftUnwrapper::new(recipient_keys);
+        assert!(unwrapper.unwrap_gift_wrapped_message(&gift_wrap).is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_bitchat_packet_must_be_sealed_by_bound_key() {
+        use bitchat_core::protocol::{MessageType, PacketFlags};
+
+        let sender_keys = Keys::generate();
+        let recipient_keys = Keys::generate();
+        let sender = PeerId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let packet = BitchatPacket::new(
+            MessageType::Message,
+            sender,
+            None,
+            bitchat_core::types::Timestamp::now(),
+            b"sealed by me".to_vec(),
+            PacketFlags::NONE,
+        )
+        .unwrap();
+        let content = Nip17Content::from_bitchat_packet(&packet).unwrap();
+        let gift_wrap = Nip17GiftWrapper::new(sender_keys.clone())
+            .create_gift_wrapped_message(&content, &recipient_keys.public_key())
+            .unwrap();
+        let unwrapper = Nip17GiftUnwrapper::new(recipient_keys);
+
+        let unwrapped = unwrapper
+            .unwrap_bitchat_packet(&gift_wrap, |peer_id| {
+                (*peer_id == sender).then(|| sender_keys.public_key())
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(unwrapped.payload, b"sealed by me");
+
+        // The same wrap claims a peer bound to another key, or to none at all
+        assert!(unwrapper
+            .unwrap_bitchat_packet(&gift_wrap, |_| Some(Keys::generate().public_key()))
+            .is_err());
+        assert!(unwrapper
+            .unwrap_bitchat_packet(&gift_wrap, |_| None)
+            .is_err());
+    }
+
+    /// Official NIP-44 v2 test vectors
+    #[cfg(not(target_arch = "wasm32"))]
+    mod nip44_vectors {
+        use super::*;
+
+        use nostr_sdk::nostr::nips::nip44::v2::{self, ConversationKey};
+        use rand::RngCore;
+
+        /// Hands out a fixed nonce in place of random bytes
+        struct FixedNonce([u8; 32]);
+
+        impl RngCore for FixedNonce {
+            fn next_u32(&mut self) -> u32 {
+                unimplemented!("only nonces are drawn")
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                unimplemented!("only nonces are drawn")
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                dest.copy_from_slice(&self.0[..dest.len()]);
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        fn secret_key(hex: &str) -> SecretKey {
+            SecretKey::from_hex(hex).unwrap()
+        }
+
+        fn hex_bytes(hex: &str) -> Vec<u8> {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect()
+        }
+
+        /// First valid `get_conversation_key` vector
+        #[test]
+        fn test_conversation_key() {
+            let sec1 =
+                secret_key("315e59ff51cb9209768cf7da80791ddcaae56ac9775eb25b6dee1234bc5d2268");
+            let pub2 = PublicKey::from_hex(
+                "c2f9d9948dc8c7c38321e4b85c8558872eafa0641cd269db76848a6073e69133",
+            )
+            .unwrap();
+
+            let conversation_key = ConversationKey::derive(&sec1, &pub2);
+            assert_eq!(
+                conversation_key.as_bytes().to_vec(),
+                hex_bytes("3dfef0ce2a4d80a25e7a328accf73448ef67096f65f79588e358d9a0eb9013f1")
+            );
+        }
+
+        /// First valid `encrypt_decrypt` vector, encrypted under its fixed nonce
+        #[test]
+        fn test_encrypt_with_fixed_nonce() {
+            let sec1 =
+                secret_key("0000000000000000000000000000000000000000000000000000000000000001");
+            let sec2 =
+                secret_key("0000000000000000000000000000000000000000000000000000000000000002");
+            let pub2 = Keys::new(sec2).public_key();
+            let payload = "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABee0G5VSK0/9YypIObAtDKfYEAjD35uVkHyB0F4DwrcNaCXlCWZKaArsGrY6M9wnuTMxWfp1RTN9Xga8no+kF5Vsb";
+
+            let conversation_key = ConversationKey::derive(&sec1, &pub2);
+            assert_eq!(
+                conversation_key.as_bytes().to_vec(),
+                hex_bytes("c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d")
+            );
+
+            let mut nonce = [0u8; 32];
+            nonce[31] = 1;
+            let encrypted =
+                v2::encrypt_to_bytes_with_rng(&mut FixedNonce(nonce), &conversation_key, "a")
+                    .unwrap();
+            assert_eq!(general_purpose::STANDARD.encode(encrypted), payload);
+        }
+    }
+
+    /// First `encrypt_decrypt` vector from the NIP-44 v2 test vector set
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_nip44_v2_vector() {
+        let sec1 =
+            SecretKey::from_hex("0000000000000000000000000000000000000000000000000000000000000001")
+                .unwrap();
+        let sec2 =
+            SecretKey::from_hex("0000000000000000000000000000000000000000000000000000000000000002")
+                .unwrap();
+        let pub1 = Keys::new(sec1.clone()).public_key();
+        let pub2 = Keys::new(sec2.clone()).public_key();
+        let payload = "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABee0G5VSK0/9YypIObAtDKfYEAjD35uVkHyB0F4DwrcNaCXlCWZKaArsGrY6M9wnuTMxWfp1RTN9Xga8no+kF5Vsb";
+
+        // The conversation key is symmetric, so both sides read the same payload
+        assert_eq!(nip44::decrypt(&sec2, &pub1, payload).unwrap(), "a");
+        assert_eq!(nip44::decrypt(&sec1, &pub2, payload).unwrap(), "a");
+
+        // A tampered MAC must be rejected
+        let tampered = payload.replace("F5Vsb", "F5Vsc");
+        assert!(nip44::decrypt(&sec2, &pub1, tampered).is_err());
+    }
+
     #[test]
     fn test_non_bitchat_content() {
         let content = Nip17Content {
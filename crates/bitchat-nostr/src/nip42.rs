@@ -0,0 +1,111 @@
+//! NIP-42 client authentication
+//!
+//! Relays may send an `["AUTH", <challenge>]` message and refuse to accept events or
+//! serve subscriptions until the client answers with a signed kind 22242 event that
+//! carries the challenge and the relay URL. Refusals are signalled with an
+//! `auth-required:` prefix on `OK` and `CLOSED` messages.
+
+use nostr_sdk::prelude::*;
+use nostr_sdk::Event as NostrEvent;
+use std::string::String;
+
+use super::error::NostrTransportError;
+
+/// Machine-readable prefix relays put on messages refused for lack of authentication
+pub const AUTH_REQUIRED_PREFIX: &str = "auth-required:";
+
+/// How far an `AUTH` event's timestamp may drift from the relay's clock
+pub const AUTH_EVENT_MAX_AGE_SECS: u64 = 600;
+
+/// Build the signed event answering a relay's `AUTH` challenge
+pub fn build_auth_event(
+    keys: &Keys,
+    challenge: &str,
+    relay_url: &str,
+) -> Result<NostrEvent, NostrTransportError> {
+    let relay = Url::parse(relay_url).map_err(|_| NostrTransportError::InvalidRelayUrl {
+        url: relay_url.to_string(),
+    })?;
+
+    EventBuilder::auth(challenge, relay)
+        .to_event(keys)
+        .map_err(|e| NostrTransportError::KeyOperationFailed(e.to_string()))
+}
+
+/// Whether a relay's `OK` or `CLOSED` message, or a publish error wrapping one,
+/// refuses the request pending authentication
+pub fn is_auth_required(message: &str) -> bool {
+    message.contains(AUTH_REQUIRED_PREFIX)
+}
+
+/// Check an `AUTH` event received by a relay against the challenge it issued
+///
+/// Returns the authenticated public key.
+pub fn verify_auth_event(
+    event: &NostrEvent,
+    challenge: &str,
+    now: Timestamp,
+) -> Result<PublicKey, String> {
+    if event.kind != Kind::Authentication {
+        return Err("invalid: not an auth event".to_string());
+    }
+    if event.verify().is_err() {
+        return Err("invalid: bad event id or signature".to_string());
+    }
+
+    let age = now.as_u64().abs_diff(event.created_at.as_u64());
+    if age > AUTH_EVENT_MAX_AGE_SECS {
+        return Err("invalid: auth event is too old or too far in the future".to_string());
+    }
+
+    let answers_challenge = event.tags.iter().any(|tag| {
+        let tag = tag.as_vec();
+        tag.len() >= 2 && tag[0] == "challenge" && tag[1] == challenge
+    });
+    if !answers_challenge {
+        return Err("invalid: challenge does not match".to_string());
+    }
+
+    Ok(event.pubkey)
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_event_answers_challenge() {
+        let keys = Keys::generate();
+        let event = build_auth_event(&keys, "challenge-123", "wss://relay.example.com").unwrap();
+
+        assert_eq!(event.kind, Kind::Authentication);
+        assert_eq!(
+            verify_auth_event(&event, "challenge-123", Timestamp::now()),
+            Ok(keys.public_key())
+        );
+        assert!(verify_auth_event(&event, "other-challenge", Timestamp::now()).is_err());
+    }
+
+    #[test]
+    fn test_stale_auth_event_is_rejected() {
+        let keys = Keys::generate();
+        let event = build_auth_event(&keys, "challenge", "wss://relay.example.com").unwrap();
+        let later = Timestamp::from(event.created_at.as_u64() + AUTH_EVENT_MAX_AGE_SECS + 1);
+
+        assert!(verify_auth_event(&event, "challenge", later).is_err());
+    }
+
+    #[test]
+    fn test_auth_required_prefix() {
+        assert!(is_auth_required("auth-required: gift wraps need NIP-42"));
+        assert!(is_auth_required(
+            "event not published: auth-required: gift wraps need NIP-42"
+        ));
+        assert!(!is_auth_required("blocked: not on allow list"));
+        assert!(build_auth_event(&Keys::generate(), "c", "not a url").is_err());
+    }
+}
@@ -0,0 +1,743 @@
+//! Minimal embedded Nostr relay
+//!
+//! A small in-memory relay for tests and private deployments that should not depend
+//! on external infrastructure. It speaks the NIP-01 client protocol (`EVENT`, `REQ`,
+//! `CLOSE`) and supports everything BitChat publishes:
+//!
+//! - Regular events are verified, stored and fanned out to matching subscriptions
+//! - Ephemeral kinds (20000-29999) are fanned out but never stored
+//! - Gift wraps (kind 1059) and other tagged events are matched with tag filters
+//!   such as `#p`, so recipients only see their own wraps
+//!
+//! Plain HTTP requests with `Accept: application/nostr+json` are answered with a
+//! NIP-11 information document describing the relay's limits.
+//!
+//! Events are kept in insertion order up to [`RelayServerConfig::max_stored_events`].
+//! Replaceable event semantics are intentionally not applied: BitChat's own kind is
+//! parameterized replaceable and every message must survive until it is fetched.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use nostr_sdk::prelude::*;
+use nostr_sdk::Event as NostrEvent;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+use super::error::NostrTransportError;
+use super::nip11::{RelayInformationDocument, RelayLimitation, NIP11_CONTENT_TYPE};
+
+/// First pause after `accept` fails, doubled on each further failure
+const ACCEPT_BACKOFF_START: Duration = Duration::from_millis(10);
+
+/// Longest pause between failed `accept` calls
+const ACCEPT_BACKOFF_MAX: Duration = Duration::from_secs(1);
+
+// ----------------------------------------------------------------------------
+// Configuration
+// ----------------------------------------------------------------------------
+
+/// Configuration for the embedded relay
+#[derive(Debug, Clone)]
+pub struct RelayServerConfig {
+    /// Address to listen on; port 0 picks a free port
+    pub listen_addr: SocketAddr,
+    /// Maximum number of stored events before the oldest are evicted
+    pub max_stored_events: usize,
+    /// Maximum number of open subscriptions per connection
+    pub max_subscriptions: usize,
+    /// Maximum size of a single WebSocket message in bytes
+    pub max_message_size: usize,
+}
+
+impl Default for RelayServerConfig {
+    fn default() -> Self {
+        Self {
+            // Matches NostrConfig::local_development()
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 7777)),
+            max_stored_events: 10_000,
+            max_subscriptions: 32,
+            max_message_size: 128 * 1024,
+        }
+    }
+}
+
+impl RelayServerConfig {
+    /// Configuration listening on the given address
+    pub fn with_listen_addr(listen_addr: SocketAddr) -> Self {
+        Self {
+            listen_addr,
+            ..Self::default()
+        }
+    }
+
+    /// Configuration for tests: loopback on a random free port
+    pub fn for_testing() -> Self {
+        Self::with_listen_addr(SocketAddr::from(([127, 0, 0, 1], 0)))
+    }
+
+    /// NIP-11 document advertising this relay's limits
+    pub fn information_document(&self) -> RelayInformationDocument {
+        RelayInformationDocument {
+            name: Some("bitchat embedded relay".to_string()),
+            description: Some(
+                "In-memory relay for BitChat tests and private deployments".to_string(),
+            ),
+            supported_nips: Some(vec![1, 11, 59]),
+            software: Some("bitchat-nostr".to_string()),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            limitation: Some(RelayLimitation {
+                max_message_length: Some(self.max_message_size as u64),
+                max_subscriptions: Some(self.max_subscriptions as u32),
+                auth_required: Some(false),
+                payment_required: Some(false),
+                ..RelayLimitation::default()
+            }),
+            ..RelayInformationDocument::default()
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Event Store
+// ----------------------------------------------------------------------------
+
+/// In-memory event storage
+#[derive(Debug, Default)]
+struct EventStore {
+    events: VecDeque<NostrEvent>,
+    ids: HashSet<EventId>,
+    capacity: usize,
+}
+
+impl EventStore {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    fn contains(&self, id: &EventId) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn insert(&mut self, event: NostrEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.events.len() >= self.capacity {
+            if let Some(evicted) = self.events.pop_front() {
+                self.ids.remove(&evicted.id);
+            }
+        }
+        self.ids.insert(event.id);
+        self.events.push_back(event);
+    }
+
+    /// Stored events matching any of the filters, newest first, honouring each filter's limit
+    fn query(&self, filters: &[Filter]) -> Vec<NostrEvent> {
+        let mut matched: Vec<&NostrEvent> = Vec::new();
+        let mut seen = HashSet::new();
+
+        for filter in filters {
+            let mut candidates: Vec<&NostrEvent> = self
+                .events
+                .iter()
+                .filter(|event| filter.match_event(event))
+                .collect();
+            candidates.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            if let Some(limit) = filter.limit {
+                candidates.truncate(limit);
+            }
+            for event in candidates {
+                if seen.insert(event.id) {
+                    matched.push(event);
+                }
+            }
+        }
+
+        matched.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        matched.into_iter().cloned().collect()
+    }
+
+    fn len(&self) -> usize {
+        self.events.len()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Relay Server
+// ----------------------------------------------------------------------------
+
+/// State shared between all connections
+#[derive(Debug)]
+struct RelayShared {
+    config: RelayServerConfig,
+    store: RwLock<EventStore>,
+    /// Newly accepted events for live subscriptions
+    live_events: broadcast::Sender<NostrEvent>,
+}
+
+/// Embedded Nostr relay bound to a local address
+#[derive(Debug)]
+pub struct RelayServer {
+    listener: TcpListener,
+    local_addr: SocketAddr,
+    shared: Arc<RelayShared>,
+}
+
+impl RelayServer {
+    /// Bind the relay to its configured address
+    pub async fn bind(config: RelayServerConfig) -> Result<Self, NostrTransportError> {
+        let listener = TcpListener::bind(config.listen_addr).await.map_err(|e| {
+            NostrTransportError::ConnectionFailed(format!(
+                "Failed to bind relay to {}: {}",
+                config.listen_addr, e
+            ))
+        })?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| NostrTransportError::ConnectionFailed(e.to_string()))?;
+        let (live_events, _) = broadcast::channel(1024);
+
+        Ok(Self {
+            listener,
+            local_addr,
+            shared: Arc::new(RelayShared {
+                store: RwLock::new(EventStore::new(config.max_stored_events)),
+                config,
+                live_events,
+            }),
+        })
+    }
+
+    /// Address the relay is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// WebSocket URL clients should connect to
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.local_addr)
+    }
+
+    /// Accept connections until the task is cancelled
+    ///
+    /// Failing to accept one connection, for instance because we ran out of file
+    /// descriptors or the client reset it first, doesn't stop the relay: we back
+    /// off briefly and keep accepting.
+    pub async fn run(self) -> Result<(), NostrTransportError> {
+        info!("Nostr relay listening on {}", self.url());
+
+        let mut backoff = ACCEPT_BACKOFF_START;
+        loop {
+            let (stream, peer_addr) = match self.listener.accept().await {
+                Ok(accepted) => {
+                    backoff = ACCEPT_BACKOFF_START;
+                    accepted
+                }
+                Err(e) => {
+                    warn!("Relay failed to accept a connection: {}", e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(ACCEPT_BACKOFF_MAX);
+                    continue;
+                }
+            };
+
+            let shared = Arc::clone(&self.shared);
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(shared, stream, peer_addr).await {
+                    debug!("Relay connection {} closed with error: {}", peer_addr, e);
+                }
+            });
+        }
+    }
+
+    /// Run the relay in a background task
+    pub fn spawn(self) -> RelayServerHandle {
+        let url = self.url();
+        let local_addr = self.local_addr;
+        let shared = Arc::clone(&self.shared);
+        let task = tokio::spawn(async move {
+            if let Err(e) = self.run().await {
+                warn!("Nostr relay stopped: {}", e);
+            }
+        });
+
+        RelayServerHandle {
+            url,
+            local_addr,
+            shared,
+            task,
+        }
+    }
+}
+
+/// Handle to a relay running in the background; the relay stops when it is dropped
+#[derive(Debug)]
+pub struct RelayServerHandle {
+    url: String,
+    local_addr: SocketAddr,
+    shared: Arc<RelayShared>,
+    task: JoinHandle<()>,
+}
+
+impl RelayServerHandle {
+    /// WebSocket URL clients should connect to
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Address the relay is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Number of events currently stored
+    pub async fn stored_event_count(&self) -> usize {
+        self.shared.store.read().await.len()
+    }
+
+    /// Stop accepting new connections
+    pub fn shutdown(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for RelayServerHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Connection Handling
+// ----------------------------------------------------------------------------
+
+async fn handle_connection(
+    shared: Arc<RelayShared>,
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+) -> Result<(), NostrTransportError> {
+    if is_information_request(&stream).await {
+        debug!("Relay serving NIP-11 document to {}", peer_addr);
+        return serve_information_document(&shared.config, stream).await;
+    }
+
+    let ws_config = WebSocketConfig {
+        max_message_size: Some(shared.config.max_message_size),
+        max_frame_size: Some(shared.config.max_message_size),
+        ..Default::default()
+    };
+    let websocket = tokio_tungstenite::accept_async_with_config(stream, Some(ws_config))
+        .await
+        .map_err(|e| NostrTransportError::ConnectionFailed(e.to_string()))?;
+    let (mut sink, mut source) = websocket.split();
+    debug!("Relay accepted connection from {}", peer_addr);
+
+    let mut live_events = shared.live_events.subscribe();
+    let mut subscriptions: HashMap<SubscriptionId, Vec<Filter>> = HashMap::new();
+
+    loop {
+        let replies = tokio::select! {
+            message = source.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_client_message(&shared, &mut subscriptions, &text).await
+                }
+                Some(Ok(Message::Ping(data))) => {
+                    sink.send(Message::Pong(data))
+                        .await
+                        .map_err(|e| NostrTransportError::ConnectionFailed(e.to_string()))?;
+                    continue;
+                }
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(NostrTransportError::ConnectionFailed(e.to_string())),
+            },
+            event = live_events.recv() => match event {
+                Ok(event) => subscriptions
+                    .iter()
+                    .filter(|(_, filters)| filters.iter().any(|filter| filter.match_event(&event)))
+                    .map(|(id, _)| RelayMessage::event(id.clone(), event.clone()))
+                    .collect(),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Relay connection {} lagged, skipped {} events", peer_addr, skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+
+        for reply in replies {
+            sink.send(Message::Text(reply.as_json()))
+                .await
+                .map_err(|e| NostrTransportError::ConnectionFailed(e.to_string()))?;
+        }
+    }
+
+    debug!("Relay connection from {} closed", peer_addr);
+    Ok(())
+}
+
+/// Whether the connection carries a NIP-11 request rather than a WebSocket upgrade
+///
+/// Only peeks at the request head, so the stream can still be handed to the
+/// WebSocket handshake afterwards.
+async fn is_information_request(stream: &TcpStream) -> bool {
+    let mut buf = [0u8; 4096];
+    for _ in 0..100 {
+        let read = match stream.peek(&mut buf).await {
+            Ok(0) | Err(_) => return false,
+            Ok(read) => read,
+        };
+        let head = String::from_utf8_lossy(&buf[..read]).to_ascii_lowercase();
+        if head.contains("\r\n\r\n") || read == buf.len() {
+            return head.contains(NIP11_CONTENT_TYPE) && !head.contains("upgrade: websocket");
+        }
+        // The request head has not fully arrived yet
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    false
+}
+
+/// Answer a NIP-11 request and close the connection
+async fn serve_information_document(
+    config: &RelayServerConfig,
+    mut stream: TcpStream,
+) -> Result<(), NostrTransportError> {
+    let io_error = |e: std::io::Error| NostrTransportError::ConnectionFailed(e.to_string());
+
+    // Consume the request head; the document does not depend on it
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192 {
+        let read = stream.read(&mut buf).await.map_err(io_error)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let body = config.information_document().to_json();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        NIP11_CONTENT_TYPE,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(io_error)?;
+    stream.shutdown().await.map_err(io_error)
+}
+
+/// Process one client message and return the replies to send
+async fn handle_client_message(
+    shared: &RelayShared,
+    subscriptions: &mut HashMap<SubscriptionId, Vec<Filter>>,
+    text: &str,
+) -> Vec<RelayMessage> {
+    let message = match ClientMessage::from_json(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return vec![RelayMessage::notice(format!(
+                "error: invalid message: {}",
+                e
+            ))]
+        }
+    };
+
+    match message {
+        ClientMessage::Event(event) => vec![handle_event(shared, *event).await],
+        ClientMessage::Req {
+            subscription_id,
+            filters,
+        } => {
+            if !subscriptions.contains_key(&subscription_id)
+                && subscriptions.len() >= shared.config.max_subscriptions
+            {
+                return vec![RelayMessage::closed(
+                    subscription_id,
+                    "error: too many subscriptions",
+                )];
+            }
+
+            let stored = shared.store.read().await.query(&filters);
+            let mut replies: Vec<RelayMessage> = stored
+                .into_iter()
+                .map(|event| RelayMessage::event(subscription_id.clone(), event))
+                .collect();
+            replies.push(RelayMessage::eose(subscription_id.clone()));
+
+            // A REQ with an existing ID replaces the previous subscription
+            subscriptions.insert(subscription_id, filters);
+            replies
+        }
+        ClientMessage::Close(subscription_id) => {
+            subscriptions.remove(&subscription_id);
+            Vec::new()
+        }
+        _ => vec![RelayMessage::notice("error: unsupported message")],
+    }
+}
+
+/// Verify, store and fan out a published event
+async fn handle_event(shared: &RelayShared, event: NostrEvent) -> RelayMessage {
+    let event_id = event.id;
+    if event.verify().is_err() {
+        return RelayMessage::ok(event_id, false, "invalid: bad event id or signature");
+    }
+
+    if !event.kind.is_ephemeral() {
+        let mut store = shared.store.write().await;
+        if store.contains(&event_id) {
+            return RelayMessage::ok(event_id, true, "duplicate: already have this event");
+        }
+        store.insert(event.clone());
+    }
+
+    // Nobody listening is not an error
+    let _ = shared.live_events.send(event);
+    RelayMessage::ok(event_id, true, "")
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    async fn start_relay() -> RelayServerHandle {
+        RelayServer::bind(RelayServerConfig::for_testing())
+            .await
+            .unwrap()
+            .spawn()
+    }
+
+    async fn connect(relay: &RelayServerHandle) -> Client {
+        let (client, _) = tokio_tungstenite::connect_async(relay.url()).await.unwrap();
+        client
+    }
+
+    async fn send(client: &mut Client, message: ClientMessage) {
+        client.send(Message::Text(message.as_json())).await.unwrap();
+    }
+
+    async fn recv(client: &mut Client) -> RelayMessage {
+        loop {
+            let message = tokio::time::timeout(Duration::from_secs(2), client.next())
+                .await
+                .expect("relay should reply")
+                .unwrap()
+                .unwrap();
+            if let Message::Text(text) = message {
+                return RelayMessage::from_json(text).unwrap();
+            }
+        }
+    }
+
+    async fn publish(client: &mut Client, event: NostrEvent) {
+        send(client, ClientMessage::event(event)).await;
+        assert!(matches!(
+            recv(client).await,
+            RelayMessage::Ok { status: true, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stored_events_are_returned_before_eose() {
+        let relay = start_relay().await;
+        let mut client = connect(&relay).await;
+        let keys = Keys::generate();
+
+        let note = EventBuilder::text_note("hello relay", [])
+            .to_event(&keys)
+            .unwrap();
+        publish(&mut client, note.clone()).await;
+
+        let sub = SubscriptionId::new("stored");
+        send(
+            &mut client,
+            ClientMessage::req(sub.clone(), vec![Filter::new().author(keys.public_key())]),
+        )
+        .await;
+
+        match recv(&mut client).await {
+            RelayMessage::Event {
+                subscription_id,
+                event,
+            } => {
+                assert_eq!(subscription_id, sub);
+                assert_eq!(event.id, note.id);
+            }
+            other => panic!("expected stored event, got {:?}", other),
+        }
+        assert!(matches!(
+            recv(&mut client).await,
+            RelayMessage::EndOfStoredEvents(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_live_subscription_and_close() {
+        let relay = start_relay().await;
+        let mut subscriber = connect(&relay).await;
+        let mut publisher = connect(&relay).await;
+        let keys = Keys::generate();
+
+        let sub = SubscriptionId::new("live");
+        send(
+            &mut subscriber,
+            ClientMessage::req(sub.clone(), vec![Filter::new().kind(Kind::TextNote)]),
+        )
+        .await;
+        assert!(matches!(
+            recv(&mut subscriber).await,
+            RelayMessage::EndOfStoredEvents(_)
+        ));
+
+        let note = EventBuilder::text_note("live", []).to_event(&keys).unwrap();
+        publish(&mut publisher, note.clone()).await;
+        match recv(&mut subscriber).await {
+            RelayMessage::Event { event, .. } => assert_eq!(event.id, note.id),
+            other => panic!("expected live event, got {:?}", other),
+        }
+
+        send(&mut subscriber, ClientMessage::close(sub)).await;
+        let marker_kind = Kind::Custom(4242);
+        let other = SubscriptionId::new("other");
+        send(
+            &mut subscriber,
+            ClientMessage::req(other, vec![Filter::new().kind(marker_kind)]),
+        )
+        .await;
+        assert!(matches!(
+            recv(&mut subscriber).await,
+            RelayMessage::EndOfStoredEvents(_)
+        ));
+
+        // The closed subscription no longer sees text notes, so the marker arrives first
+        let after_close = EventBuilder::text_note("after close", [])
+            .to_event(&keys)
+            .unwrap();
+        publish(&mut publisher, after_close).await;
+        let marker = EventBuilder::new(marker_kind, "marker", [])
+            .to_event(&keys)
+            .unwrap();
+        publish(&mut publisher, marker.clone()).await;
+        match recv(&mut subscriber).await {
+            RelayMessage::Event { event, .. } => assert_eq!(event.id, marker.id),
+            other => panic!("expected marker event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_events_are_not_stored() {
+        let relay = start_relay().await;
+        let mut client = connect(&relay).await;
+        let keys = Keys::generate();
+
+        let ephemeral = EventBuilder::new(Kind::Custom(20_001), "presence", [])
+            .to_event(&keys)
+            .unwrap();
+        publish(&mut client, ephemeral).await;
+
+        assert_eq!(relay.stored_event_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_gift_wraps_match_recipient_tag() {
+        let relay = start_relay().await;
+        let mut client = connect(&relay).await;
+        let wrapper = Keys::generate();
+        let alice = Keys::generate();
+        let bob = Keys::generate();
+
+        let mut wraps = Vec::new();
+        for recipient in [&alice, &bob] {
+            let wrap = EventBuilder::new(
+                Kind::GiftWrap,
+                "sealed",
+                [Tag::public_key(recipient.public_key())],
+            )
+            .to_event(&wrapper)
+            .unwrap();
+            wraps.push(wrap.id);
+            publish(&mut client, wrap).await;
+        }
+
+        let sub = SubscriptionId::new("inbox");
+        let filter = Filter::new()
+            .kind(Kind::GiftWrap)
+            .pubkey(alice.public_key());
+        send(&mut client, ClientMessage::req(sub, vec![filter])).await;
+
+        match recv(&mut client).await {
+            RelayMessage::Event { event, .. } => assert_eq!(event.id, wraps[0]),
+            other => panic!("expected gift wrap, got {:?}", other),
+        }
+        assert!(matches!(
+            recv(&mut client).await,
+            RelayMessage::EndOfStoredEvents(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_serves_information_document() {
+        let relay = start_relay().await;
+
+        let document =
+            crate::nip11::fetch_information_document(relay.url(), Duration::from_secs(2))
+                .await
+                .unwrap();
+        assert_eq!(document.supports_nip(11), Some(true));
+        assert_eq!(document.supports_nip(59), Some(true));
+        assert_eq!(
+            document.limitation.unwrap().max_message_length,
+            Some(RelayServerConfig::default().max_message_size as u64)
+        );
+
+        // WebSocket clients on the same port are unaffected
+        let mut client = connect(&relay).await;
+        let note = EventBuilder::text_note("after nip-11", [])
+            .to_event(&Keys::generate())
+            .unwrap();
+        publish(&mut client, note).await;
+    }
+
+    #[test]
+    fn test_store_evicts_oldest() {
+        let keys = Keys::generate();
+        let mut store = EventStore::new(2);
+        let events: Vec<NostrEvent> = (0..3)
+            .map(|i| {
+                EventBuilder::text_note(format!("note {}", i), [])
+                    .to_event(&keys)
+                    .unwrap()
+            })
+            .collect();
+        for event in &events {
+            store.insert(event.clone());
+        }
+
+        assert_eq!(store.len(), 2);
+        assert!(!store.contains(&events[0].id));
+        assert!(store.contains(&events[2].id));
+    }
+}
@@ -9,6 +9,7 @@ use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
 use super::error::NostrTransportError;
+use super::nip11::RelayInformationDocument;
 use bitchat_core::types::{PeerId, TimeSource, Timestamp};
 
 cfg_if::cfg_if! {
@@ -50,6 +51,25 @@ impl Default for RelayHealth {
     }
 }
 
+/// NIP-42 authentication state of a relay connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RelayAuthState {
+    /// No challenge received yet
+    Unauthenticated,
+    /// Challenge answered, waiting for the relay's `OK`
+    Pending,
+    /// Relay accepted our `AUTH` event
+    Authenticated,
+    /// Relay rejected our `AUTH` event
+    Failed,
+}
+
+impl Default for RelayAuthState {
+    fn default() -> Self {
+        Self::Unauthenticated
+    }
+}
+
 /// Relay capabilities and features
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelayCapabilities {
@@ -65,6 +85,21 @@ pub struct RelayCapabilities {
     pub rate_limit_per_minute: Option<u32>,
     /// Payment required for posting
     pub requires_payment: bool,
+    /// NIP-42 authentication required
+    #[serde(default)]
+    pub requires_auth: bool,
+    /// NIPs listed in the relay's information document, if it lists any
+    #[serde(default)]
+    pub supported_nips: Option<Vec<u16>>,
+    /// Maximum characters in an event's content
+    #[serde(default)]
+    pub max_content_length: Option<usize>,
+    /// Maximum open subscriptions per connection
+    #[serde(default)]
+    pub max_subscriptions: Option<u32>,
+    /// Whether these capabilities came from the relay's NIP-11 document
+    #[serde(default)]
+    pub discovered: bool,
 }
 
 impl Default for RelayCapabilities {
@@ -76,8 +111,89 @@ impl Default for RelayCapabilities {
             max_event_size: Some(65535), // Common limit
             rate_limit_per_minute: None,
             requires_payment: false,
+            requires_auth: false,
+            supported_nips: None,
+            max_content_length: None,
+            max_subscriptions: None,
+            discovered: false,
+        }
+    }
+}
+
+impl RelayCapabilities {
+    /// Fill in capabilities from a relay's NIP-11 information document
+    ///
+    /// Limits the document leaves out keep their current values. NIP-11 defines
+    /// no rate-limit field, so `rate_limit_per_minute` is never overwritten.
+    pub fn apply_information_document(&mut self, document: &RelayInformationDocument) {
+        if let Some(nips) = &document.supported_nips {
+            self.supports_nip04 = nips.contains(&4);
+            self.supports_nip17 = nips.contains(&17) || nips.contains(&59);
+            self.supported_nips = Some(nips.clone());
+        }
+
+        if let Some(limitation) = &document.limitation {
+            if let Some(max) = limitation.max_message_length {
+                self.max_event_size = Some(max as usize);
+            }
+            if let Some(max) = limitation.max_content_length {
+                self.max_content_length = Some(max as usize);
+            }
+            if limitation.max_subscriptions.is_some() {
+                self.max_subscriptions = limitation.max_subscriptions;
+            }
+            self.requires_auth = limitation.auth_required.unwrap_or(false);
+            self.requires_payment = limitation.payment_required.unwrap_or(false);
+        }
+
+        self.discovered = true;
+    }
+
+    /// Whether the relay can carry gift-wrapped (NIP-59) events
+    ///
+    /// Relays that publish no NIP list get the benefit of the doubt.
+    pub fn supports_gift_wrap(&self) -> bool {
+        match &self.supported_nips {
+            Some(_) => self.supports_nip17,
+            None => true,
         }
     }
+
+    /// Whether an event with the given requirements can be published to the relay
+    ///
+    /// Until the relay's information document has been fetched nothing is ruled
+    /// out, since the defaults are guesses rather than advertised limits.
+    pub fn can_carry(&self, requirements: &PublishRequirements) -> bool {
+        if !self.discovered {
+            return true;
+        }
+        if self.requires_payment {
+            return false;
+        }
+        if requirements.gift_wrapped && !self.supports_gift_wrap() {
+            return false;
+        }
+        if self
+            .max_event_size
+            .is_some_and(|max| requirements.message_size > max)
+        {
+            return false;
+        }
+        !self
+            .max_content_length
+            .is_some_and(|max| requirements.content_length > max)
+    }
+}
+
+/// What an event needs from a relay in order to be published there
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PublishRequirements {
+    /// Size of the full `["EVENT", ...]` client message in bytes
+    pub message_size: usize,
+    /// Characters in the event content
+    pub content_length: usize,
+    /// Whether the event is a NIP-59 gift wrap
+    pub gift_wrapped: bool,
 }
 
 /// Comprehensive relay information and status
@@ -103,6 +219,71 @@ pub struct RelayInfo {
     pub consecutive_failures: u32,
     /// Whether relay is currently connected
     pub is_connected: bool,
+    /// Relay is skipped by selection until this time after repeated failures
+    #[serde(default)]
+    pub retry_after: Option<Timestamp>,
+    /// NIP-42 authentication state
+    #[serde(default)]
+    pub auth_state: RelayAuthState,
+    /// Relay is only read from and never selected for publishing
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl RelayInfo {
+    /// Create an untested relay entry for the given URL
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            health: RelayHealth::Unknown,
+            capabilities: RelayCapabilities::default(),
+            privacy_score: 0.7,
+            location: None,
+            stats: RelayStats::default(),
+            last_connection_attempt: None,
+            last_success: None,
+            consecutive_failures: 0,
+            is_connected: false,
+            retry_after: None,
+            auth_state: RelayAuthState::Unauthenticated,
+            read_only: false,
+        }
+    }
+
+    /// Whether the relay is still backing off at the given time
+    pub fn is_backing_off(&self, now: Timestamp) -> bool {
+        self.retry_after
+            .is_some_and(|retry_after| now < retry_after)
+    }
+
+    /// Combined health score (0.0 = worst, 1.0 = best) used to rank relays
+    ///
+    /// Untested relays score in the middle so they get a chance ahead of
+    /// relays that are known to be failing.
+    pub fn health_score(&self) -> f64 {
+        let base = match self.health {
+            RelayHealth::Healthy => 1.0,
+            RelayHealth::Degraded => 0.6,
+            RelayHealth::Unknown => 0.5,
+            RelayHealth::Unhealthy => 0.1,
+        };
+
+        let total_events = self.stats.events_sent + self.stats.events_failed;
+        let success = if total_events == 0 {
+            1.0
+        } else {
+            self.stats.event_success_rate()
+        };
+
+        // Penalise slow relays, bottoming out at half score for 5s+ latency
+        let latency = self
+            .stats
+            .average_latency_ms
+            .map(|ms| 1.0 - (ms.min(5000) as f64 / 10000.0))
+            .unwrap_or(1.0);
+
+        base * success * latency
+    }
 }
 
 /// Relay connection and performance statistics
@@ -196,6 +377,9 @@ impl GeoRelayDirectory {
                 last_success: None,
                 consecutive_failures: 0,
                 is_connected: false,
+                retry_after: None,
+                auth_state: RelayAuthState::Unauthenticated,
+                read_only: false,
             })
             .collect()
     }
@@ -253,6 +437,9 @@ impl GeoRelayDirectory {
                     last_success: None,
                     consecutive_failures: 0,
                     is_connected: false,
+                    retry_after: None,
+                    auth_state: RelayAuthState::Unauthenticated,
+                    read_only: false,
                 };
 
                 self.add_geo_relay(geohash_prefix, relay_info);
@@ -471,50 +658,100 @@ impl<T: TimeSource + Clone + Send + Sync + 'static> NostrRelayManager<T> {
 
     /// Select relays based on current strategy
     pub fn select_relays(&self, geohash: Option<&str>, count: usize) -> Vec<String> {
+        self.select_relays_with(geohash, count, None)
+    }
+
+    /// Select relays based on current strategy, skipping relays that cannot carry the event
+    pub fn select_relays_for(
+        &self,
+        geohash: Option<&str>,
+        count: usize,
+        requirements: &PublishRequirements,
+    ) -> Vec<String> {
+        self.select_relays_with(geohash, count, Some(requirements))
+    }
+
+    fn select_relays_with(
+        &self,
+        geohash: Option<&str>,
+        count: usize,
+        requirements: Option<&PublishRequirements>,
+    ) -> Vec<String> {
         match self.selection_strategy {
             RelaySelectionStrategy::Geographic => {
                 if let Some(geohash) = geohash {
                     self.geo_directory
                         .closest_relays(geohash, count)
                         .into_iter()
+                        .filter(|r| requirements.is_none_or(|req| r.capabilities.can_carry(req)))
                         .map(|r| r.url)
                         .collect()
                 } else {
-                    self.select_healthy_relays(count)
+                    self.select_healthy_relays(count, requirements)
                 }
             }
-            RelaySelectionStrategy::HealthBased => self.select_healthy_relays(count),
-            RelaySelectionStrategy::PrivacyFocused => self.select_privacy_relays(count),
-            RelaySelectionStrategy::RoundRobin => self.select_round_robin(count),
-            RelaySelectionStrategy::BroadcastAll => {
-                self.relays.keys().cloned().collect()
+            RelaySelectionStrategy::HealthBased => self.select_healthy_relays(count, requirements),
+            RelaySelectionStrategy::PrivacyFocused => {
+                self.select_privacy_relays(count, requirements)
             }
+            RelaySelectionStrategy::RoundRobin => self.select_round_robin(count, requirements),
+            RelaySelectionStrategy::BroadcastAll => self
+                .available_relays(requirements)
+                .into_iter()
+                .map(|r| r.url.clone())
+                .collect(),
+        }
+    }
+
+    /// Writable relays that can carry the event and are not currently backing off
+    ///
+    /// Falls back to every capable relay when all of them are in backoff, so a
+    /// transient outage never leaves the transport with nothing to publish to.
+    fn available_relays(&self, requirements: Option<&PublishRequirements>) -> Vec<&RelayInfo> {
+        let now = self.time_source.now();
+        let capable: Vec<_> = self
+            .relays
+            .values()
+            .filter(|relay| !relay.read_only)
+            .filter(|relay| requirements.is_none_or(|req| relay.capabilities.can_carry(req)))
+            .collect();
+        let available: Vec<_> = capable
+            .iter()
+            .copied()
+            .filter(|relay| !relay.is_backing_off(now))
+            .collect();
+
+        if available.is_empty() {
+            capable
+        } else {
+            available
         }
     }
 
     /// Select healthiest relays
-    fn select_healthy_relays(&self, count: usize) -> Vec<String> {
-        let mut relays: Vec<_> = self.relays.values().collect();
+    fn select_healthy_relays(
+        &self,
+        count: usize,
+        requirements: Option<&PublishRequirements>,
+    ) -> Vec<String> {
+        let mut relays = self.available_relays(requirements);
         relays.sort_by(|a, b| {
-            // Sort by health first, then by success rate
-            match (a.health, b.health) {
-                (RelayHealth::Healthy, RelayHealth::Healthy) => {
-                    b.stats.connection_success_rate()
-                        .partial_cmp(&a.stats.connection_success_rate())
-                        .unwrap_or(core::cmp::Ordering::Equal)
-                }
-                (RelayHealth::Healthy, _) => core::cmp::Ordering::Less,
-                (_, RelayHealth::Healthy) => core::cmp::Ordering::Greater,
-                _ => core::cmp::Ordering::Equal,
-            }
+            b.health_score()
+                .partial_cmp(&a.health_score())
+                .unwrap_or(core::cmp::Ordering::Equal)
+                .then_with(|| a.url.cmp(&b.url))
         });
 
         relays.into_iter().take(count).map(|r| r.url.clone()).collect()
     }
 
     /// Select highest privacy score relays
-    fn select_privacy_relays(&self, count: usize) -> Vec<String> {
-        let mut relays: Vec<_> = self.relays.values().collect();
+    fn select_privacy_relays(
+        &self,
+        count: usize,
+        requirements: Option<&PublishRequirements>,
+    ) -> Vec<String> {
+        let mut relays = self.available_relays(requirements);
         relays.sort_by(|a, b| {
             b.privacy_score
                 .partial_cmp(&a.privacy_score)
@@ -525,8 +762,16 @@ impl<T: TimeSource + Clone + Send + Sync + 'static> NostrRelayManager<T> {
     }
 
     /// Select relays using round-robin strategy
-    fn select_round_robin(&self, count: usize) -> Vec<String> {
-        let relay_urls: Vec<_> = self.relays.keys().cloned().collect();
+    fn select_round_robin(
+        &self,
+        count: usize,
+        requirements: Option<&PublishRequirements>,
+    ) -> Vec<String> {
+        let relay_urls: Vec<_> = self
+            .available_relays(requirements)
+            .into_iter()
+            .map(|r| r.url.clone())
+            .collect();
         if relay_urls.is_empty() {
             return Vec::new();
         }
@@ -551,11 +796,21 @@ impl<T: TimeSource + Clone + Send + Sync + 'static> NostrRelayManager<T> {
     }
 
     /// Update relay health based on connection results
+    ///
+    /// Each consecutive failure doubles the time the relay is left out of
+    /// selection, up to `max_backoff`; a success clears the backoff.
     pub fn update_relay_health(&mut self, url: &str, success: bool, latency_ms: Option<u64>) {
+        let now = self.time_source.now();
+        let failures = self
+            .relays
+            .get(url)
+            .map_or(0, |relay| relay.consecutive_failures + 1);
+        let backoff = self.backoff_for(failures);
         if let Some(relay) = self.relays.get_mut(url) {
             if success {
                 relay.consecutive_failures = 0;
-                relay.last_success = Some(self.time_source.now());
+                relay.last_success = Some(now);
+                relay.retry_after = None;
                 relay.health = RelayHealth::Healthy;
                 
                 if let Some(latency) = latency_ms {
@@ -574,6 +829,8 @@ impl<T: TimeSource + Clone + Send + Sync + 'static> NostrRelayManager<T> {
                 }
             } else {
                 relay.consecutive_failures += 1;
+                relay.last_connection_attempt = Some(now);
+                relay.retry_after = Some(now + backoff.as_millis() as u64);
                 if relay.consecutive_failures >= 3 {
                     relay.health = RelayHealth::Unhealthy;
                 }
@@ -581,6 +838,67 @@ impl<T: TimeSource + Clone + Send + Sync + 'static> NostrRelayManager<T> {
         }
     }
 
+    /// Record the outcome of publishing an event, as reported by the relay's `OK`
+    pub fn record_publish_result(&mut self, url: &str, accepted: bool, latency_ms: Option<u64>) {
+        if let Some(relay) = self.relays.get_mut(url) {
+            if accepted {
+                relay.stats.events_sent += 1;
+            } else {
+                relay.stats.events_failed += 1;
+            }
+        }
+        self.update_relay_health(url, accepted, latency_ms);
+    }
+
+    /// Backoff to apply after the given number of consecutive failures
+    fn backoff_for(&self, consecutive_failures: u32) -> Duration {
+        let exponent = consecutive_failures.saturating_sub(1).min(32) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exponent);
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    /// Record a relay's NIP-11 information document
+    ///
+    /// Returns `false` if the relay is not managed.
+    pub fn apply_information_document(
+        &mut self,
+        url: &str,
+        document: &RelayInformationDocument,
+    ) -> bool {
+        match self.relays.get_mut(url) {
+            Some(relay) => {
+                relay.capabilities.apply_information_document(document);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record a change in a relay's NIP-42 authentication state
+    ///
+    /// A successful authentication means the relay now requires it, and clears
+    /// any backoff caused by publishes it rejected beforehand.
+    pub fn set_auth_state(&mut self, url: &str, state: RelayAuthState) {
+        if let Some(relay) = self.relays.get_mut(url) {
+            relay.auth_state = state;
+            if state == RelayAuthState::Authenticated {
+                relay.capabilities.requires_auth = true;
+                relay.consecutive_failures = 0;
+                relay.retry_after = None;
+            }
+        }
+    }
+
+    /// Iterate over all known relays
+    pub fn relays(&self) -> impl Iterator<Item = &RelayInfo> {
+        self.relays.values()
+    }
+
+    /// Current time according to the manager's time source
+    pub fn now(&self) -> Timestamp {
+        self.time_source.now()
+    }
+
     /// Get relay manager statistics
     pub fn get_stats(&self) -> RelayManagerStats {
         let mut stats = RelayManagerStats::default();
@@ -684,6 +1002,9 @@ mod tests {
             last_success: None,
             consecutive_failures: 0,
             is_connected: true,
+            retry_after: None,
+            auth_state: RelayAuthState::Unauthenticated,
+            read_only: false,
         };
 
         assert_eq!(relay.url, "wss://relay.example.com");
@@ -719,6 +1040,9 @@ mod tests {
             last_success: None,
             consecutive_failures: 0,
             is_connected: false,
+            retry_after: None,
+            auth_state: RelayAuthState::Unauthenticated,
+            read_only: false,
         };
 
         directory.add_geo_relay("9q8yy".to_string(), relay);
@@ -745,6 +1069,9 @@ mod tests {
             last_success: None,
             consecutive_failures: 0,
             is_connected: false,
+            retry_after: None,
+            auth_state: RelayAuthState::Unauthenticated,
+            read_only: false,
         };
 
         let relay2 = RelayInfo {
@@ -758,6 +1085,9 @@ mod tests {
             last_success: None,
             consecutive_failures: 0,
             is_connected: false,
+            retry_after: None,
+            auth_state: RelayAuthState::Unauthenticated,
+            read_only: false,
         };
 
         manager.add_relay(relay1);
@@ -775,4 +1105,170 @@ mod tests {
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0], "wss://relay2.example.com"); // Higher privacy score
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_failed_publish_backs_off_relay() {
+        let mut manager = NostrRelayManager::new(SystemTimeSource);
+        manager.set_selection_strategy(RelaySelectionStrategy::HealthBased);
+        manager.add_relay(RelayInfo::new("wss://relay1.example.com"));
+        manager.add_relay(RelayInfo::new("wss://relay2.example.com"));
+
+        manager.record_publish_result("wss://relay1.example.com", false, None);
+
+        let relay = manager.get_relay("wss://relay1.example.com").unwrap();
+        assert_eq!(relay.stats.events_failed, 1);
+        assert!(relay.is_backing_off(manager.now()));
+        assert_eq!(
+            manager.select_relays(None, 2),
+            vec!["wss://relay2.example.com".to_string()]
+        );
+
+        // Once every relay is backing off, selection falls back to all of them
+        manager.record_publish_result("wss://relay2.example.com", false, None);
+        assert_eq!(manager.select_relays(None, 2).len(), 2);
+
+        manager.record_publish_result("wss://relay1.example.com", true, Some(120));
+        let relay = manager.get_relay("wss://relay1.example.com").unwrap();
+        assert_eq!(relay.retry_after, None);
+        assert_eq!(relay.consecutive_failures, 0);
+        assert_eq!(relay.stats.events_sent, 1);
+    }
+
+    #[test]
+    fn test_read_only_relay_tracks_auth_but_is_not_selected() {
+        let mut manager = NostrRelayManager::new(SystemTimeSource);
+        manager.set_selection_strategy(RelaySelectionStrategy::BroadcastAll);
+        manager.add_relay(RelayInfo::new("wss://write.example.com"));
+        manager.add_relay(RelayInfo {
+            read_only: true,
+            ..RelayInfo::new("wss://read.example.com")
+        });
+
+        assert_eq!(
+            manager.select_relays(None, 2),
+            vec!["wss://write.example.com".to_string()]
+        );
+
+        manager.set_auth_state("wss://read.example.com", RelayAuthState::Authenticated);
+        let relay = manager.get_relay("wss://read.example.com").unwrap();
+        assert_eq!(relay.auth_state, RelayAuthState::Authenticated);
+        assert!(relay.capabilities.requires_auth);
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_and_caps() {
+        let manager = NostrRelayManager::new(SystemTimeSource);
+
+        assert_eq!(manager.backoff_for(1), Duration::from_secs(1));
+        assert_eq!(manager.backoff_for(2), Duration::from_secs(2));
+        assert_eq!(manager.backoff_for(4), Duration::from_secs(8));
+        assert_eq!(manager.backoff_for(20), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_health_score_prefers_reliable_relays() {
+        let mut reliable = RelayInfo::new("wss://reliable.example.com");
+        reliable.health = RelayHealth::Healthy;
+        reliable.stats.events_sent = 10;
+        reliable.stats.average_latency_ms = Some(100);
+
+        let mut flaky = RelayInfo::new("wss://flaky.example.com");
+        flaky.health = RelayHealth::Degraded;
+        flaky.stats.events_sent = 5;
+        flaky.stats.events_failed = 5;
+
+        assert!(reliable.health_score() > flaky.health_score());
+        assert!(RelayInfo::new("wss://new.example.com").health_score() > flaky.health_score());
+    }
+
+    fn discovered_relay(url: &str, document_json: &str) -> RelayInfo {
+        let mut relay = RelayInfo::new(url);
+        relay.health = RelayHealth::Healthy;
+        relay.capabilities.apply_information_document(
+            &RelayInformationDocument::from_json(document_json).unwrap(),
+        );
+        relay
+    }
+
+    #[test]
+    fn test_information_document_fills_capabilities() {
+        let relay = discovered_relay(
+            "wss://relay.example.com",
+            r#"{"supported_nips": [1, 4, 11, 59],
+                "limitation": {"max_message_length": 16384, "max_subscriptions": 10,
+                               "auth_required": true}}"#,
+        );
+        let capabilities = &relay.capabilities;
+
+        assert!(capabilities.discovered);
+        assert!(capabilities.supports_nip04);
+        assert!(capabilities.supports_gift_wrap());
+        assert_eq!(capabilities.max_event_size, Some(16384));
+        assert_eq!(capabilities.max_subscriptions, Some(10));
+        assert!(capabilities.requires_auth);
+        assert!(!capabilities.requires_payment);
+    }
+
+    #[test]
+    fn test_selection_skips_relays_that_cannot_carry_event() {
+        let mut manager = NostrRelayManager::new(SystemTimeSource);
+        manager.set_selection_strategy(RelaySelectionStrategy::HealthBased);
+        manager.add_relay(discovered_relay(
+            "wss://small.example.com",
+            r#"{"supported_nips": [1, 11, 59], "limitation": {"max_message_length": 4096}}"#,
+        ));
+        manager.add_relay(discovered_relay(
+            "wss://public.example.com",
+            r#"{"supported_nips": [1, 11]}"#,
+        ));
+        manager.add_relay(RelayInfo::new("wss://undiscovered.example.com"));
+
+        let large_wrap = PublishRequirements {
+            message_size: 20_000,
+            content_length: 19_000,
+            gift_wrapped: true,
+        };
+        assert_eq!(
+            manager.select_relays_for(None, 3, &large_wrap),
+            vec!["wss://undiscovered.example.com".to_string()]
+        );
+
+        let small_wrap = PublishRequirements {
+            message_size: 1_000,
+            content_length: 800,
+            gift_wrapped: true,
+        };
+        let mut selected = manager.select_relays_for(None, 3, &small_wrap);
+        selected.sort();
+        assert_eq!(
+            selected,
+            vec![
+                "wss://small.example.com".to_string(),
+                "wss://undiscovered.example.com".to_string()
+            ]
+        );
+
+        // Capability checks only apply when routing a specific event
+        assert_eq!(manager.select_relays(None, 3).len(), 3);
+    }
+
+    #[test]
+    fn test_authentication_clears_backoff() {
+        let mut manager = NostrRelayManager::new(SystemTimeSource);
+        manager.add_relay(RelayInfo::new("wss://auth.example.com"));
+
+        // Publishes are rejected with `auth-required` until the challenge is answered
+        manager.record_publish_result("wss://auth.example.com", false, None);
+        manager.set_auth_state("wss://auth.example.com", RelayAuthState::Pending);
+        assert!(manager
+            .get_relay("wss://auth.example.com")
+            .unwrap()
+            .is_backing_off(manager.now()));
+
+        manager.set_auth_state("wss://auth.example.com", RelayAuthState::Authenticated);
+        let relay = manager.get_relay("wss://auth.example.com").unwrap();
+        assert_eq!(relay.auth_state, RelayAuthState::Authenticated);
+        assert!(relay.capabilities.requires_auth);
+        assert!(!relay.is_backing_off(manager.now()));
+    }
+}
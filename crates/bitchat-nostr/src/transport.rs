@@ -1,6 +1,8 @@
 //! Nostr transport task implementation for BitChat hybrid architecture
 
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use tracing::{debug, error, info, warn};
 
 cfg_if::cfg_if! {
@@ -37,14 +39,17 @@ cfg_if::cfg_if! {
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
+        use futures::future::join_all;
         use tokio::select;
-        use tokio::time::{interval, sleep, Duration};
+        use tokio::sync::mpsc;
+        use tokio::time::{interval, sleep, timeout, Duration, Instant};
     } else if #[cfg(feature = "wasm")] {
         use wasm_bindgen_futures::spawn_local;
     }
 }
 
-use bitchat_core::internal::TransportError;
+use bitchat_core::channel::communication::{RelayHealthStatus, RelayStatus};
+use bitchat_core::internal::{SystemTimeSource, TransportError};
 use bitchat_core::protocol::{BitchatPacket, WireFormat};
 use bitchat_core::{
     BitchatError, EffectReceiver, EventSender, PeerId, Result as BitchatResult, TransportTask,
@@ -57,9 +62,95 @@ use bitchat_harness::{
 use super::config::NostrConfig;
 use super::error::NostrTransportError;
 use super::message::{BitchatNostrMessage, BITCHAT_KIND};
-use super::nip17::{Nip17GiftUnwrapper, Nip17GiftWrapper};
+use super::nip17::{Nip17GiftUnwrapper, Nip17GiftWrapper, GIFT_WRAP_TIMESTAMP_JITTER_SECONDS};
 use super::embedding::{EmbeddingStrategy, EmbeddingConfig, NostrEmbeddedBitChat};
-// use super::relay_manager::{NostrRelayManager, RelaySelectionStrategy};
+use super::nip11::fetch_information_document;
+use super::nip42::{build_auth_event, is_auth_required};
+use super::relay_manager::{
+    NostrRelayManager, PublishRequirements, RelayAuthState, RelayHealth, RelayInfo,
+    RelaySelectionStrategy,
+};
+
+/// Events rejected with `auth-required:` kept per relay until authentication succeeds
+const MAX_PENDING_AUTH_PUBLISHES: usize = 64;
+
+/// Relay URLs as used for relay manager and auth bookkeeping
+///
+/// The relay pool reports URLs with a trailing slash that configured URLs usually lack.
+fn relay_key(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+/// NIP-42 signals queued between the notification listener and the transport loop
+const AUTH_SIGNAL_CAPACITY: usize = 64;
+
+/// NIP-42 traffic forwarded from the notification listener to the transport loop
+#[derive(Debug)]
+enum RelayAuthSignal {
+    /// Relay sent an `AUTH` challenge
+    Challenge {
+        relay_url: String,
+        challenge: String,
+    },
+    /// Relay answered an event with `OK`
+    Ok {
+        relay_url: String,
+        event_id: EventId,
+        accepted: bool,
+        message: String,
+    },
+}
+
+/// Pass a relay's NIP-42 traffic on to the transport loop
+///
+/// Only `OK`s for our own `AUTH` events are forwarded, so the answers to ordinary
+/// publishes never crowd a challenge out of the channel.
+#[cfg(feature = "std")]
+async fn forward_auth_signal(
+    auth_sender: &mpsc::Sender<RelayAuthSignal>,
+    pending_auth_events: &RwLock<HashMap<String, EventId>>,
+    relay_url: String,
+    message: RelayMessage,
+) -> Result<(), mpsc::error::SendError<RelayAuthSignal>> {
+    let signal = match message {
+        RelayMessage::Auth { challenge } => RelayAuthSignal::Challenge {
+            relay_url,
+            challenge,
+        },
+        RelayMessage::Ok {
+            event_id,
+            status,
+            message,
+        } => {
+            let is_auth_event = pending_auth_events
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&relay_url)
+                == Some(&event_id);
+            if !is_auth_event {
+                return Ok(());
+            }
+            RelayAuthSignal::Ok {
+                relay_url,
+                event_id,
+                accepted: status,
+                message,
+            }
+        }
+        RelayMessage::Closed {
+            subscription_id,
+            message,
+        } if is_auth_required(&message) => {
+            debug!(
+                "Relay {} closed subscription {} until we authenticate",
+                relay_url, subscription_id
+            );
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    auth_sender.send(signal).await
+}
 
 async fn forward_event(sender: &EventSender, event: Event) -> Result<(), String> {
     cfg_if::cfg_if! {
@@ -98,8 +189,23 @@ pub struct NostrTransportTask {
     gift_wrapper: Option<Nip17GiftWrapper>,
     /// NIP-17 gift unwrapper for decrypting received messages
     gift_unwrapper: Option<Nip17GiftUnwrapper>,
-    /// Canonical relay manager for health monitoring and selection (TODO: Add back with proper threading)
-    // relay_manager: Option<NostrRelayManager<bitchat_core::types::SystemTimeSource>>,
+    /// Canonical relay manager for health monitoring and selection
+    relay_manager: NostrRelayManager<SystemTimeSource>,
+    /// Nostr public keys of peers, as bound by Core Logic, shared with the listener
+    /// so gift wraps are only accepted when sealed by the claimed peer's key
+    peer_pubkeys: Arc<RwLock<HashMap<PeerId, PublicKey>>>,
+    /// Per-relay keys for answering NIP-42 challenges
+    auth_keys: HashMap<String, Keys>,
+    /// Our unanswered `AUTH` event per relay, shared with the listener so it only
+    /// forwards the relays' answers to those
+    pending_auth_events: Arc<RwLock<HashMap<String, EventId>>>,
+    /// Events to republish once the relay has authenticated us
+    pending_auth_publishes: HashMap<String, Vec<NostrEvent>>,
+    /// Active subscription, re-sent once a relay has authenticated us
+    subscription: Option<(SubscriptionId, Vec<Filter>)>,
+    /// Auth signals from the notification listener
+    #[cfg(feature = "std")]
+    auth_signals: Option<mpsc::Receiver<RelayAuthSignal>>,
     /// Embedding strategy for BitChat messages
     embedding_strategy: EmbeddingStrategy,
     /// Embedding configuration for privacy features
@@ -110,15 +216,25 @@ impl NostrTransportTask {
     /// Create new Nostr transport task
     pub fn new(config: NostrConfig) -> BitchatResult<Self> {
         let keys = config.private_key.clone().unwrap_or_else(Keys::generate);
-        
-        // TODO: Add relay manager back with proper threading support
-        // #[cfg(not(target_arch = "wasm32"))]
-        // let time_source = bitchat_core::types::SystemTimeSource;
-        // #[cfg(target_arch = "wasm32")]
-        // let time_source = bitchat_core::types::SystemTimeSource; // WASM also uses SystemTimeSource
-        // 
-        // let mut relay_manager = NostrRelayManager::new(time_source);
-        // relay_manager.load_default_relays();
+
+        // Publishing goes to the configured writable relays only, ranked by observed
+        // health; read-only relays are tracked too so their auth state is kept
+        let mut relay_manager = NostrRelayManager::new(SystemTimeSource);
+        relay_manager.set_selection_strategy(RelaySelectionStrategy::HealthBased);
+        for relay_config in &config.relays {
+            relay_manager.add_relay(RelayInfo {
+                read_only: relay_config.read_only,
+                ..RelayInfo::new(relay_key(&relay_config.url))
+            });
+        }
+        let auth_keys = config
+            .relays
+            .iter()
+            .filter_map(|relay| {
+                let keys = relay.auth_key.clone()?;
+                Some((relay_key(&relay.url), keys))
+            })
+            .collect();
 
         Ok(Self {
             transport_type: ChannelTransportType::Nostr,
@@ -129,7 +245,14 @@ impl NostrTransportTask {
             client: None,
             gift_wrapper: None,
             gift_unwrapper: None,
-            // relay_manager: Some(relay_manager),
+            relay_manager,
+            peer_pubkeys: Arc::new(RwLock::new(HashMap::new())),
+            auth_keys,
+            pending_auth_events: Arc::new(RwLock::new(HashMap::new())),
+            pending_auth_publishes: HashMap::new(),
+            subscription: None,
+            #[cfg(feature = "std")]
+            auth_signals: None,
             embedding_strategy: EmbeddingStrategy::default(),
             embedding_config: EmbeddingConfig::default(),
         })
@@ -159,6 +282,7 @@ impl NostrTransportTask {
 
         // Start listening for Nostr events
         self.start_listening().await?;
+        self.report_local_identity().await;
 
         let channels = self.channels.as_mut().ok_or_else(|| {
             BitchatError::Transport(TransportError::InvalidConfiguration {
@@ -170,6 +294,12 @@ impl NostrTransportTask {
                 reason: "Nostr transport already running".to_string(),
             })
         })?;
+        #[cfg(feature = "std")]
+        let mut auth_signals = self.auth_signals.take().ok_or_else(|| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: "Nostr transport listener not started".to_string(),
+            })
+        })?;
 
         cfg_if::cfg_if! {
             if #[cfg(feature = "std")] {
@@ -194,11 +324,17 @@ impl NostrTransportTask {
                             }
                         }
 
+                        // NIP-42 challenges and the relays' answers to our AUTH events
+                        Some(signal) = auth_signals.recv() => {
+                            self.handle_auth_signal(signal).await;
+                        }
+
                         // Periodic reconnection check
                         _ = reconnect_timer.tick() => {
                             if self.config.auto_reconnect {
                                 self.check_and_reconnect().await;
                             }
+                            self.report_relay_status().await;
                         }
                     }
                 }
@@ -276,6 +412,8 @@ impl NostrTransportTask {
             // but for simplicity, we'll proceed without the delay
         }
 
+        self.discover_relay_capabilities().await;
+
         // Initialize NIP-17 wrappers
         self.gift_wrapper = Some(Nip17GiftWrapper::new(self.keys.clone()));
         self.gift_unwrapper = Some(Nip17GiftUnwrapper::new(self.keys.clone()));
@@ -289,8 +427,39 @@ impl NostrTransportTask {
         Ok(())
     }
 
+    /// Fetch every managed relay's NIP-11 document and record its capabilities
+    ///
+    /// Relays that do not serve a document keep default capabilities and are
+    /// not excluded from routing.
+    #[cfg(feature = "std")]
+    async fn discover_relay_capabilities(&mut self) {
+        let mut fetches = tokio::task::JoinSet::new();
+        for url in self.relay_manager.relays().map(|relay| relay.url.clone()) {
+            let timeout = self.config.connection_timeout;
+            fetches.spawn(async move {
+                let document = fetch_information_document(&url, timeout).await;
+                (url, document)
+            });
+        }
+
+        while let Some(result) = fetches.join_next().await {
+            match result {
+                Ok((url, Ok(document))) => {
+                    debug!("Relay {} supports NIPs {:?}", url, document.supported_nips);
+                    self.relay_manager
+                        .apply_information_document(&url, &document);
+                }
+                Ok((url, Err(e))) => debug!("No NIP-11 document for relay {}: {}", url, e),
+                Err(e) => warn!("NIP-11 fetch task failed: {}", e),
+            }
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    async fn discover_relay_capabilities(&mut self) {}
+
     /// Start listening for BitChat messages on Nostr
-    async fn start_listening(&self) -> BitchatResult<()> {
+    async fn start_listening(&mut self) -> BitchatResult<()> {
         let client = self
             .client
             .as_ref()
@@ -315,20 +484,29 @@ impl NostrTransportTask {
                 .kind(Kind::EncryptedDirectMessage)
                 .pubkey(self.keys.public_key())
                 .since(Timestamp::now()),
-            // Gift-wrapped events (NIP-17) - we subscribe to all and try to decrypt
-            Filter::new().kind(Kind::GiftWrap).since(Timestamp::now()),
+            // Gift-wrapped events (NIP-17) addressed to us, backdated by up to the jitter window
+            Filter::new()
+                .kind(Kind::GiftWrap)
+                .pubkey(self.keys.public_key())
+                .since(Timestamp::now() - GIFT_WRAP_TIMESTAMP_JITTER_SECONDS as u64),
         ];
 
-        client.subscribe(subscription_filters, None).await;
+        // Listen before subscribing so no stored events are missed
+        let mut notifications = client.notifications();
+        let subscription_id = client.subscribe(subscription_filters.clone(), None).await;
+        self.subscription = Some((subscription_id, subscription_filters));
 
         // Handle incoming events
-        let mut notifications = client.notifications();
         let local_peer_id = self.local_peer_id;
         let local_pubkey = self.keys.public_key();
 
         #[cfg(feature = "std")]
         {
             let gift_unwrapper = self.gift_unwrapper.clone();
+            let peer_pubkeys = Arc::clone(&self.peer_pubkeys);
+            let pending_auth_events = Arc::clone(&self.pending_auth_events);
+            let (auth_sender, auth_receiver) = mpsc::channel(AUTH_SIGNAL_CAPACITY);
+            self.auth_signals = Some(auth_receiver);
             tokio::spawn(async move {
                 while let Ok(notification) = notifications.recv().await {
                     match notification {
@@ -344,12 +522,27 @@ impl NostrTransportTask {
                                 &event_sender,
                                 local_peer_id,
                                 gift_unwrapper.as_ref(),
+                                &peer_pubkeys,
                             )
                             .await
                             {
                                 debug!("Failed to process Nostr event: {}", e);
                             }
                         }
+                        RelayPoolNotification::Message { relay_url, message } => {
+                            if forward_auth_signal(
+                                &auth_sender,
+                                &pending_auth_events,
+                                relay_key(relay_url.as_str()),
+                                message,
+                            )
+                            .await
+                            .is_err()
+                            {
+                                debug!("Transport loop stopped, no longer forwarding auth signals");
+                                break;
+                            }
+                        }
                         RelayPoolNotification::Shutdown => {
                             info!("Nostr relay pool shutdown");
                             break;
@@ -364,6 +557,7 @@ impl NostrTransportTask {
         {
             // WASM-compatible version using wasm-bindgen-futures::spawn_local
             let gift_unwrapper = self.gift_unwrapper.clone();
+            let peer_pubkeys = Arc::clone(&self.peer_pubkeys);
             spawn_local(async move {
                 while let Ok(notification) = notifications.recv().await {
                     match notification {
@@ -379,6 +573,7 @@ impl NostrTransportTask {
                                 &event_sender,
                                 local_peer_id,
                                 gift_unwrapper.as_ref(),
+                                &peer_pubkeys,
                             )
                             .await
                             {
@@ -405,6 +600,7 @@ impl NostrTransportTask {
         event_sender: &EventSender,
         local_peer_id: Option<PeerId>,
         gift_unwrapper: Option<&Nip17GiftUnwrapper>,
+        peer_pubkeys: &RwLock<HashMap<PeerId, PublicKey>>,
     ) -> Result<(), NostrTransportError> {
         // Only process relevant event kinds
         if event.kind != BITCHAT_KIND
@@ -417,47 +613,51 @@ impl NostrTransportTask {
         // Handle NIP-17 gift-wrapped events
         if event.kind == Kind::GiftWrap {
             if let Some(unwrapper) = gift_unwrapper {
-                match unwrapper.unwrap_gift_wrapped_message(event) {
-                    Ok(Some(content)) => {
-                        // Process the unwrapped content
-                        if let Ok(Some(packet)) = content.to_bitchat_packet() {
-                            // Check if message is for us
-                            let is_for_us = match local_peer_id {
-                                Some(our_peer_id) => {
-                                    packet.is_broadcast()
-                                        || packet.recipient_id == Some(our_peer_id)
-                                }
-                                None => packet.is_broadcast(),
-                            };
-
-                            if !is_for_us {
-                                return Ok(());
+                // The seal must be signed by the Nostr key bound to the claimed sender
+                let bound_key = |peer_id: &PeerId| {
+                    peer_pubkeys
+                        .read()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .get(peer_id)
+                        .copied()
+                };
+                match unwrapper.unwrap_bitchat_packet(event, bound_key) {
+                    Ok(Some(packet)) => {
+                        // Check if message is for us
+                        let is_for_us = match local_peer_id {
+                            Some(our_peer_id) => {
+                                packet.is_broadcast() || packet.recipient_id == Some(our_peer_id)
                             }
+                            None => packet.is_broadcast(),
+                        };
 
-                            // Send peer discovery event
-                            let discovery_event = bitchat_core::Event::PeerDiscovered {
-                                peer_id: packet.sender_id,
-                                transport: ChannelTransportType::Nostr,
-                                signal_strength: None,
-                            };
+                        if !is_for_us {
+                            return Ok(());
+                        }
 
-                            if let Err(e) = forward_event(event_sender, discovery_event).await {
-                                warn!("Failed to send peer discovery event: {}", e);
-                            }
+                        // Send peer discovery event
+                        let discovery_event = bitchat_core::Event::PeerDiscovered {
+                            peer_id: packet.sender_id,
+                            transport: ChannelTransportType::Nostr,
+                            signal_strength: None,
+                        };
 
-                            // Send packet event
-                            let packet_event = bitchat_core::Event::BitchatPacketReceived {
-                                from: packet.sender_id,
-                                packet,
-                                transport: ChannelTransportType::Nostr,
-                            };
+                        if let Err(e) = forward_event(event_sender, discovery_event).await {
+                            warn!("Failed to send peer discovery event: {}", e);
+                        }
 
-                            if let Err(e) = forward_event(event_sender, packet_event).await {
-                                warn!("Failed to send packet event: {}", e);
-                            }
+                        // Send packet event
+                        let packet_event = bitchat_core::Event::BitchatPacketReceived {
+                            from: packet.sender_id,
+                            packet,
+                            transport: ChannelTransportType::Nostr,
+                        };
 
-                            return Ok(());
+                        if let Err(e) = forward_event(event_sender, packet_event).await {
+                            warn!("Failed to send packet event: {}", e);
                         }
+
+                        return Ok(());
                     }
                     Ok(None) => {
                         // Not a message for us, ignore
@@ -574,7 +774,7 @@ impl NostrTransportTask {
     }
 
     /// Handle effects from Core Logic
-    async fn handle_effect(&self, effect: Effect) -> BitchatResult<()> {
+    async fn handle_effect(&mut self, effect: Effect) -> BitchatResult<()> {
         match effect {
             Effect::SendPacket {
                 peer_id,
@@ -614,6 +814,19 @@ impl NostrTransportTask {
                     self.initiate_connection(peer_id).await?;
                 }
             }
+            Effect::BindNostrIdentity {
+                peer_id,
+                public_key,
+            } => match PublicKey::from_slice(&public_key) {
+                Ok(public_key) => {
+                    debug!("Peer {} is reachable at {}", peer_id, public_key);
+                    self.peer_pubkeys
+                        .write()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(peer_id, public_key);
+                }
+                Err(e) => warn!("Peer {} advertised an invalid Nostr key: {}", peer_id, e),
+            },
             _ => {
                 // Ignore effects not relevant to Nostr transport
             }
@@ -622,12 +835,7 @@ impl NostrTransportTask {
     }
 
     /// Send data to a specific peer via Nostr
-    async fn send_data_to_peer(&self, peer_id: PeerId, data: Vec<u8>) -> BitchatResult<()> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| NostrTransportError::ClientNotInitialized)?;
-
+    async fn send_data_to_peer(&mut self, peer_id: PeerId, data: Vec<u8>) -> BitchatResult<()> {
         let local_peer_id = self
             .local_peer_id
             .ok_or_else(|| NostrTransportError::ClientNotInitialized)?;
@@ -652,10 +860,7 @@ impl NostrTransportTask {
             .to_event(&self.keys)
             .map_err(|e| NostrTransportError::DeserializationFailed(e.to_string()))?;
 
-        client
-            .send_event(event)
-            .await
-            .map_err(NostrTransportError::EventSendFailed)?;
+        self.publish_event(event).await?;
 
         debug!("Sent data to peer {} via Nostr", peer_id);
         Ok(())
@@ -663,7 +868,7 @@ impl NostrTransportTask {
 
     /// Send BitChat packet to specific peer via Nostr using canonical embedding
     async fn send_bitchat_packet_to_peer(
-        &self,
+        &mut self,
         peer_id: PeerId,
         packet: BitchatPacket,
     ) -> BitchatResult<()> {
@@ -681,11 +886,6 @@ impl NostrTransportTask {
                 // For now, we'll skip jitter in WASM environments
             }
         }
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| NostrTransportError::ClientNotInitialized)?;
-
         let local_peer_id = self
             .local_peer_id
             .ok_or_else(|| NostrTransportError::ClientNotInitialized)?;
@@ -722,18 +922,20 @@ impl NostrTransportTask {
                         expiration: None,
                     };
 
-                    // Convert peer_id to Nostr public key
-                    let recipient_pubkey = super::nip17::peer_id_to_pubkey(&peer_id)
-                        .map_err(|e| NostrTransportError::KeyOperationFailed(e.to_string()))?;
+                    // Peers are reachable once Core Logic has bound their advertised key
+                    let recipient_pubkey = self
+                        .peer_pubkeys
+                        .read()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .get(&peer_id)
+                        .copied()
+                        .ok_or(NostrTransportError::NoNostrIdentity { peer_id })?;
 
                     let mut wrapper = gift_wrapper.clone();
                     let gift_wrapped_event = wrapper.create_gift_wrapped_message(&nip17_content, &recipient_pubkey)
                         .map_err(|e| NostrTransportError::EncryptionFailed(e.to_string()))?;
 
-                    client
-                        .send_event(gift_wrapped_event)
-                        .await
-                        .map_err(NostrTransportError::EventSendFailed)?;
+                    self.publish_event(gift_wrapped_event).await?;
                 } else {
                     return Err(NostrTransportError::EncryptionFailed(
                         "NIP-17 gift wrapper not available".to_string(),
@@ -746,10 +948,7 @@ impl NostrTransportTask {
                     .to_event(&self.keys)
                     .map_err(|e| NostrTransportError::DeserializationFailed(e.to_string()))?;
 
-                client
-                    .send_event(event)
-                    .await
-                    .map_err(NostrTransportError::EventSendFailed)?;
+                self.publish_event(event).await?;
             }
             EmbeddingStrategy::CustomKind(kind) => {
                 // Use custom event kind
@@ -758,10 +957,7 @@ impl NostrTransportTask {
                     .to_event(&self.keys)
                     .map_err(|e| NostrTransportError::DeserializationFailed(e.to_string()))?;
 
-                client
-                    .send_event(event)
-                    .await
-                    .map_err(NostrTransportError::EventSendFailed)?;
+                self.publish_event(event).await?;
             }
         }
 
@@ -770,12 +966,7 @@ impl NostrTransportTask {
     }
 
     /// Broadcast BitChat packet via Nostr using canonical embedding
-    async fn broadcast_bitchat_packet(&self, packet: BitchatPacket) -> BitchatResult<()> {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| NostrTransportError::ClientNotInitialized)?;
-
+    async fn broadcast_bitchat_packet(&mut self, packet: BitchatPacket) -> BitchatResult<()> {
         // Serialize the packet to binary wire format
         let data = WireFormat::encode(&packet).map_err(|e| {
             BitchatError::Transport(TransportError::InvalidConfiguration {
@@ -810,16 +1001,291 @@ impl NostrTransportTask {
             .to_event(&self.keys)
             .map_err(|e| NostrTransportError::DeserializationFailed(e.to_string()))?;
 
-        // Send to all connected relays 
-        client
-            .send_event(event)
-            .await
-            .map_err(NostrTransportError::EventSendFailed)?;
+        self.publish_event(event).await?;
 
         debug!("Broadcast BitChat packet via Nostr");
         Ok(())
     }
 
+    /// Publish an event to the relays picked by the relay manager
+    ///
+    /// Sends to all of them at once and waits for each relay's NIP-01 `OK`, so one
+    /// slow relay costs at most one message timeout. Acceptance and round-trip
+    /// latency are fed back into the manager, which backs off relays that keep
+    /// failing.
+    async fn publish_event(&mut self, event: NostrEvent) -> BitchatResult<()> {
+        if self.client.is_none() {
+            return Err(NostrTransportError::ClientNotInitialized.into());
+        }
+
+        let requirements = PublishRequirements {
+            message_size: ClientMessage::event(event.clone()).as_json().len(),
+            content_length: event.content.chars().count(),
+            gift_wrapped: event.kind == Kind::GiftWrap,
+        };
+        let selected = self.relay_manager.select_relays_for(
+            None,
+            self.config.max_publish_relays,
+            &requirements,
+        );
+        if selected.is_empty() {
+            return Err(NostrTransportError::ConfigurationError(format!(
+                "No relay can carry event ({} bytes, gift wrapped: {})",
+                requirements.message_size, requirements.gift_wrapped
+            ))
+            .into());
+        }
+
+        let results = join_all(
+            selected
+                .iter()
+                .map(|url| self.send_to_relay(url, event.clone())),
+        )
+        .await;
+
+        let mut accepted = 0;
+        for (url, result) in selected.iter().zip(results) {
+            match result {
+                Ok(latency_ms) => {
+                    debug!("Relay {} accepted event in {}ms", url, latency_ms);
+                    self.relay_manager
+                        .record_publish_result(url, true, Some(latency_ms));
+                    accepted += 1;
+                }
+                Err(reason) => {
+                    warn!("Relay {} did not accept event: {}", url, reason);
+                    self.relay_manager.record_publish_result(url, false, None);
+                    if is_auth_required(&reason) {
+                        self.queue_until_authenticated(url, &event);
+                    }
+                }
+            }
+        }
+
+        if accepted == 0 {
+            return Err(NostrTransportError::ConnectionFailed(format!(
+                "None of {} selected relays accepted the event",
+                selected.len()
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Send an event to one relay and wait for its `OK`, returning the round-trip latency
+    async fn send_to_relay(&self, url: &str, event: NostrEvent) -> Result<u64, String> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| NostrTransportError::ClientNotInitialized.to_string())?;
+        let relay = client.relay(url).await.map_err(|e| e.to_string())?;
+
+        let started = Instant::now();
+        match timeout(
+            self.config.message_timeout,
+            relay.send_event(event, RelaySendOptions::default()),
+        )
+        .await
+        {
+            Ok(Ok(_)) => Ok(started.elapsed().as_millis() as u64),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("timed out waiting for OK".to_string()),
+        }
+    }
+
+    /// Keep an event a relay refused until we have authenticated to it
+    fn queue_until_authenticated(&mut self, url: &str, event: &NostrEvent) {
+        let queue = self
+            .pending_auth_publishes
+            .entry(url.to_string())
+            .or_default();
+        if queue.iter().any(|queued| queued.id == event.id) {
+            return;
+        }
+        if queue.len() >= MAX_PENDING_AUTH_PUBLISHES {
+            queue.remove(0);
+        }
+        queue.push(event.clone());
+    }
+
+    /// React to NIP-42 traffic seen by the notification listener
+    async fn handle_auth_signal(&mut self, signal: RelayAuthSignal) {
+        match signal {
+            RelayAuthSignal::Challenge {
+                relay_url,
+                challenge,
+            } => {
+                if let Err(e) = self.answer_auth_challenge(&relay_url, &challenge).await {
+                    warn!("Failed to authenticate to relay {}: {}", relay_url, e);
+                    self.relay_manager
+                        .set_auth_state(&relay_url, RelayAuthState::Failed);
+                }
+            }
+            RelayAuthSignal::Ok {
+                relay_url,
+                event_id,
+                accepted,
+                message,
+            } => {
+                {
+                    let mut pending_auth_events = self
+                        .pending_auth_events
+                        .write()
+                        .unwrap_or_else(|e| e.into_inner());
+                    if pending_auth_events.get(&relay_url) != Some(&event_id) {
+                        return;
+                    }
+                    pending_auth_events.remove(&relay_url);
+                }
+
+                if accepted {
+                    info!("Authenticated to relay {}", relay_url);
+                    self.relay_manager
+                        .set_auth_state(&relay_url, RelayAuthState::Authenticated);
+                    self.resume_after_auth(&relay_url).await;
+                } else {
+                    warn!("Relay {} rejected authentication: {}", relay_url, message);
+                    self.relay_manager
+                        .set_auth_state(&relay_url, RelayAuthState::Failed);
+                }
+            }
+        }
+    }
+
+    /// Sign and send the `AUTH` event for a relay's challenge
+    async fn answer_auth_challenge(
+        &mut self,
+        relay_url: &str,
+        challenge: &str,
+    ) -> Result<(), NostrTransportError> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or(NostrTransportError::ClientNotInitialized)?;
+        let keys = self.auth_keys.get(relay_url).unwrap_or(&self.keys);
+        let auth_event = build_auth_event(keys, challenge, relay_url)?;
+        let relay = client.relay(relay_url).await?;
+
+        // Registered before sending so the listener already expects the relay's `OK`
+        self.pending_auth_events
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(relay_url.to_string(), auth_event.id);
+        relay
+            .send_msg(ClientMessage::auth(auth_event), RelaySendOptions::default())
+            .await
+            .map_err(|e| NostrTransportError::ConnectionFailed(e.to_string()))?;
+
+        debug!("Answered AUTH challenge from relay {}", relay_url);
+        self.relay_manager
+            .set_auth_state(relay_url, RelayAuthState::Pending);
+        Ok(())
+    }
+
+    /// Re-send what the relay refused before we authenticated
+    async fn resume_after_auth(&mut self, relay_url: &str) {
+        if let (Some(client), Some((subscription_id, filters))) = (&self.client, &self.subscription)
+        {
+            // Re-sending a REQ with the same ID replaces a subscription the relay closed
+            match client.relay(relay_url).await {
+                Ok(relay) => {
+                    if let Err(e) = relay
+                        .subscribe_with_id(
+                            subscription_id.clone(),
+                            filters.clone(),
+                            SubscribeOptions::default(),
+                        )
+                        .await
+                    {
+                        warn!("Failed to resubscribe on relay {}: {}", relay_url, e);
+                    }
+                }
+                Err(e) => warn!(
+                    "Relay {} disappeared after authentication: {}",
+                    relay_url, e
+                ),
+            }
+        }
+
+        let pending = self
+            .pending_auth_publishes
+            .remove(relay_url)
+            .unwrap_or_default();
+        if !pending.is_empty() {
+            info!(
+                "Republishing {} events to relay {} after authentication",
+                pending.len(),
+                relay_url
+            );
+        }
+        for event in pending {
+            match self.send_to_relay(relay_url, event).await {
+                Ok(latency_ms) => {
+                    self.relay_manager
+                        .record_publish_result(relay_url, true, Some(latency_ms));
+                }
+                Err(reason) => {
+                    warn!("Relay {} still refused event: {}", relay_url, reason);
+                    self.relay_manager
+                        .record_publish_result(relay_url, false, None);
+                }
+            }
+        }
+    }
+
+    /// Tell Core Logic which Nostr public key peers should address us at
+    async fn report_local_identity(&self) {
+        let Some(channels) = self.channels.as_ref() else {
+            return;
+        };
+        let event = Event::LocalNostrIdentity {
+            public_key: self.keys.public_key().to_bytes(),
+        };
+        if let Err(e) = forward_event(&channels.event_sender(), event).await {
+            warn!("Failed to report local Nostr identity: {}", e);
+        }
+    }
+
+    /// Send the current per-relay statistics to Core Logic
+    async fn report_relay_status(&self) {
+        let Some(channels) = self.channels.as_ref() else {
+            return;
+        };
+
+        let now = self.relay_manager.now();
+        let mut relays: Vec<RelayStatus> = self
+            .relay_manager
+            .relays()
+            .map(|relay| RelayStatus {
+                url: relay.url.clone(),
+                health: match relay.health {
+                    RelayHealth::Healthy => RelayHealthStatus::Healthy,
+                    RelayHealth::Degraded => RelayHealthStatus::Degraded,
+                    RelayHealth::Unhealthy => RelayHealthStatus::Unhealthy,
+                    RelayHealth::Unknown => RelayHealthStatus::Unknown,
+                },
+                score: relay.health_score(),
+                events_accepted: relay.stats.events_sent,
+                events_failed: relay.stats.events_failed,
+                average_latency_ms: relay.stats.average_latency_ms,
+                consecutive_failures: relay.consecutive_failures,
+                backoff_remaining_ms: relay
+                    .retry_after
+                    .filter(|_| relay.is_backing_off(now))
+                    .map(|retry_after| retry_after - now),
+            })
+            .collect();
+        relays.sort_by(|a, b| a.url.cmp(&b.url));
+
+        let event = Event::RelayStatusUpdated {
+            relays,
+            timestamp: now.as_millis(),
+        };
+        if let Err(e) = forward_event(&channels.event_sender(), event).await {
+            debug!("Failed to send relay status: {}", e);
+        }
+    }
+
     /// Start discovery (already handled by subscriptions)
     async fn start_discovery(&self) -> BitchatResult<()> {
         info!("Nostr discovery is always active via subscriptions");
@@ -916,3 +1382,68 @@ impl TransportTask for NostrTransportTask {
         self.transport_type
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const RELAY: &str = "wss://relay.example.com";
+
+    fn note_id(keys: &Keys, i: usize) -> EventId {
+        EventBuilder::text_note(format!("note {}", i), [])
+            .to_event(keys)
+            .unwrap()
+            .id
+    }
+
+    #[tokio::test]
+    async fn test_challenge_is_forwarded_after_many_publishes() {
+        let keys = Keys::generate();
+        let pending_auth_events = RwLock::new(HashMap::new());
+        let (auth_sender, mut auth_receiver) = mpsc::channel(AUTH_SIGNAL_CAPACITY);
+
+        // Nothing drains the channel while the relay acknowledges our publishes
+        for i in 0..AUTH_SIGNAL_CAPACITY * 2 {
+            let ok = RelayMessage::ok(note_id(&keys, i), true, "");
+            timeout(
+                Duration::from_secs(1),
+                forward_auth_signal(&auth_sender, &pending_auth_events, RELAY.to_string(), ok),
+            )
+            .await
+            .expect("publish acknowledgements should not fill the channel")
+            .unwrap();
+        }
+
+        let challenge = RelayMessage::Auth {
+            challenge: "challenge-123".to_string(),
+        };
+        forward_auth_signal(
+            &auth_sender,
+            &pending_auth_events,
+            RELAY.to_string(),
+            challenge,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            auth_receiver.try_recv(),
+            Ok(RelayAuthSignal::Challenge { challenge, .. }) if challenge == "challenge-123"
+        ));
+
+        // The relay's answer to our AUTH event still reaches the transport loop
+        let auth_event_id = build_auth_event(&keys, "challenge-123", RELAY).unwrap().id;
+        pending_auth_events
+            .write()
+            .unwrap()
+            .insert(RELAY.to_string(), auth_event_id);
+        let ok = RelayMessage::ok(auth_event_id, true, "");
+        forward_auth_signal(&auth_sender, &pending_auth_events, RELAY.to_string(), ok)
+            .await
+            .unwrap();
+        assert!(matches!(
+            auth_receiver.try_recv(),
+            Ok(RelayAuthSignal::Ok { event_id, accepted: true, .. }) if event_id == auth_event_id
+        ));
+        assert!(auth_receiver.try_recv().is_err());
+    }
+}
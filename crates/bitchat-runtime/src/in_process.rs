@@ -0,0 +1,671 @@
+//! In-Process Transport
+//!
+//! Connects any number of `BitchatRuntime`s living in the same process through a
+//! shared [`InProcessSwitchboard`]. The switchboard decides which nodes can hear each
+//! other according to a [`Topology`], and each node runs an [`InProcessTransport`]
+//! that behaves like a mesh radio: it reports links as they come and go, delivers
+//! packets addressed to its node and relays everything else with a decremented TTL.
+//!
+//! Packets travel in the BitChat wire format, so handshakes, encryption and
+//! forwarding go through exactly the same code paths as on a real transport.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use bitchat_core::internal::TransportError;
+use bitchat_core::protocol::{BitchatPacket, DeduplicationManager, PacketId, WireFormat};
+use bitchat_core::{
+    BitchatError, BitchatResult, ChannelTransportType, Effect, EffectReceiver, Event, EventSender,
+    PeerId, TransportTask,
+};
+use bitchat_harness::TransportHandle;
+use tokio::sync::mpsc;
+
+// ----------------------------------------------------------------------------
+// Topology
+// ----------------------------------------------------------------------------
+
+/// Which nodes on a switchboard are linked to each other
+///
+/// Links are derived from the order in which nodes were registered, so register
+/// every node before starting the runtimes when using an index based topology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Topology {
+    /// Every node hears every other node
+    #[default]
+    FullMesh,
+    /// Node `i` hears nodes `i - 1` and `i + 1`
+    Line,
+    /// A line whose ends are also linked
+    Ring,
+    /// The first node hears everyone, everyone else only hears the first node
+    Star,
+    /// Nodes laid out row by row with the given number of columns, linked to the
+    /// nodes above, below, left and right of them
+    Grid { columns: usize },
+    /// No links except those added with [`InProcessSwitchboard::link`]
+    Manual,
+}
+
+impl Topology {
+    /// Whether the nodes at indices `a` and `b` are linked in a switchboard of `count` nodes
+    fn links(&self, a: usize, b: usize, count: usize) -> bool {
+        if a == b {
+            return false;
+        }
+        let (low, high) = (a.min(b), a.max(b));
+        match *self {
+            Topology::FullMesh => true,
+            Topology::Line => high - low == 1,
+            Topology::Ring => high - low == 1 || (count > 2 && low == 0 && high == count - 1),
+            Topology::Star => low == 0,
+            Topology::Grid { columns } => {
+                let columns = columns.max(1);
+                let same_row = low / columns == high / columns;
+                (same_row && high - low == 1) || high - low == columns
+            }
+            Topology::Manual => false,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Switchboard
+// ----------------------------------------------------------------------------
+
+/// Frames exchanged between the switchboard and its transports
+#[derive(Debug)]
+enum Frame {
+    /// A link to the peer came up
+    LinkUp(PeerId),
+    /// The link to the peer went down
+    LinkDown(PeerId),
+    /// Wire-encoded packet from a linked peer
+    Packet { from: PeerId, data: Vec<u8> },
+}
+
+#[derive(Debug)]
+struct Node {
+    peer_id: PeerId,
+    inbox: mpsc::UnboundedSender<Frame>,
+    online: bool,
+}
+
+#[derive(Debug, Default)]
+struct SwitchboardState {
+    topology: Topology,
+    nodes: Vec<Node>,
+    /// Links added or removed on top of the topology
+    overrides: HashMap<(PeerId, PeerId), bool>,
+}
+
+impl SwitchboardState {
+    fn index_of(&self, peer_id: &PeerId) -> Option<usize> {
+        self.nodes.iter().position(|node| node.peer_id == *peer_id)
+    }
+
+    fn linked(&self, a: usize, b: usize) -> bool {
+        let key = link_key(self.nodes[a].peer_id, self.nodes[b].peer_id);
+        match self.overrides.get(&key) {
+            Some(linked) => *linked && a != b,
+            None => self.topology.links(a, b, self.nodes.len()),
+        }
+    }
+
+    /// Online nodes linked to the node at `index`
+    fn neighbours(&self, index: usize) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&other| self.nodes[other].online && self.linked(index, other))
+            .collect()
+    }
+
+    fn send(&self, index: usize, frame: Frame) {
+        // A closed inbox means the transport was dropped; it is offline either way
+        let _ = self.nodes[index].inbox.send(frame);
+    }
+}
+
+fn link_key(a: PeerId, b: PeerId) -> (PeerId, PeerId) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Shared medium connecting [`InProcessTransport`]s
+///
+/// Cloning the switchboard yields another handle to the same medium.
+#[derive(Debug, Clone, Default)]
+pub struct InProcessSwitchboard {
+    state: Arc<Mutex<SwitchboardState>>,
+}
+
+impl InProcessSwitchboard {
+    /// Create a switchboard with the given topology
+    pub fn new(topology: Topology) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SwitchboardState {
+                topology,
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SwitchboardState> {
+        // Every critical section leaves the state consistent, so a poisoned lock is
+        // still safe to use
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Register a node and create its transport with the default configuration
+    pub fn create_transport(&self, peer_id: PeerId) -> BitchatResult<InProcessTransport> {
+        self.create_transport_with_config(peer_id, InProcessTransportConfig::default())
+    }
+
+    /// Register a node and create its transport
+    pub fn create_transport_with_config(
+        &self,
+        peer_id: PeerId,
+        config: InProcessTransportConfig,
+    ) -> BitchatResult<InProcessTransport> {
+        let (inbox, frames) = mpsc::unbounded_channel();
+
+        let mut state = self.lock();
+        if state.index_of(&peer_id).is_some() {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: format!("Peer {} is already on the switchboard", peer_id),
+                },
+            ));
+        }
+        state.nodes.push(Node {
+            peer_id,
+            inbox,
+            online: false,
+        });
+        drop(state);
+
+        Ok(InProcessTransport {
+            switchboard: self.clone(),
+            local_peer_id: peer_id,
+            config,
+            transport_channels: None,
+            frames: Some(frames),
+            neighbours: HashSet::new(),
+            deduplication: DeduplicationManager::for_ble_mesh(),
+            paused: false,
+        })
+    }
+
+    /// Number of registered nodes
+    pub fn node_count(&self) -> usize {
+        self.lock().nodes.len()
+    }
+
+    /// Peer IDs of all registered nodes, in registration order
+    pub fn peer_ids(&self) -> Vec<PeerId> {
+        self.lock().nodes.iter().map(|node| node.peer_id).collect()
+    }
+
+    /// Whether the node's transport task is running
+    pub fn is_online(&self, peer_id: &PeerId) -> bool {
+        let state = self.lock();
+        state
+            .index_of(peer_id)
+            .is_some_and(|index| state.nodes[index].online)
+    }
+
+    /// Peers currently linked to the given peer (online nodes only)
+    pub fn neighbours(&self, peer_id: &PeerId) -> Vec<PeerId> {
+        let state = self.lock();
+        match state.index_of(peer_id) {
+            Some(index) => state
+                .neighbours(index)
+                .into_iter()
+                .map(|other| state.nodes[other].peer_id)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Link two nodes regardless of topology
+    pub fn link(&self, a: PeerId, b: PeerId) -> BitchatResult<()> {
+        self.set_link(a, b, true)
+    }
+
+    /// Unlink two nodes regardless of topology
+    pub fn unlink(&self, a: PeerId, b: PeerId) -> BitchatResult<()> {
+        self.set_link(a, b, false)
+    }
+
+    fn set_link(&self, a: PeerId, b: PeerId, linked: bool) -> BitchatResult<()> {
+        let mut state = self.lock();
+        let (Some(index_a), Some(index_b)) = (state.index_of(&a), state.index_of(&b)) else {
+            return Err(BitchatError::Transport(TransportError::PeerNotFound {
+                peer_id: format!("{} or {}", a, b),
+            }));
+        };
+
+        let was_linked = state.linked(index_a, index_b);
+        state.overrides.insert(link_key(a, b), linked);
+        if was_linked == linked || !state.nodes[index_a].online || !state.nodes[index_b].online {
+            return Ok(());
+        }
+
+        let (to_a, to_b) = if linked {
+            (Frame::LinkUp(b), Frame::LinkUp(a))
+        } else {
+            (Frame::LinkDown(b), Frame::LinkDown(a))
+        };
+        state.send(index_a, to_a);
+        state.send(index_b, to_b);
+        Ok(())
+    }
+
+    /// Mark a node online or offline and notify its neighbours
+    fn set_online(&self, peer_id: PeerId, online: bool) {
+        let mut state = self.lock();
+        let Some(index) = state.index_of(&peer_id) else {
+            return;
+        };
+        if state.nodes[index].online == online {
+            return;
+        }
+
+        state.nodes[index].online = online;
+        for other in state.neighbours(index) {
+            if online {
+                state.send(index, Frame::LinkUp(state.nodes[other].peer_id));
+                state.send(other, Frame::LinkUp(peer_id));
+            } else {
+                state.send(other, Frame::LinkDown(peer_id));
+            }
+        }
+    }
+
+    /// Hand a wire packet to a linked neighbour
+    fn deliver(&self, from: PeerId, to: PeerId, data: Vec<u8>) -> BitchatResult<()> {
+        let state = self.lock();
+        let reachable = match (state.index_of(&from), state.index_of(&to)) {
+            (Some(a), Some(b)) => state.nodes[b].online && state.linked(a, b),
+            _ => false,
+        };
+        if !reachable {
+            return Err(BitchatError::Transport(TransportError::ConnectionFailed {
+                peer_id: to.to_string(),
+                reason: "Peer not linked".to_string(),
+            }));
+        }
+
+        if let Some(index) = state.index_of(&to) {
+            state.send(index, Frame::Packet { from, data });
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Transport
+// ----------------------------------------------------------------------------
+
+/// Configuration for an in-process transport
+#[derive(Debug, Clone)]
+pub struct InProcessTransportConfig {
+    /// Transport type reported to Core Logic; effects for other types are ignored
+    pub transport_type: ChannelTransportType,
+    /// Signal strength reported with discovered peers
+    pub signal_strength: Option<i8>,
+}
+
+impl Default for InProcessTransportConfig {
+    fn default() -> Self {
+        Self {
+            // Core Logic drives discovery and private messages over BLE, so posing
+            // as BLE lets the switchboard stand in for a radio without extra wiring
+            transport_type: ChannelTransportType::Ble,
+            signal_strength: Some(-50),
+        }
+    }
+}
+
+/// Transport task attached to an [`InProcessSwitchboard`]
+pub struct InProcessTransport {
+    switchboard: InProcessSwitchboard,
+    local_peer_id: PeerId,
+    config: InProcessTransportConfig,
+    /// Channels provided by the runtime harness
+    transport_channels: Option<TransportHandle>,
+    /// Frames from the switchboard
+    frames: Option<mpsc::UnboundedReceiver<Frame>>,
+    /// Currently linked peers
+    neighbours: HashSet<PeerId>,
+    /// Packets already seen, to stop flooded packets from looping
+    deduplication: DeduplicationManager,
+    /// Whether Core Logic paused this transport
+    paused: bool,
+}
+
+/// Takes the node off the switchboard when the transport task stops or is aborted
+struct OnlineGuard {
+    switchboard: InProcessSwitchboard,
+    peer_id: PeerId,
+}
+
+impl Drop for OnlineGuard {
+    fn drop(&mut self) {
+        self.switchboard.set_online(self.peer_id, false);
+    }
+}
+
+impl InProcessTransport {
+    /// Peer ID of the node this transport belongs to
+    pub fn local_peer_id(&self) -> PeerId {
+        self.local_peer_id
+    }
+
+    async fn send_event(&self, event: Event) -> BitchatResult<()> {
+        let sender = self
+            .transport_channels
+            .as_ref()
+            .ok_or_else(|| {
+                BitchatError::Transport(TransportError::InvalidConfiguration {
+                    reason: "In-process transport missing event sender".to_string(),
+                })
+            })?
+            .event_sender();
+
+        sender.send(event).await.map_err(|_| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: "Failed to send event - channel closed".to_string(),
+            })
+        })
+    }
+
+    /// Process a frame from the switchboard
+    async fn handle_frame(&mut self, frame: Frame) -> BitchatResult<()> {
+        match frame {
+            Frame::LinkUp(peer_id) => {
+                if !self.neighbours.insert(peer_id) {
+                    return Ok(());
+                }
+                self.send_event(Event::PeerDiscovered {
+                    peer_id,
+                    transport: self.config.transport_type,
+                    signal_strength: self.config.signal_strength,
+                })
+                .await?;
+                self.send_event(Event::ConnectionEstablished {
+                    peer_id,
+                    transport: self.config.transport_type,
+                })
+                .await
+            }
+            Frame::LinkDown(peer_id) => {
+                if !self.neighbours.remove(&peer_id) {
+                    return Ok(());
+                }
+                self.send_event(Event::ConnectionLost {
+                    peer_id,
+                    transport: self.config.transport_type,
+                    reason: "Link down".to_string(),
+                })
+                .await
+            }
+            Frame::Packet { from, data } => {
+                if self.paused {
+                    return Ok(());
+                }
+                self.handle_incoming_packet(from, data).await
+            }
+        }
+    }
+
+    /// Deliver packets for this node and relay the rest to the other neighbours
+    async fn handle_incoming_packet(
+        &mut self,
+        from_peer: PeerId,
+        data: Vec<u8>,
+    ) -> BitchatResult<()> {
+        let packet = match WireFormat::decode(&data) {
+            Ok(packet) => packet,
+            Err(e) => {
+                tracing::debug!("Failed to decode packet from {}: {}", from_peer, e);
+                return Ok(());
+            }
+        };
+
+        // Our own packets come back to us through the mesh
+        if packet.sender_id == self.local_peer_id || self.is_duplicate(&packet) {
+            return Ok(());
+        }
+
+        let is_for_us = packet.recipient_id == Some(self.local_peer_id);
+        if is_for_us || packet.is_broadcast() {
+            self.send_event(Event::BitchatPacketReceived {
+                from: packet.sender_id,
+                packet: packet.clone(),
+                transport: self.config.transport_type,
+            })
+            .await?;
+        }
+
+        if !is_for_us {
+            self.forward_packet(packet, from_peer)?;
+        }
+        Ok(())
+    }
+
+    fn is_duplicate(&mut self, packet: &BitchatPacket) -> bool {
+        let packet_id = PacketId::from_packet_data(
+            packet.sender_id,
+            packet.header.timestamp.as_millis(),
+            &packet.payload,
+        );
+        self.deduplication.check_and_add(packet_id)
+    }
+
+    /// Relay a packet with a decremented TTL to every neighbour except the one it came from
+    fn forward_packet(
+        &mut self,
+        mut packet: BitchatPacket,
+        from_peer: PeerId,
+    ) -> BitchatResult<()> {
+        let Some(ttl) = packet.header.ttl.decrement() else {
+            tracing::debug!("Dropping packet with TTL=0");
+            return Ok(());
+        };
+        packet.header.ttl = ttl;
+        self.flood(&packet, Some(from_peer))
+    }
+
+    /// Send a packet to every neighbour, optionally skipping one
+    fn flood(&self, packet: &BitchatPacket, except: Option<PeerId>) -> BitchatResult<()> {
+        let data = WireFormat::encode(packet)?;
+        for peer_id in self.neighbours.iter().filter(|p| Some(**p) != except) {
+            if let Err(e) = self
+                .switchboard
+                .deliver(self.local_peer_id, *peer_id, data.clone())
+            {
+                tracing::debug!("Failed to relay packet to {}: {}", peer_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a packet to a peer, flooding it through the mesh if the peer is not a neighbour
+    fn send_bitchat_packet(&mut self, peer_id: PeerId, packet: BitchatPacket) -> BitchatResult<()> {
+        // Remember our own packet so relayed copies are not forwarded again
+        self.is_duplicate(&packet);
+
+        if self.neighbours.contains(&peer_id) {
+            let data = WireFormat::encode(&packet)?;
+            self.switchboard.deliver(self.local_peer_id, peer_id, data)
+        } else {
+            self.flood(&packet, None)
+        }
+    }
+
+    /// Process effect from Core Logic
+    async fn process_effect(&mut self, effect: Effect) -> BitchatResult<()> {
+        let transport_type = self.config.transport_type;
+        match effect {
+            Effect::PauseTransport { transport } if transport == transport_type => {
+                self.paused = true;
+            }
+            Effect::ResumeTransport { transport } if transport == transport_type => {
+                self.paused = false;
+            }
+            _ if self.paused => {}
+            Effect::SendPacket {
+                peer_id,
+                data,
+                transport,
+            } if transport == transport_type => {
+                self.switchboard
+                    .deliver(self.local_peer_id, peer_id, data.to_vec())?;
+            }
+            Effect::SendBitchatPacket {
+                peer_id,
+                packet,
+                transport,
+            } if transport == transport_type => {
+                self.send_bitchat_packet(peer_id, packet)?;
+            }
+            Effect::BroadcastBitchatPacket { packet, transport } if transport == transport_type => {
+                self.is_duplicate(&packet);
+                self.flood(&packet, None)?;
+            }
+            Effect::InitiateConnection { peer_id, transport } if transport == transport_type => {
+                if !self.neighbours.contains(&peer_id) {
+                    return Err(BitchatError::Transport(TransportError::PeerNotFound {
+                        peer_id: peer_id.to_string(),
+                    }));
+                }
+                self.send_event(Event::ConnectionEstablished {
+                    peer_id,
+                    transport: transport_type,
+                })
+                .await?;
+            }
+            _ => {
+                // Effect not for this transport - ignore
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TransportTask for InProcessTransport {
+    fn attach_channels(
+        &mut self,
+        event_sender: EventSender,
+        effect_receiver: EffectReceiver,
+    ) -> BitchatResult<()> {
+        if self.transport_channels.is_some() {
+            return Err(BitchatError::Transport(
+                TransportError::InvalidConfiguration {
+                    reason: "In-process transport channels already attached".to_string(),
+                },
+            ));
+        }
+        self.transport_channels = Some(TransportHandle::new(event_sender, effect_receiver));
+        Ok(())
+    }
+
+    async fn run(&mut self) -> BitchatResult<()> {
+        let mut effect_receiver = self
+            .transport_channels
+            .as_mut()
+            .and_then(|channels| channels.take_effect_receiver())
+            .ok_or_else(|| {
+                BitchatError::Transport(TransportError::InvalidConfiguration {
+                    reason: "In-process transport started without channels or twice".to_string(),
+                })
+            })?;
+        let mut frames = self.frames.take().ok_or_else(|| {
+            BitchatError::Transport(TransportError::InvalidConfiguration {
+                reason: "In-process transport started twice".to_string(),
+            })
+        })?;
+
+        self.switchboard.set_online(self.local_peer_id, true);
+        let _online = OnlineGuard {
+            switchboard: self.switchboard.clone(),
+            peer_id: self.local_peer_id,
+        };
+        tracing::info!("In-process transport for {} online", self.local_peer_id);
+
+        loop {
+            tokio::select! {
+                effect = effect_receiver.recv() => match effect {
+                    Ok(effect) => {
+                        if let Err(e) = self.process_effect(effect).await {
+                            tracing::debug!("Effect processing error: {}", e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("In-process transport lagged, skipped {} effects", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+                frame = frames.recv() => match frame {
+                    Some(frame) => {
+                        if let Err(e) = self.handle_frame(frame).await {
+                            tracing::error!("Failed to handle switchboard frame: {}", e);
+                        }
+                    }
+                    None => break,
+                },
+            }
+        }
+
+        tracing::info!("In-process transport for {} offline", self.local_peer_id);
+        Ok(())
+    }
+
+    fn transport_type(&self) -> ChannelTransportType {
+        self.config.transport_type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topology_links() {
+        assert!(Topology::Line.links(0, 1, 3));
+        assert!(!Topology::Line.links(0, 2, 3));
+        assert!(Topology::Ring.links(0, 2, 3));
+        assert!(Topology::Star.links(0, 5, 6));
+        assert!(!Topology::Star.links(1, 5, 6));
+
+        let grid = Topology::Grid { columns: 3 };
+        assert!(grid.links(0, 1, 9));
+        assert!(grid.links(1, 4, 9));
+        assert!(!grid.links(2, 3, 9));
+        assert!(!Topology::Manual.links(0, 1, 2));
+    }
+
+    #[test]
+    fn test_manual_links_and_duplicate_registration() {
+        let switchboard = InProcessSwitchboard::new(Topology::Manual);
+        let a = PeerId::new([1; 8]);
+        let b = PeerId::new([2; 8]);
+        let _ta = switchboard.create_transport(a).unwrap();
+        let _tb = switchboard.create_transport(b).unwrap();
+        assert!(switchboard.create_transport(a).is_err());
+
+        switchboard.set_online(a, true);
+        switchboard.set_online(b, true);
+        assert!(switchboard.neighbours(&a).is_empty());
+
+        switchboard.link(a, b).unwrap();
+        assert_eq!(switchboard.neighbours(&a), vec![b]);
+        switchboard.unlink(a, b).unwrap();
+        assert!(switchboard.neighbours(&b).is_empty());
+    }
+}
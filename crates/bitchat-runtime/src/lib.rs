@@ -21,11 +21,19 @@ pub mod builder;
 pub mod supervisor;
 pub mod tasks;
 
+#[cfg(feature = "in-process")]
+pub mod in_process;
+
 pub use builder::{MonitoringConfig, RuntimeBuilder, RuntimeHandle};
 pub use managers::*;
 pub use runtime::*;
 pub use supervisor::SupervisorTask;
 
+#[cfg(feature = "in-process")]
+pub use in_process::{
+    InProcessSwitchboard, InProcessTransport, InProcessTransportConfig, Topology,
+};
+
 // Re-export core types for convenience
 pub use bitchat_core::{
     channel::utils::{
@@ -0,0 +1,637 @@
+//! Device Linking Handlers
+//!
+//! Linking devices to one identity, and keeping their private message history in
+//! sync.
+
+use super::handlers::CommandHandlers;
+use bitchat_core::{AppEvent, BitchatResult, Effect};
+
+#[cfg(feature = "experimental")]
+use super::state::{CoreState, SystemTimeSource};
+#[cfg(feature = "experimental")]
+use bitchat_core::internal::TimeSource;
+#[cfg(feature = "experimental")]
+use bitchat_core::{
+    channel::communication::LinkedDeviceInfo,
+    internal::{ContentAddressedMessage, IdentityKeyPair, Timestamp},
+    protocol::session_sync::MAX_DEVICES_PER_IDENTITY,
+    BitchatError, DeviceAnnouncement, DeviceHeartbeat, DeviceInfo, DeviceLinkMessage,
+    DeviceLinkOffer, DeviceLinkRequest, DeviceStatus, LinkedDevice, LinkedIdentity, MessageRef,
+    NegotiationStatus, NoisePayload, NoisePayloadType, PeerId, SessionSyncMessage,
+};
+
+#[cfg(all(feature = "experimental", not(feature = "std")))]
+use log::warn;
+#[cfg(all(feature = "experimental", feature = "std"))]
+use tracing::warn;
+
+/// How often linked devices are sent heartbeats and asked for history we lack
+#[cfg(feature = "experimental")]
+const DEVICE_SYNC_INTERVAL_MS: u64 = 60 * 1000;
+
+impl CommandHandlers {
+    /// Handle link device command
+    ///
+    /// The offer's URI is meant to be shown as a QR code or copied to the new
+    /// device, which answers it with `AcceptDeviceLink`.
+    #[cfg(feature = "experimental")]
+    pub fn handle_link_device(
+        state: &mut CoreState,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let offer = state
+            .device_linker
+            .create_offer(state.peer_id, SystemTimeSource.now());
+        let app_event = AppEvent::DeviceLinkOffer {
+            uri: offer.to_uri()?,
+            expires_at: offer.expires_at.as_millis(),
+        };
+
+        Ok((Vec::new(), vec![app_event]))
+    }
+
+    /// Handle accept device link command
+    ///
+    /// Sends our link request to the device that created the offer. We take on its
+    /// identity once it answers with a grant.
+    #[cfg(feature = "experimental")]
+    pub fn handle_accept_device_link(
+        state: &mut CoreState,
+        uri: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let request = DeviceLinkOffer::from_uri(&uri).and_then(|offer| {
+            if offer.peer_id == state.peer_id {
+                return Err(BitchatError::invalid_packet(
+                    "Offer was created by this device",
+                ));
+            }
+            let peer_id = offer.peer_id;
+            let device = state.devices.local_device().clone();
+            let request = state
+                .device_linker
+                .request(offer, device, SystemTimeSource.now())?;
+            Ok((peer_id, request))
+        });
+        let (peer_id, request) = match request {
+            Ok(request) => request,
+            Err(e) => {
+                return Ok((
+                    Vec::new(),
+                    vec![AppEvent::SystemError {
+                        error: format!("Cannot link device: {}", e),
+                    }],
+                ))
+            }
+        };
+
+        let payload = DeviceLinkMessage::Request(request).to_noise_payload();
+        Ok((
+            Self::send_device_payload(state, peer_id, payload),
+            Vec::new(),
+        ))
+    }
+
+    /// Handle list devices command
+    #[cfg(feature = "experimental")]
+    pub fn handle_list_devices(state: &CoreState) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let devices = state
+            .devices
+            .get_devices()
+            .into_iter()
+            .filter_map(|device| Self::linked_device_info(state, device))
+            .collect();
+
+        Ok((Vec::new(), vec![AppEvent::DevicesReport { devices }]))
+    }
+
+    /// Report device commands as unavailable in builds without experimental payloads
+    #[cfg(not(feature = "experimental"))]
+    pub fn handle_device_linking_unavailable() -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        Ok((
+            Vec::new(),
+            vec![AppEvent::SystemError {
+                error: "Device linking requires the experimental feature".to_string(),
+            }],
+        ))
+    }
+
+    /// Send device payloads whose recipients finished capability negotiation, and
+    /// periodically send every linked device a heartbeat and a sync request
+    ///
+    /// A device still holding the previous round's payloads is skipped, so an
+    /// unreachable device does not pile them up.
+    #[cfg(feature = "experimental")]
+    pub fn handle_device_tick(state: &mut CoreState, now: Timestamp) -> Vec<Effect> {
+        let waiting: Vec<PeerId> = state.pending_device_payloads.keys().copied().collect();
+        let mut effects: Vec<Effect> = waiting
+            .into_iter()
+            .flat_map(|peer_id| Self::flush_device_payloads(state, peer_id))
+            .collect();
+
+        if now - state.last_device_sync < DEVICE_SYNC_INTERVAL_MS {
+            return effects;
+        }
+        state.last_device_sync = now;
+
+        let device_id = state.devices.local_device().device_id.clone();
+        let linked: Vec<PeerId> = state.device_peers.values().copied().collect();
+        for peer_id in linked {
+            if state.pending_device_payloads.contains_key(&peer_id) {
+                continue;
+            }
+            let heartbeat = SessionSyncMessage::Heartbeat(DeviceHeartbeat::new(
+                device_id.clone(),
+                DeviceStatus::Online,
+            ));
+            effects.append(&mut Self::send_sync_message(state, peer_id, &heartbeat));
+            effects.append(&mut Self::request_device_sync(state, peer_id));
+        }
+        effects
+    }
+
+    /// Handle a device link payload received over a peer's Noise session
+    #[cfg(feature = "experimental")]
+    pub(super) fn handle_device_link_message(
+        state: &mut CoreState,
+        from: PeerId,
+        message: DeviceLinkMessage,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        match message {
+            DeviceLinkMessage::Request(request) => Self::grant_device_link(state, from, request),
+            DeviceLinkMessage::Grant(grant) => match state.device_linker.open_grant(from, &grant) {
+                Ok(identity) => Self::adopt_linked_identity(state, from, identity),
+                Err(e) => {
+                    warn!("Ignoring device link grant from peer {}: {}", from, e);
+                    (Vec::new(), Vec::new())
+                }
+            },
+        }
+    }
+
+    /// Answer a link request that proves it scanned one of our offers
+    ///
+    /// The new device gets our identity key, nickname and the devices already
+    /// linked, so it can announce itself to them.
+    #[cfg(feature = "experimental")]
+    fn grant_device_link(
+        state: &mut CoreState,
+        from: PeerId,
+        request: DeviceLinkRequest,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        if state.devices.get_devices().len() >= MAX_DEVICES_PER_IDENTITY {
+            warn!(
+                "Refusing device link request from peer {}: too many linked devices",
+                from
+            );
+            return (Vec::new(), Vec::new());
+        }
+
+        let identity = LinkedIdentity {
+            identity_key: state.identity_key.private_key_bytes(),
+            nickname: state.nickname.clone(),
+            devices: Self::linked_devices(state),
+        };
+        let grant = match state
+            .device_linker
+            .grant(&request, &identity, SystemTimeSource.now())
+        {
+            Ok(grant) => grant,
+            Err(e) => {
+                warn!("Refusing device link request from peer {}: {}", from, e);
+                return (Vec::new(), Vec::new());
+            }
+        };
+
+        let payload = DeviceLinkMessage::Grant(grant).to_noise_payload();
+        let effects = Self::send_device_payload(state, from, payload);
+        let app_events = Self::add_linked_device(state, from, request.device)
+            .into_iter()
+            .collect();
+        (effects, app_events)
+    }
+
+    /// Take on the identity a linked device granted us
+    ///
+    /// The identity key is stored so the link survives a restart. Peers we already
+    /// announced ourselves to are sent the new identity key, the other linked
+    /// devices are told about us, and the granting device is asked for the history
+    /// we are missing.
+    #[cfg(feature = "experimental")]
+    fn adopt_linked_identity(
+        state: &mut CoreState,
+        from: PeerId,
+        identity: LinkedIdentity,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let identity_key = match IdentityKeyPair::from_bytes(&identity.identity_key) {
+            Ok(identity_key) => identity_key,
+            Err(e) => {
+                return (
+                    Vec::new(),
+                    vec![AppEvent::SystemError {
+                        error: format!("Cannot link to device {}: {}", from, e),
+                    }],
+                )
+            }
+        };
+        if let Err(e) = state.set_identity_key(identity_key) {
+            return (
+                Vec::new(),
+                vec![AppEvent::SystemError {
+                    error: format!("Cannot keep the identity linked from {}: {}", from, e),
+                }],
+            );
+        }
+        state.nickname = identity.nickname;
+
+        let mut effects = Vec::new();
+        let announced: Vec<PeerId> = state.announced_peers.drain().collect();
+        for peer_id in announced {
+            match Self::announce_packet(state, peer_id) {
+                Ok(effect) => effects.push(effect),
+                Err(e) => warn!("Failed to announce to peer {}: {}", peer_id, e),
+            }
+        }
+
+        let local_device_id = state.devices.local_device().device_id.clone();
+        let announcement =
+            DeviceAnnouncement::signed(state.devices.local_device().clone(), &state.identity_key)
+                .map(SessionSyncMessage::Announcement);
+        let mut app_events = Vec::new();
+        for linked in identity.devices {
+            if linked.device.device_id == local_device_id {
+                continue;
+            }
+            let peer_id = linked.peer_id;
+            app_events.extend(Self::add_linked_device(state, peer_id, linked.device));
+            if peer_id == from {
+                continue;
+            }
+            match &announcement {
+                Ok(announcement) => {
+                    effects.append(&mut Self::send_sync_message(state, peer_id, announcement))
+                }
+                Err(e) => warn!("Failed to sign device announcement: {}", e),
+            }
+        }
+        effects.append(&mut Self::request_device_sync(state, from));
+        app_events.push(AppEvent::NicknameChanged {
+            nickname: state.nickname.clone(),
+        });
+
+        (effects, app_events)
+    }
+
+    /// Handle a session sync payload received over a peer's Noise session
+    ///
+    /// Announcements are accepted from any peer that signs them with our identity
+    /// key; everything else only from the peer a linked device was announced at.
+    #[cfg(feature = "experimental")]
+    pub(super) fn handle_session_sync_message(
+        state: &mut CoreState,
+        from: PeerId,
+        message: SessionSyncMessage,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let message = match message {
+            SessionSyncMessage::Announcement(announcement) => {
+                if !announcement.verify(&state.identity_key.public_key_bytes()) {
+                    warn!(
+                        "Ignoring device announcement from peer {} not signed by our identity",
+                        from
+                    );
+                    return (Vec::new(), Vec::new());
+                }
+                let app_events: Vec<AppEvent> =
+                    Self::add_linked_device(state, from, announcement.device_info)
+                        .into_iter()
+                        .collect();
+                let effects = if app_events.is_empty() {
+                    Vec::new()
+                } else {
+                    Self::request_device_sync(state, from)
+                };
+                return (effects, app_events);
+            }
+            message => message,
+        };
+
+        if state.device_peers.get(message.device_id()) != Some(&from) {
+            warn!(
+                "Ignoring {:?} from peer {}, which is not a linked device",
+                message.payload_type(),
+                from
+            );
+            return (Vec::new(), Vec::new());
+        }
+
+        match message {
+            SessionSyncMessage::Heartbeat(heartbeat) => {
+                state.devices.record_heartbeat(&heartbeat);
+                (Vec::new(), Vec::new())
+            }
+            SessionSyncMessage::SyncRequest(request) => {
+                let response = match state.devices.process_sync_request(&request) {
+                    Ok(response) => response,
+                    Err(e) => {
+                        warn!("Failed to answer sync request from peer {}: {}", from, e);
+                        return (Vec::new(), Vec::new());
+                    }
+                };
+                // The requester has messages we lack; ask for them in turn
+                let missing = !response.missing_messages.is_empty();
+                let mut effects = Self::send_sync_message(
+                    state,
+                    from,
+                    &SessionSyncMessage::SyncResponse(response),
+                );
+                if missing {
+                    effects.append(&mut Self::request_device_sync(state, from));
+                }
+                (effects, Vec::new())
+            }
+            SessionSyncMessage::SyncResponse(response) => {
+                match state.devices.process_sync_response(&response) {
+                    Ok(changed) => (Vec::new(), Self::apply_synced_messages(state, changed)),
+                    Err(e) => {
+                        warn!("Failed to apply sync response from peer {}: {}", from, e);
+                        (Vec::new(), Vec::new())
+                    }
+                }
+            }
+            SessionSyncMessage::Announcement(_) => (Vec::new(), Vec::new()),
+        }
+    }
+
+    /// Store messages a linked device sent or received, and report them
+    ///
+    /// Messages sent by any of our devices are reported as sent, the rest as
+    /// received; each conversation they belong to is reported as updated.
+    #[cfg(feature = "experimental")]
+    fn apply_synced_messages(state: &mut CoreState, synced: Vec<MessageRef>) -> Vec<AppEvent> {
+        let mut app_events = Vec::new();
+        let mut conversations = Vec::new();
+        for message in synced {
+            let Some(content) = message.content else {
+                continue;
+            };
+            let outgoing = message.sender == state.peer_id
+                || state
+                    .device_peers
+                    .values()
+                    .any(|peer| *peer == message.sender);
+            let peer_id = match (outgoing, message.recipient) {
+                (false, _) => message.sender,
+                (true, Some(recipient)) => recipient,
+                (true, None) => continue,
+            };
+
+            state.message_sequence = state.message_sequence.wrapping_add(1);
+            let stored = match ContentAddressedMessage::from_metadata(
+                message.sender,
+                message.recipient,
+                content.clone(),
+                state.message_sequence,
+                message.timestamp.as_millis(),
+                None,
+            ) {
+                Ok(stored) => stored,
+                Err(e) => {
+                    warn!("Dropping synced message {}: {}", message.message_id, e);
+                    continue;
+                }
+            };
+            if let Err(e) = state.message_store.store_message(stored.clone()) {
+                warn!("Dropping synced message {}: {}", message.message_id, e);
+                continue;
+            }
+
+            app_events.push(if outgoing {
+                AppEvent::MessageSent {
+                    to: peer_id,
+                    content,
+                    timestamp: stored.timestamp,
+                }
+            } else {
+                AppEvent::MessageReceived {
+                    from: peer_id,
+                    content,
+                    timestamp: stored.timestamp,
+                }
+            });
+            if !conversations.contains(&peer_id) {
+                conversations.push(peer_id);
+            }
+        }
+
+        for peer_id in conversations {
+            let messages: Vec<&ContentAddressedMessage> = state
+                .message_store
+                .get_peer_conversations(&peer_id)
+                .into_iter()
+                .flat_map(|conversation| {
+                    state.message_store.get_conversation_messages(conversation)
+                })
+                .collect();
+            app_events.push(AppEvent::ConversationUpdated {
+                peer_id,
+                message_count: messages.len(),
+                last_message_time: messages
+                    .iter()
+                    .map(|message| message.timestamp)
+                    .max()
+                    .unwrap_or_default(),
+            });
+        }
+        app_events
+    }
+
+    /// Keep a private message for syncing with our linked devices
+    ///
+    /// Sending a message to a peer marks what they sent us as read.
+    #[cfg(feature = "experimental")]
+    pub(super) fn remember_private_message(
+        state: &mut CoreState,
+        message: &ContentAddressedMessage,
+    ) {
+        let outgoing = message.sender == state.peer_id;
+        if outgoing {
+            if let Some(recipient) = message.recipient {
+                state.devices.mark_read_from(&recipient);
+            }
+        }
+
+        let mut message_ref = MessageRef::new(
+            message.id.to_hex(),
+            message.sender,
+            message.recipient,
+            Timestamp::new(message.timestamp),
+            MessageRef::hash_content(&message.content),
+        )
+        .with_content(message.content.clone());
+        if outgoing {
+            message_ref.mark_read();
+        }
+        if let Err(e) = state.devices.add_message_ref(message_ref) {
+            warn!(
+                "Failed to keep message {} for device sync: {}",
+                message.id, e
+            );
+        }
+    }
+
+    /// Record a device as linked at a peer ID
+    ///
+    /// Returns an event if the device was not linked before.
+    #[cfg(feature = "experimental")]
+    fn add_linked_device(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        mut device: DeviceInfo,
+    ) -> Option<AppEvent> {
+        if device.device_id == state.devices.local_device().device_id {
+            return None;
+        }
+        let known = state.device_peers.contains_key(&device.device_id);
+        device.update_last_seen();
+        if let Err(e) = state.devices.add_device(device.clone()) {
+            warn!("Cannot link device {}: {}", device.device_id, e);
+            return None;
+        }
+        state.device_peers.insert(device.device_id.clone(), peer_id);
+
+        if known {
+            return None;
+        }
+        Self::linked_device_info(state, &device).map(|device| AppEvent::DeviceLinked { device })
+    }
+
+    /// Ask a linked device for the messages and read state we are missing
+    #[cfg(feature = "experimental")]
+    fn request_device_sync(state: &mut CoreState, peer_id: PeerId) -> Vec<Effect> {
+        let request = SessionSyncMessage::SyncRequest(state.devices.create_sync_request());
+        Self::send_sync_message(state, peer_id, &request)
+    }
+
+    /// Every linked device, including this one, with the peer ID it is reached at
+    #[cfg(feature = "experimental")]
+    fn linked_devices(state: &CoreState) -> Vec<LinkedDevice> {
+        state
+            .devices
+            .get_devices()
+            .into_iter()
+            .filter_map(|device| {
+                Self::linked_device_info(state, device).map(|info| LinkedDevice {
+                    peer_id: info.peer_id,
+                    device: device.clone(),
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "experimental")]
+    fn send_sync_message(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        message: &SessionSyncMessage,
+    ) -> Vec<Effect> {
+        Self::send_device_payload(state, peer_id, message.to_noise_payload())
+    }
+
+    /// Queue a device payload for a peer and send it once the peer can take it
+    #[cfg(feature = "experimental")]
+    fn send_device_payload(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        payload: BitchatResult<NoisePayload>,
+    ) -> Vec<Effect> {
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!(
+                    "Failed to encode device payload for peer {}: {}",
+                    peer_id, e
+                );
+                return Vec::new();
+            }
+        };
+        state
+            .pending_device_payloads
+            .entry(peer_id)
+            .or_default()
+            .push(payload);
+
+        let session_established = state
+            .session_manager
+            .get_session(&peer_id)
+            .is_some_and(|session| session.is_established());
+        if session_established {
+            return Self::flush_device_payloads(state, peer_id);
+        }
+        Self::initiate_handshake(state, peer_id).unwrap_or_else(|e| {
+            warn!("Failed to start handshake with peer {}: {}", peer_id, e);
+            Vec::new()
+        })
+    }
+
+    /// Send the device payloads queued for a peer once it can take them
+    #[cfg(feature = "experimental")]
+    pub(super) fn flush_device_payloads(state: &mut CoreState, peer_id: PeerId) -> Vec<Effect> {
+        let session_established = state
+            .session_manager
+            .get_session(&peer_id)
+            .is_some_and(|session| session.is_established());
+        if !session_established || !state.pending_device_payloads.contains_key(&peer_id) {
+            return Vec::new();
+        }
+        let supported = state.peer_accepts(&peer_id, NoisePayloadType::SessionSyncRequest);
+        if !supported
+            && matches!(
+                state.capabilities.get_negotiation_status(&peer_id),
+                NegotiationStatus::Unknown | NegotiationStatus::Pending
+            )
+        {
+            return Vec::new();
+        }
+
+        let payloads = state
+            .pending_device_payloads
+            .remove(&peer_id)
+            .unwrap_or_default();
+        if !supported {
+            warn!(
+                "Dropping {} device payloads for peer {} without multi-device support",
+                payloads.len(),
+                peer_id
+            );
+            return Vec::new();
+        }
+        payloads
+            .iter()
+            .filter_map(
+                |payload| match Self::encrypted_packet(state, peer_id, payload) {
+                    Ok(effect) => Some(effect),
+                    Err(e) => {
+                        warn!("Failed to send device payload to peer {}: {}", peer_id, e);
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+
+    #[cfg(feature = "experimental")]
+    fn linked_device_info(state: &CoreState, device: &DeviceInfo) -> Option<LinkedDeviceInfo> {
+        let this_device = device.device_id == state.devices.local_device().device_id;
+        let peer_id = if this_device {
+            state.peer_id
+        } else {
+            *state.device_peers.get(&device.device_id)?
+        };
+        Some(LinkedDeviceInfo {
+            device_id: device.device_id.to_string(),
+            name: device.name.clone(),
+            peer_id,
+            this_device,
+            online: this_device || device.is_online(),
+            last_seen: device.last_seen.as_millis(),
+        })
+    }
+}
@@ -0,0 +1,870 @@
+//! File Transfer Handlers
+//!
+//! Offering, accepting, streaming and resuming file transfers between peers.
+
+use super::handlers::CommandHandlers;
+use bitchat_core::{AppEvent, BitchatResult, Effect};
+
+#[cfg(feature = "experimental")]
+use super::state::{CoreState, OutgoingTransfer};
+#[cfg(feature = "experimental")]
+use bitchat_core::{
+    channel::communication::{FileTransferInfo, FileTransferState, TransferDirection},
+    internal::Timestamp,
+    ChannelTransportType, FileAccept, FileChunk, FileComplete, FileOffer, FileResume,
+    FileTransferId, FileTransferMessage, FileTransferSession, NegotiationStatus, NoisePayloadType,
+    PeerId, TransferStatus,
+};
+#[cfg(feature = "experimental")]
+use std::path::{Path, PathBuf};
+
+#[cfg(all(feature = "experimental", not(feature = "std")))]
+use log::{debug, warn};
+#[cfg(all(feature = "experimental", feature = "std"))]
+use tracing::{debug, warn};
+
+/// Minimum gap between file chunks sent over BLE (about 160 KiB/s)
+#[cfg(feature = "experimental")]
+const BLE_CHUNK_INTERVAL_MS: u64 = 100;
+
+/// Minimum gap between file chunks published over Nostr, which relays rate-limit
+#[cfg(feature = "experimental")]
+const NOSTR_CHUNK_INTERVAL_MS: u64 = 1000;
+
+/// How often finished and expired file transfers are cleaned up
+#[cfg(feature = "experimental")]
+const TRANSFER_CLEANUP_INTERVAL_MS: u64 = 60 * 1000;
+
+/// How long a transfer may go quiet before its missing chunks are requested again
+#[cfg(feature = "experimental")]
+const TRANSFER_STALL_MS: u64 = 10 * 1000;
+
+/// How often the progress of incoming transfers is saved for resuming
+#[cfg(feature = "experimental")]
+const TRANSFER_SAVE_INTERVAL_MS: u64 = 5 * 1000;
+
+impl CommandHandlers {
+    /// Handle send file command
+    ///
+    /// The file is hashed up front, so a missing or unreadable file is reported
+    /// straight away. The offer then waits, like a private message, for a Noise
+    /// session, and for capability negotiation to show the peer supports file
+    /// transfer.
+    #[cfg(feature = "experimental")]
+    pub async fn handle_send_file(
+        state: &mut CoreState,
+        recipient: PeerId,
+        path: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let offer = match state
+            .file_transfers
+            .offer_file(Path::new(&path), recipient, None, None)
+            .await
+        {
+            Ok(offer) => offer,
+            Err(e) => {
+                return Ok((
+                    Vec::new(),
+                    vec![AppEvent::SystemError {
+                        error: format!("Cannot send {}: {}", path, e),
+                    }],
+                ))
+            }
+        };
+        state
+            .pending_file_offers
+            .entry(recipient)
+            .or_default()
+            .push(offer);
+
+        let session_established = state
+            .session_manager
+            .get_session(&recipient)
+            .is_some_and(|session| session.is_established());
+        if !session_established {
+            debug!(
+                "No established session with peer {}, queueing file offer",
+                recipient
+            );
+            let effects = Self::initiate_handshake(state, recipient)?;
+            return Ok((effects, Vec::new()));
+        }
+
+        Ok(Self::flush_file_offers(state, recipient).await)
+    }
+
+    /// Handle accept file command
+    #[cfg(feature = "experimental")]
+    pub async fn handle_accept_file(
+        state: &mut CoreState,
+        transfer_id: String,
+        directory: Option<String>,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let (transfer_id, sender) = match Self::resolve_incoming_offer(state, &transfer_id) {
+            Ok(offer) => offer,
+            Err(error) => return Ok((Vec::new(), vec![AppEvent::SystemError { error }])),
+        };
+
+        let download_dir = directory
+            .map(PathBuf::from)
+            .unwrap_or_else(|| state.download_dir.clone());
+        let accepted = match state
+            .file_transfers
+            .accept(&transfer_id, download_dir)
+            .await
+        {
+            Ok(accept) => FileTransferMessage::Accept(accept)
+                .to_noise_payload()
+                .and_then(|payload| Self::encrypted_packet(state, sender, &payload)),
+            Err(e) => Err(e),
+        };
+        let effect = match accepted {
+            Ok(effect) => effect,
+            Err(e) => {
+                let app_events = Self::fail_transfer(state, &transfer_id, e.to_string()).await;
+                return Ok((Vec::new(), app_events));
+            }
+        };
+
+        let app_events = Self::transfer_info(state, &transfer_id)
+            .map(|transfer| AppEvent::FileTransferProgress { transfer })
+            .into_iter()
+            .collect();
+        Ok((vec![effect], app_events))
+    }
+
+    /// Handle reject file command
+    #[cfg(feature = "experimental")]
+    pub async fn handle_reject_file(
+        state: &mut CoreState,
+        transfer_id: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let (transfer_id, sender) = match Self::resolve_incoming_offer(state, &transfer_id) {
+            Ok(offer) => offer,
+            Err(error) => return Ok((Vec::new(), vec![AppEvent::SystemError { error }])),
+        };
+
+        let reject = state
+            .file_transfers
+            .reject(&transfer_id, Some("Rejected by recipient".to_string()))?;
+        let mut effects = Vec::new();
+        match FileTransferMessage::Accept(reject)
+            .to_noise_payload()
+            .and_then(|payload| Self::encrypted_packet(state, sender, &payload))
+        {
+            Ok(effect) => effects.push(effect),
+            Err(e) => warn!(
+                "Failed to tell peer {} about rejected transfer {}: {}",
+                sender, transfer_id, e
+            ),
+        }
+
+        let app_events = Self::transfer_info(state, &transfer_id)
+            .map(|transfer| AppEvent::FileTransferFailed {
+                transfer,
+                reason: "Rejected".to_string(),
+            })
+            .into_iter()
+            .collect();
+        Ok((effects, app_events))
+    }
+
+    /// Handle list transfers command
+    #[cfg(feature = "experimental")]
+    pub fn handle_list_transfers(state: &CoreState) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let mut sessions = state.file_transfers.sessions();
+        sessions.sort_by_key(|session| session.started_at);
+        let transfers = sessions
+            .into_iter()
+            .map(|session| Self::session_info(state.peer_id, session))
+            .collect();
+
+        Ok((Vec::new(), vec![AppEvent::TransfersReport { transfers }]))
+    }
+
+    /// Report file transfer commands as unavailable in builds without experimental payloads
+    #[cfg(not(feature = "experimental"))]
+    pub fn handle_file_transfer_unavailable() -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        Ok((
+            Vec::new(),
+            vec![AppEvent::SystemError {
+                error: "File transfer requires the experimental feature".to_string(),
+            }],
+        ))
+    }
+
+    /// Send the next file chunks, settle queued file offers and revive stalled
+    /// transfers
+    ///
+    /// Chunks are paced per transport rather than per transfer, since transfers to
+    /// different peers share the same radio or relays. BLE carries a chunk every
+    /// `BLE_CHUNK_INTERVAL_MS`; Nostr relays rate-limit publishers, so they only get
+    /// one every `NOSTR_CHUNK_INTERVAL_MS`. Transfers on the same transport take
+    /// turns. A transfer whose peer has no usable session is paused until the
+    /// recipient asks to resume it.
+    #[cfg(feature = "experimental")]
+    pub async fn handle_transfer_tick(
+        state: &mut CoreState,
+        now: Timestamp,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let mut effects = Vec::new();
+        let mut app_events = Vec::new();
+
+        // Hello timeouts may have settled negotiation for peers with queued offers
+        let waiting: Vec<PeerId> = state.pending_file_offers.keys().copied().collect();
+        for peer_id in waiting {
+            let (mut flushed_effects, mut flushed_events) =
+                Self::flush_file_offers(state, peer_id).await;
+            effects.append(&mut flushed_effects);
+            app_events.append(&mut flushed_events);
+        }
+
+        for _ in 0..state.outgoing_transfers.len() {
+            let Some(mut transfer) = state.outgoing_transfers.pop_front() else {
+                break;
+            };
+            let transport = state.transport_for(&transfer.recipient);
+            if state
+                .chunk_slots
+                .get(&transport)
+                .is_some_and(|slot| *slot > now)
+            {
+                state.outgoing_transfers.push_back(transfer);
+                continue;
+            }
+
+            let session_established = state
+                .session_manager
+                .get_session(&transfer.recipient)
+                .is_some_and(|session| session.is_established());
+            if !session_established
+                || !state.peer_accepts(&transfer.recipient, NoisePayloadType::FileChunk)
+            {
+                debug!(
+                    "Pausing transfer {} until peer {} is reachable",
+                    transfer.transfer_id, transfer.recipient
+                );
+                state.outgoing_transfers.push_back(transfer);
+                continue;
+            }
+            let Some(chunk_index) = transfer.next_chunk() else {
+                continue;
+            };
+
+            match Self::next_chunk_packet(state, &transfer, chunk_index).await {
+                Ok(effect) => {
+                    effects.push(effect);
+                    state
+                        .chunk_slots
+                        .insert(transport, now + Self::chunk_interval_ms(transport));
+                    if let Some(session) = state.file_transfers.session(&transfer.transfer_id) {
+                        let sent = session.chunks_received.iter().filter(|&&s| s).count() as u32;
+                        if Self::crossed_percent(sent, session.total_chunks) {
+                            app_events.push(AppEvent::FileTransferProgress {
+                                transfer: Self::session_info(state.peer_id, session),
+                            });
+                        }
+                    }
+                    if !transfer.is_done() {
+                        state.outgoing_transfers.push_back(transfer);
+                    }
+                }
+                Err(e) => {
+                    let mut failed =
+                        Self::fail_transfer(state, &transfer.transfer_id, e.to_string()).await;
+                    app_events.append(&mut failed);
+                }
+            }
+        }
+
+        effects.append(&mut Self::revive_stalled_transfers(state, now));
+
+        if now - state.last_transfer_save >= TRANSFER_SAVE_INTERVAL_MS {
+            state.last_transfer_save = now;
+            if let Err(e) = state.file_transfers.save_progress().await {
+                warn!("Failed to save file transfer progress: {}", e);
+            }
+        }
+
+        if now - state.last_transfer_cleanup >= TRANSFER_CLEANUP_INTERVAL_MS {
+            state.last_transfer_cleanup = now;
+            state.file_transfers.cleanup().await;
+            let transfers = &state.file_transfers;
+            state
+                .resume_attempts
+                .retain(|transfer_id, _| transfers.session(transfer_id).is_some());
+        }
+
+        (effects, app_events)
+    }
+
+    /// Reload file transfers left in progress by an earlier run
+    ///
+    /// Restored transfers look stalled, so the next transfer tick reconnects to
+    /// their peers and the recipients ask for whatever they are missing.
+    #[cfg(feature = "experimental")]
+    pub async fn handle_restore_transfers(
+        state: &mut CoreState,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let restored = state.file_transfers.restore().await?;
+        let app_events = restored
+            .iter()
+            .filter_map(|transfer_id| Self::transfer_info(state, transfer_id))
+            .map(|transfer| AppEvent::FileTransferProgress { transfer })
+            .collect();
+        Ok((Vec::new(), app_events))
+    }
+
+    /// Get transfers that have gone quiet moving again
+    ///
+    /// Without a session the peer is handshaken with again, from either side,
+    /// which also covers a peer that restarted. Once negotiation completes the
+    /// recipient asks for its missing chunks; a recipient that already has a
+    /// session asks straight away, which also recovers chunks lost in transit.
+    #[cfg(feature = "experimental")]
+    fn revive_stalled_transfers(state: &mut CoreState, now: Timestamp) -> Vec<Effect> {
+        let stalled: Vec<(FileTransferId, PeerId, bool)> = state
+            .file_transfers
+            .sessions()
+            .into_iter()
+            .filter(|session| session.status == TransferStatus::InProgress)
+            .filter(|session| {
+                let last_attempt = state
+                    .resume_attempts
+                    .get(&session.transfer_id)
+                    .copied()
+                    .unwrap_or(session.last_activity);
+                now - session.last_activity.max(last_attempt) >= TRANSFER_STALL_MS
+            })
+            .filter(|session| {
+                !state
+                    .outgoing_transfers
+                    .iter()
+                    .any(|transfer| transfer.transfer_id == session.transfer_id)
+            })
+            .map(|session| {
+                let incoming = session.sender != state.peer_id;
+                let peer_id = if incoming {
+                    session.sender
+                } else {
+                    session.recipient
+                };
+                (session.transfer_id.clone(), peer_id, incoming)
+            })
+            .collect();
+
+        let mut effects = Vec::new();
+        for (transfer_id, peer_id, incoming) in stalled {
+            state.resume_attempts.insert(transfer_id.clone(), now);
+            let session_established = state
+                .session_manager
+                .get_session(&peer_id)
+                .is_some_and(|session| session.is_established());
+            if !session_established {
+                match Self::initiate_handshake(state, peer_id) {
+                    Ok(mut handshake) => effects.append(&mut handshake),
+                    Err(e) => warn!("Failed to reconnect to peer {}: {}", peer_id, e),
+                }
+            } else if incoming {
+                effects.extend(Self::resume_packet(state, peer_id, &transfer_id));
+            }
+        }
+        effects
+    }
+
+    /// Ask a peer to resend what our incoming transfers from it are missing
+    ///
+    /// Called whenever capability negotiation with the peer settles, which is the
+    /// first point a new session can carry file transfer payloads.
+    #[cfg(feature = "experimental")]
+    pub(super) fn request_resumes(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        now: Timestamp,
+    ) -> Vec<Effect> {
+        let transfer_ids: Vec<FileTransferId> = state
+            .file_transfers
+            .sessions()
+            .into_iter()
+            .filter(|session| {
+                session.sender == peer_id && session.status == TransferStatus::InProgress
+            })
+            .map(|session| session.transfer_id.clone())
+            .collect();
+
+        let mut effects = Vec::new();
+        for transfer_id in transfer_ids {
+            if let Some(effect) = Self::resume_packet(state, peer_id, &transfer_id) {
+                state.resume_attempts.insert(transfer_id, now);
+                effects.push(effect);
+            }
+        }
+        effects
+    }
+
+    /// Encrypt a resume request for an incoming transfer, if the peer can take one
+    #[cfg(feature = "experimental")]
+    fn resume_packet(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        transfer_id: &FileTransferId,
+    ) -> Option<Effect> {
+        if !state.peer_accepts(&peer_id, NoisePayloadType::FileResume) {
+            return None;
+        }
+        let resume = state.file_transfers.resume_request(transfer_id)?;
+        debug!(
+            "Asking peer {} to resend {} chunks of transfer {}",
+            peer_id,
+            resume.chunk_count(),
+            transfer_id
+        );
+        match FileTransferMessage::Resume(resume)
+            .to_noise_payload()
+            .and_then(|payload| Self::encrypted_packet(state, peer_id, &payload))
+        {
+            Ok(effect) => Some(effect),
+            Err(e) => {
+                warn!(
+                    "Failed to request resume of transfer {}: {}",
+                    transfer_id, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Minimum gap between file chunks on a transport
+    ///
+    /// Everything but Nostr, including the in-process transport, is paced like BLE.
+    #[cfg(feature = "experimental")]
+    fn chunk_interval_ms(transport: ChannelTransportType) -> u64 {
+        if transport == ChannelTransportType::Nostr {
+            NOSTR_CHUNK_INTERVAL_MS
+        } else {
+            BLE_CHUNK_INTERVAL_MS
+        }
+    }
+
+    /// Whether `done` of `total` chunks reaches a new whole percentage
+    ///
+    /// Keeps progress events to at most a hundred per transfer.
+    #[cfg(feature = "experimental")]
+    fn crossed_percent(done: u32, total: u32) -> bool {
+        let (done, total) = (u64::from(done), u64::from(total));
+        done >= total || (done.saturating_sub(1) * 100 / total) != (done * 100 / total)
+    }
+
+    /// Read and encrypt a chunk of an outgoing transfer
+    #[cfg(feature = "experimental")]
+    async fn next_chunk_packet(
+        state: &mut CoreState,
+        transfer: &OutgoingTransfer,
+        chunk_index: u32,
+    ) -> BitchatResult<Effect> {
+        let chunk: FileChunk = state
+            .file_transfers
+            .read_chunk(&transfer.transfer_id, chunk_index)
+            .await?;
+        let payload = FileTransferMessage::Chunk(chunk).to_noise_payload()?;
+        Self::encrypted_packet(state, transfer.recipient, &payload)
+    }
+
+    /// Send file offers queued for a peer once capability negotiation has settled
+    ///
+    /// Offers stay queued while negotiation is pending. They fail if the peer turns
+    /// out not to support file transfer, including when it is a legacy peer.
+    #[cfg(feature = "experimental")]
+    pub(super) async fn flush_file_offers(
+        state: &mut CoreState,
+        peer_id: PeerId,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let mut effects = Vec::new();
+        let mut app_events = Vec::new();
+        if !state.pending_file_offers.contains_key(&peer_id) {
+            return (effects, app_events);
+        }
+        let supported = state.peer_accepts(&peer_id, NoisePayloadType::FileOffer);
+        if !supported
+            && matches!(
+                state.capabilities.get_negotiation_status(&peer_id),
+                NegotiationStatus::Unknown | NegotiationStatus::Pending
+            )
+        {
+            return (effects, app_events);
+        }
+
+        let offers: Vec<FileOffer> = state
+            .pending_file_offers
+            .remove(&peer_id)
+            .unwrap_or_default();
+        for offer in offers {
+            let sent = if supported {
+                FileTransferMessage::Offer(offer.clone())
+                    .to_noise_payload()
+                    .and_then(|payload| Self::encrypted_packet(state, peer_id, &payload))
+            } else {
+                Err(bitchat_core::BitchatError::invalid_packet(
+                    "Peer does not support file transfer",
+                ))
+            };
+            match sent {
+                Ok(effect) => {
+                    effects.push(effect);
+                    app_events.extend(
+                        Self::transfer_info(state, &offer.transfer_id)
+                            .map(|transfer| AppEvent::FileTransferProgress { transfer }),
+                    );
+                }
+                Err(e) => {
+                    let mut failed =
+                        Self::fail_transfer(state, &offer.transfer_id, e.to_string()).await;
+                    app_events.append(&mut failed);
+                }
+            }
+        }
+
+        (effects, app_events)
+    }
+
+    /// Handle a file transfer message received over a peer's Noise session
+    ///
+    /// Each message must come from the side of the transfer that sends it: offers
+    /// and chunks from the sender, answers, completion notices and resume requests
+    /// from the recipient.
+    #[cfg(feature = "experimental")]
+    pub(super) async fn handle_file_transfer_message(
+        state: &mut CoreState,
+        from: PeerId,
+        message: FileTransferMessage,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let expected_direction = match &message {
+            FileTransferMessage::Offer(_) => None,
+            FileTransferMessage::Chunk(_) => Some(TransferDirection::Incoming),
+            FileTransferMessage::Accept(_)
+            | FileTransferMessage::Complete(_)
+            | FileTransferMessage::Resume(_) => Some(TransferDirection::Outgoing),
+        };
+        if let Some(direction) = expected_direction {
+            if !Self::transfer_info(state, message.transfer_id())
+                .is_some_and(|transfer| transfer.direction == direction && transfer.peer_id == from)
+            {
+                warn!(
+                    "Dropping {:?} for transfer {} from peer {}",
+                    message.payload_type(),
+                    message.transfer_id(),
+                    from
+                );
+                return Ok((Vec::new(), Vec::new()));
+            }
+        }
+
+        match message {
+            FileTransferMessage::Offer(offer) => Self::handle_file_offer(state, from, offer),
+            FileTransferMessage::Accept(accept) => Self::handle_file_accept(state, accept).await,
+            FileTransferMessage::Chunk(chunk) => Self::handle_file_chunk(state, from, chunk).await,
+            FileTransferMessage::Complete(complete) => {
+                Self::handle_file_complete(state, complete).await
+            }
+            FileTransferMessage::Resume(resume) => Self::handle_file_resume(state, resume),
+        }
+    }
+
+    /// Register a file offered by a peer and let the user decide on it
+    #[cfg(feature = "experimental")]
+    fn handle_file_offer(
+        state: &mut CoreState,
+        from: PeerId,
+        offer: FileOffer,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        state.file_transfers.receive_offer(&offer, from)?;
+        let app_events = Self::transfer_info(state, &offer.transfer_id)
+            .map(|transfer| AppEvent::FileOffered {
+                transfer,
+                description: offer.description,
+            })
+            .into_iter()
+            .collect();
+        Ok((Vec::new(), app_events))
+    }
+
+    /// Start sending an accepted file, or fail a rejected one
+    #[cfg(feature = "experimental")]
+    async fn handle_file_accept(
+        state: &mut CoreState,
+        accept: FileAccept,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        state.file_transfers.process_accept(&accept).await?;
+        let Some(transfer) = Self::transfer_info(state, &accept.transfer_id) else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        if !accept.accepted {
+            let reason = accept
+                .reason
+                .unwrap_or_else(|| "Rejected by recipient".to_string());
+            return Ok((
+                Vec::new(),
+                vec![AppEvent::FileTransferFailed { transfer, reason }],
+            ));
+        }
+
+        let total_chunks = state
+            .file_transfers
+            .session(&accept.transfer_id)
+            .map_or(0, |session| session.total_chunks);
+        state.outgoing_transfers.push_back(OutgoingTransfer {
+            transfer_id: accept.transfer_id,
+            recipient: transfer.peer_id,
+            pending: (total_chunks > 0)
+                .then_some(0..total_chunks)
+                .into_iter()
+                .collect(),
+        });
+        Ok((
+            Vec::new(),
+            vec![AppEvent::FileTransferProgress { transfer }],
+        ))
+    }
+
+    /// Queue the chunks the recipient of one of our files says it is missing
+    ///
+    /// The request replaces whatever was still queued for the transfer, since the
+    /// recipient knows best what it has.
+    #[cfg(feature = "experimental")]
+    fn handle_file_resume(
+        state: &mut CoreState,
+        resume: FileResume,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        if let Err(e) = state.file_transfers.process_resume(&resume) {
+            warn!("Ignoring resume of transfer {}: {}", resume.transfer_id, e);
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let Some(transfer) = Self::transfer_info(state, &resume.transfer_id) else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        debug!(
+            "Peer {} asked for {} chunks of transfer {}",
+            transfer.peer_id,
+            resume.chunk_count(),
+            resume.transfer_id
+        );
+
+        state
+            .outgoing_transfers
+            .retain(|queued| queued.transfer_id != resume.transfer_id);
+        if resume.chunk_count() > 0 {
+            state.outgoing_transfers.push_back(OutgoingTransfer {
+                transfer_id: resume.transfer_id,
+                recipient: transfer.peer_id,
+                pending: resume
+                    .missing
+                    .into_iter()
+                    .filter(|r| !r.is_empty())
+                    .collect(),
+            });
+        }
+        Ok((
+            Vec::new(),
+            vec![AppEvent::FileTransferProgress { transfer }],
+        ))
+    }
+
+    /// Store a received chunk, finishing the transfer once every chunk is in
+    ///
+    /// Bad chunks are dropped without failing the transfer. The sender learns the
+    /// outcome from a completion notice once the file hash has been checked.
+    #[cfg(feature = "experimental")]
+    async fn handle_file_chunk(
+        state: &mut CoreState,
+        from: PeerId,
+        chunk: FileChunk,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        if let Err(e) = state.file_transfers.receive_chunk(&chunk).await {
+            warn!(
+                "Dropping chunk {} of transfer {}: {}",
+                chunk.chunk_index, chunk.transfer_id, e
+            );
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let transfer_id = chunk.transfer_id;
+        let Some(session) = state.file_transfers.session(&transfer_id) else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        if !session.is_fully_received() {
+            let received = session.chunks_received.iter().filter(|&&r| r).count() as u32;
+            let app_events = Self::crossed_percent(received, session.total_chunks)
+                .then(|| Self::session_info(state.peer_id, session))
+                .map(|transfer| AppEvent::FileTransferProgress { transfer })
+                .into_iter()
+                .collect();
+            return Ok((Vec::new(), app_events));
+        }
+
+        let (complete, outcome) = match state.file_transfers.finish(&transfer_id).await {
+            Ok((path, complete)) => (complete, Ok(path)),
+            Err(e) => (
+                FileComplete::failure(transfer_id.clone(), e.to_string()),
+                Err(e),
+            ),
+        };
+
+        let mut effects = Vec::new();
+        match FileTransferMessage::Complete(complete)
+            .to_noise_payload()
+            .and_then(|payload| Self::encrypted_packet(state, from, &payload))
+        {
+            Ok(effect) => effects.push(effect),
+            Err(e) => warn!(
+                "Failed to send completion of transfer {} to peer {}: {}",
+                transfer_id, from, e
+            ),
+        }
+
+        let app_events = match outcome {
+            Ok(path) => Self::transfer_info(state, &transfer_id)
+                .map(|transfer| AppEvent::FileTransferCompleted {
+                    transfer,
+                    saved_to: Some(path.display().to_string()),
+                })
+                .into_iter()
+                .collect(),
+            Err(e) => Self::fail_transfer(state, &transfer_id, e.to_string()).await,
+        };
+        Ok((effects, app_events))
+    }
+
+    /// Record the recipient's verdict on a file we sent
+    #[cfg(feature = "experimental")]
+    async fn handle_file_complete(
+        state: &mut CoreState,
+        complete: FileComplete,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        state
+            .outgoing_transfers
+            .retain(|transfer| transfer.transfer_id != complete.transfer_id);
+        if let Err(e) = state.file_transfers.process_complete(&complete).await {
+            let app_events = Self::fail_transfer(state, &complete.transfer_id, e.to_string()).await;
+            return Ok((Vec::new(), app_events));
+        }
+
+        let Some(transfer) = Self::transfer_info(state, &complete.transfer_id) else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let app_event = if complete.success {
+            AppEvent::FileTransferCompleted {
+                transfer,
+                saved_to: None,
+            }
+        } else {
+            AppEvent::FileTransferFailed {
+                transfer,
+                reason: complete
+                    .error
+                    .unwrap_or_else(|| "Transfer failed".to_string()),
+            }
+        };
+        Ok((Vec::new(), vec![app_event]))
+    }
+
+    /// Stop a transfer that can no longer continue and report why
+    ///
+    /// Transfers that are still running are cancelled, which deletes their temp
+    /// files; ones the other side already failed keep their status.
+    #[cfg(feature = "experimental")]
+    async fn fail_transfer(
+        state: &mut CoreState,
+        transfer_id: &FileTransferId,
+        reason: String,
+    ) -> Vec<AppEvent> {
+        warn!("File transfer {} failed: {}", transfer_id, reason);
+        state
+            .outgoing_transfers
+            .retain(|transfer| &transfer.transfer_id != transfer_id);
+        state.resume_attempts.remove(transfer_id);
+        let running = state
+            .file_transfers
+            .session(transfer_id)
+            .is_some_and(|session| {
+                matches!(
+                    session.status,
+                    TransferStatus::Offered | TransferStatus::InProgress
+                )
+            });
+        if running {
+            if let Err(e) = state.file_transfers.cancel(transfer_id).await {
+                debug!("Failed to cancel transfer {}: {}", transfer_id, e);
+            }
+        }
+
+        Self::transfer_info(state, transfer_id)
+            .map(|transfer| AppEvent::FileTransferFailed { transfer, reason })
+            .into_iter()
+            .collect()
+    }
+
+    /// Find a pending incoming offer by transfer ID or unique ID prefix
+    ///
+    /// Returns the transfer ID and the sender, or an error message for the UI.
+    #[cfg(feature = "experimental")]
+    fn resolve_incoming_offer(
+        state: &CoreState,
+        transfer_id: &str,
+    ) -> Result<(FileTransferId, PeerId), String> {
+        let offers: Vec<&FileTransferSession> = state
+            .file_transfers
+            .sessions()
+            .into_iter()
+            .filter(|session| {
+                session.status == TransferStatus::Offered
+                    && session.sender != state.peer_id
+                    && session.transfer_id.as_str().starts_with(transfer_id)
+            })
+            .collect();
+        let exact = offers
+            .iter()
+            .find(|session| session.transfer_id.as_str() == transfer_id);
+        match (exact, offers.as_slice()) {
+            (Some(session), _) | (None, [session]) => {
+                Ok((session.transfer_id.clone(), session.sender))
+            }
+            (None, []) => Err(format!("No pending file offer {}", transfer_id)),
+            (None, _) => Err(format!("Transfer ID {} is ambiguous", transfer_id)),
+        }
+    }
+
+    /// Snapshot of a transfer for app events
+    #[cfg(feature = "experimental")]
+    fn transfer_info(state: &CoreState, transfer_id: &FileTransferId) -> Option<FileTransferInfo> {
+        state
+            .file_transfers
+            .session(transfer_id)
+            .map(|session| Self::session_info(state.peer_id, session))
+    }
+
+    /// Describe a transfer session from our side
+    #[cfg(feature = "experimental")]
+    fn session_info(local_peer_id: PeerId, session: &FileTransferSession) -> FileTransferInfo {
+        let (peer_id, direction) = if session.sender == local_peer_id {
+            (session.recipient, TransferDirection::Outgoing)
+        } else {
+            (session.sender, TransferDirection::Incoming)
+        };
+        FileTransferInfo {
+            transfer_id: session.transfer_id.to_string(),
+            peer_id,
+            direction,
+            filename: session.metadata.filename.clone(),
+            mime_type: session.metadata.mime_type.clone(),
+            size: session.metadata.size,
+            bytes_transferred: session.bytes_transferred(),
+            state: match session.status {
+                TransferStatus::Offered => FileTransferState::Offered,
+                TransferStatus::InProgress => FileTransferState::InProgress,
+                TransferStatus::Completed => FileTransferState::Completed,
+                TransferStatus::Failed => FileTransferState::Failed,
+                TransferStatus::Cancelled => FileTransferState::Cancelled,
+                TransferStatus::Expired => FileTransferState::Expired,
+            },
+        }
+    }
+}
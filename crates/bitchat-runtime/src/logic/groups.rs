@@ -0,0 +1,779 @@
+//! Group Messaging Handlers
+//!
+//! Creating and managing groups, and sending and receiving group messages under
+//! each member's sender key.
+
+use super::handlers::CommandHandlers;
+use bitchat_core::{AppEvent, BitchatResult, Effect};
+
+#[cfg(feature = "experimental")]
+use super::state::{CoreState, SystemTimeSource};
+#[cfg(feature = "experimental")]
+use bitchat_core::internal::TimeSource;
+#[cfg(feature = "experimental")]
+use bitchat_core::{
+    channel::communication::{GroupInfo, GroupMemberInfo},
+    internal::generate_fingerprint,
+    protocol::packet::{CURRENT_PROTOCOL_VERSION, MAX_PAYLOAD_SIZE_V1, PROTOCOL_VERSION_2},
+    BitchatPacket, ChannelTransportType, EncryptedGroupMessage, GroupId, GroupManager,
+    GroupMessagingMessage, GroupMetadata, MessageType, NegotiationStatus, NoisePayloadType,
+    PacketFlags, PeerId, SignedGroupOperation,
+};
+
+#[cfg(all(feature = "experimental", not(feature = "std")))]
+use log::{debug, warn};
+#[cfg(all(feature = "experimental", feature = "std"))]
+use tracing::{debug, warn};
+
+/// Group payloads queued per peer while its session or negotiation is pending
+#[cfg(feature = "experimental")]
+const MAX_PENDING_GROUP_PAYLOADS_PER_PEER: usize = 64;
+
+impl CommandHandlers {
+    /// Handle create group command
+    #[cfg(feature = "experimental")]
+    pub fn handle_create_group(
+        state: &mut CoreState,
+        name: String,
+        description: Option<String>,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let fingerprint = generate_fingerprint(state.session_manager.local_public_key());
+        let created = state.groups.create_group(
+            name.clone(),
+            description,
+            state.nickname.clone(),
+            fingerprint,
+        );
+        let app_event = match created {
+            Ok(create) => match state.groups.get_group(create.group_id()) {
+                Some(group) => AppEvent::GroupMembershipChanged {
+                    group: Self::group_info(group),
+                    joined: vec![state.peer_id],
+                    left: Vec::new(),
+                },
+                None => return Ok((Vec::new(), Vec::new())),
+            },
+            Err(e) => AppEvent::SystemError {
+                error: format!("Cannot create group {}: {}", name, e),
+            },
+        };
+
+        Ok((Vec::new(), vec![app_event]))
+    }
+
+    /// Handle invite to group command
+    ///
+    /// Invitations name the invitee's identity key, so a peer that has not
+    /// announced itself to us is sent our announce first; the invitation goes out
+    /// once its answer arrives.
+    #[cfg(feature = "experimental")]
+    pub fn handle_invite_to_group(
+        state: &mut CoreState,
+        group_id: String,
+        peer_id: PeerId,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let group_id = match Self::resolve_group(state.groups.get_all_groups(), &group_id) {
+            Ok(group_id) => group_id,
+            Err(error) => return Ok((Vec::new(), vec![AppEvent::SystemError { error }])),
+        };
+
+        if let Some(signing_key) = Self::peer_signing_key(state, &peer_id) {
+            return Ok(
+                match Self::invite_to_group(state, &group_id, peer_id, signing_key) {
+                    Ok(effects) => (effects, Vec::new()),
+                    Err(e) => (
+                        Vec::new(),
+                        vec![AppEvent::SystemError {
+                            error: format!(
+                                "Cannot invite {} to group {}: {}",
+                                peer_id, group_id, e
+                            ),
+                        }],
+                    ),
+                },
+            );
+        }
+
+        state
+            .pending_group_invites
+            .entry(peer_id)
+            .or_default()
+            .push(group_id);
+        let session_established = state
+            .session_manager
+            .get_session(&peer_id)
+            .is_some_and(|session| session.is_established());
+        if !session_established {
+            debug!(
+                "No established session with peer {}, queueing group invitation",
+                peer_id
+            );
+            return Ok((Self::initiate_handshake(state, peer_id)?, Vec::new()));
+        }
+
+        Ok((vec![Self::announce_packet(state, peer_id)?], Vec::new()))
+    }
+
+    /// Handle join group command
+    #[cfg(feature = "experimental")]
+    pub fn handle_join_group(
+        state: &mut CoreState,
+        group_id: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let group_id = match Self::resolve_group(state.groups.get_invitations(), &group_id) {
+            Ok(group_id) => group_id,
+            Err(error) => return Ok((Vec::new(), vec![AppEvent::SystemError { error }])),
+        };
+
+        let fingerprint = generate_fingerprint(state.session_manager.local_public_key());
+        let join = match state
+            .groups
+            .join_group(&group_id, state.nickname.clone(), fingerprint)
+        {
+            Ok(join) => join,
+            Err(e) => {
+                return Ok((
+                    Vec::new(),
+                    vec![AppEvent::SystemError {
+                        error: format!("Cannot join group {}: {}", group_id, e),
+                    }],
+                ))
+            }
+        };
+
+        let mut effects =
+            Self::send_to_group(state, &group_id, &GroupMessagingMessage::Operation(join));
+        for member in Self::other_members(state, &group_id) {
+            effects.append(&mut Self::send_sender_key(state, &group_id, member));
+        }
+        let app_events = state
+            .groups
+            .get_group(&group_id)
+            .map(|group| AppEvent::GroupMembershipChanged {
+                group: Self::group_info(group),
+                joined: vec![state.peer_id],
+                left: Vec::new(),
+            })
+            .into_iter()
+            .collect();
+
+        Ok((effects, app_events))
+    }
+
+    /// Handle leave group command
+    #[cfg(feature = "experimental")]
+    pub fn handle_leave_group(
+        state: &mut CoreState,
+        group_id: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        Self::change_group_membership(state, group_id, |groups, group_id| {
+            groups.leave_group(group_id, None)
+        })
+    }
+
+    /// Handle kick from group command
+    #[cfg(feature = "experimental")]
+    pub fn handle_kick_from_group(
+        state: &mut CoreState,
+        group_id: String,
+        peer_id: PeerId,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        Self::change_group_membership(state, group_id, |groups, group_id| {
+            groups.kick_member(group_id, peer_id, None)
+        })
+    }
+
+    /// Append a local operation that removes a member from a group
+    ///
+    /// The operation goes to everyone who was a member before it, so a removed
+    /// member learns it is out.
+    #[cfg(feature = "experimental")]
+    fn change_group_membership<F>(
+        state: &mut CoreState,
+        group_id: String,
+        change: F,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)>
+    where
+        F: FnOnce(&mut GroupManager, &GroupId) -> BitchatResult<SignedGroupOperation>,
+    {
+        let group_id = match Self::resolve_group(state.groups.get_all_groups(), &group_id) {
+            Ok(group_id) => group_id,
+            Err(error) => return Ok((Vec::new(), vec![AppEvent::SystemError { error }])),
+        };
+
+        let before = state.groups.get_group(&group_id).map(Self::group_info);
+        let members = Self::other_members(state, &group_id);
+        let operation = match change(&mut state.groups, &group_id) {
+            Ok(operation) => GroupMessagingMessage::Operation(operation),
+            Err(e) => {
+                return Ok((
+                    Vec::new(),
+                    vec![AppEvent::SystemError {
+                        error: format!("Cannot change group {}: {}", group_id, e),
+                    }],
+                ))
+            }
+        };
+
+        let mut effects = Vec::new();
+        for member in members {
+            effects.append(&mut Self::send_group_payload(state, member, &operation));
+        }
+        let mut app_events = Vec::new();
+        if let Some(before) = before {
+            let (mut settled_effects, mut settled_events) =
+                Self::settle_group_changes(state, &group_id, before);
+            effects.append(&mut settled_effects);
+            app_events.append(&mut settled_events);
+        }
+
+        Ok((effects, app_events))
+    }
+
+    /// Handle send group message command
+    ///
+    /// The message is encrypted once under our sender key and the ciphertext is
+    /// flooded through the mesh for every member at once.
+    #[cfg(feature = "experimental")]
+    pub fn handle_send_group_message(
+        state: &mut CoreState,
+        group_id: String,
+        content: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let group_id = match Self::resolve_group(state.groups.get_all_groups(), &group_id) {
+            Ok(group_id) => group_id,
+            Err(error) => return Ok((Vec::new(), vec![AppEvent::SystemError { error }])),
+        };
+
+        let (message, encrypted) = match state.groups.encrypt_message(&group_id, content) {
+            Ok(encrypted) => encrypted,
+            Err(e) => {
+                return Ok((
+                    Vec::new(),
+                    vec![AppEvent::SystemError {
+                        error: format!("Cannot send to group {}: {}", group_id, e),
+                    }],
+                ))
+            }
+        };
+
+        let effects = Self::broadcast_group_message(state, &group_id, &encrypted)?;
+        Ok((
+            effects,
+            vec![AppEvent::GroupMessageSent {
+                group_id: group_id.to_string(),
+                content: message.content,
+                timestamp: message.timestamp.as_millis(),
+            }],
+        ))
+    }
+
+    /// Handle list groups command
+    #[cfg(feature = "experimental")]
+    pub fn handle_list_groups(state: &CoreState) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let groups = state
+            .groups
+            .get_all_groups()
+            .into_iter()
+            .map(Self::group_info)
+            .collect();
+        let invitations = state
+            .groups
+            .get_invitations()
+            .into_iter()
+            .map(Self::group_info)
+            .collect();
+
+        Ok((
+            Vec::new(),
+            vec![AppEvent::GroupsReport {
+                groups,
+                invitations,
+            }],
+        ))
+    }
+
+    /// Report group commands as unavailable in builds without experimental payloads
+    #[cfg(not(feature = "experimental"))]
+    pub fn handle_group_messaging_unavailable() -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        Ok((
+            Vec::new(),
+            vec![AppEvent::SystemError {
+                error: "Group messaging requires the experimental feature".to_string(),
+            }],
+        ))
+    }
+
+    /// Send group payloads whose recipients finished capability negotiation
+    ///
+    /// Hello timeouts may settle negotiation without a message from the peer.
+    #[cfg(feature = "experimental")]
+    pub fn handle_group_tick(state: &mut CoreState) -> Vec<Effect> {
+        let waiting: Vec<PeerId> = state.pending_group_payloads.keys().copied().collect();
+        waiting
+            .into_iter()
+            .flat_map(|peer_id| Self::flush_group_payloads(state, peer_id))
+            .collect()
+    }
+
+    /// Handle a group message flooded through the mesh
+    ///
+    /// Every node on the mesh sees the flood; nodes outside the group drop it.
+    #[cfg(feature = "experimental")]
+    pub(super) fn handle_group_broadcast(
+        state: &mut CoreState,
+        packet: BitchatPacket,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let from = packet.sender_id;
+        if from == state.peer_id {
+            return (Vec::new(), Vec::new());
+        }
+        let encrypted = match EncryptedGroupMessage::from_bytes(&packet.payload) {
+            Ok(encrypted) => encrypted,
+            Err(e) => {
+                debug!("Dropping malformed group message from peer {}: {}", from, e);
+                return (Vec::new(), Vec::new());
+            }
+        };
+        if encrypted.sender != from || state.groups.get_group(&encrypted.group_id).is_none() {
+            debug!(
+                "Ignoring group message for group {} from peer {}",
+                encrypted.group_id, from
+            );
+            return (Vec::new(), Vec::new());
+        }
+        Self::handle_group_message(state, from, GroupMessagingMessage::Message(encrypted))
+    }
+
+    /// Handle a group messaging payload received over a peer's Noise session
+    ///
+    /// A payload that cannot be applied is dropped, but whatever it changed before
+    /// failing is still reported. A member whose log is missing operations asks the
+    /// sender for them.
+    #[cfg(feature = "experimental")]
+    pub(super) fn handle_group_message(
+        state: &mut CoreState,
+        from: PeerId,
+        message: GroupMessagingMessage,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let group_id = message.group_id().clone();
+        let payload_type = message.payload_type();
+        let before = state.groups.get_group(&group_id).map(Self::group_info);
+        let invited = state
+            .groups
+            .get_invitations()
+            .iter()
+            .any(|group| group.group_id == group_id);
+
+        let mut effects = Vec::new();
+        let mut app_events = Vec::new();
+        let received = match message {
+            GroupMessagingMessage::Operation(operation) => {
+                state.groups.process_operation(operation)
+            }
+            GroupMessagingMessage::Sync(sync) => {
+                state.groups.process_sync(from, sync).map(|reply| {
+                    if let Some(reply) = reply {
+                        effects.append(&mut Self::send_group_payload(
+                            state,
+                            from,
+                            &GroupMessagingMessage::Sync(reply),
+                        ));
+                    }
+                })
+            }
+            GroupMessagingMessage::SenderKey(distribution) => {
+                // A member whose key we did not hold may not hold ours either
+                let known = state.groups.has_sender_key(&group_id, &from);
+                state
+                    .groups
+                    .process_sender_key(from, &distribution)
+                    .map(|()| {
+                        if !known {
+                            effects.append(&mut Self::send_sender_key(state, &group_id, from));
+                        }
+                    })
+            }
+            GroupMessagingMessage::Message(encrypted) => {
+                state.groups.decrypt_message(&encrypted).map(|message| {
+                    app_events.push(AppEvent::GroupMessageReceived {
+                        group_id: group_id.to_string(),
+                        from: message.sender,
+                        content: message.content,
+                        timestamp: message.timestamp.as_millis(),
+                    });
+                })
+            }
+        };
+        if let Err(e) = received {
+            warn!(
+                "Dropping {:?} for group {} from peer {}: {}",
+                payload_type, group_id, from, e
+            );
+        }
+
+        let Some(before) = before else {
+            if !invited {
+                app_events.extend(
+                    state
+                        .groups
+                        .get_invitations()
+                        .into_iter()
+                        .find(|group| group.group_id == group_id)
+                        .map(|group| AppEvent::GroupInvitation {
+                            group: Self::group_info(group),
+                            from,
+                        }),
+                );
+            }
+            return (effects, app_events);
+        };
+
+        if state.groups.needs_sync(&group_id) {
+            match state.groups.sync_request(&group_id) {
+                Ok(sync) => effects.append(&mut Self::send_group_payload(
+                    state,
+                    from,
+                    &GroupMessagingMessage::Sync(sync),
+                )),
+                Err(e) => warn!("Failed to request sync of group {}: {}", group_id, e),
+            }
+        }
+        let (mut settled_effects, mut settled_events) =
+            Self::settle_group_changes(state, &group_id, before);
+        effects.append(&mut settled_effects);
+        app_events.append(&mut settled_events);
+
+        (effects, app_events)
+    }
+
+    /// Report membership changes to a group since `before` and act on them
+    ///
+    /// New members are sent our log, since the operations they joined after may
+    /// not have reached them, and then our sender key. Sender keys rotated because
+    /// a member left go to everyone who remains.
+    #[cfg(feature = "experimental")]
+    fn settle_group_changes(
+        state: &mut CoreState,
+        group_id: &GroupId,
+        before: GroupInfo,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let mut effects = Vec::new();
+        let mut app_events = Vec::new();
+        let after = state.groups.get_group(group_id).map(Self::group_info);
+
+        let members = |group: &GroupInfo| -> Vec<PeerId> {
+            group.members.iter().map(|member| member.peer_id).collect()
+        };
+        let (joined, left) = match &after {
+            Some(group) => {
+                let (was, is) = (members(&before), members(group));
+                (
+                    is.iter().filter(|p| !was.contains(p)).copied().collect(),
+                    was.iter().filter(|p| !is.contains(p)).copied().collect(),
+                )
+            }
+            // We left or were removed, and the group is gone
+            None => (Vec::new(), vec![state.peer_id]),
+        };
+
+        for &member in &joined {
+            if member == state.peer_id {
+                continue;
+            }
+            match state.groups.full_log(group_id) {
+                Ok(log) => effects.append(&mut Self::send_group_payload(
+                    state,
+                    member,
+                    &GroupMessagingMessage::Sync(log),
+                )),
+                Err(e) => warn!("Failed to share the log of group {}: {}", group_id, e),
+            }
+            effects.append(&mut Self::send_sender_key(state, group_id, member));
+        }
+        for distribution in state.groups.take_key_rotations() {
+            let message = GroupMessagingMessage::SenderKey(distribution);
+            for member in Self::other_members(state, group_id) {
+                effects.append(&mut Self::send_group_payload(state, member, &message));
+            }
+        }
+
+        if !joined.is_empty() || !left.is_empty() {
+            app_events.push(AppEvent::GroupMembershipChanged {
+                group: after.unwrap_or(before),
+                joined,
+                left,
+            });
+        }
+        (effects, app_events)
+    }
+
+    /// Invite a peer with a known identity key to a group
+    ///
+    /// The invitation is sent to the group, and the invitee gets the whole log.
+    #[cfg(feature = "experimental")]
+    fn invite_to_group(
+        state: &mut CoreState,
+        group_id: &GroupId,
+        peer_id: PeerId,
+        signing_key: [u8; 32],
+    ) -> BitchatResult<Vec<Effect>> {
+        let invite = state
+            .groups
+            .invite_member(group_id, peer_id, signing_key, None)?;
+        let log = state.groups.full_log(group_id)?;
+
+        let mut effects =
+            Self::send_to_group(state, group_id, &GroupMessagingMessage::Operation(invite));
+        effects.append(&mut Self::send_group_payload(
+            state,
+            peer_id,
+            &GroupMessagingMessage::Sync(log),
+        ));
+        Ok(effects)
+    }
+
+    /// Send the group invitations that were waiting for a peer's identity key
+    #[cfg(feature = "experimental")]
+    pub(super) fn flush_group_invites(
+        state: &mut CoreState,
+        peer_id: PeerId,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        let mut effects = Vec::new();
+        let mut app_events = Vec::new();
+        let Some(signing_key) = Self::peer_signing_key(state, &peer_id) else {
+            return (effects, app_events);
+        };
+
+        for group_id in state
+            .pending_group_invites
+            .remove(&peer_id)
+            .unwrap_or_default()
+        {
+            match Self::invite_to_group(state, &group_id, peer_id, signing_key) {
+                Ok(mut invited) => effects.append(&mut invited),
+                Err(e) => app_events.push(AppEvent::SystemError {
+                    error: format!("Cannot invite {} to group {}: {}", peer_id, group_id, e),
+                }),
+            }
+        }
+        (effects, app_events)
+    }
+
+    /// Identity key a peer announced over its current or an earlier session
+    #[cfg(feature = "experimental")]
+    fn peer_signing_key(state: &CoreState, peer_id: &PeerId) -> Option<[u8; 32]> {
+        state
+            .peer_fingerprints
+            .get(peer_id)
+            .and_then(|fingerprint| state.identities.get_cryptographic_identity(fingerprint))
+            .and_then(|identity| identity.signing_public_key)
+    }
+
+    /// Members of a group other than us
+    #[cfg(feature = "experimental")]
+    fn other_members(state: &CoreState, group_id: &GroupId) -> Vec<PeerId> {
+        state
+            .groups
+            .get_group(group_id)
+            .map(|group| group.member_ids())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|peer_id| *peer_id != state.peer_id)
+            .collect()
+    }
+
+    /// Flood a group message through the mesh, once for all members
+    ///
+    /// The message is already encrypted and signed under our sender key, so it
+    /// needs no pairwise session. Members we only reach over Nostr are sent the
+    /// same packet directly.
+    #[cfg(feature = "experimental")]
+    fn broadcast_group_message(
+        state: &CoreState,
+        group_id: &GroupId,
+        encrypted: &EncryptedGroupMessage,
+    ) -> BitchatResult<Vec<Effect>> {
+        let payload = encrypted.to_bytes()?;
+        let version = if payload.len() <= MAX_PAYLOAD_SIZE_V1 {
+            CURRENT_PROTOCOL_VERSION
+        } else {
+            PROTOCOL_VERSION_2
+        };
+        let packet = BitchatPacket::new(
+            MessageType::GroupMessage,
+            state.peer_id,
+            None,
+            SystemTimeSource.now(),
+            payload,
+            PacketFlags::NONE,
+        )?
+        .with_version(version);
+
+        let mut effects = Self::mesh_broadcast(state, packet.clone());
+        effects.extend(
+            Self::other_members(state, group_id)
+                .into_iter()
+                .filter(|member| state.transport_for(member) == ChannelTransportType::Nostr)
+                .map(|peer_id| Effect::SendBitchatPacket {
+                    peer_id,
+                    packet: packet.clone(),
+                    transport: ChannelTransportType::Nostr,
+                }),
+        );
+        Ok(effects)
+    }
+
+    /// Send a group payload to every other member of the group
+    #[cfg(feature = "experimental")]
+    fn send_to_group(
+        state: &mut CoreState,
+        group_id: &GroupId,
+        message: &GroupMessagingMessage,
+    ) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        for member in Self::other_members(state, group_id) {
+            effects.append(&mut Self::send_group_payload(state, member, message));
+        }
+        effects
+    }
+
+    /// Send our sender key for a group to one of its members
+    #[cfg(feature = "experimental")]
+    fn send_sender_key(state: &mut CoreState, group_id: &GroupId, peer_id: PeerId) -> Vec<Effect> {
+        match state.groups.sender_key_distribution(group_id) {
+            Ok(distribution) => Self::send_group_payload(
+                state,
+                peer_id,
+                &GroupMessagingMessage::SenderKey(distribution),
+            ),
+            Err(e) => {
+                warn!("No sender key for group {}: {}", group_id, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Send a group payload to a peer over its Noise session
+    ///
+    /// Like file offers, payloads wait for a session and for capability
+    /// negotiation to show the peer supports group messaging. Only the newest
+    /// payloads are kept for a peer that never gets that far; members missing
+    /// operations catch up through log sync.
+    #[cfg(feature = "experimental")]
+    fn send_group_payload(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        message: &GroupMessagingMessage,
+    ) -> Vec<Effect> {
+        let payload = match message.to_noise_payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to encode {:?}: {}", message.payload_type(), e);
+                return Vec::new();
+            }
+        };
+        let pending = state.pending_group_payloads.entry(peer_id).or_default();
+        if pending.len() >= MAX_PENDING_GROUP_PAYLOADS_PER_PEER {
+            let dropped = pending.remove(0);
+            warn!(
+                "Dropping queued {:?} for peer {}, too many group payloads waiting",
+                dropped.payload_type, peer_id
+            );
+        }
+        pending.push(payload);
+
+        let session_established = state
+            .session_manager
+            .get_session(&peer_id)
+            .is_some_and(|session| session.is_established());
+        if session_established {
+            return Self::flush_group_payloads(state, peer_id);
+        }
+        Self::initiate_handshake(state, peer_id).unwrap_or_else(|e| {
+            warn!("Failed to start handshake with peer {}: {}", peer_id, e);
+            Vec::new()
+        })
+    }
+
+    /// Send the group payloads queued for a peer once it can take them
+    #[cfg(feature = "experimental")]
+    pub(super) fn flush_group_payloads(state: &mut CoreState, peer_id: PeerId) -> Vec<Effect> {
+        let session_established = state
+            .session_manager
+            .get_session(&peer_id)
+            .is_some_and(|session| session.is_established());
+        if !session_established || !state.pending_group_payloads.contains_key(&peer_id) {
+            return Vec::new();
+        }
+        let supported = state.peer_accepts(&peer_id, NoisePayloadType::GroupMessage);
+        if !supported
+            && matches!(
+                state.capabilities.get_negotiation_status(&peer_id),
+                NegotiationStatus::Unknown | NegotiationStatus::Pending
+            )
+        {
+            return Vec::new();
+        }
+
+        let payloads = state
+            .pending_group_payloads
+            .remove(&peer_id)
+            .unwrap_or_default();
+        if !supported {
+            warn!(
+                "Dropping {} group payloads for peer {} without group messaging support",
+                payloads.len(),
+                peer_id
+            );
+            return Vec::new();
+        }
+        payloads
+            .iter()
+            .filter_map(
+                |payload| match Self::encrypted_packet(state, peer_id, payload) {
+                    Ok(effect) => Some(effect),
+                    Err(e) => {
+                        warn!("Failed to send group payload to peer {}: {}", peer_id, e);
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Find a group by ID or unique ID prefix
+    #[cfg(feature = "experimental")]
+    fn resolve_group(groups: Vec<&GroupMetadata>, group_id: &str) -> Result<GroupId, String> {
+        let matching: Vec<&GroupId> = groups
+            .into_iter()
+            .map(|group| &group.group_id)
+            .filter(|id| id.as_str().starts_with(group_id))
+            .collect();
+        let exact = matching.iter().find(|id| id.as_str() == group_id);
+        match (exact, matching.as_slice()) {
+            (Some(id), _) | (None, [id]) => Ok((*id).clone()),
+            (None, []) => Err(format!("No group {}", group_id)),
+            (None, _) => Err(format!("Group ID {} is ambiguous", group_id)),
+        }
+    }
+
+    /// Snapshot of a group for app events
+    #[cfg(feature = "experimental")]
+    fn group_info(group: &GroupMetadata) -> GroupInfo {
+        GroupInfo {
+            group_id: group.group_id.to_string(),
+            name: group.name.clone(),
+            description: group.description.clone(),
+            members: group
+                .members
+                .values()
+                .map(|member| GroupMemberInfo {
+                    peer_id: member.peer_id,
+                    nickname: member.nickname.clone(),
+                    admin: member.role.can_kick(),
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,15 +1,30 @@
 //! Core Logic Command and Event Handlers
 //!
-//! Contains all the command and event handling logic for the Core Logic task.
+//! Contains the command and event handling logic for the Core Logic task. The
+//! file transfer, group messaging and device linking handlers extend
+//! `CommandHandlers` from `file_transfer`, `groups` and `device_linking`.
 
 use super::state::{CoreState, SystemTimeSource};
 use bitchat_core::internal::TimeSource;
 use bitchat_core::{
+    channel::communication::FavoriteStatus,
     internal::{
-        ConnectionEvent, ConnectionState, ContentAddressedMessage, MessageId, SessionState,
-        StateTransition,
+        generate_fingerprint, ConnectionEvent, ConnectionState, ContentAddressedMessage, MessageId,
+        SessionParams, SessionState, StateTransition, Timestamp,
     },
-    AppEvent, BitchatResult, ChannelTransportType, ConnectionStatus, Effect, PeerId,
+    protocol::{
+        packet::{CURRENT_PROTOCOL_VERSION, MAX_PAYLOAD_SIZE_V1},
+        AnnouncePayload, CanonicalFavorite, DiscoveredPeer, PacketId,
+    },
+    AppEvent, BitchatMessage, BitchatPacket, BitchatResult, ChannelTransportType, ConnectionStatus,
+    Effect, Fingerprint, MessageType, NoisePayload, NoisePayloadType, PacketFlags, PeerId,
+};
+
+#[cfg(feature = "experimental")]
+use bitchat_core::{
+    protocol::{packet::PROTOCOL_VERSION_2, FavoriteNotification},
+    CapabilityMessage, CapabilityRejection, DeviceLinkMessage, FileTransferMessage,
+    GroupMessagingMessage, RejectionReason, SessionSyncMessage,
 };
 
 #[cfg(not(feature = "std"))]
@@ -17,112 +32,175 @@ use log::{debug, error, info, warn};
 #[cfg(feature = "std")]
 use tracing::{debug, error, warn};
 
+/// Length of the first Noise XX message (initiator ephemeral key with empty payload)
+const NOISE_XX_INITIAL_MESSAGE_LEN: usize = 32;
+
+/// Longest nickname we announce, in bytes, short enough for group member records
+const MAX_NICKNAME_LEN: usize = 64;
+
+/// Public messages held per sender until its announce arrives
+const MAX_HELD_BROADCASTS_PER_PEER: usize = 16;
+
+/// Unannounced senders whose public messages are held at once
+const MAX_HELD_BROADCAST_SENDERS: usize = 64;
+
+/// How long a public message waits for its sender's announce
+const HELD_BROADCAST_TTL_MS: u64 = 30_000;
+
+/// Private messages queued per peer while the Noise handshake with it runs
+const MAX_PENDING_MESSAGES_PER_PEER: usize = 32;
+
+/// How long a private message waits for the Noise handshake with its recipient
+const PENDING_MESSAGE_TTL_MS: u64 = 60_000;
+
 /// Command and event handlers for the Core Logic task
 pub struct CommandHandlers;
 
 impl CommandHandlers {
     /// Handle send message command
+    ///
+    /// Messages are encrypted over the peer's Noise session. If no session exists yet
+    /// the message is queued and a handshake is started; queued messages are flushed
+    /// once the handshake completes, or reported as unsent if it doesn't complete in
+    /// time. Peers do not need to be direct neighbours: the handshake and the
+    /// encrypted packets are relayed by the mesh transports.
     pub async fn handle_send_message(
         state: &mut CoreState,
         recipient: PeerId,
         content: String,
     ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
-        // Check if we can send to this peer
-        let connection = state.connections.get(&recipient);
-        if let Some(conn) = connection {
-            if !conn.can_send_messages() {
+        Self::send_private_message(state, recipient, content, None).await
+    }
+
+    /// Send a private message, optionally as a reply to an earlier message
+    async fn send_private_message(
+        state: &mut CoreState,
+        recipient: PeerId,
+        content: String,
+        reply_to: Option<MessageId>,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let session_established = state
+            .session_manager
+            .get_session(&recipient)
+            .is_some_and(|session| session.state() == SessionState::Established);
+
+        if !session_established {
+            let pending = state.pending_messages.entry(recipient).or_default();
+            if pending.len() >= MAX_PENDING_MESSAGES_PER_PEER {
                 return Ok((
                     Vec::new(),
                     vec![AppEvent::SystemError {
-                        error: format!("Cannot send to peer {} - not connected", recipient),
+                        error: format!(
+                            "Message not sent: {} messages are already waiting for a session with {}",
+                            pending.len(),
+                            recipient
+                        ),
                     }],
                 ));
             }
-        } else {
-            return Ok((
-                Vec::new(),
-                vec![AppEvent::SystemError {
-                    error: format!("Peer {} not found", recipient),
-                }],
-            ));
+            debug!(
+                "No established session with peer {}, queueing message",
+                recipient
+            );
+            pending.push((SystemTimeSource.now(), content, reply_to));
+            let effects = Self::initiate_handshake(state, recipient)?;
+            return Ok((effects, Vec::new()));
         }
 
-        // Create content-addressed message with proper protocol layering
-        // Note: In a full implementation, this would go through Noise Protocol Framework
-        // and proper packet fragmentation instead of raw content transmission
-        state.message_sequence += 1;
-        let message = ContentAddressedMessage::new(
-            state.peer_id,
-            Some(recipient),
-            content.clone(),
-            state.message_sequence,
-        );
-
-        // Store message
-        state.message_store.store_message(message.clone())?;
-        state.stats.messages_sent += 1;
-
-        // Encrypt message using Noise Protocol if session is established
-        let packet_data = if let Some(session) = state.session_manager.get_session_mut(&recipient) {
-            if session.state() == SessionState::Established {
-                // Use proper Noise Protocol encryption
-                match session.encrypt(content.as_bytes(), &SystemTimeSource) {
-                    Ok(encrypted) => encrypted,
-                    Err(e) => {
-                        error!("Failed to encrypt message for peer {}: {}", recipient, e);
-                        return Ok((
-                            Vec::new(),
-                            vec![AppEvent::SystemError {
-                                error: format!("Encryption failed: {}", e),
-                            }],
-                        ));
-                    }
-                }
-            } else {
-                // Session not established, cannot send encrypted message
-                warn!(
-                    "Session with peer {} not established, cannot send message",
-                    recipient
-                );
-                return Ok((
+        match Self::encrypt_private_message(state, recipient, content, reply_to) {
+            Ok((effect, app_event)) => Ok((vec![effect], vec![app_event])),
+            Err(e) => {
+                error!("Failed to encrypt message for peer {}: {}", recipient, e);
+                Ok((
                     Vec::new(),
                     vec![AppEvent::SystemError {
-                        error: format!("Session with peer {} not established", recipient),
+                        error: format!("Encryption failed: {}", e),
                     }],
-                ));
+                ))
             }
-        } else {
-            // No session exists, need to establish one first
-            warn!("No session with peer {}, cannot send message", recipient);
-            return Ok((
-                Vec::new(),
-                vec![AppEvent::SystemError {
-                    error: format!("No session with peer {}", recipient),
-                }],
+        }
+    }
+
+    /// Handle send broadcast command
+    ///
+    /// The message is signed with our identity key and flooded through the mesh,
+    /// where every node that hears it shows it in the public timeline.
+    pub fn handle_send_broadcast(
+        state: &mut CoreState,
+        content: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        Self::send_public_message(state, content, None)
+    }
+
+    /// Sign and flood a public message, optionally as a reply to an earlier message
+    ///
+    /// Canonical apps read the reply flag as a channel name and broadcasts can't be
+    /// negotiated per peer, so a public reply is only threaded in our own store.
+    fn send_public_message(
+        state: &mut CoreState,
+        content: String,
+        reply_to: Option<MessageId>,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        if content.trim().is_empty() {
+            return Err(bitchat_core::BitchatError::config_error(
+                "Message must not be empty",
             ));
-        };
+        }
 
-        // Determine transport from connection state
-        let transport =
-            if let Some(ConnectionState::Connected(conn)) = state.connections.get(&recipient) {
-                conn.transport
-            } else {
-                ChannelTransportType::Ble // Default fallback
-            };
+        // Public messages have no sequence, so every node derives the same ID
+        let stored = ContentAddressedMessage::from_metadata(
+            state.peer_id,
+            None,
+            content.clone(),
+            0,
+            SystemTimeSource.now().as_millis(),
+            None,
+        )?;
+        let message = Self::outgoing_message(state, &stored, None);
+        let stored = stored
+            .with_reply_to(reply_to)
+            .with_mentions(message.mentions.clone().unwrap_or_default());
+        let packet =
+            BitchatPacket::create_public_message(state.peer_id, &message, &state.identity_key)?;
 
-        let effects = vec![Effect::SendPacket {
-            peer_id: recipient,
-            data: packet_data,
-            transport,
-        }];
+        state.message_store.store_message(stored.clone())?;
+        state.stats.messages_sent += 1;
 
-        let app_events = vec![AppEvent::MessageSent {
-            to: recipient,
-            content,
-            timestamp: message.timestamp,
-        }];
+        Ok((
+            Self::mesh_broadcast(state, packet),
+            vec![AppEvent::BroadcastSent {
+                message_id: stored.id,
+                content: stored.content,
+                timestamp: stored.timestamp,
+            }],
+        ))
+    }
 
-        Ok((effects, app_events))
+    /// Handle reply to message command
+    ///
+    /// Replies to public messages are public; replies in a private conversation go
+    /// to the other peer in it.
+    pub async fn handle_reply_to_message(
+        state: &mut CoreState,
+        message_id: String,
+        content: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let parent = match Self::resolve_message(state, &message_id) {
+            Ok(parent) => parent,
+            Err(error) => return Ok((Vec::new(), vec![AppEvent::SystemError { error }])),
+        };
+
+        match parent.recipient {
+            None => Self::send_public_message(state, content, Some(parent.id)),
+            Some(recipient) => {
+                let peer_id = if parent.sender == state.peer_id {
+                    recipient
+                } else {
+                    parent.sender
+                };
+                Self::send_private_message(state, peer_id, content, Some(parent.id)).await
+            }
+        }
     }
 
     /// Handle connect to peer command
@@ -142,7 +220,15 @@ impl CommandHandlers {
         }) {
             Ok(transition) => {
                 Self::apply_state_transition(state, transition).await;
-                Ok((Vec::new(), Vec::new())) // Effects handled by transition
+
+                // Peers we have already heard from (directly or via the mesh) can be
+                // handshaken with immediately
+                let effects = if state.peer_transports.contains_key(&peer_id) {
+                    Self::initiate_handshake(state, peer_id)?
+                } else {
+                    Vec::new()
+                };
+                Ok((effects, Vec::new()))
             }
             Err(e) => {
                 error!("Failed to start discovery for peer {}: {}", peer_id, e);
@@ -220,15 +306,24 @@ impl CommandHandlers {
     }
 
     /// Handle shutdown command
-    pub async fn handle_shutdown() -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
-        let effects = vec![
+    ///
+    /// Neighbours hear a signed leave before we stop listening, so they drop us
+    /// without waiting for our announces to go stale.
+    pub async fn handle_shutdown(state: &CoreState) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let leave = BitchatPacket::create_leave(
+            state.peer_id,
+            &state.identity_key,
+            SystemTimeSource.now(),
+        )?;
+        let mut effects = Self::mesh_broadcast(state, leave);
+        effects.extend([
             Effect::StopListening {
                 transport: ChannelTransportType::Ble,
             },
             Effect::StopListening {
                 transport: ChannelTransportType::Nostr,
             },
-        ];
+        ]);
 
         Ok((effects, Vec::new()))
     }
@@ -262,6 +357,10 @@ impl CommandHandlers {
             }
         }
 
+        state
+            .peers
+            .record_signal(&peer_id, transport, signal_strength, SystemTimeSource.now());
+
         let app_events = vec![AppEvent::PeerStatusChanged {
             peer_id,
             status: ConnectionStatus::Discovering,
@@ -318,6 +417,11 @@ impl CommandHandlers {
         )?;
 
         state.message_store.store_message(message.clone())?;
+        // Broadcasts are heard by every nearby device, so only private messages sync
+        #[cfg(feature = "experimental")]
+        if recipient.is_some() {
+            Self::remember_private_message(state, &message);
+        }
         state.stats.messages_received += 1;
 
         let app_events = vec![AppEvent::MessageReceived {
@@ -333,8 +437,8 @@ impl CommandHandlers {
     pub async fn handle_bitchat_packet_received(
         state: &mut CoreState,
         from: PeerId,
-        packet: bitchat_core::protocol::BitchatPacket,
-        _transport: ChannelTransportType,
+        packet: BitchatPacket,
+        transport: ChannelTransportType,
     ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
         // Update connection activity
         if let Some(connection) = state.connections.remove(&from) {
@@ -351,6 +455,39 @@ impl CommandHandlers {
             }
         }
 
+        state.peer_transports.insert(packet.sender_id, transport);
+        state
+            .peers
+            .record_activity(&packet.sender_id, transport, SystemTimeSource.now());
+
+        match packet.message_type() {
+            MessageType::NoiseHandshake | MessageType::NoiseEncrypted
+                if packet.recipient_id != Some(state.peer_id) =>
+            {
+                debug!(
+                    "Ignoring {:?} packet addressed to another peer",
+                    packet.message_type()
+                );
+                return Ok((Vec::new(), Vec::new()));
+            }
+            MessageType::NoiseHandshake => {
+                return Self::handle_noise_handshake(state, packet, transport)
+            }
+            MessageType::NoiseEncrypted => {
+                return Self::handle_noise_encrypted(state, packet).await
+            }
+            MessageType::Announce => return Self::handle_announce(state, packet, transport),
+            MessageType::Leave => return Self::handle_leave(state, packet, transport),
+            MessageType::Message if packet.is_broadcast() => {
+                return Self::handle_public_message(state, packet)
+            }
+            #[cfg(feature = "experimental")]
+            MessageType::GroupMessage => return Ok(Self::handle_group_broadcast(state, packet)),
+            #[cfg(not(feature = "experimental"))]
+            MessageType::GroupMessage => return Ok((Vec::new(), Vec::new())),
+            _ => {}
+        }
+
         // Extract message content from packet payload
         let content = String::from_utf8_lossy(&packet.payload).to_string();
         let resolved_timestamp = packet.header.timestamp.as_millis();
@@ -387,21 +524,43 @@ impl CommandHandlers {
         peer_id: PeerId,
         transport: ChannelTransportType,
     ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
-        if let Some(connection) = state.connections.remove(&peer_id) {
-            match connection.transition(ConnectionEvent::ConnectionEstablished {
-                session_id: format!("session-{}-{}", peer_id, transport),
-            }) {
-                Ok(transition) => {
-                    Self::apply_state_transition(state, transition).await;
-                }
-                Err(e) => {
-                    error!("Connection established transition failed: {}", e);
-                    // Create new disconnected state since transition failed
-                    let new_connection = ConnectionState::new_disconnected(peer_id);
-                    state.connections.insert(peer_id, new_connection);
-                }
+        let connection = state
+            .connections
+            .remove(&peer_id)
+            .unwrap_or_else(|| ConnectionState::new_disconnected(peer_id));
+        let session_id = format!("session-{}-{}", peer_id, transport);
+
+        // Links reported by a transport may arrive before we asked for them (the
+        // remote side connected to us), so walk the state machine up to Connected
+        let result = match connection {
+            ConnectionState::Connecting(_) => {
+                connection.transition(ConnectionEvent::ConnectionEstablished { session_id })
+            }
+            other => Self::promote_to_connected(state, other, transport, session_id),
+        };
+
+        match result {
+            Ok(transition) => {
+                Self::apply_state_transition(state, transition).await;
+            }
+            Err(e) => {
+                error!("Connection established transition failed: {}", e);
+                // Create new disconnected state since transition failed
+                let new_connection = ConnectionState::new_disconnected(peer_id);
+                state.connections.insert(peer_id, new_connection);
             }
         }
+        state.peer_transports.insert(peer_id, transport);
+        if transport != ChannelTransportType::Nostr {
+            state.announce_schedule.announce_soon();
+        }
+
+        // Only one side of a link initiates the Noise handshake: the lower peer ID
+        let effects = if state.peer_id < peer_id {
+            Self::initiate_handshake(state, peer_id)?
+        } else {
+            Vec::new()
+        };
 
         let app_events = vec![AppEvent::PeerStatusChanged {
             peer_id,
@@ -409,7 +568,45 @@ impl CommandHandlers {
             transport: Some(transport),
         }];
 
-        Ok((Vec::new(), app_events))
+        Ok((effects, app_events))
+    }
+
+    /// Drive a connection through discovery and connecting into the connected state
+    fn promote_to_connected(
+        state: &mut CoreState,
+        mut connection: ConnectionState,
+        transport: ChannelTransportType,
+        session_id: String,
+    ) -> Result<StateTransition, bitchat_core::internal::StateTransitionError> {
+        let mut events = Vec::new();
+        if !matches!(
+            connection,
+            ConnectionState::Disconnected(_) | ConnectionState::Discovering(_)
+        ) {
+            events.push(ConnectionEvent::Disconnect);
+        }
+        if !matches!(connection, ConnectionState::Discovering(_)) {
+            events.push(ConnectionEvent::StartDiscovery {
+                timeout_seconds: None,
+            });
+        }
+        events.push(ConnectionEvent::InitiateConnection {
+            transport,
+            session_params: SessionParams {
+                protocol_version: 1,
+                encryption_key: Vec::new(),
+                timeout_seconds: 30,
+            },
+        });
+
+        for event in events {
+            let transition = connection.transition(event)?;
+            connection = transition.new_state.clone();
+            state.audit_trail.push(transition.audit_entry);
+            state.stats.state_transitions += 1;
+        }
+
+        connection.transition(ConnectionEvent::ConnectionEstablished { session_id })
     }
 
     /// Handle connection lost event
@@ -435,6 +632,14 @@ impl CommandHandlers {
             }
         }
 
+        // The peer left range of this transport; mutual favorites fall back to Nostr
+        if state.peer_transports.get(&peer_id) == Some(&transport) {
+            state.peer_transports.remove(&peer_id);
+        }
+        if transport != ChannelTransportType::Nostr {
+            state.announce_schedule.announce_soon();
+        }
+
         let app_events = vec![AppEvent::PeerStatusChanged {
             peer_id,
             status: ConnectionStatus::Error,
@@ -456,6 +661,1232 @@ impl CommandHandlers {
         Ok((Vec::new(), app_events))
     }
 
+    /// Start a Noise XX handshake with a peer as initiator
+    ///
+    /// Does nothing if a session is already handshaking or established.
+    pub fn initiate_handshake(
+        state: &mut CoreState,
+        peer_id: PeerId,
+    ) -> BitchatResult<Vec<Effect>> {
+        if let Some(session) = state.session_manager.get_session(&peer_id) {
+            if !session.is_failed() {
+                return Ok(Vec::new());
+            }
+            state.session_manager.remove_session(&peer_id);
+        }
+
+        let session = state.session_manager.get_or_create_outbound(peer_id)?;
+        let message = session.create_handshake_message(&[], &SystemTimeSource)?;
+        debug!("Initiating Noise handshake with peer {}", peer_id);
+
+        Ok(vec![Self::directed_packet(
+            state,
+            peer_id,
+            MessageType::NoiseHandshake,
+            message,
+        )?])
+    }
+
+    /// Handle a Noise handshake packet addressed to us
+    pub fn handle_noise_handshake(
+        state: &mut CoreState,
+        packet: BitchatPacket,
+        transport: ChannelTransportType,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let from = packet.sender_id;
+
+        // Decide which handshake the message belongs to from the step each
+        // session expects next, so a stray initiation can't reset a session
+        let rehandshake = match state.session_manager.get_session(&from) {
+            None => {
+                state.session_manager.create_inbound(from)?;
+                false
+            }
+            Some(session) if session.state() == SessionState::Handshaking => {
+                if !session.awaits_handshake_message() {
+                    debug!("Dropping unexpected handshake message from peer {}", from);
+                    return Ok((Vec::new(), Vec::new()));
+                }
+                // Both sides initiated at once: message 2 is never initiation-sized,
+                // so the initiator can recognise the collision and the higher peer
+                // ID yields
+                let collision =
+                    session.is_initiator() && packet.payload.len() == NOISE_XX_INITIAL_MESSAGE_LEN;
+                if collision {
+                    if state.peer_id < from {
+                        debug!("Ignoring colliding handshake initiation from peer {}", from);
+                        return Ok((Vec::new(), Vec::new()));
+                    }
+                    state.session_manager.create_inbound(from)?;
+                }
+                false
+            }
+            // The peer is handshaking again while our session is live: run the new
+            // handshake on the side and keep the old session until it completes
+            Some(_) => {
+                // Anything but the final message of a pending handshake starts a new one
+                let continues = state
+                    .session_manager
+                    .get_pending_mut(&from)
+                    .is_some_and(|pending| pending.handshake_step() == 2);
+                if !continues {
+                    state.session_manager.create_pending_inbound(from)?;
+                }
+                true
+            }
+        };
+
+        let session = if rehandshake {
+            state.session_manager.get_pending_mut(&from)
+        } else {
+            state.session_manager.get_session_mut(&from)
+        };
+        let Some(session) = session else {
+            debug!("Dropping stray handshake message from peer {}", from);
+            return Ok((Vec::new(), Vec::new()));
+        };
+
+        if let Err(e) = session.process_handshake_message(&packet.payload, &SystemTimeSource) {
+            if rehandshake {
+                state.session_manager.remove_pending(&from);
+            } else {
+                state.session_manager.remove_session(&from);
+            }
+            return Err(e);
+        }
+
+        let mut effects = Vec::new();
+        let established = session.is_established();
+        let response = if established {
+            None
+        } else {
+            Some(session.create_handshake_message(&[], &SystemTimeSource)?)
+        };
+        // Reading this packet completed the handshake, so the link it arrived over
+        // leads to whoever holds the peer's session key
+        let completed = session.is_established();
+        if let Some(response) = response {
+            effects.push(Self::directed_packet(
+                state,
+                from,
+                MessageType::NoiseHandshake,
+                response,
+            )?);
+        }
+
+        let mut app_events = Vec::new();
+        if established {
+            if rehandshake && !state.session_manager.promote_pending(&from)? {
+                return Ok((effects, app_events));
+            }
+            let (mut flushed_effects, mut flushed_events) =
+                Self::handle_session_established(state, from);
+            effects.append(&mut flushed_effects);
+            app_events.append(&mut flushed_events);
+        }
+        if completed {
+            effects.push(Effect::BindLinkIdentity {
+                peer_id: from,
+                handshake: PacketId::from_packet_data(
+                    from,
+                    packet.header.timestamp.as_millis(),
+                    &packet.payload,
+                ),
+                transport,
+            });
+        }
+
+        Ok((effects, app_events))
+    }
+
+    /// Handle an encrypted packet addressed to us
+    pub async fn handle_noise_encrypted(
+        state: &mut CoreState,
+        packet: BitchatPacket,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let from = packet.sender_id;
+        let Some(session) = state
+            .session_manager
+            .get_session_mut(&from)
+            .filter(|session| session.is_established())
+        else {
+            warn!(
+                "Dropping encrypted packet from peer {} without an established session",
+                from
+            );
+            return Ok((Vec::new(), Vec::new()));
+        };
+
+        let plaintext = session.decrypt(&packet.payload, &SystemTimeSource)?;
+        let payload = NoisePayload::from_binary(&plaintext)?;
+
+        match payload.payload_type {
+            NoisePayloadType::PrivateMessage => {
+                let message = BitchatMessage::from_binary(&payload.data)?;
+                // Canonical apps send favorite notices as private messages
+                if let Some(notice) = CanonicalFavorite::from_content(&message.content) {
+                    return Self::handle_canonical_favorite(state, from, notice);
+                }
+
+                // Adopt the sender's ID when it is content-addressed, so replies
+                // on either side refer to the same message
+                let shared_id = MessageId::from_hex(&message.id).ok();
+                let reply_to = Self::replied_to(&message);
+                let stored = ContentAddressedMessage::from_metadata(
+                    from,
+                    Some(state.peer_id),
+                    message.content.clone(),
+                    0,
+                    message.timestamp.as_millis(),
+                    shared_id,
+                )
+                .or_else(|_| {
+                    ContentAddressedMessage::from_metadata(
+                        from,
+                        Some(state.peer_id),
+                        message.content.clone(),
+                        0,
+                        message.timestamp.as_millis(),
+                        None,
+                    )
+                })?
+                .with_reply_to(reply_to)
+                .with_mentions(message.mentions.unwrap_or_default());
+                state.message_store.store_message(stored.clone())?;
+                #[cfg(feature = "experimental")]
+                Self::remember_private_message(state, &stored);
+                state.stats.messages_received += 1;
+
+                let mut app_events = vec![AppEvent::MessageReceived {
+                    from,
+                    content: message.content,
+                    timestamp: stored.timestamp,
+                }];
+                app_events.extend(Self::highlight(state, &stored));
+                Ok((Vec::new(), app_events))
+            }
+            #[cfg(feature = "experimental")]
+            NoisePayloadType::FavoriteNotification => {
+                let notification = FavoriteNotification::from_binary(&payload.data)?;
+                Self::handle_favorite_notification(state, from, notification)
+            }
+            #[cfg(feature = "experimental")]
+            NoisePayloadType::VersionHello
+            | NoisePayloadType::VersionAck
+            | NoisePayloadType::CapabilityRejection => {
+                let message = CapabilityMessage::from_noise_payload(&payload)?;
+                let (mut effects, mut app_events) =
+                    Self::handle_capability_message(state, from, message)?;
+                let (mut flushed_effects, mut flushed_events) =
+                    Self::flush_file_offers(state, from).await;
+                effects.append(&mut flushed_effects);
+                app_events.append(&mut flushed_events);
+                effects.append(&mut Self::request_resumes(
+                    state,
+                    from,
+                    SystemTimeSource.now(),
+                ));
+                effects.append(&mut Self::flush_group_payloads(state, from));
+                effects.append(&mut Self::flush_device_payloads(state, from));
+                Ok((effects, app_events))
+            }
+            #[cfg(feature = "experimental")]
+            NoisePayloadType::FileOffer
+            | NoisePayloadType::FileAccept
+            | NoisePayloadType::FileChunk
+            | NoisePayloadType::FileComplete
+            | NoisePayloadType::FileResume => {
+                let message = FileTransferMessage::from_noise_payload(&payload)?;
+                Self::handle_file_transfer_message(state, from, message).await
+            }
+            #[cfg(feature = "experimental")]
+            NoisePayloadType::GroupCreate
+            | NoisePayloadType::GroupInvite
+            | NoisePayloadType::GroupJoin
+            | NoisePayloadType::GroupLeave
+            | NoisePayloadType::GroupMessage
+            | NoisePayloadType::GroupUpdate
+            | NoisePayloadType::GroupKick
+            | NoisePayloadType::GroupSenderKey
+            | NoisePayloadType::GroupSync => {
+                let message = GroupMessagingMessage::from_noise_payload(&payload)?;
+                Ok(Self::handle_group_message(state, from, message))
+            }
+            #[cfg(feature = "experimental")]
+            NoisePayloadType::DeviceLinkRequest | NoisePayloadType::DeviceLinkGrant => {
+                let message = DeviceLinkMessage::from_noise_payload(&payload)?;
+                Ok(Self::handle_device_link_message(state, from, message))
+            }
+            #[cfg(feature = "experimental")]
+            NoisePayloadType::DeviceAnnouncement
+            | NoisePayloadType::SessionSyncRequest
+            | NoisePayloadType::SessionSyncResponse
+            | NoisePayloadType::DeviceHeartbeat => {
+                let message = SessionSyncMessage::from_noise_payload(&payload)?;
+                Ok(Self::handle_session_sync_message(state, from, message))
+            }
+            other => {
+                debug!("Unhandled noise payload {:?} from peer {}", other, from);
+                Ok((Vec::new(), Vec::new()))
+            }
+        }
+    }
+
+    /// Handle an announce packet
+    ///
+    /// The announced identity key and any Nostr public key are only bound to the peer
+    /// when the announced Noise key is the one its established session authenticated,
+    /// so a third party cannot redirect a peer's Nostr traffic by announcing under its
+    /// peer ID. A peer we have not announced ourselves to over this session gets our
+    /// announce in reply.
+    ///
+    /// The peer directory also takes announces whose peer ID is derived from the
+    /// announced Noise key, which no one else can sign for. Announces we would
+    /// discard anyway are dropped before checking their signature, since every
+    /// peer's periodic announces reach the whole mesh.
+    pub fn handle_announce(
+        state: &mut CoreState,
+        packet: BitchatPacket,
+        transport: ChannelTransportType,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let from = packet.sender_id;
+        let payload = packet.parse_announce()?;
+
+        let announced_fingerprint = generate_fingerprint(payload.noise_public_key);
+        let authenticated = state
+            .session_manager
+            .get_session(&from)
+            .filter(|session| session.is_established())
+            .and_then(|session| session.peer_fingerprint())
+            .is_some_and(|fingerprint| *fingerprint == announced_fingerprint);
+        let self_certified = PeerId::from_noise_key(&payload.noise_public_key) == from;
+        let mut app_events = Vec::new();
+        if authenticated || self_certified {
+            packet.verify_signature(&payload.signing_public_key)?;
+            let previous = state
+                .peers
+                .get(&from)
+                .map(|entry| entry.peer.nickname.clone());
+            let peer = DiscoveredPeer::from_payload(from, payload.clone(), SystemTimeSource.now());
+            let announced_at = packet.header.timestamp;
+            if state
+                .peers
+                .record_announce(peer, transport, announced_at, authenticated)?
+            {
+                debug!("Peer {} joined as '{}'", from, payload.nickname);
+            }
+            if let Some(previous) = previous.filter(|previous| *previous != payload.nickname) {
+                debug!("Peer {} is now '{}'", from, payload.nickname);
+                app_events.push(AppEvent::PeerNicknameChanged {
+                    peer_id: from,
+                    previous,
+                    nickname: payload.nickname.clone(),
+                });
+            }
+            app_events.extend(Self::release_held_broadcasts(state, from));
+        }
+        if !authenticated {
+            if payload.nostr_public_key.is_some() {
+                warn!(
+                    "Ignoring Nostr key announced by peer {} without a matching Noise session",
+                    from
+                );
+            }
+            return Ok((Vec::new(), app_events));
+        }
+
+        let fingerprint = state.identities.create_cryptographic_identity(
+            payload.noise_public_key,
+            Some(payload.signing_public_key),
+        )?;
+        let claimed = state
+            .identities
+            .get_social_identity(&fingerprint)
+            .and_then(|social| social.claimed_nickname.as_deref());
+        if claimed != Some(payload.nickname.as_str()) {
+            state
+                .identities
+                .set_nickname(&fingerprint, Some(payload.nickname.clone()))?;
+        }
+
+        let mut effects = Vec::new();
+        // Answer once per session, so the peer learns our identity key too
+        if !state.announced_peers.contains(&from) {
+            effects.push(Self::announce_packet(state, from)?);
+        }
+        if let Some(nostr_public_key) = payload.nostr_public_key {
+            state
+                .identities
+                .set_nostr_public_key(&fingerprint, Some(nostr_public_key))?;
+            debug!("Bound Nostr identity for peer {}", from);
+            effects.push(Effect::BindNostrIdentity {
+                peer_id: from,
+                public_key: nostr_public_key,
+            });
+        }
+        #[cfg(feature = "experimental")]
+        {
+            let (mut invited_effects, mut invited_events) = Self::flush_group_invites(state, from);
+            effects.append(&mut invited_effects);
+            app_events.append(&mut invited_events);
+        }
+
+        Ok((effects, app_events))
+    }
+
+    /// Record our Nostr public key and advertise it to every peer we have a session with
+    pub fn handle_local_nostr_identity(
+        state: &mut CoreState,
+        public_key: [u8; 32],
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        if state.nostr_public_key == Some(public_key) {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        state.nostr_public_key = Some(public_key);
+
+        let established: Vec<PeerId> = state
+            .session_manager
+            .sessions()
+            .filter(|(_, session)| session.is_established())
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+        let effects = established
+            .into_iter()
+            .map(|peer_id| Self::announce_packet(state, peer_id))
+            .collect::<BitchatResult<Vec<_>>>()?;
+
+        Ok((effects, Vec::new()))
+    }
+
+    /// Handle set nickname command
+    ///
+    /// The mesh hears the new nickname from our next broadcast announce, which
+    /// goes out as soon as the announce schedule allows. Peers we reach over
+    /// other transports get the announce over their established sessions. The
+    /// change is confirmed with `NicknameChanged`; a nickname we can't use is
+    /// refused with `SystemError`.
+    pub fn handle_set_nickname(
+        state: &mut CoreState,
+        nickname: String,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let nickname = nickname.trim().to_string();
+        let refusal = if nickname.is_empty() {
+            Some("Nickname must not be empty".to_string())
+        } else if nickname.len() > MAX_NICKNAME_LEN {
+            Some(format!(
+                "Nickname must be at most {} bytes",
+                MAX_NICKNAME_LEN
+            ))
+        } else {
+            None
+        };
+        if let Some(error) = refusal {
+            return Ok((Vec::new(), vec![AppEvent::SystemError { error }]));
+        }
+        if state.nickname == nickname {
+            return Ok((Vec::new(), vec![AppEvent::NicknameChanged { nickname }]));
+        }
+        state.nickname = nickname.clone();
+        state.announce_schedule.announce_soon();
+
+        let off_mesh: Vec<PeerId> = state
+            .session_manager
+            .sessions()
+            .filter(|(_, session)| session.is_established())
+            .map(|(peer_id, _)| *peer_id)
+            .filter(|peer_id| state.transport_for(peer_id) != ChannelTransportType::Ble)
+            .collect();
+        let effects = off_mesh
+            .into_iter()
+            .map(|peer_id| Self::announce_packet(state, peer_id))
+            .collect::<BitchatResult<Vec<_>>>()?;
+        debug!("Nickname changed to '{}'", nickname);
+
+        Ok((effects, vec![AppEvent::NicknameChanged { nickname }]))
+    }
+
+    /// Build a signed announce carrying our identity and Nostr public keys for a peer
+    pub(super) fn announce_packet(state: &mut CoreState, peer_id: PeerId) -> BitchatResult<Effect> {
+        let packet = Self::signed_announce(state)?;
+        state.announced_peers.insert(peer_id);
+
+        Ok(Effect::SendBitchatPacket {
+            peer_id,
+            packet,
+            transport: state.transport_for(&peer_id),
+        })
+    }
+
+    /// Sign an announce of our nickname, identity key and Nostr public key
+    fn signed_announce(state: &CoreState) -> BitchatResult<BitchatPacket> {
+        let mut payload = AnnouncePayload::new(
+            state.nickname.clone(),
+            state.session_manager.local_public_key(),
+            state.identity_key.public_key_bytes(),
+            None,
+        )?;
+        if let Some(nostr_public_key) = state.nostr_public_key {
+            payload = payload.with_nostr_public_key(nostr_public_key);
+        }
+
+        BitchatPacket::create_announce_from_payload(
+            state.peer_id,
+            &payload,
+            &state.identity_key,
+            SystemTimeSource.now(),
+        )
+    }
+
+    /// Broadcast our announce to the mesh when the announce schedule says so
+    pub fn handle_announce_tick(
+        state: &mut CoreState,
+        now: Timestamp,
+    ) -> BitchatResult<Vec<Effect>> {
+        let neighbours = state.mesh_neighbours();
+        if !state
+            .announce_schedule
+            .poll(now, neighbours, &mut rand_core::OsRng)
+        {
+            return Ok(Vec::new());
+        }
+
+        let packet = Self::signed_announce(state)?;
+        Ok(Self::mesh_broadcast(state, packet))
+    }
+
+    /// Flood a packet on every mesh transport we have neighbours on
+    pub(super) fn mesh_broadcast(state: &CoreState, packet: BitchatPacket) -> Vec<Effect> {
+        state
+            .mesh_transports()
+            .into_iter()
+            .map(|transport| Effect::BroadcastBitchatPacket {
+                packet: packet.clone(),
+                transport,
+            })
+            .collect()
+    }
+
+    /// Handle a public message flooded through the mesh
+    ///
+    /// Messages must be signed by the sender's announced key; those from senders
+    /// we have no announce for yet are held until it arrives. The same message
+    /// arriving over several paths is only reported once.
+    fn handle_public_message(
+        state: &mut CoreState,
+        packet: BitchatPacket,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let from = packet.sender_id;
+        if from == state.peer_id {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let message = packet.parse_public_message()?;
+        let Some(entry) = state.peers.get(&from) else {
+            Self::hold_broadcast(state, packet);
+            return Ok((Vec::new(), Vec::new()));
+        };
+        packet.verify_signature(&entry.peer.signing_public_key)?;
+        let nickname = entry.peer.nickname.clone();
+
+        // The ID is derived from the signed content, never taken from the sender
+        let stored = ContentAddressedMessage::from_metadata(
+            from,
+            None,
+            message.content.clone(),
+            0,
+            message.timestamp.as_millis(),
+            None,
+        )?
+        .with_reply_to(Self::replied_to(&message))
+        .with_mentions(message.mentions.unwrap_or_default());
+        if !state.message_store.store_message(stored.clone())? {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        state.stats.messages_received += 1;
+
+        let mut app_events = vec![AppEvent::BroadcastReceived {
+            message_id: stored.id,
+            from,
+            nickname,
+            content: message.content,
+            timestamp: stored.timestamp,
+        }];
+        app_events.extend(Self::highlight(state, &stored));
+        Ok((Vec::new(), app_events))
+    }
+
+    /// Keep a public message from an unannounced sender until its announce arrives
+    fn hold_broadcast(state: &mut CoreState, packet: BitchatPacket) {
+        let from = packet.sender_id;
+        if !state.held_broadcasts.contains_key(&from)
+            && state.held_broadcasts.len() >= MAX_HELD_BROADCAST_SENDERS
+        {
+            debug!("Dropping public message from unannounced peer {}", from);
+            return;
+        }
+        let held = state.held_broadcasts.entry(from).or_default();
+        if held.len() >= MAX_HELD_BROADCASTS_PER_PEER {
+            held.remove(0);
+        }
+        held.push((SystemTimeSource.now(), packet));
+        debug!("Holding public message from unannounced peer {}", from);
+    }
+
+    /// Deliver the public messages held for a peer that has just announced
+    fn release_held_broadcasts(state: &mut CoreState, peer_id: PeerId) -> Vec<AppEvent> {
+        let mut app_events = Vec::new();
+        for (_, packet) in state.held_broadcasts.remove(&peer_id).unwrap_or_default() {
+            match Self::handle_public_message(state, packet) {
+                Ok((_, mut events)) => app_events.append(&mut events),
+                Err(e) => debug!("Dropping held public message from {}: {}", peer_id, e),
+            }
+        }
+        app_events
+    }
+
+    /// Highlight a received message that mentions us or replies to one of ours
+    fn highlight(state: &CoreState, message: &ContentAddressedMessage) -> Option<AppEvent> {
+        let mentioned = message.mentions.contains(&state.nickname);
+        let replies_to_us = message
+            .reply_to
+            .and_then(|parent| state.message_store.get_message(&parent))
+            .is_some_and(|parent| parent.sender == state.peer_id);
+        if !mentioned && !replies_to_us {
+            return None;
+        }
+
+        Some(AppEvent::MessageHighlighted {
+            message_id: message.id,
+            from: message.sender,
+            content: message.content.clone(),
+            reply_to: message.reply_to,
+            mentioned,
+            timestamp: message.timestamp,
+        })
+    }
+
+    /// ID of the message a received message replies to, if it is content-addressed
+    fn replied_to(message: &BitchatMessage) -> Option<MessageId> {
+        message
+            .reply_to
+            .as_deref()
+            .and_then(|id| MessageId::from_hex(id).ok())
+    }
+
+    /// Find a stored message by ID or unique ID prefix
+    fn resolve_message(
+        state: &CoreState,
+        message_id: &str,
+    ) -> Result<ContentAddressedMessage, String> {
+        match state.message_store.find_by_id_prefix(message_id).as_slice() {
+            [message] => Ok((*message).clone()),
+            [] => Err(format!("No message {}", message_id)),
+            _ => Err(format!("Message ID {} is ambiguous", message_id)),
+        }
+    }
+
+    /// Handle a leave packet, forgetting the peer if it is signed by its announced key
+    ///
+    /// Only peers whose signing key was confirmed over a Noise session can leave,
+    /// and only with a leave signed after their latest announce.
+    fn handle_leave(
+        state: &mut CoreState,
+        packet: BitchatPacket,
+        transport: ChannelTransportType,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let from = packet.sender_id;
+        let Some(entry) = state.peers.get(&from) else {
+            debug!("Ignoring leave from unannounced peer {}", from);
+            return Ok((Vec::new(), Vec::new()));
+        };
+        packet.verify_leave(&entry.peer.signing_public_key)?;
+        if !state
+            .peers
+            .accepts_leave(&from, packet.header.timestamp, SystemTimeSource.now())
+        {
+            debug!("Ignoring unauthenticated or stale leave from peer {}", from);
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        state.peers.remove(&from);
+        state.announced_peers.remove(&from);
+        if state.peer_transports.get(&from) == Some(&transport) {
+            state.peer_transports.remove(&from);
+        }
+        debug!("Peer {} left", from);
+
+        let app_events = vec![AppEvent::PeerStatusChanged {
+            peer_id: from,
+            status: ConnectionStatus::Disconnected,
+            transport: Some(transport),
+        }];
+
+        Ok((Vec::new(), app_events))
+    }
+
+    /// Handle list peers command
+    pub fn handle_list_peers(state: &CoreState) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let peers = state.peers.list();
+        Ok((Vec::new(), vec![AppEvent::PeerListReport { peers }]))
+    }
+
+    /// Forget announced peers that have gone quiet
+    ///
+    /// Public messages from senders that never announced are dropped too, and so
+    /// are private messages to peers we never completed a handshake with. Each
+    /// of those peers is reported with a `SystemError`.
+    pub fn handle_peer_tick(state: &mut CoreState, now: Timestamp) -> Vec<AppEvent> {
+        for peer_id in state.peers.evict_stale(now) {
+            debug!("Evicted stale peer {}", peer_id);
+        }
+        let cutoff = now.as_millis().saturating_sub(HELD_BROADCAST_TTL_MS);
+        state.held_broadcasts.retain(|_, held| {
+            held.retain(|(received, _)| received.as_millis() >= cutoff);
+            !held.is_empty()
+        });
+
+        let mut app_events = Vec::new();
+        let cutoff = now.as_millis().saturating_sub(PENDING_MESSAGE_TTL_MS);
+        state.pending_messages.retain(|peer_id, pending| {
+            let queued = pending.len();
+            pending.retain(|(queued_at, _, _)| queued_at.as_millis() >= cutoff);
+            let expired = queued - pending.len();
+            if expired > 0 {
+                warn!(
+                    "Dropping {} queued messages to {}: no Noise session",
+                    expired, peer_id
+                );
+                app_events.push(AppEvent::SystemError {
+                    error: format!(
+                        "{} queued messages to {} were not sent: the handshake never completed",
+                        expired, peer_id
+                    ),
+                });
+            }
+            !pending.is_empty()
+        });
+        app_events
+    }
+
+    /// Handle set favorite command
+    ///
+    /// The peer is notified over its Noise session; without one the change is
+    /// queued and a handshake is started.
+    pub fn handle_set_favorite(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        favorite: bool,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let session_established = state
+            .session_manager
+            .get_session(&peer_id)
+            .is_some_and(|session| session.is_established());
+        if !session_established {
+            debug!(
+                "No established session with peer {}, queueing favorite change",
+                peer_id
+            );
+            state.pending_favorites.insert(peer_id, favorite);
+            let effects = Self::initiate_handshake(state, peer_id)?;
+            return Ok((effects, Vec::new()));
+        }
+
+        let (effect, app_event) = Self::apply_favorite(state, peer_id, favorite)?;
+        Ok((vec![effect], vec![app_event]))
+    }
+
+    /// Handle list favorites command
+    pub fn handle_list_favorites(state: &CoreState) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let mut favorites: Vec<FavoriteStatus> = state
+            .peer_fingerprints
+            .keys()
+            .filter_map(|peer_id| Self::favorite_status(state, *peer_id))
+            .filter(|status| status.favorite || status.favorited_us)
+            .collect();
+        favorites.sort_by_key(|status| status.peer_id);
+
+        Ok((Vec::new(), vec![AppEvent::FavoritesReport { favorites }]))
+    }
+
+    /// Record a favorite change and build the notification for the peer
+    ///
+    /// Peers that negotiated signed favorite notifications get one; anyone else
+    /// gets the canonical private message.
+    fn apply_favorite(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        favorite: bool,
+    ) -> BitchatResult<(Effect, AppEvent)> {
+        let fingerprint = state
+            .peer_fingerprints
+            .get(&peer_id)
+            .cloned()
+            .ok_or_else(|| {
+                bitchat_core::BitchatError::Session(
+                    bitchat_core::internal::SessionError::SessionNotFound {
+                        peer_id: peer_id.to_string(),
+                    },
+                )
+            })?;
+
+        let payload = Self::favorite_payload(state, peer_id, favorite)?;
+        let effect = Self::encrypted_packet(state, peer_id, &payload)?;
+        state.identities.set_favorite(&fingerprint, favorite)?;
+
+        let status = Self::favorite_status(state, peer_id).ok_or_else(|| {
+            bitchat_core::BitchatError::invalid_packet("Favorite peer has no identity")
+        })?;
+        Ok((effect, AppEvent::FavoriteStatusChanged { status }))
+    }
+
+    /// Noise payload telling a peer we favorited or unfavorited it
+    fn favorite_payload(
+        state: &mut CoreState,
+        peer_id: PeerId,
+        favorite: bool,
+    ) -> BitchatResult<NoisePayload> {
+        #[cfg(feature = "experimental")]
+        if state.peer_accepts(&peer_id, NoisePayloadType::FavoriteNotification) {
+            let notification = FavoriteNotification::new_signed(
+                favorite,
+                peer_id,
+                state.nostr_public_key,
+                &state.identity_key,
+            );
+            return Ok(NoisePayload::new(
+                notification.payload_type(),
+                notification.to_binary()?,
+            ));
+        }
+
+        let notice = CanonicalFavorite {
+            favorite,
+            nostr_public_key: state.nostr_public_key,
+        };
+        let message = ContentAddressedMessage::from_metadata(
+            state.peer_id,
+            Some(peer_id),
+            notice.to_content(),
+            0,
+            SystemTimeSource.now().as_millis(),
+            None,
+        )?;
+        let body =
+            BitchatMessage::new(message.id.to_hex(), state.nickname.clone(), message.content)
+                .to_binary()?;
+        Ok(NoisePayload::new(NoisePayloadType::PrivateMessage, body))
+    }
+
+    /// Handle a signed favorite notification received over a peer's Noise session
+    ///
+    /// The notification must be fresh, newer than the last one from the peer, and
+    /// signed by the identity key the peer announced over this session.
+    #[cfg(feature = "experimental")]
+    fn handle_favorite_notification(
+        state: &mut CoreState,
+        from: PeerId,
+        notification: FavoriteNotification,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let Some(fingerprint) = state.peer_fingerprints.get(&from).cloned() else {
+            warn!(
+                "Dropping favorite notification from unauthenticated peer {}",
+                from
+            );
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let session_signing_key = state
+            .identities
+            .get_cryptographic_identity(&fingerprint)
+            .and_then(|identity| identity.signing_public_key);
+        if session_signing_key != Some(notification.signing_public_key) {
+            warn!(
+                "Dropping favorite notification from peer {} not signed by its announced key",
+                from
+            );
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let last_seen = state.identities.favorite_notified_at(&fingerprint);
+        notification.verify(state.peer_id, last_seen)?;
+
+        Self::record_favorite(
+            state,
+            from,
+            &fingerprint,
+            notification.favorite,
+            notification.nostr_public_key,
+            Some(notification.timestamp),
+        )
+    }
+
+    /// Handle a canonical favorite notice received over a peer's Noise session
+    ///
+    /// The notice is unsigned; the established session is what authenticates it.
+    fn handle_canonical_favorite(
+        state: &mut CoreState,
+        from: PeerId,
+        notice: CanonicalFavorite,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        let Some(fingerprint) = state.peer_fingerprints.get(&from).cloned() else {
+            warn!(
+                "Dropping favorite notice from unauthenticated peer {}",
+                from
+            );
+            return Ok((Vec::new(), Vec::new()));
+        };
+        Self::record_favorite(
+            state,
+            from,
+            &fingerprint,
+            notice.favorite,
+            notice.nostr_public_key,
+            None,
+        )
+    }
+
+    /// Record that a peer favorited or unfavorited us and bind its Nostr key
+    ///
+    /// The Nostr key is bound like an announced one, since the Noise session
+    /// already authenticates the sender.
+    fn record_favorite(
+        state: &mut CoreState,
+        from: PeerId,
+        fingerprint: &Fingerprint,
+        favorite: bool,
+        nostr_public_key: Option<[u8; 32]>,
+        notified_at: Option<Timestamp>,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        state
+            .identities
+            .set_favorited_us(fingerprint, favorite, notified_at)?;
+
+        let mut effects = Vec::new();
+        if let Some(public_key) = nostr_public_key {
+            state
+                .identities
+                .set_nostr_public_key(fingerprint, Some(public_key))?;
+            effects.push(Effect::BindNostrIdentity {
+                peer_id: from,
+                public_key,
+            });
+        }
+
+        let app_events = Self::favorite_status(state, from)
+            .map(|status| AppEvent::FavoriteStatusChanged { status })
+            .into_iter()
+            .collect();
+        Ok((effects, app_events))
+    }
+
+    /// Favorite relationship with a peer we have authenticated
+    fn favorite_status(state: &CoreState, peer_id: PeerId) -> Option<FavoriteStatus> {
+        let fingerprint = state.peer_fingerprints.get(&peer_id)?;
+        let social = state.identities.get_social_identity(fingerprint);
+        Some(FavoriteStatus {
+            peer_id,
+            favorite: social.is_some_and(|social| social.is_favorite),
+            favorited_us: social.is_some_and(|social| social.favorited_us),
+            nostr_public_key: social.and_then(|social| social.nostr_public_key),
+        })
+    }
+
+    /// Flush messages queued while the session with a peer was being established
+    ///
+    /// Peers also learn our Nostr public key here, once the session can vouch for
+    /// the Noise key in our announce.
+    fn handle_session_established(
+        state: &mut CoreState,
+        peer_id: PeerId,
+    ) -> (Vec<Effect>, Vec<AppEvent>) {
+        debug!("Noise session established with peer {}", peer_id);
+        if let Some(fingerprint) = state
+            .session_manager
+            .get_session(&peer_id)
+            .and_then(|session| session.peer_fingerprint())
+        {
+            state.peer_fingerprints.insert(peer_id, fingerprint.clone());
+        }
+
+        let mut effects = Vec::new();
+        let mut app_events = Vec::new();
+        #[cfg(feature = "experimental")]
+        match Self::capability_hello(state, peer_id) {
+            Ok(effect) => effects.push(effect),
+            Err(e) => warn!("Failed to send version hello to peer {}: {}", peer_id, e),
+        }
+        // Announce over every new session, so the peer can trust our signing key;
+        // it may also be a restarted peer that forgot our announce
+        state.announced_peers.remove(&peer_id);
+        match Self::announce_packet(state, peer_id) {
+            Ok(effect) => effects.push(effect),
+            Err(e) => warn!("Failed to announce to peer {}: {}", peer_id, e),
+        }
+        if let Some(favorite) = state.pending_favorites.remove(&peer_id) {
+            match Self::apply_favorite(state, peer_id, favorite) {
+                Ok((effect, app_event)) => {
+                    effects.push(effect);
+                    app_events.push(app_event);
+                }
+                Err(e) => app_events.push(AppEvent::SystemError {
+                    error: format!("Failed to favorite {}: {}", peer_id, e),
+                }),
+            }
+        }
+        for (_, content, reply_to) in state.pending_messages.remove(&peer_id).unwrap_or_default() {
+            match Self::encrypt_private_message(state, peer_id, content, reply_to) {
+                Ok((effect, app_event)) => {
+                    effects.push(effect);
+                    app_events.push(app_event);
+                }
+                Err(e) => app_events.push(AppEvent::SystemError {
+                    error: format!("Failed to send queued message to {}: {}", peer_id, e),
+                }),
+            }
+        }
+
+        (effects, app_events)
+    }
+
+    /// Start capability negotiation with a peer whose session was just established
+    ///
+    /// Anything negotiated over an earlier session is forgotten, since the peer may
+    /// have restarted with a different build. Both sides send a hello, so
+    /// negotiation completes even if one of them is lost.
+    #[cfg(feature = "experimental")]
+    fn capability_hello(state: &mut CoreState, peer_id: PeerId) -> BitchatResult<Effect> {
+        state.capabilities.remove_peer(&peer_id);
+        let hello = CapabilityMessage::Hello(state.capabilities.create_hello()?);
+        let effect = Self::encrypted_packet(state, peer_id, &hello.to_noise_payload()?)?;
+        state.capabilities.track_hello_sent(peer_id);
+        Ok(effect)
+    }
+
+    /// Handle a capability negotiation message from a peer
+    ///
+    /// A hello is answered with the mutually supported capabilities, or rejected when
+    /// no protocol version is compatible; the peer then gets core payloads only.
+    #[cfg(feature = "experimental")]
+    fn handle_capability_message(
+        state: &mut CoreState,
+        from: PeerId,
+        message: CapabilityMessage,
+    ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
+        if message.peer_id() != from {
+            warn!(
+                "Dropping capability message from peer {} claiming to be from {}",
+                from,
+                message.peer_id()
+            );
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let mut effects = Vec::new();
+        match message {
+            CapabilityMessage::Hello(hello) => {
+                let reply = match state.capabilities.process_hello(&hello) {
+                    Ok(ack) => CapabilityMessage::Ack(ack),
+                    Err(e) => {
+                        warn!("Capability negotiation with peer {} failed: {}", from, e);
+                        state.capabilities.mark_as_legacy_peer(from);
+                        CapabilityMessage::Rejection(CapabilityRejection::new(
+                            state.peer_id,
+                            RejectionReason::IncompatibleVersion,
+                            Some(e.to_string()),
+                        ))
+                    }
+                };
+                effects.push(Self::encrypted_packet(
+                    state,
+                    from,
+                    &reply.to_noise_payload()?,
+                )?);
+            }
+            CapabilityMessage::Ack(ack) => state.capabilities.process_ack(&ack)?,
+            CapabilityMessage::Rejection(rejection) => {
+                warn!(
+                    "Peer {} rejected capability negotiation: {:?}",
+                    from, rejection.reason
+                );
+                state.capabilities.mark_as_legacy_peer(from);
+            }
+        }
+
+        debug!(
+            "Capability negotiation with peer {}: {:?}",
+            from,
+            state.capabilities.get_negotiation_status(&from)
+        );
+        Ok((effects, Vec::new()))
+    }
+
+    /// Store and encrypt a private message for a peer with an established session
+    fn encrypt_private_message(
+        state: &mut CoreState,
+        recipient: PeerId,
+        content: String,
+        reply_to: Option<MessageId>,
+    ) -> BitchatResult<(Effect, AppEvent)> {
+        // Private messages have no sequence, so the recipient derives the same ID
+        let message = ContentAddressedMessage::from_metadata(
+            state.peer_id,
+            Some(recipient),
+            content.clone(),
+            0,
+            SystemTimeSource.now().as_millis(),
+            None,
+        )?;
+
+        // Canonical apps read the reply flag as a channel name
+        #[cfg(feature = "experimental")]
+        let wire_reply_to = reply_to.filter(|_| {
+            state.peer_uses(&recipient, &bitchat_core::CapabilityId::message_replies())
+        });
+        #[cfg(not(feature = "experimental"))]
+        let wire_reply_to = None;
+        let body = Self::outgoing_message(state, &message, wire_reply_to);
+        let message = message
+            .with_reply_to(reply_to)
+            .with_mentions(body.mentions.clone().unwrap_or_default());
+        let body = body.to_binary()?;
+        let payload = NoisePayload::new(NoisePayloadType::PrivateMessage, body);
+        let effect = Self::encrypted_packet(state, recipient, &payload)?;
+
+        // Store message
+        state.message_store.store_message(message.clone())?;
+        #[cfg(feature = "experimental")]
+        Self::remember_private_message(state, &message);
+        state.stats.messages_sent += 1;
+
+        let app_event = AppEvent::MessageSent {
+            to: recipient,
+            content,
+            timestamp: message.timestamp,
+        };
+
+        Ok((effect, app_event))
+    }
+
+    /// Build the wire message for a stored outgoing message
+    ///
+    /// Mentions are taken from the `@nickname` words in the content.
+    fn outgoing_message(
+        state: &CoreState,
+        message: &ContentAddressedMessage,
+        reply_to: Option<MessageId>,
+    ) -> BitchatMessage {
+        let mut body = BitchatMessage::new(
+            message.id.to_hex(),
+            state.nickname.clone(),
+            message.content.clone(),
+        )
+        .with_mentions_from_content();
+        body.timestamp = Timestamp::new(message.timestamp);
+        match reply_to {
+            Some(reply_to) => body.with_reply_to(reply_to.to_hex()),
+            None => body,
+        }
+    }
+
+    /// Encrypt a Noise payload for a peer with an established session
+    ///
+    /// Experimental payloads are refused for peers that have not negotiated them.
+    pub(super) fn encrypted_packet(
+        state: &mut CoreState,
+        recipient: PeerId,
+        payload: &NoisePayload,
+    ) -> BitchatResult<Effect> {
+        #[cfg(feature = "experimental")]
+        if !state.peer_accepts(&recipient, payload.payload_type) {
+            return Err(bitchat_core::internal::TransportError::ProtocolMismatch {
+                expected: format!("support for {:?} payloads", payload.payload_type),
+                actual: format!(
+                    "{:?} peer {}",
+                    state.capabilities.get_negotiation_status(&recipient),
+                    recipient
+                ),
+            }
+            .into());
+        }
+
+        let session = state
+            .session_manager
+            .get_session_mut(&recipient)
+            .ok_or_else(|| {
+                bitchat_core::BitchatError::Session(
+                    bitchat_core::internal::SessionError::SessionNotFound {
+                        peer_id: recipient.to_string(),
+                    },
+                )
+            })?;
+        let ciphertext = session.encrypt(&payload.to_binary(), &SystemTimeSource)?;
+        if ciphertext.len() <= MAX_PAYLOAD_SIZE_V1 {
+            return Self::directed_packet(
+                state,
+                recipient,
+                MessageType::NoiseEncrypted,
+                ciphertext,
+            );
+        }
+
+        // Larger payloads need v2 packets. Only peers that negotiated capabilities are
+        // known to parse them; a legacy peer just drops the VersionHello and times out.
+        #[cfg(feature = "experimental")]
+        if CapabilityMessage::is_negotiation_payload(payload.payload_type)
+            || bitchat_core::CapabilityId::required_for(payload.payload_type).is_some()
+        {
+            return Self::versioned_packet(
+                state,
+                recipient,
+                MessageType::NoiseEncrypted,
+                ciphertext,
+                PROTOCOL_VERSION_2,
+            );
+        }
+
+        Err(bitchat_core::BitchatError::invalid_packet(
+            "Message too long for a single packet",
+        ))
+    }
+
+    /// Build a send effect for a packet addressed to a single peer
+    fn directed_packet(
+        state: &CoreState,
+        recipient: PeerId,
+        message_type: MessageType,
+        payload: Vec<u8>,
+    ) -> BitchatResult<Effect> {
+        Self::versioned_packet(
+            state,
+            recipient,
+            message_type,
+            payload,
+            CURRENT_PROTOCOL_VERSION,
+        )
+    }
+
+    /// Build a send effect for a directed packet of a specific protocol version
+    fn versioned_packet(
+        state: &CoreState,
+        recipient: PeerId,
+        message_type: MessageType,
+        payload: Vec<u8>,
+        version: u8,
+    ) -> BitchatResult<Effect> {
+        let packet = BitchatPacket::new(
+            message_type,
+            state.peer_id,
+            Some(recipient),
+            SystemTimeSource.now(),
+            payload,
+            PacketFlags::NONE,
+        )?
+        .with_version(version);
+
+        Ok(Effect::SendBitchatPacket {
+            peer_id: recipient,
+            packet,
+            transport: state.transport_for(&recipient),
+        })
+    }
+
     /// Apply a state transition and update internal state
     pub async fn apply_state_transition(state: &mut CoreState, transition: StateTransition) {
         let peer_id = transition.new_state.peer_id();
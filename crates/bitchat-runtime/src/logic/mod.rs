@@ -3,6 +3,9 @@
 //! This module contains the core logic task implementation split into focused components:
 //! - `state`: Core application state and statistics
 //! - `handlers`: Command and event handlers
+//! - `file_transfer`: File transfer handlers
+//! - `groups`: Group messaging handlers
+//! - `device_linking`: Device linking and history sync handlers
 //! - `task`: Main CoreLogicTask implementation and coordination
 //!
 //! ## Architecture Design Trade-offs
@@ -54,10 +57,15 @@
 //! Keep the current single-task design until measurements prove it's a bottleneck.
 //! The correctness benefits far outweigh hypothetical performance concerns for most use cases.
 
+mod device_linking;
+mod file_transfer;
+mod groups;
 pub mod handlers;
 pub mod state;
 pub mod task;
 
 pub use handlers::CommandHandlers;
+#[cfg(feature = "experimental")]
+pub use state::OutgoingTransfer;
 pub use state::{CoreState, CoreStats, LoggerWrapper, SystemTimeSource};
 pub use task::CoreLogicTask;
@@ -2,15 +2,32 @@
 //!
 //! Contains the core application state, statistics, and logger wrapper.
 
+#[cfg(feature = "experimental")]
+use crate::managers::{default_partial_dir, StreamingFileTransfers};
 use crate::managers::{DeliveryTracker, NoiseSessionManager, SessionTimeouts};
 use bitchat_core::{
     internal::{
-        AuditEntry, ConnectionState, ConsoleLogger, DeliveryConfig, LogLevel, MessageStore,
-        NoOpLogger, SessionConfig, TaskId, TaskLogger, TimeSource, Timestamp,
+        AuditEntry, ConnectionState, ConsoleLogger, DeliveryConfig, IdentityKeyPair, LogLevel,
+        MessageId, MessageStore, NoOpLogger, SecureStorage, SessionConfig, StorageConfig, TaskId,
+        TaskLogger, TimeSource, Timestamp,
     },
-    AppEvent, BitchatResult, Command, Effect, Event, PeerId,
+    protocol::{AnnounceSchedule, PeerDirectory},
+    AppEvent, BitchatPacket, BitchatResult, ChannelTransportType, Command, Effect, Event,
+    Fingerprint, PeerId, SecureIdentityStateManager,
 };
-use std::collections::HashMap;
+#[cfg(feature = "experimental")]
+use bitchat_core::{
+    CapabilityId, CapabilityManager, DeviceId, DeviceInfo, DeviceLinker, DeviceType, FileOffer,
+    FileTransferId, GroupId, GroupManager, MultiDeviceSessionManager, NoisePayload,
+    NoisePayloadType,
+};
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "experimental")]
+use std::collections::VecDeque;
+#[cfg(feature = "experimental")]
+use std::ops::Range;
+#[cfg(feature = "experimental")]
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 // ----------------------------------------------------------------------------
@@ -21,6 +38,16 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub struct CoreState {
     /// Our peer identity
     pub peer_id: PeerId,
+    /// Nickname we announce ourselves with
+    pub nickname: String,
+    /// Ed25519 key announces are signed with
+    pub identity_key: IdentityKeyPair,
+    /// Our Nostr public key, once the Nostr transport has reported it
+    pub nostr_public_key: Option<[u8; 32]>,
+    /// Cryptographic and social identities of known peers
+    pub identities: SecureIdentityStateManager,
+    /// Noise fingerprint each peer authenticated with, kept after its session ends
+    pub peer_fingerprints: HashMap<PeerId, Fingerprint>,
     /// Session manager for handling cryptographic sessions
     pub session_manager: NoiseSessionManager<SystemTimeSource>,
     /// Delivery tracker for message reliability
@@ -29,6 +56,73 @@ pub struct CoreState {
     pub message_store: MessageStore,
     /// Connection states for each peer
     pub connections: HashMap<PeerId, ConnectionState>,
+    /// Transport each peer was last heard on (includes multi-hop mesh peers)
+    pub peer_transports: HashMap<PeerId, ChannelTransportType>,
+    /// Peers known from their announces on any transport
+    pub peers: PeerDirectory,
+    /// Public messages from senders we have no announce for yet, with when they
+    /// arrived, held until the sender's signing key is known
+    pub held_broadcasts: HashMap<PeerId, Vec<(Timestamp, BitchatPacket)>>,
+    /// Messages waiting for a Noise session with their recipient, with when they
+    /// were queued and the message each replies to
+    pub pending_messages: HashMap<PeerId, Vec<(Timestamp, String, Option<MessageId>)>>,
+    /// Favorite changes waiting for a Noise session with the peer
+    pub pending_favorites: HashMap<PeerId, bool>,
+    /// Peers we have announced ourselves to over their current session
+    pub announced_peers: HashSet<PeerId>,
+    /// When to next broadcast our announce to the mesh
+    pub announce_schedule: AnnounceSchedule,
+    /// Capabilities negotiated with each peer after its Noise handshake
+    #[cfg(feature = "experimental")]
+    pub capabilities: CapabilityManager,
+    /// Disk-backed file transfers in either direction
+    #[cfg(feature = "experimental")]
+    pub file_transfers: StreamingFileTransfers,
+    /// File offers waiting for capability negotiation with their recipient
+    #[cfg(feature = "experimental")]
+    pub pending_file_offers: HashMap<PeerId, Vec<FileOffer>>,
+    /// Accepted outgoing transfers with chunks left to send, in round-robin order
+    #[cfg(feature = "experimental")]
+    pub outgoing_transfers: VecDeque<OutgoingTransfer>,
+    /// Earliest time each transport may carry the next file chunk
+    #[cfg(feature = "experimental")]
+    pub chunk_slots: HashMap<ChannelTransportType, Timestamp>,
+    /// When each stalled transfer last asked for a resume or a new session
+    #[cfg(feature = "experimental")]
+    pub resume_attempts: HashMap<FileTransferId, Timestamp>,
+    /// Directory accepted files are saved to unless the accept names another
+    #[cfg(feature = "experimental")]
+    pub download_dir: PathBuf,
+    /// When finished and expired transfers were last cleaned up
+    #[cfg(feature = "experimental")]
+    pub last_transfer_cleanup: Timestamp,
+    /// When incoming transfer progress was last saved to disk
+    #[cfg(feature = "experimental")]
+    pub last_transfer_save: Timestamp,
+    /// Groups we are in or were invited to
+    #[cfg(feature = "experimental")]
+    pub groups: GroupManager,
+    /// Group payloads waiting for a Noise session and capability negotiation
+    #[cfg(feature = "experimental")]
+    pub pending_group_payloads: HashMap<PeerId, Vec<NoisePayload>>,
+    /// Group invitations waiting for the invitee to announce its identity key
+    #[cfg(feature = "experimental")]
+    pub pending_group_invites: HashMap<PeerId, Vec<GroupId>>,
+    /// Devices linked to our identity and the private history they share
+    #[cfg(feature = "experimental")]
+    pub devices: MultiDeviceSessionManager,
+    /// Device link offers we have shown or scanned
+    #[cfg(feature = "experimental")]
+    pub device_linker: DeviceLinker,
+    /// Peer ID each linked device is reached at
+    #[cfg(feature = "experimental")]
+    pub device_peers: HashMap<DeviceId, PeerId>,
+    /// Device payloads waiting for a Noise session and capability negotiation
+    #[cfg(feature = "experimental")]
+    pub pending_device_payloads: HashMap<PeerId, Vec<NoisePayload>>,
+    /// When linked devices were last sent heartbeats and sync requests
+    #[cfg(feature = "experimental")]
+    pub last_device_sync: Timestamp,
     /// Audit trail for state transitions
     pub audit_trail: Vec<AuditEntry>,
     /// Sequence counter for message ordering
@@ -50,25 +144,233 @@ impl CoreState {
 
         // Generate or load cryptographic keys (simplified for now)
         let noise_key = bitchat_core::internal::NoiseKeyPair::generate();
+        #[cfg(feature = "experimental")]
+        let local_device = DeviceInfo::new(
+            DeviceId::generate(),
+            peer_id.to_string(),
+            DeviceType::Unknown,
+            noise_key.fingerprint(),
+        );
         let timeouts = SessionTimeouts {
             handshake_timeout: session_config.handshake_timeout,
             idle_timeout: session_config.idle_timeout,
         };
         let session_manager = NoiseSessionManager::new(noise_key, time_source, timeouts);
         let delivery_tracker = DeliveryTracker::with_config(delivery_config, SystemTimeSource);
+        let identity_key = IdentityKeyPair::generate()?;
+        #[cfg(feature = "experimental")]
+        let groups = GroupManager::new(peer_id, &identity_key);
 
         Ok(Self {
             peer_id,
+            nickname: peer_id.to_string(),
+            identity_key,
+            nostr_public_key: None,
+            identities: SecureIdentityStateManager::new()?,
+            peer_fingerprints: HashMap::new(),
             session_manager,
             delivery_tracker,
             message_store: MessageStore::new(),
             connections: HashMap::new(),
+            peer_transports: HashMap::new(),
+            peers: PeerDirectory::default(),
+            held_broadcasts: HashMap::new(),
+            pending_messages: HashMap::new(),
+            pending_favorites: HashMap::new(),
+            announced_peers: HashSet::new(),
+            announce_schedule: AnnounceSchedule::default(),
+            #[cfg(feature = "experimental")]
+            capabilities: CapabilityManager::new(peer_id)?,
+            #[cfg(feature = "experimental")]
+            file_transfers: StreamingFileTransfers::new(
+                peer_id,
+                default_partial_dir(Path::new("."), peer_id),
+            ),
+            #[cfg(feature = "experimental")]
+            pending_file_offers: HashMap::new(),
+            #[cfg(feature = "experimental")]
+            outgoing_transfers: VecDeque::new(),
+            #[cfg(feature = "experimental")]
+            chunk_slots: HashMap::new(),
+            #[cfg(feature = "experimental")]
+            resume_attempts: HashMap::new(),
+            #[cfg(feature = "experimental")]
+            download_dir: PathBuf::from("."),
+            #[cfg(feature = "experimental")]
+            last_transfer_cleanup: SystemTimeSource.now(),
+            #[cfg(feature = "experimental")]
+            last_transfer_save: SystemTimeSource.now(),
+            #[cfg(feature = "experimental")]
+            groups,
+            #[cfg(feature = "experimental")]
+            pending_group_payloads: HashMap::new(),
+            #[cfg(feature = "experimental")]
+            pending_group_invites: HashMap::new(),
+            #[cfg(feature = "experimental")]
+            devices: MultiDeviceSessionManager::new(local_device),
+            #[cfg(feature = "experimental")]
+            device_linker: DeviceLinker::new(),
+            #[cfg(feature = "experimental")]
+            device_peers: HashMap::new(),
+            #[cfg(feature = "experimental")]
+            pending_device_payloads: HashMap::new(),
+            #[cfg(feature = "experimental")]
+            last_device_sync: SystemTimeSource.now(),
             audit_trail: Vec::new(),
             message_sequence: 0,
             start_time: SystemTimeSource.now(),
             stats: CoreStats::default(),
         })
     }
+
+    /// Transport to use when sending to a peer
+    ///
+    /// Prefers the transport of an established connection, then the mesh transport
+    /// the peer was last heard on. Mutual favorites that are out of mesh range are
+    /// reached over Nostr; anyone else falls back to the transport they were last
+    /// heard on, or BLE for peers we know nothing about.
+    pub fn transport_for(&self, peer_id: &PeerId) -> ChannelTransportType {
+        if let Some(ConnectionState::Connected(conn)) = self.connections.get(peer_id) {
+            return conn.transport;
+        }
+        match self.peer_transports.get(peer_id) {
+            Some(&transport) if transport != ChannelTransportType::Nostr => transport,
+            _ if self.reachable_over_nostr(peer_id) => ChannelTransportType::Nostr,
+            last_heard => last_heard.copied().unwrap_or(ChannelTransportType::Ble),
+        }
+    }
+
+    /// Transports that flood packets through the local mesh
+    ///
+    /// Every transport other than Nostr that we have a connected neighbour on, or
+    /// BLE while we have no links yet.
+    pub fn mesh_transports(&self) -> Vec<ChannelTransportType> {
+        let mut transports = Vec::new();
+        for connection in self.connections.values() {
+            if let ConnectionState::Connected(conn) = connection {
+                if conn.transport != ChannelTransportType::Nostr
+                    && !transports.contains(&conn.transport)
+                {
+                    transports.push(conn.transport);
+                }
+            }
+        }
+        if transports.is_empty() {
+            transports.push(ChannelTransportType::Ble);
+        }
+        transports
+    }
+
+    /// Number of neighbours connected over mesh transports
+    pub fn mesh_neighbours(&self) -> usize {
+        self.connections
+            .values()
+            .filter(|connection| {
+                matches!(connection, ConnectionState::Connected(conn)
+                    if conn.transport != ChannelTransportType::Nostr)
+            })
+            .count()
+    }
+
+    /// Whether the peer is a mutual favorite with a bound Nostr key
+    pub fn reachable_over_nostr(&self, peer_id: &PeerId) -> bool {
+        self.peer_fingerprints
+            .get(peer_id)
+            .is_some_and(|fingerprint| {
+                self.identities.is_mutual_favorite(fingerprint)
+                    && self.identities.nostr_public_key(fingerprint).is_some()
+            })
+    }
+
+    /// Save accepted files to `download_dir` and keep part files in `partial_dir`
+    ///
+    /// Part files default to a directory under the download directory, so that
+    /// finished files are renamed into place rather than copied.
+    #[cfg(feature = "experimental")]
+    pub fn set_file_transfer_dirs(&mut self, download_dir: PathBuf, partial_dir: Option<PathBuf>) {
+        let partial_dir =
+            partial_dir.unwrap_or_else(|| default_partial_dir(&download_dir, self.peer_id));
+        self.file_transfers = StreamingFileTransfers::new(self.peer_id, partial_dir);
+        self.download_dir = download_dir;
+    }
+
+    /// Keep known peer identities and our identity key in the given storage
+    ///
+    /// Adopts the identity key already in the storage, or stores the one we
+    /// generated if the storage has none yet.
+    pub fn set_identity_storage(&mut self, storage: Box<dyn SecureStorage>) -> BitchatResult<()> {
+        self.identities =
+            SecureIdentityStateManager::with_storage(storage, StorageConfig::default())?;
+        match self.identities.local_identity_key()? {
+            Some(bytes) => self.use_identity_key(IdentityKeyPair::from_bytes(&bytes)?),
+            None => self
+                .identities
+                .store_local_identity_key(&self.identity_key.private_key_bytes())?,
+        }
+        Ok(())
+    }
+
+    /// Sign as a different identity and keep its key for the next run
+    pub fn set_identity_key(&mut self, identity_key: IdentityKeyPair) -> BitchatResult<()> {
+        self.identities
+            .store_local_identity_key(&identity_key.private_key_bytes())?;
+        self.use_identity_key(identity_key);
+        Ok(())
+    }
+
+    fn use_identity_key(&mut self, identity_key: IdentityKeyPair) {
+        #[cfg(feature = "experimental")]
+        self.groups.set_identity_key(&identity_key);
+        self.identity_key = identity_key;
+    }
+
+    /// Whether a peer can be sent a Noise payload of the given type
+    ///
+    /// Experimental payloads are only sent to peers that negotiated the matching
+    /// capability. Peers that never answered our VersionHello are treated as the
+    /// canonical implementation and only get core payloads.
+    #[cfg(feature = "experimental")]
+    pub fn peer_accepts(&mut self, peer_id: &PeerId, payload_type: NoisePayloadType) -> bool {
+        CapabilityId::required_for(payload_type)
+            .is_none_or(|capability| self.peer_uses(peer_id, &capability))
+    }
+
+    /// Whether a peer negotiated a capability, so we may use it with that peer
+    #[cfg(feature = "experimental")]
+    pub fn peer_uses(&mut self, peer_id: &PeerId, capability: &CapabilityId) -> bool {
+        self.capabilities.check_hello_timeouts();
+        self.capabilities.should_use_feature(peer_id, capability)
+    }
+}
+
+/// Accepted outgoing file transfer and the chunks left to send
+#[cfg(feature = "experimental")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingTransfer {
+    /// Transfer ID
+    pub transfer_id: FileTransferId,
+    /// Peer receiving the file
+    pub recipient: PeerId,
+    /// Chunk indices still to send, as half-open ranges in sending order
+    pub pending: VecDeque<Range<u32>>,
+}
+
+#[cfg(feature = "experimental")]
+impl OutgoingTransfer {
+    /// Take the next chunk index to send
+    pub fn next_chunk(&mut self) -> Option<u32> {
+        let range = self.pending.front_mut()?;
+        let index = range.next();
+        if range.start == range.end {
+            self.pending.pop_front();
+        }
+        index
+    }
+
+    /// Whether every queued chunk has been taken
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
 }
 
 /// Logger wrapper for object safety
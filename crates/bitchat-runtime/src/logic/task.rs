@@ -6,10 +6,12 @@ use super::handlers::CommandHandlers;
 use super::state::{CoreState, CoreStats, LoggerWrapper, SystemTimeSource};
 use crate::rate_limiter::RateLimiter;
 use bitchat_core::{
+    config::{BleTransportConfig, TimingConfig},
     internal::{
-        AppEventSender, CommandReceiver, EffectSender, EventReceiver, LogLevel, TaskId, TimeSource,
-        TransportError,
+        AppEventSender, CommandReceiver, EffectSender, EventReceiver, LogLevel, SecureStorage,
+        TaskId, TimeSource, TransportError,
     },
+    protocol::{AnnounceSchedule, PeerDirectory},
     AppEvent, BitchatError, BitchatResult, Command, Effect, Event, PeerId,
 };
 
@@ -21,6 +23,10 @@ cfg_if::cfg_if! {
     }
 }
 
+/// How often the Core Logic task runs periodic work; also the resolution of file
+/// transfer chunk pacing
+const MAINTENANCE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
 // ----------------------------------------------------------------------------
 // Core Logic Task
 // ----------------------------------------------------------------------------
@@ -80,6 +86,35 @@ impl CoreLogicTask {
         })
     }
 
+    /// Evict announced peers per the given timing configuration
+    pub fn with_timing_config(mut self, timing: &TimingConfig) -> Self {
+        self.state.peers = PeerDirectory::new(timing);
+        self
+    }
+
+    /// Schedule our announce broadcasts per the given BLE configuration
+    pub fn with_announce_config(mut self, ble: &BleTransportConfig) -> Self {
+        self.state.announce_schedule = AnnounceSchedule::new(ble);
+        self
+    }
+
+    /// Keep our identity key and known peer identities in `storage` across runs
+    pub fn with_identity_storage(mut self, storage: Box<dyn SecureStorage>) -> BitchatResult<Self> {
+        self.state.set_identity_storage(storage)?;
+        Ok(self)
+    }
+
+    /// Save accepted files to `download_dir` and keep part files in `partial_dir`
+    #[cfg(feature = "experimental")]
+    pub fn with_file_transfer_dirs(
+        mut self,
+        download_dir: std::path::PathBuf,
+        partial_dir: Option<std::path::PathBuf>,
+    ) -> Self {
+        self.state.set_file_transfer_dirs(download_dir, partial_dir);
+        self
+    }
+
     /// Run the main Core Logic task loop
     #[cfg(feature = "std")]
     pub async fn run(&mut self) -> BitchatResult<()> {
@@ -89,6 +124,19 @@ impl CoreLogicTask {
             "Core Logic task starting",
         );
 
+        #[cfg(feature = "experimental")]
+        match CommandHandlers::handle_restore_transfers(&mut self.state).await {
+            Ok((_, app_events)) => {
+                for app_event in app_events {
+                    self.send_app_event(app_event).await?;
+                }
+            }
+            Err(e) => warn!("Failed to restore file transfers: {}", e),
+        }
+
+        let mut maintenance = tokio::time::interval(MAINTENANCE_INTERVAL);
+        maintenance.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
         while self.running {
             tokio::select! {
                 // Process command from UI or external systems
@@ -146,6 +194,8 @@ impl CoreLogicTask {
                                 Event::TransportHealthCheckCompleted { transport_type, .. } => *transport_type,
                                 Event::TransportMetricsUpdated { transport_type, .. } => *transport_type,
                                 Event::TransportFailoverOccurred { from_transport, .. } => *from_transport,
+                                Event::RelayStatusUpdated { .. } => bitchat_core::ChannelTransportType::Nostr,
+                                Event::LocalNostrIdentity { .. } => bitchat_core::ChannelTransportType::Nostr,
                             };
 
                             self.logger.log_receive_event(
@@ -188,9 +238,29 @@ impl CoreLogicTask {
                         }
                     }
                 }
+
+                // Periodic work not driven by a command or event
+                _ = maintenance.tick() => {
+                    if let Err(e) = self.run_maintenance().await {
+                        match e {
+                            BitchatError::Channel { .. } => {
+                                error!("Unrecoverable error during maintenance, shutting down CoreLogicTask: {}", e);
+                                self.running = false;
+                                break;
+                            },
+                            _ => {
+                                error!("Error during maintenance: {}", e);
+                            }
+                        }
+                    }
+                }
             }
         }
 
+        if let Err(e) = self.state.identities.flush() {
+            warn!("Failed to save identities: {}", e);
+        }
+
         self.logger
             .log_task_event(TaskId::CoreLogic, LogLevel::Info, "Core Logic task stopped");
 
@@ -210,6 +280,16 @@ impl CoreLogicTask {
             Command::SendMessage { recipient, content } => {
                 CommandHandlers::handle_send_message(&mut self.state, recipient, content).await?
             }
+            Command::SendBroadcast { content } => {
+                CommandHandlers::handle_send_broadcast(&mut self.state, content)?
+            }
+            Command::ReplyToMessage {
+                message_id,
+                content,
+            } => {
+                CommandHandlers::handle_reply_to_message(&mut self.state, message_id, content)
+                    .await?
+            }
             Command::ConnectToPeer { peer_id } => {
                 CommandHandlers::handle_connect_to_peer(&mut self.state, peer_id).await?
             }
@@ -240,9 +320,85 @@ impl CoreLogicTask {
                 self.handle_query_delivery_status(peer_id).await?
             }
             Command::QueryInternalState => self.handle_query_internal_state().await?,
+            Command::SetFavorite { peer_id, favorite } => {
+                CommandHandlers::handle_set_favorite(&mut self.state, peer_id, favorite)?
+            }
+            Command::ListFavorites => CommandHandlers::handle_list_favorites(&self.state)?,
+            Command::ListPeers => CommandHandlers::handle_list_peers(&self.state)?,
+            Command::SetNickname { nickname } => {
+                CommandHandlers::handle_set_nickname(&mut self.state, nickname)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::SendFile { recipient, path } => {
+                CommandHandlers::handle_send_file(&mut self.state, recipient, path).await?
+            }
+            #[cfg(feature = "experimental")]
+            Command::AcceptFile {
+                transfer_id,
+                directory,
+            } => {
+                CommandHandlers::handle_accept_file(&mut self.state, transfer_id, directory).await?
+            }
+            #[cfg(feature = "experimental")]
+            Command::RejectFile { transfer_id } => {
+                CommandHandlers::handle_reject_file(&mut self.state, transfer_id).await?
+            }
+            #[cfg(feature = "experimental")]
+            Command::ListTransfers => CommandHandlers::handle_list_transfers(&self.state)?,
+            #[cfg(not(feature = "experimental"))]
+            Command::SendFile { .. }
+            | Command::AcceptFile { .. }
+            | Command::RejectFile { .. }
+            | Command::ListTransfers => CommandHandlers::handle_file_transfer_unavailable()?,
+            #[cfg(feature = "experimental")]
+            Command::CreateGroup { name, description } => {
+                CommandHandlers::handle_create_group(&mut self.state, name, description)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::InviteToGroup { group_id, peer_id } => {
+                CommandHandlers::handle_invite_to_group(&mut self.state, group_id, peer_id)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::JoinGroup { group_id } => {
+                CommandHandlers::handle_join_group(&mut self.state, group_id)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::LeaveGroup { group_id } => {
+                CommandHandlers::handle_leave_group(&mut self.state, group_id)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::KickFromGroup { group_id, peer_id } => {
+                CommandHandlers::handle_kick_from_group(&mut self.state, group_id, peer_id)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::SendGroupMessage { group_id, content } => {
+                CommandHandlers::handle_send_group_message(&mut self.state, group_id, content)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::ListGroups => CommandHandlers::handle_list_groups(&self.state)?,
+            #[cfg(not(feature = "experimental"))]
+            Command::CreateGroup { .. }
+            | Command::InviteToGroup { .. }
+            | Command::JoinGroup { .. }
+            | Command::LeaveGroup { .. }
+            | Command::KickFromGroup { .. }
+            | Command::SendGroupMessage { .. }
+            | Command::ListGroups => CommandHandlers::handle_group_messaging_unavailable()?,
+            #[cfg(feature = "experimental")]
+            Command::LinkDevice => CommandHandlers::handle_link_device(&mut self.state)?,
+            #[cfg(feature = "experimental")]
+            Command::AcceptDeviceLink { uri } => {
+                CommandHandlers::handle_accept_device_link(&mut self.state, uri)?
+            }
+            #[cfg(feature = "experimental")]
+            Command::ListDevices => CommandHandlers::handle_list_devices(&self.state)?,
+            #[cfg(not(feature = "experimental"))]
+            Command::LinkDevice | Command::AcceptDeviceLink { .. } | Command::ListDevices => {
+                CommandHandlers::handle_device_linking_unavailable()?
+            }
             Command::Shutdown => {
                 self.running = false;
-                CommandHandlers::handle_shutdown().await?
+                CommandHandlers::handle_shutdown(&self.state).await?
             }
         };
 
@@ -259,6 +415,39 @@ impl CoreLogicTask {
         Ok(())
     }
 
+    /// Run periodic work such as announcing ourselves, evicting stale peers and
+    /// pacing file transfer chunks
+    #[cfg(feature = "std")]
+    async fn run_maintenance(&mut self) -> BitchatResult<()> {
+        let now = SystemTimeSource.now();
+        for app_event in CommandHandlers::handle_peer_tick(&mut self.state, now) {
+            self.send_app_event(app_event).await?;
+        }
+        for effect in CommandHandlers::handle_announce_tick(&mut self.state, now)? {
+            self.send_effect(effect).await?;
+        }
+
+        #[cfg(feature = "experimental")]
+        {
+            let (mut effects, app_events) =
+                CommandHandlers::handle_transfer_tick(&mut self.state, SystemTimeSource.now())
+                    .await;
+            effects.append(&mut CommandHandlers::handle_group_tick(&mut self.state));
+            effects.append(&mut CommandHandlers::handle_device_tick(
+                &mut self.state,
+                SystemTimeSource.now(),
+            ));
+            for effect in effects {
+                self.send_effect(effect).await?;
+            }
+            for app_event in app_events {
+                self.send_app_event(app_event).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Process an event from transport tasks
     async fn process_event(&mut self, event: Event) -> BitchatResult<()> {
         self.state.stats.events_processed += 1;
@@ -347,6 +536,13 @@ impl CoreLogicTask {
                 tracing::info!(?from_transport, ?to_transport, reason, timestamp, "Transport failover occurred");
                 (Vec::new(), Vec::new())
             }
+            Event::RelayStatusUpdated { relays, timestamp } => {
+                tracing::debug!(relays = relays.len(), timestamp, "Relay status updated");
+                (Vec::new(), vec![AppEvent::RelayStatusReport { relays }])
+            }
+            Event::LocalNostrIdentity { public_key } => {
+                CommandHandlers::handle_local_nostr_identity(&mut self.state, public_key)?
+            }
         };
 
         // Send effects to transport tasks
@@ -380,6 +576,8 @@ impl CoreLogicTask {
             Effect::RequestTransportHealthCheck { transport_type, .. } => *transport_type,
             Effect::UpdateTransportMetrics { transport_type, .. } => *transport_type,
             Effect::SwitchPrimaryTransport { from_transport, .. } => *from_transport,
+            Effect::BindNostrIdentity { .. } => bitchat_core::ChannelTransportType::Nostr,
+            Effect::BindLinkIdentity { transport, .. } => *transport,
         };
 
         self.logger.log_send_effect(
@@ -514,14 +712,17 @@ impl CoreLogicTask {
         peer_id: PeerId,
     ) -> BitchatResult<(Vec<Effect>, Vec<AppEvent>)> {
         use bitchat_core::channel::communication::{AppEvent, EncryptionStatus, PeerSessionState};
+        use bitchat_core::internal::SessionState;
 
         // Query session manager for peer session state
-        let session_state = if let Some(_session) = self.state.session_manager.get_session(&peer_id)
-        {
-            // For now, assume sessions are established if they exist
-            PeerSessionState::Established
-        } else {
-            PeerSessionState::None
+        let session_state = match self.state.session_manager.get_session(&peer_id) {
+            Some(session) => match session.state() {
+                SessionState::Handshaking => PeerSessionState::Establishing,
+                SessionState::Established => PeerSessionState::Established,
+                SessionState::Failed => PeerSessionState::Failed,
+                SessionState::Rekeying => PeerSessionState::Rekeying,
+            },
+            None => PeerSessionState::None,
         };
 
         let encryption_status = if session_state == PeerSessionState::Established {
@@ -0,0 +1,1031 @@
+//! Streaming file transfers for the BitChat runtime
+//!
+//! This module wraps the core `FileTransferManager` with disk-backed sources and
+//! sinks so that files of hundreds of megabytes never have to fit in memory.
+//! Outgoing files are hashed in a single streaming pass and then read chunk by
+//! chunk on demand. Incoming chunks are written straight into a preallocated part
+//! file, and the assembled file is only moved into place once its hash matches
+//! the offer. Part files live in a directory beside the downloads by default, so
+//! the move is a rename on the same filesystem.
+//!
+//! Every accepted transfer also keeps a small record next to its part files, so
+//! a restarted process can pick up where it left off and ask the peer for only
+//! the chunks it is still missing.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use bitchat_core::{
+    protocol::file_transfer::MAX_CHUNK_SIZE, BitchatError, BitchatResult, FileAccept, FileChunk,
+    FileComplete, FileHash, FileHasher, FileMetadata, FileOffer, FileResume, FileTransferId,
+    FileTransferManager, FileTransferSession, PeerId, TransferStatus,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
+use uuid::Uuid;
+
+/// Buffer size used when hashing or spooling files
+const IO_BUFFER_SIZE: usize = 4 * MAX_CHUNK_SIZE;
+
+/// Extension of the saved state of a resumable transfer
+const RECORD_EXTENSION: &str = "transfer";
+
+/// Directory under the download directory that part files default to
+pub const PARTIAL_DIR_NAME: &str = ".bitchat-partial";
+
+/// Default directory for a peer's spooled and partially received files
+///
+/// Each peer gets its own subdirectory, so nodes sharing a download directory
+/// never restore each other's transfers.
+pub fn default_partial_dir(download_dir: &Path, peer_id: PeerId) -> PathBuf {
+    download_dir
+        .join(PARTIAL_DIR_NAME)
+        .join(peer_id.to_string())
+}
+
+// ----------------------------------------------------------------------------
+// Transfer Files
+// ----------------------------------------------------------------------------
+
+/// Local file an outgoing transfer reads its chunks from
+#[derive(Debug)]
+struct OutgoingFile {
+    path: PathBuf,
+    file: File,
+    /// Whether the file is a spool we created and must delete afterwards
+    spooled: bool,
+}
+
+/// Part file an incoming transfer writes its chunks into
+#[derive(Debug)]
+struct IncomingFile {
+    path: PathBuf,
+    file: File,
+    /// Directory the finished file is moved into
+    download_dir: PathBuf,
+}
+
+/// Saved state of an accepted transfer, enough to reopen its file after a restart
+#[derive(Debug, Serialize, Deserialize)]
+struct TransferRecord {
+    session: FileTransferSession,
+    /// File chunks are read from (outgoing) or written into (incoming)
+    path: PathBuf,
+    /// Whether an outgoing file is a spool we must delete afterwards
+    spooled: bool,
+    /// Directory an incoming file is moved into once complete
+    download_dir: Option<PathBuf>,
+}
+
+// ----------------------------------------------------------------------------
+// Streaming File Transfers
+// ----------------------------------------------------------------------------
+
+/// Disk-backed file transfers built on the core `FileTransferManager`
+///
+/// The core manager tracks session state and which chunks have been sent or
+/// received; this type owns the files behind each session.
+#[derive(Debug)]
+pub struct StreamingFileTransfers {
+    /// Session state for every transfer
+    manager: FileTransferManager,
+    /// Our peer ID, the recipient of incoming offers
+    local_peer_id: PeerId,
+    /// Directory for spooled and partially received files
+    partial_dir: PathBuf,
+    /// Files we are sending
+    outgoing: HashMap<FileTransferId, OutgoingFile>,
+    /// Files we are receiving
+    incoming: HashMap<FileTransferId, IncomingFile>,
+    /// Incoming transfers that received chunks since their record was last saved
+    unsaved: HashSet<FileTransferId>,
+}
+
+impl StreamingFileTransfers {
+    /// Create a transfer store keeping its part files in `partial_dir`
+    pub fn new(local_peer_id: PeerId, partial_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            manager: FileTransferManager::new(),
+            local_peer_id,
+            partial_dir: partial_dir.into(),
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+            unsaved: HashSet::new(),
+        }
+    }
+
+    /// Offer a file on disk to a peer
+    ///
+    /// The file is hashed in one streaming pass; chunks are read from it later as
+    /// they are sent, so it must stay in place until the transfer finishes.
+    pub async fn offer_file(
+        &mut self,
+        path: &Path,
+        recipient: PeerId,
+        mime_type: Option<String>,
+        description: Option<String>,
+    ) -> BitchatResult<FileOffer> {
+        let filename = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| BitchatError::invalid_packet("File path has no file name"))?
+            .to_string();
+        let mut file = File::open(path).await.map_err(io_error)?;
+        let (size, hash) = hash_file(&mut file).await?;
+
+        let metadata = FileMetadata::with_hash(filename, size, mime_type, hash);
+        let source = OutgoingFile {
+            path: path.to_path_buf(),
+            file,
+            spooled: false,
+        };
+        self.start_offer(metadata, description, recipient, source)
+    }
+
+    /// Offer the contents of a reader to a peer
+    ///
+    /// The reader is spooled to a part file while it is hashed, since chunks may
+    /// need to be read again in any order.
+    pub async fn offer_reader<R: AsyncRead + Unpin>(
+        &mut self,
+        mut reader: R,
+        filename: String,
+        recipient: PeerId,
+        mime_type: Option<String>,
+        description: Option<String>,
+    ) -> BitchatResult<FileOffer> {
+        fs::create_dir_all(&self.partial_dir)
+            .await
+            .map_err(io_error)?;
+        let path = self.partial_dir.join(format!("{}.out", Uuid::new_v4()));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+            .map_err(io_error)?;
+
+        let spooled = async {
+            let mut hasher = FileHasher::new();
+            let mut buffer = vec![0u8; IO_BUFFER_SIZE];
+            loop {
+                let read = reader.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+                file.write_all(&buffer[..read]).await?;
+            }
+            file.flush().await?;
+            Ok::<_, std::io::Error>(hasher)
+        }
+        .await;
+        let hasher = match spooled {
+            Ok(hasher) => hasher,
+            Err(e) => {
+                let _ = fs::remove_file(&path).await;
+                return Err(io_error(e));
+            }
+        };
+
+        let metadata = FileMetadata::with_hash(
+            filename,
+            hasher.bytes_hashed(),
+            mime_type,
+            hasher.finalize(),
+        );
+        let source = OutgoingFile {
+            path: path.clone(),
+            file,
+            spooled: true,
+        };
+        let offer = self.start_offer(metadata, description, recipient, source);
+        if offer.is_err() {
+            let _ = fs::remove_file(&path).await;
+        }
+        offer
+    }
+
+    fn start_offer(
+        &mut self,
+        metadata: FileMetadata,
+        description: Option<String>,
+        recipient: PeerId,
+        source: OutgoingFile,
+    ) -> BitchatResult<FileOffer> {
+        let offer = FileOffer::new(metadata, description)?;
+        self.manager
+            .start_transfer(&offer, self.local_peer_id, recipient)?;
+        self.outgoing.insert(offer.transfer_id.clone(), source);
+        Ok(offer)
+    }
+
+    /// Handle the recipient's answer to one of our offers
+    ///
+    /// Accepted transfers are saved so they can be resumed after a restart.
+    pub async fn process_accept(&mut self, accept: &FileAccept) -> BitchatResult<()> {
+        if !self.outgoing.contains_key(&accept.transfer_id) {
+            return Err(BitchatError::invalid_packet("Transfer not found"));
+        }
+        self.manager.process_accept(accept)?;
+        if !accept.accepted {
+            self.release_outgoing(&accept.transfer_id).await;
+            return Ok(());
+        }
+        self.save_record(&accept.transfer_id).await
+    }
+
+    /// Rewind an outgoing transfer to the chunks the recipient says it is missing
+    pub fn process_resume(&mut self, resume: &FileResume) -> BitchatResult<()> {
+        if !self.outgoing.contains_key(&resume.transfer_id) {
+            return Err(BitchatError::invalid_packet("Transfer not found"));
+        }
+        self.manager.process_resume(resume)
+    }
+
+    /// Read a chunk of an accepted outgoing transfer and mark it as sent
+    pub async fn read_chunk(
+        &mut self,
+        transfer_id: &FileTransferId,
+        chunk_index: u32,
+    ) -> BitchatResult<FileChunk> {
+        let session = self
+            .manager
+            .get_session_mut(transfer_id)
+            .filter(|session| session.status == TransferStatus::InProgress)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not in progress"))?;
+        let source = self
+            .outgoing
+            .get_mut(transfer_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not found"))?;
+        let len = session
+            .metadata
+            .chunk_len(chunk_index)
+            .ok_or_else(|| BitchatError::invalid_packet("Chunk index out of range"))?;
+
+        let mut data = vec![0u8; len];
+        source
+            .file
+            .seek(SeekFrom::Start(session.metadata.chunk_offset(chunk_index)))
+            .await
+            .map_err(io_error)?;
+        source.file.read_exact(&mut data).await.map_err(io_error)?;
+
+        let chunk = FileChunk::new(transfer_id.clone(), chunk_index, session.total_chunks, data)?;
+        session.chunks_received[chunk_index as usize] = true;
+        Ok(chunk)
+    }
+
+    /// Record the recipient's completion notice for one of our outgoing transfers
+    pub async fn process_complete(&mut self, complete: &FileComplete) -> BitchatResult<()> {
+        if !self.outgoing.contains_key(&complete.transfer_id) {
+            return Err(BitchatError::invalid_packet("Transfer not found"));
+        }
+        let result = self.manager.process_complete(complete);
+        self.release_outgoing(&complete.transfer_id).await;
+        result
+    }
+
+    /// Register an offer received from a peer
+    pub fn receive_offer(&mut self, offer: &FileOffer, sender: PeerId) -> BitchatResult<()> {
+        offer.metadata.validate()?;
+        self.manager
+            .receive_offer(offer, sender, self.local_peer_id)?;
+        Ok(())
+    }
+
+    /// Accept an incoming offer, preallocating the part file its chunks go into
+    ///
+    /// The finished file will be moved into `download_dir`.
+    pub async fn accept(
+        &mut self,
+        transfer_id: &FileTransferId,
+        download_dir: PathBuf,
+    ) -> BitchatResult<FileAccept> {
+        let session = self
+            .manager
+            .get_session(transfer_id)
+            .filter(|session| {
+                session.status == TransferStatus::Offered && session.sender != self.local_peer_id
+            })
+            .ok_or_else(|| BitchatError::invalid_packet("No pending offer for transfer"))?;
+
+        fs::create_dir_all(&self.partial_dir)
+            .await
+            .map_err(io_error)?;
+        let path = self.partial_dir.join(format!("{}.part", Uuid::new_v4()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+            .map_err(io_error)?;
+        if let Err(e) = file.set_len(session.metadata.size).await {
+            let _ = fs::remove_file(&path).await;
+            return Err(io_error(e));
+        }
+
+        let accept = FileAccept::accept(transfer_id.clone());
+        self.manager.process_accept(&accept)?;
+        self.incoming.insert(
+            transfer_id.clone(),
+            IncomingFile {
+                path,
+                file,
+                download_dir,
+            },
+        );
+        if let Err(e) = self.save_record(transfer_id).await {
+            let _ = self.cancel(transfer_id).await;
+            return Err(e);
+        }
+        Ok(accept)
+    }
+
+    /// Reject an incoming offer
+    pub fn reject(
+        &mut self,
+        transfer_id: &FileTransferId,
+        reason: Option<String>,
+    ) -> BitchatResult<FileAccept> {
+        if !self
+            .manager
+            .get_session(transfer_id)
+            .is_some_and(|session| session.status == TransferStatus::Offered)
+        {
+            return Err(BitchatError::invalid_packet(
+                "No pending offer for transfer",
+            ));
+        }
+        let reject = FileAccept::reject(transfer_id.clone(), reason);
+        self.manager.process_accept(&reject)?;
+        Ok(reject)
+    }
+
+    /// Write a received chunk into its transfer's part file
+    ///
+    /// The chunk is only marked as received once it is written, so the missing
+    /// chunk list never claims data that is not on disk.
+    pub async fn receive_chunk(&mut self, chunk: &FileChunk) -> BitchatResult<()> {
+        let sink = self
+            .incoming
+            .get_mut(&chunk.transfer_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not found"))?;
+        let session = self
+            .manager
+            .get_session(&chunk.transfer_id)
+            .filter(|session| session.status == TransferStatus::InProgress)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not in progress"))?;
+        if session.metadata.chunk_len(chunk.chunk_index) != Some(chunk.data.len()) {
+            return Err(BitchatError::invalid_packet("Chunk length mismatch"));
+        }
+        if !chunk.verify() {
+            return Err(BitchatError::invalid_packet("Chunk verification failed"));
+        }
+
+        sink.file
+            .seek(SeekFrom::Start(
+                session.metadata.chunk_offset(chunk.chunk_index),
+            ))
+            .await
+            .map_err(io_error)?;
+        sink.file.write_all(&chunk.data).await.map_err(io_error)?;
+
+        self.manager.process_chunk(chunk)?;
+        self.unsaved.insert(chunk.transfer_id.clone());
+        Ok(())
+    }
+
+    /// Verify a fully received file and move it into its download directory
+    ///
+    /// The file keeps the offered name, stripped of any directories and made
+    /// unique within the download directory. A download directory on another
+    /// filesystem than the part file gets a copy instead of a rename. Returns where the file was saved and
+    /// the completion notice for the sender.
+    pub async fn finish(
+        &mut self,
+        transfer_id: &FileTransferId,
+    ) -> BitchatResult<(PathBuf, FileComplete)> {
+        if !self
+            .manager
+            .get_session(transfer_id)
+            .is_some_and(FileTransferSession::is_fully_received)
+        {
+            return Err(BitchatError::invalid_packet("Not all chunks received"));
+        }
+        let mut sink = self
+            .incoming
+            .remove(transfer_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not found"))?;
+        self.remove_record(transfer_id).await;
+
+        let hash = match hash_file(&mut sink.file).await {
+            Ok((_, hash)) => hash,
+            Err(e) => {
+                let _ = fs::remove_file(&sink.path).await;
+                return Err(e);
+            }
+        };
+        let session = self
+            .manager
+            .get_session_mut(transfer_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not found"))?;
+        if let Err(e) = session.complete(&hash) {
+            let _ = fs::remove_file(&sink.path).await;
+            return Err(e);
+        }
+        let filename = safe_filename(&session.metadata.filename);
+        drop(sink.file);
+
+        fs::create_dir_all(&sink.download_dir)
+            .await
+            .map_err(io_error)?;
+        let destination = unique_destination(&sink.download_dir, &filename).await?;
+        move_file(&sink.path, &destination)
+            .await
+            .map_err(io_error)?;
+
+        Ok((
+            destination,
+            FileComplete::success(transfer_id.clone(), hash),
+        ))
+    }
+
+    /// Cancel a transfer in either direction and delete its part files
+    pub async fn cancel(&mut self, transfer_id: &FileTransferId) -> BitchatResult<()> {
+        self.manager.cancel_transfer(transfer_id)?;
+        self.release_outgoing(transfer_id).await;
+        self.release_incoming(transfer_id).await;
+        Ok(())
+    }
+
+    /// Get a transfer session by ID
+    pub fn session(&self, transfer_id: &FileTransferId) -> Option<&FileTransferSession> {
+        self.manager.get_session(transfer_id)
+    }
+
+    /// All transfer sessions in either direction
+    pub fn sessions(&self) -> Vec<&FileTransferSession> {
+        self.manager.active_sessions()
+    }
+
+    /// Chunk indices of an incoming transfer that have not been received yet
+    pub fn missing_chunks(&self, transfer_id: &FileTransferId) -> Option<Vec<u32>> {
+        self.incoming
+            .contains_key(transfer_id)
+            .then(|| self.manager.get_session(transfer_id))
+            .flatten()
+            .map(FileTransferSession::missing_chunks)
+    }
+
+    /// Request for the chunks an incoming transfer is still missing
+    ///
+    /// Returns `None` unless the transfer is in progress with chunks to go.
+    pub fn resume_request(&self, transfer_id: &FileTransferId) -> Option<FileResume> {
+        self.incoming
+            .contains_key(transfer_id)
+            .then(|| self.manager.get_session(transfer_id))
+            .flatten()
+            .filter(|session| {
+                session.status == TransferStatus::InProgress && !session.is_fully_received()
+            })
+            .map(FileTransferSession::resume_request)
+    }
+
+    /// Save the received-chunk lists of incoming transfers that changed
+    ///
+    /// Chunks are written before they are marked, so a record may lag behind the
+    /// file but never claims data that is not on disk.
+    pub async fn save_progress(&mut self) -> BitchatResult<()> {
+        let unsaved: Vec<FileTransferId> = self.unsaved.drain().collect();
+        for transfer_id in unsaved {
+            if self.incoming.contains_key(&transfer_id) {
+                self.save_record(&transfer_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reload the transfers an earlier run left in progress
+    ///
+    /// Records that can no longer be resumed, because the transfer expired or its
+    /// file is gone, are deleted along with their part files. Returns the IDs of
+    /// the restored transfers.
+    pub async fn restore(&mut self) -> BitchatResult<Vec<FileTransferId>> {
+        let mut entries = match fs::read_dir(&self.partial_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        let mut restored = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+            let path = entry.path();
+            if path.extension() != Some(OsStr::new(RECORD_EXTENSION)) {
+                continue;
+            }
+            match self.restore_record(&path).await {
+                Ok(transfer_id) => restored.push(transfer_id),
+                Err(_) => {
+                    let _ = fs::remove_file(&path).await;
+                }
+            }
+        }
+        Ok(restored)
+    }
+
+    async fn restore_record(&mut self, path: &Path) -> BitchatResult<FileTransferId> {
+        let bytes = fs::read(path).await.map_err(io_error)?;
+        let record: TransferRecord = bincode::deserialize(&bytes)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+        let session = record.session;
+        let outgoing = session.sender == self.local_peer_id;
+        let ours_to_delete = !outgoing || record.spooled;
+
+        let resumable = session.status == TransferStatus::InProgress
+            && !session.is_expired()
+            && (outgoing || session.recipient == self.local_peer_id);
+        let file = if !resumable {
+            Err(BitchatError::invalid_packet("Transfer is not resumable"))
+        } else if outgoing {
+            File::open(&record.path).await.map_err(io_error)
+        } else {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&record.path)
+                .await
+                .map_err(io_error)
+        };
+        let file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                if ours_to_delete {
+                    let _ = fs::remove_file(&record.path).await;
+                }
+                return Err(e);
+            }
+        };
+
+        let transfer_id = session.transfer_id.clone();
+        self.manager.restore_session(session)?;
+        if outgoing {
+            let source = OutgoingFile {
+                path: record.path,
+                file,
+                spooled: record.spooled,
+            };
+            self.outgoing.insert(transfer_id.clone(), source);
+        } else {
+            let sink = IncomingFile {
+                path: record.path,
+                file,
+                download_dir: record.download_dir.unwrap_or_else(|| PathBuf::from(".")),
+            };
+            self.incoming.insert(transfer_id.clone(), sink);
+        }
+        Ok(transfer_id)
+    }
+
+    /// Drop finished or expired transfers and delete the files they left behind
+    pub async fn cleanup(&mut self) {
+        self.manager.cleanup_sessions();
+        let stale_outgoing: Vec<FileTransferId> = self
+            .outgoing
+            .keys()
+            .filter(|id| self.manager.get_session(id).is_none())
+            .cloned()
+            .collect();
+        for transfer_id in stale_outgoing {
+            self.release_outgoing(&transfer_id).await;
+        }
+        let stale_incoming: Vec<FileTransferId> = self
+            .incoming
+            .keys()
+            .filter(|id| self.manager.get_session(id).is_none())
+            .cloned()
+            .collect();
+        for transfer_id in stale_incoming {
+            self.release_incoming(&transfer_id).await;
+        }
+    }
+
+    /// Stop reading an outgoing file, deleting it if it was spooled
+    async fn release_outgoing(&mut self, transfer_id: &FileTransferId) {
+        if let Some(source) = self.outgoing.remove(transfer_id) {
+            drop(source.file);
+            if source.spooled {
+                let _ = fs::remove_file(&source.path).await;
+            }
+            self.remove_record(transfer_id).await;
+        }
+    }
+
+    /// Stop receiving a file and delete what arrived of it
+    async fn release_incoming(&mut self, transfer_id: &FileTransferId) {
+        if let Some(sink) = self.incoming.remove(transfer_id) {
+            drop(sink.file);
+            let _ = fs::remove_file(&sink.path).await;
+            self.remove_record(transfer_id).await;
+        }
+    }
+
+    /// Where a transfer's record is saved
+    ///
+    /// Transfer IDs come from peers, so the file is named after a hash of the ID
+    /// rather than the ID itself.
+    fn record_path(&self, transfer_id: &FileTransferId) -> PathBuf {
+        let digest = Sha256::digest(transfer_id.as_str().as_bytes());
+        let name: String = digest[..16]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.partial_dir
+            .join(format!("{}.{}", name, RECORD_EXTENSION))
+    }
+
+    /// Save a transfer's session and file locations, replacing any earlier record
+    async fn save_record(&self, transfer_id: &FileTransferId) -> BitchatResult<()> {
+        let session = self
+            .manager
+            .get_session(transfer_id)
+            .ok_or_else(|| BitchatError::invalid_packet("Transfer not found"))?;
+        let record = if let Some(source) = self.outgoing.get(transfer_id) {
+            TransferRecord {
+                session: session.clone(),
+                path: source.path.clone(),
+                spooled: source.spooled,
+                download_dir: None,
+            }
+        } else if let Some(sink) = self.incoming.get(transfer_id) {
+            TransferRecord {
+                session: session.clone(),
+                path: sink.path.clone(),
+                spooled: false,
+                download_dir: Some(sink.download_dir.clone()),
+            }
+        } else {
+            return Err(BitchatError::invalid_packet("Transfer not found"));
+        };
+        let bytes = bincode::serialize(&record)
+            .map_err(|e| BitchatError::serialization_error_with_message(e.to_string()))?;
+
+        // Write then rename, so a crash never leaves a half-written record
+        fs::create_dir_all(&self.partial_dir)
+            .await
+            .map_err(io_error)?;
+        let path = self.record_path(transfer_id);
+        let partial = path.with_extension("tmp");
+        fs::write(&partial, bytes).await.map_err(io_error)?;
+        fs::rename(&partial, &path).await.map_err(io_error)
+    }
+
+    async fn remove_record(&mut self, transfer_id: &FileTransferId) {
+        self.unsaved.remove(transfer_id);
+        let _ = fs::remove_file(self.record_path(transfer_id)).await;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Helpers
+// ----------------------------------------------------------------------------
+
+fn io_error(error: std::io::Error) -> BitchatError {
+    BitchatError::storage_error(error.to_string())
+}
+
+/// Hash a file from its start, returning its size and hash
+async fn hash_file(file: &mut File) -> BitchatResult<(u64, FileHash)> {
+    file.flush().await.map_err(io_error)?;
+    file.seek(SeekFrom::Start(0)).await.map_err(io_error)?;
+    let mut hasher = FileHasher::new();
+    let mut buffer = vec![0u8; IO_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer).await.map_err(io_error)?;
+        if read == 0 {
+            return Ok((hasher.bytes_hashed(), hasher.finalize()));
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+/// Reduce a peer-supplied file name to a plain name without directories
+fn safe_filename(name: &str) -> String {
+    Path::new(name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.starts_with('.'))
+        .map(str::to_string)
+        .unwrap_or_else(|| "received-file".to_string())
+}
+
+/// Move a file, copying it when the destination is on another filesystem
+async fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to).await {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => copy_into_place(from, to).await,
+        result => result,
+    }
+}
+
+/// Copy a file to `to` and delete the original
+///
+/// The copy is synced under a hidden name beside the destination and then
+/// renamed, so the destination never holds a partial file.
+async fn copy_into_place(from: &Path, to: &Path) -> std::io::Result<()> {
+    let staging = to.with_file_name(format!(".{}.part", Uuid::new_v4()));
+    let copied = async {
+        fs::copy(from, &staging).await?;
+        OpenOptions::new()
+            .write(true)
+            .open(&staging)
+            .await?
+            .sync_all()
+            .await?;
+        fs::rename(&staging, to).await
+    }
+    .await;
+    if let Err(e) = copied {
+        let _ = fs::remove_file(&staging).await;
+        return Err(e);
+    }
+    fs::remove_file(from).await
+}
+
+/// Pick a path in `dir` for `filename` that does not exist yet
+async fn unique_destination(dir: &Path, filename: &str) -> BitchatResult<PathBuf> {
+    let candidate = dir.join(filename);
+    if !fs::try_exists(&candidate).await.map_err(io_error)? {
+        return Ok(candidate);
+    }
+
+    let name = Path::new(filename);
+    let stem = name
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename);
+    let extension = name.extension().and_then(|ext| ext.to_str());
+    for n in 1.. {
+        let numbered = match extension {
+            Some(extension) => format!("{} ({}).{}", stem, n, extension),
+            None => format!("{} ({})", stem, n),
+        };
+        let candidate = dir.join(numbered);
+        if !fs::try_exists(&candidate).await.map_err(io_error)? {
+            return Ok(candidate);
+        }
+    }
+    unreachable!("ran out of file names")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("bitchat-files-{}", Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sample_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_stream_file_between_peers() {
+        let dir = TestDir::new();
+        let alice = PeerId::new([1; 8]);
+        let bob = PeerId::new([2; 8]);
+        let mut sender = StreamingFileTransfers::new(alice, dir.0.join("alice"));
+        let mut receiver = StreamingFileTransfers::new(bob, dir.0.join("bob"));
+
+        let data = sample_data(3 * MAX_CHUNK_SIZE + 123);
+        std::fs::create_dir_all(&dir.0).unwrap();
+        let source = dir.0.join("report.pdf");
+        std::fs::write(&source, &data).unwrap();
+
+        let offer = sender
+            .offer_file(&source, bob, Some("application/pdf".to_string()), None)
+            .await
+            .unwrap();
+        assert_eq!(offer.metadata.filename, "report.pdf");
+        assert_eq!(offer.metadata.hash, FileHash::from_data(&data));
+        assert_eq!(offer.total_chunks(), 4);
+
+        receiver.receive_offer(&offer, alice).unwrap();
+        let downloads = dir.0.join("downloads");
+        let accept = receiver
+            .accept(&offer.transfer_id, downloads.clone())
+            .await
+            .unwrap();
+        sender.process_accept(&accept).await.unwrap();
+
+        // Chunks may arrive in any order
+        for index in [3, 1, 0] {
+            let chunk = sender.read_chunk(&offer.transfer_id, index).await.unwrap();
+            receiver.receive_chunk(&chunk).await.unwrap();
+        }
+        assert_eq!(receiver.missing_chunks(&offer.transfer_id), Some(vec![2]));
+        assert!(receiver.finish(&offer.transfer_id).await.is_err());
+
+        let chunk = sender.read_chunk(&offer.transfer_id, 2).await.unwrap();
+        receiver.receive_chunk(&chunk).await.unwrap();
+        let (saved, complete) = receiver.finish(&offer.transfer_id).await.unwrap();
+        assert_eq!(saved, downloads.join("report.pdf"));
+        assert_eq!(std::fs::read(&saved).unwrap(), data);
+        assert_eq!(
+            receiver.session(&offer.transfer_id).unwrap().status,
+            TransferStatus::Completed
+        );
+
+        sender.process_complete(&complete).await.unwrap();
+        assert_eq!(
+            sender.session(&offer.transfer_id).unwrap().status,
+            TransferStatus::Completed
+        );
+        assert!(source.exists(), "offered files are left in place");
+    }
+
+    #[tokio::test]
+    async fn test_reader_offer_is_spooled_and_names_are_sanitized() {
+        let dir = TestDir::new();
+        let alice = PeerId::new([1; 8]);
+        let bob = PeerId::new([2; 8]);
+        let mut sender = StreamingFileTransfers::new(alice, dir.0.join("alice"));
+        let mut receiver = StreamingFileTransfers::new(bob, dir.0.join("bob"));
+        let downloads = dir.0.join("downloads");
+        std::fs::create_dir_all(&downloads).unwrap();
+        std::fs::write(downloads.join("notes.txt"), b"already here").unwrap();
+
+        let data = sample_data(MAX_CHUNK_SIZE + 1);
+        let offer = sender
+            .offer_reader(
+                data.as_slice(),
+                "../../notes.txt".to_string(),
+                bob,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_dir(dir.0.join("alice")).unwrap().count(), 1);
+
+        receiver.receive_offer(&offer, alice).unwrap();
+        sender
+            .process_accept(
+                &receiver
+                    .accept(&offer.transfer_id, downloads.clone())
+                    .await
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        for index in 0..offer.total_chunks() {
+            let chunk = sender.read_chunk(&offer.transfer_id, index).await.unwrap();
+            receiver.receive_chunk(&chunk).await.unwrap();
+        }
+
+        let (saved, complete) = receiver.finish(&offer.transfer_id).await.unwrap();
+        assert_eq!(saved, downloads.join("notes (1).txt"));
+        assert_eq!(std::fs::read(&saved).unwrap(), data);
+        assert_eq!(std::fs::read_dir(dir.0.join("bob")).unwrap().count(), 0);
+
+        sender.process_complete(&complete).await.unwrap();
+        assert_eq!(std::fs::read_dir(dir.0.join("alice")).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_and_tampered_chunks() {
+        let dir = TestDir::new();
+        let alice = PeerId::new([1; 8]);
+        let bob = PeerId::new([2; 8]);
+        let mut sender = StreamingFileTransfers::new(alice, dir.0.join("alice"));
+        let mut receiver = StreamingFileTransfers::new(bob, dir.0.join("bob"));
+
+        let data = sample_data(100);
+        let offer = sender
+            .offer_reader(data.as_slice(), "a.bin".to_string(), bob, None, None)
+            .await
+            .unwrap();
+        receiver.receive_offer(&offer, alice).unwrap();
+
+        // Chunks are refused before the offer is accepted
+        let mut chunk = FileChunk::new(offer.transfer_id.clone(), 0, 1, data.clone()).unwrap();
+        assert!(receiver.receive_chunk(&chunk).await.is_err());
+
+        receiver
+            .accept(&offer.transfer_id, dir.0.join("downloads"))
+            .await
+            .unwrap();
+        chunk.data[0] ^= 0xFF;
+        assert!(receiver.receive_chunk(&chunk).await.is_err());
+        assert_eq!(receiver.missing_chunks(&offer.transfer_id), Some(vec![0]));
+
+        receiver.cancel(&offer.transfer_id).await.unwrap();
+        assert_eq!(std::fs::read_dir(dir.0.join("bob")).unwrap().count(), 0);
+
+        let reject = FileAccept::reject(offer.transfer_id.clone(), None);
+        sender.process_accept(&reject).await.unwrap();
+        assert_eq!(
+            sender.session(&offer.transfer_id).unwrap().status,
+            TransferStatus::Failed
+        );
+        assert_eq!(std::fs::read_dir(dir.0.join("alice")).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_transfers_resume_after_restart() {
+        let dir = TestDir::new();
+        let alice = PeerId::new([1; 8]);
+        let bob = PeerId::new([2; 8]);
+        let downloads = dir.0.join("downloads");
+        let mut sender = StreamingFileTransfers::new(alice, dir.0.join("alice"));
+        let mut receiver = StreamingFileTransfers::new(bob, dir.0.join("bob"));
+
+        let data = sample_data(3 * MAX_CHUNK_SIZE + 7);
+        let offer = sender
+            .offer_reader(data.as_slice(), "big.bin".to_string(), bob, None, None)
+            .await
+            .unwrap();
+        receiver.receive_offer(&offer, alice).unwrap();
+        let accept = receiver
+            .accept(&offer.transfer_id, downloads.clone())
+            .await
+            .unwrap();
+        sender.process_accept(&accept).await.unwrap();
+        for index in [0, 2] {
+            let chunk = sender.read_chunk(&offer.transfer_id, index).await.unwrap();
+            receiver.receive_chunk(&chunk).await.unwrap();
+        }
+        receiver.save_progress().await.unwrap();
+
+        // Both processes restart; only what was saved to disk survives
+        drop((sender, receiver));
+        let mut sender = StreamingFileTransfers::new(alice, dir.0.join("alice"));
+        let mut receiver = StreamingFileTransfers::new(bob, dir.0.join("bob"));
+        assert_eq!(
+            sender.restore().await.unwrap(),
+            vec![offer.transfer_id.clone()]
+        );
+        assert_eq!(
+            receiver.restore().await.unwrap(),
+            vec![offer.transfer_id.clone()]
+        );
+
+        let resume = receiver.resume_request(&offer.transfer_id).unwrap();
+        assert_eq!(resume.missing, vec![1..2, 3..4]);
+        sender.process_resume(&resume).unwrap();
+        for index in resume.chunks() {
+            let chunk = sender.read_chunk(&offer.transfer_id, index).await.unwrap();
+            receiver.receive_chunk(&chunk).await.unwrap();
+        }
+        assert!(receiver.resume_request(&offer.transfer_id).is_none());
+
+        let (saved, complete) = receiver.finish(&offer.transfer_id).await.unwrap();
+        assert_eq!(saved, downloads.join("big.bin"));
+        assert_eq!(std::fs::read(&saved).unwrap(), data);
+        sender.process_complete(&complete).await.unwrap();
+        assert_eq!(std::fs::read_dir(dir.0.join("alice")).unwrap().count(), 0);
+        assert_eq!(std::fs::read_dir(dir.0.join("bob")).unwrap().count(), 0);
+
+        // Nothing is left to restore
+        let mut receiver = StreamingFileTransfers::new(bob, dir.0.join("bob"));
+        assert!(receiver.restore().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_place_across_filesystems() {
+        let dir = TestDir::new();
+        let partial = default_partial_dir(&dir.0, PeerId::new([2; 8]));
+        std::fs::create_dir_all(&partial).unwrap();
+        let source = partial.join("done.part");
+        let data = sample_data(MAX_CHUNK_SIZE + 3);
+        std::fs::write(&source, &data).unwrap();
+
+        // The fallback for a rename that would cross filesystems
+        let destination = dir.0.join("done.bin");
+        copy_into_place(&source, &destination).await.unwrap();
+        assert_eq!(std::fs::read(&destination).unwrap(), data);
+        assert!(!source.exists());
+
+        // No staging copy is left beside the destination
+        let names: Vec<_> = std::fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names.len(), 2, "unexpected files {:?}", names);
+    }
+}
@@ -5,8 +5,12 @@
 
 pub mod connection;
 pub mod delivery;
+#[cfg(feature = "experimental")]
+pub mod file_transfer;
 pub mod session;
 
 pub use connection::{ConnectionManager, ConnectionStats, StateDistribution};
 pub use delivery::{DeliveryStatistics, DeliveryTracker};
+#[cfg(feature = "experimental")]
+pub use file_transfer::{default_partial_dir, StreamingFileTransfers, PARTIAL_DIR_NAME};
 pub use session::{NoiseSessionManager, SessionTimeouts};
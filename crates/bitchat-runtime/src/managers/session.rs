@@ -44,6 +44,8 @@ pub struct NoiseSessionManager<T: TimeSource> {
     local_key: NoiseKeyPair,
     /// Active sessions by peer ID
     sessions: HashMap<PeerId, NoiseSession>,
+    /// Re-handshakes from peers that already have an established session
+    pending_handshakes: HashMap<PeerId, NoiseSession>,
     /// Session timeout configuration
     timeouts: SessionTimeouts,
     /// Time source for generating timestamps
@@ -56,6 +58,7 @@ impl<T: TimeSource> NoiseSessionManager<T> {
         Self {
             local_key,
             sessions: HashMap::new(),
+            pending_handshakes: HashMap::new(),
             timeouts,
             time_source,
         }
@@ -70,6 +73,7 @@ impl<T: TimeSource> NoiseSessionManager<T> {
         Self {
             local_key,
             sessions: HashMap::new(),
+            pending_handshakes: HashMap::new(),
             timeouts,
             time_source,
         }
@@ -100,6 +104,68 @@ impl<T: TimeSource> NoiseSessionManager<T> {
         })
     }
 
+    /// Start an inbound re-handshake without touching the established session
+    ///
+    /// The new handshake only replaces the current session through
+    /// [`Self::promote_pending`], once it has authenticated the same static key.
+    pub fn create_pending_inbound(&mut self, peer_id: PeerId) -> BitchatResult<&mut NoiseSession> {
+        let session = NoiseSession::new_inbound(peer_id, &self.local_key, &self.time_source)?;
+        self.pending_handshakes.insert(peer_id, session);
+        self.pending_handshakes.get_mut(&peer_id).ok_or_else(|| {
+            BitchatError::Session(SessionError::SessionNotFound {
+                peer_id: peer_id.to_string(),
+            })
+        })
+    }
+
+    /// Get mutable pending re-handshake
+    pub fn get_pending_mut(&mut self, peer_id: &PeerId) -> Option<&mut NoiseSession> {
+        self.pending_handshakes.get_mut(peer_id)
+    }
+
+    /// Remove pending re-handshake
+    pub fn remove_pending(&mut self, peer_id: &PeerId) -> Option<NoiseSession> {
+        self.pending_handshakes.remove(peer_id)
+    }
+
+    /// Replace the established session with a completed re-handshake
+    ///
+    /// Returns `Ok(true)` if the pending session authenticated the same static
+    /// key as the current session and took its place, `Ok(false)` if it is
+    /// still handshaking. A pending session for a different key is dropped and
+    /// the current session is kept.
+    pub fn promote_pending(&mut self, peer_id: &PeerId) -> BitchatResult<bool> {
+        if !self
+            .pending_handshakes
+            .get(peer_id)
+            .is_some_and(|pending| pending.is_established())
+        {
+            return Ok(false);
+        }
+        let Some(pending) = self.pending_handshakes.remove(peer_id) else {
+            return Ok(false);
+        };
+        let current = self
+            .sessions
+            .get(peer_id)
+            .and_then(|session| session.peer_fingerprint());
+        let same_key = match (current, pending.peer_fingerprint()) {
+            (Some(current), Some(new)) => current == new,
+            (None, new) => new.is_some(),
+            (Some(_), None) => false,
+        };
+        if !same_key {
+            return Err(BitchatError::Session(SessionError::InvalidState {
+                peer_id: peer_id.to_string(),
+                expected: "Re-handshake with the established static key".to_string(),
+                actual: "Re-handshake authenticated a different static key".to_string(),
+            }));
+        }
+
+        self.sessions.insert(*peer_id, pending);
+        Ok(true)
+    }
+
     /// Get existing session
     pub fn get_session(&self, peer_id: &PeerId) -> Option<&NoiseSession> {
         self.sessions.get(peer_id)
@@ -162,12 +228,22 @@ impl<T: TimeSource> NoiseSessionManager<T> {
         for peer_id in expired_peers {
             self.sessions.remove(&peer_id);
         }
+
+        let handshake_timeout = self.timeouts.handshake_timeout;
+        let time_source = &self.time_source;
+        self.pending_handshakes
+            .retain(|_, session| session.time_since_activity(time_source) <= handshake_timeout);
     }
 
     /// Get local key fingerprint
     pub fn local_fingerprint(&self) -> Fingerprint {
         self.local_key.fingerprint()
     }
+
+    /// Get local static public key
+    pub fn local_public_key(&self) -> [u8; 32] {
+        self.local_key.public_key_bytes()
+    }
 }
 
 // ----------------------------------------------------------------------------
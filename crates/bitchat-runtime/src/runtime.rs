@@ -76,14 +76,20 @@ use bitchat_core::{
     internal::{
         create_app_event_channel, create_command_channel, create_effect_channel,
         create_effect_receiver, create_event_channel, AppEventReceiver, BitchatConfig,
-        CommandSender, ConsoleLogger, LogLevel, NoOpLogger, TaskId, TaskLogger, TransportError,
+        CommandSender, ConsoleLogger, FileStorage, LogLevel, NoOpLogger, TaskId, TaskLogger,
+        TransportError,
     },
-    BitchatError, BitchatResult, ChannelTransportType, EffectReceiver, EventSender, PeerId,
-    TransportTask,
+    BitchatError, BitchatResult, ChannelTransportType, Command, EffectReceiver, EventSender,
+    PeerId, TransportTask,
 };
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 use tokio::task::JoinHandle;
 
+/// How long `stop` waits for each task to wind down before aborting it
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 // ----------------------------------------------------------------------------
 // BitChat Runtime
 // ----------------------------------------------------------------------------
@@ -118,6 +124,11 @@ pub struct BitchatRuntime {
     command_sender: Option<CommandSender>,
     /// App event receiver for external use
     app_event_receiver: Option<AppEventReceiver>,
+    /// Directory keeping our identity key and known peer identities between runs
+    identity_dir: Option<PathBuf>,
+    /// Download and part file directories for file transfers, if not the defaults
+    #[cfg(feature = "experimental")]
+    file_transfer_dirs: Option<(PathBuf, Option<PathBuf>)>,
     /// Running state
     running: bool,
 }
@@ -145,6 +156,9 @@ impl BitchatRuntime {
             core_logic_handle: None,
             command_sender: None,
             app_event_receiver: None,
+            identity_dir: None,
+            #[cfg(feature = "experimental")]
+            file_transfer_dirs: None,
             running: false,
         }
     }
@@ -177,6 +191,9 @@ impl BitchatRuntime {
             core_logic_handle: None,
             command_sender: None,
             app_event_receiver: None,
+            identity_dir: None,
+            #[cfg(feature = "experimental")]
+            file_transfer_dirs: None,
             running: false,
         }
     }
@@ -186,6 +203,23 @@ impl BitchatRuntime {
         Self::new(peer_id, BitchatConfig::testing())
     }
 
+    /// Keep our identity key and known peer identities in `dir` between runs
+    ///
+    /// Without one, a new identity is generated on every start. Must be called
+    /// before `start()`.
+    pub fn set_identity_dir(&mut self, dir: PathBuf) {
+        self.identity_dir = Some(dir);
+    }
+
+    /// Save accepted files to `download_dir` and keep part files in `partial_dir`
+    ///
+    /// Part files default to a directory under the download directory. Must be
+    /// called before `start()`.
+    #[cfg(feature = "experimental")]
+    pub fn set_file_transfer_dirs(&mut self, download_dir: PathBuf, partial_dir: Option<PathBuf>) {
+        self.file_transfer_dirs = Some((download_dir, partial_dir));
+    }
+
     /// Add a transport task to the runtime
     ///
     /// Transport tasks must be added before calling `start()`. Each transport type
@@ -331,7 +365,16 @@ impl BitchatRuntime {
             self.config.session.clone(),
             self.config.delivery.clone(),
             self.config.rate_limiting.clone(),
-        )?;
+        )?
+        .with_timing_config(&self.config.timing)
+        .with_announce_config(&self.config.ble);
+        if let Some(dir) = &self.identity_dir {
+            core_logic = core_logic.with_identity_storage(Box::new(FileStorage::open(dir)?))?;
+        }
+        #[cfg(feature = "experimental")]
+        if let Some((download_dir, partial_dir)) = self.file_transfer_dirs.clone() {
+            core_logic = core_logic.with_file_transfer_dirs(download_dir, partial_dir);
+        }
 
         let core_handle = tokio::spawn(async move { core_logic.run().await });
         self.core_logic_handle = Some(core_handle);
@@ -373,6 +416,10 @@ impl BitchatRuntime {
     }
 
     /// Stop the BitChat application
+    ///
+    /// Core Logic gets a `Shutdown` command so it can tell peers we are leaving.
+    /// Once it exits, the effect channel closes and transports stop after sending
+    /// its last effects.
     pub async fn stop(&mut self) -> BitchatResult<()> {
         if !self.running {
             return Ok(());
@@ -380,8 +427,20 @@ impl BitchatRuntime {
 
         self.running = false;
 
+        if let Some(command_sender) = self.command_sender.take() {
+            let _ = command_sender.send(Command::Shutdown).await;
+        }
+        if let Some(mut handle) = self.core_logic_handle.take() {
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut handle)
+                .await
+                .is_err()
+            {
+                handle.abort();
+            }
+        }
+
         // Stop all transport tasks
-        for (transport_type, handle) in self.transport_handles.drain() {
+        for (transport_type, mut handle) in self.transport_handles.drain() {
             if let LoggerWrapper::Console(ref logger) = self.logger {
                 logger.log_task_event(
                     bitchat_core::internal::TaskId::Transport(transport_type),
@@ -389,12 +448,12 @@ impl BitchatRuntime {
                     &format!("Stopping {:?} transport task", transport_type),
                 );
             }
-            handle.abort();
-        }
-
-        // Stop core logic task
-        if let Some(handle) = self.core_logic_handle.take() {
-            handle.abort();
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut handle)
+                .await
+                .is_err()
+            {
+                handle.abort();
+            }
         }
 
         // Clear channels
@@ -0,0 +1,143 @@
+//! Capability negotiation tests
+//!
+//! Drives the Core Logic handlers of two peers directly, delivering each side's
+//! packets to the other, to check that VersionHello/VersionAck run right after the
+//! Noise handshake and that experimental payloads and message extensions are
+//! gated per peer.
+
+#![cfg(feature = "experimental")]
+
+mod common;
+
+use bitchat_core::internal::MessageId;
+use bitchat_core::{MessageType, NegotiationStatus, NoisePayloadType};
+use bitchat_runtime::logic::{CommandHandlers, CoreState};
+use bitchat_runtime::AppEvent;
+
+use common::{alice, bob, connect, exchange, exchange_where, new_state};
+
+// ----------------------------------------------------------------------------
+// Helpers
+// ----------------------------------------------------------------------------
+
+/// Alice messages Bob and Bob replies
+///
+/// Returns the ID of Alice's message and the events the reply raised for her.
+async fn reply_to_alice(
+    alice_state: &mut CoreState,
+    bob_state: &mut CoreState,
+) -> (MessageId, Vec<AppEvent>) {
+    let (effects, _) =
+        CommandHandlers::handle_send_message(alice_state, bob(), "lunch?".to_string())
+            .await
+            .unwrap();
+    exchange(alice_state, bob_state, effects).await;
+
+    let sent = alice_state.message_store.find_by_id_prefix("")[0].id;
+    let (effects, _) =
+        CommandHandlers::handle_reply_to_message(bob_state, sent.to_hex(), "sure".to_string())
+            .await
+            .unwrap();
+    let events = exchange(alice_state, bob_state, effects).await.alice_events;
+    (sent, events)
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_negotiation_follows_noise_handshake() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+
+    // Three handshake messages, two announces, two hellos and two acks
+    assert_eq!(connect(&mut alice_state, &mut bob_state).await.delivered, 9);
+
+    assert_eq!(
+        alice_state.capabilities.get_negotiation_status(&bob()),
+        NegotiationStatus::Negotiated
+    );
+    assert_eq!(
+        bob_state.capabilities.get_negotiation_status(&alice()),
+        NegotiationStatus::Negotiated
+    );
+    assert!(alice_state.peer_accepts(&bob(), NoisePayloadType::FileOffer));
+    assert!(bob_state.peer_accepts(&alice(), NoisePayloadType::GroupMessage));
+}
+
+#[tokio::test]
+async fn test_experimental_payloads_gated_until_negotiated() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+
+    // Complete the handshake but hold back both VersionHellos
+    let effects = CommandHandlers::initiate_handshake(&mut alice_state, bob()).unwrap();
+    let held_back = exchange_where(&mut alice_state, &mut bob_state, effects, |packet| {
+        packet.message_type() != MessageType::NoiseEncrypted
+    })
+    .await
+    .held_back;
+    assert_eq!(held_back.len(), 2);
+
+    assert_eq!(
+        alice_state.capabilities.get_negotiation_status(&bob()),
+        NegotiationStatus::Pending
+    );
+    assert!(alice_state.peer_accepts(&bob(), NoisePayloadType::PrivateMessage));
+    assert!(!alice_state.peer_accepts(&bob(), NoisePayloadType::FileOffer));
+
+    // A peer that never answers is treated as the canonical implementation
+    alice_state.capabilities.mark_as_legacy_peer(bob());
+    assert!(alice_state.peer_accepts(&bob(), NoisePayloadType::PrivateMessage));
+    assert!(!alice_state.peer_accepts(&bob(), NoisePayloadType::FileOffer));
+    assert!(!alice_state.peer_accepts(&bob(), NoisePayloadType::DeviceAnnouncement));
+
+    // A late answer still upgrades the peer
+    exchange(&mut alice_state, &mut bob_state, held_back).await;
+    assert_eq!(
+        alice_state.capabilities.get_negotiation_status(&bob()),
+        NegotiationStatus::Negotiated
+    );
+    assert!(alice_state.peer_accepts(&bob(), NoisePayloadType::FileOffer));
+}
+
+#[tokio::test]
+async fn test_private_replies_are_referenced_for_negotiated_peers() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+    connect(&mut alice_state, &mut bob_state).await;
+
+    let (sent, events) = reply_to_alice(&mut alice_state, &mut bob_state).await;
+    assert!(events.iter().any(|event| matches!(
+        event,
+        AppEvent::MessageHighlighted { reply_to: Some(id), .. } if *id == sent
+    )));
+    assert_eq!(alice_state.message_store.get_replies(&sent).len(), 1);
+}
+
+#[tokio::test]
+async fn test_private_replies_to_legacy_peers_are_plain() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+
+    // Canonical apps would read the reply flag as a channel name
+    let effects = CommandHandlers::initiate_handshake(&mut alice_state, bob()).unwrap();
+    exchange_where(&mut alice_state, &mut bob_state, effects, |packet| {
+        packet.message_type() != MessageType::NoiseEncrypted
+    })
+    .await;
+    bob_state.capabilities.mark_as_legacy_peer(alice());
+
+    let (sent, events) = reply_to_alice(&mut alice_state, &mut bob_state).await;
+    assert!(events.iter().any(
+        |event| matches!(event, AppEvent::MessageReceived { content, .. } if content == "sure")
+    ));
+    assert!(!events
+        .iter()
+        .any(|event| matches!(event, AppEvent::MessageHighlighted { .. })));
+
+    // Only the replying side threads the reply
+    assert!(alice_state.message_store.get_replies(&sent).is_empty());
+    assert_eq!(bob_state.message_store.get_replies(&sent).len(), 1);
+}
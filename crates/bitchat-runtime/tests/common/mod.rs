@@ -0,0 +1,193 @@
+//! Shared fixtures for the Core Logic handler tests
+//!
+//! These tests skip the runtime tasks and transports: each peer is a bare
+//! `CoreState`, and packets are passed between peers by feeding the effects one
+//! handler returns into the receive handler of the peer they address.
+
+// Each test crate only uses some of these
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+
+use bitchat_core::internal::{DeliveryConfig, SessionConfig};
+use bitchat_core::BitchatPacket;
+use bitchat_runtime::logic::{CommandHandlers, CoreState};
+use bitchat_runtime::{AppEvent, ChannelTransportType, Effect, PeerId};
+
+// ----------------------------------------------------------------------------
+// Peers
+// ----------------------------------------------------------------------------
+
+pub fn alice() -> PeerId {
+    PeerId::new([1, 0, 0, 0, 0, 0, 0, 0])
+}
+
+pub fn bob() -> PeerId {
+    PeerId::new([2, 0, 0, 0, 0, 0, 0, 0])
+}
+
+pub fn new_state(peer_id: PeerId) -> CoreState {
+    CoreState::new(peer_id, SessionConfig::default(), DeliveryConfig::default()).unwrap()
+}
+
+/// Three peers, addressed by index, with peer IDs starting at 1
+pub fn new_peers() -> Vec<CoreState> {
+    (1..=3)
+        .map(|id| new_state(PeerId::new([id, 0, 0, 0, 0, 0, 0, 0])))
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+// Two Peers
+// ----------------------------------------------------------------------------
+
+/// What happened while packets were passed between two peers
+#[derive(Debug, Default)]
+pub struct Exchange {
+    /// App events raised by the first peer
+    pub alice_events: Vec<AppEvent>,
+    /// App events raised by the second peer
+    pub bob_events: Vec<AppEvent>,
+    /// Packets the recipient handled
+    pub delivered: usize,
+    /// Packets the recipient's handlers failed on
+    pub errors: usize,
+    /// Effects whose packets `deliver` rejected, in the order they were sent
+    pub held_back: Vec<Effect>,
+}
+
+/// Deliver packets between Alice and Bob until neither side has anything to send
+///
+/// Broadcasts reach the peer that didn't send them. Packets rejected by `deliver`
+/// are held back, and handler failures are counted rather than unwrapped.
+pub async fn exchange_where<F>(
+    alice: &mut CoreState,
+    bob: &mut CoreState,
+    effects: Vec<Effect>,
+    deliver: F,
+) -> Exchange
+where
+    F: Fn(&BitchatPacket) -> bool,
+{
+    let mut queue: VecDeque<Effect> = effects.into();
+    let mut exchange = Exchange::default();
+    while let Some(effect) = queue.pop_front() {
+        let (to_alice, packet) = match &effect {
+            Effect::SendBitchatPacket {
+                peer_id, packet, ..
+            } => (*peer_id == alice.peer_id, packet),
+            Effect::BroadcastBitchatPacket { packet, .. } => {
+                (packet.sender_id != alice.peer_id, packet)
+            }
+            _ => continue,
+        };
+        if !deliver(packet) {
+            exchange.held_back.push(effect);
+            continue;
+        }
+        let (recipient, sender, events) = if to_alice {
+            (&mut *alice, bob.peer_id, &mut exchange.alice_events)
+        } else {
+            (&mut *bob, alice.peer_id, &mut exchange.bob_events)
+        };
+        match CommandHandlers::handle_bitchat_packet_received(
+            recipient,
+            sender,
+            packet.clone(),
+            ChannelTransportType::Ble,
+        )
+        .await
+        {
+            Ok((effects, app_events)) => {
+                queue.extend(effects);
+                events.extend(app_events);
+                exchange.delivered += 1;
+            }
+            Err(_) => exchange.errors += 1,
+        }
+    }
+    exchange
+}
+
+/// Deliver every packet between Alice and Bob, failing if a handler does
+pub async fn exchange(
+    alice: &mut CoreState,
+    bob: &mut CoreState,
+    effects: Vec<Effect>,
+) -> Exchange {
+    let exchange = exchange_where(alice, bob, effects, |_| true).await;
+    assert_eq!(exchange.errors, 0, "a packet handler failed");
+    exchange
+}
+
+/// Complete the Noise handshake and capability negotiation between Alice and Bob
+pub async fn connect(alice: &mut CoreState, bob: &mut CoreState) -> Exchange {
+    let effects = CommandHandlers::initiate_handshake(alice, bob.peer_id).unwrap();
+    exchange(alice, bob, effects).await
+}
+
+// ----------------------------------------------------------------------------
+// Several Peers
+// ----------------------------------------------------------------------------
+
+/// Deliver packets between the peers until none has anything left to send
+///
+/// `effects` were produced by peer `from`. Broadcasts reach every other peer, as
+/// on a mesh where all of them are neighbours. Returns the app events each peer
+/// raised, by index.
+pub async fn mesh_exchange(
+    peers: &mut [CoreState],
+    from: usize,
+    effects: Vec<Effect>,
+) -> Vec<Vec<AppEvent>> {
+    let sender = peers[from].peer_id;
+    let mut queue: VecDeque<(PeerId, Effect)> =
+        effects.into_iter().map(|effect| (sender, effect)).collect();
+    let mut events = vec![Vec::new(); peers.len()];
+    while let Some((sender, effect)) = queue.pop_front() {
+        let (recipients, packet) = match effect {
+            Effect::SendBitchatPacket {
+                peer_id, packet, ..
+            } => (vec![peer_id], packet),
+            Effect::BroadcastBitchatPacket { packet, .. } => (
+                peers
+                    .iter()
+                    .map(|peer| peer.peer_id)
+                    .filter(|peer_id| *peer_id != sender)
+                    .collect(),
+                packet,
+            ),
+            _ => continue,
+        };
+        for peer_id in recipients {
+            let index = peers
+                .iter()
+                .position(|peer| peer.peer_id == peer_id)
+                .unwrap();
+            let (effects, app_events) = CommandHandlers::handle_bitchat_packet_received(
+                &mut peers[index],
+                sender,
+                packet.clone(),
+                ChannelTransportType::Ble,
+            )
+            .await
+            .unwrap();
+            queue.extend(effects.into_iter().map(|effect| (peer_id, effect)));
+            events[index].extend(app_events);
+        }
+    }
+    events
+}
+
+/// Run a command handler on one peer and deliver what it sends
+///
+/// Returns the handler's own app events first, then those raised by delivery.
+pub async fn run<F>(peers: &mut [CoreState], index: usize, handler: F) -> Vec<Vec<AppEvent>>
+where
+    F: FnOnce(&mut CoreState) -> (Vec<Effect>, Vec<AppEvent>),
+{
+    let (effects, app_events) = handler(&mut peers[index]);
+    let mut events = mesh_exchange(peers, index, effects).await;
+    events[index].splice(0..0, app_events);
+    events
+}
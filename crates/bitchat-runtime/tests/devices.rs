@@ -0,0 +1,186 @@
+//! Multi-device linking tests
+//!
+//! Drives the Core Logic handlers of two devices and a contact directly, as in
+//! the group messaging tests, to check that a linked device takes on the
+//! identity that showed the link offer, and that private history and read state
+//! reach both devices over their Noise session.
+
+#![cfg(feature = "experimental")]
+
+mod common;
+
+use bitchat_core::internal::Timestamp;
+use bitchat_runtime::logic::{CommandHandlers, CoreState};
+use bitchat_runtime::{AppEvent, PeerId};
+
+use common::{mesh_exchange, new_peers, run};
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+const PHONE: usize = 0;
+const LAPTOP: usize = 1;
+const BOB: usize = 2;
+
+/// Past the runtime's interval between device sync rounds
+const SYNC_INTERVAL_MS: u64 = 60 * 1000;
+
+async fn send(peers: &mut [CoreState], from: usize, to: usize, content: &str) {
+    let recipient = peers[to].peer_id;
+    let (effects, _) =
+        CommandHandlers::handle_send_message(&mut peers[from], recipient, content.to_string())
+            .await
+            .unwrap();
+    mesh_exchange(peers, from, effects).await;
+}
+
+/// Link the laptop to the phone's identity with a fresh offer
+async fn link(peers: &mut [CoreState]) -> Vec<Vec<AppEvent>> {
+    let events = run(peers, PHONE, |state| {
+        CommandHandlers::handle_link_device(state).unwrap()
+    })
+    .await;
+    let uri = match events[PHONE].as_slice() {
+        [AppEvent::DeviceLinkOffer { uri, .. }] => uri.clone(),
+        other => panic!("Expected a link offer, got {:?}", other),
+    };
+
+    run(peers, LAPTOP, |state| {
+        CommandHandlers::handle_accept_device_link(state, uri).unwrap()
+    })
+    .await
+}
+
+async fn sync(peers: &mut [CoreState], index: usize, now: Timestamp) -> Vec<Vec<AppEvent>> {
+    run(peers, index, |state| {
+        (CommandHandlers::handle_device_tick(state, now), Vec::new())
+    })
+    .await
+}
+
+fn linked_peer(events: &[AppEvent]) -> Option<PeerId> {
+    events.iter().find_map(|event| match event {
+        AppEvent::DeviceLinked { device } => Some(device.peer_id),
+        _ => None,
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_linked_device_takes_identity_and_history() {
+    let mut peers = new_peers();
+    let (phone, laptop, bob) = (
+        peers[PHONE].peer_id,
+        peers[LAPTOP].peer_id,
+        peers[BOB].peer_id,
+    );
+    peers[PHONE].nickname = "alice".to_string();
+    send(&mut peers, BOB, PHONE, "Are you there?").await;
+
+    let events = link(&mut peers).await;
+    assert_eq!(linked_peer(&events[PHONE]), Some(laptop));
+    assert_eq!(linked_peer(&events[LAPTOP]), Some(phone));
+    assert_eq!(
+        peers[LAPTOP].identity_key.public_key_bytes(),
+        peers[PHONE].identity_key.public_key_bytes()
+    );
+    assert_eq!(peers[LAPTOP].nickname, "alice");
+
+    // History from before the link arrives with it
+    assert!(events[LAPTOP].iter().any(|event| matches!(
+        event,
+        AppEvent::MessageReceived { from, content, .. }
+            if *from == bob && content == "Are you there?"
+    )));
+    assert!(events[LAPTOP].iter().any(|event| matches!(
+        event,
+        AppEvent::ConversationUpdated { peer_id, message_count: 1, .. } if *peer_id == bob
+    )));
+
+    let (_, app_events) = CommandHandlers::handle_list_devices(&peers[LAPTOP]).unwrap();
+    match app_events.as_slice() {
+        [AppEvent::DevicesReport { devices }] => {
+            assert_eq!(devices.len(), 2);
+            assert!(devices
+                .iter()
+                .any(|device| device.this_device && device.peer_id == laptop));
+            assert!(devices
+                .iter()
+                .any(|device| !device.this_device && device.peer_id == phone && device.online));
+        }
+        other => panic!("Expected a devices report, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_link_offer_is_single_use() {
+    let mut peers = new_peers();
+    let events = run(&mut peers, PHONE, |state| {
+        CommandHandlers::handle_link_device(state).unwrap()
+    })
+    .await;
+    let Some(AppEvent::DeviceLinkOffer { uri, .. }) = events[PHONE].first().cloned() else {
+        panic!("Expected a link offer, got {:?}", events[PHONE]);
+    };
+
+    let events = run(&mut peers, LAPTOP, |state| {
+        CommandHandlers::handle_accept_device_link(state, uri.clone()).unwrap()
+    })
+    .await;
+    assert!(linked_peer(&events[LAPTOP]).is_some());
+
+    // Bob got hold of the same URI, but the laptop already used it
+    let bob_key = peers[BOB].identity_key.public_key_bytes();
+    let events = run(&mut peers, BOB, |state| {
+        CommandHandlers::handle_accept_device_link(state, uri).unwrap()
+    })
+    .await;
+    assert!(linked_peer(&events[BOB]).is_none());
+    assert!(linked_peer(&events[PHONE]).is_none());
+    assert_eq!(peers[BOB].identity_key.public_key_bytes(), bob_key);
+
+    let (effects, app_events) = CommandHandlers::handle_accept_device_link(
+        &mut peers[BOB],
+        "bitchat://verify?data=AAAA".to_string(),
+    )
+    .unwrap();
+    assert!(effects.is_empty());
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::SystemError { .. }]
+    ));
+}
+
+#[tokio::test]
+async fn test_linked_devices_sync_replies_and_read_state() {
+    let mut peers = new_peers();
+    let bob = peers[BOB].peer_id;
+    link(&mut peers).await;
+    let now = Timestamp::now();
+
+    send(&mut peers, BOB, PHONE, "Lunch?").await;
+    let events = sync(&mut peers, LAPTOP, now + SYNC_INTERVAL_MS).await;
+    assert!(events[LAPTOP].iter().any(|event| matches!(
+        event,
+        AppEvent::MessageReceived { content, .. } if content == "Lunch?"
+    )));
+
+    // Answering from the laptop marks Bob's message read, and the phone learns both
+    send(&mut peers, LAPTOP, BOB, "Sure").await;
+    let events = sync(&mut peers, LAPTOP, now + 2 * SYNC_INTERVAL_MS).await;
+    assert!(events[PHONE].iter().any(|event| matches!(
+        event,
+        AppEvent::MessageSent { to, content, .. } if *to == bob && content == "Sure"
+    )));
+    let messages = peers[PHONE].devices.get_message_refs();
+    assert_eq!(messages.len(), 2);
+    assert!(messages.iter().all(|message| message.read));
+
+    // Nothing is sent again before the next round is due
+    let events = sync(&mut peers, LAPTOP, now + 2 * SYNC_INTERVAL_MS).await;
+    assert!(events.iter().all(Vec::is_empty));
+}
@@ -0,0 +1,378 @@
+//! File transfer tests
+//!
+//! Drives the Core Logic handlers of two peers directly, as in the capability
+//! negotiation tests, to check that files are offered, accepted, paced chunk by
+//! chunk and verified end to end over an established Noise session, paused while
+//! the session is down, and resumed after chunks are lost or a peer restarts.
+
+#![cfg(feature = "experimental")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use bitchat_core::channel::communication::{FileTransferState, TransferDirection};
+use bitchat_core::internal::Timestamp;
+use bitchat_core::protocol::file_transfer::MAX_CHUNK_SIZE;
+use bitchat_core::MessageType;
+use bitchat_runtime::logic::{CommandHandlers, CoreState};
+use bitchat_runtime::{AppEvent, ChannelTransportType, Effect, PeerId, StreamingFileTransfers};
+use uuid::Uuid;
+
+use common::{alice, bob, connect, exchange, exchange_where};
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("bitchat-transfer-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn new_state(peer_id: PeerId, dir: &TestDir) -> CoreState {
+    let mut state = common::new_state(peer_id);
+    state.file_transfers = StreamingFileTransfers::new(peer_id, dir.0.join(peer_id.to_string()));
+    state
+}
+
+fn sample_data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 % 253) as u8).collect()
+}
+
+/// Offer a file from Alice and return its transfer ID as Bob sees it
+async fn offer(alice: &mut CoreState, bob: &mut CoreState, dir: &TestDir, data: &[u8]) -> String {
+    let path = dir.0.join("notes.txt");
+    std::fs::write(&path, data).unwrap();
+    let (effects, _) =
+        CommandHandlers::handle_send_file(alice, bob.peer_id, path.display().to_string())
+            .await
+            .unwrap();
+    let bob_events = exchange(alice, bob, effects).await.bob_events;
+
+    match bob_events.as_slice() {
+        [AppEvent::FileOffered { transfer, .. }] => {
+            assert_eq!(transfer.peer_id, alice.peer_id);
+            assert_eq!(transfer.direction, TransferDirection::Incoming);
+            assert_eq!(transfer.filename, "notes.txt");
+            assert_eq!(transfer.size, data.len() as u64);
+            transfer.transfer_id.clone()
+        }
+        other => panic!("Expected a single file offer, got {:?}", other),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_file_transfer_end_to_end() {
+    let dir = TestDir::new();
+    let mut alice_state = new_state(alice(), &dir);
+    let mut bob_state = new_state(bob(), &dir);
+    connect(&mut alice_state, &mut bob_state).await;
+
+    let data = sample_data(2 * MAX_CHUNK_SIZE + 500);
+    let transfer_id = offer(&mut alice_state, &mut bob_state, &dir, &data).await;
+
+    // Accept by ID prefix into a chosen directory
+    let downloads = dir.0.join("downloads");
+    let (effects, _) = CommandHandlers::handle_accept_file(
+        &mut bob_state,
+        transfer_id[..8].to_string(),
+        Some(downloads.display().to_string()),
+    )
+    .await
+    .unwrap();
+    let alice_events = exchange(&mut alice_state, &mut bob_state, effects)
+        .await
+        .alice_events;
+    assert!(matches!(
+        alice_events.as_slice(),
+        [AppEvent::FileTransferProgress { transfer }] if transfer.state == FileTransferState::InProgress
+    ));
+
+    let mut now = Timestamp::now();
+    let mut alice_events = Vec::new();
+    let mut bob_events = Vec::new();
+    for _ in 0..3 {
+        let (effects, mut events) =
+            CommandHandlers::handle_transfer_tick(&mut alice_state, now).await;
+        assert_eq!(effects.len(), 1);
+        alice_events.append(&mut events);
+        let (mut alice_received, mut bob_received) =
+            exchange(&mut alice_state, &mut bob_state, effects).await;
+        alice_events.append(&mut alice_received);
+        bob_events.append(&mut bob_received);
+        now = now + 100;
+    }
+
+    let saved_to = bob_events
+        .iter()
+        .find_map(|event| match event {
+            AppEvent::FileTransferCompleted { transfer, saved_to } => {
+                assert_eq!(transfer.direction, TransferDirection::Incoming);
+                assert_eq!(transfer.bytes_transferred, data.len() as u64);
+                saved_to.clone()
+            }
+            _ => None,
+        })
+        .expect("Bob should complete the transfer");
+    assert_eq!(PathBuf::from(&saved_to), downloads.join("notes.txt"));
+    assert_eq!(std::fs::read(&saved_to).unwrap(), data);
+
+    assert!(alice_events.iter().any(|event| matches!(
+        event,
+        AppEvent::FileTransferCompleted { transfer, saved_to: None }
+            if transfer.direction == TransferDirection::Outgoing
+                && transfer.state == FileTransferState::Completed
+    )));
+    assert!(alice_state.outgoing_transfers.is_empty());
+
+    // Nothing left to send
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, now).await;
+    assert!(effects.is_empty());
+}
+
+#[tokio::test]
+async fn test_chunks_paced_per_transport() {
+    let dir = TestDir::new();
+    let mut alice_state = new_state(alice(), &dir);
+    let mut bob_state = new_state(bob(), &dir);
+    connect(&mut alice_state, &mut bob_state).await;
+
+    let data = sample_data(4 * MAX_CHUNK_SIZE);
+    let transfer_id = offer(&mut alice_state, &mut bob_state, &dir, &data).await;
+    let (effects, _) = CommandHandlers::handle_accept_file(&mut bob_state, transfer_id, None)
+        .await
+        .unwrap();
+    exchange(&mut alice_state, &mut bob_state, effects).await;
+
+    let start = Timestamp::now();
+    let sent = |effects: &[Effect]| effects.len();
+
+    // BLE carries a chunk every 100ms
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start).await;
+    assert_eq!(sent(&effects), 1);
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start + 50).await;
+    assert_eq!(sent(&effects), 0);
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start + 100).await;
+    assert_eq!(sent(&effects), 1);
+    assert!(effects.iter().all(|effect| matches!(
+        effect,
+        Effect::SendBitchatPacket {
+            transport: ChannelTransportType::Ble,
+            ..
+        }
+    )));
+
+    // Over Nostr the same transfer slows to a chunk a second
+    alice_state
+        .peer_transports
+        .insert(bob(), ChannelTransportType::Nostr);
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start + 200).await;
+    assert_eq!(sent(&effects), 1);
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start + 700).await;
+    assert_eq!(sent(&effects), 0);
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start + 1200).await;
+    assert_eq!(sent(&effects), 1);
+    assert!(effects.iter().all(|effect| matches!(
+        effect,
+        Effect::SendBitchatPacket {
+            transport: ChannelTransportType::Nostr,
+            ..
+        }
+    )));
+}
+
+#[tokio::test]
+async fn test_transfer_waits_for_session_to_return() {
+    let dir = TestDir::new();
+    let mut alice_state = new_state(alice(), &dir);
+    let mut bob_state = new_state(bob(), &dir);
+    connect(&mut alice_state, &mut bob_state).await;
+
+    let data = sample_data(2 * MAX_CHUNK_SIZE);
+    let transfer_id = offer(&mut alice_state, &mut bob_state, &dir, &data).await;
+    let (effects, _) = CommandHandlers::handle_accept_file(&mut bob_state, transfer_id, None)
+        .await
+        .unwrap();
+    exchange(&mut alice_state, &mut bob_state, effects).await;
+
+    // The session drops before the first chunk goes out
+    alice_state.session_manager.remove_session(&bob());
+    let start = Timestamp::now();
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start).await;
+    assert!(effects.is_empty());
+    assert_eq!(alice_state.outgoing_transfers.len(), 1);
+
+    // Once Alice handshakes again the paused transfer picks up where it stopped
+    connect(&mut alice_state, &mut bob_state).await;
+    let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, start + 100).await;
+    assert_eq!(effects.len(), 1);
+}
+
+#[tokio::test]
+async fn test_rejected_offer_fails_for_sender() {
+    let dir = TestDir::new();
+    let mut alice_state = new_state(alice(), &dir);
+    let mut bob_state = new_state(bob(), &dir);
+    connect(&mut alice_state, &mut bob_state).await;
+
+    let transfer_id = offer(&mut alice_state, &mut bob_state, &dir, b"not wanted").await;
+    let (effects, bob_events) = CommandHandlers::handle_reject_file(&mut bob_state, transfer_id)
+        .await
+        .unwrap();
+    assert!(matches!(
+        bob_events.as_slice(),
+        [AppEvent::FileTransferFailed { transfer, .. }] if transfer.direction == TransferDirection::Incoming
+    ));
+
+    let alice_events = exchange(&mut alice_state, &mut bob_state, effects)
+        .await
+        .alice_events;
+    assert!(matches!(
+        alice_events.as_slice(),
+        [AppEvent::FileTransferFailed { transfer, reason }]
+            if transfer.state == FileTransferState::Failed && reason == "Rejected by recipient"
+    ));
+    assert!(alice_state.outgoing_transfers.is_empty());
+
+    // The offer can no longer be answered
+    let (effects, bob_events) =
+        CommandHandlers::handle_accept_file(&mut bob_state, "".to_string(), None)
+            .await
+            .unwrap();
+    assert!(effects.is_empty());
+    assert!(matches!(
+        bob_events.as_slice(),
+        [AppEvent::SystemError { .. }]
+    ));
+}
+
+#[tokio::test]
+async fn test_offer_waits_for_negotiation_and_fails_for_legacy_peers() {
+    let dir = TestDir::new();
+    let mut alice_state = new_state(alice(), &dir);
+    let mut bob_state = new_state(bob(), &dir);
+
+    // Complete the handshake but lose both VersionHellos
+    let effects = CommandHandlers::initiate_handshake(&mut alice_state, bob()).unwrap();
+    exchange_where(&mut alice_state, &mut bob_state, effects, |packet| {
+        packet.message_type() == MessageType::NoiseHandshake
+    })
+    .await;
+
+    let path = dir.0.join("queued.bin");
+    std::fs::write(&path, sample_data(1000)).unwrap();
+    let (effects, app_events) =
+        CommandHandlers::handle_send_file(&mut alice_state, bob(), path.display().to_string())
+            .await
+            .unwrap();
+    assert!(effects.is_empty());
+    assert!(app_events.is_empty());
+    assert_eq!(alice_state.pending_file_offers[&bob()].len(), 1);
+
+    // Bob never answers, so he is treated as a legacy peer
+    alice_state.capabilities.mark_as_legacy_peer(bob());
+    let (effects, app_events) =
+        CommandHandlers::handle_transfer_tick(&mut alice_state, Timestamp::now()).await;
+    assert!(effects.is_empty());
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::FileTransferFailed { transfer, .. }]
+            if transfer.state == FileTransferState::Cancelled
+    ));
+    assert!(alice_state.pending_file_offers.is_empty());
+}
+
+#[tokio::test]
+async fn test_transfer_resumes_after_lost_chunks_and_restart() {
+    let dir = TestDir::new();
+    let mut alice_state = new_state(alice(), &dir);
+    let mut bob_state = new_state(bob(), &dir);
+    connect(&mut alice_state, &mut bob_state).await;
+
+    let data = sample_data(3 * MAX_CHUNK_SIZE + 42);
+    let transfer_id = offer(&mut alice_state, &mut bob_state, &dir, &data).await;
+    let downloads = dir.0.join("downloads");
+    let (effects, _) = CommandHandlers::handle_accept_file(
+        &mut bob_state,
+        transfer_id,
+        Some(downloads.display().to_string()),
+    )
+    .await
+    .unwrap();
+    exchange(&mut alice_state, &mut bob_state, effects).await;
+
+    // The first two chunks arrive, then the link drops and the rest are lost
+    let mut now = Timestamp::now();
+    for delivered in [true, true, false, false] {
+        let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, now).await;
+        assert_eq!(effects.len(), 1);
+        if delivered {
+            exchange(&mut alice_state, &mut bob_state, effects).await;
+        }
+        now = now + 100;
+    }
+    assert!(alice_state.outgoing_transfers.is_empty());
+
+    // Bob restarts with only what he saved to disk
+    bob_state.file_transfers.save_progress().await.unwrap();
+    let mut bob_state = new_state(bob(), &dir);
+    let (_, bob_events) = CommandHandlers::handle_restore_transfers(&mut bob_state)
+        .await
+        .unwrap();
+    assert!(matches!(
+        bob_events.as_slice(),
+        [AppEvent::FileTransferProgress { transfer }]
+            if transfer.bytes_transferred == 2 * MAX_CHUNK_SIZE as u64
+    ));
+
+    // The stalled transfer reconnects, and after negotiation Bob asks for the gap
+    let (effects, _) =
+        CommandHandlers::handle_transfer_tick(&mut bob_state, Timestamp::now() + 10_000).await;
+    assert_eq!(effects.len(), 1);
+    exchange(&mut alice_state, &mut bob_state, effects).await;
+    assert_eq!(alice_state.outgoing_transfers.len(), 1);
+
+    // Only the two missing chunks are sent again
+    let mut resent = 0;
+    let mut bob_events = Vec::new();
+    let mut alice_events = Vec::new();
+    while !alice_state.outgoing_transfers.is_empty() {
+        now = now + 100;
+        let (effects, _) = CommandHandlers::handle_transfer_tick(&mut alice_state, now).await;
+        resent += effects.len();
+        let (mut alice_received, mut bob_received) =
+            exchange(&mut alice_state, &mut bob_state, effects).await;
+        alice_events.append(&mut alice_received);
+        bob_events.append(&mut bob_received);
+    }
+    assert_eq!(resent, 2);
+
+    let saved_to = bob_events
+        .iter()
+        .find_map(|event| match event {
+            AppEvent::FileTransferCompleted { saved_to, .. } => saved_to.clone(),
+            _ => None,
+        })
+        .expect("Bob should complete the resumed transfer");
+    assert_eq!(PathBuf::from(&saved_to), downloads.join("notes.txt"));
+    assert_eq!(std::fs::read(&saved_to).unwrap(), data);
+    assert!(alice_events
+        .iter()
+        .any(|event| matches!(event, AppEvent::FileTransferCompleted { .. })));
+}
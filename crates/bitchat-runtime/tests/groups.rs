@@ -0,0 +1,208 @@
+//! Group messaging tests
+//!
+//! Drives the Core Logic handlers of three peers directly, as in the file transfer
+//! tests, to check that invitations wait for the invitee's identity key, and that
+//! membership changes reach every member over pairwise Noise sessions while group
+//! messages are flooded once for all of them.
+
+#![cfg(feature = "experimental")]
+
+mod common;
+
+use bitchat_core::channel::communication::GroupInfo;
+use bitchat_runtime::logic::{CommandHandlers, CoreState};
+use bitchat_runtime::{AppEvent, Effect, PeerId};
+
+use common::{mesh_exchange, new_peers, run};
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+const ALICE: usize = 0;
+const BOB: usize = 1;
+const CAROL: usize = 2;
+
+async fn invite(peers: &mut [CoreState], inviter: usize, invitee: usize, group_id: &str) {
+    let peer_id = peers[invitee].peer_id;
+    let events = run(peers, inviter, |state| {
+        CommandHandlers::handle_invite_to_group(state, group_id.to_string(), peer_id).unwrap()
+    })
+    .await;
+
+    let inviter_id = peers[inviter].peer_id;
+    assert!(
+        events[invitee].iter().any(|event| matches!(
+            event,
+            AppEvent::GroupInvitation { group, from }
+                if group.group_id.starts_with(group_id) && *from == inviter_id
+        )),
+        "invitee should be told about the invitation, got {:?}",
+        events[invitee]
+    );
+}
+
+fn members(group: &GroupInfo) -> Vec<PeerId> {
+    group.members.iter().map(|member| member.peer_id).collect()
+}
+
+fn received(events: &[AppEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            AppEvent::GroupMessageReceived { content, .. } => Some(content.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_group_lifecycle_over_noise() {
+    let mut peers = new_peers();
+    let (alice, bob, carol) = (
+        peers[ALICE].peer_id,
+        peers[BOB].peer_id,
+        peers[CAROL].peer_id,
+    );
+
+    let events = run(&mut peers, ALICE, |state| {
+        CommandHandlers::handle_create_group(state, "Hikers".to_string(), None).unwrap()
+    })
+    .await;
+    let group_id = match events[ALICE].as_slice() {
+        [AppEvent::GroupMembershipChanged { group, joined, .. }] => {
+            assert_eq!(group.name, "Hikers");
+            assert_eq!(joined, &vec![alice]);
+            group.group_id.clone()
+        }
+        other => panic!("Expected the new group, got {:?}", other),
+    };
+
+    // Neither invitee has a session or has announced its identity key yet
+    invite(&mut peers, ALICE, BOB, &group_id[..8]).await;
+    invite(&mut peers, ALICE, CAROL, &group_id).await;
+
+    let events = run(&mut peers, BOB, |state| {
+        CommandHandlers::handle_join_group(state, group_id.clone()).unwrap()
+    })
+    .await;
+    assert!(events[ALICE].iter().any(|event| matches!(
+        event,
+        AppEvent::GroupMembershipChanged { joined, .. } if joined == &vec![bob]
+    )));
+
+    // Carol has never talked to Bob, so her join opens a session with him
+    let events = run(&mut peers, CAROL, |state| {
+        CommandHandlers::handle_join_group(state, group_id.clone()).unwrap()
+    })
+    .await;
+    for index in [ALICE, BOB] {
+        assert!(events[index].iter().any(|event| matches!(
+            event,
+            AppEvent::GroupMembershipChanged { group, joined, .. }
+                if joined == &vec![carol] && members(group) == vec![alice, bob, carol]
+        )));
+    }
+
+    // The message goes out once for the whole group, not once per member
+    let (effects, app_events) = CommandHandlers::handle_send_group_message(
+        &mut peers[CAROL],
+        group_id.clone(),
+        "Trail at 9".into(),
+    )
+    .unwrap();
+    assert!(matches!(
+        effects.as_slice(),
+        [Effect::BroadcastBitchatPacket { .. }]
+    ));
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::GroupMessageSent { content, .. }] if content == "Trail at 9"
+    ));
+    let events = mesh_exchange(&mut peers, CAROL, effects).await;
+    assert_eq!(received(&events[ALICE]), vec!["Trail at 9"]);
+    assert_eq!(received(&events[BOB]), vec!["Trail at 9"]);
+
+    // Kicking Bob tells him he is out and rotates Alice's key for Carol
+    let events = run(&mut peers, ALICE, |state| {
+        CommandHandlers::handle_kick_from_group(state, group_id.clone(), bob).unwrap()
+    })
+    .await;
+    for index in [ALICE, BOB, CAROL] {
+        assert!(events[index].iter().any(|event| matches!(
+            event,
+            AppEvent::GroupMembershipChanged { left, .. } if left == &vec![bob]
+        )));
+    }
+
+    let events = run(&mut peers, ALICE, |state| {
+        CommandHandlers::handle_send_group_message(state, group_id.clone(), "Bob is out".into())
+            .unwrap()
+    })
+    .await;
+    assert_eq!(received(&events[CAROL]), vec!["Bob is out"]);
+    assert!(received(&events[BOB]).is_empty());
+
+    let events = run(&mut peers, CAROL, |state| {
+        CommandHandlers::handle_list_groups(state).unwrap()
+    })
+    .await;
+    match events[CAROL].as_slice() {
+        [AppEvent::GroupsReport {
+            groups,
+            invitations,
+        }] => {
+            assert_eq!(groups.len(), 1);
+            assert_eq!(members(&groups[0]), vec![alice, carol]);
+            assert!(invitations.is_empty());
+        }
+        other => panic!("Expected a groups report, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_group_commands_report_unknown_groups() {
+    let mut peers = new_peers();
+    let bob = peers[BOB].peer_id;
+
+    let events = run(&mut peers, ALICE, |state| {
+        CommandHandlers::handle_create_group(state, "Hikers".to_string(), None).unwrap()
+    })
+    .await;
+    let group_id = match events[ALICE].as_slice() {
+        [AppEvent::GroupMembershipChanged { group, .. }] => group.group_id.clone(),
+        other => panic!("Expected the new group, got {:?}", other),
+    };
+
+    // Bob cannot join uninvited, and nobody can use a group they are not in
+    let (effects, app_events) =
+        CommandHandlers::handle_join_group(&mut peers[BOB], group_id.clone()).unwrap();
+    assert!(effects.is_empty());
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::SystemError { .. }]
+    ));
+    let (effects, app_events) = CommandHandlers::handle_send_group_message(
+        &mut peers[BOB],
+        group_id.clone(),
+        "Hello?".to_string(),
+    )
+    .unwrap();
+    assert!(effects.is_empty());
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::SystemError { .. }]
+    ));
+    let (effects, app_events) =
+        CommandHandlers::handle_invite_to_group(&mut peers[ALICE], "missing".to_string(), bob)
+            .unwrap();
+    assert!(effects.is_empty());
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::SystemError { .. }]
+    ));
+}
@@ -0,0 +1,488 @@
+//! Multi-node mesh tests over the in-process transport
+//!
+//! Spins up a full mesh of `BitchatRuntime`s in one process, connected through an
+//! `InProcessSwitchboard`, and checks that neighbours complete Noise handshakes,
+//! that encrypted messages are relayed across several hops, that public messages,
+//! replies, mentions and nickname changes reach other nodes and that peers leaving
+//! the mesh are dropped.
+
+#![cfg(feature = "in-process")]
+
+use std::time::Duration;
+
+use bitchat_core::channel::communication::PeerSessionState;
+use bitchat_runtime::{
+    AppEvent, AppEventReceiver, BitchatRuntime, Command, ConnectionStatus, InProcessSwitchboard,
+    PeerId, Topology,
+};
+use tokio::time::{sleep, timeout, Instant};
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+const NODE_COUNT: usize = 20;
+const GRID_COLUMNS: usize = 5;
+
+struct Node {
+    runtime: BitchatRuntime,
+    app_events: AppEventReceiver,
+}
+
+impl Node {
+    fn peer_id(&self) -> PeerId {
+        self.runtime.peer_id()
+    }
+
+    async fn send(&self, command: Command) {
+        self.runtime
+            .command_sender()
+            .expect("runtime should be started")
+            .send(command)
+            .await
+            .expect("command channel should be open");
+    }
+
+    /// Wait for the first app event matching the predicate, discarding the others
+    async fn wait_for<F>(&mut self, within: Duration, mut predicate: F) -> Option<AppEvent>
+    where
+        F: FnMut(&AppEvent) -> bool,
+    {
+        timeout(within, async {
+            while let Some(event) = self.app_events.recv().await {
+                if predicate(&event) {
+                    return Some(event);
+                }
+            }
+            None
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    async fn session_state(&mut self, peer_id: PeerId) -> Option<PeerSessionState> {
+        self.send(Command::QueryPeerSession { peer_id }).await;
+        match self
+            .wait_for(Duration::from_secs(1), |event| {
+                matches!(event, AppEvent::PeerSessionReport { peer_id: p, .. } if *p == peer_id)
+            })
+            .await
+        {
+            Some(AppEvent::PeerSessionReport { session_state, .. }) => Some(session_state),
+            _ => None,
+        }
+    }
+
+    async fn announced_peers(&mut self) -> Vec<PeerId> {
+        self.send(Command::ListPeers).await;
+        match self
+            .wait_for(Duration::from_secs(1), |event| {
+                matches!(event, AppEvent::PeerListReport { .. })
+            })
+            .await
+        {
+            Some(AppEvent::PeerListReport { peers }) => {
+                peers.into_iter().map(|peer| peer.peer_id).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Poll until the session with the peer is established
+    async fn wait_for_session(&mut self, peer_id: PeerId, within: Duration) -> bool {
+        let deadline = Instant::now() + within;
+        while Instant::now() < deadline {
+            if self.session_state(peer_id).await == Some(PeerSessionState::Established) {
+                return true;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        false
+    }
+}
+
+fn create_test_peer_id(index: usize) -> PeerId {
+    PeerId::new([index as u8 + 1, 0, 0, 0, 0, 0, 0, 0])
+}
+
+async fn start_mesh(topology: Topology, count: usize) -> (InProcessSwitchboard, Vec<Node>) {
+    let switchboard = InProcessSwitchboard::new(topology);
+
+    // Register every node before starting any of them so topology links are stable
+    let mut runtimes = Vec::with_capacity(count);
+    for index in 0..count {
+        let peer_id = create_test_peer_id(index);
+        let mut runtime = BitchatRuntime::for_testing(peer_id);
+        runtime
+            .add_transport(switchboard.create_transport(peer_id).unwrap())
+            .unwrap();
+        runtimes.push(runtime);
+    }
+
+    let mut nodes = Vec::with_capacity(count);
+    for mut runtime in runtimes {
+        runtime.start().await.expect("runtime should start");
+        let app_events = runtime.take_app_event_receiver().unwrap();
+        nodes.push(Node {
+            runtime,
+            app_events,
+        });
+    }
+
+    // Transport tasks go online once the runtime has spawned them
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !nodes
+        .iter()
+        .all(|node| switchboard.is_online(&node.peer_id()))
+    {
+        assert!(Instant::now() < deadline, "transports should come online");
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    (switchboard, nodes)
+}
+
+async fn stop_mesh(nodes: &mut [Node]) {
+    for node in nodes {
+        node.runtime.stop().await.unwrap();
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_neighbours_complete_handshakes() {
+    let (switchboard, mut nodes) = start_mesh(
+        Topology::Grid {
+            columns: GRID_COLUMNS,
+        },
+        NODE_COUNT,
+    )
+    .await;
+
+    // Node 6 sits in the middle of the grid with four neighbours
+    let center = nodes[6].peer_id();
+    let neighbours = switchboard.neighbours(&center);
+    assert_eq!(neighbours.len(), 4);
+
+    for neighbour in neighbours {
+        assert!(
+            nodes[6]
+                .wait_for_session(neighbour, Duration::from_secs(5))
+                .await,
+            "session with neighbour {} should be established",
+            neighbour
+        );
+    }
+
+    stop_mesh(&mut nodes).await;
+}
+
+#[tokio::test]
+async fn test_private_message_across_twenty_node_mesh() {
+    let (_switchboard, mut nodes) = start_mesh(
+        Topology::Grid {
+            columns: GRID_COLUMNS,
+        },
+        NODE_COUNT,
+    )
+    .await;
+
+    // Opposite corners of a 5x4 grid are seven hops apart, the full TTL budget
+    let sender = nodes[0].peer_id();
+    let recipient = nodes[NODE_COUNT - 1].peer_id();
+
+    nodes[0]
+        .send(Command::SendMessage {
+            recipient,
+            content: "hello from across the mesh".to_string(),
+        })
+        .await;
+
+    let received = nodes[NODE_COUNT - 1]
+        .wait_for(Duration::from_secs(10), |event| {
+            matches!(event, AppEvent::MessageReceived { .. })
+        })
+        .await;
+
+    match received {
+        Some(AppEvent::MessageReceived { from, content, .. }) => {
+            assert_eq!(from, sender);
+            assert_eq!(content, "hello from across the mesh");
+        }
+        other => panic!("expected multi-hop message, got {:?}", other),
+    }
+
+    assert!(
+        nodes[NODE_COUNT - 1]
+            .wait_for_session(sender, Duration::from_secs(1))
+            .await
+    );
+
+    stop_mesh(&mut nodes).await;
+}
+
+#[tokio::test]
+async fn test_broadcast_reaches_every_node_once() {
+    let (_switchboard, mut nodes) = start_mesh(Topology::Ring, 4).await;
+    let sender = nodes[0].peer_id();
+
+    nodes[0]
+        .send(Command::SendBroadcast {
+            content: "hello everyone".to_string(),
+        })
+        .await;
+    let sent = nodes[0]
+        .wait_for(Duration::from_secs(1), |event| {
+            matches!(event, AppEvent::BroadcastSent { .. })
+        })
+        .await;
+    let Some(AppEvent::BroadcastSent { message_id, .. }) = sent else {
+        panic!("expected the broadcast to be sent, got {:?}", sent);
+    };
+
+    for node in &mut nodes[1..] {
+        let received = node
+            .wait_for(Duration::from_secs(3), |event| {
+                matches!(event, AppEvent::BroadcastReceived { .. })
+            })
+            .await;
+        match received {
+            Some(AppEvent::BroadcastReceived {
+                message_id: id,
+                from,
+                nickname,
+                content,
+                ..
+            }) => {
+                assert_eq!(id, message_id);
+                assert_eq!(from, sender);
+                assert_eq!(nickname, sender.to_string());
+                assert_eq!(content, "hello everyone");
+            }
+            other => panic!("expected a public message, got {:?}", other),
+        }
+    }
+
+    // The opposite node hears the message over both halves of the ring
+    let again = nodes[2]
+        .wait_for(Duration::from_millis(300), |event| {
+            matches!(event, AppEvent::BroadcastReceived { .. })
+        })
+        .await;
+    assert!(again.is_none(), "public message reported twice");
+
+    stop_mesh(&mut nodes).await;
+}
+
+#[tokio::test]
+async fn test_replies_and_mentions_are_highlighted() {
+    let (_switchboard, mut nodes) = start_mesh(Topology::Line, 3).await;
+    let (first, last) = (nodes[0].peer_id(), nodes[2].peer_id());
+
+    // Nicknames default to the peer ID
+    nodes[0]
+        .send(Command::SendBroadcast {
+            content: format!("lunch, @{}?", last),
+        })
+        .await;
+    let sent = nodes[0]
+        .wait_for(Duration::from_secs(1), |event| {
+            matches!(event, AppEvent::BroadcastSent { .. })
+        })
+        .await;
+    let Some(AppEvent::BroadcastSent { message_id, .. }) = sent else {
+        panic!("expected the broadcast to be sent, got {:?}", sent);
+    };
+
+    let mentioned = nodes[2]
+        .wait_for(Duration::from_secs(3), |event| {
+            matches!(event, AppEvent::MessageHighlighted { .. })
+        })
+        .await;
+    match mentioned {
+        Some(AppEvent::MessageHighlighted {
+            message_id: id,
+            from,
+            mentioned,
+            reply_to,
+            ..
+        }) => {
+            assert_eq!(id, message_id);
+            assert_eq!(from, first);
+            assert!(mentioned);
+            assert_eq!(reply_to, None);
+        }
+        other => panic!("expected a mention, got {:?}", other),
+    }
+
+    // Replies to public messages are public, but canonical apps would read the
+    // reply reference as a channel, so it stays with the replying node
+    let prefix = message_id.to_hex()[..8].to_string();
+    nodes[2]
+        .send(Command::ReplyToMessage {
+            message_id: prefix,
+            content: "sure".to_string(),
+        })
+        .await;
+    let reply = nodes[0]
+        .wait_for(Duration::from_secs(3), |event| {
+            matches!(event, AppEvent::BroadcastReceived { .. })
+        })
+        .await;
+    match reply {
+        Some(AppEvent::BroadcastReceived { from, content, .. }) => {
+            assert_eq!(from, last);
+            assert_eq!(content, "sure");
+        }
+        other => panic!("expected the reply, got {:?}", other),
+    }
+    let highlighted = nodes[0]
+        .wait_for(Duration::from_millis(300), |event| {
+            matches!(event, AppEvent::MessageHighlighted { .. })
+        })
+        .await;
+    assert!(
+        highlighted.is_none(),
+        "public reply carried a reply reference"
+    );
+
+    stop_mesh(&mut nodes).await;
+}
+
+#[tokio::test]
+async fn test_link_down_is_reported() {
+    let (switchboard, mut nodes) = start_mesh(Topology::Line, 3).await;
+    let (first, second) = (nodes[0].peer_id(), nodes[1].peer_id());
+
+    assert!(
+        nodes[0]
+            .wait_for_session(second, Duration::from_secs(5))
+            .await
+    );
+    switchboard.unlink(first, second).unwrap();
+
+    let disconnected = nodes[0]
+        .wait_for(Duration::from_secs(2), |event| {
+            matches!(
+                event,
+                AppEvent::PeerStatusChanged { peer_id, status, .. }
+                    if *peer_id == second && *status != ConnectionStatus::Connected
+            )
+        })
+        .await;
+    assert!(disconnected.is_some());
+    assert!(switchboard.neighbours(&first).is_empty());
+
+    stop_mesh(&mut nodes).await;
+}
+
+#[tokio::test]
+async fn test_nickname_change_reaches_neighbours() {
+    let (_switchboard, mut nodes) = start_mesh(Topology::Line, 2).await;
+    let second = nodes[1].peer_id();
+
+    assert!(
+        nodes[0]
+            .wait_for_session(second, Duration::from_secs(5))
+            .await
+    );
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !nodes[0].announced_peers().await.contains(&second) {
+        assert!(
+            Instant::now() < deadline,
+            "second node should have announced"
+        );
+        sleep(Duration::from_millis(20)).await;
+    }
+
+    nodes[1]
+        .send(Command::SetNickname {
+            nickname: "  bob ".to_string(),
+        })
+        .await;
+    let changed = nodes[1]
+        .wait_for(Duration::from_secs(1), |event| {
+            matches!(event, AppEvent::NicknameChanged { .. })
+        })
+        .await;
+    assert!(
+        matches!(changed, Some(AppEvent::NicknameChanged { nickname }) if nickname == "bob"),
+        "nickname should be trimmed and accepted"
+    );
+
+    // A nickname we can't use is refused without stopping Core Logic
+    nodes[1]
+        .send(Command::SetNickname {
+            nickname: "   ".to_string(),
+        })
+        .await;
+    let refused = nodes[1]
+        .wait_for(Duration::from_secs(1), |event| {
+            matches!(
+                event,
+                AppEvent::NicknameChanged { .. } | AppEvent::SystemError { .. }
+            )
+        })
+        .await;
+    assert!(
+        matches!(refused, Some(AppEvent::SystemError { .. })),
+        "blank nickname should be refused"
+    );
+
+    let renamed = nodes[0]
+        .wait_for(Duration::from_secs(3), |event| {
+            matches!(event, AppEvent::PeerNicknameChanged { peer_id, .. } if *peer_id == second)
+        })
+        .await;
+    match renamed {
+        Some(AppEvent::PeerNicknameChanged {
+            previous, nickname, ..
+        }) => {
+            assert_eq!(previous, second.to_string());
+            assert_eq!(nickname, "bob");
+        }
+        other => panic!("expected a nickname change, got {:?}", other),
+    }
+
+    stop_mesh(&mut nodes).await;
+}
+
+#[tokio::test]
+async fn test_leaving_peer_is_dropped() {
+    let (_switchboard, mut nodes) = start_mesh(Topology::Line, 2).await;
+    let second = nodes[1].peer_id();
+
+    assert!(
+        nodes[0]
+            .wait_for_session(second, Duration::from_secs(5))
+            .await
+    );
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !nodes[0].announced_peers().await.contains(&second) {
+        assert!(
+            Instant::now() < deadline,
+            "second node should have announced"
+        );
+        sleep(Duration::from_millis(20)).await;
+    }
+
+    // Stopping a runtime broadcasts a leave before its transport goes down
+    nodes[1].runtime.stop().await.unwrap();
+    let left = nodes[0]
+        .wait_for(Duration::from_secs(2), |event| {
+            matches!(
+                event,
+                AppEvent::PeerStatusChanged { peer_id, status: ConnectionStatus::Disconnected, .. }
+                    if *peer_id == second
+            )
+        })
+        .await;
+    assert!(left.is_some(), "leave should be reported");
+    assert!(nodes[0].announced_peers().await.is_empty());
+
+    stop_mesh(&mut nodes).await;
+}
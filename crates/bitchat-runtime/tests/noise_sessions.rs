@@ -0,0 +1,219 @@
+//! Noise session lifecycle tests
+//!
+//! Drives the Core Logic handlers of two peers directly to check that a peer can
+//! re-handshake over an established session, that a handshake forged under the
+//! peer's ID can't replace the session it already has, that a completed handshake
+//! names the packet whose link the transport may bind to the peer, and that
+//! messages waiting for a handshake that never completes are capped and reported
+//! as unsent.
+
+mod common;
+
+use bitchat_core::protocol::PacketId;
+use bitchat_core::{BitchatPacket, MessageType, SystemTimeSource, Timestamp};
+use bitchat_runtime::logic::{CommandHandlers, CoreState};
+use bitchat_runtime::{AppEvent, ChannelTransportType, Effect, PeerId};
+
+use common::{alice, bob, connect, exchange, exchange_where, new_state};
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+fn is_handshake(packet: &BitchatPacket) -> bool {
+    packet.message_type() == MessageType::NoiseHandshake
+}
+
+/// The one handshake packet among `effects`
+fn handshake_packet(effects: &[Effect]) -> BitchatPacket {
+    let packets: Vec<_> = effects
+        .iter()
+        .filter_map(|effect| match effect {
+            Effect::SendBitchatPacket { packet, .. } if is_handshake(packet) => Some(packet),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(packets.len(), 1);
+    packets[0].clone()
+}
+
+/// Peers and handshake packets that `effects` bind links to
+fn link_bindings(effects: &[Effect]) -> Vec<(PeerId, PacketId)> {
+    effects
+        .iter()
+        .filter_map(|effect| match effect {
+            Effect::BindLinkIdentity {
+                peer_id, handshake, ..
+            } => Some((*peer_id, handshake.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn packet_id(packet: &BitchatPacket) -> PacketId {
+    PacketId::from_packet_data(
+        packet.sender_id,
+        packet.header.timestamp.as_millis(),
+        &packet.payload,
+    )
+}
+
+async fn deliver(recipient: &mut CoreState, packet: BitchatPacket) -> Vec<Effect> {
+    let (effects, _) = CommandHandlers::handle_bitchat_packet_received(
+        recipient,
+        packet.sender_id,
+        packet,
+        ChannelTransportType::Ble,
+    )
+    .await
+    .unwrap();
+    effects
+}
+
+/// Check that Alice and Bob still share working transport keys
+fn assert_session_works(alice: &mut CoreState, bob: &mut CoreState) {
+    let ciphertext = alice
+        .session_manager
+        .get_session_mut(&bob.peer_id)
+        .expect("alice should have a session")
+        .encrypt(b"still here", &SystemTimeSource)
+        .unwrap();
+    let plaintext = bob
+        .session_manager
+        .get_session_mut(&alice.peer_id)
+        .expect("bob should have a session")
+        .decrypt(&ciphertext, &SystemTimeSource)
+        .unwrap();
+    assert_eq!(plaintext, b"still here");
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_forged_handshake_keeps_established_session() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+    connect(&mut alice_state, &mut bob_state).await;
+
+    // Mallory claims Alice's peer ID but holds a different static key
+    let mut mallory_state = new_state(alice());
+    let effects = CommandHandlers::initiate_handshake(&mut mallory_state, bob()).unwrap();
+
+    // A lone initiation doesn't disturb the live session
+    let first = exchange_where(&mut mallory_state, &mut bob_state, effects, |packet| {
+        is_handshake(packet) && packet.recipient_id == Some(bob())
+    })
+    .await;
+    assert_eq!(first.errors, 0);
+    assert_eq!(first.held_back.len(), 1);
+    assert!(bob_state
+        .session_manager
+        .get_session(&alice())
+        .is_some_and(|session| session.is_established()));
+    assert_session_works(&mut alice_state, &mut bob_state);
+
+    // Completing the handshake authenticates the wrong key and is rejected
+    let rest = exchange_where(
+        &mut mallory_state,
+        &mut bob_state,
+        first.held_back,
+        is_handshake,
+    )
+    .await;
+    assert_eq!(rest.errors, 1);
+    assert_eq!(
+        bob_state
+            .session_manager
+            .get_session(&alice())
+            .and_then(|session| session.peer_fingerprint().cloned()),
+        Some(alice_state.session_manager.local_fingerprint())
+    );
+    assert_session_works(&mut alice_state, &mut bob_state);
+}
+
+#[tokio::test]
+async fn test_completed_handshake_binds_the_link_it_arrived_over() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+
+    let effects = CommandHandlers::initiate_handshake(&mut alice_state, bob()).unwrap();
+    let effects = deliver(&mut bob_state, handshake_packet(&effects)).await;
+    assert!(link_bindings(&effects).is_empty());
+
+    // Message 2 carries Bob's static key, and Alice completes by writing message 3
+    let message2 = handshake_packet(&effects);
+    let effects = deliver(&mut alice_state, message2.clone()).await;
+    assert_eq!(link_bindings(&effects), vec![(bob(), packet_id(&message2))]);
+
+    let message3 = handshake_packet(&effects);
+    let effects = deliver(&mut bob_state, message3.clone()).await;
+    assert_eq!(
+        link_bindings(&effects),
+        vec![(alice(), packet_id(&message3))]
+    );
+}
+
+#[tokio::test]
+async fn test_rehandshake_with_same_key_replaces_session() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+    connect(&mut alice_state, &mut bob_state).await;
+
+    // Alice drops the session, e.g. after a restart, and handshakes again
+    alice_state.session_manager.remove_session(&bob());
+    connect(&mut alice_state, &mut bob_state).await;
+
+    assert!(bob_state
+        .session_manager
+        .get_session(&alice())
+        .is_some_and(|session| session.is_established()));
+    assert_session_works(&mut alice_state, &mut bob_state);
+}
+
+#[tokio::test]
+async fn test_simultaneous_initiation_settles_on_one_session() {
+    let mut alice_state = new_state(alice());
+    let mut bob_state = new_state(bob());
+
+    let mut effects = CommandHandlers::initiate_handshake(&mut alice_state, bob()).unwrap();
+    effects.extend(CommandHandlers::initiate_handshake(&mut bob_state, alice()).unwrap());
+    exchange(&mut alice_state, &mut bob_state, effects).await;
+
+    assert_session_works(&mut alice_state, &mut bob_state);
+}
+
+#[tokio::test]
+async fn test_queued_messages_are_capped_and_expire_without_a_session() {
+    let mut alice_state = new_state(alice());
+
+    // Bob never answers, so every message waits for the handshake
+    for i in 0..32 {
+        let (_, app_events) =
+            CommandHandlers::handle_send_message(&mut alice_state, bob(), format!("hi {}", i))
+                .await
+                .unwrap();
+        assert!(app_events.is_empty());
+    }
+    let (effects, app_events) =
+        CommandHandlers::handle_send_message(&mut alice_state, bob(), "one more".to_string())
+            .await
+            .unwrap();
+    assert!(effects.is_empty());
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::SystemError { .. }]
+    ));
+    assert_eq!(alice_state.pending_messages[&bob()].len(), 32);
+
+    // Queued messages wait out the handshake, then are reported as unsent
+    let now = Timestamp::now();
+    assert!(CommandHandlers::handle_peer_tick(&mut alice_state, now).is_empty());
+    let app_events = CommandHandlers::handle_peer_tick(&mut alice_state, now + 61_000);
+    assert!(matches!(
+        app_events.as_slice(),
+        [AppEvent::SystemError { error }] if error.contains("32 queued messages")
+    ));
+    assert!(alice_state.pending_messages.is_empty());
+}
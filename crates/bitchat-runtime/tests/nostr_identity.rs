@@ -0,0 +1,356 @@
+//! Nostr identity binding and mutual favorite tests
+//!
+//! Two runtimes meet over the in-process transport while a stub Nostr transport on
+//! each side reports a local Nostr key. Once their Noise session is established,
+//! each side should be told to bind the other's peer ID to the advertised key, list
+//! the other among its announced peers, and reach mutual favorites over Nostr once
+//! they leave mesh range.
+
+#![cfg(feature = "in-process")]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bitchat_runtime::{
+    AppEvent, AppEventReceiver, BitchatResult, BitchatRuntime, ChannelTransportType, Command,
+    Effect, EffectReceiver, Event, EventSender, InProcessSwitchboard, PeerId, Topology,
+    TransportTask,
+};
+use tokio::time::{sleep, timeout, Instant};
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+/// What Core Logic asked the stub Nostr transport to do
+#[derive(Default)]
+struct NostrLog {
+    bindings: Vec<(PeerId, [u8; 32])>,
+    packets_sent_to: Vec<PeerId>,
+}
+
+type SharedNostrLog = Arc<Mutex<NostrLog>>;
+
+/// Stands in for the Nostr transport: reports a fixed key and records effects
+struct StubNostrTransport {
+    public_key: [u8; 32],
+    log: SharedNostrLog,
+    channels: Option<(EventSender, EffectReceiver)>,
+}
+
+impl StubNostrTransport {
+    fn new(public_key: [u8; 32]) -> (Self, SharedNostrLog) {
+        let log = SharedNostrLog::default();
+        let transport = Self {
+            public_key,
+            log: log.clone(),
+            channels: None,
+        };
+        (transport, log)
+    }
+}
+
+#[async_trait::async_trait]
+impl TransportTask for StubNostrTransport {
+    fn attach_channels(
+        &mut self,
+        event_sender: EventSender,
+        effect_receiver: EffectReceiver,
+    ) -> BitchatResult<()> {
+        self.channels = Some((event_sender, effect_receiver));
+        Ok(())
+    }
+
+    async fn run(&mut self) -> BitchatResult<()> {
+        let (event_sender, mut effect_receiver) =
+            self.channels.take().expect("channels should be attached");
+        let _ = event_sender
+            .send(Event::LocalNostrIdentity {
+                public_key: self.public_key,
+            })
+            .await;
+
+        loop {
+            match effect_receiver.recv().await {
+                Ok(Effect::BindNostrIdentity {
+                    peer_id,
+                    public_key,
+                }) => self
+                    .log
+                    .lock()
+                    .unwrap()
+                    .bindings
+                    .push((peer_id, public_key)),
+                Ok(Effect::SendBitchatPacket {
+                    peer_id,
+                    transport: ChannelTransportType::Nostr,
+                    ..
+                }) => self.log.lock().unwrap().packets_sent_to.push(peer_id),
+                Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    fn transport_type(&self) -> ChannelTransportType {
+        ChannelTransportType::Nostr
+    }
+}
+
+/// Poll the log until the predicate holds
+async fn wait_for_log<F>(log: &SharedNostrLog, predicate: F) -> bool
+where
+    F: Fn(&NostrLog) -> bool,
+{
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if predicate(&log.lock().unwrap()) {
+            return true;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+/// Wait for the first app event matching the predicate, discarding the others
+async fn wait_for_event<F>(events: &mut AppEventReceiver, mut predicate: F) -> Option<AppEvent>
+where
+    F: FnMut(&AppEvent) -> bool,
+{
+    timeout(Duration::from_secs(5), async {
+        while let Some(event) = events.recv().await {
+            if predicate(&event) {
+                return Some(event);
+            }
+        }
+        None
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+struct Pair {
+    switchboard: InProcessSwitchboard,
+    runtimes: Vec<BitchatRuntime>,
+    logs: Vec<SharedNostrLog>,
+    app_events: Vec<AppEventReceiver>,
+}
+
+fn alice() -> PeerId {
+    PeerId::new([1, 0, 0, 0, 0, 0, 0, 0])
+}
+
+fn bob() -> PeerId {
+    PeerId::new([2, 0, 0, 0, 0, 0, 0, 0])
+}
+
+const ALICE_KEY: [u8; 32] = [0xA1; 32];
+const BOB_KEY: [u8; 32] = [0xB2; 32];
+
+/// Start Alice and Bob as mesh neighbours, each with a stub Nostr transport
+async fn start_pair() -> Pair {
+    let switchboard = InProcessSwitchboard::new(Topology::Line);
+
+    let mut runtimes = Vec::new();
+    let mut logs = Vec::new();
+    for (peer_id, key) in [(alice(), ALICE_KEY), (bob(), BOB_KEY)] {
+        let mut runtime = BitchatRuntime::for_testing(peer_id);
+        runtime
+            .add_transport(switchboard.create_transport(peer_id).unwrap())
+            .unwrap();
+        let (nostr, log) = StubNostrTransport::new(key);
+        runtime.add_transport(nostr).unwrap();
+        runtimes.push(runtime);
+        logs.push(log);
+    }
+
+    let mut app_events = Vec::new();
+    for runtime in &mut runtimes {
+        runtime.start().await.expect("runtime should start");
+        app_events.push(runtime.take_app_event_receiver().unwrap());
+    }
+
+    // Let the stub transports report their keys before the handshake starts
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !(switchboard.is_online(&alice()) && switchboard.is_online(&bob())) {
+        assert!(Instant::now() < deadline, "transports should come online");
+        sleep(Duration::from_millis(10)).await;
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    Pair {
+        switchboard,
+        runtimes,
+        logs,
+        app_events,
+    }
+}
+
+impl Pair {
+    async fn send(&self, index: usize, command: Command) {
+        self.runtimes[index]
+            .command_sender()
+            .expect("runtime should be started")
+            .send(command)
+            .await
+            .expect("command channel should be open");
+    }
+
+    async fn stop(&mut self) {
+        for runtime in &mut self.runtimes {
+            runtime.stop().await.unwrap();
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_peers_bind_advertised_nostr_keys() {
+    let mut pair = start_pair().await;
+    pair.send(
+        0,
+        Command::SendMessage {
+            recipient: bob(),
+            content: "hello".to_string(),
+        },
+    )
+    .await;
+
+    assert!(
+        wait_for_log(&pair.logs[1], |log| log
+            .bindings
+            .contains(&(alice(), ALICE_KEY)))
+        .await,
+        "bob should bind alice's Nostr key"
+    );
+    assert!(
+        wait_for_log(&pair.logs[0], |log| log
+            .bindings
+            .contains(&(bob(), BOB_KEY)))
+        .await,
+        "alice should bind bob's Nostr key"
+    );
+
+    pair.stop().await;
+}
+
+#[tokio::test]
+async fn test_announced_peers_are_listed() {
+    let mut pair = start_pair().await;
+    pair.send(
+        0,
+        Command::SendMessage {
+            recipient: bob(),
+            content: "hello".to_string(),
+        },
+    )
+    .await;
+    assert!(
+        wait_for_log(&pair.logs[0], |log| log
+            .bindings
+            .contains(&(bob(), BOB_KEY)))
+        .await,
+        "alice should receive bob's announce"
+    );
+
+    pair.send(0, Command::ListPeers).await;
+    match wait_for_event(&mut pair.app_events[0], |event| {
+        matches!(event, AppEvent::PeerListReport { .. })
+    })
+    .await
+    {
+        Some(AppEvent::PeerListReport { peers }) => {
+            assert_eq!(peers.len(), 1);
+            assert_eq!(peers[0].peer_id, bob());
+            assert_eq!(peers[0].nickname, bob().to_string());
+            assert_eq!(peers[0].nostr_public_key, Some(BOB_KEY));
+            assert!(!peers[0].transports.is_empty());
+        }
+        other => panic!("expected peer list, got {:?}", other),
+    }
+
+    pair.stop().await;
+}
+
+#[tokio::test]
+async fn test_mutual_favorites_fall_back_to_nostr() {
+    let mut pair = start_pair().await;
+
+    pair.send(
+        0,
+        Command::SetFavorite {
+            peer_id: bob(),
+            favorite: true,
+        },
+    )
+    .await;
+    let favorited = wait_for_event(&mut pair.app_events[1], |event| {
+        matches!(event, AppEvent::FavoriteStatusChanged { status } if status.peer_id == alice())
+    })
+    .await;
+    match favorited {
+        Some(AppEvent::FavoriteStatusChanged { status }) => {
+            assert!(status.favorited_us && !status.is_mutual());
+            assert_eq!(status.nostr_public_key, Some(ALICE_KEY));
+        }
+        other => panic!("expected favorite notification, got {:?}", other),
+    }
+
+    pair.send(
+        1,
+        Command::SetFavorite {
+            peer_id: alice(),
+            favorite: true,
+        },
+    )
+    .await;
+    let mutual = wait_for_event(
+        &mut pair.app_events[0],
+        |event| matches!(event, AppEvent::FavoriteStatusChanged { status } if status.is_mutual()),
+    )
+    .await;
+    assert!(
+        mutual.is_some(),
+        "alice should see bob as a mutual favorite"
+    );
+
+    pair.send(0, Command::ListFavorites).await;
+    match wait_for_event(&mut pair.app_events[0], |event| {
+        matches!(event, AppEvent::FavoritesReport { .. })
+    })
+    .await
+    {
+        Some(AppEvent::FavoritesReport { favorites }) => {
+            assert_eq!(favorites.len(), 1);
+            assert!(favorites[0].peer_id == bob() && favorites[0].is_mutual());
+        }
+        other => panic!("expected favorites report, got {:?}", other),
+    }
+
+    // Out of mesh range, messages to a mutual favorite go over Nostr
+    pair.switchboard.unlink(alice(), bob()).unwrap();
+    wait_for_event(
+        &mut pair.app_events[0],
+        |event| matches!(event, AppEvent::PeerStatusChanged { peer_id, .. } if *peer_id == bob()),
+    )
+    .await
+    .expect("alice should notice bob leaving");
+    pair.send(
+        0,
+        Command::SendMessage {
+            recipient: bob(),
+            content: "still there?".to_string(),
+        },
+    )
+    .await;
+    assert!(
+        wait_for_log(&pair.logs[0], |log| log.packets_sent_to.contains(&bob())).await,
+        "message to an out-of-range mutual favorite should use Nostr"
+    );
+
+    pair.stop().await;
+}
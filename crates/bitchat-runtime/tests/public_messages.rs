@@ -0,0 +1,86 @@
+//! Public message tests
+//!
+//! Drives the Core Logic handlers of two peers directly to check that public
+//! messages are held until their sender has announced, are shown under the
+//! sender's announced nickname and are stored under their recomputed content
+//! address.
+
+mod common;
+
+use bitchat_core::{BitchatMessage, BitchatPacket, Timestamp};
+use bitchat_runtime::logic::{CommandHandlers, CoreState};
+use bitchat_runtime::{AppEvent, ChannelTransportType, Effect, PeerId};
+
+use common::{alice, bob, connect, exchange};
+
+// ----------------------------------------------------------------------------
+// Test Utilities
+// ----------------------------------------------------------------------------
+
+fn new_state(peer_id: PeerId, nickname: &str) -> CoreState {
+    let mut state = common::new_state(peer_id);
+    state.nickname = nickname.to_string();
+    state
+}
+
+fn received(events: &[AppEvent]) -> Vec<(&str, &str)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            AppEvent::BroadcastReceived {
+                nickname, content, ..
+            } => Some((nickname.as_str(), content.as_str())),
+            _ => None,
+        })
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+// Tests
+// ----------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_messages_wait_for_sender_announce() {
+    let mut alice_state = new_state(alice(), "alice");
+    let mut bob_state = new_state(bob(), "bob");
+
+    let (effects, _) =
+        CommandHandlers::handle_send_broadcast(&mut alice_state, "hello mesh".to_string()).unwrap();
+    let events = exchange(&mut alice_state, &mut bob_state, effects)
+        .await
+        .bob_events;
+    assert!(received(&events).is_empty());
+    assert_eq!(bob_state.held_broadcasts[&alice()].len(), 1);
+
+    // Alice announces over the new session, releasing the message
+    let events = connect(&mut alice_state, &mut bob_state).await.bob_events;
+    assert_eq!(received(&events), vec![("alice", "hello mesh")]);
+    assert!(bob_state.held_broadcasts.is_empty());
+}
+
+#[tokio::test]
+async fn test_claimed_nickname_and_id_are_ignored() {
+    let mut alice_state = new_state(alice(), "alice");
+    let mut bob_state = new_state(bob(), "bob");
+    connect(&mut alice_state, &mut bob_state).await;
+
+    // The message claims another sender's nickname and an ID it doesn't hash to
+    let forged_id = "ab".repeat(32);
+    let mut message = BitchatMessage::new(forged_id.clone(), "carol".to_string(), "hi".to_string());
+    message.timestamp = Timestamp::now();
+    let packet =
+        BitchatPacket::create_public_message(alice(), &message, &alice_state.identity_key).unwrap();
+    let effects = vec![Effect::BroadcastBitchatPacket {
+        packet,
+        transport: ChannelTransportType::Ble,
+    }];
+    let events = exchange(&mut alice_state, &mut bob_state, effects)
+        .await
+        .bob_events;
+
+    assert_eq!(received(&events), vec![("alice", "hi")]);
+    let Some(AppEvent::BroadcastReceived { message_id, .. }) = events.first() else {
+        panic!("expected a public message, got {:?}", events);
+    };
+    assert_ne!(message_id.to_hex(), forged_id);
+}
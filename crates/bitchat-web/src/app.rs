@@ -7,6 +7,9 @@
 //! 4. Managing the AppEvent stream and forwarding events to JavaScript UI
 
 use bitchat_core::{
+    channel::communication::{
+        FavoriteStatus, FileTransferInfo, GroupInfo, LinkedDeviceInfo, PeerInfo,
+    },
     internal::{create_app_event_channel, create_command_channel, ChannelConfig, CommandSender},
     AppEvent, Command, PeerId,
 };
@@ -107,6 +110,41 @@ impl From<AppEvent> for JsAppEvent {
                     }).unwrap_or(JsValue::NULL),
                 }
             }
+            AppEvent::BroadcastReceived { message_id, from, nickname, content, timestamp } => {
+                Self {
+                    event_type: "broadcast_received".to_string(),
+                    data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                        "message_id": message_id.to_hex(),
+                        "from": from.to_string(),
+                        "nickname": nickname,
+                        "content": content,
+                        "timestamp": timestamp
+                    })).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::BroadcastSent { message_id, content, timestamp } => {
+                Self {
+                    event_type: "broadcast_sent".to_string(),
+                    data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                        "message_id": message_id.to_hex(),
+                        "content": content,
+                        "timestamp": timestamp
+                    })).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::MessageHighlighted { message_id, from, content, reply_to, mentioned, timestamp } => {
+                Self {
+                    event_type: "message_highlighted".to_string(),
+                    data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                        "message_id": message_id.to_hex(),
+                        "from": from.to_string(),
+                        "content": content,
+                        "reply_to": reply_to.map(|id| id.to_hex()),
+                        "mentioned": mentioned,
+                        "timestamp": timestamp
+                    })).unwrap_or(JsValue::NULL),
+                }
+            }
             AppEvent::SystemBusy { reason } => {
                 Self {
                     event_type: "system_busy".to_string(),
@@ -218,10 +256,258 @@ impl From<AppEvent> for JsAppEvent {
                     })).unwrap_or(JsValue::NULL),
                 }
             }
+            AppEvent::RelayStatusReport { relays } => {
+                Self {
+                    event_type: "relay_status_report".to_string(),
+                    data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                        "relays": relays.iter().map(|relay| {
+                            serde_json::json!({
+                                "url": relay.url,
+                                "health": format!("{:?}", relay.health),
+                                "score": relay.score,
+                                "events_accepted": relay.events_accepted,
+                                "events_failed": relay.events_failed,
+                                "average_latency_ms": relay.average_latency_ms,
+                                "consecutive_failures": relay.consecutive_failures,
+                                "backoff_remaining_ms": relay.backoff_remaining_ms
+                            })
+                        }).collect::<Vec<_>>()
+                    })).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::FavoriteStatusChanged { status } => Self {
+                event_type: "favorite_status_changed".to_string(),
+                data: serde_wasm_bindgen::to_value(&favorite_json(&status))
+                    .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::FavoritesReport { favorites } => Self {
+                event_type: "favorites_report".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "favorites": favorites.iter().map(favorite_json).collect::<Vec<_>>()
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::PeerListReport { peers } => Self {
+                event_type: "peer_list_report".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "peers": peers.iter().map(peer_json).collect::<Vec<_>>()
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::NicknameChanged { nickname } => Self {
+                event_type: "nickname_changed".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "nickname": nickname
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::PeerNicknameChanged {
+                peer_id,
+                previous,
+                nickname,
+            } => Self {
+                event_type: "peer_nickname_changed".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "peer_id": peer_id.to_string(),
+                    "previous": previous,
+                    "nickname": nickname
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::FileOffered {
+                transfer,
+                description,
+            } => {
+                let mut data = transfer_json(&transfer);
+                data["description"] = serde_json::json!(description);
+                Self {
+                    event_type: "file_offered".to_string(),
+                    data: serde_wasm_bindgen::to_value(&data).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::FileTransferProgress { transfer } => Self {
+                event_type: "file_transfer_progress".to_string(),
+                data: serde_wasm_bindgen::to_value(&transfer_json(&transfer))
+                    .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::FileTransferCompleted { transfer, saved_to } => {
+                let mut data = transfer_json(&transfer);
+                data["saved_to"] = serde_json::json!(saved_to);
+                Self {
+                    event_type: "file_transfer_completed".to_string(),
+                    data: serde_wasm_bindgen::to_value(&data).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::FileTransferFailed { transfer, reason } => {
+                let mut data = transfer_json(&transfer);
+                data["reason"] = serde_json::json!(reason);
+                Self {
+                    event_type: "file_transfer_failed".to_string(),
+                    data: serde_wasm_bindgen::to_value(&data).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::TransfersReport { transfers } => Self {
+                event_type: "transfers_report".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "transfers": transfers.iter().map(transfer_json).collect::<Vec<_>>()
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::GroupInvitation { group, from } => {
+                let mut data = group_json(&group);
+                data["from"] = serde_json::json!(from.to_string());
+                Self {
+                    event_type: "group_invitation".to_string(),
+                    data: serde_wasm_bindgen::to_value(&data).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::GroupMembershipChanged {
+                group,
+                joined,
+                left,
+            } => {
+                let mut data = group_json(&group);
+                data["joined"] = serde_json::json!(joined
+                    .iter()
+                    .map(|peer_id| peer_id.to_string())
+                    .collect::<Vec<_>>());
+                data["left"] = serde_json::json!(left
+                    .iter()
+                    .map(|peer_id| peer_id.to_string())
+                    .collect::<Vec<_>>());
+                Self {
+                    event_type: "group_membership_changed".to_string(),
+                    data: serde_wasm_bindgen::to_value(&data).unwrap_or(JsValue::NULL),
+                }
+            }
+            AppEvent::GroupMessageReceived {
+                group_id,
+                from,
+                content,
+                timestamp,
+            } => Self {
+                event_type: "group_message_received".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "group_id": group_id,
+                    "from": from.to_string(),
+                    "content": content,
+                    "timestamp": timestamp
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::GroupMessageSent {
+                group_id,
+                content,
+                timestamp,
+            } => Self {
+                event_type: "group_message_sent".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "group_id": group_id,
+                    "content": content,
+                    "timestamp": timestamp
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::GroupsReport {
+                groups,
+                invitations,
+            } => Self {
+                event_type: "groups_report".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "groups": groups.iter().map(group_json).collect::<Vec<_>>(),
+                    "invitations": invitations.iter().map(group_json).collect::<Vec<_>>()
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::DeviceLinkOffer { uri, expires_at } => Self {
+                event_type: "device_link_offer".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "uri": uri,
+                    "expires_at": expires_at
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::DeviceLinked { device } => Self {
+                event_type: "device_linked".to_string(),
+                data: serde_wasm_bindgen::to_value(&device_json(&device))
+                    .unwrap_or(JsValue::NULL),
+            },
+            AppEvent::DevicesReport { devices } => Self {
+                event_type: "devices_report".to_string(),
+                data: serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "devices": devices.iter().map(device_json).collect::<Vec<_>>()
+                }))
+                .unwrap_or(JsValue::NULL),
+            },
         }
     }
 }
 
+/// JSON shape of a favorite relationship for the JavaScript UI
+fn favorite_json(status: &FavoriteStatus) -> serde_json::Value {
+    serde_json::json!({
+        "peer_id": status.peer_id.to_string(),
+        "favorite": status.favorite,
+        "favorited_us": status.favorited_us,
+        "mutual": status.is_mutual(),
+        "nostr_public_key": status.nostr_public_key.map(hex::encode)
+    })
+}
+
+/// JSON shape of an announced peer for the JavaScript UI
+fn peer_json(peer: &PeerInfo) -> serde_json::Value {
+    serde_json::json!({
+        "peer_id": peer.peer_id.to_string(),
+        "nickname": peer.nickname,
+        "fingerprint": peer.fingerprint,
+        "nostr_public_key": peer.nostr_public_key.map(hex::encode),
+        "transports": peer.transports.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "rssi": peer.rssi,
+        "last_seen": peer.last_seen
+    })
+}
+
+/// JSON shape of a file transfer for the JavaScript UI
+fn transfer_json(transfer: &FileTransferInfo) -> serde_json::Value {
+    serde_json::json!({
+        "transfer_id": transfer.transfer_id,
+        "peer_id": transfer.peer_id.to_string(),
+        "direction": transfer.direction.to_string(),
+        "filename": transfer.filename,
+        "mime_type": transfer.mime_type,
+        "size": transfer.size,
+        "bytes_transferred": transfer.bytes_transferred,
+        "progress": transfer.progress(),
+        "state": format!("{:?}", transfer.state)
+    })
+}
+
+/// JSON shape of a group and its members for the JavaScript UI
+fn group_json(group: &GroupInfo) -> serde_json::Value {
+    serde_json::json!({
+        "group_id": group.group_id,
+        "name": group.name,
+        "description": group.description,
+        "members": group.members.iter().map(|member| serde_json::json!({
+            "peer_id": member.peer_id.to_string(),
+            "nickname": member.nickname,
+            "admin": member.admin
+        })).collect::<Vec<_>>()
+    })
+}
+
+/// JSON shape of a linked device for the JavaScript UI
+fn device_json(device: &LinkedDeviceInfo) -> serde_json::Value {
+    serde_json::json!({
+        "device_id": device.device_id,
+        "name": device.name,
+        "peer_id": device.peer_id.to_string(),
+        "this_device": device.this_device,
+        "online": device.online,
+        "last_seen": device.last_seen
+    })
+}
+
 // ----------------------------------------------------------------------------
 // BitChat Web Application
 // ----------------------------------------------------------------------------
@@ -372,6 +658,39 @@ impl BitchatWebApp {
         }
     }
 
+    /// Send a public message to everyone in the mesh
+    #[wasm_bindgen]
+    pub fn send_broadcast(&self, content: &str) -> Result<(), JsValue> {
+        if let Some(sender) = &self.command_sender {
+            sender
+                .clone()
+                .try_send(Command::SendBroadcast {
+                    content: content.to_string(),
+                })
+                .map_err(|_| JsValue::from_str("Failed to send command"))?;
+            Ok(())
+        } else {
+            Err(JsValue::from_str("Application not started"))
+        }
+    }
+
+    /// Reply to a message by ID or unique ID prefix
+    #[wasm_bindgen]
+    pub fn reply_to_message(&self, message_id: &str, content: &str) -> Result<(), JsValue> {
+        if let Some(sender) = &self.command_sender {
+            sender
+                .clone()
+                .try_send(Command::ReplyToMessage {
+                    message_id: message_id.to_string(),
+                    content: content.to_string(),
+                })
+                .map_err(|_| JsValue::from_str("Failed to send command"))?;
+            Ok(())
+        } else {
+            Err(JsValue::from_str("Application not started"))
+        }
+    }
+
     /// Start peer discovery
     #[wasm_bindgen]
     pub fn start_discovery(&self) -> Result<(), JsValue> {
@@ -448,6 +767,34 @@ impl BitchatWebApp {
         }
     }
 
+    /// Request the peers known from their announces
+    #[wasm_bindgen]
+    pub fn list_peers(&self) -> Result<(), JsValue> {
+        if let Some(sender) = &self.command_sender {
+            sender
+                .clone()
+                .try_send(Command::ListPeers)
+                .map_err(|_| JsValue::from_str("Failed to send command"))?;
+            Ok(())
+        } else {
+            Err(JsValue::from_str("Application not started"))
+        }
+    }
+
+    /// Change our nickname and announce it to peers
+    #[wasm_bindgen]
+    pub fn set_nickname(&self, nickname: String) -> Result<(), JsValue> {
+        if let Some(sender) = &self.command_sender {
+            sender
+                .clone()
+                .try_send(Command::SetNickname { nickname })
+                .map_err(|_| JsValue::from_str("Failed to send command"))?;
+            Ok(())
+        } else {
+            Err(JsValue::from_str("Application not started"))
+        }
+    }
+
     /// Check if the application is running
     #[wasm_bindgen]
     pub fn is_running(&self) -> bool {
@@ -569,6 +916,9 @@ mod tests {
             AppEvent::PeerStatusChanged { .. } => "peer_status_changed",
             AppEvent::MessageReceived { .. } => "message_received",
             AppEvent::MessageSent { .. } => "message_sent",
+            AppEvent::BroadcastReceived { .. } => "broadcast_received",
+            AppEvent::BroadcastSent { .. } => "broadcast_sent",
+            AppEvent::MessageHighlighted { .. } => "message_highlighted",
             AppEvent::SystemBusy { .. } => "system_busy",
             AppEvent::SystemError { .. } => "system_error",
             AppEvent::DiscoveryStateChanged { .. } => "discovery_state_changed",
@@ -578,6 +928,25 @@ mod tests {
             AppEvent::PeerSessionReport { .. } => "peer_session_report",
             AppEvent::DeliveryStatusReport { .. } => "delivery_status_report",
             AppEvent::InternalStateReport { .. } => "internal_state_report",
+            AppEvent::RelayStatusReport { .. } => "relay_status_report",
+            AppEvent::FavoriteStatusChanged { .. } => "favorite_status_changed",
+            AppEvent::FavoritesReport { .. } => "favorites_report",
+            AppEvent::PeerListReport { .. } => "peer_list_report",
+            AppEvent::NicknameChanged { .. } => "nickname_changed",
+            AppEvent::PeerNicknameChanged { .. } => "peer_nickname_changed",
+            AppEvent::FileOffered { .. } => "file_offered",
+            AppEvent::FileTransferProgress { .. } => "file_transfer_progress",
+            AppEvent::FileTransferCompleted { .. } => "file_transfer_completed",
+            AppEvent::FileTransferFailed { .. } => "file_transfer_failed",
+            AppEvent::TransfersReport { .. } => "transfers_report",
+            AppEvent::GroupInvitation { .. } => "group_invitation",
+            AppEvent::GroupMembershipChanged { .. } => "group_membership_changed",
+            AppEvent::GroupMessageReceived { .. } => "group_message_received",
+            AppEvent::GroupMessageSent { .. } => "group_message_sent",
+            AppEvent::GroupsReport { .. } => "groups_report",
+            AppEvent::DeviceLinkOffer { .. } => "device_link_offer",
+            AppEvent::DeviceLinked { .. } => "device_linked",
+            AppEvent::DevicesReport { .. } => "devices_report",
         };
 
         assert_eq!(event_type, "peer_status_changed");